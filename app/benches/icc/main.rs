@@ -4,7 +4,7 @@
  * // Use of this source code is governed by a BSD-style
  * // license that can be found in the LICENSE file.
  */
-use criterion::{Criterion, criterion_group, criterion_main};
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
 use lcms2::{Intent, PixelFormat, Profile, Transform};
 use moxcms::{ColorProfile, InterpolationMethod, Layout, TransformOptions};
 use std::fs;
@@ -155,6 +155,58 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         })
     });
 
+    // Same primaries on both sides, compare with "RGBA -> RGBA" for the matrix stage cost
+    c.bench_function("moxcms: RGBA -> RGBA TRC only", |b| {
+        let color_profile = ColorProfile::new_srgb();
+        let mut dest_profile = ColorProfile::new_srgb();
+        dest_profile.red_trc = Some(moxcms::curve_from_gamma(2.2));
+        dest_profile.green_trc = dest_profile.red_trc.clone();
+        dest_profile.blue_trc = dest_profile.red_trc.clone();
+        let mut dst = vec![0u8; rgba.len()];
+        let transform = color_profile
+            .create_transform_8bit(
+                Layout::Rgba,
+                &dest_profile,
+                Layout::Rgba,
+                TransformOptions::default(),
+            )
+            .unwrap();
+        b.iter(|| {
+            transform.transform(&rgba, &mut dst).unwrap();
+        })
+    });
+
+    let rgba16 = rgba.iter().map(|&x| x as u16 * 257).collect::<Vec<u16>>();
+
+    for (name, lazy_gamma_tables) in [("eager", false), ("lazy", true)] {
+        let options = TransformOptions {
+            lazy_gamma_tables,
+            ..Default::default()
+        };
+
+        c.bench_function(&format!("moxcms: RGBA16 create {name}"), |b| {
+            let color_profile = ColorProfile::new_from_slice(&src_icc_profile).unwrap();
+            let dest_profile = ColorProfile::new_bt2020();
+            b.iter(|| {
+                color_profile
+                    .create_transform_16bit(Layout::Rgba, &dest_profile, Layout::Rgba, options)
+                    .unwrap()
+            })
+        });
+
+        c.bench_function(&format!("moxcms: RGBA16 -> RGBA16 {name}"), |b| {
+            let color_profile = ColorProfile::new_from_slice(&src_icc_profile).unwrap();
+            let dest_profile = ColorProfile::new_bt2020();
+            let mut dst = vec![0u16; rgba16.len()];
+            let transform = color_profile
+                .create_transform_16bit(Layout::Rgba, &dest_profile, Layout::Rgba, options)
+                .unwrap();
+            b.iter(|| {
+                transform.transform(&rgba16, &mut dst).unwrap();
+            })
+        });
+    }
+
     c.bench_function("lcms2: RGB -> RGB", |b| {
         let custom_profile = Profile::new_icc(&src_icc_profile).unwrap();
         let profile_bytes = fs::read("../assets/bt_2020.icc").unwrap();
@@ -330,6 +382,54 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         })
     });
 
+    c.bench_function("moxcms: CMYK first call -> RGBA", |b| {
+        let color_profile = ColorProfile::new_from_slice(&us_swop_icc).unwrap();
+        let dest_profile = ColorProfile::new_srgb();
+        let row = &cmyk[..img.width() as usize * 4];
+        let mut dst = vec![0u8; row.len()];
+        b.iter_batched(
+            || {
+                color_profile
+                    .create_transform_8bit(
+                        Layout::Rgba,
+                        &dest_profile,
+                        Layout::Rgba,
+                        TransformOptions::default(),
+                    )
+                    .unwrap()
+            },
+            |transform| {
+                transform.transform(row, &mut dst).unwrap();
+            },
+            BatchSize::PerIteration,
+        )
+    });
+
+    c.bench_function("moxcms: CMYK first call after warmup -> RGBA", |b| {
+        let color_profile = ColorProfile::new_from_slice(&us_swop_icc).unwrap();
+        let dest_profile = ColorProfile::new_srgb();
+        let row = &cmyk[..img.width() as usize * 4];
+        let mut dst = vec![0u8; row.len()];
+        b.iter_batched(
+            || {
+                let transform = color_profile
+                    .create_transform_8bit(
+                        Layout::Rgba,
+                        &dest_profile,
+                        Layout::Rgba,
+                        TransformOptions::default(),
+                    )
+                    .unwrap();
+                transform.warmup();
+                transform
+            },
+            |transform| {
+                transform.transform(row, &mut dst).unwrap();
+            },
+            BatchSize::PerIteration,
+        )
+    });
+
     c.bench_function("lcms2: CMYK -> RGBA", |b| {
         let cmyk_profile = Profile::new_icc(&us_swop_icc).unwrap();
         let dest_profile = Profile::new_srgb();
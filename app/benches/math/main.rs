@@ -4,6 +4,7 @@
  * // Use of this source code is governed by a BSD-style
  * // license that can be found in the LICENSE file.
  */
+#![allow(clippy::excessive_precision)]
 use criterion::{Criterion, black_box, criterion_group, criterion_main};
 use moxcms::{atanf, cbrtf, cosf, exp, expf, logf, pow, powf, sinf};
 
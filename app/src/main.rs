@@ -27,7 +27,7 @@
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 // use jxl_oxide::{JxlImage, JxlThreadPool, Lcms2, Moxcms};
-use lcms2::{Intent, PixelFormat, Profile, Transform};
+use lcms2::{Intent, PixelFormat, Transform};
 use moxcms::{ColorProfile, InterpolationMethod, Layout, RenderingIntent, TransformOptions};
 use std::fs;
 use std::fs::File;
@@ -75,53 +75,21 @@ fn compute_abs_diff4(src: &[f32], dst: &[[f32; 4]], highlights: &mut [f32]) {
     println!("Abs A {}", abs_a);
 }
 
-fn compute_abs_diff42(src: &[f32], dst: &[f32]) {
-    let mut abs_r = f32::MIN;
-    let mut abs_g = f32::MIN;
-    let mut abs_b = f32::MIN;
-    let mut abs_a = f32::MIN;
-    let mut mean_r = 0f32;
-    let mut mean_g = 0f32;
-    let mut mean_b = 0f32;
-    for (src, dst) in src.chunks_exact(4).zip(dst.chunks_exact(4)) {
-        let dr = (src[0] - dst[0]).abs();
-        abs_r = dr.max(abs_r);
-        mean_r += dr.abs();
-        abs_g = (src[1] - dst[1]).abs().max(abs_g);
-        mean_g += (src[1] - dst[1]).abs();
-        abs_b = (src[2] - dst[2]).abs().max(abs_b);
-        mean_b += (src[2] - dst[2]).abs();
-        abs_a = (src[3] - dst[3]).abs().max(abs_a);
-    }
-    mean_r /= dst.len() as f32;
-    mean_g /= dst.len() as f32;
-    mean_b /= dst.len() as f32;
-    println!("Abs R {} Mean R {}", abs_r, mean_r);
-    println!("Abs G {} Mean G {}", abs_g, mean_g);
-    println!("Abs B {} Mean G {}", abs_b, mean_b);
-    println!("Abs A {}", abs_a);
-}
-
 fn main() {
     let funny_icc = fs::read("./assets/CGATS21_CRPC5.icc").unwrap();
 
     // println!("{:?}", decoded);
 
-    let srgb_perceptual_icc = fs::read("./assets/srgb_perceptual.icc").unwrap();
-
     let funny_profile = ColorProfile::new_from_slice(&funny_icc).unwrap();
 
-    let srgb_perceptual_profile = ColorProfile::new_from_slice(&srgb_perceptual_icc).unwrap();
     let out_profile = ColorProfile::new_srgb();
 
     let f_str = "./assets/bench.jpg";
     let file = File::open(f_str).expect("Failed to open file");
 
     let img = image::ImageReader::open(f_str).unwrap().decode().unwrap();
-    let rgb = img.to_rgb8();
 
     let reader = BufReader::new(file);
-    let ref_reader = &reader;
 
     let options = DecoderOptions::new_fast().jpeg_set_out_colorspace(ColorSpace::RGB);
 
@@ -132,10 +100,6 @@ fn main() {
     decoder.decode_headers().unwrap();
     let mut real_dst = vec![0u8; decoder.output_buffer_size().unwrap()];
 
-    let custom_profile = Profile::new_icc(&srgb_perceptual_icc).unwrap();
-    //
-    let srgb_profile = Profile::new_srgb();
-
     decoder.decode_into(&mut real_dst).unwrap();
 
     let real_dst = real_dst
@@ -168,11 +132,8 @@ fn main() {
 
     let mut cmyk_lcms2 = vec![[0f32; 4]; (decoder.output_buffer_size().unwrap() / 3) * 4];
 
-    let icc = decoder.icc_profile().unwrap();
-    let color_profile = ColorProfile::new_from_slice(&srgb_perceptual_icc).unwrap();
-    let cmyk_profile = ColorProfile::new_from_slice(&funny_icc).unwrap();
     // let color_profile = ColorProfile::new_gray_with_gamma(2.2);
-    let mut dest_profile = ColorProfile::new_srgb();
+    let dest_profile = ColorProfile::new_srgb();
 
     // t1.transform_pixels(&real_dst, &mut cmyk);
 
@@ -183,8 +144,6 @@ fn main() {
 
     t1.transform_pixels(lcms2_src.as_slice(), cmyk_lcms2.as_mut_slice());
 
-    let time = Instant::now();
-
     let transform = dest_profile
         .create_transform_f32(
             Layout::Rgba,
@@ -195,7 +154,7 @@ fn main() {
                 allow_use_cicp_transfer: false,
                 prefer_fixed_point: false,
                 interpolation_method: InterpolationMethod::Tetrahedral,
-                black_point_compensation: false,
+                ..Default::default()
             },
         )
         .unwrap();
@@ -214,7 +173,7 @@ fn main() {
                 allow_use_cicp_transfer: false,
                 prefer_fixed_point: false,
                 interpolation_method: InterpolationMethod::Tetrahedral,
-                black_point_compensation: false,
+                ..Default::default()
             },
         )
         .unwrap();
@@ -238,24 +197,24 @@ fn main() {
         .flat_map(|x| [x[0] / v_max, x[1] / v_max, x[2] / v_max, x[3] / v_max])
         .collect::<Vec<_>>();
 
-    for (src, dst) in clms
-        .chunks_exact(img.width() as usize * 4)
-        .zip(dst.chunks_exact_mut(img.width() as usize * 4))
-    {
-        transform
-            .transform(
-                &src[..img.width() as usize * 4],
-                &mut dst[..img.width() as usize * 4],
-            )
-            .unwrap();
-    }
+    let stride = img.width() as usize * 4;
+    transform
+        .transform_strided(
+            &clms,
+            stride,
+            &mut dst,
+            stride,
+            img.width() as usize,
+            img.height() as usize,
+        )
+        .unwrap();
 
     dst = dst
         .chunks_exact(4)
         .flat_map(|x| [x[0], x[1], x[2], 1.])
         .collect();
 
-    let mut rgba_lcms2 = vec![[0f32; 4]; (decoder.output_buffer_size().unwrap() / 3)];
+    let mut rgba_lcms2 = vec![[0f32; 4]; decoder.output_buffer_size().unwrap() / 3];
 
     t2.transform_pixels(&cmyk_lcms2, &mut rgba_lcms2);
 
@@ -296,7 +255,6 @@ fn main() {
     //     .flat_map(|x| [x[0], x[1], x[2], x[3]])
     //     .collect::<Vec<_>>();
     //
-    // compute_abs_diff42(&real_img_mox, &real_img_lcms);
     //
     // let real_img_data = img_buf
     //     .chunks_exact(5)
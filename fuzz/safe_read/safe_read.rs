@@ -1,8 +1,7 @@
 #![no_main]
 
 use libfuzzer_sys::fuzz_target;
-use moxcms::{ColorProfile, Layout, RenderingIntent, TransformOptions};
-use std::fs;
+use moxcms::ColorProfile;
 
 fuzz_target!(|data: &[u8]| {
     // Never panic expected
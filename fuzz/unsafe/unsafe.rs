@@ -1,16 +1,16 @@
 #![no_main]
 
 use libfuzzer_sys::fuzz_target;
-use moxcms::{ColorProfile, Layout, RenderingIntent, TransformOptions};
+use moxcms::{ColorProfile, Layout, TransformOptions};
 use std::fs;
 
 fuzz_target!(|data: (u8, u8, u16, u8, u8,)| {
-    let src_layout = if data.3 % 2 == 0 {
+    let src_layout = if data.3.is_multiple_of(2) {
         Layout::Rgba
     } else {
         Layout::Rgb
     };
-    let dst_layout = if data.4 % 2 == 0 {
+    let dst_layout = if data.4.is_multiple_of(2) {
         Layout::Rgba
     } else {
         Layout::Rgb
@@ -0,0 +1,319 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::lab::Lab;
+use crate::roundtrip::{device_layout, normalized_xyz_profile};
+use crate::{
+    CmsError, ColorProfile, DataColorSpace, InterpolationMethod, Layout, RenderingIntent,
+    TransformExecutor, TransformOptions,
+};
+use num_traits::AsPrimitive;
+
+/// Result of [estimate_accuracy]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccuracyReport {
+    /// Maximum CIEDE2000 difference between transform and reference output
+    pub max_de2000: f32,
+    /// Mean CIEDE2000 difference between transform and reference output
+    pub mean_de2000: f32,
+    /// Normalized source value that has the maximum error
+    pub worst_input: Vec<f32>,
+    /// Count of evaluated source values
+    pub samples: usize,
+}
+
+fn layout_of(color_space: DataColorSpace) -> Result<Layout, CmsError> {
+    match color_space {
+        DataColorSpace::Gray => Ok(Layout::Gray),
+        _ => device_layout(color_space),
+    }
+}
+
+/// Compares output of `transform` with double precision reference of the same conversion.
+///
+/// `transform` must convert from `src_profile` to `dst_profile` with `intent`, using layouts
+/// without alpha: `Gray`, `Rgb` for RGB and `Rgba` for CMYK.
+/// `bit_depth` is the number of significant bits of `T`, `1` for floating point.
+///
+/// Reference is `f64` transform with floating point matrices and tetrahedral interpolation,
+/// both outputs are measured in CIE Lab through `dst_profile`. Source is sampled with
+/// `samples` values: an eighth of them on the neutral axis, a quarter on the faces
+/// of device cube, where gamut surface is, and the rest spread evenly inside by
+/// quasi-random sequence, so reports are reproducible.
+pub fn estimate_accuracy<T: Copy + Default + AsPrimitive<f32>>(
+    transform: &dyn TransformExecutor<T>,
+    src_profile: &ColorProfile,
+    dst_profile: &ColorProfile,
+    intent: RenderingIntent,
+    bit_depth: usize,
+    samples: usize,
+) -> Result<AccuracyReport, CmsError>
+where
+    f32: AsPrimitive<T>,
+{
+    let src_layout = layout_of(src_profile.color_space)?;
+    let dst_layout = layout_of(dst_profile.color_space)?;
+    let reference_options = TransformOptions {
+        rendering_intent: intent,
+        prefer_fixed_point: false,
+        interpolation_method: InterpolationMethod::Tetrahedral,
+        deterministic: true,
+        ..Default::default()
+    };
+    let reference =
+        src_profile.create_transform_f64(src_layout, dst_profile, dst_layout, reference_options)?;
+    let measure = dst_profile.create_transform_f64(
+        dst_layout,
+        &normalized_xyz_profile(),
+        Layout::Rgb,
+        TransformOptions {
+            rendering_intent: RenderingIntent::RelativeColorimetric,
+            ..reference_options
+        },
+    )?;
+
+    let src_channels = src_layout.channels();
+    let dst_channels = dst_layout.channels();
+    let max_value = if bit_depth <= 1 {
+        1.
+    } else {
+        ((1u64 << bit_depth.min(32)) - 1) as f32
+    };
+    let quantize = |v: f32| {
+        if bit_depth <= 1 {
+            v
+        } else {
+            (v * max_value).round() / max_value
+        }
+    };
+
+    let src = accuracy_samples(src_channels, samples.max(16))
+        .into_iter()
+        .map(quantize)
+        .collect::<Vec<f32>>();
+    let count = src.len() / src_channels;
+
+    let src_t = src
+        .iter()
+        .map(|&v| (v * max_value).as_())
+        .collect::<Vec<T>>();
+    let mut dst_t = vec![T::default(); count * dst_channels];
+    transform.transform(&src_t, &mut dst_t)?;
+    let tested = dst_t
+        .iter()
+        .map(|&v| v.as_() as f64 / max_value as f64)
+        .collect::<Vec<f64>>();
+
+    let src_f64 = src.iter().map(|&v| v as f64).collect::<Vec<f64>>();
+    let mut expected = vec![0f64; count * dst_channels];
+    reference.transform(&src_f64, &mut expected)?;
+
+    let mut xyz_tested = vec![0f64; count * 3];
+    measure.transform(&tested, &mut xyz_tested)?;
+    let mut xyz_expected = vec![0f64; count * 3];
+    measure.transform(&expected, &mut xyz_expected)?;
+
+    let mut sum = 0f64;
+    let mut max_de2000 = 0f32;
+    let mut worst = 0usize;
+    for (i, (v0, v1)) in xyz_expected
+        .chunks_exact(3)
+        .zip(xyz_tested.chunks_exact(3))
+        .enumerate()
+    {
+        let lab0 = Lab::from_normalized_xyz(v0[0] as f32, v0[1] as f32, v0[2] as f32);
+        let lab1 = Lab::from_normalized_xyz(v1[0] as f32, v1[1] as f32, v1[2] as f32);
+        let delta_e = lab0.delta_e_2000(&lab1);
+        sum += delta_e as f64;
+        if delta_e > max_de2000 {
+            max_de2000 = delta_e;
+            worst = i;
+        }
+    }
+
+    Ok(AccuracyReport {
+        max_de2000,
+        mean_de2000: (sum / count as f64) as f32,
+        worst_input: src[worst * src_channels..(worst + 1) * src_channels].to_vec(),
+        samples: count,
+    })
+}
+
+/// Normalized source values, see [estimate_accuracy] for their distribution.
+fn accuracy_samples(channels: usize, samples: usize) -> Vec<f32> {
+    let mut result = Vec::with_capacity(samples * channels);
+    let ramp = |i: usize, n: usize| i as f32 / (n - 1).max(1) as f32;
+    if channels == 1 {
+        result.extend((0..samples).map(|i| ramp(i, samples)));
+        return result;
+    }
+
+    // Additive recurrence with generalized golden ratio,
+    // it has the lowest discrepancy of simple sequences in any dimension.
+    let mut phi = 2f64;
+    for _ in 0..32 {
+        phi = (1. + phi).powf(1. / (channels + 1) as f64);
+    }
+    let alphas = (1..=channels)
+        .map(|c| 1. / phi.powi(c as i32))
+        .collect::<Vec<f64>>();
+    let point = |i: usize, dst: &mut [f32]| {
+        for (v, &alpha) in dst.iter_mut().zip(alphas.iter()) {
+            *v = (0.5 + alpha * i as f64).fract() as f32;
+        }
+    };
+
+    let neutral = (samples / 8).max(2);
+    let surface = samples / 4;
+    for i in 0..neutral {
+        let v = ramp(i, neutral);
+        if channels == 4 {
+            // CMYK neutrals are made either with CMY or with K
+            if i % 2 == 0 {
+                result.extend_from_slice(&[v, v, v, 0.]);
+            } else {
+                result.extend_from_slice(&[0., 0., 0., v]);
+            }
+        } else {
+            result.extend(std::iter::repeat_n(v, channels));
+        }
+    }
+    let mut value = [0f32; 4];
+    for i in 0..surface {
+        point(i, &mut value[..channels]);
+        value[i % channels] = ((i / channels) % 2) as f32;
+        result.extend_from_slice(&value[..channels]);
+    }
+    for i in 0..samples - neutral - surface {
+        point(i + surface, &mut value[..channels]);
+        result.extend_from_slice(&value[..channels]);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversions::make_lut3x3_executor;
+
+    fn sampled_lut<const GRID_SIZE: usize>(
+        src: &ColorProfile,
+        dst: &ColorProfile,
+    ) -> Box<dyn TransformExecutor<u8> + Send + Sync> {
+        let scale = 1. / (GRID_SIZE - 1) as f32;
+        let mut origins = Vec::with_capacity(GRID_SIZE * GRID_SIZE * GRID_SIZE * 3);
+        for x in 0..GRID_SIZE {
+            for y in 0..GRID_SIZE {
+                for z in 0..GRID_SIZE {
+                    origins.extend_from_slice(&[
+                        x as f32 * scale,
+                        y as f32 * scale,
+                        z as f32 * scale,
+                    ]);
+                }
+            }
+        }
+        let options = TransformOptions {
+            prefer_fixed_point: false,
+            ..Default::default()
+        };
+        let mut lut = vec![0f32; origins.len()];
+        src.create_transform_f32(Layout::Rgb, dst, Layout::Rgb, options)
+            .unwrap()
+            .transform(&origins, &mut lut)
+            .unwrap();
+        make_lut3x3_executor::<u8, GRID_SIZE, 8>(Layout::Rgb, Layout::Rgb, lut, options)
+    }
+
+    #[test]
+    fn test_accuracy_lut_grid_size() {
+        let srgb = ColorProfile::new_srgb();
+        let pro_photo = ColorProfile::new_pro_photo_rgb();
+        let intent = RenderingIntent::Perceptual;
+        let coarse = sampled_lut::<5>(&srgb, &pro_photo);
+        let fine = sampled_lut::<33>(&srgb, &pro_photo);
+        let coarse = estimate_accuracy(&*coarse, &srgb, &pro_photo, intent, 8, 2000).unwrap();
+        let fine = estimate_accuracy(&*fine, &srgb, &pro_photo, intent, 8, 2000).unwrap();
+        assert_eq!(coarse.samples, 2000);
+        assert_eq!(coarse.worst_input.len(), 3);
+        assert!(coarse.max_de2000 > fine.max_de2000, "{coarse:?} {fine:?}");
+        assert!(
+            coarse.mean_de2000 > fine.mean_de2000 * 2.,
+            "{coarse:?} {fine:?}"
+        );
+        assert!(fine.mean_de2000 < 0.5, "{fine:?}");
+    }
+
+    #[test]
+    fn test_accuracy_matrix_shaper() {
+        let srgb = ColorProfile::new_srgb();
+        let bt2020 = ColorProfile::new_bt2020();
+        let intent = RenderingIntent::Perceptual;
+        let transform = srgb
+            .create_transform_f32(
+                Layout::Rgb,
+                &bt2020,
+                Layout::Rgb,
+                TransformOptions::default(),
+            )
+            .unwrap();
+        let report = estimate_accuracy(&*transform, &srgb, &bt2020, intent, 1, 1000).unwrap();
+        assert!(report.max_de2000 < 0.1, "{report:?}");
+
+        let transform = srgb
+            .create_transform_8bit(
+                Layout::Rgb,
+                &bt2020,
+                Layout::Rgb,
+                TransformOptions::default(),
+            )
+            .unwrap();
+        let report8 = estimate_accuracy(&*transform, &srgb, &bt2020, intent, 8, 1000).unwrap();
+        assert!(report8.mean_de2000 > report.mean_de2000, "{report8:?}");
+        assert!(report8.max_de2000 < 1.5, "{report8:?}");
+    }
+
+    #[test]
+    fn test_accuracy_samples() {
+        let samples = accuracy_samples(3, 800);
+        assert_eq!(samples.len(), 800 * 3);
+        assert!(samples.iter().all(|&v| (0. ..=1.).contains(&v)));
+        let neutral = samples
+            .chunks_exact(3)
+            .filter(|v| v[0] == v[1] && v[1] == v[2])
+            .count();
+        assert!(neutral >= 100);
+        let surface = samples
+            .chunks_exact(3)
+            .filter(|v| v.iter().any(|&x| x == 0. || x == 1.))
+            .count();
+        assert!(surface >= 200);
+        assert_eq!(accuracy_samples(4, 100).len(), 400);
+        assert_eq!(accuracy_samples(1, 100).len(), 100);
+    }
+}
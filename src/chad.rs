@@ -39,6 +39,54 @@ pub(crate) const BRADFORD_D: Matrix3d = Matrix3d {
 
 pub(crate) const BRADFORD_F: Matrix3f = BRADFORD_D.to_f32();
 
+pub(crate) const CAT02_D: Matrix3d = Matrix3d {
+    v: [
+        [0.7328, 0.4296, -0.1624],
+        [-0.7036, 1.6975, 0.0061],
+        [0.0030, 0.0136, 0.9834],
+    ],
+};
+
+/// Hunt-Pointer-Estevez cone response normalized to D65
+pub(crate) const VON_KRIES_D: Matrix3d = Matrix3d {
+    v: [
+        [0.40024, 0.70760, -0.08081],
+        [-0.22630, 1.16532, 0.04570],
+        [0., 0., 0.91822],
+    ],
+};
+
+/// Chromatic adaptation transform, defines cone response space white points are scaled in
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default)]
+pub enum ChromaticAdaptation {
+    /// Bradford, used by ICC for `chad` tag
+    #[default]
+    Bradford,
+    /// CIECAM02 adaptation
+    Cat02,
+    /// Von Kries with Hunt-Pointer-Estevez cone response
+    VonKries,
+    /// Scaling of XYZ, no cone response space
+    Xyz,
+}
+
+impl ChromaticAdaptation {
+    /// Returns cone response matrix from XYZ
+    pub const fn cone_matrix(self) -> Matrix3d {
+        match self {
+            ChromaticAdaptation::Bradford => BRADFORD_D,
+            ChromaticAdaptation::Cat02 => CAT02_D,
+            ChromaticAdaptation::VonKries => VON_KRIES_D,
+            ChromaticAdaptation::Xyz => Matrix3d::IDENTITY,
+        }
+    }
+
+    /// Makes matrix adapting XYZ from `source_white` into `target_white`
+    pub const fn adaptation_matrix(self, source_white: Xyz, target_white: Xyz) -> Matrix3f {
+        compute_chromatic_adaption_d(source_white, target_white, self.cone_matrix()).to_f32()
+    }
+}
+
 #[inline]
 pub(crate) const fn compute_chromatic_adaption(
     source_white_point: Xyz,
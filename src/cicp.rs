@@ -28,12 +28,12 @@
  */
 use crate::gamma::{
     bt1361_to_linear, hlg_to_linear, iec61966_to_linear, log100_sqrt10_to_linear, log100_to_linear,
-    pq_to_linear, smpte240_to_linear, smpte428_to_linear,
+    pq_to_linear, smpte428_to_linear,
 };
 use crate::{
     Chromaticity,
     err::CmsError,
-    trc::{ToneReprCurve, build_srgb_gamma_table, build_trc_table, curve_from_gamma},
+    trc::{ToneReprCurve, build_adaptive_trc_table, curve_from_gamma},
 };
 use std::convert::TryFrom;
 
@@ -420,8 +420,6 @@ impl TryFrom<TransferCharacteristics> for ToneReprCurve {
     /// See [ICC.1:2010](https://www.color.org/specification/ICC1v43_2010-12.pdf)
     /// See [Rec. ITU-R BT.2100-2](https://www.itu.int/dms_pubrec/itu-r/rec/bt/R-REC-BT.2100-2-201807-I!!PDF-E.pdf)
     fn try_from(value: TransferCharacteristics) -> Result<Self, Self::Error> {
-        const NUM_TRC_TABLE_ENTRIES: i32 = 1024;
-
         Ok(match value {
             TransferCharacteristics::Reserved => {
                 return Err(CmsError::UnsupportedTrc(value as u8));
@@ -482,41 +480,48 @@ impl TryFrom<TransferCharacteristics> for ToneReprCurve {
             TransferCharacteristics::Bt470M => curve_from_gamma(2.2),
             TransferCharacteristics::Bt470Bg => curve_from_gamma(2.8),
             TransferCharacteristics::Smpte240 => {
-                let table = build_trc_table(NUM_TRC_TABLE_ENTRIES, smpte240_to_linear);
-                ToneReprCurve::Lut(table)
+                // Same form as BT.709 with α = 1.1115, β = 0.0228 and 4.0 linear slope
+                const ALPHA: f32 = 1.111572195921731;
+                const BETA: f32 = 0.022821585529445;
+                ToneReprCurve::Parametric(vec![
+                    1. / 0.45,
+                    1. / ALPHA,
+                    1. - 1. / ALPHA,
+                    1. / 4.,
+                    4. * BETA,
+                ])
             }
             TransferCharacteristics::Linear => curve_from_gamma(1.),
             TransferCharacteristics::Log100 => {
-                let table = build_trc_table(NUM_TRC_TABLE_ENTRIES, log100_to_linear);
+                let table = build_adaptive_trc_table(log100_to_linear);
                 ToneReprCurve::Lut(table)
             }
             TransferCharacteristics::Log100sqrt10 => {
-                let table = build_trc_table(NUM_TRC_TABLE_ENTRIES, log100_sqrt10_to_linear);
+                let table = build_adaptive_trc_table(log100_sqrt10_to_linear);
                 ToneReprCurve::Lut(table)
             }
             TransferCharacteristics::Iec61966 => {
-                let table = build_trc_table(NUM_TRC_TABLE_ENTRIES, iec61966_to_linear);
+                let table = build_adaptive_trc_table(iec61966_to_linear);
                 ToneReprCurve::Lut(table)
             }
             TransferCharacteristics::Bt1361 => {
-                let table = build_trc_table(NUM_TRC_TABLE_ENTRIES, bt1361_to_linear);
+                let table = build_adaptive_trc_table(bt1361_to_linear);
                 ToneReprCurve::Lut(table)
             }
             TransferCharacteristics::Srgb => {
-                // Should we prefer this or curveType::Parametric?
-                ToneReprCurve::Lut(build_srgb_gamma_table(NUM_TRC_TABLE_ENTRIES))
+                ToneReprCurve::Parametric(vec![2.4, 1. / 1.055, 0.055 / 1.055, 1. / 12.92, 0.04045])
             }
 
             TransferCharacteristics::Smpte2084 => {
-                let table = build_trc_table(NUM_TRC_TABLE_ENTRIES, pq_to_linear);
+                let table = build_adaptive_trc_table(pq_to_linear);
                 ToneReprCurve::Lut(table)
             }
             TransferCharacteristics::Smpte428 => {
-                let table = build_trc_table(NUM_TRC_TABLE_ENTRIES, smpte428_to_linear);
+                let table = build_adaptive_trc_table(smpte428_to_linear);
                 ToneReprCurve::Lut(table)
             }
             TransferCharacteristics::Hlg => {
-                let table = build_trc_table(NUM_TRC_TABLE_ENTRIES, hlg_to_linear);
+                let table = build_adaptive_trc_table(hlg_to_linear);
                 ToneReprCurve::Lut(table)
             }
         })
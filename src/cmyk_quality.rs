@@ -0,0 +1,243 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::lab::Lab;
+use crate::roundtrip::normalized_xyz_profile;
+use crate::{CmsError, ColorProfile, DataColorSpace, Layout, RenderingIntent, TransformOptions};
+
+/// Steps of C, M and Y in synthesized patches, ink levels of ISO 12642-2 target
+const PATCH_INK_LEVELS: [f32; 6] = [0., 0.1, 0.2, 0.4, 0.7, 1.];
+/// Steps of K in synthesized patches
+const PATCH_K_LEVELS: [f32; 6] = [0., 0.2, 0.4, 0.6, 0.8, 1.];
+/// Points of single ink, K and destination gray ramps
+const RAMP_STEPS: usize = 33;
+
+/// Result of [cmyk_quality_report]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CmykQualityReport {
+    /// Mean CIE 1976 delta E between destination colors of patches and of
+    /// their CMYK → destination → CMYK round trip
+    pub patches_mean_delta_e: f32,
+    /// Maximum CIE 1976 delta E between destination colors of patches and of
+    /// their CMYK → destination → CMYK round trip
+    pub patches_max_delta_e: f32,
+    /// Largest total ink coverage in percent of round tripped patches,
+    /// shows ink limit the profile separation holds
+    pub max_total_ink: f32,
+    /// Mean absolute change of total ink coverage in percent between patches
+    /// and round tripped ones
+    pub mean_total_ink_change: f32,
+    /// Largest rise of L* along single ink ramps, 0 when every ramp gets darker monotonically
+    pub max_ramp_reversal: f32,
+    /// Largest chroma C*ab of destination gray ramp round tripped through CMYK
+    pub gray_max_chroma: f32,
+    /// Largest second difference of L* error of destination gray ramp round tripped
+    /// through CMYK, smooth gray axis keeps it small even when the error itself is not
+    pub gray_max_roughness: f32,
+    /// Largest chroma C*ab of pure K ramp in destination
+    pub k_max_chroma: f32,
+}
+
+fn chroma(lab: &Lab) -> f32 {
+    lab.a.hypot(lab.b)
+}
+
+fn ramp(steps: usize) -> impl Iterator<Item = f32> {
+    (0..steps).map(move |i| i as f32 / (steps - 1) as f32)
+}
+
+/// Measures print oriented quality of CMYK profile transforms into RGB `destination`.
+///
+/// Patches are synthesized from C, M and Y at 0, 10, 20, 40, 70 and 100% over K
+/// at 0, 20, 40, 60, 80 and 100%. Ramps of every single ink, of pure K and of destination
+/// gray are sampled at 33 points. Colors are measured in CIE Lab of the destination.
+pub fn cmyk_quality_report(
+    cmyk: &ColorProfile,
+    destination: &ColorProfile,
+    intent: RenderingIntent,
+) -> Result<CmykQualityReport, CmsError> {
+    if cmyk.color_space != DataColorSpace::Cmyk || destination.color_space != DataColorSpace::Rgb {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+    let options = TransformOptions {
+        rendering_intent: intent,
+        ..Default::default()
+    };
+    let to_destination =
+        cmyk.create_transform_f32(Layout::Rgba, destination, Layout::Rgb, options)?;
+    let to_cmyk = destination.create_transform_f32(Layout::Rgb, cmyk, Layout::Rgba, options)?;
+    let measure = destination.create_transform_f32(
+        Layout::Rgb,
+        &normalized_xyz_profile(),
+        Layout::Rgb,
+        TransformOptions::default(),
+    )?;
+
+    let rgb_to_lab = |rgb: &[f32]| -> Result<Vec<Lab>, CmsError> {
+        let mut xyz = vec![0f32; rgb.len()];
+        measure.transform(rgb, &mut xyz)?;
+        Ok(xyz
+            .chunks_exact(3)
+            .map(|v| Lab::from_normalized_xyz(v[0], v[1], v[2]))
+            .collect())
+    };
+    let cmyk_to_rgb = |cmyk: &[f32]| -> Result<Vec<f32>, CmsError> {
+        let mut rgb = vec![0f32; cmyk.len() / 4 * 3];
+        to_destination.transform(cmyk, &mut rgb)?;
+        Ok(rgb)
+    };
+    let rgb_to_cmyk = |rgb: &[f32]| -> Result<Vec<f32>, CmsError> {
+        let mut cmyk = vec![0f32; rgb.len() / 3 * 4];
+        to_cmyk.transform(rgb, &mut cmyk)?;
+        Ok(cmyk)
+    };
+
+    // Patches
+    let mut patches = Vec::with_capacity(PATCH_INK_LEVELS.len().pow(3) * PATCH_K_LEVELS.len() * 4);
+    for k in PATCH_K_LEVELS {
+        for c in PATCH_INK_LEVELS {
+            for m in PATCH_INK_LEVELS {
+                for y in PATCH_INK_LEVELS {
+                    patches.extend_from_slice(&[c, m, y, k]);
+                }
+            }
+        }
+    }
+    let rgb = cmyk_to_rgb(&patches)?;
+    let round_tripped = rgb_to_cmyk(&rgb)?;
+    let labs = rgb_to_lab(&rgb)?;
+    let round_tripped_labs = rgb_to_lab(&cmyk_to_rgb(&round_tripped)?)?;
+    let count = labs.len();
+    let mut delta_e_sum = 0f64;
+    let mut patches_max_delta_e = 0f32;
+    for (lab0, lab1) in labs.iter().zip(round_tripped_labs.iter()) {
+        let delta_e = lab0.delta_e_76(lab1);
+        delta_e_sum += delta_e as f64;
+        patches_max_delta_e = patches_max_delta_e.max(delta_e);
+    }
+    let mut max_total_ink = 0f32;
+    let mut ink_change_sum = 0f64;
+    for (src, dst) in patches.chunks_exact(4).zip(round_tripped.chunks_exact(4)) {
+        let src_ink = src.iter().sum::<f32>() * 100.;
+        let dst_ink = dst.iter().sum::<f32>() * 100.;
+        max_total_ink = max_total_ink.max(dst_ink);
+        ink_change_sum += (dst_ink - src_ink).abs() as f64;
+    }
+
+    // Single ink ramps
+    let mut ink_ramps = Vec::with_capacity(4 * RAMP_STEPS * 4);
+    for channel in 0..4 {
+        for v in ramp(RAMP_STEPS) {
+            let mut pixel = [0f32; 4];
+            pixel[channel] = v;
+            ink_ramps.extend_from_slice(&pixel);
+        }
+    }
+    let ink_labs = rgb_to_lab(&cmyk_to_rgb(&ink_ramps)?)?;
+    let max_ramp_reversal = ink_labs
+        .chunks_exact(RAMP_STEPS)
+        .flat_map(|ramp| ramp.windows(2).map(|w| w[1].l - w[0].l))
+        .fold(0f32, f32::max);
+    // Last ramp is pure K
+    let k_max_chroma = ink_labs[3 * RAMP_STEPS..]
+        .iter()
+        .map(chroma)
+        .fold(0f32, f32::max);
+
+    // Destination gray axis
+    let gray = ramp(RAMP_STEPS)
+        .flat_map(|v| [v, v, v])
+        .collect::<Vec<f32>>();
+    let gray_labs = rgb_to_lab(&gray)?;
+    let gray_round_tripped = rgb_to_lab(&cmyk_to_rgb(&rgb_to_cmyk(&gray)?)?)?;
+    let gray_max_chroma = gray_round_tripped.iter().map(chroma).fold(0f32, f32::max);
+    let gray_error = gray_round_tripped
+        .iter()
+        .zip(gray_labs.iter())
+        .map(|(a, b)| a.l - b.l)
+        .collect::<Vec<f32>>();
+    let gray_max_roughness = gray_error
+        .windows(3)
+        .map(|w| (w[2] - 2. * w[1] + w[0]).abs())
+        .fold(0f32, f32::max);
+
+    Ok(CmykQualityReport {
+        patches_mean_delta_e: (delta_e_sum / count as f64) as f32,
+        patches_max_delta_e,
+        max_total_ink,
+        mean_total_ink_change: (ink_change_sum / count as f64) as f32,
+        max_ramp_reversal,
+        gray_max_chroma,
+        gray_max_roughness,
+        k_max_chroma,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cmyk_quality_swop() {
+        let swop =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let srgb = ColorProfile::new_srgb();
+        // Bounds have some headroom over measured values, they guard against
+        // interpolation and sampling changes degrading separations
+        for (intent, max_delta_e, max_roughness) in [
+            (RenderingIntent::Perceptual, 16., 1.7),
+            (RenderingIntent::RelativeColorimetric, 12., 4.),
+        ] {
+            let report = cmyk_quality_report(&swop, &srgb, intent).unwrap();
+            assert!(report.patches_mean_delta_e < 2.5, "{intent:?} {report:?}");
+            assert!(
+                report.patches_max_delta_e < max_delta_e,
+                "{intent:?} {report:?}"
+            );
+            // SWOP separation holds 300% ink limit
+            assert!(report.max_total_ink < 310., "{intent:?} {report:?}");
+            assert!(report.mean_total_ink_change < 45., "{intent:?} {report:?}");
+            assert_eq!(report.max_ramp_reversal, 0., "{intent:?} {report:?}");
+            assert!(report.gray_max_chroma < 1., "{intent:?} {report:?}");
+            assert!(
+                report.gray_max_roughness < max_roughness,
+                "{intent:?} {report:?}"
+            );
+            assert!(report.k_max_chroma < 2.5, "{intent:?} {report:?}");
+        }
+    }
+
+    #[test]
+    fn test_cmyk_quality_rejects_rgb() {
+        let srgb = ColorProfile::new_srgb();
+        assert_eq!(
+            cmyk_quality_report(&srgb, &srgb, RenderingIntent::Perceptual),
+            Err(CmsError::UnsupportedProfileConnection)
+        );
+    }
+}
@@ -0,0 +1,206 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::writer::{ByteSink, write_u16_be, write_u32_be};
+use crate::{CmsError, DataColorSpace, Vector3f};
+
+/// Colorant of `clrt` tag, naming a device channel of the profile
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NamedColorant {
+    /// ASCII name, at most 31 characters are stored
+    pub name: String,
+    /// Colorant in profile PCS, L*a*b* for Lab PCS, XYZ otherwise
+    pub pcs: Vector3f,
+}
+
+const NAME_SIZE: usize = 32;
+const COLORANT_SIZE: usize = NAME_SIZE + 3 * 2;
+
+/// Decodes 16 bit PCS value, Lab uses ICC v4 encoding and XYZ u1Fixed15
+fn decode_pcs(pcs: DataColorSpace, v: [u16; 3]) -> Vector3f {
+    if pcs == DataColorSpace::Lab {
+        Vector3f {
+            v: [
+                v[0] as f32 * (100. / 65535.),
+                v[1] as f32 * (255. / 65535.) - 128.,
+                v[2] as f32 * (255. / 65535.) - 128.,
+            ],
+        }
+    } else {
+        Vector3f {
+            v: v.map(|x| x as f32 / 32768.),
+        }
+    }
+}
+
+fn encode_pcs(pcs: DataColorSpace, v: Vector3f) -> [u16; 3] {
+    let quantize = |x: f32| (x * 65535. + 0.5).max(0.).min(65535.) as u16;
+    if pcs == DataColorSpace::Lab {
+        [
+            quantize(v.v[0] / 100.),
+            quantize((v.v[1] + 128.) / 255.),
+            quantize((v.v[2] + 128.) / 255.),
+        ]
+    } else {
+        v.v.map(|x| (x * 32768. + 0.5).max(0.).min(65535.) as u16)
+    }
+}
+
+/// Parses `clrt` tag data, starting with tag type signature
+pub(crate) fn parse_colorant_table(
+    slice: &[u8],
+    pcs: DataColorSpace,
+) -> Result<Vec<NamedColorant>, CmsError> {
+    if slice.len() < 12 || &slice[0..4] != b"clrt" {
+        return Err(CmsError::InvalidProfile);
+    }
+    let count = u32::from_be_bytes([slice[8], slice[9], slice[10], slice[11]]) as usize;
+    let data = count
+        .checked_mul(COLORANT_SIZE)
+        .and_then(|size| slice.get(12..12usize.checked_add(size)?))
+        .ok_or(CmsError::InvalidProfile)?;
+    data.chunks_exact(COLORANT_SIZE)
+        .map(|chunk| {
+            let name = &chunk[..NAME_SIZE];
+            let name = &name[..name.iter().position(|&x| x == 0).unwrap_or(NAME_SIZE)];
+            if !name.is_ascii() {
+                return Err(CmsError::InvalidProfile);
+            }
+            let value = |i: usize| {
+                u16::from_be_bytes([chunk[NAME_SIZE + i * 2], chunk[NAME_SIZE + i * 2 + 1]])
+            };
+            Ok(NamedColorant {
+                name: String::from_utf8_lossy(name).into_owned(),
+                pcs: decode_pcs(pcs, [value(0), value(1), value(2)]),
+            })
+        })
+        .collect()
+}
+
+/// Writes tag data, names longer than 31 characters are truncated.
+/// Returns count of bytes written.
+pub(crate) fn encode_colorant_table(
+    colorants: &[NamedColorant],
+    pcs: DataColorSpace,
+    into: &mut impl ByteSink,
+) -> Result<usize, CmsError> {
+    if colorants.iter().any(|x| !x.name.is_ascii()) {
+        return Err(CmsError::InvalidProfile);
+    }
+    for &byte in b"clrt" {
+        into.push(byte);
+    }
+    write_u32_be(into, 0);
+    write_u32_be(into, colorants.len() as u32);
+    for colorant in colorants.iter() {
+        let name = colorant.name.as_bytes();
+        let name = &name[..name.len().min(NAME_SIZE - 1)];
+        for i in 0..NAME_SIZE {
+            into.push(name.get(i).copied().unwrap_or(0));
+        }
+        for v in encode_pcs(pcs, colorant.pcs) {
+            write_u16_be(into, v);
+        }
+    }
+    Ok(12 + colorants.len() * COLORANT_SIZE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colorant_table_round_trip() {
+        let colorants = vec![
+            NamedColorant {
+                name: "Cyan".to_string(),
+                pcs: Vector3f {
+                    v: [55., -37., -50.],
+                },
+            },
+            NamedColorant {
+                name: "Orange".to_string(),
+                pcs: Vector3f { v: [67., 50., 80.] },
+            },
+            NamedColorant {
+                name: "A name that is longer than the thirty one bytes".to_string(),
+                pcs: Vector3f { v: [100., 0., 0.] },
+            },
+        ];
+        let mut encoded = Vec::new();
+        let size = encode_colorant_table(&colorants, DataColorSpace::Lab, &mut encoded).unwrap();
+        assert_eq!(size, encoded.len());
+        assert_eq!(size, 12 + 3 * 38);
+        let decoded = parse_colorant_table(&encoded, DataColorSpace::Lab).unwrap();
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[0].name, "Cyan");
+        assert_eq!(decoded[1].name, "Orange");
+        assert_eq!(decoded[2].name, "A name that is longer than the ");
+        for (a, b) in decoded.iter().zip(colorants.iter()) {
+            for (x, y) in a.pcs.v.iter().zip(b.pcs.v.iter()) {
+                assert!((x - y).abs() < 0.01, "{x} vs {y}");
+            }
+        }
+
+        let xyz = [NamedColorant {
+            name: "Black".to_string(),
+            pcs: Vector3f {
+                v: [0.0326, 0.0338, 0.0279],
+            },
+        }];
+        let mut encoded = Vec::new();
+        encode_colorant_table(&xyz, DataColorSpace::Xyz, &mut encoded).unwrap();
+        let decoded = parse_colorant_table(&encoded, DataColorSpace::Xyz).unwrap();
+        for (x, y) in decoded[0].pcs.v.iter().zip(xyz[0].pcs.v.iter()) {
+            assert!((x - y).abs() < 1e-4, "{x} vs {y}");
+        }
+    }
+
+    #[test]
+    fn test_colorant_table_malformed() {
+        let mut encoded = Vec::new();
+        let colorant = NamedColorant {
+            name: "Magenta".to_string(),
+            pcs: Vector3f::default(),
+        };
+        encode_colorant_table(
+            &[colorant.clone(), colorant],
+            DataColorSpace::Lab,
+            &mut encoded,
+        )
+        .unwrap();
+        assert!(parse_colorant_table(&encoded[..encoded.len() - 1], DataColorSpace::Lab).is_err());
+        encoded[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(parse_colorant_table(&encoded, DataColorSpace::Lab).is_err());
+        let non_ascii = NamedColorant {
+            name: "Grün".to_string(),
+            pcs: Vector3f::default(),
+        };
+        assert!(encode_colorant_table(&[non_ascii], DataColorSpace::Lab, &mut Vec::new()).is_err());
+    }
+}
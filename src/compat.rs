@@ -0,0 +1,58 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+#![allow(deprecated)]
+//! Items which were public by accident and are kept only to not break dependents.
+use crate::CmsError;
+
+/// Helper for intermediate transformation stages
+#[deprecated(
+    since = "0.5.2",
+    note = "internal helper, it is not used by transforms anymore and will be removed"
+)]
+#[doc(hidden)]
+pub trait Stage {
+    fn transform(&self, src: &[f32], dst: &mut [f32]) -> Result<(), CmsError>;
+}
+
+#[deprecated(since = "0.5.2", note = "internal helper, will be removed")]
+#[doc(hidden)]
+#[inline(always)]
+pub const fn rounding_div_ceil(value: i32, div: i32) -> i32 {
+    crate::math::rounding_div_ceil(value, div)
+}
+
+/// 3D CLUT helper
+#[deprecated(since = "0.5.2", note = "internal helper, will be removed")]
+#[doc(hidden)]
+pub type Array3D<'a> = crate::nd_array::Array3D<'a>;
+
+/// 4D CLUT helper
+#[deprecated(since = "0.5.2", note = "internal helper, will be removed")]
+#[doc(hidden)]
+pub type Array4D<'a> = crate::nd_array::Array4D<'a>;
@@ -0,0 +1,427 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::conversions::{make_lut3x3_executor, make_lut4x3_executor};
+use crate::dispatch::{KernelFamily, with_forced_kernel};
+use crate::{ColorProfile, Layout, TransformExecutor, TransformOptions};
+use num_traits::AsPrimitive;
+
+/// Transform path checked by [verify_simd_consistency]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum KernelPath {
+    /// RGB matrix-shaper transform in floating point
+    Matrix,
+    /// RGB matrix-shaper transform in Q4.12 fixed point
+    MatrixFixedPoint,
+    /// 3 channels to 3 channels LUT
+    Lut3x3,
+    /// 4 channels to 3 channels LUT
+    Lut4x3,
+    /// Gray to RGB transform
+    Gray,
+}
+
+/// Deviation of one kernel family from the scalar reference
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct KernelDeviation {
+    pub path: KernelPath,
+    pub family: KernelFamily,
+    pub bit_depth: usize,
+    /// Maximum absolute difference against scalar output, in fractions of full range
+    pub max_abs_diff: f32,
+}
+
+/// Result of [verify_simd_consistency]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConsistencyReport {
+    pub deviations: Vec<KernelDeviation>,
+}
+
+impl ConsistencyReport {
+    /// Checks that every kernel family is within `tolerance` of the scalar reference,
+    /// tolerance is expressed in fractions of full range.
+    pub fn all_within(&self, tolerance: f32) -> bool {
+        self.deviations.iter().all(|x| x.max_abs_diff <= tolerance)
+    }
+
+    /// Checks if report contains `family` for `path`
+    pub fn covers(&self, path: KernelPath, family: KernelFamily) -> bool {
+        self.deviations
+            .iter()
+            .any(|x| x.path == path && x.family == family)
+    }
+}
+
+/// Runs every kernel family available on the current machine on reference inputs
+/// and reports maximum deviation of each from the scalar implementation.
+///
+/// Matrix, LUT and gray paths are checked at 8 and 16 bit,
+/// fixed point matrix path at 8 and 10 bit.
+/// Paths without dedicated SIMD kernels are reported only for [KernelFamily::Scalar].
+pub fn verify_simd_consistency() -> ConsistencyReport {
+    let mut report = ConsistencyReport::default();
+    let families = KernelFamily::available();
+    let simd_families = families
+        .iter()
+        .copied()
+        .filter(|&x| x != KernelFamily::Scalar)
+        .collect::<Vec<_>>();
+
+    let src = ColorProfile::new_srgb();
+    let dst = ColorProfile::new_bt2020();
+    let float_options = TransformOptions {
+        prefer_fixed_point: false,
+        ..Default::default()
+    };
+    let fixed_options = TransformOptions {
+        prefer_fixed_point: true,
+        ..Default::default()
+    };
+
+    let rgba8 = reference_inputs::<u8, 8>(4);
+    let rgba16 = reference_inputs::<u16, 16>(4);
+
+    compare(
+        &mut report,
+        KernelPath::Matrix,
+        8,
+        &simd_families,
+        &rgba8,
+        4,
+        || {
+            src.create_transform_8bit(Layout::Rgba, &dst, Layout::Rgba, float_options)
+                .ok()
+        },
+    );
+    compare(
+        &mut report,
+        KernelPath::Matrix,
+        16,
+        &simd_families,
+        &rgba16,
+        4,
+        || {
+            src.create_transform_16bit(Layout::Rgba, &dst, Layout::Rgba, float_options)
+                .ok()
+        },
+    );
+    compare(
+        &mut report,
+        KernelPath::MatrixFixedPoint,
+        8,
+        &simd_families,
+        &rgba8,
+        4,
+        || {
+            src.create_transform_8bit(Layout::Rgba, &dst, Layout::Rgba, fixed_options)
+                .ok()
+        },
+    );
+    let rgba10 = reference_inputs::<u16, 10>(4);
+    compare(
+        &mut report,
+        KernelPath::MatrixFixedPoint,
+        10,
+        &simd_families,
+        &rgba10,
+        4,
+        || {
+            src.create_transform_10bit(Layout::Rgba, &dst, Layout::Rgba, fixed_options)
+                .ok()
+        },
+    );
+
+    const GRID_SIZE: usize = 17;
+    let lut3 = reference_lut(3, GRID_SIZE);
+    compare(
+        &mut report,
+        KernelPath::Lut3x3,
+        8,
+        &simd_families,
+        &rgba8,
+        4,
+        || {
+            Some(make_lut3x3_executor::<u8, GRID_SIZE, 8>(
+                Layout::Rgba,
+                Layout::Rgba,
+                lut3.clone(),
+                float_options,
+            ))
+        },
+    );
+    compare(
+        &mut report,
+        KernelPath::Lut3x3,
+        16,
+        &simd_families,
+        &rgba16,
+        4,
+        || {
+            Some(make_lut3x3_executor::<u16, GRID_SIZE, 16>(
+                Layout::Rgba,
+                Layout::Rgba,
+                lut3.clone(),
+                float_options,
+            ))
+        },
+    );
+
+    let lut4 = reference_lut(4, GRID_SIZE);
+    compare(
+        &mut report,
+        KernelPath::Lut4x3,
+        8,
+        &simd_families,
+        &rgba8,
+        4,
+        || {
+            Some(make_lut4x3_executor::<u8, GRID_SIZE, 8>(
+                Layout::Rgba,
+                lut4.clone(),
+                float_options,
+            ))
+        },
+    );
+    compare(
+        &mut report,
+        KernelPath::Lut4x3,
+        16,
+        &simd_families,
+        &rgba16,
+        4,
+        || {
+            Some(make_lut4x3_executor::<u16, GRID_SIZE, 16>(
+                Layout::Rgba,
+                lut4.clone(),
+                float_options,
+            ))
+        },
+    );
+
+    let gray = ColorProfile::new_gray_with_gamma(2.2);
+    let gray8 = reference_inputs::<u8, 8>(1);
+    let gray16 = reference_inputs::<u16, 16>(1);
+    compare(&mut report, KernelPath::Gray, 8, &[], &gray8, 1, || {
+        gray.create_transform_8bit(Layout::Gray, &src, Layout::Rgba, float_options)
+            .ok()
+    });
+    compare(&mut report, KernelPath::Gray, 16, &[], &gray16, 1, || {
+        gray.create_transform_16bit(Layout::Gray, &src, Layout::Rgba, float_options)
+            .ok()
+    });
+
+    report
+}
+
+/// Regular sweep over every channel, odd levels included to hit
+/// in-between grid nodes and rounding boundaries.
+fn reference_inputs<T: Copy + 'static, const BIT_DEPTH: usize>(channels: usize) -> Vec<T>
+where
+    u32: AsPrimitive<T>,
+{
+    const LEVELS: u32 = 13;
+    let max_value = (1u32 << BIT_DEPTH) - 1;
+    let total = (LEVELS as usize).pow(channels as u32);
+    let mut result = Vec::with_capacity(total * channels);
+    for index in 0..total {
+        let mut rest = index;
+        for _ in 0..channels {
+            let level = (rest % LEVELS as usize) as u32;
+            rest /= LEVELS as usize;
+            result.push((level * max_value / (LEVELS - 1)).as_());
+        }
+    }
+    result
+}
+
+/// Smooth non-linear 3-output LUT over `inputs` dimensions.
+fn reference_lut(inputs: usize, grid_size: usize) -> Vec<f32> {
+    let total = grid_size.pow(inputs as u32);
+    let scale = 1. / (grid_size - 1) as f32;
+    let mut lut = Vec::with_capacity(total * 3);
+    for index in 0..total {
+        let mut rest = index;
+        let mut v = [0f32; 4];
+        for dst in v.iter_mut().take(inputs) {
+            *dst = (rest % grid_size) as f32 * scale;
+            rest /= grid_size;
+        }
+        lut.push((v[0] * v[0] * 0.7 + v[1] * 0.2 + v[2] * 0.1 - v[3] * 0.3).clamp(0., 1.));
+        lut.push((v[1] * 0.8 + v[2] * v[0] * 0.2 - v[3] * v[3] * 0.2).clamp(0., 1.));
+        lut.push((v[2].sqrt() * 0.9 + v[0] * 0.1 - v[3] * 0.1).clamp(0., 1.));
+    }
+    lut
+}
+
+fn run<T: Copy + Default>(
+    family: KernelFamily,
+    src: &[T],
+    src_channels: usize,
+    make: &impl Fn() -> Option<Box<dyn TransformExecutor<T> + Send + Sync>>,
+) -> Option<Vec<T>> {
+    let executor = with_forced_kernel(family, make)?;
+    // Every checked path writes RGBA
+    let mut dst = vec![T::default(); src.len() / src_channels * 4];
+    executor.transform(src, &mut dst).ok()?;
+    Some(dst)
+}
+
+fn compare<T: Copy + Default + AsPrimitive<f32>>(
+    report: &mut ConsistencyReport,
+    path: KernelPath,
+    bit_depth: usize,
+    simd_families: &[KernelFamily],
+    src: &[T],
+    src_channels: usize,
+    make: impl Fn() -> Option<Box<dyn TransformExecutor<T> + Send + Sync>>,
+) {
+    let Some(reference) = run(KernelFamily::Scalar, src, src_channels, &make) else {
+        return;
+    };
+    report.deviations.push(KernelDeviation {
+        path,
+        family: KernelFamily::Scalar,
+        bit_depth,
+        max_abs_diff: 0.,
+    });
+    let max_value = ((1u32 << bit_depth) - 1) as f32;
+    for &family in simd_families {
+        let Some(output) = run(family, src, src_channels, &make) else {
+            continue;
+        };
+        let max_abs_diff = reference
+            .iter()
+            .zip(output.iter())
+            .map(|(&a, &b)| {
+                let a: f32 = a.as_();
+                let b: f32 = b.as_();
+                (a - b).abs()
+            })
+            .fold(0f32, |acc, x| acc.max(x));
+        report.deviations.push(KernelDeviation {
+            path,
+            family,
+            bit_depth,
+            max_abs_diff: max_abs_diff / max_value,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InterpolationMethod;
+
+    #[test]
+    fn test_consistency_covers_available_families() {
+        let report = verify_simd_consistency();
+        for path in [
+            KernelPath::Matrix,
+            KernelPath::MatrixFixedPoint,
+            KernelPath::Lut3x3,
+            KernelPath::Lut4x3,
+            KernelPath::Gray,
+        ] {
+            assert!(report.covers(path, KernelFamily::Scalar), "{path:?}");
+        }
+        for family in KernelFamily::available() {
+            for path in [
+                KernelPath::Matrix,
+                KernelPath::MatrixFixedPoint,
+                KernelPath::Lut3x3,
+                KernelPath::Lut4x3,
+            ] {
+                assert!(report.covers(path, family), "{path:?} {family:?}");
+            }
+        }
+        assert!(report.all_within(1.5 / 255.), "{report:?}");
+    }
+
+    #[test]
+    fn test_lut4x3_interpolators_agree_on_grid_nodes() {
+        // 17 segments divide 65535, so every 15th 8 bit value is exactly a grid node
+        const GRID: usize = 18;
+        const STEP: usize = 255 / (GRID - 1);
+        let lut = (0..GRID * GRID * GRID * GRID * 3)
+            .map(|i| ((i * 7919) % 256) as f32 / 255.)
+            .collect::<Vec<f32>>();
+
+        let mut src = Vec::new();
+        for c in (0..=255).step_by(STEP) {
+            for m in (0..=255).step_by(STEP) {
+                for y in (0..=255).step_by(STEP) {
+                    // K is also sampled between nodes to check blending of both cubes
+                    for k in (0..=255).step_by(5) {
+                        src.extend_from_slice(&[c as u8, m as u8, y as u8, k as u8]);
+                    }
+                }
+            }
+        }
+
+        let node_value = |k: usize, c: u8, m: u8, y: u8, channel: usize| -> f32 {
+            let [c, m, y] = [c, m, y].map(|x| x as usize / STEP);
+            lut[(((k * GRID + c) * GRID + m) * GRID + y) * 3 + channel]
+        };
+
+        for family in KernelFamily::available() {
+            for interpolation_method in [
+                InterpolationMethod::Tetrahedral,
+                InterpolationMethod::Pyramid,
+                InterpolationMethod::Prism,
+                InterpolationMethod::Linear,
+            ] {
+                let options = TransformOptions {
+                    interpolation_method,
+                    ..Default::default()
+                };
+                let executor = with_forced_kernel(family, || {
+                    make_lut4x3_executor::<u8, GRID, 8>(Layout::Rgb, lut.clone(), options)
+                });
+                let mut dst = vec![0u8; src.len() / 4 * 3];
+                executor.transform(&src, &mut dst).unwrap();
+
+                for (src, dst) in src.chunks_exact(4).zip(dst.chunks_exact(3)) {
+                    let k = src[3] as usize;
+                    let w = k * (GRID - 1) / 255;
+                    let w_n = (w + 1).min(GRID - 1);
+                    let t = (k * (GRID - 1)) as f32 / 255. - w as f32;
+                    for (channel, &v) in dst.iter().enumerate() {
+                        let expected = node_value(w, src[0], src[1], src[2], channel) * (1. - t)
+                            + node_value(w_n, src[0], src[1], src[2], channel) * t;
+                        let expected = (expected * 255.).round() as u8;
+                        let tolerance = if k % STEP == 0 { 0 } else { 1 };
+                        assert!(
+                            v.abs_diff(expected) <= tolerance,
+                            "{family:?} {interpolation_method:?} {src:?} {dst:?}, expected {expected}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -29,13 +29,14 @@
 #![allow(dead_code)]
 use crate::conversions::lut_transforms::LUT_SAMPLING;
 use crate::math::FusedMultiplyAdd;
-use crate::rounding_div_ceil;
+use crate::math::rounding_div_ceil;
 #[cfg(target_arch = "x86")]
 use std::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
 use std::ops::{Add, Mul, Sub};
 
+#[derive(Copy, Clone)]
 #[repr(align(16), C)]
 pub(crate) struct SseAlignedF32(pub(crate) [f32; 4]);
 
@@ -612,7 +613,7 @@ impl<const GRID_SIZE: usize> PrismaticAvxFmaDouble<'_, GRID_SIZE> {
         let z: i32 = in_b as i32 * (GRID_SIZE as i32 - 1) / LUT_SAMPLING as i32;
 
         let c0_0 = r0.fetch(x, y, z);
-        let c0_1 = r0.fetch(x, y, z);
+        let c0_1 = r1.fetch(x, y, z);
 
         let x_n: i32 = rounding_div_ceil(in_r as i32 * (GRID_SIZE as i32 - 1), LUT_SAMPLING as i32);
         let y_n: i32 = rounding_div_ceil(in_g as i32 * (GRID_SIZE as i32 - 1), LUT_SAMPLING as i32);
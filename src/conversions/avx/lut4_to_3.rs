@@ -32,8 +32,9 @@ use crate::conversions::avx::interpolator::{
     TetrahedralAvxFmaDouble, TrilinearAvxFmaDouble,
 };
 use crate::conversions::lut_transforms::{LUT_SAMPLING, Lut4x3Factory};
-use crate::transform::PointeeSizeExpressible;
-use crate::{CmsError, InterpolationMethod, Layout, TransformExecutor, rounding_div_ceil};
+use crate::math::rounding_div_ceil;
+use crate::transform::{PointeeSizeExpressible, touch_pages};
+use crate::{CmsError, InterpolationMethod, Layout, TransformExecutor};
 use num_traits::AsPrimitive;
 #[cfg(target_arch = "x86")]
 use std::arch::x86::*;
@@ -140,6 +141,10 @@ where
     f32: AsPrimitive<T>,
     u32: AsPrimitive<T>,
 {
+    fn warmup(&self) {
+        touch_pages(&self.lut);
+    }
+
     fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
         let cn = Layout::from(LAYOUT);
         let channels = cn.channels();
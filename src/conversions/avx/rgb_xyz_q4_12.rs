@@ -336,6 +336,10 @@ impl<
 where
     u32: AsPrimitive<T>,
 {
+    fn warmup(&self) {
+        self.profile.warmup();
+    }
+
     fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
         unsafe { self.transform_avx2(src, dst) }
     }
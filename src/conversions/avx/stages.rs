@@ -322,6 +322,10 @@ impl<
 where
     u32: AsPrimitive<T>,
 {
+    fn warmup(&self) {
+        self.profile.warmup();
+    }
+
     fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
         unsafe {
             if std::arch::is_x86_feature_detected!("fma") {
@@ -32,7 +32,7 @@ use crate::conversions::avx::interpolator::{
     AvxMdInterpolation, PrismaticAvxFma, PyramidalAvxFma, SseAlignedF32, TrilinearAvxFma,
 };
 use crate::conversions::lut_transforms::Lut3x3Factory;
-use crate::transform::PointeeSizeExpressible;
+use crate::transform::{PointeeSizeExpressible, touch_pages};
 use crate::{CmsError, InterpolationMethod, Layout, TransformExecutor};
 use num_traits::AsPrimitive;
 #[cfg(target_arch = "x86")]
@@ -138,6 +138,10 @@ where
     f32: AsPrimitive<T>,
     u32: AsPrimitive<T>,
 {
+    fn warmup(&self) {
+        touch_pages(&self.lut);
+    }
+
     fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
         let src_cn = Layout::from(SRC_LAYOUT);
         let src_channels = src_cn.channels();
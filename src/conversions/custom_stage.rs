@@ -0,0 +1,386 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::conversions::wrap_premultiplied_alpha;
+use crate::transform::{PointeeSizeExpressible, TransformPlan};
+use crate::trc::ToneReprCurve;
+use crate::{
+    AlphaMode, ClampPolicy, CmsError, ColorProfile, DataColorSpace, InPlaceStage, Layout,
+    TransformExecutor, TransformF32BitExecutor, TransformOptions,
+};
+use num_traits::AsPrimitive;
+use std::sync::Arc;
+
+/// Pixels evaluated at once
+const CHUNK: usize = 1024;
+
+/// Runs user stage on linear RGB in destination primaries.
+struct TransformCustomStage<T, const BIT_DEPTH: usize> {
+    /// From the source into linear copy of the destination
+    to_linear: Box<TransformF32BitExecutor>,
+    stage: Arc<dyn InPlaceStage + Send + Sync>,
+    /// From linear copy of the destination into the destination
+    encode: Box<TransformF32BitExecutor>,
+    src_layout: Layout,
+    dst_layout: Layout,
+    /// Source alpha is copied into the destination
+    copy_alpha: bool,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+impl<
+    T: Copy + Default + AsPrimitive<f32> + PointeeSizeExpressible + Send + Sync,
+    const BIT_DEPTH: usize,
+> TransformExecutor<T> for TransformCustomStage<T, BIT_DEPTH>
+where
+    f32: AsPrimitive<T>,
+{
+    fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
+        let src_channels = self.src_layout.channels();
+        let dst_channels = self.dst_layout.channels();
+        if src.len() % src_channels != 0 || dst.len() % dst_channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if src.len() / src_channels != dst.len() / dst_channels {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+
+        let max_value = ((1u32 << BIT_DEPTH) - 1) as f32;
+        let scale = 1. / max_value;
+        let mut device = vec![0f32; CHUNK * src_channels];
+        let mut linear = vec![0f32; CHUNK * 3];
+        let mut out = vec![0f32; CHUNK * dst_channels];
+
+        for (src, dst) in src
+            .chunks(CHUNK * src_channels)
+            .zip(dst.chunks_mut(CHUNK * dst_channels))
+        {
+            let pixels = src.len() / src_channels;
+            let device = &mut device[..src.len()];
+            let linear = &mut linear[..pixels * 3];
+            let out = &mut out[..dst.len()];
+            for (dst, &src) in device.iter_mut().zip(src.iter()) {
+                *dst = src.as_() * scale;
+            }
+            self.to_linear.transform(device, linear)?;
+            self.stage.transform(linear)?;
+            self.encode.transform(linear, out)?;
+            if self.copy_alpha {
+                let src_a = self.src_layout.a_i();
+                let dst_a = self.dst_layout.a_i();
+                for (out, device) in out
+                    .chunks_exact_mut(dst_channels)
+                    .zip(device.chunks_exact(src_channels))
+                {
+                    out[dst_a] = device[src_a];
+                }
+            }
+            if T::FINITE {
+                for (dst, &src) in dst.iter_mut().zip(out.iter()) {
+                    *dst = (src * max_value).round().max(0.).min(max_value).as_();
+                }
+            } else {
+                for (dst, &src) in dst.iter_mut().zip(out.iter()) {
+                    *dst = src.as_();
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Creates transform running `stage` between the source and destination encoding.
+///
+/// Destination has to be RGB matrix-shaper profile, source is decoded into
+/// linear copy of it, so the stage receives linear RGB in destination primaries.
+pub(crate) fn make_custom_stage_transform<
+    T: Copy
+        + Default
+        + AsPrimitive<f32>
+        + AsPrimitive<usize>
+        + PointeeSizeExpressible
+        + Send
+        + Sync
+        + 'static,
+    const BIT_DEPTH: usize,
+>(
+    source: &ColorProfile,
+    src_layout: Layout,
+    dest: &ColorProfile,
+    dst_layout: Layout,
+    options: TransformOptions,
+    stage: Arc<dyn InPlaceStage + Send + Sync>,
+) -> Result<Box<dyn TransformExecutor<T> + Send + Sync>, CmsError>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    if dest.color_space != DataColorSpace::Rgb
+        || dest.pcs != DataColorSpace::Xyz
+        || !dest.has_full_colors_triplet()
+    {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+    if dst_layout.try_r_i().is_none() {
+        return Err(CmsError::InvalidLayout);
+    }
+
+    let mut linear = dest.clone();
+    linear.red_trc = Some(ToneReprCurve::Lut(vec![]));
+    linear.green_trc = Some(ToneReprCurve::Lut(vec![]));
+    linear.blue_trc = Some(ToneReprCurve::Lut(vec![]));
+    linear.cicp = None;
+    linear.vcgt = None;
+    linear.lut_a_to_b_perceptual = None;
+    linear.lut_a_to_b_colorimetric = None;
+    linear.lut_a_to_b_saturation = None;
+    linear.lut_b_to_a_perceptual = None;
+    linear.lut_b_to_a_colorimetric = None;
+    linear.lut_b_to_a_saturation = None;
+
+    // Alpha is handled here, both halves see straight color without it
+    let straight = TransformOptions {
+        alpha_mode: AlphaMode::Straight,
+        ..options
+    };
+    let to_linear = source.create_transform_f32(
+        src_layout,
+        &linear,
+        Layout::Rgb,
+        TransformOptions {
+            clamp_policy: ClampPolicy::PreserveExtended,
+            ycbcr_output: None,
+            apply_vcgt: false,
+            ..straight
+        },
+    )?;
+    let encode = linear.create_transform_f32(
+        Layout::Rgb,
+        dest,
+        dst_layout,
+        TransformOptions {
+            surround_compensation: None,
+            ..straight
+        },
+    )?;
+
+    // Fourth channel of CMYK and similar spaces is not alpha
+    let src_alpha = matches!(
+        source.color_space,
+        DataColorSpace::Rgb | DataColorSpace::Gray
+    );
+    let executor: Box<dyn TransformExecutor<T> + Send + Sync> =
+        Box::new(TransformCustomStage::<T, BIT_DEPTH> {
+            to_linear,
+            stage,
+            encode,
+            src_layout,
+            dst_layout,
+            copy_alpha: src_alpha && src_layout.has_alpha() && dst_layout.has_alpha(),
+            _phantom: std::marker::PhantomData,
+        });
+    if options.alpha_mode != AlphaMode::Premultiplied {
+        return Ok(executor);
+    }
+    match wrap_premultiplied_alpha::<T, BIT_DEPTH>(
+        TransformPlan::Ready(executor),
+        src_layout,
+        src_alpha,
+        dst_layout,
+        true,
+    ) {
+        TransformPlan::Ready(executor) => Ok(executor),
+        TransformPlan::Lut(_) => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Gain([f32; 3]);
+
+    impl InPlaceStage for Gain {
+        fn transform(&self, dst: &mut [f32]) -> Result<(), CmsError> {
+            for rgb in dst.chunks_exact_mut(3) {
+                for (v, gain) in rgb.iter_mut().zip(self.0.iter()) {
+                    *v *= gain;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn srgb_to_linear(v: f32) -> f32 {
+        if v <= 0.04045 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        }
+    }
+
+    fn linear_to_srgb(v: f32) -> f32 {
+        if v <= 0.0031308 {
+            v * 12.92
+        } else {
+            1.055 * v.powf(1. / 2.4) - 0.055
+        }
+    }
+
+    #[test]
+    fn test_gain_stage_applies_in_linear_light() {
+        let srgb = ColorProfile::new_srgb();
+        let gain = [0.5f32, 1., 0.25];
+        let stage = Arc::new(Gain(gain));
+        let options = TransformOptions::default();
+
+        let src = (0..=255u8)
+            .flat_map(|v| [v, 255 - v, v / 2, v])
+            .collect::<Vec<_>>();
+        let transform = srgb
+            .create_transform_with_stage_8bit(
+                Layout::Rgba,
+                &srgb,
+                Layout::Rgba,
+                options,
+                stage.clone(),
+            )
+            .unwrap();
+        let mut dst = vec![0u8; src.len()];
+        transform.transform(&src, &mut dst).unwrap();
+        for (src, dst) in src.chunks_exact(4).zip(dst.chunks_exact(4)) {
+            for c in 0..3 {
+                let expected =
+                    linear_to_srgb(srgb_to_linear(src[c] as f32 / 255.) * gain[c]) * 255.;
+                assert!(
+                    (dst[c] as f32 - expected).abs() <= 1.,
+                    "{src:?} gave {dst:?}, expected {expected} in channel {c}"
+                );
+            }
+            assert_eq!(dst[3], src[3]);
+        }
+
+        let src = (0..=1000)
+            .flat_map(|v| {
+                let v = v as f32 / 1000.;
+                [v, 1. - v, v * 0.5]
+            })
+            .collect::<Vec<_>>();
+        let transform = srgb
+            .create_transform_with_stage_f32(Layout::Rgb, &srgb, Layout::Rgb, options, stage)
+            .unwrap();
+        let mut dst = vec![0f32; src.len()];
+        transform.transform(&src, &mut dst).unwrap();
+        for (src, dst) in src.chunks_exact(3).zip(dst.chunks_exact(3)) {
+            for c in 0..3 {
+                let expected = linear_to_srgb(srgb_to_linear(src[c]) * gain[c]);
+                assert!(
+                    (dst[c] - expected).abs() < 2e-3,
+                    "{src:?} gave {dst:?}, expected {expected} in channel {c}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_noop_stage_matches_plain_transform() {
+        let srgb = ColorProfile::new_srgb();
+        let bt2020 = ColorProfile::new_bt2020();
+        let stage = Arc::new(Gain([1.; 3]));
+        let options = TransformOptions::default();
+
+        let src = (0..=1000)
+            .flat_map(|v| {
+                let v = v as f32 / 1000.;
+                [v, 1. - v, v * 0.5]
+            })
+            .collect::<Vec<_>>();
+        let plain = srgb
+            .create_transform_f32(Layout::Rgb, &bt2020, Layout::Rgb, options)
+            .unwrap();
+        let mut expected = vec![0f32; src.len()];
+        plain.transform(&src, &mut expected).unwrap();
+        let staged = srgb
+            .create_transform_with_stage_f32(
+                Layout::Rgb,
+                &bt2020,
+                Layout::Rgb,
+                options,
+                stage.clone(),
+            )
+            .unwrap();
+        let mut dst = vec![0f32; src.len()];
+        staged.transform(&src, &mut dst).unwrap();
+        // Only rounding of stored linear values differs
+        let max_diff = dst
+            .iter()
+            .zip(expected.iter())
+            .map(|(a, b)| (a - b).abs())
+            .fold(0f32, f32::max);
+        assert!(max_diff < 1e-6, "max difference {max_diff}");
+
+        let src = (0..=255u8)
+            .flat_map(|v| [v, 255 - v, v / 2])
+            .collect::<Vec<_>>();
+        let plain = srgb
+            .create_transform_8bit(Layout::Rgb, &bt2020, Layout::Rgb, options)
+            .unwrap();
+        let mut expected = vec![0u8; src.len()];
+        plain.transform(&src, &mut expected).unwrap();
+        let staged = srgb
+            .create_transform_with_stage_8bit(Layout::Rgb, &bt2020, Layout::Rgb, options, stage)
+            .unwrap();
+        let mut dst = vec![0u8; src.len()];
+        staged.transform(&src, &mut dst).unwrap();
+        for (a, b) in dst.iter().zip(expected.iter()) {
+            assert!(a.abs_diff(*b) <= 1, "{a} differs from {b}");
+        }
+    }
+
+    #[test]
+    fn test_stage_requires_rgb_matrix_shaper_destination() {
+        let srgb = ColorProfile::new_srgb();
+        let gray = ColorProfile::new_gray_with_gamma(2.2);
+        let stage = Arc::new(Gain([1.; 3]));
+        let options = TransformOptions::default();
+        assert!(matches!(
+            srgb.create_transform_with_stage_8bit(
+                Layout::Rgb,
+                &gray,
+                Layout::Gray,
+                options,
+                stage.clone()
+            ),
+            Err(CmsError::UnsupportedProfileConnection)
+        ));
+        assert!(matches!(
+            srgb.create_transform_with_stage_8bit(Layout::Rgb, &srgb, Layout::Gray, options, stage),
+            Err(CmsError::InvalidLayout)
+        ));
+    }
+}
@@ -0,0 +1,541 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::conversions::CompressForLut;
+use crate::conversions::lut_transforms::{
+    GRID_SIZE_3, GRID_SIZE_4, LutSampler, LutShape, make_shaped_executor,
+};
+use crate::conversions::lut3x4::create_lut3_samples_norm;
+use crate::conversions::lut4::create_lut4_samples_norm;
+use crate::mlaf::mlaf;
+use crate::trc::lut_interp_linear_float;
+use crate::{
+    CmsError, ColorProfile, DataColorSpace, Layout, LutWarehouse, Matrix3f, PointeeSizeExpressible,
+    ProfileClass, ToneReprCurve, TransformExecutor, TransformOptions, Vector3f,
+};
+use num_traits::AsPrimitive;
+use std::marker::PhantomData;
+
+const CURVE_DEPTH: usize = 4096;
+
+/// "M" curves with matrix and bias of `mAB`, only valid for 3 outputs
+struct MatrixStage {
+    curves: [Vec<f32>; 3],
+    matrix: Matrix3f,
+    bias: Vector3f,
+}
+
+/// `A2B0` of device link flattened to curves, CLUT and curves.
+///
+/// Evaluation is channel count agnostic, CLUT is interpolated multilinearly.
+struct DeviceLinkLut {
+    input_curves: Vec<Vec<f32>>,
+    grid_points: Vec<usize>,
+    clut: Vec<f32>,
+    matrix_stage: Option<MatrixStage>,
+    output_curves: Vec<Vec<f32>>,
+    output_channels: usize,
+}
+
+fn split_curves(table: &[f32], channels: usize, entries: usize) -> Result<Vec<Vec<f32>>, CmsError> {
+    if entries < 2 || table.len() != channels * entries {
+        return Err(CmsError::InvalidAtoBLut);
+    }
+    Ok(table.chunks_exact(entries).map(|x| x.to_vec()).collect())
+}
+
+fn linearize_curves(curves: &[ToneReprCurve]) -> Result<Vec<Vec<f32>>, CmsError> {
+    curves
+        .iter()
+        .map(|curve| {
+            curve
+                .build_linearize_table::<u16, CURVE_DEPTH, 16>()
+                .map(|x| x.to_vec())
+                .ok_or(CmsError::InvalidTrcCurve)
+        })
+        .collect()
+}
+
+impl DeviceLinkLut {
+    fn new(
+        lut: &LutWarehouse,
+        input_channels: usize,
+        output_channels: usize,
+    ) -> Result<DeviceLinkLut, CmsError> {
+        match lut {
+            LutWarehouse::Lut(lut) => {
+                if lut.num_input_channels as usize != input_channels
+                    || lut.num_output_channels as usize != output_channels
+                {
+                    return Err(CmsError::UnsupportedProfileConnection);
+                }
+                let grid_size = lut.num_clut_grid_points as usize;
+                if grid_size < 2
+                    || lut.clut_table.len()
+                        != grid_size.pow(input_channels as u32) * output_channels
+                {
+                    return Err(CmsError::InvalidAtoBLut);
+                }
+                Ok(DeviceLinkLut {
+                    input_curves: split_curves(
+                        &lut.input_table,
+                        input_channels,
+                        lut.num_input_table_entries as usize,
+                    )?,
+                    grid_points: vec![grid_size; input_channels],
+                    clut: lut.clut_table.clone(),
+                    matrix_stage: None,
+                    output_curves: split_curves(
+                        &lut.output_table,
+                        output_channels,
+                        lut.num_output_table_entries as usize,
+                    )?,
+                    output_channels,
+                })
+            }
+            LutWarehouse::MCurves(mab) => {
+                if mab.num_input_channels as usize != input_channels
+                    || mab.num_output_channels as usize != output_channels
+                {
+                    return Err(CmsError::UnsupportedProfileConnection);
+                }
+                // Changing channel count is not possible without CLUT
+                if (mab.clut.is_empty() && input_channels != output_channels)
+                    || (!mab.clut.is_empty() && mab.a_curves.len() != input_channels)
+                    || mab.b_curves.len() != output_channels
+                {
+                    return Err(CmsError::InvalidAtoBLut);
+                }
+                let grid_points = if mab.clut.is_empty() {
+                    vec![]
+                } else {
+                    let grid_points = mab.grid_points[..input_channels]
+                        .iter()
+                        .map(|&x| x as usize)
+                        .collect::<Vec<_>>();
+                    if grid_points.iter().any(|&x| x < 2)
+                        || mab.clut.len() != grid_points.iter().product::<usize>() * output_channels
+                    {
+                        return Err(CmsError::InvalidAtoBLut);
+                    }
+                    grid_points
+                };
+                let matrix_stage = if mab.m_curves.len() == 3 && output_channels == 3 {
+                    let curves = linearize_curves(&mab.m_curves)?;
+                    Some(MatrixStage {
+                        curves: [curves[0].clone(), curves[1].clone(), curves[2].clone()],
+                        matrix: mab.matrix,
+                        bias: mab.bias,
+                    })
+                } else if mab.m_curves.is_empty() {
+                    None
+                } else {
+                    return Err(CmsError::InvalidAtoBLut);
+                };
+                Ok(DeviceLinkLut {
+                    input_curves: if mab.clut.is_empty() {
+                        vec![]
+                    } else {
+                        linearize_curves(&mab.a_curves)?
+                    },
+                    grid_points,
+                    clut: mab.clut.clone(),
+                    matrix_stage,
+                    output_curves: linearize_curves(&mab.b_curves)?,
+                    output_channels,
+                })
+            }
+        }
+    }
+
+    fn interpolate_clut(&self, src: &[f32], dst: &mut [f32]) {
+        let inputs = self.grid_points.len();
+        let mut lower = [0usize; 4];
+        let mut weights = [0f32; 4];
+        let mut strides = [0usize; 4];
+        let mut stride = self.output_channels;
+        for i in (0..inputs).rev() {
+            strides[i] = stride;
+            stride *= self.grid_points[i];
+        }
+        for i in 0..inputs {
+            let max_index = self.grid_points[i] - 1;
+            let x = src[i].max(0.).min(1.) * max_index as f32;
+            let floor = (x.floor() as usize).min(max_index - 1);
+            lower[i] = floor;
+            weights[i] = x - floor as f32;
+        }
+
+        dst.fill(0.);
+        for corner in 0..1usize << inputs {
+            let mut weight = 1f32;
+            let mut offset = 0usize;
+            for i in 0..inputs {
+                if corner & (1 << i) != 0 {
+                    weight *= weights[i];
+                    offset += (lower[i] + 1) * strides[i];
+                } else {
+                    weight *= 1. - weights[i];
+                    offset += lower[i] * strides[i];
+                }
+            }
+            if weight == 0. {
+                continue;
+            }
+            for (dst, &node) in dst
+                .iter_mut()
+                .zip(self.clut[offset..offset + self.output_channels].iter())
+            {
+                *dst = mlaf(*dst, node, weight);
+            }
+        }
+    }
+
+    /// Evaluates `src` device values in place of `dst`
+    fn evaluate(&self, src: &[f32], dst: &mut [f32]) {
+        let mut linear = [0f32; 4];
+        if self.grid_points.is_empty() {
+            dst.copy_from_slice(src);
+        } else {
+            for ((linear, &src), curve) in linear
+                .iter_mut()
+                .zip(src.iter())
+                .zip(self.input_curves.iter())
+            {
+                *linear = lut_interp_linear_float(src, curve);
+            }
+            self.interpolate_clut(&linear[..src.len()], dst);
+        }
+
+        if let Some(stage) = &self.matrix_stage {
+            let mut v = [0f32; 3];
+            for ((v, &x), curve) in v.iter_mut().zip(dst.iter()).zip(stage.curves.iter()) {
+                *v = lut_interp_linear_float(x, curve);
+            }
+            let m = stage.matrix;
+            let b = stage.bias;
+            for (i, dst) in dst.iter_mut().enumerate() {
+                *dst = mlaf(
+                    mlaf(mlaf(b.v[i], v[0], m.v[i][0]), v[1], m.v[i][1]),
+                    v[2],
+                    m.v[i][2],
+                );
+            }
+        }
+
+        for (dst, curve) in dst.iter_mut().zip(self.output_curves.iter()) {
+            *dst = lut_interp_linear_float(*dst, curve);
+        }
+    }
+}
+
+struct DeviceLinkSampler<T, const BIT_DEPTH: usize> {
+    shape: LutShape,
+    lut: DeviceLinkLut,
+    origins: Vec<f32>,
+    input_channels: usize,
+    src_layout: Layout,
+    dst_layout: Layout,
+    options: TransformOptions,
+    _phantom: PhantomData<T>,
+}
+
+impl<
+    T: Copy
+        + Default
+        + AsPrimitive<f32>
+        + Send
+        + Sync
+        + CompressForLut
+        + AsPrimitive<usize>
+        + PointeeSizeExpressible,
+    const BIT_DEPTH: usize,
+> LutSampler<T> for DeviceLinkSampler<T, BIT_DEPTH>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    fn grid_points(&self) -> usize {
+        self.origins.len() / self.input_channels
+    }
+
+    fn output_channels(&self) -> usize {
+        self.lut.output_channels
+    }
+
+    fn sample(&self, start: usize, end: usize, dst: &mut [f32]) -> Result<(), CmsError> {
+        if start > end || end > self.grid_points() {
+            return Err(CmsError::OverflowingError);
+        }
+        let output_channels = self.lut.output_channels;
+        if dst.len() != (end - start) * output_channels {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        let origins = &self.origins[start * self.input_channels..end * self.input_channels];
+        for (src, dst) in origins
+            .chunks_exact(self.input_channels)
+            .zip(dst.chunks_exact_mut(output_channels))
+        {
+            self.lut.evaluate(src, dst);
+        }
+        Ok(())
+    }
+
+    fn make_executor(
+        self: Box<Self>,
+        lut: Vec<f32>,
+    ) -> Box<dyn TransformExecutor<T> + Send + Sync> {
+        make_shaped_executor::<T, BIT_DEPTH>(
+            self.shape,
+            self.src_layout,
+            self.dst_layout,
+            lut,
+            self.options,
+        )
+    }
+}
+
+/// Prepares grid sampling of `A2B0` of device link `link`.
+///
+/// Device link converts from its `color_space` to the space stored in place of PCS.
+pub(crate) fn make_device_link_sampler<
+    T: Copy
+        + Default
+        + AsPrimitive<f32>
+        + Send
+        + Sync
+        + CompressForLut
+        + AsPrimitive<usize>
+        + PointeeSizeExpressible,
+    const BIT_DEPTH: usize,
+>(
+    link: &ColorProfile,
+    src_layout: Layout,
+    dst_layout: Layout,
+    options: TransformOptions,
+) -> Result<Box<dyn LutSampler<T>>, CmsError>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    if link.profile_class != ProfileClass::DeviceLink {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+    let is_3 = |x: DataColorSpace| matches!(x, DataColorSpace::Rgb | DataColorSpace::Color3);
+    let is_4 = |x: DataColorSpace| matches!(x, DataColorSpace::Cmyk | DataColorSpace::Color4);
+    let (shape, input_channels, output_channels) = match (link.color_space, link.pcs) {
+        (src, dst) if is_3(src) && is_3(dst) => (LutShape::Lut3x3, 3, 3),
+        (src, dst) if is_3(src) && is_4(dst) => (LutShape::Lut3x4, 3, 4),
+        (src, dst) if is_4(src) && is_3(dst) => (LutShape::Lut4x3, 4, 3),
+        (src, dst) if is_4(src) && is_4(dst) => (LutShape::Lut4x4, 4, 4),
+        _ => return Err(CmsError::UnsupportedProfileConnection),
+    };
+    link.color_space.check_layout(src_layout)?;
+    link.pcs.check_layout(dst_layout)?;
+
+    let a_to_b = link
+        .lut_a_to_b_perceptual
+        .as_ref()
+        .ok_or(CmsError::UnsupportedProfileConnection)?;
+    let lut = DeviceLinkLut::new(a_to_b, input_channels, output_channels)?;
+    let origins = if input_channels == 4 {
+        create_lut4_samples_norm::<GRID_SIZE_4>()
+    } else {
+        create_lut3_samples_norm::<GRID_SIZE_3>()
+    };
+
+    Ok(Box::new(DeviceLinkSampler::<T, BIT_DEPTH> {
+        shape,
+        lut,
+        origins,
+        input_channels,
+        src_layout,
+        dst_layout,
+        options,
+        _phantom: PhantomData,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::LutDataType;
+    use crate::{AlphaMode, LutType};
+
+    /// Device link with 2 points grid, exact for maps linear in each channel
+    fn make_link(
+        color_space: DataColorSpace,
+        pcs: DataColorSpace,
+        input_channels: usize,
+        output_channels: usize,
+        map: impl Fn(&[f32]) -> Vec<f32>,
+    ) -> ColorProfile {
+        let mut clut_table = Vec::new();
+        for node in 0..1usize << input_channels {
+            let device = (0..input_channels)
+                .map(|i| ((node >> (input_channels - 1 - i)) & 1) as f32)
+                .collect::<Vec<_>>();
+            clut_table.extend(map(&device));
+        }
+        ColorProfile {
+            profile_class: ProfileClass::DeviceLink,
+            color_space,
+            pcs,
+            lut_a_to_b_perceptual: Some(LutWarehouse::Lut(LutDataType {
+                num_input_channels: input_channels as u8,
+                num_output_channels: output_channels as u8,
+                num_clut_grid_points: 2,
+                matrix: Matrix3f::IDENTITY,
+                num_input_table_entries: 2,
+                num_output_table_entries: 2,
+                input_table: [0., 1.].repeat(input_channels),
+                clut_table,
+                output_table: [0., 1.].repeat(output_channels),
+                lut_type: LutType::Lut16,
+            })),
+            ..Default::default()
+        }
+    }
+
+    fn assert_close(actual: &[u8], expected: &[u8]) {
+        for (&a, &e) in actual.iter().zip(expected.iter()) {
+            assert!(
+                (a as i32 - e as i32).abs() <= 1,
+                "expected {expected:?}, got {actual:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_device_link_rgb_to_rgb() {
+        let link = make_link(DataColorSpace::Rgb, DataColorSpace::Rgb, 3, 3, |x| {
+            vec![x[2], x[1], x[0]]
+        });
+        let transform = link
+            .create_device_link_transform_8bit(Layout::Rgb, Layout::Rgba, Default::default())
+            .unwrap();
+        let src = [10u8, 128, 250, 0, 255, 77];
+        let mut dst = [0u8; 8];
+        transform.transform(&src, &mut dst).unwrap();
+        assert_close(&dst[0..3], &[250, 128, 10]);
+        assert_close(&dst[4..7], &[77, 255, 0]);
+        assert_eq!(dst[3], 255);
+        assert_eq!(dst[7], 255);
+    }
+
+    #[test]
+    fn test_device_link_rgb_to_cmyk() {
+        let link = make_link(DataColorSpace::Rgb, DataColorSpace::Cmyk, 3, 4, |x| {
+            vec![1. - x[0], 1. - x[1], 1. - x[2], 0.]
+        });
+        let transform = link
+            .create_device_link_transform_8bit(Layout::Rgb, Layout::Rgba, Default::default())
+            .unwrap();
+        let src = [10u8, 128, 250];
+        let mut dst = [0u8; 4];
+        transform.transform(&src, &mut dst).unwrap();
+        assert_close(&dst, &[245, 127, 5, 0]);
+    }
+
+    #[test]
+    fn test_device_link_cmyk_to_cmyk() {
+        let link = make_link(DataColorSpace::Cmyk, DataColorSpace::Cmyk, 4, 4, |x| {
+            vec![x[0], x[1], x[2], x[3] * 0.5]
+        });
+        let transform = link
+            .create_device_link_transform_8bit(Layout::Rgba, Layout::Rgba, Default::default())
+            .unwrap();
+        let src = [0u8, 64, 200, 255, 30, 255, 0, 100];
+        let mut dst = [0u8; 8];
+        transform.transform(&src, &mut dst).unwrap();
+        assert_close(&dst, &[0, 64, 200, 128, 30, 255, 0, 50]);
+    }
+
+    #[test]
+    fn test_device_link_premultiplied_alpha() {
+        let options = TransformOptions {
+            alpha_mode: AlphaMode::Premultiplied,
+            ..Default::default()
+        };
+        let invert = make_link(DataColorSpace::Rgb, DataColorSpace::Rgb, 3, 3, |x| {
+            x.iter().map(|v| 1. - v).collect()
+        });
+        // Straight (199, 40, 90) at half alpha, inverted to (56, 215, 165)
+        let src = [100u8, 20, 45, 128];
+        let transform = invert
+            .create_device_link_transform_8bit(Layout::Rgba, Layout::Rgba, options)
+            .unwrap();
+        let mut dst = [0u8; 4];
+        transform.transform(&src, &mut dst).unwrap();
+        assert_close(&dst, &[28, 108, 83, 128]);
+
+        let to_cmyk = make_link(DataColorSpace::Rgb, DataColorSpace::Cmyk, 3, 4, |x| {
+            vec![1. - x[0], 1. - x[1], 1. - x[2], 0.]
+        });
+        let transform = to_cmyk
+            .create_device_link_transform_8bit(Layout::Rgba, Layout::Rgba, options)
+            .unwrap();
+        transform.transform(&src, &mut dst).unwrap();
+        assert_close(&dst, &[56, 215, 165, 0]);
+
+        // K is not alpha
+        let cmyk = make_link(DataColorSpace::Cmyk, DataColorSpace::Cmyk, 4, 4, |x| {
+            x.to_vec()
+        });
+        let transform = cmyk
+            .create_device_link_transform_8bit(Layout::Rgba, Layout::Rgba, options)
+            .unwrap();
+        transform.transform(&src, &mut dst).unwrap();
+        assert_close(&dst, &src);
+    }
+
+    #[test]
+    fn test_device_link_rejects_mismatched_layouts() {
+        let link = make_link(DataColorSpace::Rgb, DataColorSpace::Cmyk, 3, 4, |x| {
+            vec![x[0], x[1], x[2], 0.]
+        });
+        assert!(matches!(
+            link.create_device_link_transform_8bit(Layout::Rgb, Layout::Rgb, Default::default()),
+            Err(CmsError::InvalidLayout)
+        ));
+        assert!(matches!(
+            link.create_device_link_transform_8bit(Layout::Gray, Layout::Rgba, Default::default()),
+            Err(CmsError::InvalidLayout)
+        ));
+        let mut display = link.clone();
+        display.profile_class = ProfileClass::DisplayDevice;
+        assert!(matches!(
+            display.create_device_link_transform_8bit(
+                Layout::Rgb,
+                Layout::Rgba,
+                Default::default()
+            ),
+            Err(CmsError::UnsupportedProfileConnection)
+        ));
+    }
+}
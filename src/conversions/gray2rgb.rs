@@ -26,7 +26,8 @@
  * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
-use crate::transform::PointeeSizeExpressible;
+use crate::conversions::WORKING_LANES;
+use crate::transform::{PointeeSizeExpressible, touch_pages};
 use crate::{CmsError, Layout, TransformExecutor};
 use num_traits::AsPrimitive;
 
@@ -61,8 +62,9 @@ where
         return Err(CmsError::UnsupportedProfileConnection);
     }
     match src_layout {
-        Layout::Rgb => unreachable!(),
-        Layout::Rgba => unreachable!(),
+        Layout::Rgb | Layout::Rgba | Layout::Bgr | Layout::Bgra | Layout::Argb | Layout::Abgr => {
+            unreachable!()
+        }
         Layout::Gray => match dst_layout {
             Layout::Rgb => Ok(Box::new(TransformProfileGrayToRgb::<
                 T,
@@ -108,6 +110,50 @@ where
                 gray_linear,
                 gray_gamma,
             })),
+            Layout::Bgr => Ok(Box::new(TransformProfileGrayToRgb::<
+                T,
+                { Layout::Gray as u8 },
+                { Layout::Bgr as u8 },
+                BUCKET,
+                BIT_DEPTH,
+                GAMMA_LUT,
+            > {
+                gray_linear,
+                gray_gamma,
+            })),
+            Layout::Bgra => Ok(Box::new(TransformProfileGrayToRgb::<
+                T,
+                { Layout::Gray as u8 },
+                { Layout::Bgra as u8 },
+                BUCKET,
+                BIT_DEPTH,
+                GAMMA_LUT,
+            > {
+                gray_linear,
+                gray_gamma,
+            })),
+            Layout::Argb => Ok(Box::new(TransformProfileGrayToRgb::<
+                T,
+                { Layout::Gray as u8 },
+                { Layout::Argb as u8 },
+                BUCKET,
+                BIT_DEPTH,
+                GAMMA_LUT,
+            > {
+                gray_linear,
+                gray_gamma,
+            })),
+            Layout::Abgr => Ok(Box::new(TransformProfileGrayToRgb::<
+                T,
+                { Layout::Gray as u8 },
+                { Layout::Abgr as u8 },
+                BUCKET,
+                BIT_DEPTH,
+                GAMMA_LUT,
+            > {
+                gray_linear,
+                gray_gamma,
+            })),
         },
         Layout::GrayAlpha => match dst_layout {
             Layout::Rgb => Ok(Box::new(TransformProfileGrayToRgb::<
@@ -154,6 +200,50 @@ where
                 gray_linear,
                 gray_gamma,
             })),
+            Layout::Bgr => Ok(Box::new(TransformProfileGrayToRgb::<
+                T,
+                { Layout::GrayAlpha as u8 },
+                { Layout::Bgr as u8 },
+                BUCKET,
+                BIT_DEPTH,
+                GAMMA_LUT,
+            > {
+                gray_linear,
+                gray_gamma,
+            })),
+            Layout::Bgra => Ok(Box::new(TransformProfileGrayToRgb::<
+                T,
+                { Layout::GrayAlpha as u8 },
+                { Layout::Bgra as u8 },
+                BUCKET,
+                BIT_DEPTH,
+                GAMMA_LUT,
+            > {
+                gray_linear,
+                gray_gamma,
+            })),
+            Layout::Argb => Ok(Box::new(TransformProfileGrayToRgb::<
+                T,
+                { Layout::GrayAlpha as u8 },
+                { Layout::Argb as u8 },
+                BUCKET,
+                BIT_DEPTH,
+                GAMMA_LUT,
+            > {
+                gray_linear,
+                gray_gamma,
+            })),
+            Layout::Abgr => Ok(Box::new(TransformProfileGrayToRgb::<
+                T,
+                { Layout::GrayAlpha as u8 },
+                { Layout::Abgr as u8 },
+                BUCKET,
+                BIT_DEPTH,
+                GAMMA_LUT,
+            > {
+                gray_linear,
+                gray_gamma,
+            })),
         },
     }
 }
@@ -170,6 +260,11 @@ impl<
 where
     u32: AsPrimitive<T>,
 {
+    fn warmup(&self) {
+        touch_pages(self.gray_linear.as_slice());
+        touch_pages(self.gray_gamma.as_slice());
+    }
+
     fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
         let src_cn = Layout::from(SRC_LAYOUT);
         let dst_cn = Layout::from(DST_LAYOUT);
@@ -185,7 +280,46 @@ where
         if dst.len() % dst_channels != 0 {
             return Err(CmsError::LaneMultipleOfChannels);
         }
+        self.transform_chunk(src, dst);
+        Ok(())
+    }
 
+    fn transform_in_place(&self, buf: &mut [T]) -> Result<(), CmsError> {
+        let channels = Layout::from(SRC_LAYOUT).channels();
+        if channels != Layout::from(DST_LAYOUT).channels() {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        if buf.len() % channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        let mut staged = [T::default(); WORKING_LANES];
+        for chunk in buf.chunks_mut(WORKING_LANES) {
+            let staged = &mut staged[..chunk.len()];
+            staged.copy_from_slice(chunk);
+            self.transform_chunk(staged, chunk);
+        }
+        Ok(())
+    }
+}
+
+impl<
+    T: Copy + Default + PointeeSizeExpressible + 'static,
+    const SRC_LAYOUT: u8,
+    const DST_LAYOUT: u8,
+    const BUCKET: usize,
+    const BIT_DEPTH: usize,
+    const GAMMA_LUT: usize,
+> TransformProfileGrayToRgb<T, SRC_LAYOUT, DST_LAYOUT, BUCKET, BIT_DEPTH, GAMMA_LUT>
+where
+    u32: AsPrimitive<T>,
+{
+    /// Transforms whole pixels, lengths are validated by the caller
+    #[inline(always)]
+    fn transform_chunk(&self, src: &[T], dst: &mut [T]) {
+        let src_cn = Layout::from(SRC_LAYOUT);
+        let dst_cn = Layout::from(DST_LAYOUT);
+        let src_channels = src_cn.channels();
+        let dst_channels = dst_cn.channels();
         let is_gray_alpha = src_cn == Layout::GrayAlpha;
 
         let max_value: T = ((1u32 << BIT_DEPTH as u32) - 1u32).as_();
@@ -201,19 +335,19 @@ where
             let possible_value = ((g * max_lut_size).round() as u16) as usize;
             let gamma_value = self.gray_gamma[possible_value];
 
-            dst[0] = gamma_value;
-            if dst_cn == Layout::GrayAlpha {
-                dst[1] = a;
-            } else if dst_cn == Layout::Rgb {
-                dst[1] = gamma_value;
-                dst[2] = gamma_value;
-            } else if dst_cn == Layout::Rgba {
-                dst[1] = gamma_value;
-                dst[2] = gamma_value;
-                dst[3] = a;
+            if dst_cn == Layout::Gray || dst_cn == Layout::GrayAlpha {
+                dst[0] = gamma_value;
+                if dst_cn == Layout::GrayAlpha {
+                    dst[1] = a;
+                }
+            } else {
+                dst[dst_cn.r_i()] = gamma_value;
+                dst[dst_cn.g_i()] = gamma_value;
+                dst[dst_cn.b_i()] = gamma_value;
+                if dst_cn.has_alpha() {
+                    dst[dst_cn.a_i()] = a;
+                }
             }
         }
-
-        Ok(())
     }
 }
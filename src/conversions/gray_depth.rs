@@ -0,0 +1,151 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::depth::ConvertDepth;
+use crate::transform::PointeeSizeExpressible;
+use crate::{CmsError, Layout, TransformDepthExecutor};
+use num_traits::AsPrimitive;
+
+/// Source linearization and destination gamma tables of gray to gray transform
+pub(crate) struct GrayDepthTrc<D> {
+    pub(crate) linear: Box<[f32]>,
+    pub(crate) gamma: Box<[D; 65536]>,
+    /// Last index of used part of gamma table
+    pub(crate) gamma_scale: f32,
+}
+
+struct TransformGrayDepth<
+    S,
+    D,
+    const SRC_LAYOUT: u8,
+    const DST_LAYOUT: u8,
+    const DST_BIT_DEPTH: usize,
+> {
+    /// `None` if gray is treated as alpha and only depth changes
+    trc: Option<GrayDepthTrc<D>>,
+    _phantom: std::marker::PhantomData<S>,
+}
+
+macro_rules! gray_depth_executor {
+    ($src: ident, $dst: ident, $trc: ident) => {
+        Ok(Box::new(TransformGrayDepth::<
+            S,
+            D,
+            { Layout::$src as u8 },
+            { Layout::$dst as u8 },
+            DST_BIT_DEPTH,
+        > {
+            trc: $trc,
+            _phantom: std::marker::PhantomData,
+        }))
+    };
+}
+
+pub(crate) fn make_gray_depth_transform<
+    S: Copy + Default + PointeeSizeExpressible + ConvertDepth<D> + Send + Sync + 'static,
+    D: Copy + Default + Send + Sync + 'static,
+    const DST_BIT_DEPTH: usize,
+>(
+    src_layout: Layout,
+    dst_layout: Layout,
+    trc: Option<GrayDepthTrc<D>>,
+) -> Result<Box<dyn TransformDepthExecutor<S, D> + Send + Sync>, CmsError>
+where
+    u32: AsPrimitive<D>,
+{
+    match (src_layout, dst_layout) {
+        (Layout::Gray, Layout::Gray) => gray_depth_executor!(Gray, Gray, trc),
+        (Layout::Gray, Layout::GrayAlpha) => gray_depth_executor!(Gray, GrayAlpha, trc),
+        (Layout::GrayAlpha, Layout::Gray) => gray_depth_executor!(GrayAlpha, Gray, trc),
+        (Layout::GrayAlpha, Layout::GrayAlpha) => {
+            gray_depth_executor!(GrayAlpha, GrayAlpha, trc)
+        }
+        _ => Err(CmsError::InvalidLayout),
+    }
+}
+
+impl<
+    S: Copy + Default + PointeeSizeExpressible + ConvertDepth<D>,
+    D: Copy + Default + 'static,
+    const SRC_LAYOUT: u8,
+    const DST_LAYOUT: u8,
+    const DST_BIT_DEPTH: usize,
+> TransformDepthExecutor<S, D> for TransformGrayDepth<S, D, SRC_LAYOUT, DST_LAYOUT, DST_BIT_DEPTH>
+where
+    u32: AsPrimitive<D>,
+{
+    fn transform(&self, src: &[S], dst: &mut [D]) -> Result<(), CmsError> {
+        let src_cn = Layout::from(SRC_LAYOUT);
+        let dst_cn = Layout::from(DST_LAYOUT);
+        let src_channels = src_cn.channels();
+        let dst_channels = dst_cn.channels();
+
+        if src.len() / src_channels != dst.len() / dst_channels {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        if src.len() % src_channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if dst.len() % dst_channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+
+        let max_value: D = ((1u32 << DST_BIT_DEPTH as u32) - 1u32).as_();
+        let alpha = |src: &[S]| {
+            if src_cn == Layout::GrayAlpha {
+                src[1].convert_depth()
+            } else {
+                max_value
+            }
+        };
+
+        let src_chunks = src.chunks_exact(src_channels);
+        let dst_chunks = dst.chunks_exact_mut(dst_channels);
+        match &self.trc {
+            Some(trc) => {
+                for (src, dst) in src_chunks.zip(dst_chunks) {
+                    let g = trc.linear[src[0]._as_usize()].max(0.).min(1.);
+                    dst[0] = trc.gamma[((g * trc.gamma_scale).round() as u16) as usize];
+                    if dst_cn == Layout::GrayAlpha {
+                        dst[1] = alpha(src);
+                    }
+                }
+            }
+            None => {
+                for (src, dst) in src_chunks.zip(dst_chunks) {
+                    dst[0] = src[0].convert_depth();
+                    if dst_cn == Layout::GrayAlpha {
+                        dst[1] = alpha(src);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
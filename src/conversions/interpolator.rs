@@ -29,7 +29,8 @@
 #![allow(dead_code)]
 use crate::conversions::lut_transforms::LUT_SAMPLING;
 use crate::math::FusedMultiplyAdd;
-use crate::{Vector3f, Vector4f, rounding_div_ceil};
+use crate::math::rounding_div_ceil;
+use crate::{Vector3f, Vector4f};
 use std::ops::{Add, Mul, Sub};
 
 pub(crate) struct Tetrahedral<'a, const GRID_SIZE: usize> {
@@ -60,6 +61,21 @@ pub(crate) trait MultidimensionalInterpolation<'a, const GRID_SIZE: usize> {
     fn new(table: &'a [f32]) -> Self;
     fn inter3(&self, in_r: u16, in_g: u16, in_b: u16) -> Vector3f;
     fn inter4(&self, in_r: u16, in_g: u16, in_b: u16) -> Vector4f;
+    fn inter1(&self, in_r: u16, in_g: u16, in_b: u16) -> f32;
+}
+
+struct TetrahedralFetchScalar<'a, const GRID_SIZE: usize> {
+    cube: &'a [f32],
+}
+
+impl<const GRID_SIZE: usize> Fetcher<f32> for TetrahedralFetchScalar<'_, GRID_SIZE> {
+    #[inline(always)]
+    fn fetch(&self, x: i32, y: i32, z: i32) -> f32 {
+        let offset = (x as u32 * (GRID_SIZE as u32 * GRID_SIZE as u32)
+            + y as u32 * GRID_SIZE as u32
+            + z as u32) as usize;
+        self.cube[offset]
+    }
 }
 
 impl<const GRID_SIZE: usize> Fetcher<Vector3f> for TetrahedralFetchVector3f<'_, GRID_SIZE> {
@@ -192,6 +208,16 @@ macro_rules! define_md_inter {
                     TetrahedralFetchVector4f::<GRID_SIZE> { cube: self.cube },
                 )
             }
+
+            #[inline(always)]
+            fn inter1(&self, in_r: u16, in_g: u16, in_b: u16) -> f32 {
+                self.interpolate(
+                    in_r,
+                    in_g,
+                    in_b,
+                    TetrahedralFetchScalar::<GRID_SIZE> { cube: self.cube },
+                )
+            }
         }
     };
 }
@@ -0,0 +1,242 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::math::m_clamp;
+use crate::mlaf::mlaf;
+use crate::nd_array::Array3D;
+use crate::profile::LutDataType;
+use crate::trc::lut_interp_linear_float;
+use crate::{
+    CmsError, InterpolationMethod, LutMCurvesType, LutWarehouse, Matrix3f, ToneReprCurve, Vector3f,
+};
+
+/// Interpolates single channel CLUT
+fn fetch_clut(clut: &Array3D, method: InterpolationMethod, x: f32, y: f32, z: f32) -> f32 {
+    match method {
+        InterpolationMethod::Tetrahedral => clut.tetra_scalar(x, y, z),
+        InterpolationMethod::Pyramid => clut.pyramid_scalar(x, y, z),
+        InterpolationMethod::Prism => clut.prism_scalar(x, y, z),
+        InterpolationMethod::Linear => clut.trilinear_scalar(x, y, z),
+    }
+}
+
+fn lut_3x1(
+    lut: &LutDataType,
+    src: &[f32],
+    dst: &mut [f32],
+    apply_matrix: bool,
+    method: InterpolationMethod,
+) -> Result<(), CmsError> {
+    let entries = lut.num_input_table_entries as usize;
+    let out_entries = lut.num_output_table_entries as usize;
+    if lut.input_table.len() != entries * 3
+        || lut.output_table.len() != out_entries
+        || lut.clut_table.len() != (lut.num_clut_grid_points as usize).pow(3)
+    {
+        return Err(CmsError::InvalidProfile);
+    }
+    let input = [
+        &lut.input_table[..entries],
+        &lut.input_table[entries..entries * 2],
+        &lut.input_table[entries * 2..],
+    ];
+    let clut = Array3D::new(&lut.clut_table, lut.num_clut_grid_points as usize);
+    // the matrix of lutType is only used when the input color space is XYZ.
+    let m = lut.matrix;
+    let use_matrix = apply_matrix && !m.test_equality(Matrix3f::IDENTITY);
+
+    for (src, dst) in src.chunks_exact(3).zip(dst.iter_mut()) {
+        let (x, y, z) = if use_matrix {
+            (
+                m_clamp(
+                    mlaf(
+                        mlaf(src[0] * m.v[0][0], src[1], m.v[0][1]),
+                        src[2],
+                        m.v[0][2],
+                    ),
+                    0.,
+                    1.,
+                ),
+                m_clamp(
+                    mlaf(
+                        mlaf(src[0] * m.v[1][0], src[1], m.v[1][1]),
+                        src[2],
+                        m.v[1][2],
+                    ),
+                    0.,
+                    1.,
+                ),
+                m_clamp(
+                    mlaf(
+                        mlaf(src[0] * m.v[2][0], src[1], m.v[2][1]),
+                        src[2],
+                        m.v[2][2],
+                    ),
+                    0.,
+                    1.,
+                ),
+            )
+        } else {
+            (src[0], src[1], src[2])
+        };
+        let linear_x = lut_interp_linear_float(x, input[0]);
+        let linear_y = lut_interp_linear_float(y, input[1]);
+        let linear_z = lut_interp_linear_float(z, input[2]);
+        let v = fetch_clut(&clut, method, linear_x, linear_y, linear_z);
+        *dst = lut_interp_linear_float(m_clamp(v, 0., 1.), &lut.output_table);
+    }
+    Ok(())
+}
+
+fn curve_table(curve: &ToneReprCurve) -> Result<Box<[f32; 256]>, CmsError> {
+    curve
+        .build_linearize_table::<u8, 256, 8>()
+        .ok_or(CmsError::InvalidTrcCurve)
+}
+
+fn mab_3x1(
+    mab: &LutMCurvesType,
+    src: &[f32],
+    dst: &mut [f32],
+    method: InterpolationMethod,
+) -> Result<(), CmsError> {
+    if mab.clut.is_empty()
+        || mab.grid_points[0] != mab.grid_points[1]
+        || mab.grid_points[1] != mab.grid_points[2]
+        || mab.clut.len() != (mab.grid_points[0] as usize).pow(3)
+    {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+    let clut = Array3D::new(&mab.clut, mab.grid_points[0] as usize);
+
+    if mab.b_curves.len() == 3 {
+        // mBA: B curves -> matrix -> M curves -> CLUT -> A curve
+        let b = [
+            curve_table(&mab.b_curves[0])?,
+            curve_table(&mab.b_curves[1])?,
+            curve_table(&mab.b_curves[2])?,
+        ];
+        let m_curves = if mab.m_curves.len() == 3 {
+            Some([
+                curve_table(&mab.m_curves[0])?,
+                curve_table(&mab.m_curves[1])?,
+                curve_table(&mab.m_curves[2])?,
+            ])
+        } else {
+            None
+        };
+        let a = match mab.a_curves.first() {
+            Some(curve) => Some(curve_table(curve)?),
+            None => None,
+        };
+        let m = mab.matrix;
+        let bias: Vector3f = mab.bias;
+        for (src, dst) in src.chunks_exact(3).zip(dst.iter_mut()) {
+            let mut v = [
+                lut_interp_linear_float(src[0], b[0].as_slice()),
+                lut_interp_linear_float(src[1], b[1].as_slice()),
+                lut_interp_linear_float(src[2], b[2].as_slice()),
+            ];
+            if let Some(m_curves) = &m_curves {
+                let x = v[0];
+                let y = v[1];
+                let z = v[2];
+                for (i, (dst, curve)) in v.iter_mut().zip(m_curves.iter()).enumerate() {
+                    let r = mlaf(
+                        mlaf(mlaf(bias.v[i], x, m.v[i][0]), y, m.v[i][1]),
+                        z,
+                        m.v[i][2],
+                    );
+                    *dst = lut_interp_linear_float(m_clamp(r, 0., 1.), curve.as_slice());
+                }
+            }
+            let w = m_clamp(fetch_clut(&clut, method, v[0], v[1], v[2]), 0., 1.);
+            *dst = match &a {
+                Some(curve) => lut_interp_linear_float(w, curve.as_slice()),
+                None => w,
+            };
+        }
+    } else if mab.a_curves.len() == 3 {
+        // mAB: A curves -> CLUT -> M curve -> B curve
+        let a = [
+            curve_table(&mab.a_curves[0])?,
+            curve_table(&mab.a_curves[1])?,
+            curve_table(&mab.a_curves[2])?,
+        ];
+        let m_curve = match mab.m_curves.first() {
+            Some(curve) => Some(curve_table(curve)?),
+            None => None,
+        };
+        let b_curve = match mab.b_curves.first() {
+            Some(curve) => Some(curve_table(curve)?),
+            None => None,
+        };
+        for (src, dst) in src.chunks_exact(3).zip(dst.iter_mut()) {
+            let x = lut_interp_linear_float(src[0], a[0].as_slice());
+            let y = lut_interp_linear_float(src[1], a[1].as_slice());
+            let z = lut_interp_linear_float(src[2], a[2].as_slice());
+            let mut w = m_clamp(fetch_clut(&clut, method, x, y, z), 0., 1.);
+            if let Some(curve) = &m_curve {
+                w = lut_interp_linear_float(w, curve.as_slice());
+            }
+            if let Some(curve) = &b_curve {
+                w = lut_interp_linear_float(w, curve.as_slice());
+            }
+            *dst = w;
+        }
+    } else {
+        return Err(CmsError::InvalidAtoBLut);
+    }
+    Ok(())
+}
+
+/// Evaluates LUT with 3 inputs and 1 output, such as `gamt` tag,
+/// on normalized PCS values.
+pub(crate) fn create_lut3x1(
+    lut: &LutWarehouse,
+    src: &[f32],
+    apply_matrix: bool,
+    method: InterpolationMethod,
+) -> Result<Vec<f32>, CmsError> {
+    let mut dst = vec![0f32; src.len() / 3];
+    match lut {
+        LutWarehouse::Lut(lut) => {
+            if lut.num_input_channels != 3 || lut.num_output_channels != 1 {
+                return Err(CmsError::UnsupportedProfileConnection);
+            }
+            lut_3x1(lut, src, &mut dst, apply_matrix, method)?;
+        }
+        LutWarehouse::MCurves(mab) => {
+            if mab.num_input_channels != 3 || mab.num_output_channels != 1 {
+                return Err(CmsError::UnsupportedProfileConnection);
+            }
+            mab_3x1(mab, src, &mut dst, method)?;
+        }
+    }
+    Ok(dst)
+}
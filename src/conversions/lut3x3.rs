@@ -28,9 +28,11 @@
  */
 
 use crate::math::m_clamp;
+use crate::nd_array::Array3D;
 use crate::profile::LutDataType;
+use crate::transform::Stage;
 use crate::trc::lut_interp_linear_float;
-use crate::{Array3D, CmsError, InterpolationMethod, Stage, TransformOptions, Vector3f};
+use crate::{CmsError, InterpolationMethod, TransformOptions, Vector3f};
 
 #[derive(Default)]
 struct Lut3x3 {
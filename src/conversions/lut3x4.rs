@@ -27,9 +27,11 @@
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 use crate::math::m_clamp;
+use crate::nd_array::Array3D;
 use crate::profile::LutDataType;
+use crate::transform::Stage;
 use crate::trc::lut_interp_linear_float;
-use crate::{Array3D, CmsError, InterpolationMethod, Stage, TransformOptions, Vector4f};
+use crate::{CmsError, InterpolationMethod, TransformOptions, Vector4f};
 use num_traits::AsPrimitive;
 
 #[derive(Default)]
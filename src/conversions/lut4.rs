@@ -27,9 +27,11 @@
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 use crate::math::m_clamp;
+use crate::nd_array::Array4D;
 use crate::profile::LutDataType;
+use crate::transform::Stage;
 use crate::trc::lut_interp_linear_float;
-use crate::{Array4D, CmsError, InterpolationMethod, Stage, TransformOptions, Vector3f};
+use crate::{CmsError, InterpolationMethod, TransformOptions, Vector3f};
 
 #[derive(Default)]
 struct Lut4 {
@@ -127,19 +129,14 @@ fn stage_lut_4x3(lut: &LutDataType, options: TransformOptions) -> Box<dyn Stage>
     Box::new(transform)
 }
 
-pub(crate) fn create_lut4<const SAMPLES: usize>(
-    lut: &LutDataType,
-    options: TransformOptions,
-) -> Result<Vec<f32>, CmsError> {
-    if lut.num_input_channels != 4 {
-        return Err(CmsError::UnsupportedProfileConnection);
-    }
+pub(crate) fn create_lut4_samples_norm<const SAMPLES: usize>() -> Vec<f32> {
     let lut_size: u32 = (4 * SAMPLES * SAMPLES * SAMPLES * SAMPLES) as u32;
 
-    let mut src = Vec::with_capacity(lut_size as usize);
-    let mut dest = vec![0.; (lut_size as usize) / 4 * 3];
+    assert!(SAMPLES >= 1);
 
     let recpeq = 1f32 / (SAMPLES - 1) as f32;
+
+    let mut src = Vec::with_capacity(lut_size as usize);
     for k in 0..SAMPLES {
         for c in 0..SAMPLES {
             for m in 0..SAMPLES {
@@ -152,7 +149,21 @@ pub(crate) fn create_lut4<const SAMPLES: usize>(
             }
         }
     }
+    src
+}
+
+pub(crate) fn create_lut4(
+    lut: &LutDataType,
+    src: &[f32],
+    options: TransformOptions,
+) -> Result<Vec<f32>, CmsError> {
+    if lut.num_input_channels != 4 {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+
+    let mut dest = vec![0.; (src.len() / 4) * 3];
+
     let lut_stage = stage_lut_4x3(lut, options);
-    lut_stage.transform(&src, &mut dest)?;
+    lut_stage.transform(src, &mut dest)?;
     Ok(dest)
 }
@@ -0,0 +1,557 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::conversions::lut_transforms::{StageAbsoluteWhite, convert_pcs, lab_v2_to_v4};
+use crate::roundtrip::normalized_xyz_profile;
+use crate::transform::InPlaceStage;
+use crate::trc::lut_interp_linear_float;
+use crate::{
+    Chromaticity, CmsError, ColorProfile, DataColorSpace, Layout, LutWarehouse, Matrix3f,
+    RenderingIntent, ToneReprCurve, TransformExecutor, TransformF32BitExecutor, TransformOptions,
+    Vector3f,
+};
+use num_traits::AsPrimitive;
+
+/// Most device channels [NChannelToPcs] interpolates over, it reads `2^N` CLUT nodes per pixel
+pub(crate) const MAX_NCHANNELS: usize = 8;
+
+/// Pixels evaluated at once
+const CHUNK: usize = 256;
+
+/// Points tone curves of `lutAtoBType` are sampled to
+const CURVE_TABLE_SIZE: usize = 4096;
+
+fn curve_table(curve: &ToneReprCurve) -> Result<Vec<f32>, CmsError> {
+    let evaluator = curve
+        .linearize_evaluator()
+        .ok_or(CmsError::InvalidTrcCurve)?;
+    let scale = 1. / (CURVE_TABLE_SIZE - 1) as f32;
+    Ok((0..CURVE_TABLE_SIZE)
+        .map(|i| evaluator.evaluate(i as f32 * scale))
+        .collect())
+}
+
+fn curve_tables(curves: &[ToneReprCurve]) -> Result<Vec<Vec<f32>>, CmsError> {
+    curves.iter().map(curve_table).collect()
+}
+
+/// Device to PCS tables of a profile with N device channels.
+///
+/// CLUT is interpolated multilinearly over the N-cube, grid of every
+/// channel may have its own size, as `lutAtoBType` allows.
+struct NChannelToPcs {
+    channels: usize,
+    input_curves: Vec<Vec<f32>>,
+    clut: Vec<f32>,
+    grid_points: [usize; MAX_NCHANNELS],
+    /// Offset to the next node along each channel, first channel changes slowest
+    strides: [usize; MAX_NCHANNELS],
+    /// Output curves of `lut8Type`/`lut16Type` or "M" curves of `lutAtoBType`
+    clut_curves: Vec<Vec<f32>>,
+    /// Matrix and bias of `lutAtoBType`, applied after "M" curves
+    matrix: Option<(Matrix3f, Vector3f)>,
+    /// "B" curves of `lutAtoBType`
+    pcs_curves: Vec<Vec<f32>>,
+    legacy_lab: bool,
+}
+
+impl NChannelToPcs {
+    fn new(
+        lut: &LutWarehouse,
+        channels: usize,
+        pcs: DataColorSpace,
+    ) -> Result<NChannelToPcs, CmsError> {
+        let mut grid_points = [1usize; MAX_NCHANNELS];
+        let (input_curves, clut, clut_curves, matrix, pcs_curves) = match lut {
+            LutWarehouse::Lut(lut) => {
+                if lut.num_input_channels as usize != channels || lut.num_output_channels != 3 {
+                    return Err(CmsError::UnsupportedProfileConnection);
+                }
+                let entries = lut.num_input_table_entries as usize;
+                let input_curves = lut
+                    .input_table
+                    .chunks_exact(entries.max(1))
+                    .take(channels)
+                    .map(|x| x.to_vec())
+                    .collect::<Vec<_>>();
+                let entries = lut.num_output_table_entries as usize;
+                let output_curves = lut
+                    .output_table
+                    .chunks_exact(entries.max(1))
+                    .take(3)
+                    .map(|x| x.to_vec())
+                    .collect::<Vec<_>>();
+                if input_curves.len() != channels || output_curves.len() != 3 {
+                    return Err(CmsError::InvalidAtoBLut);
+                }
+                grid_points[..channels].fill(lut.num_clut_grid_points as usize);
+                let clut = lut.clut_table.clone();
+                (input_curves, clut, output_curves, None, Vec::new())
+            }
+            LutWarehouse::MCurves(mab) => {
+                if mab.num_input_channels as usize != channels || mab.num_output_channels != 3 {
+                    return Err(CmsError::UnsupportedProfileConnection);
+                }
+                // There is no way to reduce N channels to PCS without CLUT
+                if mab.a_curves.len() != channels || mab.clut.is_empty() || mab.b_curves.len() != 3
+                {
+                    return Err(CmsError::InvalidAtoBLut);
+                }
+                for (dst, &src) in grid_points.iter_mut().zip(mab.grid_points.iter()) {
+                    *dst = src as usize;
+                }
+                grid_points[channels..].fill(1);
+                let (m_curves, matrix) = if mab.m_curves.len() == 3 {
+                    (curve_tables(&mab.m_curves)?, Some((mab.matrix, mab.bias)))
+                } else {
+                    (Vec::new(), None)
+                };
+                (
+                    curve_tables(&mab.a_curves)?,
+                    mab.clut.clone(),
+                    m_curves,
+                    matrix,
+                    curve_tables(&mab.b_curves)?,
+                )
+            }
+        };
+
+        let mut strides = [0usize; MAX_NCHANNELS];
+        let mut stride = 3usize;
+        for channel in (0..channels).rev() {
+            strides[channel] = stride;
+            stride = stride
+                .checked_mul(grid_points[channel])
+                .ok_or(CmsError::OverflowingError)?;
+        }
+        if grid_points[..channels].contains(&0) || clut.len() != stride {
+            return Err(CmsError::InvalidAtoBLut);
+        }
+
+        Ok(NChannelToPcs {
+            channels,
+            input_curves,
+            clut,
+            grid_points,
+            strides,
+            clut_curves,
+            matrix,
+            pcs_curves,
+            legacy_lab: pcs == DataColorSpace::Lab && lut.has_legacy_lab_encoding(),
+        })
+    }
+
+    /// Interpolates CLUT at already linearized point
+    fn interpolate(&self, point: &[f32; MAX_NCHANNELS]) -> [f32; 3] {
+        let mut base = 0usize;
+        let mut fractions = [0f32; MAX_NCHANNELS];
+        let mut steps = [0usize; MAX_NCHANNELS];
+        for channel in 0..self.channels {
+            let grid = self.grid_points[channel];
+            if grid < 2 {
+                continue;
+            }
+            let x = point[channel].max(0.).min(1.) * (grid - 1) as f32;
+            let node = (x as usize).min(grid - 2);
+            fractions[channel] = x - node as f32;
+            steps[channel] = self.strides[channel];
+            base += node * self.strides[channel];
+        }
+
+        let mut out = [0f32; 3];
+        for corner in 0..1usize << self.channels {
+            let mut weight = 1f32;
+            let mut offset = base;
+            for channel in 0..self.channels {
+                if corner & (1 << channel) != 0 {
+                    weight *= fractions[channel];
+                    offset += steps[channel];
+                } else {
+                    weight *= 1. - fractions[channel];
+                }
+            }
+            if weight == 0. {
+                continue;
+            }
+            let node = &self.clut[offset..offset + 3];
+            out[0] += weight * node[0];
+            out[1] += weight * node[1];
+            out[2] += weight * node[2];
+        }
+        out
+    }
+
+    /// Evaluates normalized device values into normalized PCS values
+    fn transform(&self, src: &[f32], dst: &mut [f32]) -> Result<(), CmsError> {
+        let apply_curves = |v: &mut [f32], curves: &[Vec<f32>]| {
+            for (v, curve) in v.iter_mut().zip(curves.iter()) {
+                *v = lut_interp_linear_float(*v, curve);
+            }
+        };
+        for (src, dst) in src.chunks_exact(self.channels).zip(dst.chunks_exact_mut(3)) {
+            let mut point = [0f32; MAX_NCHANNELS];
+            point[..self.channels].copy_from_slice(src);
+            apply_curves(&mut point, &self.input_curves);
+            let mut pcs = self.interpolate(&point);
+            apply_curves(&mut pcs, &self.clut_curves);
+            if let Some((m, b)) = self.matrix {
+                pcs = [
+                    b.v[0] + pcs[0] * m.v[0][0] + pcs[1] * m.v[0][1] + pcs[2] * m.v[0][2],
+                    b.v[1] + pcs[0] * m.v[1][0] + pcs[1] * m.v[1][1] + pcs[2] * m.v[1][2],
+                    b.v[2] + pcs[0] * m.v[2][0] + pcs[1] * m.v[2][1] + pcs[2] * m.v[2][2],
+                ];
+            }
+            apply_curves(&mut pcs, &self.pcs_curves);
+            dst.copy_from_slice(&pcs);
+        }
+        if self.legacy_lab {
+            lab_v2_to_v4(dst);
+        }
+        Ok(())
+    }
+}
+
+struct TransformNChannel<T, const BIT_DEPTH: usize> {
+    to_pcs: NChannelToPcs,
+    pcs: DataColorSpace,
+    absolute_white: Option<StageAbsoluteWhite>,
+    /// Rescales normalized PCS XYZ, stored as u1Fixed15, to XYZ divided by D50 white point
+    pcs_scale: [f32; 3],
+    /// From XYZ divided by D50 white point to the destination
+    pcs_to_device: Box<TransformF32BitExecutor>,
+    dst_channels: usize,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+impl<T: Copy + Default + AsPrimitive<f32> + Send + Sync, const BIT_DEPTH: usize>
+    TransformExecutor<T> for TransformNChannel<T, BIT_DEPTH>
+where
+    f32: AsPrimitive<T>,
+{
+    fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
+        let channels = self.to_pcs.channels;
+        if src.len() % channels != 0 || dst.len() % self.dst_channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if src.len() / channels != dst.len() / self.dst_channels {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+
+        let max_value = ((1u32 << BIT_DEPTH) - 1) as f32;
+        let scale = 1. / max_value;
+        let mut device = vec![0f32; CHUNK * channels];
+        let mut pcs = vec![0f32; CHUNK * 3];
+        let mut out = vec![0f32; CHUNK * self.dst_channels];
+
+        for (src, dst) in src
+            .chunks(CHUNK * channels)
+            .zip(dst.chunks_mut(CHUNK * self.dst_channels))
+        {
+            let pixels = src.len() / channels;
+            let device = &mut device[..src.len()];
+            let pcs = &mut pcs[..pixels * 3];
+            let out = &mut out[..dst.len()];
+            for (dst, &src) in device.iter_mut().zip(src.iter()) {
+                *dst = src.as_() * scale;
+            }
+            self.to_pcs.transform(device, pcs)?;
+            convert_pcs(self.pcs, DataColorSpace::Xyz, pcs)?;
+            if let Some(absolute_white) = &self.absolute_white {
+                absolute_white.transform(pcs)?;
+            }
+            for xyz in pcs.chunks_exact_mut(3) {
+                for (v, scale) in xyz.iter_mut().zip(self.pcs_scale.iter()) {
+                    *v *= scale;
+                }
+            }
+            self.pcs_to_device.transform(pcs, out)?;
+            for (dst, &src) in dst.iter_mut().zip(out.iter()) {
+                *dst = (src * max_value).round().max(0.).min(max_value).as_();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Creates transform from a profile with `channels` device channels through its `AToB` table.
+///
+/// Source side is evaluated per pixel, destination side goes through
+/// a transform from PCS XYZ divided by D50 white point, so any destination is supported.
+pub(crate) fn make_nchannel_transform<
+    T: Copy + Default + AsPrimitive<f32> + Send + Sync + 'static,
+    const BIT_DEPTH: usize,
+>(
+    channels: usize,
+    source: &ColorProfile,
+    dest: &ColorProfile,
+    dst_layout: Layout,
+    options: TransformOptions,
+) -> Result<Box<dyn TransformExecutor<T> + Send + Sync>, CmsError>
+where
+    f32: AsPrimitive<T>,
+{
+    if channels == 0 || channels > MAX_NCHANNELS {
+        return Err(CmsError::UnsupportedChannelConfiguration);
+    }
+    if source.color_space.channels() != channels {
+        return Err(CmsError::UnsupportedChannelConfiguration);
+    }
+    if source.pcs != DataColorSpace::Lab && source.pcs != DataColorSpace::Xyz {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+    let device_to_pcs = source.get_device_to_pcs(options.rendering_intent).ok_or(
+        CmsError::UnsupportedLutRenderingIntent(options.rendering_intent),
+    )?;
+    let to_pcs = NChannelToPcs::new(device_to_pcs, channels, source.pcs)?;
+
+    let pcs_profile = normalized_xyz_profile();
+    let absolute_white = if options.rendering_intent == RenderingIntent::AbsoluteColorimetric {
+        let scale = source.absolute_intent_scale(&pcs_profile);
+        scale
+            .v
+            .iter()
+            .any(|x| (x - 1.).abs() > 1e-4)
+            .then_some(StageAbsoluteWhite {
+                scale,
+                pcs: DataColorSpace::Xyz,
+            })
+    } else {
+        None
+    };
+    let pcs_to_device = pcs_profile.create_transform_f32(Layout::Rgb, dest, dst_layout, options)?;
+
+    let d50 = Chromaticity::D50.to_xyz();
+    let u1_fixed15 = 65535. / 32768.;
+    Ok(Box::new(TransformNChannel::<T, BIT_DEPTH> {
+        to_pcs,
+        pcs: source.pcs,
+        absolute_white,
+        pcs_scale: [u1_fixed15 / d50.x, u1_fixed15 / d50.y, u1_fixed15 / d50.z],
+        pcs_to_device,
+        dst_channels: dst_layout.channels(),
+        _phantom: std::marker::PhantomData,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::LutDataType;
+    use crate::{LutMCurvesType, ProfileClass};
+
+    fn swop() -> ColorProfile {
+        ColorProfile::new_from_slice(include_bytes!("../../assets/us_swop_coated.icc")).unwrap()
+    }
+
+    fn cmyk_samples() -> Vec<u8> {
+        let mut src = Vec::new();
+        for c in (0..=255u32).step_by(51) {
+            for m in (0..=255u32).step_by(51) {
+                for y in (0..=255u32).step_by(85) {
+                    for k in (0..=255u32).step_by(85) {
+                        src.extend([c as u8, m as u8, y as u8, k as u8]);
+                    }
+                }
+            }
+        }
+        src
+    }
+
+    #[test]
+    fn test_nchannel_matches_cmyk_transform() {
+        let swop = swop();
+        let srgb = ColorProfile::new_srgb();
+        let src = cmyk_samples();
+        let options = TransformOptions::default();
+
+        let reference = swop
+            .create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgb, options)
+            .unwrap();
+        let mut expected = vec![0u8; src.len() / 4 * 3];
+        reference.transform(&src, &mut expected).unwrap();
+
+        let transform = swop
+            .create_nchannel_transform_8bit(4, &srgb, Layout::Rgb, options)
+            .unwrap();
+        let mut dst = vec![0u8; src.len() / 4 * 3];
+        transform.transform(&src, &mut dst).unwrap();
+
+        // Regular transform samples a 4D grid first, it differs most
+        // where dark out of gamut colors are clipped
+        let diffs = dst
+            .iter()
+            .zip(expected.iter())
+            .map(|(&a, &b)| a.abs_diff(b) as u32)
+            .collect::<Vec<_>>();
+        let max_diff = diffs.iter().max().copied().unwrap();
+        let mean_diff = diffs.iter().sum::<u32>() as f32 / diffs.len() as f32;
+        assert!(max_diff <= 8, "max difference {max_diff}");
+        assert!(mean_diff < 0.5, "mean difference {mean_diff}");
+
+        assert!(matches!(
+            swop.create_nchannel_transform_8bit(5, &srgb, Layout::Rgb, options),
+            Err(CmsError::UnsupportedChannelConfiguration)
+        ));
+        assert!(matches!(
+            transform.transform(&src[..6], &mut dst[..3]),
+            Err(CmsError::LaneMultipleOfChannels)
+        ));
+    }
+
+    #[test]
+    fn test_five_channels() {
+        // SWOP tables with the fifth channel that doesn't change the color
+        let swop = swop();
+        let Some(LutWarehouse::Lut(lut)) = swop.get_device_to_pcs(RenderingIntent::Perceptual)
+        else {
+            unreachable!()
+        };
+        let entries = lut.num_input_table_entries as usize;
+        let mut input_table = lut.input_table.clone();
+        input_table.extend_from_slice(&lut.input_table[..entries]);
+        let grid = lut.num_clut_grid_points as usize;
+        let clut_table = lut
+            .clut_table
+            .chunks_exact(3)
+            .flat_map(|node| node.repeat(grid))
+            .collect::<Vec<_>>();
+        let five = ColorProfile {
+            color_space: DataColorSpace::Color5,
+            lut_a_to_b_perceptual: Some(LutWarehouse::Lut(LutDataType {
+                num_input_channels: 5,
+                input_table,
+                clut_table,
+                ..lut.clone()
+            })),
+            lut_a_to_b_colorimetric: None,
+            lut_a_to_b_saturation: None,
+            ..swop.clone()
+        };
+
+        let srgb = ColorProfile::new_srgb();
+        let options = TransformOptions::default();
+        let cmyk = cmyk_samples();
+        let src = cmyk
+            .chunks_exact(4)
+            .enumerate()
+            .flat_map(|(i, x)| [x[0], x[1], x[2], x[3], (i * 37) as u8])
+            .collect::<Vec<_>>();
+
+        let four = swop
+            .create_nchannel_transform_8bit(4, &srgb, Layout::Rgb, options)
+            .unwrap();
+        let mut expected = vec![0u8; cmyk.len() / 4 * 3];
+        four.transform(&cmyk, &mut expected).unwrap();
+
+        let transform = five
+            .create_nchannel_transform_8bit(5, &srgb, Layout::Rgb, options)
+            .unwrap();
+        let mut dst = vec![0u8; expected.len()];
+        transform.transform(&src, &mut dst).unwrap();
+        for (&a, &b) in dst.iter().zip(expected.iter()) {
+            assert!(a.abs_diff(b) <= 1, "{a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn test_six_channels_mab() {
+        // PCS linear in every channel is reproduced exactly by multilinear interpolation
+        const GRID: usize = 3;
+        let base = [0.45f32, 0.48, 0.40];
+        let pcs_of = |device: &[f32]| -> [f32; 3] {
+            let mut pcs = base;
+            for (i, &d) in device.iter().enumerate() {
+                pcs[0] -= d * 0.010 * (i + 1) as f32;
+                pcs[1] -= d * 0.012 * (6 - i) as f32;
+                pcs[2] -= d * 0.009 * ((i % 3) + 1) as f32;
+            }
+            pcs
+        };
+        let mut clut = Vec::new();
+        for node in 0..GRID.pow(6) {
+            let device = (0..6)
+                .map(|i| ((node / GRID.pow(5 - i)) % GRID) as f32 / (GRID - 1) as f32)
+                .collect::<Vec<_>>();
+            clut.extend(pcs_of(&device));
+        }
+        let mut grid_points = [0u8; 16];
+        grid_points[..6].fill(GRID as u8);
+        let profile = ColorProfile {
+            profile_class: ProfileClass::OutputDevice,
+            color_space: DataColorSpace::Color6,
+            pcs: DataColorSpace::Xyz,
+            lut_a_to_b_perceptual: Some(LutWarehouse::MCurves(LutMCurvesType {
+                num_input_channels: 6,
+                num_output_channels: 3,
+                grid_points,
+                clut,
+                a_curves: vec![ToneReprCurve::Lut(Vec::new()); 6],
+                b_curves: vec![ToneReprCurve::Lut(Vec::new()); 3],
+                m_curves: Vec::new(),
+                matrix: Matrix3f::IDENTITY,
+                bias: Vector3f::default(),
+            })),
+            ..Default::default()
+        };
+        // 6 channels CLUT survives encoding
+        let profile = ColorProfile::new_from_slice(&profile.encode().unwrap()).unwrap();
+        assert_eq!(profile.color_space, DataColorSpace::Color6);
+
+        let srgb = ColorProfile::new_srgb();
+        let options = TransformOptions::default();
+        let transform = profile
+            .create_nchannel_transform_16bit(6, &srgb, Layout::Rgba, options)
+            .unwrap();
+
+        let src = (0..600u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 16) as u16)
+            .collect::<Vec<_>>();
+        let mut dst = vec![0u16; 100 * 4];
+        transform.transform(&src, &mut dst).unwrap();
+
+        let pcs = src
+            .chunks_exact(6)
+            .flat_map(|x| pcs_of(&x.iter().map(|&v| v as f32 / 65535.).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        let d50 = Chromaticity::D50.to_xyz();
+        let pcs = pcs
+            .chunks_exact(3)
+            .flat_map(|x| [x[0] / d50.x, x[1] / d50.y, x[2] / d50.z].map(|v| v * 65535. / 32768.))
+            .collect::<Vec<_>>();
+        let reference = normalized_xyz_profile()
+            .create_transform_f32(Layout::Rgb, &srgb, Layout::Rgba, options)
+            .unwrap();
+        let mut expected = vec![0f32; dst.len()];
+        reference.transform(&pcs, &mut expected).unwrap();
+        for (&a, &b) in dst.iter().zip(expected.iter()) {
+            let b = (b * 65535.).round() as u16;
+            assert!(a.abs_diff(b) <= 1, "{a} vs {b}");
+        }
+        assert!(dst.chunks_exact(4).all(|x| x[3] == 65535));
+    }
+}
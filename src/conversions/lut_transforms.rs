@@ -26,17 +26,25 @@
  * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
+use crate::conversions::lut3x1::create_lut3x1;
 use crate::conversions::lut3x3::create_lut3x3;
 use crate::conversions::lut3x4::{create_lut3_samples, create_lut3_samples_norm, create_lut3x4};
-use crate::conversions::lut4::create_lut4;
-use crate::conversions::mab::{prepare_mab_3x3, prepare_mba_3x3};
+use crate::conversions::lut4::{create_lut4, create_lut4_samples_norm};
+use crate::conversions::mab::{prepare_mab_3x3, prepare_mab_4x3, prepare_mba_3x3, prepare_mba_3x4};
+use crate::conversions::transform_lut3_to_1::TransformLut3x1;
 use crate::conversions::transform_lut3_to_4::TransformLut3x4;
+use crate::conversions::transform_lut4_to_1::TransformLut4x1;
+use crate::conversions::transform_lut4_to_4::TransformLut4x4;
+#[allow(unused)]
+use crate::dispatch::{KernelFamily, use_kernel};
 use crate::lab::Lab;
 use crate::math::m_clamp;
 use crate::mlaf::mlaf;
+use crate::profile::LutDataType;
+use crate::transform::InPlaceStage;
 use crate::{
-    CmsError, ColorProfile, DataColorSpace, InPlaceStage, InterpolationMethod, Layout,
-    LutWarehouse, Matrix3f, ProfileVersion, TransformExecutor, TransformOptions, Xyz,
+    CmsError, ColorProfile, DataColorSpace, InterpolationMethod, Layout, LutWarehouse, Matrix3f,
+    ProfileClass, RenderingIntent, TransformExecutor, TransformOptions, Vector3f, Xyz,
 };
 use num_traits::AsPrimitive;
 use std::marker::PhantomData;
@@ -73,6 +81,53 @@ impl InPlaceStage for StageXyzToLab {
     }
 }
 
+/// Scales PCS by ratio of media white points for absolute colorimetric intent
+pub(crate) struct StageAbsoluteWhite {
+    pub(crate) scale: Vector3f,
+    pub(crate) pcs: DataColorSpace,
+}
+
+impl InPlaceStage for StageAbsoluteWhite {
+    fn transform(&self, dst: &mut [f32]) -> Result<(), CmsError> {
+        let lab = self.pcs == DataColorSpace::Lab;
+        for dst in dst.chunks_exact_mut(3) {
+            let mut xyz = if lab {
+                Lab::new(dst[0], dst[1], dst[2]).to_pcs_xyz()
+            } else {
+                Xyz::new(dst[0], dst[1], dst[2])
+            };
+            xyz.x = m_clamp(xyz.x * self.scale.v[0], 0., 1.);
+            xyz.y = m_clamp(xyz.y * self.scale.v[1], 0., 1.);
+            xyz.z = m_clamp(xyz.z * self.scale.v[2], 0., 1.);
+            if lab {
+                let lab = Lab::from_pcs_xyz(xyz);
+                dst[0] = lab.l;
+                dst[1] = lab.a;
+                dst[2] = lab.b;
+            } else {
+                dst[0] = xyz.x;
+                dst[1] = xyz.y;
+                dst[2] = xyz.z;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads gamma table at fractional `position`, interpolating between neighbour entries.
+///
+/// Grid nodes take whole table entries otherwise, which is off by several codes
+/// at 16 bits where the curve is steep.
+#[inline(always)]
+fn gamma_lookup<T: AsPrimitive<f32>>(table: &[T], position: f32, lut_cap: f32) -> f32 {
+    let position = position.min(lut_cap).max(0f32);
+    let index = position as usize;
+    let next = (index + 1).min(lut_cap as usize);
+    let lo: f32 = table[index].as_();
+    let hi: f32 = table[next].as_();
+    mlaf(lo, hi - lo, position - index as f32)
+}
+
 struct XyzToRgbStage<T: Clone, const BIT_DEPTH: usize, const GAMMA_LUT: usize> {
     r_gamma: Box<[T; 65536]>,
     g_gamma: Box<[T; 65536]>,
@@ -113,18 +168,40 @@ impl<T: Clone + AsPrimitive<f32>, const BIT_DEPTH: usize, const GAMMA_LUT: usize
         let lut_cap = (GAMMA_LUT - 1) as f32;
 
         for dst in dst.chunks_exact_mut(3) {
-            let r = mlaf(0.5f32, dst[0], lut_cap).min(lut_cap).max(0f32) as u16;
-            let g = mlaf(0.5f32, dst[1], lut_cap).min(lut_cap).max(0f32) as u16;
-            let b = mlaf(0.5f32, dst[2], lut_cap).min(lut_cap).max(0f32) as u16;
-            dst[0] = self.r_gamma[r as usize].as_() * color_scale;
-            dst[1] = self.g_gamma[g as usize].as_() * color_scale;
-            dst[2] = self.b_gamma[b as usize].as_() * color_scale;
+            dst[0] = gamma_lookup(self.r_gamma.as_slice(), dst[0] * lut_cap, lut_cap) * color_scale;
+            dst[1] = gamma_lookup(self.g_gamma.as_slice(), dst[1] * lut_cap, lut_cap) * color_scale;
+            dst[2] = gamma_lookup(self.b_gamma.as_slice(), dst[2] * lut_cap, lut_cap) * color_scale;
         }
 
         Ok(())
     }
 }
 
+/// Takes luminance from PCS XYZ and encodes it with gray TRC,
+/// output has a single channel.
+struct XyzToGrayStage<T: Clone, const BIT_DEPTH: usize, const GAMMA_LUT: usize> {
+    gray_gamma: Box<[T; 65536]>,
+}
+
+impl<T: Clone + AsPrimitive<f32>, const BIT_DEPTH: usize, const GAMMA_LUT: usize>
+    XyzToGrayStage<T, BIT_DEPTH, GAMMA_LUT>
+{
+    fn transform(&self, src: &[f32]) -> Vec<f32> {
+        assert!(BIT_DEPTH > 0);
+        let max_colors = (1 << BIT_DEPTH) - 1;
+        let color_scale = 1f32 / max_colors as f32;
+        let lut_cap = (GAMMA_LUT - 1) as f32;
+        // PCS XYZ is encoded as xyz / (1 + 32767 / 32768)
+        let y_scale = 65535.0 / 32768.0 * lut_cap;
+
+        src.chunks_exact(3)
+            .map(|xyz| {
+                gamma_lookup(self.gray_gamma.as_slice(), xyz[1] * y_scale, lut_cap) * color_scale
+            })
+            .collect()
+    }
+}
+
 struct MatrixStage {
     matrices: Vec<Matrix3f>,
 }
@@ -158,10 +235,28 @@ impl InPlaceStage for MatrixStage {
     }
 }
 
+/// Applies matrix of lut8Type/lut16Type, which is only valid when the input color space is XYZ.
+fn apply_lut_matrix(lut_data: &LutDataType, lut: &mut [f32]) -> Result<(), CmsError> {
+    if lut_data.matrix.test_equality(Matrix3f::IDENTITY) {
+        return Ok(());
+    }
+    let stage = MatrixStage {
+        matrices: vec![lut_data.matrix],
+    };
+    stage.transform(lut)
+}
+
+/// Brings LUT transform input into `0..=LUT_SAMPLING` range, that interpolators index grid with.
 pub(crate) trait CompressForLut {
+    /// `BIT_DEPTH` is declared depth of integer input, values above it saturate
     fn compress_lut<const BIT_DEPTH: usize>(self) -> u16;
 }
 
+/// Resolution LUT transform inputs are sampled at, for every bit depth.
+///
+/// Integer inputs are shifted into the full 16-bit range
+/// and floats are rounded to it, so 10, 12 and 16-bit data keeps its precision
+/// between grid nodes rather than being quantized to 8 bits first.
 pub(crate) const LUT_SAMPLING: u16 = 65535;
 
 impl CompressForLut for u8 {
@@ -174,8 +269,11 @@ impl CompressForLut for u8 {
 impl CompressForLut for u16 {
     #[inline(always)]
     fn compress_lut<const BIT_DEPTH: usize>(self) -> u16 {
-        let target_expand_bits = 16u32 - BIT_DEPTH as u32;
-        ((self) << target_expand_bits) | ((self) >> (16 - target_expand_bits))
+        // Values above declared bit depth saturate. Shifting alone would map max value
+        // of 10 and 12-bit data below 65535, so value is rescaled with rounding instead.
+        let max_value = (1u32 << BIT_DEPTH) - 1;
+        let v = (self as u32).min(max_value);
+        ((v * LUT_SAMPLING as u32 + (max_value >> 1)) / max_value) as u16
     }
 }
 
@@ -277,50 +375,52 @@ impl<
     }
 }
 
-fn pcs_lab_v4_to_v2(profile: &ColorProfile, lut: &mut [f32]) {
-    if profile.pcs == DataColorSpace::Lab
-        && profile.version_internal <= ProfileVersion::V4_0
-        && lut.len() % 3 == 0
-    {
-        assert_eq!(
-            lut.len() % 3,
-            0,
-            "Lut {:?} is not a multiple of 3, this should not happen for lab",
-            lut.len()
-        );
-        let v_mat = vec![Matrix3f {
-            v: [
-                [65280.0 / 65535.0, 0f32, 0f32],
-                [0f32, 65280.0 / 65535.0, 0f32],
-                [0f32, 0f32, 65280.0 / 65535.0f32],
-            ],
-        }];
-        let stage = MatrixStage { matrices: v_mat };
-        stage.transform(lut).unwrap();
-    }
+fn lab_v4_to_v2(lut: &mut [f32]) {
+    assert_eq!(
+        lut.len() % 3,
+        0,
+        "Lut {:?} is not a multiple of 3, this should not happen for lab",
+        lut.len()
+    );
+    let v_mat = vec![Matrix3f {
+        v: [
+            [65280.0 / 65535.0, 0f32, 0f32],
+            [0f32, 65280.0 / 65535.0, 0f32],
+            [0f32, 0f32, 65280.0 / 65535.0f32],
+        ],
+    }];
+    let stage = MatrixStage { matrices: v_mat };
+    stage.transform(lut).unwrap();
 }
 
-fn pcs_lab_v2_to_v4(profile: &ColorProfile, lut: &mut [f32]) {
-    if profile.pcs == DataColorSpace::Lab
-        && profile.version_internal <= ProfileVersion::V4_0
-        && lut.len() % 3 == 0
-    {
-        assert_eq!(
-            lut.len() % 3,
-            0,
-            "Lut {:?} is not a multiple of 3, this should not happen for lab",
-            lut.len()
-        );
-        let v_mat = vec![Matrix3f {
-            v: [
-                [65535.0 / 65280.0f32, 0f32, 0f32],
-                [0f32, 65535.0f32 / 65280.0f32, 0f32],
-                [0f32, 0f32, 65535.0f32 / 65280.0f32],
-            ],
-        }];
-        let stage = MatrixStage { matrices: v_mat };
-        stage.transform(lut).unwrap();
-    }
+pub(crate) fn lab_v2_to_v4(lut: &mut [f32]) {
+    assert_eq!(
+        lut.len() % 3,
+        0,
+        "Lut {:?} is not a multiple of 3, this should not happen for lab",
+        lut.len()
+    );
+    let v_mat = vec![Matrix3f {
+        v: [
+            [65535.0 / 65280.0f32, 0f32, 0f32],
+            [0f32, 65535.0f32 / 65280.0f32, 0f32],
+            [0f32, 0f32, 65535.0f32 / 65280.0f32],
+        ],
+    }];
+    let stage = MatrixStage { matrices: v_mat };
+    stage.transform(lut).unwrap();
+}
+
+macro_rules! lut3x3_executor {
+    ($src: ident, $dst: ident, ($exec_impl: ident, $lut: ident, $options: ident)) => {
+        Box::new($exec_impl::make_transform_3x3::<
+            T,
+            { Layout::$src as u8 },
+            { Layout::$dst as u8 },
+            GRID_SIZE,
+            BIT_DEPTH,
+        >($lut, $options.interpolation_method))
+    };
 }
 
 macro_rules! make_transform_3x3_fn {
@@ -346,43 +446,12 @@ macro_rules! make_transform_3x3_fn {
             f32: AsPrimitive<T>,
             u32: AsPrimitive<T>,
         {
-            match src_layout {
-                Layout::Rgb => match dst_layout {
-                    Layout::Rgb => Box::new($exec_impl::make_transform_3x3::<
-                        T,
-                        { Layout::Rgb as u8 },
-                        { Layout::Rgb as u8 },
-                        GRID_SIZE,
-                        BIT_DEPTH,
-                    >(lut, options.interpolation_method)),
-                    Layout::Rgba => Box::new($exec_impl::make_transform_3x3::<
-                        T,
-                        { Layout::Rgb as u8 },
-                        { Layout::Rgba as u8 },
-                        GRID_SIZE,
-                        BIT_DEPTH,
-                    >(lut, options.interpolation_method)),
-                    _ => unimplemented!(),
-                },
-                Layout::Rgba => match dst_layout {
-                    Layout::Rgb => Box::new($exec_impl::make_transform_3x3::<
-                        T,
-                        { Layout::Rgba as u8 },
-                        { Layout::Rgb as u8 },
-                        GRID_SIZE,
-                        BIT_DEPTH,
-                    >(lut, options.interpolation_method)),
-                    Layout::Rgba => Box::new($exec_impl::make_transform_3x3::<
-                        T,
-                        { Layout::Rgba as u8 },
-                        { Layout::Rgba as u8 },
-                        GRID_SIZE,
-                        BIT_DEPTH,
-                    >(lut, options.interpolation_method)),
-                    _ => unimplemented!(),
-                },
-                _ => unimplemented!(),
-            }
+            dispatch_layouts!(
+                src_layout => [Rgb, Rgba, Bgr, Bgra, Argb, Abgr],
+                dst_layout => [Rgb, Rgba, Bgr, Bgra, Argb, Abgr],
+                unimplemented!(),
+                lut3x3_executor($exec_impl, lut, options)
+            )
         }
     };
 }
@@ -422,20 +491,42 @@ macro_rules! make_transform_4x3_fn {
                     GRID_SIZE,
                     BIT_DEPTH,
                 >(lut, options.interpolation_method)),
+                Layout::Bgr => Box::new($exec_name::make_transform_4x3::<
+                    T,
+                    { Layout::Bgr as u8 },
+                    GRID_SIZE,
+                    BIT_DEPTH,
+                >(lut, options.interpolation_method)),
+                Layout::Bgra => Box::new($exec_name::make_transform_4x3::<
+                    T,
+                    { Layout::Bgra as u8 },
+                    GRID_SIZE,
+                    BIT_DEPTH,
+                >(lut, options.interpolation_method)),
+                Layout::Argb => Box::new($exec_name::make_transform_4x3::<
+                    T,
+                    { Layout::Argb as u8 },
+                    GRID_SIZE,
+                    BIT_DEPTH,
+                >(lut, options.interpolation_method)),
+                Layout::Abgr => Box::new($exec_name::make_transform_4x3::<
+                    T,
+                    { Layout::Abgr as u8 },
+                    GRID_SIZE,
+                    BIT_DEPTH,
+                >(lut, options.interpolation_method)),
                 _ => unimplemented!(),
             }
         }
     };
 }
 
-#[cfg(all(target_arch = "aarch64", target_feature = "neon", feature = "neon"))]
+#[cfg(all(target_arch = "aarch64", feature = "neon"))]
 use crate::conversions::neon::NeonLut3x3Factory;
-#[cfg(all(target_arch = "aarch64", target_feature = "neon", feature = "neon"))]
-make_transform_3x3_fn!(make_transformer_3x3, NeonLut3x3Factory);
+#[cfg(all(target_arch = "aarch64", feature = "neon"))]
+make_transform_3x3_fn!(make_transformer_3x3_neon, NeonLut3x3Factory);
 
-#[cfg(not(all(target_arch = "aarch64", target_feature = "neon", feature = "neon")))]
 use crate::conversions::transform_lut3_to_3::DefaultLut3x3Factory;
-#[cfg(not(all(target_arch = "aarch64", target_feature = "neon", feature = "neon")))]
 make_transform_3x3_fn!(make_transformer_3x3, DefaultLut3x3Factory);
 
 #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "avx"))]
@@ -460,21 +551,104 @@ use crate::conversions::sse::SseLut4x3Factory;
 #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "sse"))]
 make_transform_4x3_fn!(make_transformer_4x3_sse41, SseLut4x3Factory);
 
-#[cfg(not(all(target_arch = "aarch64", target_feature = "neon", feature = "neon")))]
 use crate::conversions::transform_lut4_to_4::DefaultLut4x3Factory;
 
-#[cfg(not(all(target_arch = "aarch64", target_feature = "neon", feature = "neon")))]
 make_transform_4x3_fn!(make_transformer_4x3, DefaultLut4x3Factory);
 
-#[cfg(all(target_arch = "aarch64", target_feature = "neon", feature = "neon"))]
+#[cfg(all(target_arch = "aarch64", feature = "neon"))]
 use crate::conversions::neon::NeonLut4x3Factory;
 use crate::transform::PointeeSizeExpressible;
 use crate::trc::GammaLutInterpolate;
 
-#[cfg(all(target_arch = "aarch64", target_feature = "neon", feature = "neon"))]
-make_transform_4x3_fn!(make_transformer_4x3, NeonLut4x3Factory);
+#[cfg(all(target_arch = "aarch64", feature = "neon"))]
+make_transform_4x3_fn!(make_transformer_4x3_neon, NeonLut4x3Factory);
+
+/// Defines which executor sampled grid is going to feed.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum LutShape {
+    /// CMYK to RGB/Lab
+    Lut4x3,
+    /// RGB/Lab to CMYK
+    Lut3x4,
+    /// RGB/Lab to RGB/Lab
+    Lut3x3,
+    /// RGB/Lab to a single channel, used for `gamt` tag
+    Lut3x1,
+    /// CMYK to Gray
+    Lut4x1,
+    /// CMYK to CMYK
+    Lut4x4,
+}
+
+pub(crate) const GRID_SIZE_4: usize = 17;
+pub(crate) const GRID_SIZE_3: usize = 33;
+
+/// Samples LUT based transform grid.
+///
+/// Every grid point is computed independently, so grid might be sampled
+/// in arbitrary ranges and will be exactly the same as sampled at once.
+pub(crate) trait LutSampler<T: Copy + Default>: Send + Sync {
+    /// Total count of grid points
+    fn grid_points(&self) -> usize;
+    /// Count of values produced for every grid point
+    fn output_channels(&self) -> usize;
+    /// Samples grid points `start..end` into `dst`
+    fn sample(&self, start: usize, end: usize, dst: &mut [f32]) -> Result<(), CmsError>;
+    /// Creates executor from completely sampled grid
+    fn make_executor(self: Box<Self>, lut: Vec<f32>)
+    -> Box<dyn TransformExecutor<T> + Send + Sync>;
+}
+
+enum DeviceToPcs<T: Clone, const BIT_DEPTH: usize, const LINEAR_CAP: usize> {
+    /// 4 channels device LUT sampled on normalized grid
+    Lut4(Vec<f32>),
+    /// 3 channels device LUT sampled on normalized grid
+    Lut3(Vec<f32>),
+    /// Matrix shaper sampled on grid indices
+    MatrixShaper {
+        origins: Vec<T>,
+        linearization: RgbLinearizationStage<T, BIT_DEPTH, LINEAR_CAP, GRID_SIZE_3>,
+        matrix: MatrixStage,
+    },
+}
+
+enum PcsToDevice<T: Clone, const BIT_DEPTH: usize, const GAMMA_LUT: usize> {
+    /// PCS to 3 channels device LUT
+    Lut3,
+    /// PCS to 4 channels device LUT
+    Lut4,
+    /// Inverse matrix shaper
+    MatrixShaper(XyzToRgbStage<T, BIT_DEPTH, GAMMA_LUT>),
+    /// Gamut tag of destination profile
+    Gamut,
+    /// Round trip through destination tables moving color more than given ΔE*ab
+    RoundTrip(f32),
+    /// Luminance encoded with gray TRC
+    Gray(XyzToGrayStage<T, BIT_DEPTH, GAMMA_LUT>),
+}
+
+struct LutSamplingPlan<
+    T: Clone,
+    const BIT_DEPTH: usize,
+    const LINEAR_CAP: usize,
+    const GAMMA_LUT: usize,
+> {
+    shape: LutShape,
+    source: ColorProfile,
+    dest: ColorProfile,
+    src_layout: Layout,
+    dst_layout: Layout,
+    options: TransformOptions,
+    device_to_pcs: DeviceToPcs<T, BIT_DEPTH, LINEAR_CAP>,
+    pcs_to_device: PcsToDevice<T, BIT_DEPTH, GAMMA_LUT>,
+    /// Abstract profiles applied in PCS with intent of their tables
+    abstracts: Vec<(ColorProfile, RenderingIntent)>,
+    /// Intent of destination tables, might differ from source one in profiles chain
+    dest_intent: RenderingIntent,
+    absolute_white: Option<StageAbsoluteWhite>,
+}
 
-pub(crate) fn make_lut_transform<
+impl<
     T: Copy
         + Default
         + AsPrimitive<f32>
@@ -487,159 +661,271 @@ pub(crate) fn make_lut_transform<
     const BIT_DEPTH: usize,
     const LINEAR_CAP: usize,
     const GAMMA_LUT: usize,
->(
-    src_layout: Layout,
-    source: &ColorProfile,
-    dst_layout: Layout,
-    dest: &ColorProfile,
-    options: TransformOptions,
-) -> Result<Box<dyn TransformExecutor<T> + Send + Sync>, CmsError>
+> LutSampler<T> for LutSamplingPlan<T, BIT_DEPTH, LINEAR_CAP, GAMMA_LUT>
 where
     f32: AsPrimitive<T>,
     u32: AsPrimitive<T>,
 {
-    if (source.color_space == DataColorSpace::Cmyk || source.color_space == DataColorSpace::Color4)
-        && (dest.color_space == DataColorSpace::Rgb || dest.color_space == DataColorSpace::Lab)
-    {
-        source.color_space.check_layout(src_layout)?;
-        dest.color_space.check_layout(dst_layout)?;
-        if source.pcs != DataColorSpace::Xyz && source.pcs != DataColorSpace::Lab {
-            return Err(CmsError::UnsupportedProfileConnection);
-        }
-        if dest.pcs != DataColorSpace::Lab && dest.pcs != DataColorSpace::Xyz {
-            return Err(CmsError::UnsupportedProfileConnection);
+    fn grid_points(&self) -> usize {
+        match self.shape {
+            LutShape::Lut4x3 | LutShape::Lut4x1 | LutShape::Lut4x4 => {
+                GRID_SIZE_4 * GRID_SIZE_4 * GRID_SIZE_4 * GRID_SIZE_4
+            }
+            LutShape::Lut3x4 | LutShape::Lut3x3 | LutShape::Lut3x1 => {
+                GRID_SIZE_3 * GRID_SIZE_3 * GRID_SIZE_3
+            }
         }
+    }
 
-        let src_lut_a_to_b = source
-            .get_device_to_pcs_lut(options.rendering_intent)
-            .ok_or(CmsError::UnsupportedLutRenderingIntent(
-                source.rendering_intent,
-            ))?;
-
-        const GRID_SIZE: usize = 17;
-
-        let mut lut = create_lut4::<GRID_SIZE>(src_lut_a_to_b, options)?;
-
-        pcs_lab_v2_to_v4(source, &mut lut);
-
-        if source.pcs == DataColorSpace::Lab {
-            let lab_to_xyz_stage = StageLabToXyz::default();
-            lab_to_xyz_stage.transform(&mut lut)?;
+    fn output_channels(&self) -> usize {
+        match self.shape {
+            LutShape::Lut4x3 | LutShape::Lut3x3 => 3,
+            LutShape::Lut3x4 | LutShape::Lut4x4 => 4,
+            LutShape::Lut3x1 | LutShape::Lut4x1 => 1,
         }
+    }
 
-        // if source.color_space == DataColorSpace::Cmyk
-        //     && (options.rendering_intent == RenderingIntent::Perceptual
-        //         || options.rendering_intent == RenderingIntent::RelativeColorimetric)
-        //     && options.black_point_compensation
-        // {
-        //     if let (Some(src_bp), Some(dst_bp)) = (
-        //         source.detect_black_point::<GRID_SIZE>(&lut),
-        //         dest.detect_black_point::<GRID_SIZE>(&lut),
-        //     ) {
-        //         compensate_bpc_in_lut(&mut lut, src_bp, dst_bp);
-        //     }
-        // }
+    fn sample(&self, start: usize, end: usize, dst: &mut [f32]) -> Result<(), CmsError> {
+        if start > end || end > self.grid_points() {
+            return Err(CmsError::OverflowingError);
+        }
+        if dst.len() != (end - start) * self.output_channels() {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        let options = self.options;
+
+        let mut lut = match &self.device_to_pcs {
+            DeviceToPcs::Lut4(origins) => {
+                let device_to_pcs = self
+                    .source
+                    .get_device_to_pcs(options.rendering_intent)
+                    .ok_or(CmsError::UnsupportedLutRenderingIntent(
+                        self.source.rendering_intent,
+                    ))?;
+                let origins = &origins[start * 4..end * 4];
+                let mut lut = match device_to_pcs {
+                    LutWarehouse::Lut(lut_data_type) => {
+                        create_lut4(lut_data_type, origins, options)?
+                    }
+                    LutWarehouse::MCurves(mab) => prepare_mab_4x3(mab, origins, options)?,
+                };
+                if self.source.pcs == DataColorSpace::Lab && device_to_pcs.has_legacy_lab_encoding()
+                {
+                    lab_v2_to_v4(&mut lut);
+                }
+                lut
+            }
+            DeviceToPcs::Lut3(origins) => device_to_pcs3_lut(
+                &self.source,
+                options.rendering_intent,
+                origins[start * 3..end * 3].to_vec(),
+                options,
+            )?,
+            DeviceToPcs::MatrixShaper {
+                origins,
+                linearization,
+                matrix,
+            } => {
+                let mut lut = vec![0f32; (end - start) * 3];
+                linearization.transform(&origins[start * 3..end * 3], &mut lut)?;
+                matrix.transform(&mut lut)?;
+                lut
+            }
+        };
 
-        if dest.pcs == DataColorSpace::Lab {
-            let lab_to_xyz_stage = StageXyzToLab::default();
-            lab_to_xyz_stage.transform(&mut lut)?;
+        let mut pcs = self.source.pcs;
+        for (profile, intent) in &self.abstracts {
+            convert_pcs(pcs, profile.color_space, &mut lut)?;
+            lut = device_to_pcs3_lut(profile, *intent, lut, options)?;
+            pcs = profile.pcs;
         }
+        convert_pcs(pcs, self.dest.pcs, &mut lut)?;
 
-        pcs_lab_v4_to_v2(dest, &mut lut);
+        if let Some(absolute_white) = &self.absolute_white {
+            absolute_white.transform(&mut lut)?;
+        }
 
-        if dest.pcs == DataColorSpace::Xyz {
-            if dest.has_full_colors_triplet() {
-                prepare_inverse_lut_rgb_xyz::<T, BIT_DEPTH, GAMMA_LUT>(dest, &mut lut, options)?;
-            } else {
-                return Err(CmsError::UnsupportedProfileConnection);
+        match &self.pcs_to_device {
+            PcsToDevice::Lut3 => {
+                let pcs_to_device = self
+                    .dest
+                    .get_pcs_to_device(self.dest_intent)
+                    .ok_or(CmsError::UnsupportedProfileConnection)?;
+                let legacy_lab = pcs_to_device.has_legacy_lab_encoding();
+                if self.dest.pcs == DataColorSpace::Lab && legacy_lab {
+                    lab_v4_to_v2(&mut lut);
+                }
+                match pcs_to_device {
+                    LutWarehouse::Lut(lut_data_type) => {
+                        if self.dest.pcs == DataColorSpace::Xyz {
+                            apply_lut_matrix(lut_data_type, &mut lut)?;
+                        }
+                        lut = create_lut3x3(lut_data_type, &lut, options)?
+                    }
+                    LutWarehouse::MCurves(mab) => prepare_mba_3x3(mab, &mut lut, options)?,
+                }
+                if self.dest.color_space == DataColorSpace::Lab && legacy_lab {
+                    lab_v2_to_v4(&mut lut);
+                }
             }
-        } else if dest.pcs == DataColorSpace::Lab {
-            let pcs_to_device = dest
-                .get_pcs_to_device(options.rendering_intent)
-                .ok_or(CmsError::UnsupportedProfileConnection)?;
-            match pcs_to_device {
-                LutWarehouse::Lut(lut_data_type) => {
-                    lut = create_lut3x3(lut_data_type, &lut, options)?
+            PcsToDevice::Lut4 => {
+                let pcs_to_device = self
+                    .dest
+                    .get_pcs_to_device(self.dest_intent)
+                    .ok_or(CmsError::UnsupportedLutRenderingIntent(self.dest_intent))?;
+                if self.dest.pcs == DataColorSpace::Lab && pcs_to_device.has_legacy_lab_encoding() {
+                    lab_v4_to_v2(&mut lut);
                 }
-                LutWarehouse::MCurves(mab) => prepare_mba_3x3(mab, &mut lut, options)?,
+                lut = match pcs_to_device {
+                    LutWarehouse::Lut(lut_data_type) => {
+                        if self.dest.pcs == DataColorSpace::Xyz {
+                            apply_lut_matrix(lut_data_type, &mut lut)?;
+                        }
+                        create_lut3x4(lut_data_type, &lut, options)?
+                    }
+                    LutWarehouse::MCurves(mab) => prepare_mba_3x4(mab, &mut lut, options)?,
+                };
             }
-        }
-
-        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-        {
-            #[cfg(feature = "avx")]
-            if std::arch::is_x86_feature_detected!("avx2")
-                && std::arch::is_x86_feature_detected!("fma")
-            {
-                return Ok(make_transformer_4x3_avx_fma::<T, GRID_SIZE, BIT_DEPTH>(
-                    dst_layout, lut, options,
-                ));
+            PcsToDevice::MatrixShaper(xyz_to_rgb_stage) => xyz_to_rgb_stage.transform(&mut lut)?,
+            PcsToDevice::Gamut => {
+                let gamut = self
+                    .dest
+                    .gamut
+                    .as_ref()
+                    .ok_or(CmsError::UnsupportedProfileConnection)?;
+                if self.dest.pcs == DataColorSpace::Lab && gamut.has_legacy_lab_encoding() {
+                    lab_v4_to_v2(&mut lut);
+                }
+                lut = create_lut3x1(
+                    gamut,
+                    &lut,
+                    self.dest.pcs == DataColorSpace::Xyz,
+                    options.interpolation_method,
+                )?;
             }
-            #[cfg(feature = "sse")]
-            if std::arch::is_x86_feature_detected!("sse4.1") {
-                return Ok(make_transformer_4x3_sse41::<T, GRID_SIZE, BIT_DEPTH>(
-                    dst_layout, lut, options,
-                ));
+            PcsToDevice::RoundTrip(max_delta_e) => {
+                lut = pcs_round_trip_delta_e(&self.dest, &lut, options)?
+                    .into_iter()
+                    .map(|delta_e| if delta_e > *max_delta_e { 1. } else { 0. })
+                    .collect();
             }
-        }
-
-        return Ok(make_transformer_4x3::<T, GRID_SIZE, BIT_DEPTH>(
-            dst_layout, lut, options,
-        ));
-    } else if (source.color_space == DataColorSpace::Rgb
-        || source.color_space == DataColorSpace::Lab)
-        && (dest.color_space == DataColorSpace::Cmyk || dest.color_space == DataColorSpace::Color4)
-    {
-        source.color_space.check_layout(src_layout)?;
-        dest.color_space.check_layout(dst_layout)?;
-        if source.pcs != DataColorSpace::Xyz && source.pcs != DataColorSpace::Lab {
-            return Err(CmsError::UnsupportedProfileConnection);
-        }
-
-        let dest_lut_b_to_a = dest.get_pcs_to_device_lut(options.rendering_intent).ok_or(
-            CmsError::UnsupportedLutRenderingIntent(source.rendering_intent),
-        )?;
-
-        const GRID_SIZE: usize = 33;
-
-        let mut lut: Vec<f32>;
-
-        if source.has_device_to_pcs_lut() {
-            let device_to_pcs = source
-                .get_device_to_pcs(options.rendering_intent)
-                .ok_or(CmsError::UnsupportedProfileConnection)?;
-            lut = create_lut3_samples_norm::<GRID_SIZE>();
-
-            match device_to_pcs {
-                LutWarehouse::Lut(lut_data_type) => {
-                    lut = create_lut3x3(lut_data_type, &lut, options)?;
+            PcsToDevice::Gray(gray_stage) => {
+                if self.dest.pcs == DataColorSpace::Lab {
+                    let lab_to_xyz_stage = StageLabToXyz::default();
+                    lab_to_xyz_stage.transform(&mut lut)?;
                 }
-                LutWarehouse::MCurves(mab) => prepare_mab_3x3(mab, &mut lut, options)?,
+                lut = gray_stage.transform(&lut);
             }
-        } else if source.has_full_colors_triplet() {
-            lut = create_rgb_lin_lut::<T, BIT_DEPTH, LINEAR_CAP, GRID_SIZE>(source, options)?;
-        } else {
-            return Err(CmsError::UnsupportedProfileConnection);
-        }
-
-        pcs_lab_v2_to_v4(source, &mut lut);
-
-        if source.pcs == DataColorSpace::Xyz && dest.pcs == DataColorSpace::Lab {
-            let xyz_to_lab = StageXyzToLab::default();
-            xyz_to_lab.transform(&mut lut)?;
-        } else if source.pcs == DataColorSpace::Lab && dest.pcs == DataColorSpace::Xyz {
-            let lab_to_xyz_stage = StageLabToXyz::default();
-            lab_to_xyz_stage.transform(&mut lut)?;
         }
 
-        pcs_lab_v4_to_v2(dest, &mut lut);
+        dst.copy_from_slice(&lut);
+        Ok(())
+    }
 
-        let lut = create_lut3x4(dest_lut_b_to_a, &lut, options)?;
+    fn make_executor(
+        self: Box<Self>,
+        lut: Vec<f32>,
+    ) -> Box<dyn TransformExecutor<T> + Send + Sync> {
+        make_shaped_executor::<T, BIT_DEPTH>(
+            self.shape,
+            self.src_layout,
+            self.dst_layout,
+            lut,
+            self.options,
+        )
+    }
+}
 
-        return Ok(match src_layout {
+/// Creates executor of given shape from completely sampled grid
+pub(crate) fn make_shaped_executor<
+    T: Copy
+        + Default
+        + AsPrimitive<f32>
+        + Send
+        + Sync
+        + CompressForLut
+        + AsPrimitive<usize>
+        + PointeeSizeExpressible,
+    const BIT_DEPTH: usize,
+>(
+    shape: LutShape,
+    src_layout: Layout,
+    dst_layout: Layout,
+    lut: Vec<f32>,
+    options: TransformOptions,
+) -> Box<dyn TransformExecutor<T> + Send + Sync>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    match shape {
+        LutShape::Lut4x3 => {
+            make_lut4x3_executor::<T, GRID_SIZE_4, BIT_DEPTH>(dst_layout, lut, options)
+        }
+        LutShape::Lut3x4 => match src_layout {
+            Layout::Rgb => {
+                Box::new(
+                    TransformLut3x4::<T, { Layout::Rgb as u8 }, GRID_SIZE_3, BIT_DEPTH> {
+                        lut,
+                        _phantom: PhantomData,
+                        interpolation_method: options.interpolation_method,
+                    },
+                )
+            }
+            Layout::Rgba => {
+                Box::new(
+                    TransformLut3x4::<T, { Layout::Rgba as u8 }, GRID_SIZE_3, BIT_DEPTH> {
+                        lut,
+                        _phantom: PhantomData,
+                        interpolation_method: options.interpolation_method,
+                    },
+                )
+            }
+            Layout::Bgr => {
+                Box::new(
+                    TransformLut3x4::<T, { Layout::Bgr as u8 }, GRID_SIZE_3, BIT_DEPTH> {
+                        lut,
+                        _phantom: PhantomData,
+                        interpolation_method: options.interpolation_method,
+                    },
+                )
+            }
+            Layout::Bgra => {
+                Box::new(
+                    TransformLut3x4::<T, { Layout::Bgra as u8 }, GRID_SIZE_3, BIT_DEPTH> {
+                        lut,
+                        _phantom: PhantomData,
+                        interpolation_method: options.interpolation_method,
+                    },
+                )
+            }
+            Layout::Argb => {
+                Box::new(
+                    TransformLut3x4::<T, { Layout::Argb as u8 }, GRID_SIZE_3, BIT_DEPTH> {
+                        lut,
+                        _phantom: PhantomData,
+                        interpolation_method: options.interpolation_method,
+                    },
+                )
+            }
+            Layout::Abgr => {
+                Box::new(
+                    TransformLut3x4::<T, { Layout::Abgr as u8 }, GRID_SIZE_3, BIT_DEPTH> {
+                        lut,
+                        _phantom: PhantomData,
+                        interpolation_method: options.interpolation_method,
+                    },
+                )
+            }
+            _ => unimplemented!(),
+        },
+        LutShape::Lut3x3 => {
+            make_lut3x3_executor::<T, GRID_SIZE_3, BIT_DEPTH>(src_layout, dst_layout, lut, options)
+        }
+        LutShape::Lut3x1 => match src_layout {
             Layout::Rgb => {
                 Box::new(
-                    TransformLut3x4::<T, { Layout::Rgb as u8 }, GRID_SIZE, BIT_DEPTH> {
+                    TransformLut3x1::<T, { Layout::Rgb as u8 }, GRID_SIZE_3, BIT_DEPTH> {
                         lut,
                         _phantom: PhantomData,
                         interpolation_method: options.interpolation_method,
@@ -648,7 +934,43 @@ where
             }
             Layout::Rgba => {
                 Box::new(
-                    TransformLut3x4::<T, { Layout::Rgba as u8 }, GRID_SIZE, BIT_DEPTH> {
+                    TransformLut3x1::<T, { Layout::Rgba as u8 }, GRID_SIZE_3, BIT_DEPTH> {
+                        lut,
+                        _phantom: PhantomData,
+                        interpolation_method: options.interpolation_method,
+                    },
+                )
+            }
+            Layout::Bgr => {
+                Box::new(
+                    TransformLut3x1::<T, { Layout::Bgr as u8 }, GRID_SIZE_3, BIT_DEPTH> {
+                        lut,
+                        _phantom: PhantomData,
+                        interpolation_method: options.interpolation_method,
+                    },
+                )
+            }
+            Layout::Bgra => {
+                Box::new(
+                    TransformLut3x1::<T, { Layout::Bgra as u8 }, GRID_SIZE_3, BIT_DEPTH> {
+                        lut,
+                        _phantom: PhantomData,
+                        interpolation_method: options.interpolation_method,
+                    },
+                )
+            }
+            Layout::Argb => {
+                Box::new(
+                    TransformLut3x1::<T, { Layout::Argb as u8 }, GRID_SIZE_3, BIT_DEPTH> {
+                        lut,
+                        _phantom: PhantomData,
+                        interpolation_method: options.interpolation_method,
+                    },
+                )
+            }
+            Layout::Abgr => {
+                Box::new(
+                    TransformLut3x1::<T, { Layout::Abgr as u8 }, GRID_SIZE_3, BIT_DEPTH> {
                         lut,
                         _phantom: PhantomData,
                         interpolation_method: options.interpolation_method,
@@ -656,7 +978,215 @@ where
                 )
             }
             _ => unimplemented!(),
-        });
+        },
+        LutShape::Lut4x1 => match dst_layout {
+            Layout::Gray => {
+                Box::new(
+                    TransformLut4x1::<T, { Layout::Gray as u8 }, GRID_SIZE_4, BIT_DEPTH> {
+                        lut,
+                        _phantom: PhantomData,
+                        interpolation_method: options.interpolation_method,
+                    },
+                )
+            }
+            Layout::GrayAlpha => {
+                Box::new(
+                    TransformLut4x1::<T, { Layout::GrayAlpha as u8 }, GRID_SIZE_4, BIT_DEPTH> {
+                        lut,
+                        _phantom: PhantomData,
+                        interpolation_method: options.interpolation_method,
+                    },
+                )
+            }
+            _ => unimplemented!(),
+        },
+        LutShape::Lut4x4 => Box::new(TransformLut4x4::<T, GRID_SIZE_4, BIT_DEPTH> {
+            lut,
+            _phantom: PhantomData,
+            interpolation_method: options.interpolation_method,
+        }),
+    }
+}
+
+pub(crate) fn make_lut4x3_executor<
+    T: Copy
+        + Default
+        + AsPrimitive<f32>
+        + Send
+        + Sync
+        + CompressForLut
+        + AsPrimitive<usize>
+        + PointeeSizeExpressible,
+    const GRID_SIZE: usize,
+    const BIT_DEPTH: usize,
+>(
+    dst_layout: Layout,
+    lut: Vec<f32>,
+    options: TransformOptions,
+) -> Box<dyn TransformExecutor<T> + Send + Sync>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+    if KernelFamily::Neon.is_available() && use_kernel(KernelFamily::Neon) {
+        return make_transformer_4x3_neon::<T, GRID_SIZE, BIT_DEPTH>(dst_layout, lut, options);
+    }
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        #[cfg(feature = "avx")]
+        if use_kernel(KernelFamily::Avx2)
+            && std::arch::is_x86_feature_detected!("avx2")
+            && std::arch::is_x86_feature_detected!("fma")
+        {
+            return make_transformer_4x3_avx_fma::<T, GRID_SIZE, BIT_DEPTH>(
+                dst_layout, lut, options,
+            );
+        }
+        #[cfg(feature = "sse")]
+        if use_kernel(KernelFamily::Sse41) && std::arch::is_x86_feature_detected!("sse4.1") {
+            return make_transformer_4x3_sse41::<T, GRID_SIZE, BIT_DEPTH>(dst_layout, lut, options);
+        }
+    }
+
+    make_transformer_4x3::<T, GRID_SIZE, BIT_DEPTH>(dst_layout, lut, options)
+}
+
+pub(crate) fn make_lut3x3_executor<
+    T: Copy
+        + Default
+        + AsPrimitive<f32>
+        + Send
+        + Sync
+        + CompressForLut
+        + AsPrimitive<usize>
+        + PointeeSizeExpressible,
+    const GRID_SIZE: usize,
+    const BIT_DEPTH: usize,
+>(
+    src_layout: Layout,
+    dst_layout: Layout,
+    lut: Vec<f32>,
+    options: TransformOptions,
+) -> Box<dyn TransformExecutor<T> + Send + Sync>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+    if KernelFamily::Neon.is_available() && use_kernel(KernelFamily::Neon) {
+        return make_transformer_3x3_neon::<T, GRID_SIZE, BIT_DEPTH>(
+            src_layout, dst_layout, lut, options,
+        );
+    }
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        #[cfg(feature = "avx")]
+        if use_kernel(KernelFamily::Avx2)
+            && std::arch::is_x86_feature_detected!("avx2")
+            && std::arch::is_x86_feature_detected!("fma")
+        {
+            return make_transformer_3x3_avx_fma::<T, GRID_SIZE, BIT_DEPTH>(
+                src_layout, dst_layout, lut, options,
+            );
+        }
+        #[cfg(feature = "sse")]
+        if use_kernel(KernelFamily::Sse41) && std::arch::is_x86_feature_detected!("sse4.1") {
+            return make_transformer_3x3_sse41::<T, GRID_SIZE, BIT_DEPTH>(
+                src_layout, dst_layout, lut, options,
+            );
+        }
+    }
+
+    make_transformer_3x3::<T, GRID_SIZE, BIT_DEPTH>(src_layout, dst_layout, lut, options)
+}
+
+/// Prepares grid sampling for LUT based transform, grid is not sampled here.
+pub(crate) fn make_lut_sampler<
+    T: Copy
+        + Default
+        + AsPrimitive<f32>
+        + Send
+        + Sync
+        + CompressForLut
+        + AsPrimitive<usize>
+        + PointeeSizeExpressible
+        + GammaLutInterpolate,
+    const BIT_DEPTH: usize,
+    const LINEAR_CAP: usize,
+    const GAMMA_LUT: usize,
+>(
+    src_layout: Layout,
+    source: &ColorProfile,
+    dst_layout: Layout,
+    dest: &ColorProfile,
+    options: TransformOptions,
+) -> Result<Box<dyn LutSampler<T>>, CmsError>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    make_multi_profile_sampler::<T, BIT_DEPTH, LINEAR_CAP, GAMMA_LUT>(
+        src_layout,
+        source,
+        &[],
+        dst_layout,
+        dest,
+        options.rendering_intent,
+        options,
+    )
+}
+
+/// Prepares grid sampling through chain of profiles.
+///
+/// `abstracts` are Lab or XYZ abstract profiles applied in PCS in given order, source tables
+/// are selected by `options`, destination ones by `dest_intent`.
+pub(crate) fn make_multi_profile_sampler<
+    T: Copy
+        + Default
+        + AsPrimitive<f32>
+        + Send
+        + Sync
+        + CompressForLut
+        + AsPrimitive<usize>
+        + PointeeSizeExpressible
+        + GammaLutInterpolate,
+    const BIT_DEPTH: usize,
+    const LINEAR_CAP: usize,
+    const GAMMA_LUT: usize,
+>(
+    src_layout: Layout,
+    source: &ColorProfile,
+    abstracts: &[(&ColorProfile, RenderingIntent)],
+    dst_layout: Layout,
+    dest: &ColorProfile,
+    dest_intent: RenderingIntent,
+    options: TransformOptions,
+) -> Result<Box<dyn LutSampler<T>>, CmsError>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    let shape = if (source.color_space == DataColorSpace::Cmyk
+        || source.color_space == DataColorSpace::Color4)
+        && (dest.color_space == DataColorSpace::Rgb || dest.color_space == DataColorSpace::Lab)
+    {
+        LutShape::Lut4x3
+    } else if (source.color_space == DataColorSpace::Cmyk
+        || source.color_space == DataColorSpace::Color4)
+        && dest.color_space == DataColorSpace::Gray
+    {
+        LutShape::Lut4x1
+    } else if (source.color_space == DataColorSpace::Cmyk
+        || source.color_space == DataColorSpace::Color4)
+        && (dest.color_space == DataColorSpace::Cmyk || dest.color_space == DataColorSpace::Color4)
+    {
+        LutShape::Lut4x4
+    } else if (source.color_space == DataColorSpace::Rgb
+        || source.color_space == DataColorSpace::Lab)
+        && (dest.color_space == DataColorSpace::Cmyk || dest.color_space == DataColorSpace::Color4)
+    {
+        LutShape::Lut3x4
     } else if (source.color_space == DataColorSpace::Rgb
         || source.color_space == DataColorSpace::Lab
         || source.color_space == DataColorSpace::Color3)
@@ -664,84 +1194,376 @@ where
             || dest.color_space == DataColorSpace::Lab
             || dest.color_space == DataColorSpace::Color3)
     {
-        source.color_space.check_layout(src_layout)?;
-        dest.color_space.check_layout(dst_layout)?;
-
-        const GRID_SIZE: usize = 33;
+        LutShape::Lut3x3
+    } else {
+        return Err(CmsError::UnsupportedProfileConnection);
+    };
 
-        let mut lut: Vec<f32>;
+    source.color_space.check_layout(src_layout)?;
+    dest.color_space.check_layout(dst_layout)?;
+    if source.pcs != DataColorSpace::Xyz && source.pcs != DataColorSpace::Lab {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+    if dest.pcs != DataColorSpace::Lab && dest.pcs != DataColorSpace::Xyz {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+    for (profile, intent) in abstracts {
+        let is_pcs = |x: DataColorSpace| x == DataColorSpace::Lab || x == DataColorSpace::Xyz;
+        if profile.profile_class != ProfileClass::Abstract
+            || !is_pcs(profile.color_space)
+            || !is_pcs(profile.pcs)
+        {
+            return Err(CmsError::UnsupportedProfileConnection);
+        }
+        profile
+            .get_device_to_pcs(*intent)
+            .ok_or(CmsError::UnsupportedLutRenderingIntent(*intent))?;
+    }
 
-        if source.has_device_to_pcs_lut() {
-            let device_to_pcs = source
-                .get_device_to_pcs(options.rendering_intent)
-                .ok_or(CmsError::UnsupportedProfileConnection)?;
-            lut = create_lut3_samples_norm::<GRID_SIZE>();
+    let device_to_pcs = if matches!(
+        shape,
+        LutShape::Lut4x3 | LutShape::Lut4x1 | LutShape::Lut4x4
+    ) {
+        source.get_device_to_pcs(options.rendering_intent).ok_or(
+            CmsError::UnsupportedLutRenderingIntent(source.rendering_intent),
+        )?;
+        DeviceToPcs::Lut4(create_lut4_samples_norm::<GRID_SIZE_4>())
+    } else {
+        make_device_to_pcs3::<T, BIT_DEPTH, LINEAR_CAP>(source, options)?
+    };
 
-            match device_to_pcs {
-                LutWarehouse::Lut(lut_data_type) => {
-                    lut = create_lut3x3(lut_data_type, &lut, options)?;
+    let pcs_to_device = match shape {
+        LutShape::Lut4x3 => {
+            if dest.pcs == DataColorSpace::Xyz {
+                if dest.has_full_colors_triplet() {
+                    PcsToDevice::MatrixShaper(
+                        make_inverse_rgb_xyz_stage::<T, BIT_DEPTH, GAMMA_LUT>(dest, options)?,
+                    )
+                } else {
+                    return Err(CmsError::UnsupportedProfileConnection);
                 }
-                LutWarehouse::MCurves(mab) => prepare_mab_3x3(mab, &mut lut, options)?,
+            } else {
+                dest.get_pcs_to_device(dest_intent)
+                    .ok_or(CmsError::UnsupportedProfileConnection)?;
+                PcsToDevice::Lut3
             }
-        } else if source.has_full_colors_triplet() {
-            lut = create_rgb_lin_lut::<T, BIT_DEPTH, LINEAR_CAP, GRID_SIZE>(source, options)?;
-        } else {
-            return Err(CmsError::UnsupportedProfileConnection);
         }
-
-        pcs_lab_v2_to_v4(source, &mut lut);
-
-        if source.pcs == DataColorSpace::Xyz && dest.pcs == DataColorSpace::Lab {
-            let xyz_to_lab = StageXyzToLab::default();
-            xyz_to_lab.transform(&mut lut)?;
-        } else if source.pcs == DataColorSpace::Lab && dest.pcs == DataColorSpace::Xyz {
-            let lab_to_xyz_stage = StageLabToXyz::default();
-            lab_to_xyz_stage.transform(&mut lut)?;
+        LutShape::Lut3x4 | LutShape::Lut4x4 => {
+            dest.get_pcs_to_device(dest_intent)
+                .ok_or(CmsError::UnsupportedLutRenderingIntent(dest_intent))?;
+            PcsToDevice::Lut4
         }
+        LutShape::Lut3x3 => {
+            if dest.has_pcs_to_device_lut() {
+                dest.get_pcs_to_device(dest_intent)
+                    .ok_or(CmsError::UnsupportedProfileConnection)?;
+                PcsToDevice::Lut3
+            } else if dest.has_full_colors_triplet() {
+                PcsToDevice::MatrixShaper(make_inverse_rgb_xyz_stage::<T, BIT_DEPTH, GAMMA_LUT>(
+                    dest, options,
+                )?)
+            } else {
+                return Err(CmsError::UnsupportedProfileConnection);
+            }
+        }
+        LutShape::Lut4x1 => PcsToDevice::Gray(XyzToGrayStage {
+            gray_gamma: dest.build_gamma_table::<T, 65536, GAMMA_LUT, BIT_DEPTH>(
+                &dest.gray_trc,
+                options.allow_use_cicp_transfer,
+            )?,
+        }),
+        // Gamut samplers are created only via `make_gamut_sampler`
+        LutShape::Lut3x1 => return Err(CmsError::UnsupportedProfileConnection),
+    };
 
-        pcs_lab_v4_to_v2(dest, &mut lut);
+    // Absolute intent falling back to perceptual tables stays perceptual
+    let colorimetric_tables = match device_to_pcs {
+        DeviceToPcs::Lut4(_) | DeviceToPcs::Lut3(_) => source.lut_a_to_b_colorimetric.is_some(),
+        DeviceToPcs::MatrixShaper { .. } => true,
+    } && match pcs_to_device {
+        PcsToDevice::Lut3 | PcsToDevice::Lut4 => dest.lut_b_to_a_colorimetric.is_some(),
+        _ => true,
+    };
+    let absolute_white = if options.rendering_intent == RenderingIntent::AbsoluteColorimetric
+        && colorimetric_tables
+    {
+        let scale = source.absolute_intent_scale(dest);
+        scale
+            .v
+            .iter()
+            .any(|x| (x - 1.).abs() > 1e-4)
+            .then_some(StageAbsoluteWhite {
+                scale,
+                pcs: dest.pcs,
+            })
+    } else {
+        None
+    };
 
-        if dest.has_pcs_to_device_lut() {
-            let pcs_to_device = dest
-                .get_pcs_to_device(options.rendering_intent)
-                .ok_or(CmsError::UnsupportedProfileConnection)?;
-            match pcs_to_device {
-                LutWarehouse::Lut(lut_data_type) => {
-                    lut = create_lut3x3(lut_data_type, &lut, options)?
-                }
-                LutWarehouse::MCurves(mab) => prepare_mba_3x3(mab, &mut lut, options)?,
+    Ok(Box::new(LutSamplingPlan::<
+        T,
+        BIT_DEPTH,
+        LINEAR_CAP,
+        GAMMA_LUT,
+    > {
+        shape,
+        source: source.clone(),
+        dest: dest.clone(),
+        src_layout,
+        dst_layout,
+        options,
+        device_to_pcs,
+        pcs_to_device,
+        abstracts: abstracts
+            .iter()
+            .map(|&(profile, intent)| (profile.clone(), intent))
+            .collect(),
+        dest_intent,
+        absolute_white,
+    }))
+}
+
+/// Evaluates 3 channels `A2B` tables of `profile` over normalized values
+fn device_to_pcs3_lut(
+    profile: &ColorProfile,
+    intent: RenderingIntent,
+    mut lut: Vec<f32>,
+    options: TransformOptions,
+) -> Result<Vec<f32>, CmsError> {
+    let device_to_pcs = profile
+        .get_device_to_pcs(intent)
+        .ok_or(CmsError::UnsupportedProfileConnection)?;
+    let legacy_lab = device_to_pcs.has_legacy_lab_encoding();
+    if profile.color_space == DataColorSpace::Lab && legacy_lab {
+        lab_v4_to_v2(&mut lut);
+    }
+    match device_to_pcs {
+        LutWarehouse::Lut(lut_data_type) => {
+            if profile.color_space == DataColorSpace::Xyz {
+                apply_lut_matrix(lut_data_type, &mut lut)?;
             }
-        } else if dest.has_full_colors_triplet() {
-            prepare_inverse_lut_rgb_xyz::<T, BIT_DEPTH, GAMMA_LUT>(dest, &mut lut, options)?;
-        } else {
-            return Err(CmsError::UnsupportedProfileConnection);
+            lut = create_lut3x3(lut_data_type, &lut, options)?;
         }
+        LutWarehouse::MCurves(mab) => prepare_mab_3x3(mab, &mut lut, options)?,
+    }
+    if profile.pcs == DataColorSpace::Lab && legacy_lab {
+        lab_v2_to_v4(&mut lut);
+    }
+    Ok(lut)
+}
 
-        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-        {
-            #[cfg(feature = "avx")]
-            if std::arch::is_x86_feature_detected!("avx2") && std::is_x86_feature_detected!("fma") {
-                return Ok(make_transformer_3x3_avx_fma::<T, GRID_SIZE, BIT_DEPTH>(
-                    src_layout, dst_layout, lut, options,
-                ));
+/// Converts normalized PCS values between Lab and XYZ when spaces differ
+pub(crate) fn convert_pcs(
+    from: DataColorSpace,
+    to: DataColorSpace,
+    lut: &mut [f32],
+) -> Result<(), CmsError> {
+    if from == DataColorSpace::Xyz && to == DataColorSpace::Lab {
+        let xyz_to_lab = StageXyzToLab::default();
+        xyz_to_lab.transform(lut)?;
+    } else if from == DataColorSpace::Lab && to == DataColorSpace::Xyz {
+        let lab_to_xyz_stage = StageLabToXyz::default();
+        lab_to_xyz_stage.transform(lut)?;
+    }
+    Ok(())
+}
+
+fn make_device_to_pcs3<
+    T: Copy
+        + Default
+        + AsPrimitive<f32>
+        + Send
+        + Sync
+        + CompressForLut
+        + AsPrimitive<usize>
+        + PointeeSizeExpressible,
+    const BIT_DEPTH: usize,
+    const LINEAR_CAP: usize,
+>(
+    source: &ColorProfile,
+    options: TransformOptions,
+) -> Result<DeviceToPcs<T, BIT_DEPTH, LINEAR_CAP>, CmsError>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    if source.has_device_to_pcs_lut() {
+        source
+            .get_device_to_pcs(options.rendering_intent)
+            .ok_or(CmsError::UnsupportedProfileConnection)?;
+        Ok(DeviceToPcs::Lut3(create_lut3_samples_norm::<GRID_SIZE_3>()))
+    } else if source.has_full_colors_triplet() {
+        let (linearization, matrix) =
+            make_rgb_lin_stages::<T, BIT_DEPTH, LINEAR_CAP, GRID_SIZE_3>(source, options)?;
+        Ok(DeviceToPcs::MatrixShaper {
+            origins: create_lut3_samples::<T, GRID_SIZE_3>(),
+            linearization,
+            matrix,
+        })
+    } else {
+        Err(CmsError::UnsupportedProfileConnection)
+    }
+}
+
+/// Prepares grid sampling of `gamt` tag of `printer` for 3 channels `source` device values.
+pub(crate) fn make_gamut_sampler<
+    T: Copy
+        + Default
+        + AsPrimitive<f32>
+        + Send
+        + Sync
+        + CompressForLut
+        + AsPrimitive<usize>
+        + PointeeSizeExpressible
+        + GammaLutInterpolate,
+    const BIT_DEPTH: usize,
+    const LINEAR_CAP: usize,
+    const GAMMA_LUT: usize,
+>(
+    src_layout: Layout,
+    source: &ColorProfile,
+    printer: &ColorProfile,
+    options: TransformOptions,
+) -> Result<Box<dyn LutSampler<T>>, CmsError>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    if source.color_space != DataColorSpace::Rgb
+        && source.color_space != DataColorSpace::Lab
+        && source.color_space != DataColorSpace::Color3
+    {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+    let pcs_to_device = if printer.has_gamut_tag() {
+        PcsToDevice::Gamut
+    } else if options.gamut_round_trip_delta_e != 0
+        && printer.has_device_to_pcs_lut()
+        && printer.has_pcs_to_device_lut()
+    {
+        PcsToDevice::RoundTrip(options.gamut_round_trip_delta_e as f32 / 10.)
+    } else {
+        return Err(CmsError::UnsupportedProfileConnection);
+    };
+    source.color_space.check_layout(src_layout)?;
+    if source.pcs != DataColorSpace::Xyz && source.pcs != DataColorSpace::Lab {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+    if printer.pcs != DataColorSpace::Lab && printer.pcs != DataColorSpace::Xyz {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+
+    let device_to_pcs = make_device_to_pcs3::<T, BIT_DEPTH, LINEAR_CAP>(source, options)?;
+
+    Ok(Box::new(LutSamplingPlan::<
+        T,
+        BIT_DEPTH,
+        LINEAR_CAP,
+        GAMMA_LUT,
+    > {
+        shape: LutShape::Lut3x1,
+        source: source.clone(),
+        dest: printer.clone(),
+        src_layout,
+        dst_layout: Layout::Gray,
+        options,
+        device_to_pcs,
+        pcs_to_device,
+        abstracts: Vec::new(),
+        dest_intent: options.rendering_intent,
+        absolute_white: None,
+    }))
+}
+
+/// Computes CIE ΔE*ab between colors and their B2A → A2B round trip through
+/// relative colorimetric tables of `profile`.
+///
+/// Colors are normalized PCS values of `profile`, as everywhere in LUT pipeline.
+pub(crate) fn pcs_round_trip_delta_e(
+    profile: &ColorProfile,
+    pcs: &[f32],
+    options: TransformOptions,
+) -> Result<Vec<f32>, CmsError> {
+    if profile.pcs != DataColorSpace::Lab && profile.pcs != DataColorSpace::Xyz {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+    let pcs_to_device = profile
+        .get_pcs_to_device(RenderingIntent::RelativeColorimetric)
+        .ok_or(CmsError::UnsupportedProfileConnection)?;
+    let device_to_pcs = profile
+        .get_device_to_pcs(RenderingIntent::RelativeColorimetric)
+        .ok_or(CmsError::UnsupportedProfileConnection)?;
+
+    let mut lut = pcs.to_vec();
+    if profile.pcs == DataColorSpace::Lab && pcs_to_device.has_legacy_lab_encoding() {
+        lab_v4_to_v2(&mut lut);
+    }
+    let device_channels = match pcs_to_device {
+        LutWarehouse::Lut(lut_data_type) => lut_data_type.num_output_channels,
+        LutWarehouse::MCurves(mba) => mba.num_output_channels,
+    };
+    let device = match (pcs_to_device, device_channels) {
+        (LutWarehouse::Lut(lut_data_type), 3) => {
+            if profile.pcs == DataColorSpace::Xyz {
+                apply_lut_matrix(lut_data_type, &mut lut)?;
             }
-            #[cfg(feature = "sse")]
-            if std::arch::is_x86_feature_detected!("sse4.1") {
-                return Ok(make_transformer_3x3_sse41::<T, GRID_SIZE, BIT_DEPTH>(
-                    src_layout, dst_layout, lut, options,
-                ));
+            create_lut3x3(lut_data_type, &lut, options)?
+        }
+        (LutWarehouse::Lut(lut_data_type), 4) => {
+            if profile.pcs == DataColorSpace::Xyz {
+                apply_lut_matrix(lut_data_type, &mut lut)?;
             }
+            create_lut3x4(lut_data_type, &lut, options)?
+        }
+        (LutWarehouse::MCurves(mba), 3) => {
+            prepare_mba_3x3(mba, &mut lut, options)?;
+            lut
         }
+        (LutWarehouse::MCurves(mba), 4) => prepare_mba_3x4(mba, &mut lut, options)?,
+        _ => return Err(CmsError::UnsupportedProfileConnection),
+    };
 
-        return Ok(make_transformer_3x3::<T, GRID_SIZE, BIT_DEPTH>(
-            src_layout, dst_layout, lut, options,
-        ));
+    let mut round_trip = match (device_to_pcs, device_channels) {
+        (LutWarehouse::Lut(lut_data_type), 3) => create_lut3x3(lut_data_type, &device, options)?,
+        (LutWarehouse::Lut(lut_data_type), 4) => create_lut4(lut_data_type, &device, options)?,
+        (LutWarehouse::MCurves(mab), 3) => {
+            let mut device = device;
+            prepare_mab_3x3(mab, &mut device, options)?;
+            device
+        }
+        (LutWarehouse::MCurves(mab), 4) => prepare_mab_4x3(mab, &device, options)?,
+        _ => return Err(CmsError::UnsupportedProfileConnection),
+    };
+    if profile.pcs == DataColorSpace::Lab && device_to_pcs.has_legacy_lab_encoding() {
+        lab_v2_to_v4(&mut round_trip);
     }
 
-    Err(CmsError::UnsupportedProfileConnection)
+    let to_lab = |v: &[f32]| {
+        let lab = if profile.pcs == DataColorSpace::Lab {
+            Lab::new(v[0], v[1], v[2])
+        } else {
+            Lab::from_pcs_xyz(Xyz::new(v[0], v[1], v[2]))
+        };
+        Lab::new(lab.l * 100., lab.a * 255. - 128., lab.b * 255. - 128.)
+    };
+    Ok(pcs
+        .chunks_exact(3)
+        .zip(round_trip.chunks_exact(3))
+        .map(|(src, dst)| to_lab(src).delta_e_76(&to_lab(dst)))
+        .collect())
 }
 
-fn create_rgb_lin_lut<
+/// Samples the whole grid at once and creates executor.
+pub(crate) fn make_lut_transform<T: Copy + Default>(
+    sampler: Box<dyn LutSampler<T>>,
+) -> Result<Box<dyn TransformExecutor<T> + Send + Sync>, CmsError> {
+    let grid_points = sampler.grid_points();
+    let mut lut = vec![0f32; grid_points * sampler.output_channels()];
+    sampler.sample(0, grid_points, &mut lut)?;
+    Ok(sampler.make_executor(lut))
+}
+
+fn make_rgb_lin_stages<
     T: Copy
         + Default
         + AsPrimitive<f32>
@@ -756,13 +1578,17 @@ fn create_rgb_lin_lut<
 >(
     source: &ColorProfile,
     opts: TransformOptions,
-) -> Result<Vec<f32>, CmsError>
+) -> Result<
+    (
+        RgbLinearizationStage<T, BIT_DEPTH, LINEAR_CAP, GRID_SIZE>,
+        MatrixStage,
+    ),
+    CmsError,
+>
 where
     u32: AsPrimitive<T>,
     f32: AsPrimitive<T>,
 {
-    let lut_origins = create_lut3_samples::<T, GRID_SIZE>();
-
     let lin_r =
         source.build_r_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(opts.allow_use_cicp_transfer)?;
     let lin_g =
@@ -777,9 +1603,6 @@ where
         _phantom: PhantomData,
     };
 
-    let mut lut = vec![0f32; lut_origins.len()];
-    lin_stage.transform(&lut_origins, &mut lut)?;
-
     let xyz_to_rgb = source
         .rgb_to_xyz_matrix()
         .ok_or(CmsError::UnsupportedProfileConnection)?;
@@ -796,11 +1619,10 @@ where
     ];
 
     let matrix_stage = MatrixStage { matrices };
-    matrix_stage.transform(&mut lut)?;
-    Ok(lut)
+    Ok((lin_stage, matrix_stage))
 }
 
-fn prepare_inverse_lut_rgb_xyz<
+fn make_inverse_rgb_xyz_stage<
     T: Copy
         + Default
         + AsPrimitive<f32>
@@ -814,9 +1636,8 @@ fn prepare_inverse_lut_rgb_xyz<
     const GAMMA_LUT: usize,
 >(
     dest: &ColorProfile,
-    lut: &mut [f32],
     options: TransformOptions,
-) -> Result<(), CmsError>
+) -> Result<XyzToRgbStage<T, BIT_DEPTH, GAMMA_LUT>, CmsError>
 where
     f32: AsPrimitive<T>,
     u32: AsPrimitive<T>,
@@ -845,12 +1666,10 @@ where
     }];
 
     matrices.push(xyz_to_rgb.to_f32());
-    let xyz_to_rgb_stage = XyzToRgbStage::<T, BIT_DEPTH, GAMMA_LUT> {
+    Ok(XyzToRgbStage::<T, BIT_DEPTH, GAMMA_LUT> {
         r_gamma: gamma_map_r,
         g_gamma: gamma_map_g,
         b_gamma: gamma_map_b,
         matrices,
-    };
-    xyz_to_rgb_stage.transform(lut)?;
-    Ok(())
+    })
 }
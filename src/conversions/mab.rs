@@ -27,9 +27,10 @@
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 use crate::mlaf::mlaf;
+use crate::nd_array::{Array3D, Array4D};
+use crate::transform::{InPlaceStage, Stage};
 use crate::{
-    Array3D, CmsError, InPlaceStage, InterpolationMethod, LutMCurvesType, Matrix3f,
-    TransformOptions, Vector3f,
+    CmsError, InterpolationMethod, LutMCurvesType, Matrix3f, TransformOptions, Vector3f, Vector4f,
 };
 
 struct ACurves3<'a, const DEPTH: usize> {
@@ -235,41 +236,124 @@ impl<const DEPTH: usize> InPlaceStage for BCurves<DEPTH> {
     }
 }
 
-pub(crate) fn prepare_mab_3x3(
-    mab: &LutMCurvesType,
-    lut: &mut [f32],
-    options: TransformOptions,
-) -> Result<(), CmsError> {
-    const LERP_DEPTH: usize = 256;
-    const BP: usize = 8;
-    if mab.num_input_channels != 3 && mab.num_output_channels != 3 {
-        return Err(CmsError::UnsupportedProfileConnection);
+struct ACurves4x3<'a, const DEPTH: usize> {
+    curve0: Box<[f32; DEPTH]>,
+    curve1: Box<[f32; DEPTH]>,
+    curve2: Box<[f32; DEPTH]>,
+    curve3: Box<[f32; DEPTH]>,
+    clut: &'a [f32],
+    grid_size: usize,
+    interpolation_method: InterpolationMethod,
+}
+
+impl<const DEPTH: usize> ACurves4x3<'_, DEPTH> {
+    fn transform_impl<Fetch: Fn(f32, f32, f32, f32) -> Vector3f>(
+        &self,
+        src: &[f32],
+        dst: &mut [f32],
+        fetch: Fetch,
+    ) -> Result<(), CmsError> {
+        let scale_value = (DEPTH - 1) as f32;
+
+        for (src, dst) in src.chunks_exact(4).zip(dst.chunks_exact_mut(3)) {
+            let a0 = (src[0] * scale_value).min(scale_value) as u8;
+            let a1 = (src[1] * scale_value).min(scale_value) as u8;
+            let a2 = (src[2] * scale_value).min(scale_value) as u8;
+            let a3 = (src[3] * scale_value).min(scale_value) as u8;
+            let b0 = self.curve0[a0 as usize];
+            let b1 = self.curve1[a1 as usize];
+            let b2 = self.curve2[a2 as usize];
+            let b3 = self.curve3[a3 as usize];
+            let interpolated = fetch(b0, b1, b2, b3);
+            dst[0] = interpolated.v[0];
+            dst[1] = interpolated.v[1];
+            dst[2] = interpolated.v[2];
+        }
+        Ok(())
     }
-    if mab.a_curves.len() == 3 && !mab.clut.is_empty() {
-        if mab.grid_points[0] != mab.grid_points[1] || mab.grid_points[1] != mab.grid_points[2] {
-            return Err(CmsError::UnsupportedProfileConnection);
+}
+
+impl<const DEPTH: usize> Stage for ACurves4x3<'_, DEPTH> {
+    fn transform(&self, src: &[f32], dst: &mut [f32]) -> Result<(), CmsError> {
+        let lut = Array4D::new(self.clut, self.grid_size);
+        match self.interpolation_method {
+            InterpolationMethod::Tetrahedral => {
+                self.transform_impl(src, dst, |x, y, z, w| lut.tetra(x, y, z, w))?;
+            }
+            InterpolationMethod::Pyramid => {
+                self.transform_impl(src, dst, |x, y, z, w| lut.pyramid(x, y, z, w))?;
+            }
+            InterpolationMethod::Prism => {
+                self.transform_impl(src, dst, |x, y, z, w| lut.prism(x, y, z, w))?;
+            }
+            InterpolationMethod::Linear => {
+                self.transform_impl(src, dst, |x, y, z, w| lut.quadlinear_vec3(x, y, z, w))?;
+            }
         }
-        let curve0 = mab.a_curves[0]
-            .build_linearize_table::<u8, LERP_DEPTH, BP>()
-            .ok_or(CmsError::InvalidTrcCurve)?;
-        let curve1 = mab.a_curves[1]
-            .build_linearize_table::<u8, LERP_DEPTH, BP>()
-            .ok_or(CmsError::InvalidTrcCurve)?;
-        let curve2 = mab.a_curves[2]
-            .build_linearize_table::<u8, LERP_DEPTH, BP>()
-            .ok_or(CmsError::InvalidTrcCurve)?;
-        let clut = &mab.clut;
-        let a_curves = ACurves3::<LERP_DEPTH> {
-            curve0,
-            curve1,
-            curve2,
-            clut,
-            grid_size: mab.grid_points[0] as usize,
-            interpolation_method: options.interpolation_method,
-        };
-        a_curves.transform(lut)?;
+        Ok(())
+    }
+}
+
+struct ACurves3x4Inverse<'a, const DEPTH: usize> {
+    curve0: Box<[f32; DEPTH]>,
+    curve1: Box<[f32; DEPTH]>,
+    curve2: Box<[f32; DEPTH]>,
+    curve3: Box<[f32; DEPTH]>,
+    clut: &'a [f32],
+    grid_size: usize,
+    interpolation_method: InterpolationMethod,
+}
+
+impl<const DEPTH: usize> ACurves3x4Inverse<'_, DEPTH> {
+    fn transform_impl<Fetch: Fn(f32, f32, f32) -> Vector4f>(
+        &self,
+        src: &[f32],
+        dst: &mut [f32],
+        fetch: Fetch,
+    ) -> Result<(), CmsError> {
+        let scale_value = (DEPTH - 1) as f32;
+
+        for (src, dst) in src.chunks_exact(3).zip(dst.chunks_exact_mut(4)) {
+            let interpolated = fetch(src[0], src[1], src[2]);
+            let a0 = (interpolated.v[0] * scale_value).max(0.).min(scale_value) as u8;
+            let a1 = (interpolated.v[1] * scale_value).max(0.).min(scale_value) as u8;
+            let a2 = (interpolated.v[2] * scale_value).max(0.).min(scale_value) as u8;
+            let a3 = (interpolated.v[3] * scale_value).max(0.).min(scale_value) as u8;
+            dst[0] = self.curve0[a0 as usize];
+            dst[1] = self.curve1[a1 as usize];
+            dst[2] = self.curve2[a2 as usize];
+            dst[3] = self.curve3[a3 as usize];
+        }
+        Ok(())
+    }
+}
+
+impl<const DEPTH: usize> Stage for ACurves3x4Inverse<'_, DEPTH> {
+    fn transform(&self, src: &[f32], dst: &mut [f32]) -> Result<(), CmsError> {
+        let lut = Array3D::new(self.clut, self.grid_size);
+        match self.interpolation_method {
+            InterpolationMethod::Tetrahedral => {
+                self.transform_impl(src, dst, |x, y, z| lut.tetra_vec4(x, y, z))?;
+            }
+            InterpolationMethod::Pyramid => {
+                self.transform_impl(src, dst, |x, y, z| lut.pyramid_vec4(x, y, z))?;
+            }
+            InterpolationMethod::Prism => {
+                self.transform_impl(src, dst, |x, y, z| lut.prism_vec4(x, y, z))?;
+            }
+            InterpolationMethod::Linear => {
+                self.transform_impl(src, dst, |x, y, z| lut.trilinear_vec4(x, y, z))?;
+            }
+        }
+        Ok(())
     }
+}
+
+const LERP_DEPTH: usize = 256;
+const BP: usize = 8;
 
+/// Applies "M" curves, matrix and "B" curves of mAB in the device to PCS direction
+fn prepare_mab_pcs_stages(mab: &LutMCurvesType, lut: &mut [f32]) -> Result<(), CmsError> {
     if mab.m_curves.len() == 3 {
         let curve0 = mab.m_curves[0]
             .build_linearize_table::<u8, LERP_DEPTH, BP>()
@@ -316,17 +400,8 @@ pub(crate) fn prepare_mab_3x3(
     Ok(())
 }
 
-pub(crate) fn prepare_mba_3x3(
-    mab: &LutMCurvesType,
-    lut: &mut [f32],
-    options: TransformOptions,
-) -> Result<(), CmsError> {
-    if mab.num_input_channels != 3 && mab.num_output_channels != 3 {
-        return Err(CmsError::UnsupportedProfileConnection);
-    }
-    const LERP_DEPTH: usize = 256;
-    const BP: usize = 8;
-
+/// Applies "B" curves, matrix and "M" curves of mBA in the PCS to device direction
+fn prepare_mba_pcs_stages(mab: &LutMCurvesType, lut: &mut [f32]) -> Result<(), CmsError> {
     if mab.b_curves.len() == 3 {
         let curve0 = mab.b_curves[0]
             .build_linearize_table::<u8, LERP_DEPTH, BP>()
@@ -370,6 +445,115 @@ pub(crate) fn prepare_mba_3x3(
         m_curves.transform(lut)?;
     }
 
+    Ok(())
+}
+
+/// Checks that CLUT has uniform grid over `inputs` channels and expected size
+fn uniform_clut_grid(
+    mab: &LutMCurvesType,
+    inputs: usize,
+    outputs: usize,
+) -> Result<usize, CmsError> {
+    let grid_size = mab.grid_points[0];
+    if mab.grid_points[..inputs].iter().any(|&x| x != grid_size) {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+    if grid_size < 2 || mab.clut.len() != (grid_size as usize).pow(inputs as u32) * outputs {
+        return Err(CmsError::InvalidAtoBLut);
+    }
+    Ok(grid_size as usize)
+}
+
+pub(crate) fn prepare_mab_3x3(
+    mab: &LutMCurvesType,
+    lut: &mut [f32],
+    options: TransformOptions,
+) -> Result<(), CmsError> {
+    if mab.num_input_channels != 3 && mab.num_output_channels != 3 {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+    if mab.a_curves.len() == 3 && !mab.clut.is_empty() {
+        if mab.grid_points[0] != mab.grid_points[1] || mab.grid_points[1] != mab.grid_points[2] {
+            return Err(CmsError::UnsupportedProfileConnection);
+        }
+        let curve0 = mab.a_curves[0]
+            .build_linearize_table::<u8, LERP_DEPTH, BP>()
+            .ok_or(CmsError::InvalidTrcCurve)?;
+        let curve1 = mab.a_curves[1]
+            .build_linearize_table::<u8, LERP_DEPTH, BP>()
+            .ok_or(CmsError::InvalidTrcCurve)?;
+        let curve2 = mab.a_curves[2]
+            .build_linearize_table::<u8, LERP_DEPTH, BP>()
+            .ok_or(CmsError::InvalidTrcCurve)?;
+        let clut = &mab.clut;
+        let a_curves = ACurves3::<LERP_DEPTH> {
+            curve0,
+            curve1,
+            curve2,
+            clut,
+            grid_size: mab.grid_points[0] as usize,
+            interpolation_method: options.interpolation_method,
+        };
+        a_curves.transform(lut)?;
+    }
+
+    prepare_mab_pcs_stages(mab, lut)
+}
+
+/// Evaluates 4 channels mAB (A curves, CLUT, M curves, matrix, B curves) over `src` samples
+pub(crate) fn prepare_mab_4x3(
+    mab: &LutMCurvesType,
+    src: &[f32],
+    options: TransformOptions,
+) -> Result<Vec<f32>, CmsError> {
+    if mab.num_input_channels != 4 || mab.num_output_channels != 3 {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+    // There is no way to reduce 4 channels to PCS without CLUT
+    if mab.a_curves.len() != 4 || mab.clut.is_empty() {
+        return Err(CmsError::InvalidAtoBLut);
+    }
+    let grid_size = uniform_clut_grid(mab, 4, 3)?;
+
+    let curve0 = mab.a_curves[0]
+        .build_linearize_table::<u8, LERP_DEPTH, BP>()
+        .ok_or(CmsError::InvalidTrcCurve)?;
+    let curve1 = mab.a_curves[1]
+        .build_linearize_table::<u8, LERP_DEPTH, BP>()
+        .ok_or(CmsError::InvalidTrcCurve)?;
+    let curve2 = mab.a_curves[2]
+        .build_linearize_table::<u8, LERP_DEPTH, BP>()
+        .ok_or(CmsError::InvalidTrcCurve)?;
+    let curve3 = mab.a_curves[3]
+        .build_linearize_table::<u8, LERP_DEPTH, BP>()
+        .ok_or(CmsError::InvalidTrcCurve)?;
+    let a_curves = ACurves4x3::<LERP_DEPTH> {
+        curve0,
+        curve1,
+        curve2,
+        curve3,
+        clut: &mab.clut,
+        grid_size,
+        interpolation_method: options.interpolation_method,
+    };
+    let mut lut = vec![0f32; (src.len() / 4) * 3];
+    a_curves.transform(src, &mut lut)?;
+
+    prepare_mab_pcs_stages(mab, &mut lut)?;
+    Ok(lut)
+}
+
+pub(crate) fn prepare_mba_3x3(
+    mab: &LutMCurvesType,
+    lut: &mut [f32],
+    options: TransformOptions,
+) -> Result<(), CmsError> {
+    if mab.num_input_channels != 3 && mab.num_output_channels != 3 {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+
+    prepare_mba_pcs_stages(mab, lut)?;
+
     if mab.a_curves.len() == 3 && !mab.clut.is_empty() {
         if mab.grid_points[0] != mab.grid_points[1] || mab.grid_points[1] != mab.grid_points[2] {
             return Err(CmsError::UnsupportedProfileConnection);
@@ -397,3 +581,268 @@ pub(crate) fn prepare_mba_3x3(
 
     Ok(())
 }
+
+/// Evaluates 4 channels mBA (B curves, matrix, M curves, CLUT, A curves) over PCS `lut`
+pub(crate) fn prepare_mba_3x4(
+    mab: &LutMCurvesType,
+    lut: &mut [f32],
+    options: TransformOptions,
+) -> Result<Vec<f32>, CmsError> {
+    if mab.num_input_channels != 3 || mab.num_output_channels != 4 {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+    // There is no way to expand PCS to 4 channels without CLUT
+    if mab.a_curves.len() != 4 || mab.clut.is_empty() {
+        return Err(CmsError::InvalidAtoBLut);
+    }
+    let grid_size = uniform_clut_grid(mab, 3, 4)?;
+
+    prepare_mba_pcs_stages(mab, lut)?;
+
+    let curve0 = mab.a_curves[0]
+        .build_linearize_table::<u8, LERP_DEPTH, BP>()
+        .ok_or(CmsError::InvalidTrcCurve)?;
+    let curve1 = mab.a_curves[1]
+        .build_linearize_table::<u8, LERP_DEPTH, BP>()
+        .ok_or(CmsError::InvalidTrcCurve)?;
+    let curve2 = mab.a_curves[2]
+        .build_linearize_table::<u8, LERP_DEPTH, BP>()
+        .ok_or(CmsError::InvalidTrcCurve)?;
+    let curve3 = mab.a_curves[3]
+        .build_linearize_table::<u8, LERP_DEPTH, BP>()
+        .ok_or(CmsError::InvalidTrcCurve)?;
+    let a_curves = ACurves3x4Inverse::<LERP_DEPTH> {
+        curve0,
+        curve1,
+        curve2,
+        curve3,
+        clut: &mab.clut,
+        grid_size,
+        interpolation_method: options.interpolation_method,
+    };
+    let mut dst = vec![0f32; (lut.len() / 3) * 4];
+    a_curves.transform(lut, &mut dst)?;
+    Ok(dst)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::profile::LutDataType;
+    use crate::{
+        ColorProfile, DataColorSpace, InterpolationMethod, Layout, LutMCurvesType, LutType,
+        LutWarehouse, Matrix3f, ToneReprCurve, TransformOptions, Vector3f,
+    };
+
+    fn identity_curves(count: usize) -> Vec<ToneReprCurve> {
+        vec![ToneReprCurve::Parametric(vec![1.0]); count]
+    }
+
+    fn lab_identity_profile() -> ColorProfile {
+        let mut clut_table = Vec::new();
+        for x in 0..2 {
+            for y in 0..2 {
+                for z in 0..2 {
+                    clut_table.extend_from_slice(&[x as f32, y as f32, z as f32]);
+                }
+            }
+        }
+        let lut = LutWarehouse::Lut(LutDataType {
+            num_input_channels: 3,
+            num_output_channels: 3,
+            num_clut_grid_points: 2,
+            matrix: Matrix3f::IDENTITY,
+            num_input_table_entries: 2,
+            num_output_table_entries: 2,
+            input_table: vec![0., 1., 0., 1., 0., 1.],
+            clut_table,
+            output_table: vec![0., 1., 0., 1., 0., 1.],
+            lut_type: LutType::Lut16,
+        });
+        ColorProfile {
+            color_space: DataColorSpace::Lab,
+            pcs: DataColorSpace::Lab,
+            lut_a_to_b_perceptual: Some(lut.clone()),
+            lut_b_to_a_perceptual: Some(lut),
+            ..Default::default()
+        }
+    }
+
+    // CLUT contents are affine, so any interpolation over them is exact
+    fn mab_clut(c: f32, m: f32, y: f32, k: f32) -> [f32; 3] {
+        [
+            1. - 0.2 * c - 0.2 * m - 0.2 * y - 0.4 * k,
+            0.5 + 0.3 * c - 0.3 * m,
+            0.5 + 0.3 * y - 0.15 * c - 0.15 * m,
+        ]
+    }
+
+    fn mba_clut(l: f32, a: f32, b: f32) -> [f32; 4] {
+        [
+            0.2 + 0.6 * a - 0.2 * l,
+            0.8 - 0.6 * a,
+            0.1 + 0.7 * b,
+            1. - l,
+        ]
+    }
+
+    const MAB_MATRIX: Matrix3f = Matrix3f {
+        v: [[0.5, 0., 0.], [0.1, 0.8, 0.], [0., 0., 0.8]],
+    };
+    const MAB_BIAS: Vector3f = Vector3f {
+        v: [0.25, 0.05, 0.1],
+    };
+    const MBA_MATRIX: Matrix3f = Matrix3f {
+        v: [[0.8, 0., 0.], [0., 0.8, 0.], [0., 0.1, 0.8]],
+    };
+    const MBA_BIAS: Vector3f = Vector3f {
+        v: [0.1, 0.1, 0.05],
+    };
+
+    fn cmyk_mab_profile() -> ColorProfile {
+        let mut a2b = Vec::new();
+        for c in 0..2 {
+            for m in 0..2 {
+                for y in 0..2 {
+                    for k in 0..2 {
+                        a2b.extend_from_slice(&mab_clut(c as f32, m as f32, y as f32, k as f32));
+                    }
+                }
+            }
+        }
+        let mut b2a = Vec::new();
+        for l in 0..2 {
+            for a in 0..2 {
+                for b in 0..2 {
+                    b2a.extend_from_slice(&mba_clut(l as f32, a as f32, b as f32));
+                }
+            }
+        }
+        let mut a2b_grid = [0u8; 16];
+        a2b_grid[..4].fill(2);
+        let mut b2a_grid = [0u8; 16];
+        b2a_grid[..3].fill(2);
+        ColorProfile {
+            color_space: DataColorSpace::Cmyk,
+            pcs: DataColorSpace::Lab,
+            lut_a_to_b_perceptual: Some(LutWarehouse::MCurves(LutMCurvesType {
+                num_input_channels: 4,
+                num_output_channels: 3,
+                grid_points: a2b_grid,
+                clut: a2b,
+                a_curves: identity_curves(4),
+                b_curves: identity_curves(3),
+                m_curves: identity_curves(3),
+                matrix: MAB_MATRIX,
+                bias: MAB_BIAS,
+            })),
+            lut_b_to_a_perceptual: Some(LutWarehouse::MCurves(LutMCurvesType {
+                num_input_channels: 3,
+                num_output_channels: 4,
+                grid_points: b2a_grid,
+                clut: b2a,
+                a_curves: identity_curves(4),
+                b_curves: identity_curves(3),
+                m_curves: identity_curves(3),
+                matrix: MBA_MATRIX,
+                bias: MBA_BIAS,
+            })),
+            ..Default::default()
+        }
+    }
+
+    fn apply_matrix(m: Matrix3f, bias: Vector3f, v: [f32; 3]) -> [f32; 3] {
+        let mut r = [0f32; 3];
+        for (i, r) in r.iter_mut().enumerate() {
+            *r = bias.v[i] + m.v[i][0] * v[0] + m.v[i][1] * v[1] + m.v[i][2] * v[2];
+        }
+        r
+    }
+
+    #[test]
+    fn test_mab_cmyk_all_stages() {
+        // Round trip through the encoder to check that all stages survive parsing
+        let encoded = cmyk_mab_profile().encode().unwrap();
+        let cmyk = ColorProfile::new_from_slice(&encoded).unwrap();
+        match cmyk.lut_a_to_b_perceptual.as_ref() {
+            Some(LutWarehouse::MCurves(mab)) => {
+                assert_eq!(mab.a_curves.len(), 4);
+                assert_eq!(mab.m_curves.len(), 3);
+                assert_eq!(mab.b_curves.len(), 3);
+                assert_eq!(mab.clut.len(), 16 * 3);
+                assert!(mab.matrix.test_equality(MAB_MATRIX));
+            }
+            _ => panic!("mAB tag must be decoded as MCurves"),
+        }
+        match cmyk.lut_b_to_a_perceptual.as_ref() {
+            Some(LutWarehouse::MCurves(mba)) => {
+                assert_eq!(mba.a_curves.len(), 4);
+                assert_eq!(mba.m_curves.len(), 3);
+                assert_eq!(mba.b_curves.len(), 3);
+                assert_eq!(mba.clut.len(), 8 * 4);
+                assert!(mba.matrix.test_equality(MBA_MATRIX));
+            }
+            _ => panic!("mBA tag must be decoded as MCurves"),
+        }
+
+        let lab = lab_identity_profile();
+        let options = TransformOptions {
+            interpolation_method: InterpolationMethod::Tetrahedral,
+            ..Default::default()
+        };
+        // 256 entries curves are sampled by truncation, allow few steps
+        const TOLERANCE: f32 = 3. / 255.;
+
+        let mut src = Vec::new();
+        for c in [0f32, 0.2, 0.45, 0.7, 1.] {
+            for m in [0f32, 0.35, 0.8] {
+                for y in [0f32, 0.5, 1.] {
+                    for k in [0f32, 0.3, 0.9] {
+                        src.extend_from_slice(&[c, m, y, k]);
+                    }
+                }
+            }
+        }
+        let mut dst = vec![0f32; src.len() / 4 * 3];
+        cmyk.create_transform_f32(Layout::Rgba, &lab, Layout::Rgb, options)
+            .unwrap()
+            .transform(&src, &mut dst)
+            .unwrap();
+        for (src, dst) in src.chunks_exact(4).zip(dst.chunks_exact(3)) {
+            let expected = apply_matrix(
+                MAB_MATRIX,
+                MAB_BIAS,
+                mab_clut(src[0], src[1], src[2], src[3]),
+            );
+            for (&v, &e) in dst.iter().zip(expected.iter()) {
+                assert!(
+                    (v - e).abs() <= TOLERANCE,
+                    "CMYK {src:?} -> {dst:?}, expected {expected:?}"
+                );
+            }
+        }
+
+        let mut src = Vec::new();
+        for l in [0f32, 0.25, 0.6, 1.] {
+            for a in [0f32, 0.4, 0.75, 1.] {
+                for b in [0f32, 0.5, 0.9] {
+                    src.extend_from_slice(&[l, a, b]);
+                }
+            }
+        }
+        let mut dst = vec![0f32; src.len() / 3 * 4];
+        lab.create_transform_f32(Layout::Rgb, &cmyk, Layout::Rgba, options)
+            .unwrap()
+            .transform(&src, &mut dst)
+            .unwrap();
+        for (src, dst) in src.chunks_exact(3).zip(dst.chunks_exact(4)) {
+            let pcs = apply_matrix(MBA_MATRIX, MBA_BIAS, [src[0], src[1], src[2]]);
+            let expected = mba_clut(pcs[0], pcs[1], pcs[2]);
+            for (&v, &e) in dst.iter().zip(expected.iter()) {
+                assert!(
+                    (v - e).abs() <= TOLERANCE,
+                    "Lab {src:?} -> {dst:?}, expected {expected:?}"
+                );
+            }
+        }
+    }
+}
@@ -26,29 +26,118 @@
  * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
+
+/// Resolves runtime source and destination layouts into const generic ones.
+///
+/// Expands `$make!(SrcLayout, DstLayout, $args)` for the matching pair
+/// out of the listed variants, anything else evaluates `$fallback`.
+macro_rules! dispatch_layouts {
+    ($src:expr => [$($s:ident),+], $dst:expr => $d:tt, $fallback:expr, $make:ident $args:tt) => {
+        match $src {
+            $(Layout::$s => dispatch_layouts!(@dst $s, $dst => $d, $fallback, $make $args),)+
+            #[allow(unreachable_patterns)]
+            _ => $fallback,
+        }
+    };
+    (@dst $s:ident, $dst:expr => [$($d:ident),+], $fallback:expr, $make:ident $args:tt) => {
+        match $dst {
+            $(Layout::$d => $make!($s, $d, $args),)+
+            #[allow(unreachable_patterns)]
+            _ => $fallback,
+        }
+    };
+}
+
 #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "avx"))]
 mod avx;
 mod bpc;
+mod custom_stage;
+mod device_link;
 mod gray2rgb;
+mod gray_depth;
 mod interpolator;
+mod lut3x1;
 mod lut3x3;
 mod lut3x4;
 mod lut4;
+mod lut_nchannel;
 mod lut_transforms;
 mod mab;
-#[cfg(all(target_arch = "aarch64", target_feature = "neon", feature = "neon"))]
+#[cfg(all(target_arch = "aarch64", feature = "neon"))]
 mod neon;
+mod premultiplied_alpha;
+mod qcms_compat;
 mod rgb2gray;
+mod rgb_trc;
 mod rgbxyz;
 mod rgbxyz_fixed;
+mod rgbxyz_float;
+mod rgbxyz_lazy;
+mod rgbxyz_mask;
 #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "sse"))]
 mod sse;
+mod transform_lut3_to_1;
 mod transform_lut3_to_3;
 mod transform_lut3_to_4;
+mod transform_lut4_to_1;
 mod transform_lut4_to_4;
+mod vcgt_epilogue;
+mod ycbcr_epilogue;
 
+pub(crate) use custom_stage::make_custom_stage_transform;
+pub(crate) use device_link::make_device_link_sampler;
+pub(crate) use gray_depth::{GrayDepthTrc, make_gray_depth_transform};
 pub(crate) use gray2rgb::make_gray_to_x;
-pub(crate) use lut_transforms::{CompressForLut, make_lut_transform};
+pub(crate) use lut_nchannel::make_nchannel_transform;
+pub(crate) use lut_transforms::{
+    CompressForLut, LutSampler, make_gamut_sampler, make_lut_sampler, make_lut_transform,
+    make_lut3x3_executor, make_lut4x3_executor, make_multi_profile_sampler, pcs_round_trip_delta_e,
+};
+pub(crate) use lut3x1::create_lut3x1;
+pub(crate) use premultiplied_alpha::{
+    wrap_premultiplied_alpha, wrap_premultiplied_alpha_depth, wrap_premultiplied_gamut_check,
+};
+pub(crate) use qcms_compat::{QCMS_GAMMA_LUT, make_qcms_profile_transform};
+pub(crate) use rgb_trc::{is_trc_only_transform, make_rgb_trc_transform};
 pub(crate) use rgb2gray::{ToneReproductionRgbToGray, make_rgb_to_gray};
 pub(crate) use rgbxyz::RgbXyzFactory;
 pub(crate) use rgbxyz::TransformProfileRgb;
+pub(crate) use rgbxyz::has_q4_12_simd_kernel;
+pub(crate) use rgbxyz::make_rgb_xyz_rgb_transform;
+pub(crate) use rgbxyz_float::TransformProfileRgbFloat;
+pub(crate) use rgbxyz_lazy::{
+    LazyGammaTable, TransformProfileRgbLazy, make_rgb_xyz_rgb_transform_lazy,
+};
+pub(crate) use rgbxyz_mask::make_rgb_xyz_gamut_check;
+pub(crate) use vcgt_epilogue::append_vcgt_epilogue;
+pub(crate) use ycbcr_epilogue::append_ycbcr_epilogue;
+
+/// Samples in-place transforms stage at once.
+///
+/// Common multiple of every [crate::Layout] channel count and of SIMD register widths,
+/// so a chunk always holds whole pixels.
+pub(crate) const WORKING_LANES: usize = 768;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Layout;
+
+    #[test]
+    fn test_working_lanes_hold_whole_pixels() {
+        for layout in [
+            Layout::Rgb,
+            Layout::Rgba,
+            Layout::Gray,
+            Layout::GrayAlpha,
+            Layout::Bgr,
+            Layout::Bgra,
+            Layout::Argb,
+            Layout::Abgr,
+        ] {
+            assert_eq!(WORKING_LANES % layout.channels(), 0, "{layout:?}");
+        }
+        // u16 lanes of AVX2 and NEON/SSE registers
+        assert_eq!(WORKING_LANES % 16, 0);
+    }
+}
@@ -33,8 +33,9 @@ use crate::conversions::neon::interpolator::{
     TrilinearNeonDouble,
 };
 use crate::conversions::neon::stages::NeonAlignedF32;
-use crate::transform::PointeeSizeExpressible;
-use crate::{CmsError, InterpolationMethod, Layout, TransformExecutor, rounding_div_ceil};
+use crate::math::rounding_div_ceil;
+use crate::transform::{PointeeSizeExpressible, touch_pages};
+use crate::{CmsError, InterpolationMethod, Layout, TransformExecutor};
 use num_traits::AsPrimitive;
 use std::arch::aarch64::*;
 #[cfg(target_arch = "x86")]
@@ -141,6 +142,10 @@ where
     f32: AsPrimitive<T>,
     u32: AsPrimitive<T>,
 {
+    fn warmup(&self) {
+        touch_pages(&self.lut);
+    }
+
     fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
         let cn = Layout::from(LAYOUT);
         let channels = cn.channels();
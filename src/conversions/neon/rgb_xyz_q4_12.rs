@@ -65,6 +65,10 @@ impl<
 where
     u32: AsPrimitive<T>,
 {
+    fn warmup(&self) {
+        self.profile.warmup();
+    }
+
     fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
         let src_cn = Layout::from(SRC_LAYOUT);
         let dst_cn = Layout::from(DST_LAYOUT);
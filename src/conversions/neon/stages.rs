@@ -35,6 +35,7 @@ use std::arch::aarch64::*;
 #[repr(align(16), C)]
 pub(crate) struct NeonAlignedU16([u16; 8]);
 
+#[derive(Copy, Clone)]
 #[repr(align(16), C)]
 pub(crate) struct NeonAlignedF32(pub(crate) [f32; 4]);
 
@@ -61,6 +62,10 @@ impl<
 where
     u32: AsPrimitive<T>,
 {
+    fn warmup(&self) {
+        self.profile.warmup();
+    }
+
     fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
         let src_cn = Layout::from(SRC_LAYOUT);
         let dst_cn = Layout::from(DST_LAYOUT);
@@ -31,7 +31,7 @@ use crate::conversions::lut_transforms::Lut3x3Factory;
 use crate::conversions::neon::interpolator::*;
 use crate::conversions::neon::interpolator::{NeonMdInterpolation, PyramidalNeon};
 use crate::conversions::neon::stages::NeonAlignedF32;
-use crate::transform::PointeeSizeExpressible;
+use crate::transform::{PointeeSizeExpressible, touch_pages};
 use crate::{CmsError, InterpolationMethod, Layout, TransformExecutor};
 use num_traits::AsPrimitive;
 use std::arch::aarch64::*;
@@ -127,6 +127,10 @@ where
     f32: AsPrimitive<T>,
     u32: AsPrimitive<T>,
 {
+    fn warmup(&self) {
+        touch_pages(&self.lut);
+    }
+
     fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
         let src_cn = Layout::from(SRC_LAYOUT);
         let src_channels = src_cn.channels();
@@ -0,0 +1,392 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::conversions::LutSampler;
+use crate::conversions::ycbcr_epilogue::EPILOGUE_CHUNK;
+use crate::transform::{PointeeSizeExpressible, TransformPlan};
+use crate::{
+    CmsError, GamutCheck8BitExecutor, GamutCheckExecutor, Layout, TransformDepthExecutor,
+    TransformExecutor,
+};
+use num_traits::AsPrimitive;
+
+/// Divides color channels by alpha, color of fully transparent pixels becomes zero
+#[inline(always)]
+fn unpremultiply<T: Copy + PointeeSizeExpressible + AsPrimitive<f32> + AsPrimitive<usize>>(
+    buf: &mut [T],
+    layout: Layout,
+    max_value: u32,
+) where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    let Some(a_i) = layout.try_a_i() else {
+        return;
+    };
+    for pixel in buf.chunks_exact_mut(layout.channels()) {
+        let alpha = pixel[a_i];
+        for (i, v) in pixel.iter_mut().enumerate() {
+            if i == a_i {
+                continue;
+            }
+            *v = if T::FINITE {
+                let a: usize = alpha.as_();
+                let c: usize = (*v).as_();
+                let max = max_value as usize;
+                match a {
+                    0 => 0u32.as_(),
+                    _ => (((c * max + a / 2) / a).min(max) as u32).as_(),
+                }
+            } else {
+                let a: f32 = alpha.as_();
+                let c: f32 = (*v).as_();
+                if a > 0. { c / a } else { 0. }.as_()
+            };
+        }
+    }
+}
+
+/// Multiplies color channels by alpha
+#[inline(always)]
+fn premultiply<T: Copy + PointeeSizeExpressible + AsPrimitive<f32> + AsPrimitive<usize>>(
+    buf: &mut [T],
+    layout: Layout,
+    max_value: u32,
+) where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    let Some(a_i) = layout.try_a_i() else {
+        return;
+    };
+    for pixel in buf.chunks_exact_mut(layout.channels()) {
+        let alpha = pixel[a_i];
+        for (i, v) in pixel.iter_mut().enumerate() {
+            if i == a_i {
+                continue;
+            }
+            *v = if T::FINITE {
+                let a: usize = alpha.as_();
+                let c: usize = (*v).as_();
+                let max = max_value as usize;
+                (((c * a + max / 2) / max) as u32).as_()
+            } else {
+                let a: f32 = alpha.as_();
+                let c: f32 = (*v).as_();
+                (c * a).as_()
+            };
+        }
+    }
+}
+
+/// Runs inner executor on straight alpha, reading and writing premultiplied one.
+struct TransformPremultipliedAlpha<T> {
+    executor: Box<dyn TransformExecutor<T> + Send + Sync>,
+    src_layout: Layout,
+    dst_layout: Layout,
+    /// Source carries alpha to divide by, otherwise source is read as is
+    unpremultiply: bool,
+    /// Destination carries alpha to multiply by, otherwise straight color is written
+    premultiply: bool,
+    max_value: u32,
+}
+
+impl<
+    T: Copy + Default + PointeeSizeExpressible + AsPrimitive<f32> + AsPrimitive<usize> + Send + Sync,
+> TransformExecutor<T> for TransformPremultipliedAlpha<T>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    fn warmup(&self) {
+        self.executor.warmup();
+    }
+
+    fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
+        let src_channels = self.src_layout.channels();
+        let dst_channels = self.dst_layout.channels();
+        if src.len() % src_channels != 0 || dst.len() % dst_channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if src.len() / src_channels != dst.len() / dst_channels {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        let mut straight = if self.unpremultiply {
+            vec![T::default(); src.len().min(EPILOGUE_CHUNK * src_channels)]
+        } else {
+            Vec::new()
+        };
+        for (src, dst) in src
+            .chunks(EPILOGUE_CHUNK * src_channels)
+            .zip(dst.chunks_mut(EPILOGUE_CHUNK * dst_channels))
+        {
+            if self.unpremultiply {
+                let straight = &mut straight[..src.len()];
+                straight.copy_from_slice(src);
+                unpremultiply(straight, self.src_layout, self.max_value);
+                self.executor.transform(straight, dst)?;
+            } else {
+                self.executor.transform(src, dst)?;
+            }
+            if self.premultiply {
+                premultiply(dst, self.dst_layout, self.max_value);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Forwards sampling and wraps the final executor.
+struct PremultipliedAlphaSampler<T: Copy + Default> {
+    sampler: Box<dyn LutSampler<T>>,
+    src_layout: Layout,
+    dst_layout: Layout,
+    unpremultiply: bool,
+    premultiply: bool,
+    max_value: u32,
+}
+
+impl<
+    T: Copy
+        + Default
+        + PointeeSizeExpressible
+        + AsPrimitive<f32>
+        + AsPrimitive<usize>
+        + Send
+        + Sync
+        + 'static,
+> LutSampler<T> for PremultipliedAlphaSampler<T>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    fn grid_points(&self) -> usize {
+        self.sampler.grid_points()
+    }
+
+    fn output_channels(&self) -> usize {
+        self.sampler.output_channels()
+    }
+
+    fn sample(&self, start: usize, end: usize, dst: &mut [f32]) -> Result<(), CmsError> {
+        self.sampler.sample(start, end, dst)
+    }
+
+    fn make_executor(
+        self: Box<Self>,
+        lut: Vec<f32>,
+    ) -> Box<dyn TransformExecutor<T> + Send + Sync> {
+        Box::new(TransformPremultipliedAlpha {
+            executor: self.sampler.make_executor(lut),
+            src_layout: self.src_layout,
+            dst_layout: self.dst_layout,
+            unpremultiply: self.unpremultiply,
+            premultiply: self.premultiply,
+            max_value: self.max_value,
+        })
+    }
+}
+
+/// Makes planned transform read and write premultiplied alpha.
+///
+/// `src_alpha` and `dst_alpha` tell whether the last channel of corresponding
+/// layout is alpha and not a color channel, as K of CMYK is.
+pub(crate) fn wrap_premultiplied_alpha<
+    T: Copy
+        + Default
+        + PointeeSizeExpressible
+        + AsPrimitive<f32>
+        + AsPrimitive<usize>
+        + Send
+        + Sync
+        + 'static,
+    const BIT_DEPTH: usize,
+>(
+    plan: TransformPlan<T>,
+    src_layout: Layout,
+    src_alpha: bool,
+    dst_layout: Layout,
+    dst_alpha: bool,
+) -> TransformPlan<T>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    let unpremultiply = src_alpha && src_layout.has_alpha();
+    let premultiply = dst_alpha && dst_layout.has_alpha();
+    if !unpremultiply && !premultiply {
+        return plan;
+    }
+    let max_value = if T::FINITE {
+        (1u32 << BIT_DEPTH) - 1
+    } else {
+        1
+    };
+    match plan {
+        TransformPlan::Ready(executor) => {
+            TransformPlan::Ready(Box::new(TransformPremultipliedAlpha {
+                executor,
+                src_layout,
+                dst_layout,
+                unpremultiply,
+                premultiply,
+                max_value,
+            }))
+        }
+        TransformPlan::Lut(sampler) => TransformPlan::Lut(Box::new(PremultipliedAlphaSampler {
+            sampler,
+            src_layout,
+            dst_layout,
+            unpremultiply,
+            premultiply,
+            max_value,
+        })),
+    }
+}
+
+/// Runs inner depth converting executor on straight alpha, reading and writing premultiplied one.
+struct TransformDepthPremultipliedAlpha<S, D> {
+    executor: Box<dyn TransformDepthExecutor<S, D> + Send + Sync>,
+    src_layout: Layout,
+    dst_layout: Layout,
+    src_max_value: u32,
+    dst_max_value: u32,
+}
+
+impl<
+    S: Copy + Default + PointeeSizeExpressible + AsPrimitive<f32> + AsPrimitive<usize>,
+    D: Copy + Default + PointeeSizeExpressible + AsPrimitive<f32> + AsPrimitive<usize>,
+> TransformDepthExecutor<S, D> for TransformDepthPremultipliedAlpha<S, D>
+where
+    f32: AsPrimitive<S> + AsPrimitive<D>,
+    u32: AsPrimitive<S> + AsPrimitive<D>,
+{
+    fn transform(&self, src: &[S], dst: &mut [D]) -> Result<(), CmsError> {
+        let src_channels = self.src_layout.channels();
+        let dst_channels = self.dst_layout.channels();
+        if src.len() % src_channels != 0 || dst.len() % dst_channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if src.len() / src_channels != dst.len() / dst_channels {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        let mut straight = vec![S::default(); src.len().min(EPILOGUE_CHUNK * src_channels)];
+        for (src, dst) in src
+            .chunks(EPILOGUE_CHUNK * src_channels)
+            .zip(dst.chunks_mut(EPILOGUE_CHUNK * dst_channels))
+        {
+            let straight = &mut straight[..src.len()];
+            straight.copy_from_slice(src);
+            unpremultiply(straight, self.src_layout, self.src_max_value);
+            self.executor.transform(straight, dst)?;
+            premultiply(dst, self.dst_layout, self.dst_max_value);
+        }
+        Ok(())
+    }
+}
+
+/// Makes gray depth converting transform read and write premultiplied alpha.
+pub(crate) fn wrap_premultiplied_alpha_depth<
+    S: Copy
+        + Default
+        + PointeeSizeExpressible
+        + AsPrimitive<f32>
+        + AsPrimitive<usize>
+        + Send
+        + Sync
+        + 'static,
+    D: Copy
+        + Default
+        + PointeeSizeExpressible
+        + AsPrimitive<f32>
+        + AsPrimitive<usize>
+        + Send
+        + Sync
+        + 'static,
+    const SRC_BIT_DEPTH: usize,
+    const DST_BIT_DEPTH: usize,
+>(
+    executor: Box<dyn TransformDepthExecutor<S, D> + Send + Sync>,
+    src_layout: Layout,
+    dst_layout: Layout,
+) -> Box<dyn TransformDepthExecutor<S, D> + Send + Sync>
+where
+    f32: AsPrimitive<S> + AsPrimitive<D>,
+    u32: AsPrimitive<S> + AsPrimitive<D>,
+{
+    if !src_layout.has_alpha() && !dst_layout.has_alpha() {
+        return executor;
+    }
+    Box::new(TransformDepthPremultipliedAlpha {
+        executor,
+        src_layout,
+        dst_layout,
+        src_max_value: (1u32 << SRC_BIT_DEPTH) - 1,
+        dst_max_value: (1u32 << DST_BIT_DEPTH) - 1,
+    })
+}
+
+/// Checks gamut of straight colors of premultiplied alpha pixels.
+struct PremultipliedGamutCheck {
+    check: Box<GamutCheck8BitExecutor>,
+    layout: Layout,
+}
+
+impl GamutCheckExecutor<u8> for PremultipliedGamutCheck {
+    fn check(&self, src: &[u8], mask: &mut [u8]) -> Result<(), CmsError> {
+        let channels = self.layout.channels();
+        if src.len() % channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if src.len() / channels != mask.len() {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        let mut straight = vec![0u8; src.len().min(EPILOGUE_CHUNK * channels)];
+        for (src, mask) in src
+            .chunks(EPILOGUE_CHUNK * channels)
+            .zip(mask.chunks_mut(EPILOGUE_CHUNK))
+        {
+            let straight = &mut straight[..src.len()];
+            straight.copy_from_slice(src);
+            unpremultiply(straight, self.layout, 255);
+            self.check.check(straight, mask)?;
+        }
+        Ok(())
+    }
+}
+
+/// Makes 8 bit gamut check read premultiplied alpha pixels of `layout`.
+pub(crate) fn wrap_premultiplied_gamut_check(
+    check: Box<GamutCheck8BitExecutor>,
+    layout: Layout,
+) -> Box<GamutCheck8BitExecutor> {
+    if !layout.has_alpha() {
+        return check;
+    }
+    Box::new(PremultipliedGamutCheck { check, layout })
+}
@@ -0,0 +1,648 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+//! Table construction replicating qcms matrix-shaper path with precached output,
+//! as Firefox runs it, see [crate::CompatibilityMode::Qcms].
+use crate::conversions::TransformProfileRgb;
+use crate::{CmsError, ColorProfile, Matrix3f, PointeeSizeExpressible, ToneReprCurve};
+use num_traits::AsPrimitive;
+
+/// Size of qcms precached output table
+pub(crate) const QCMS_PRECACHE_SIZE: usize = 8192;
+/// Gamma table size for executors, index `8192` only receives values that qcms clamps
+/// to `8191/8192`, so it repeats the last entry
+pub(crate) const QCMS_GAMMA_LUT: usize = QCMS_PRECACHE_SIZE + 1;
+
+const PRECACHE_OUTPUT_MAX: u32 = QCMS_PRECACHE_SIZE as u32 - 1;
+
+/// Parametric curve in unified 7 parameters form
+struct QcmsParam {
+    g: f32,
+    a: f32,
+    b: f32,
+    c: f32,
+    d: f32,
+    e: f32,
+    f: f32,
+}
+
+impl QcmsParam {
+    fn new(params: &[f32]) -> Result<QcmsParam, CmsError> {
+        let g = params[0];
+        Ok(match params[1..] {
+            [] => QcmsParam {
+                g,
+                a: 1.,
+                b: 0.,
+                c: 1.,
+                d: 0.,
+                e: 0.,
+                f: 0.,
+            },
+            [a, b] => QcmsParam {
+                g,
+                a,
+                b,
+                c: 0.,
+                d: -b / a,
+                e: 0.,
+                f: 0.,
+            },
+            [a, b, c] => QcmsParam {
+                g,
+                a,
+                b,
+                c: 0.,
+                d: -b / a,
+                e: c,
+                f: c,
+            },
+            [a, b, c, d] => QcmsParam {
+                g,
+                a,
+                b,
+                c,
+                d,
+                e: 0.,
+                f: 0.,
+            },
+            [a, b, c, d, e, f] => QcmsParam {
+                g,
+                a,
+                b,
+                c,
+                d,
+                e,
+                f,
+            },
+            _ => return Err(CmsError::InvalidTrcCurve),
+        })
+    }
+
+    fn eval(&self, x: f32) -> f32 {
+        if x < self.d {
+            self.c * x + self.f
+        } else {
+            (self.a * x + self.b).powf(self.g) + self.e
+        }
+    }
+}
+
+/// Clamps into [0, 1], NaN becomes 0
+fn clamp_float(a: f32) -> f32 {
+    if a > 1. {
+        1.
+    } else if a >= 0. {
+        a
+    } else {
+        0.
+    }
+}
+
+fn lut_interp_linear(input_value: f64, table: &[u16]) -> f32 {
+    let input_value = input_value * (table.len() - 1) as f64;
+    let upper = input_value.ceil() as usize;
+    let lower = input_value.floor() as usize;
+    let value = (table[upper] as f64 * (1. - (upper as f64 - input_value))
+        + table[lower] as f64 * (upper as f64 - input_value)) as f32;
+    value * (1.0 / 65535.0)
+}
+
+fn lut_interp_linear16(input_value: u16, table: &[u16]) -> u16 {
+    let value = input_value as u32 * (table.len() as u32 - 1);
+    let upper = value.div_ceil(65535);
+    let lower = value / 65535;
+    let interp = value % 65535;
+    ((table[upper as usize] as u32 * interp + table[lower as usize] as u32 * (65535 - interp))
+        / 65535) as u16
+}
+
+/// Inverse lookup with binary search, as lcms 1 does
+fn lut_inverse_interp16(value: u16, table: &[u16]) -> u16 {
+    let mut l: i32 = 1;
+    let mut r: i32 = 0x10000;
+    let mut x: i32 = 0;
+    let length = table.len() as i32;
+
+    let mut num_zeroes: i32 = 0;
+    while table[num_zeroes as usize] == 0 && num_zeroes < length - 1 {
+        num_zeroes += 1;
+    }
+    if num_zeroes == 0 && value == 0 {
+        return 0;
+    }
+    let mut num_poles: i32 = 0;
+    while table[(length - 1 - num_poles) as usize] == 0xffff && num_poles < length - 1 {
+        num_poles += 1;
+    }
+    if num_zeroes > 1 || num_poles > 1 {
+        if value == 0 {
+            return 0;
+        }
+        if num_zeroes > 1 {
+            l = (num_zeroes - 1) * 0xffff / (length - 1) - 1;
+        }
+        if num_poles > 1 {
+            r = (length - 1 - num_poles) * 0xffff / (length - 1) + 1;
+        }
+    }
+    if r <= l {
+        return 0;
+    }
+    while r > l {
+        x = (l + r) / 2;
+        let res = lut_interp_linear16((x - 1) as u16, table) as i32;
+        if res == value as i32 {
+            return (x - 1) as u16;
+        }
+        if res > value as i32 {
+            r = x - 1;
+        } else {
+            l = x + 1;
+        }
+    }
+
+    let val2 = (length - 1) as f64 * ((x - 1) as f64 / 65535.);
+    let cell0 = val2.floor() as i32;
+    let cell1 = val2.ceil() as i32;
+    if cell0 == cell1 {
+        return x as u16;
+    }
+    let y0 = table[cell0 as usize] as f64;
+    let x0 = 65535. * cell0 as f64 / (length - 1) as f64;
+    let y1 = table[cell1 as usize] as f64;
+    let x1 = 65535. * cell1 as f64 / (length - 1) as f64;
+    let a = (y1 - y0) / (x1 - x0);
+    let b = y0 - a * x0;
+    if a.abs() < 0.01 {
+        return x as u16;
+    }
+    let f = (value as f64 - b) / a;
+    if f < 0. {
+        return 0;
+    }
+    if f >= 65535. {
+        return 0xffff;
+    }
+    (f + 0.5).floor() as u16
+}
+
+fn invert_lut(table: &[u16], out_length: usize) -> Vec<u16> {
+    (0..out_length)
+        .map(|i| {
+            let x = i as f64 * 65535. / (out_length - 1) as f64;
+            lut_inverse_interp16((x + 0.5).floor() as u16, table)
+        })
+        .collect()
+}
+
+fn u8_fixed8_to_float(x: u16) -> f32 {
+    x as f32 / 256.
+}
+
+/// Linearization sampled at 256 points
+fn input_table(trc: &ToneReprCurve) -> Result<Box<[f32; 256]>, CmsError> {
+    let mut table = Box::new([0f32; 256]);
+    match trc {
+        ToneReprCurve::Parametric(params) => {
+            let param = QcmsParam::new(params)?;
+            for (i, dst) in table.iter_mut().enumerate() {
+                *dst = clamp_float(param.eval(i as f32 / 255.));
+            }
+        }
+        ToneReprCurve::Lut(data) => {
+            for (i, dst) in table.iter_mut().enumerate() {
+                *dst = match data.len() {
+                    0 => (i as f64 / 255.) as f32,
+                    1 => (i as f64 / 255.).powf(u8_fixed8_to_float(data[0]) as f64) as f32,
+                    _ => lut_interp_linear(i as f64 / 255., data),
+                };
+            }
+        }
+    }
+    Ok(table)
+}
+
+fn precache_lut_entry(input_value: u32, table: &[u16]) -> u8 {
+    let value = input_value * (table.len() as u32 - 1);
+    let upper = value.div_ceil(PRECACHE_OUTPUT_MAX);
+    let lower = value / PRECACHE_OUTPUT_MAX;
+    let interp = value % PRECACHE_OUTPUT_MAX;
+    let mut value = table[upper as usize] as u32 * interp
+        + table[lower as usize] as u32 * (PRECACHE_OUTPUT_MAX - interp);
+    value += PRECACHE_OUTPUT_MAX * 65535 / 255 / 2;
+    value /= PRECACHE_OUTPUT_MAX * 65535 / 255;
+    value as u8
+}
+
+/// Encoding precached into 8192 entries
+fn output_table(trc: &ToneReprCurve) -> Result<Box<[u8; QCMS_PRECACHE_SIZE]>, CmsError> {
+    let mut output = Box::new([0u8; QCMS_PRECACHE_SIZE]);
+    let inverted = match trc {
+        ToneReprCurve::Parametric(params) => {
+            let param = QcmsParam::new(params)?;
+            let table = (0..256)
+                .map(|i| (clamp_float(param.eval(i as f32 / 255.)) * 65535.) as u16)
+                .collect::<Vec<_>>();
+            invert_lut(&table, 256)
+        }
+        ToneReprCurve::Lut(data) => match data.len() {
+            0 => {
+                for (v, dst) in output.iter_mut().enumerate() {
+                    *dst = (v / (QCMS_PRECACHE_SIZE / 256)) as u8;
+                }
+                return Ok(output);
+            }
+            1 => {
+                let gamma = 1. / u8_fixed8_to_float(data[0]);
+                for (v, dst) in output.iter_mut().enumerate() {
+                    *dst = (255. * (v as f32 / PRECACHE_OUTPUT_MAX as f32).powf(gamma)) as u8;
+                }
+                return Ok(output);
+            }
+            _ => invert_lut(data, data.len().max(256)),
+        },
+    };
+    for (v, dst) in output.iter_mut().enumerate() {
+        *dst = precache_lut_entry(v as u32, &inverted);
+    }
+    Ok(output)
+}
+
+fn colorant_matrix(profile: &ColorProfile) -> [[f32; 3]; 3] {
+    let (r, g, b) = (
+        profile.red_colorant,
+        profile.green_colorant,
+        profile.blue_colorant,
+    );
+    [[r.x, g.x, b.x], [r.y, g.y, b.y], [r.z, g.z, b.z]]
+}
+
+/// Inverse through adjugate, products are widened to double as qcms does
+fn invert(m: [[f32; 3]; 3]) -> Option<[[f32; 3]; 3]> {
+    const A: [usize; 3] = [2, 2, 1];
+    const B: [usize; 3] = [1, 0, 0];
+    let det =
+        m[0][0] * m[1][1] * m[2][2] + m[0][1] * m[1][2] * m[2][0] + m[0][2] * m[1][0] * m[2][1]
+            - m[0][0] * m[1][2] * m[2][1]
+            - m[0][1] * m[1][0] * m[2][2]
+            - m[0][2] * m[1][1] * m[2][0];
+    if det == 0. {
+        return None;
+    }
+    let det = 1. / det;
+    let mut dst = [[0f32; 3]; 3];
+    for (j, row) in dst.iter_mut().enumerate() {
+        for (i, dst) in row.iter_mut().enumerate() {
+            let (ai, aj, bi, bj) = (A[i], A[j], B[i], B[j]);
+            let mut p = (m[ai][aj] * m[bi][bj] - m[ai][bj] * m[bi][aj]) as f64;
+            if (i + j) & 1 != 0 {
+                p = -p;
+            }
+            *dst = (det as f64 * p) as f32;
+        }
+    }
+    Some(dst)
+}
+
+fn multiply(a: [[f32; 3]; 3], b: [[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let mut dst = [[0f32; 3]; 3];
+    for (dy, row) in dst.iter_mut().enumerate() {
+        for (dx, dst) in row.iter_mut().enumerate() {
+            let mut v = 0f64;
+            for o in 0..3 {
+                v += (a[dy][o] * b[o][dx]) as f64;
+            }
+            *dst = v as f32;
+        }
+    }
+    dst
+}
+
+/// Builds tables and combined colorant matrix of matrix-shaper pair the way qcms does.
+///
+/// Gamma tables hold [QCMS_GAMMA_LUT] meaningful entries.
+pub(crate) fn make_qcms_profile_transform<T: Copy + Default + PointeeSizeExpressible + 'static>(
+    source: &ColorProfile,
+    dest: &ColorProfile,
+) -> Result<TransformProfileRgb<T, 256>, CmsError>
+where
+    u32: AsPrimitive<T>,
+{
+    let input = |trc: &Option<ToneReprCurve>| {
+        input_table(trc.as_ref().ok_or(CmsError::BuildTransferFunction)?)
+    };
+    let output = |trc: &Option<ToneReprCurve>| -> Result<Box<[T; 65536]>, CmsError> {
+        let precache = output_table(trc.as_ref().ok_or(CmsError::BuildTransferFunction)?)?;
+        let mut table = Box::new([T::default(); 65536]);
+        for (dst, &src) in table.iter_mut().zip(precache.iter()) {
+            *dst = (src as u32).as_();
+        }
+        table[QCMS_PRECACHE_SIZE] = table[QCMS_PRECACHE_SIZE - 1];
+        Ok(table)
+    };
+
+    let out_matrix = invert(colorant_matrix(dest)).ok_or(CmsError::DivisionByZero)?;
+    let matrix = multiply(out_matrix, colorant_matrix(source));
+    if matrix.iter().flatten().any(|x| x.is_nan()) {
+        return Err(CmsError::DivisionByZero);
+    }
+
+    Ok(TransformProfileRgb {
+        r_linear: input(&source.red_trc)?,
+        g_linear: input(&source.green_trc)?,
+        b_linear: input(&source.blue_trc)?,
+        r_gamma: output(&dest.red_trc)?,
+        g_gamma: output(&dest.green_trc)?,
+        b_gamma: output(&dest.blue_trc)?,
+        adaptation_matrix: Some(Matrix3f { v: matrix }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ColorProfile, CompatibilityMode, Layout, TransformOptions};
+
+    const LEVELS: [u8; 6] = [0, 31, 96, 160, 223, 255];
+
+    const SRGB_TO_DISPLAY_P3: [u8; 648] = [
+        0, 0, 0, 0, 0, 29, 0, 0, 92, 0, 0, 153, 0, 0, 214, 0, 0, 245, 8, 30, 3, 8, 30, 31, 8, 30,
+        92, 8, 30, 154, 8, 30, 214, 8, 30, 245, 39, 94, 23, 39, 94, 40, 39, 94, 95, 39, 94, 155,
+        39, 94, 215, 39, 94, 246, 71, 158, 44, 71, 158, 55, 71, 158, 102, 71, 158, 159, 71, 158,
+        217, 71, 158, 248, 101, 220, 65, 101, 220, 73, 101, 220, 111, 101, 220, 164, 101, 220, 221,
+        101, 220, 251, 117, 251, 76, 117, 251, 82, 117, 251, 117, 117, 251, 168, 117, 251, 224,
+        117, 251, 253, 27, 2, 1, 27, 2, 30, 27, 2, 92, 27, 2, 153, 27, 2, 214, 27, 2, 245, 31, 31,
+        4, 31, 31, 31, 31, 31, 92, 31, 31, 154, 31, 31, 214, 31, 31, 245, 50, 95, 23, 50, 95, 40,
+        50, 95, 95, 50, 95, 155, 50, 95, 215, 50, 95, 246, 77, 158, 45, 77, 158, 55, 77, 158, 102,
+        77, 158, 159, 77, 158, 218, 77, 158, 248, 105, 220, 66, 105, 220, 73, 105, 220, 111, 105,
+        220, 165, 105, 220, 221, 105, 220, 251, 120, 251, 76, 120, 251, 82, 120, 251, 117, 120,
+        251, 168, 120, 251, 224, 120, 251, 253, 87, 13, 6, 87, 13, 32, 87, 13, 93, 87, 13, 154, 87,
+        13, 214, 87, 13, 245, 88, 35, 10, 88, 35, 33, 88, 35, 93, 88, 35, 154, 88, 35, 214, 88, 35,
+        245, 96, 96, 26, 96, 96, 42, 96, 96, 96, 96, 96, 156, 96, 96, 215, 96, 96, 246, 111, 158,
+        46, 111, 158, 56, 111, 158, 102, 111, 158, 159, 111, 158, 218, 111, 158, 248, 131, 220, 67,
+        131, 220, 74, 131, 220, 112, 131, 220, 165, 131, 220, 222, 131, 220, 251, 143, 252, 77,
+        143, 252, 83, 143, 252, 118, 143, 252, 169, 143, 252, 224, 143, 252, 253, 146, 28, 17, 146,
+        28, 37, 146, 28, 94, 146, 28, 155, 146, 28, 215, 146, 28, 245, 147, 44, 20, 147, 44, 38,
+        147, 44, 95, 147, 44, 155, 147, 44, 215, 147, 44, 246, 151, 99, 32, 151, 99, 46, 151, 99,
+        98, 151, 99, 156, 151, 99, 216, 151, 99, 246, 160, 160, 50, 160, 160, 59, 160, 160, 104,
+        160, 160, 160, 160, 160, 218, 160, 160, 248, 173, 221, 69, 173, 221, 76, 173, 221, 113,
+        173, 221, 166, 173, 221, 222, 173, 221, 252, 182, 253, 79, 182, 253, 85, 182, 253, 119,
+        182, 253, 169, 182, 253, 225, 182, 253, 254, 204, 43, 29, 204, 43, 44, 204, 43, 97, 204,
+        43, 156, 204, 43, 216, 204, 43, 246, 205, 55, 31, 205, 55, 45, 205, 55, 97, 205, 55, 156,
+        205, 55, 216, 205, 55, 246, 208, 104, 40, 208, 104, 51, 208, 104, 100, 208, 104, 158, 208,
+        104, 217, 208, 104, 247, 214, 163, 55, 214, 163, 64, 214, 163, 106, 214, 163, 161, 214,
+        163, 219, 214, 163, 249, 223, 223, 73, 223, 223, 79, 223, 223, 115, 223, 223, 167, 223,
+        223, 223, 223, 223, 252, 229, 254, 82, 229, 254, 88, 229, 254, 121, 229, 254, 171, 229,
+        254, 226, 229, 254, 255, 234, 51, 35, 234, 51, 48, 234, 51, 99, 234, 51, 157, 234, 51, 216,
+        234, 51, 247, 234, 61, 36, 234, 61, 49, 234, 61, 99, 234, 61, 157, 234, 61, 216, 234, 61,
+        247, 237, 107, 44, 237, 107, 55, 237, 107, 102, 237, 107, 159, 237, 107, 217, 237, 107,
+        248, 242, 164, 58, 242, 164, 66, 242, 164, 108, 242, 164, 162, 242, 164, 220, 242, 164,
+        250, 250, 224, 75, 250, 224, 81, 250, 224, 117, 250, 224, 168, 250, 224, 224, 250, 224,
+        253, 255, 255, 84, 255, 255, 90, 255, 255, 122, 255, 255, 171, 255, 255, 226, 255, 255,
+        255,
+    ];
+
+    const DISPLAY_P3_TO_SRGB: [u8; 648] = [
+        0, 0, 0, 0, 0, 33, 0, 0, 100, 0, 0, 167, 0, 0, 232, 0, 0, 255, 0, 32, 0, 0, 32, 31, 0, 32,
+        100, 0, 32, 167, 0, 32, 232, 0, 32, 255, 0, 98, 0, 0, 98, 17, 0, 98, 97, 0, 98, 165, 0, 98,
+        231, 0, 98, 255, 0, 163, 0, 0, 163, 0, 0, 163, 89, 0, 163, 161, 0, 163, 229, 0, 163, 255,
+        0, 227, 0, 0, 227, 0, 0, 227, 75, 0, 227, 155, 0, 227, 225, 0, 227, 255, 0, 255, 0, 0, 255,
+        0, 0, 255, 63, 0, 255, 151, 0, 255, 222, 0, 255, 255, 35, 0, 0, 35, 0, 32, 35, 0, 100, 35,
+        0, 167, 35, 0, 232, 35, 0, 255, 31, 31, 0, 31, 31, 31, 31, 31, 100, 31, 31, 167, 31, 31,
+        232, 31, 31, 255, 0, 98, 0, 0, 98, 17, 0, 98, 97, 0, 98, 165, 0, 98, 231, 0, 98, 255, 0,
+        163, 0, 0, 163, 0, 0, 163, 89, 0, 163, 161, 0, 163, 229, 0, 163, 255, 0, 227, 0, 0, 227, 0,
+        0, 227, 75, 0, 227, 155, 0, 227, 225, 0, 227, 255, 0, 255, 0, 0, 255, 0, 0, 255, 63, 0,
+        255, 150, 0, 255, 222, 0, 255, 255, 106, 0, 0, 106, 0, 30, 106, 0, 100, 106, 0, 166, 106,
+        0, 232, 106, 0, 255, 105, 24, 0, 105, 24, 28, 105, 24, 99, 105, 24, 166, 105, 24, 232, 105,
+        24, 255, 96, 96, 0, 96, 96, 12, 96, 96, 96, 96, 96, 165, 96, 96, 231, 96, 96, 255, 72, 162,
+        0, 72, 162, 0, 72, 162, 88, 72, 162, 161, 72, 162, 229, 72, 162, 255, 0, 226, 0, 0, 226, 0,
+        0, 226, 74, 0, 226, 155, 0, 226, 225, 0, 226, 255, 0, 255, 0, 0, 255, 0, 0, 255, 62, 0,
+        255, 150, 0, 255, 222, 0, 255, 255, 175, 0, 0, 175, 0, 22, 175, 0, 98, 175, 0, 166, 175, 0,
+        232, 175, 0, 255, 175, 0, 0, 175, 0, 20, 175, 0, 97, 175, 0, 165, 175, 0, 231, 175, 0, 255,
+        170, 92, 0, 170, 92, 0, 170, 92, 94, 170, 92, 164, 170, 92, 230, 170, 92, 255, 160, 160, 0,
+        160, 160, 0, 160, 160, 86, 160, 160, 160, 160, 160, 228, 160, 160, 255, 141, 225, 0, 141,
+        225, 0, 141, 225, 71, 141, 225, 154, 141, 225, 224, 141, 225, 255, 125, 255, 0, 125, 255,
+        0, 125, 255, 58, 125, 255, 149, 125, 255, 221, 125, 255, 255, 244, 0, 0, 244, 0, 2, 244, 0,
+        95, 244, 0, 164, 244, 0, 231, 244, 0, 255, 244, 0, 0, 244, 0, 0, 244, 0, 94, 244, 0, 164,
+        244, 0, 230, 244, 0, 255, 241, 85, 0, 241, 85, 0, 241, 85, 91, 241, 85, 162, 241, 85, 229,
+        241, 85, 255, 234, 157, 0, 234, 157, 0, 234, 157, 83, 234, 157, 158, 234, 157, 227, 234,
+        157, 255, 223, 223, 0, 223, 223, 0, 223, 223, 67, 223, 223, 152, 223, 223, 223, 223, 223,
+        255, 215, 255, 0, 215, 255, 0, 215, 255, 53, 215, 255, 147, 215, 255, 220, 215, 255, 255,
+        255, 0, 0, 255, 0, 0, 255, 0, 93, 255, 0, 163, 255, 0, 230, 255, 0, 255, 255, 0, 0, 255, 0,
+        0, 255, 0, 92, 255, 0, 163, 255, 0, 230, 255, 0, 255, 255, 80, 0, 255, 80, 0, 255, 80, 89,
+        255, 80, 161, 255, 80, 229, 255, 80, 255, 255, 154, 0, 255, 154, 0, 255, 154, 80, 255, 154,
+        157, 255, 154, 226, 255, 154, 255, 255, 222, 0, 255, 222, 0, 255, 222, 64, 255, 222, 151,
+        255, 222, 222, 255, 222, 255, 255, 255, 0, 255, 255, 0, 255, 255, 49, 255, 255, 146, 255,
+        255, 220, 255, 255, 255,
+    ];
+
+    const ADOBE_RGB_TO_SRGB: [u8; 648] = [
+        0, 0, 0, 0, 0, 26, 0, 0, 98, 0, 0, 165, 0, 0, 228, 0, 0, 255, 0, 25, 0, 0, 25, 25, 0, 25,
+        98, 0, 25, 165, 0, 25, 228, 0, 25, 255, 0, 96, 0, 0, 96, 16, 0, 96, 96, 0, 96, 164, 0, 96,
+        227, 0, 96, 255, 0, 161, 0, 0, 161, 0, 0, 161, 92, 0, 161, 161, 0, 161, 226, 0, 161, 255,
+        0, 224, 0, 0, 224, 0, 0, 224, 84, 0, 224, 158, 0, 224, 224, 0, 224, 255, 0, 255, 0, 0, 255,
+        0, 0, 255, 79, 0, 255, 156, 0, 255, 222, 0, 255, 255, 31, 0, 0, 31, 0, 26, 31, 0, 98, 31,
+        0, 165, 31, 0, 228, 31, 0, 255, 25, 25, 0, 25, 25, 25, 25, 25, 98, 25, 25, 165, 25, 25,
+        228, 25, 25, 255, 0, 96, 0, 0, 96, 16, 0, 96, 96, 0, 96, 164, 0, 96, 227, 0, 96, 255, 0,
+        161, 0, 0, 161, 0, 0, 161, 92, 0, 161, 161, 0, 161, 226, 0, 161, 255, 0, 224, 0, 0, 224, 0,
+        0, 224, 84, 0, 224, 158, 0, 224, 224, 0, 224, 255, 0, 255, 0, 0, 255, 0, 0, 255, 79, 0,
+        255, 156, 0, 255, 222, 0, 255, 255, 112, 0, 0, 112, 0, 26, 112, 0, 98, 112, 0, 165, 112, 0,
+        228, 112, 0, 255, 111, 25, 0, 111, 25, 25, 111, 25, 98, 111, 25, 165, 111, 25, 228, 111,
+        25, 255, 96, 96, 0, 96, 96, 16, 96, 96, 96, 96, 96, 164, 96, 96, 227, 96, 96, 255, 39, 161,
+        0, 39, 161, 0, 39, 161, 92, 39, 161, 161, 39, 161, 226, 39, 161, 255, 0, 224, 0, 0, 224, 0,
+        0, 224, 84, 0, 224, 158, 0, 224, 224, 0, 224, 255, 0, 255, 0, 0, 255, 0, 0, 255, 79, 0,
+        255, 156, 0, 255, 222, 0, 255, 255, 188, 0, 0, 188, 0, 26, 188, 0, 98, 188, 0, 165, 188, 0,
+        228, 188, 0, 255, 187, 25, 0, 187, 25, 25, 187, 25, 98, 187, 25, 165, 187, 25, 228, 187,
+        25, 255, 180, 96, 0, 180, 96, 16, 180, 96, 96, 180, 96, 164, 180, 96, 227, 180, 96, 255,
+        161, 161, 0, 161, 161, 0, 161, 161, 92, 161, 161, 161, 161, 161, 226, 161, 161, 255, 125,
+        224, 0, 125, 224, 0, 125, 224, 84, 125, 224, 158, 125, 224, 224, 125, 224, 255, 90, 255, 0,
+        90, 255, 0, 90, 255, 79, 90, 255, 156, 90, 255, 222, 90, 255, 255, 255, 0, 0, 255, 0, 26,
+        255, 0, 98, 255, 0, 165, 255, 0, 228, 255, 0, 255, 255, 25, 0, 255, 25, 25, 255, 25, 98,
+        255, 25, 165, 255, 25, 228, 255, 25, 255, 254, 96, 0, 254, 96, 16, 254, 96, 96, 254, 96,
+        164, 254, 96, 227, 254, 96, 255, 243, 161, 0, 243, 161, 0, 243, 161, 92, 243, 161, 161,
+        243, 161, 226, 243, 161, 255, 224, 224, 0, 224, 224, 0, 224, 224, 84, 224, 224, 158, 224,
+        224, 224, 224, 224, 255, 210, 255, 0, 210, 255, 0, 210, 255, 79, 210, 255, 156, 210, 255,
+        222, 210, 255, 255, 255, 0, 0, 255, 0, 26, 255, 0, 98, 255, 0, 165, 255, 0, 228, 255, 0,
+        255, 255, 25, 0, 255, 25, 25, 255, 25, 98, 255, 25, 165, 255, 25, 228, 255, 25, 255, 255,
+        96, 0, 255, 96, 16, 255, 96, 96, 255, 96, 164, 255, 96, 227, 255, 96, 255, 255, 161, 0,
+        255, 161, 0, 255, 161, 92, 255, 161, 161, 255, 161, 226, 255, 162, 255, 255, 224, 0, 255,
+        224, 0, 255, 224, 84, 255, 224, 158, 255, 224, 224, 255, 224, 255, 255, 255, 0, 255, 255,
+        0, 255, 255, 79, 255, 255, 156, 255, 255, 222, 255, 255, 255,
+    ];
+
+    const SRGB_TO_PRO_PHOTO: [u8; 648] = [
+        0, 0, 0, 7, 3, 21, 26, 10, 71, 48, 19, 131, 72, 29, 198, 85, 35, 235, 12, 21, 7, 15, 22,
+        23, 29, 24, 72, 50, 30, 132, 74, 38, 199, 87, 42, 235, 41, 71, 23, 42, 71, 33, 50, 73, 76,
+        66, 75, 134, 86, 79, 201, 98, 82, 237, 77, 132, 43, 77, 132, 50, 83, 133, 86, 93, 134, 141,
+        109, 137, 205, 119, 138, 241, 116, 199, 65, 116, 199, 70, 120, 200, 100, 128, 201, 151,
+        141, 203, 213, 149, 204, 248, 137, 236, 77, 138, 236, 82, 141, 237, 109, 148, 238, 157,
+        160, 239, 218, 167, 240, 252, 16, 6, 2, 18, 7, 21, 31, 12, 71, 51, 21, 131, 75, 30, 198,
+        88, 35, 235, 21, 23, 7, 23, 23, 23, 35, 26, 72, 54, 31, 132, 76, 39, 199, 89, 43, 235, 46,
+        72, 23, 47, 72, 33, 54, 73, 76, 69, 76, 135, 88, 80, 201, 100, 82, 237, 79, 132, 43, 80,
+        132, 50, 85, 133, 86, 96, 135, 141, 111, 137, 205, 121, 139, 241, 118, 200, 65, 118, 200,
+        70, 122, 200, 100, 130, 201, 151, 143, 203, 213, 151, 204, 248, 139, 236, 77, 139, 236, 82,
+        143, 237, 109, 150, 238, 157, 161, 239, 218, 169, 240, 252, 54, 21, 7, 55, 21, 23, 62, 24,
+        72, 75, 30, 132, 93, 37, 199, 105, 42, 235, 56, 31, 11, 57, 31, 25, 63, 34, 72, 77, 38,
+        132, 95, 45, 199, 106, 49, 235, 71, 76, 25, 71, 76, 34, 77, 77, 77, 88, 79, 135, 105, 83,
+        201, 115, 86, 237, 97, 135, 44, 98, 135, 51, 102, 135, 87, 112, 137, 141, 126, 139, 206,
+        135, 141, 241, 132, 201, 66, 132, 201, 71, 135, 202, 101, 143, 203, 151, 155, 205, 213,
+        162, 206, 248, 151, 238, 78, 152, 238, 82, 155, 238, 110, 161, 239, 158, 172, 241, 218,
+        179, 242, 252, 100, 39, 14, 100, 39, 27, 105, 41, 73, 114, 45, 133, 128, 51, 199, 136, 54,
+        236, 101, 46, 16, 102, 46, 28, 106, 48, 74, 115, 51, 133, 129, 56, 200, 138, 60, 236, 111,
+        84, 28, 111, 84, 37, 115, 85, 78, 124, 87, 136, 137, 91, 202, 145, 93, 238, 131, 140, 46,
+        131, 140, 53, 135, 141, 88, 142, 142, 142, 154, 145, 206, 162, 146, 242, 159, 205, 68, 159,
+        205, 72, 162, 206, 102, 169, 207, 152, 179, 209, 214, 186, 210, 249, 176, 241, 79, 177,
+        241, 84, 179, 242, 111, 185, 243, 159, 195, 244, 219, 201, 245, 253, 151, 59, 22, 151, 59,
+        32, 154, 60, 76, 161, 63, 134, 172, 68, 200, 179, 71, 237, 152, 64, 23, 152, 64, 33, 155,
+        66, 76, 162, 68, 135, 173, 73, 201, 180, 75, 237, 159, 96, 33, 159, 96, 41, 162, 97, 81,
+        169, 99, 137, 179, 103, 203, 186, 105, 239, 174, 148, 50, 175, 149, 56, 177, 149, 90, 184,
+        151, 144, 193, 153, 207, 200, 154, 243, 198, 212, 70, 198, 212, 75, 200, 212, 104, 206,
+        213, 154, 215, 215, 215, 221, 216, 249, 212, 247, 82, 212, 247, 86, 215, 247, 113, 220,
+        248, 160, 229, 250, 220, 234, 251, 254, 179, 70, 26, 179, 70, 35, 182, 71, 77, 188, 74,
+        135, 197, 78, 201, 204, 80, 237, 179, 74, 27, 180, 75, 36, 183, 76, 78, 189, 78, 136, 198,
+        82, 201, 204, 85, 238, 186, 104, 36, 186, 104, 44, 189, 105, 82, 195, 107, 138, 204, 110,
+        204, 210, 112, 239, 199, 154, 52, 200, 154, 58, 202, 155, 92, 208, 156, 145, 217, 158, 208,
+        223, 160, 244, 221, 216, 72, 221, 216, 76, 223, 216, 105, 228, 217, 155, 237, 219, 216,
+        242, 220, 250, 234, 250, 83, 234, 251, 87, 236, 251, 114, 241, 252, 161, 249, 253, 221,
+        255, 255, 255,
+    ];
+
+    const BT2020_FILE_TO_SRGB: [u8; 648] = [
+        0, 0, 0, 0, 0, 20, 0, 0, 92, 0, 0, 163, 0, 0, 232, 0, 0, 255, 0, 20, 0, 0, 20, 19, 0, 19,
+        92, 0, 14, 163, 0, 4, 232, 0, 0, 255, 0, 93, 0, 0, 93, 0, 0, 92, 88, 0, 92, 161, 0, 90,
+        231, 0, 89, 255, 0, 164, 0, 0, 164, 0, 0, 164, 77, 0, 163, 156, 0, 163, 228, 0, 162, 255,
+        0, 234, 0, 0, 234, 0, 0, 234, 52, 0, 233, 147, 0, 233, 223, 0, 233, 255, 0, 255, 0, 0, 255,
+        0, 0, 255, 19, 0, 255, 141, 0, 255, 219, 0, 255, 255, 26, 0, 0, 26, 0, 20, 12, 0, 92, 0, 0,
+        163, 0, 0, 232, 0, 0, 255, 19, 19, 0, 18, 18, 18, 0, 17, 92, 0, 12, 163, 0, 1, 232, 0, 0,
+        255, 0, 92, 0, 0, 92, 0, 0, 92, 88, 0, 91, 161, 0, 90, 231, 0, 89, 255, 0, 164, 0, 0, 164,
+        0, 0, 163, 77, 0, 163, 156, 0, 162, 228, 0, 162, 255, 0, 234, 0, 0, 234, 0, 0, 234, 52, 0,
+        233, 147, 0, 233, 223, 0, 233, 255, 0, 255, 0, 0, 255, 0, 0, 255, 19, 0, 255, 141, 0, 255,
+        219, 0, 255, 255, 111, 0, 0, 111, 0, 16, 109, 0, 91, 103, 0, 162, 92, 0, 232, 83, 0, 255,
+        110, 0, 0, 110, 0, 15, 108, 0, 91, 102, 0, 162, 90, 0, 232, 81, 0, 255, 90, 88, 0, 90, 88,
+        0, 87, 87, 87, 79, 86, 161, 63, 85, 231, 48, 84, 255, 0, 161, 0, 0, 161, 0, 0, 161, 76, 0,
+        161, 156, 0, 160, 228, 0, 160, 255, 0, 232, 0, 0, 232, 0, 0, 232, 51, 0, 232, 147, 0, 232,
+        223, 0, 231, 255, 0, 255, 0, 0, 255, 0, 0, 255, 15, 0, 255, 140, 0, 255, 219, 0, 255, 255,
+        195, 0, 0, 194, 0, 4, 193, 0, 90, 191, 0, 162, 186, 0, 232, 182, 0, 255, 194, 0, 0, 194, 0,
+        2, 193, 0, 89, 190, 0, 162, 185, 0, 232, 182, 0, 255, 185, 74, 0, 185, 74, 0, 184, 73, 85,
+        181, 72, 160, 176, 70, 231, 172, 69, 255, 160, 155, 0, 160, 155, 0, 158, 155, 74, 155, 155,
+        155, 148, 154, 227, 144, 154, 255, 96, 229, 0, 96, 229, 0, 93, 229, 47, 86, 228, 146, 71,
+        228, 222, 59, 228, 255, 0, 255, 0, 0, 255, 0, 0, 255, 2, 0, 255, 139, 0, 255, 218, 0, 255,
+        255, 255, 0, 0, 255, 0, 0, 255, 0, 86, 255, 0, 160, 255, 0, 231, 255, 0, 255, 255, 0, 0,
+        255, 0, 0, 255, 0, 86, 255, 0, 160, 255, 0, 231, 255, 0, 255, 255, 37, 0, 255, 37, 0, 255,
+        36, 82, 255, 34, 158, 255, 29, 230, 255, 26, 255, 255, 144, 0, 255, 144, 0, 255, 144, 70,
+        254, 144, 153, 250, 143, 227, 248, 142, 255, 228, 222, 0, 228, 222, 0, 227, 222, 40, 225,
+        222, 144, 221, 221, 221, 219, 221, 255, 206, 255, 0, 206, 255, 0, 205, 255, 0, 202, 255,
+        137, 198, 255, 217, 195, 255, 255, 255, 0, 0, 255, 0, 0, 255, 0, 84, 255, 0, 159, 255, 0,
+        230, 255, 0, 255, 255, 0, 0, 255, 0, 0, 255, 0, 84, 255, 0, 159, 255, 0, 230, 255, 0, 255,
+        255, 0, 0, 255, 0, 0, 255, 0, 80, 255, 0, 157, 255, 0, 229, 255, 0, 255, 255, 136, 0, 255,
+        136, 0, 255, 136, 67, 255, 135, 152, 255, 134, 226, 255, 134, 255, 255, 217, 0, 255, 217,
+        0, 255, 217, 34, 255, 217, 143, 255, 217, 221, 255, 216, 255, 255, 255, 0, 255, 255, 0,
+        255, 255, 0, 255, 255, 136, 255, 255, 217, 255, 255, 255,
+    ];
+    fn round_trip(profile: ColorProfile) -> ColorProfile {
+        ColorProfile::new_from_slice(&profile.encode().unwrap()).unwrap()
+    }
+
+    fn check(source: ColorProfile, dest: ColorProfile, expected: &[u8]) {
+        let src = LEVELS
+            .iter()
+            .flat_map(|&r| {
+                LEVELS
+                    .iter()
+                    .flat_map(move |&g| LEVELS.iter().flat_map(move |&b| [r, g, b]))
+            })
+            .collect::<Vec<u8>>();
+        let transform = source
+            .create_transform_8bit(
+                Layout::Rgb,
+                &dest,
+                Layout::Rgb,
+                TransformOptions {
+                    compatibility_mode: CompatibilityMode::Qcms,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let mut dst = vec![0u8; src.len()];
+        transform.transform(&src, &mut dst).unwrap();
+        for (i, (got, want)) in dst
+            .chunks_exact(3)
+            .zip(expected.chunks_exact(3))
+            .enumerate()
+        {
+            assert_eq!(got, want, "pixel {:?}", &src[i * 3..i * 3 + 3]);
+        }
+    }
+
+    #[test]
+    fn test_qcms_srgb_to_display_p3() {
+        check(
+            round_trip(ColorProfile::new_srgb()),
+            round_trip(ColorProfile::new_display_p3()),
+            &SRGB_TO_DISPLAY_P3,
+        );
+    }
+
+    #[test]
+    fn test_qcms_display_p3_to_srgb() {
+        check(
+            round_trip(ColorProfile::new_display_p3()),
+            round_trip(ColorProfile::new_srgb()),
+            &DISPLAY_P3_TO_SRGB,
+        );
+    }
+
+    #[test]
+    fn test_qcms_adobe_rgb_to_srgb() {
+        check(
+            round_trip(ColorProfile::new_adobe_rgb()),
+            round_trip(ColorProfile::new_srgb()),
+            &ADOBE_RGB_TO_SRGB,
+        );
+    }
+
+    #[test]
+    fn test_qcms_srgb_to_pro_photo() {
+        check(
+            round_trip(ColorProfile::new_srgb()),
+            round_trip(ColorProfile::new_pro_photo_rgb()),
+            &SRGB_TO_PRO_PHOTO,
+        );
+    }
+
+    #[test]
+    fn test_qcms_bt2020_lut_trc_to_srgb() {
+        let source =
+            ColorProfile::new_from_slice(include_bytes!("../../assets/bt_2020.icc")).unwrap();
+        check(
+            source,
+            round_trip(ColorProfile::new_srgb()),
+            &BT2020_FILE_TO_SRGB,
+        );
+    }
+}
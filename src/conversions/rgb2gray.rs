@@ -27,7 +27,7 @@
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 use crate::mlaf::mlaf;
-use crate::transform::PointeeSizeExpressible;
+use crate::transform::{PointeeSizeExpressible, touch_pages};
 use crate::{CmsError, Layout, TransformExecutor, Vector3f};
 use num_traits::AsPrimitive;
 
@@ -39,6 +39,15 @@ pub(crate) struct ToneReproductionRgbToGray<T, const BUCKET: usize> {
     pub(crate) gray_gamma: Box<[T; 65536]>,
 }
 
+impl<T: Copy, const BUCKET: usize> ToneReproductionRgbToGray<T, BUCKET> {
+    fn warmup(&self) {
+        touch_pages(self.r_linear.as_slice());
+        touch_pages(self.g_linear.as_slice());
+        touch_pages(self.b_linear.as_slice());
+        touch_pages(self.gray_gamma.as_slice());
+    }
+}
+
 #[derive(Clone)]
 struct TransformProfileRgbToGray<
     T,
@@ -68,8 +77,12 @@ where
 {
     match src_layout {
         Layout::Rgb => match dst_layout {
-            Layout::Rgb => unreachable!(),
-            Layout::Rgba => unreachable!(),
+            Layout::Rgb
+            | Layout::Rgba
+            | Layout::Bgr
+            | Layout::Bgra
+            | Layout::Argb
+            | Layout::Abgr => unreachable!(),
             Layout::Gray => Box::new(TransformProfileRgbToGray::<
                 T,
                 { Layout::Rgb as u8 },
@@ -94,8 +107,12 @@ where
             }),
         },
         Layout::Rgba => match dst_layout {
-            Layout::Rgb => unreachable!(),
-            Layout::Rgba => unreachable!(),
+            Layout::Rgb
+            | Layout::Rgba
+            | Layout::Bgr
+            | Layout::Bgra
+            | Layout::Argb
+            | Layout::Abgr => unreachable!(),
             Layout::Gray => Box::new(TransformProfileRgbToGray::<
                 T,
                 { Layout::Rgba as u8 },
@@ -119,8 +136,127 @@ where
                 weights,
             }),
         },
-        Layout::Gray => unreachable!(),
-        Layout::GrayAlpha => unreachable!(),
+        Layout::Bgr => match dst_layout {
+            Layout::Rgb
+            | Layout::Rgba
+            | Layout::Bgr
+            | Layout::Bgra
+            | Layout::Argb
+            | Layout::Abgr => unreachable!(),
+            Layout::Gray => Box::new(TransformProfileRgbToGray::<
+                T,
+                { Layout::Bgr as u8 },
+                { Layout::Gray as u8 },
+                BUCKET,
+                BIT_DEPTH,
+                GAMMA_LUT,
+            > {
+                trc_box: trc,
+                weights,
+            }),
+            Layout::GrayAlpha => Box::new(TransformProfileRgbToGray::<
+                T,
+                { Layout::Bgr as u8 },
+                { Layout::GrayAlpha as u8 },
+                BUCKET,
+                BIT_DEPTH,
+                GAMMA_LUT,
+            > {
+                trc_box: trc,
+                weights,
+            }),
+        },
+        Layout::Bgra => match dst_layout {
+            Layout::Rgb
+            | Layout::Rgba
+            | Layout::Bgr
+            | Layout::Bgra
+            | Layout::Argb
+            | Layout::Abgr => unreachable!(),
+            Layout::Gray => Box::new(TransformProfileRgbToGray::<
+                T,
+                { Layout::Bgra as u8 },
+                { Layout::Gray as u8 },
+                BUCKET,
+                BIT_DEPTH,
+                GAMMA_LUT,
+            > {
+                trc_box: trc,
+                weights,
+            }),
+            Layout::GrayAlpha => Box::new(TransformProfileRgbToGray::<
+                T,
+                { Layout::Bgra as u8 },
+                { Layout::GrayAlpha as u8 },
+                BUCKET,
+                BIT_DEPTH,
+                GAMMA_LUT,
+            > {
+                trc_box: trc,
+                weights,
+            }),
+        },
+        Layout::Argb => match dst_layout {
+            Layout::Rgb
+            | Layout::Rgba
+            | Layout::Bgr
+            | Layout::Bgra
+            | Layout::Argb
+            | Layout::Abgr => unreachable!(),
+            Layout::Gray => Box::new(TransformProfileRgbToGray::<
+                T,
+                { Layout::Argb as u8 },
+                { Layout::Gray as u8 },
+                BUCKET,
+                BIT_DEPTH,
+                GAMMA_LUT,
+            > {
+                trc_box: trc,
+                weights,
+            }),
+            Layout::GrayAlpha => Box::new(TransformProfileRgbToGray::<
+                T,
+                { Layout::Argb as u8 },
+                { Layout::GrayAlpha as u8 },
+                BUCKET,
+                BIT_DEPTH,
+                GAMMA_LUT,
+            > {
+                trc_box: trc,
+                weights,
+            }),
+        },
+        Layout::Abgr => match dst_layout {
+            Layout::Rgb
+            | Layout::Rgba
+            | Layout::Bgr
+            | Layout::Bgra
+            | Layout::Argb
+            | Layout::Abgr => unreachable!(),
+            Layout::Gray => Box::new(TransformProfileRgbToGray::<
+                T,
+                { Layout::Abgr as u8 },
+                { Layout::Gray as u8 },
+                BUCKET,
+                BIT_DEPTH,
+                GAMMA_LUT,
+            > {
+                trc_box: trc,
+                weights,
+            }),
+            Layout::GrayAlpha => Box::new(TransformProfileRgbToGray::<
+                T,
+                { Layout::Abgr as u8 },
+                { Layout::GrayAlpha as u8 },
+                BUCKET,
+                BIT_DEPTH,
+                GAMMA_LUT,
+            > {
+                trc_box: trc,
+                weights,
+            }),
+        },
+        Layout::Gray | Layout::GrayAlpha => unreachable!(),
     }
 }
 
@@ -136,6 +272,10 @@ impl<
 where
     u32: AsPrimitive<T>,
 {
+    fn warmup(&self) {
+        self.trc_box.warmup();
+    }
+
     fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
         let src_cn = Layout::from(SRC_LAYOUT);
         let dst_cn = Layout::from(DST_LAYOUT);
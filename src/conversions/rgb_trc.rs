@@ -0,0 +1,161 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::conversions::TransformProfileRgb;
+use crate::mlaf::mlaf;
+use crate::transform::{PointeeSizeExpressible, touch_pages};
+use crate::{CmsError, Layout, Matrix3f, TransformExecutor};
+use num_traits::AsPrimitive;
+
+/// Largest deviation from identity matrix when matrix stage still may be skipped,
+/// it covers float error of matrix inversion and stays well below a step of 16-bit gamma table.
+const IDENTITY_EPSILON: f32 = 4e-6;
+
+/// Checks if matrix-shaper transform changes tone curves only,
+/// that is the case for profiles sharing primaries and white point.
+pub(crate) fn is_trc_only_transform(matrix: Option<Matrix3f>) -> bool {
+    let matrix = matrix.unwrap_or(Matrix3f::IDENTITY);
+    matrix
+        .v
+        .iter()
+        .zip(Matrix3f::IDENTITY.v.iter())
+        .all(|(row, identity)| {
+            row.iter()
+                .zip(identity.iter())
+                .all(|(&v, &i)| (v - i).abs() <= IDENTITY_EPSILON)
+        })
+}
+
+/// Source linearization and destination gamma composed into one table per channel.
+struct TransformRgbTrc<T, const SRC_LAYOUT: u8, const DST_LAYOUT: u8, const BIT_DEPTH: usize> {
+    r: Box<[T]>,
+    g: Box<[T]>,
+    b: Box<[T]>,
+}
+
+fn compose_trc<T: Copy, const LINEAR_CAP: usize>(
+    linear: &[f32; LINEAR_CAP],
+    gamma: &[T; 65536],
+    gamma_lut: usize,
+) -> Box<[T]> {
+    let scale = (gamma_lut - 1) as f32;
+    linear
+        .iter()
+        .map(|&x| gamma[mlaf(0.5f32, x.max(0f32).min(1f32), scale) as u16 as usize])
+        .collect()
+}
+
+macro_rules! rgb_trc_executor {
+    ($src: ident, $dst: ident, ($profile: ident)) => {
+        Ok(Box::new(TransformRgbTrc::<
+            T,
+            { Layout::$src as u8 },
+            { Layout::$dst as u8 },
+            BIT_DEPTH,
+        > {
+            r: compose_trc(&$profile.r_linear, &$profile.r_gamma, GAMMA_LUT),
+            g: compose_trc(&$profile.g_linear, &$profile.g_gamma, GAMMA_LUT),
+            b: compose_trc(&$profile.b_linear, &$profile.b_gamma, GAMMA_LUT),
+        }))
+    };
+}
+
+/// Makes transform skipping matrix stage, results are the same as with identity matrix.
+pub(crate) fn make_rgb_trc_transform<
+    T: Copy + Default + Send + Sync + PointeeSizeExpressible + 'static,
+    const LINEAR_CAP: usize,
+    const GAMMA_LUT: usize,
+    const BIT_DEPTH: usize,
+>(
+    src_layout: Layout,
+    dst_layout: Layout,
+    profile: TransformProfileRgb<T, LINEAR_CAP>,
+) -> Result<Box<dyn TransformExecutor<T> + Send + Sync>, CmsError>
+where
+    u32: AsPrimitive<T>,
+{
+    dispatch_layouts!(
+        src_layout => [Rgb, Rgba, Bgr, Bgra, Argb, Abgr],
+        dst_layout => [Rgb, Rgba, Bgr, Bgra, Argb, Abgr],
+        Err(CmsError::UnsupportedProfileConnection),
+        rgb_trc_executor(profile)
+    )
+}
+
+impl<
+    T: Copy + Default + PointeeSizeExpressible + 'static,
+    const SRC_LAYOUT: u8,
+    const DST_LAYOUT: u8,
+    const BIT_DEPTH: usize,
+> TransformExecutor<T> for TransformRgbTrc<T, SRC_LAYOUT, DST_LAYOUT, BIT_DEPTH>
+where
+    u32: AsPrimitive<T>,
+{
+    fn warmup(&self) {
+        touch_pages(&self.r);
+        touch_pages(&self.g);
+        touch_pages(&self.b);
+    }
+
+    fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
+        let src_cn = Layout::from(SRC_LAYOUT);
+        let dst_cn = Layout::from(DST_LAYOUT);
+        let src_channels = src_cn.channels();
+        let dst_channels = dst_cn.channels();
+
+        if src.len() / src_channels != dst.len() / dst_channels {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        if src.len() % src_channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if dst.len() % dst_channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+
+        let max_colors: T = ((1u32 << BIT_DEPTH) - 1).as_();
+
+        for (src, dst) in src
+            .chunks_exact(src_channels)
+            .zip(dst.chunks_exact_mut(dst_channels))
+        {
+            let a = if src_channels == 4 {
+                src[src_cn.a_i()]
+            } else {
+                max_colors
+            };
+            dst[dst_cn.r_i()] = self.r[src[src_cn.r_i()]._as_usize()];
+            dst[dst_cn.g_i()] = self.g[src[src_cn.g_i()]._as_usize()];
+            dst[dst_cn.b_i()] = self.b[src[src_cn.b_i()]._as_usize()];
+            if dst_channels == 4 {
+                dst[dst_cn.a_i()] = a;
+            }
+        }
+        Ok(())
+    }
+}
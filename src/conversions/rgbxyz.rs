@@ -26,7 +26,12 @@
  * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
-use crate::{CmsError, Layout, Matrix3f, TransformExecutor, TransformOptions};
+use crate::conversions::WORKING_LANES;
+use crate::conversions::rgbxyz_float::make_rgb_xyz_rgb_transform_f32;
+#[allow(unused)]
+use crate::dispatch::{KernelFamily, use_kernel};
+use crate::transform::touch_pages;
+use crate::{CmsError, ColorProfile, Layout, Matrix3f, TransformExecutor, TransformOptions};
 use num_traits::AsPrimitive;
 
 pub(crate) trait RgbXyzFactory<T: Clone + AsPrimitive<usize> + Default> {
@@ -36,6 +41,18 @@ pub(crate) trait RgbXyzFactory<T: Clone + AsPrimitive<usize> + Default> {
         profile: TransformProfileRgb<T, LINEAR_CAP>,
         transform_options: TransformOptions,
     ) -> Result<Box<dyn TransformExecutor<T> + Send + Sync>, CmsError>;
+
+    /// Makes matrix-shaper transform evaluating tone curves without tables,
+    /// `None` if sample type works with tables only.
+    fn make_evaluated_transform(
+        _src_pr: &ColorProfile,
+        _dst_pr: &ColorProfile,
+        _src_layout: Layout,
+        _dst_layout: Layout,
+        _transform_options: TransformOptions,
+    ) -> Result<Option<Box<dyn TransformExecutor<T> + Send + Sync>>, CmsError> {
+        Ok(None)
+    }
 }
 
 impl RgbXyzFactory<u16> for u16 {
@@ -45,11 +62,14 @@ impl RgbXyzFactory<u16> for u16 {
         profile: TransformProfileRgb<u16, LINEAR_CAP>,
         transform_options: TransformOptions,
     ) -> Result<Box<dyn TransformExecutor<u16> + Send + Sync>, CmsError> {
-        if (BIT_DEPTH == 10 || BIT_DEPTH == 14) && transform_options.prefer_fixed_point {
+        if (BIT_DEPTH == 10 || BIT_DEPTH == 14)
+            && transform_options.prefer_fixed_point
+            && profile.fits_q4_n::<12, GAMMA_LUT, BIT_DEPTH>()
+        {
             #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "avx"))]
             {
                 use crate::conversions::rgbxyz_fixed::make_rgb_xyz_q4_12_transform_avx2;
-                if std::arch::is_x86_feature_detected!("avx2") {
+                if use_kernel(KernelFamily::Avx2) && std::arch::is_x86_feature_detected!("avx2") {
                     return make_rgb_xyz_q4_12_transform_avx2::<
                         u16,
                         LINEAR_CAP,
@@ -62,7 +82,8 @@ impl RgbXyzFactory<u16> for u16 {
             #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "sse"))]
             {
                 use crate::conversions::rgbxyz_fixed::make_rgb_xyz_q4_12_transform_sse_41;
-                if std::arch::is_x86_feature_detected!("sse4.1") {
+                if use_kernel(KernelFamily::Sse41) && std::arch::is_x86_feature_detected!("sse4.1")
+                {
                     return make_rgb_xyz_q4_12_transform_sse_41::<
                         u16,
                         LINEAR_CAP,
@@ -72,11 +93,16 @@ impl RgbXyzFactory<u16> for u16 {
                     >(src_layout, dst_layout, profile);
                 }
             }
-            #[cfg(all(target_arch = "aarch64", target_feature = "neon", feature = "neon"))]
-            {
-                return make_rgb_xyz_q4_12::<u16, LINEAR_CAP, GAMMA_LUT, BIT_DEPTH, 12>(
-                    src_layout, dst_layout, profile,
-                );
+            #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+            if KernelFamily::Neon.is_available() && use_kernel(KernelFamily::Neon) {
+                use crate::conversions::rgbxyz_fixed::make_rgb_xyz_q4_12_transform_neon;
+                return make_rgb_xyz_q4_12_transform_neon::<
+                    u16,
+                    LINEAR_CAP,
+                    GAMMA_LUT,
+                    BIT_DEPTH,
+                    12,
+                >(src_layout, dst_layout, profile);
             }
         }
         make_rgb_xyz_rgb_transform::<u16, LINEAR_CAP, GAMMA_LUT, BIT_DEPTH>(
@@ -92,11 +118,11 @@ impl RgbXyzFactory<f32> for f32 {
         profile: TransformProfileRgb<f32, LINEAR_CAP>,
         transform_options: TransformOptions,
     ) -> Result<Box<dyn TransformExecutor<f32> + Send + Sync>, CmsError> {
-        if transform_options.prefer_fixed_point {
+        if transform_options.prefer_fixed_point && profile.fits_q4_n::<12, GAMMA_LUT, BIT_DEPTH>() {
             #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "avx"))]
             {
                 use crate::conversions::rgbxyz_fixed::make_rgb_xyz_q4_12_transform_avx2;
-                if std::arch::is_x86_feature_detected!("avx2") {
+                if use_kernel(KernelFamily::Avx2) && std::arch::is_x86_feature_detected!("avx2") {
                     return make_rgb_xyz_q4_12_transform_avx2::<
                         f32,
                         LINEAR_CAP,
@@ -109,7 +135,8 @@ impl RgbXyzFactory<f32> for f32 {
             #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "sse"))]
             {
                 use crate::conversions::rgbxyz_fixed::make_rgb_xyz_q4_12_transform_sse_41;
-                if std::arch::is_x86_feature_detected!("sse4.1") {
+                if use_kernel(KernelFamily::Sse41) && std::arch::is_x86_feature_detected!("sse4.1")
+                {
                     return make_rgb_xyz_q4_12_transform_sse_41::<
                         f32,
                         LINEAR_CAP,
@@ -119,17 +146,33 @@ impl RgbXyzFactory<f32> for f32 {
                     >(src_layout, dst_layout, profile);
                 }
             }
-            #[cfg(all(target_arch = "aarch64", target_feature = "neon", feature = "neon"))]
-            {
-                return make_rgb_xyz_q4_12::<f32, LINEAR_CAP, GAMMA_LUT, BIT_DEPTH, 12>(
-                    src_layout, dst_layout, profile,
-                );
+            #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+            if KernelFamily::Neon.is_available() && use_kernel(KernelFamily::Neon) {
+                use crate::conversions::rgbxyz_fixed::make_rgb_xyz_q4_12_transform_neon;
+                return make_rgb_xyz_q4_12_transform_neon::<
+                    f32,
+                    LINEAR_CAP,
+                    GAMMA_LUT,
+                    BIT_DEPTH,
+                    12,
+                >(src_layout, dst_layout, profile);
             }
         }
         make_rgb_xyz_rgb_transform::<f32, LINEAR_CAP, GAMMA_LUT, BIT_DEPTH>(
             src_layout, dst_layout, profile,
         )
     }
+
+    fn make_evaluated_transform(
+        src_pr: &ColorProfile,
+        dst_pr: &ColorProfile,
+        src_layout: Layout,
+        dst_layout: Layout,
+        transform_options: TransformOptions,
+    ) -> Result<Option<Box<dyn TransformExecutor<f32> + Send + Sync>>, CmsError> {
+        let profile = src_pr.make_rgb_profile_transform_f32(dst_pr, transform_options)?;
+        make_rgb_xyz_rgb_transform_f32(src_layout, dst_layout, profile).map(Some)
+    }
 }
 
 impl RgbXyzFactory<f64> for f64 {
@@ -152,11 +195,11 @@ impl RgbXyzFactory<u8> for u8 {
         profile: TransformProfileRgb<u8, LINEAR_CAP>,
         transform_options: TransformOptions,
     ) -> Result<Box<dyn TransformExecutor<u8> + Send + Sync>, CmsError> {
-        if transform_options.prefer_fixed_point {
+        if transform_options.prefer_fixed_point && profile.fits_q4_n::<12, GAMMA_LUT, 8>() {
             #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "avx"))]
             {
                 use crate::conversions::rgbxyz_fixed::make_rgb_xyz_q4_12_transform_avx2;
-                if std::arch::is_x86_feature_detected!("avx2") {
+                if use_kernel(KernelFamily::Avx2) && std::arch::is_x86_feature_detected!("avx2") {
                     return make_rgb_xyz_q4_12_transform_avx2::<u8, LINEAR_CAP, GAMMA_LUT, 8, 12>(
                         src_layout, dst_layout, profile,
                     );
@@ -165,12 +208,20 @@ impl RgbXyzFactory<u8> for u8 {
             #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "sse"))]
             {
                 use crate::conversions::rgbxyz_fixed::make_rgb_xyz_q4_12_transform_sse_41;
-                if std::arch::is_x86_feature_detected!("sse4.1") {
+                if use_kernel(KernelFamily::Sse41) && std::arch::is_x86_feature_detected!("sse4.1")
+                {
                     return make_rgb_xyz_q4_12_transform_sse_41::<u8, LINEAR_CAP, GAMMA_LUT, 8, 12>(
                         src_layout, dst_layout, profile,
                     );
                 }
             }
+            #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+            if KernelFamily::Neon.is_available() && use_kernel(KernelFamily::Neon) {
+                use crate::conversions::rgbxyz_fixed::make_rgb_xyz_q4_12_transform_neon;
+                return make_rgb_xyz_q4_12_transform_neon::<u8, LINEAR_CAP, GAMMA_LUT, 8, 12>(
+                    src_layout, dst_layout, profile,
+                );
+            }
             make_rgb_xyz_q4_12::<u8, LINEAR_CAP, GAMMA_LUT, 8, 12>(src_layout, dst_layout, profile)
         } else {
             make_rgb_xyz_rgb_transform::<u8, LINEAR_CAP, GAMMA_LUT, 8>(
@@ -180,6 +231,24 @@ impl RgbXyzFactory<u8> for u8 {
     }
 }
 
+/// Checks if [RgbXyzFactory::make_transform] of 10 bit samples may dispatch
+/// its Q4.12 kernel, only SIMD families implement it at this depth.
+pub(crate) fn has_q4_12_simd_kernel() -> bool {
+    #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "avx"))]
+    if use_kernel(KernelFamily::Avx2) && std::arch::is_x86_feature_detected!("avx2") {
+        return true;
+    }
+    #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "sse"))]
+    if use_kernel(KernelFamily::Sse41) && std::arch::is_x86_feature_detected!("sse4.1") {
+        return true;
+    }
+    #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+    if KernelFamily::Neon.is_available() && use_kernel(KernelFamily::Neon) {
+        return true;
+    }
+    false
+}
+
 pub(crate) struct TransformProfileRgb<T: Clone, const BUCKET: usize> {
     pub(crate) r_linear: Box<[f32; BUCKET]>,
     pub(crate) g_linear: Box<[f32; BUCKET]>,
@@ -191,6 +260,69 @@ pub(crate) struct TransformProfileRgb<T: Clone, const BUCKET: usize> {
 }
 
 impl<T: Clone + PointeeSizeExpressible, const BUCKET: usize> TransformProfileRgb<T, BUCKET> {
+    pub(crate) fn warmup(&self)
+    where
+        T: Copy,
+    {
+        touch_pages(self.r_linear.as_slice());
+        touch_pages(self.g_linear.as_slice());
+        touch_pages(self.b_linear.as_slice());
+        touch_pages(self.r_gamma.as_slice());
+        touch_pages(self.g_gamma.as_slice());
+        touch_pages(self.b_gamma.as_slice());
+    }
+
+    /// Scale of linearized values feeding Q4.n matrix, linear values are stored
+    /// as indices into gamma table.
+    fn q4_n_linear_scale<const GAMMA_LUT: usize, const BIT_DEPTH: usize>() -> f32 {
+        if T::FINITE {
+            let lut_scale = (GAMMA_LUT - 1) as f32 / ((1 << BIT_DEPTH) - 1) as f32;
+            ((1 << BIT_DEPTH) - 1) as f32 * lut_scale
+        } else {
+            let lut_scale = (GAMMA_LUT - 1) as f32 / (T::NOT_FINITE_LINEAR_TABLE_SIZE - 1) as f32;
+            (T::NOT_FINITE_LINEAR_TABLE_SIZE - 1) as f32 * lut_scale
+        }
+    }
+
+    /// Checks that matrix has enough headroom for Q4.n fixed point.
+    ///
+    /// Every scaled coefficient and linearized value must be representable as `i16`,
+    /// and worst-case row accumulation must fit `i32`, otherwise
+    /// `_mm_madd_epi16` and its neon counterparts silently wrap.
+    /// Transform should fall back to floating point path when this returns `false`.
+    pub(crate) fn fits_q4_n<
+        const PRECISION: i32,
+        const GAMMA_LUT: usize,
+        const BIT_DEPTH: usize,
+    >(
+        &self,
+    ) -> bool {
+        let linear_scale = Self::q4_n_linear_scale::<GAMMA_LUT, BIT_DEPTH>();
+        let max_linear = self
+            .r_linear
+            .iter()
+            .chain(self.g_linear.iter())
+            .chain(self.b_linear.iter())
+            .fold(0f32, |acc, &v| acc.max((v * linear_scale).round().abs()));
+        if !max_linear.is_finite() || max_linear > i16::MAX as f32 {
+            return false;
+        }
+        let scale: f32 = ((1 << PRECISION as i16) - 1) as f32;
+        let rnd = ((1i64 << (PRECISION - 1)) - 1) as f64;
+        let matrix = self.adaptation_matrix.unwrap_or(Matrix3f::IDENTITY);
+        matrix.v.iter().all(|row| {
+            let mut worst_case = rnd;
+            for &c in row.iter() {
+                let q = (c * scale).round();
+                if !q.is_finite() || q.abs() > i16::MAX as f32 {
+                    return false;
+                }
+                worst_case += q.abs() as f64 * max_linear as f64;
+            }
+            worst_case <= i32::MAX as f64
+        })
+    }
+
     pub(crate) fn to_q4_n<
         R: Copy + 'static + Default,
         const PRECISION: i32,
@@ -203,13 +335,7 @@ impl<T: Clone + PointeeSizeExpressible, const BUCKET: usize> TransformProfileRgb
     where
         f32: AsPrimitive<R>,
     {
-        let linear_scale = if T::FINITE {
-            let lut_scale = (GAMMA_LUT - 1) as f32 / ((1 << BIT_DEPTH) - 1) as f32;
-            ((1 << BIT_DEPTH) - 1) as f32 * lut_scale
-        } else {
-            let lut_scale = (GAMMA_LUT - 1) as f32 / (T::NOT_FINITE_LINEAR_TABLE_SIZE - 1) as f32;
-            (T::NOT_FINITE_LINEAR_TABLE_SIZE - 1) as f32 * lut_scale
-        };
+        let linear_scale = Self::q4_n_linear_scale::<GAMMA_LUT, BIT_DEPTH>();
         let mut new_box_r = Box::new([R::default(); BUCKET]);
         let mut new_box_g = Box::new([R::default(); BUCKET]);
         let mut new_box_b = Box::new([R::default(); BUCKET]);
@@ -254,10 +380,22 @@ struct TransformProfilePcsXYZRgb<
     pub(crate) profile: TransformProfileRgb<T, LINEAR_CAP>,
 }
 
-#[cfg(any(
-    any(target_arch = "x86", target_arch = "x86_64"),
-    all(target_arch = "aarch64", target_feature = "neon")
-))]
+macro_rules! rgb_xyz_executor {
+    ($src: ident, $dst: ident, ($dependant: ident, $profile: ident)) => {
+        Ok(Box::new($dependant::<
+            T,
+            { Layout::$src as u8 },
+            { Layout::$dst as u8 },
+            LINEAR_CAP,
+            GAMMA_LUT,
+            BIT_DEPTH,
+        > {
+            profile: $profile,
+        }))
+    };
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
 #[allow(unused)]
 macro_rules! create_rgb_xyz_dependant_executor {
     ($dep_name: ident, $dependant: ident) => {
@@ -274,52 +412,12 @@ macro_rules! create_rgb_xyz_dependant_executor {
         where
             u32: AsPrimitive<T>,
         {
-            if (src_layout == Layout::Rgba) && (dst_layout == Layout::Rgba) {
-                return Ok(Box::new($dependant::<
-                    T,
-                    { Layout::Rgba as u8 },
-                    { Layout::Rgba as u8 },
-                    LINEAR_CAP,
-                    GAMMA_LUT,
-                    BIT_DEPTH,
-                > {
-                    profile,
-                }));
-            } else if (src_layout == Layout::Rgb) && (dst_layout == Layout::Rgba) {
-                return Ok(Box::new($dependant::<
-                    T,
-                    { Layout::Rgb as u8 },
-                    { Layout::Rgba as u8 },
-                    LINEAR_CAP,
-                    GAMMA_LUT,
-                    BIT_DEPTH,
-                > {
-                    profile,
-                }));
-            } else if (src_layout == Layout::Rgba) && (dst_layout == Layout::Rgb) {
-                return Ok(Box::new($dependant::<
-                    T,
-                    { Layout::Rgba as u8 },
-                    { Layout::Rgb as u8 },
-                    LINEAR_CAP,
-                    GAMMA_LUT,
-                    BIT_DEPTH,
-                > {
-                    profile,
-                }));
-            } else if (src_layout == Layout::Rgb) && (dst_layout == Layout::Rgb) {
-                return Ok(Box::new($dependant::<
-                    T,
-                    { Layout::Rgb as u8 },
-                    { Layout::Rgb as u8 },
-                    LINEAR_CAP,
-                    GAMMA_LUT,
-                    BIT_DEPTH,
-                > {
-                    profile,
-                }));
-            }
-            Err(CmsError::UnsupportedProfileConnection)
+            dispatch_layouts!(
+                src_layout => [Rgb, Rgba, Bgr, Bgra, Argb, Abgr],
+                dst_layout => [Rgb, Rgba, Bgr, Bgra, Argb, Abgr],
+                Err(CmsError::UnsupportedProfileConnection),
+                rgb_xyz_executor($dependant, profile)
+            )
         }
     };
 }
@@ -342,7 +440,6 @@ create_rgb_xyz_dependant_executor!(
     TransformProfilePcsXYZRgbAvx
 );
 
-#[cfg(not(all(target_arch = "aarch64", target_feature = "neon", feature = "neon")))]
 pub(crate) fn make_rgb_xyz_rgb_transform<
     T: Clone + Send + Sync + PointeeSizeExpressible + 'static + Copy + Default,
     const LINEAR_CAP: usize,
@@ -356,77 +453,120 @@ pub(crate) fn make_rgb_xyz_rgb_transform<
 where
     u32: AsPrimitive<T>,
 {
+    #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+    if KernelFamily::Neon.is_available() && use_kernel(KernelFamily::Neon) {
+        return make_rgb_xyz_rgb_transform_neon::<T, LINEAR_CAP, GAMMA_LUT, BIT_DEPTH>(
+            src_layout, dst_layout, profile,
+        );
+    }
     #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
     {
         #[cfg(feature = "avx")]
-        if std::arch::is_x86_feature_detected!("avx2") {
+        if use_kernel(KernelFamily::Avx2) && std::arch::is_x86_feature_detected!("avx2") {
             return make_rgb_xyz_rgb_transform_avx2::<T, LINEAR_CAP, GAMMA_LUT, BIT_DEPTH>(
                 src_layout, dst_layout, profile,
             );
         }
         #[cfg(feature = "sse")]
-        if std::arch::is_x86_feature_detected!("sse4.1") {
+        if use_kernel(KernelFamily::Sse41) && std::arch::is_x86_feature_detected!("sse4.1") {
             return make_rgb_xyz_rgb_transform_sse_41::<T, LINEAR_CAP, GAMMA_LUT, BIT_DEPTH>(
                 src_layout, dst_layout, profile,
             );
         }
     }
-    if (src_layout == Layout::Rgba) && (dst_layout == Layout::Rgba) {
-        return Ok(Box::new(TransformProfilePcsXYZRgb::<
-            T,
-            { Layout::Rgba as u8 },
-            { Layout::Rgba as u8 },
-            LINEAR_CAP,
-            GAMMA_LUT,
-            BIT_DEPTH,
-        > {
-            profile,
-        }));
-    } else if (src_layout == Layout::Rgb) && (dst_layout == Layout::Rgba) {
-        return Ok(Box::new(TransformProfilePcsXYZRgb::<
-            T,
-            { Layout::Rgb as u8 },
-            { Layout::Rgba as u8 },
-            LINEAR_CAP,
-            GAMMA_LUT,
-            BIT_DEPTH,
-        > {
-            profile,
-        }));
-    } else if (src_layout == Layout::Rgba) && (dst_layout == Layout::Rgb) {
-        return Ok(Box::new(TransformProfilePcsXYZRgb::<
-            T,
-            { Layout::Rgba as u8 },
-            { Layout::Rgb as u8 },
-            LINEAR_CAP,
-            GAMMA_LUT,
-            BIT_DEPTH,
-        > {
-            profile,
-        }));
-    } else if (src_layout == Layout::Rgb) && (dst_layout == Layout::Rgb) {
-        return Ok(Box::new(TransformProfilePcsXYZRgb::<
-            T,
-            { Layout::Rgb as u8 },
-            { Layout::Rgb as u8 },
-            LINEAR_CAP,
-            GAMMA_LUT,
-            BIT_DEPTH,
-        > {
-            profile,
-        }));
-    }
-    Err(CmsError::UnsupportedProfileConnection)
+    dispatch_layouts!(
+        src_layout => [Rgb, Rgba, Bgr, Bgra, Argb, Abgr],
+        dst_layout => [Rgb, Rgba, Bgr, Bgra, Argb, Abgr],
+        Err(CmsError::UnsupportedProfileConnection),
+        rgb_xyz_executor(TransformProfilePcsXYZRgb, profile)
+    )
 }
 
-#[cfg(all(target_arch = "aarch64", target_feature = "neon", feature = "neon"))]
+#[cfg(all(target_arch = "aarch64", feature = "neon"))]
 use crate::conversions::neon::TransformProfilePcsXYZRgbNeon;
 use crate::conversions::rgbxyz_fixed::{TransformProfileRgbFixedPoint, make_rgb_xyz_q4_12};
 use crate::matrix::Matrix3;
 use crate::transform::PointeeSizeExpressible;
 
-#[cfg(all(target_arch = "aarch64", target_feature = "neon", feature = "neon"))]
-create_rgb_xyz_dependant_executor!(make_rgb_xyz_rgb_transform, TransformProfilePcsXYZRgbNeon);
+#[cfg(all(target_arch = "aarch64", feature = "neon"))]
+create_rgb_xyz_dependant_executor!(
+    make_rgb_xyz_rgb_transform_neon,
+    TransformProfilePcsXYZRgbNeon
+);
+
+#[allow(unused)]
+impl<
+    T: Clone + PointeeSizeExpressible + Copy + Default + 'static,
+    const SRC_LAYOUT: u8,
+    const DST_LAYOUT: u8,
+    const LINEAR_CAP: usize,
+    const GAMMA_LUT: usize,
+    const BIT_DEPTH: usize,
+> TransformProfilePcsXYZRgb<T, SRC_LAYOUT, DST_LAYOUT, LINEAR_CAP, GAMMA_LUT, BIT_DEPTH>
+where
+    u32: AsPrimitive<T>,
+{
+    #[inline(always)]
+    fn transform_pixel(&self, transform: &Matrix3f, src: &[T], dst: &mut [T]) {
+        use crate::mlaf::mlaf;
+        let src_cn = Layout::from(SRC_LAYOUT);
+        let dst_cn = Layout::from(DST_LAYOUT);
+        let scale = (GAMMA_LUT - 1) as f32;
+        let max_colors: T = ((1 << BIT_DEPTH) - 1).as_();
+
+        let r = self.profile.r_linear[src[src_cn.r_i()]._as_usize()];
+        let g = self.profile.g_linear[src[src_cn.g_i()]._as_usize()];
+        let b = self.profile.b_linear[src[src_cn.b_i()]._as_usize()];
+        let a = if src_cn.channels() == 4 {
+            src[src_cn.a_i()]
+        } else {
+            max_colors
+        };
+
+        let new_r = mlaf(
+            0.5f32,
+            mlaf(
+                mlaf(r * transform.v[0][0], g, transform.v[0][1]),
+                b,
+                transform.v[0][2],
+            )
+            .max(0f32)
+            .min(1f32),
+            scale,
+        );
+
+        let new_g = mlaf(
+            0.5f32,
+            mlaf(
+                mlaf(r * transform.v[1][0], g, transform.v[1][1]),
+                b,
+                transform.v[1][2],
+            )
+            .max(0f32)
+            .min(1f32),
+            scale,
+        );
+
+        let new_b = mlaf(
+            0.5f32,
+            mlaf(
+                mlaf(r * transform.v[2][0], g, transform.v[2][1]),
+                b,
+                transform.v[2][2],
+            )
+            .max(0f32)
+            .min(1f32),
+            scale,
+        );
+
+        dst[dst_cn.r_i()] = self.profile.r_gamma[(new_r as u16) as usize];
+        dst[dst_cn.g_i()] = self.profile.g_gamma[(new_g as u16) as usize];
+        dst[dst_cn.b_i()] = self.profile.b_gamma[(new_b as u16) as usize];
+        if dst_cn.channels() == 4 {
+            dst[dst_cn.a_i()] = a;
+        }
+    }
+}
 
 #[allow(unused)]
 impl<
@@ -441,8 +581,11 @@ impl<
 where
     u32: AsPrimitive<T>,
 {
+    fn warmup(&self) {
+        self.profile.warmup();
+    }
+
     fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
-        use crate::mlaf::mlaf;
         let src_cn = Layout::from(SRC_LAYOUT);
         let dst_cn = Layout::from(DST_LAYOUT);
         let src_channels = src_cn.channels();
@@ -459,63 +602,39 @@ where
         }
 
         let transform = self.profile.adaptation_matrix.unwrap_or(Matrix3f::IDENTITY);
-        let scale = (GAMMA_LUT - 1) as f32;
-        let max_colors: T = ((1 << BIT_DEPTH) - 1).as_();
 
         for (src, dst) in src
             .chunks_exact(src_channels)
             .zip(dst.chunks_exact_mut(dst_channels))
         {
-            let r = self.profile.r_linear[src[src_cn.r_i()]._as_usize()];
-            let g = self.profile.g_linear[src[src_cn.g_i()]._as_usize()];
-            let b = self.profile.b_linear[src[src_cn.b_i()]._as_usize()];
-            let a = if src_channels == 4 {
-                src[src_cn.a_i()]
-            } else {
-                max_colors
-            };
-
-            let new_r = mlaf(
-                0.5f32,
-                mlaf(
-                    mlaf(r * transform.v[0][0], g, transform.v[0][1]),
-                    b,
-                    transform.v[0][2],
-                )
-                .max(0f32)
-                .min(1f32),
-                scale,
-            );
+            self.transform_pixel(&transform, src, dst);
+        }
 
-            let new_g = mlaf(
-                0.5f32,
-                mlaf(
-                    mlaf(r * transform.v[1][0], g, transform.v[1][1]),
-                    b,
-                    transform.v[1][2],
-                )
-                .max(0f32)
-                .min(1f32),
-                scale,
-            );
+        Ok(())
+    }
 
-            let new_b = mlaf(
-                0.5f32,
-                mlaf(
-                    mlaf(r * transform.v[2][0], g, transform.v[2][1]),
-                    b,
-                    transform.v[2][2],
-                )
-                .max(0f32)
-                .min(1f32),
-                scale,
-            );
+    fn transform_in_place(&self, buf: &mut [T]) -> Result<(), CmsError> {
+        let src_cn = Layout::from(SRC_LAYOUT);
+        let dst_cn = Layout::from(DST_LAYOUT);
+        let channels = src_cn.channels();
+        if channels != dst_cn.channels() {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        if buf.len() % channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+
+        let transform = self.profile.adaptation_matrix.unwrap_or(Matrix3f::IDENTITY);
+        let mut staged = [T::default(); WORKING_LANES];
 
-            dst[dst_cn.r_i()] = self.profile.r_gamma[(new_r as u16) as usize];
-            dst[dst_cn.g_i()] = self.profile.g_gamma[(new_g as u16) as usize];
-            dst[dst_cn.b_i()] = self.profile.b_gamma[(new_b as u16) as usize];
-            if dst_channels == 4 {
-                dst[dst_cn.a_i()] = a;
+        for chunk in buf.chunks_mut(WORKING_LANES) {
+            let staged = &mut staged[..chunk.len()];
+            staged.copy_from_slice(chunk);
+            for (src, dst) in staged
+                .chunks_exact(channels)
+                .zip(chunk.chunks_exact_mut(channels))
+            {
+                self.transform_pixel(&transform, src, dst);
             }
         }
 
@@ -29,6 +29,7 @@
 use crate::Layout;
 use crate::conversions::TransformProfileRgb;
 use crate::matrix::Matrix3;
+use crate::transform::touch_pages;
 use crate::{CmsError, TransformExecutor};
 use num_traits::AsPrimitive;
 
@@ -43,6 +44,17 @@ pub(crate) struct TransformProfileRgbFixedPoint<R, T, const LINEAR_CAP: usize> {
     pub(crate) adaptation_matrix: Matrix3<i16>,
 }
 
+impl<R: Copy, T: Copy, const LINEAR_CAP: usize> TransformProfileRgbFixedPoint<R, T, LINEAR_CAP> {
+    pub(crate) fn warmup(&self) {
+        touch_pages(self.r_linear.as_slice());
+        touch_pages(self.g_linear.as_slice());
+        touch_pages(self.b_linear.as_slice());
+        touch_pages(self.r_gamma.as_slice());
+        touch_pages(self.g_gamma.as_slice());
+        touch_pages(self.b_gamma.as_slice());
+    }
+}
+
 #[allow(unused)]
 struct TransformProfilePcsXYZRgbQ4_12<
     T: Copy,
@@ -78,6 +90,10 @@ impl<
 where
     u32: AsPrimitive<T>,
 {
+    fn warmup(&self) {
+        self.profile.warmup();
+    }
+
     fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
         let src_cn = Layout::from(SRC_LAYOUT);
         let dst_cn = Layout::from(DST_LAYOUT);
@@ -145,6 +161,22 @@ where
     }
 }
 
+macro_rules! rgb_xyz_q4_12_executor {
+    ($src: ident, $dst: ident, ($dependant: ident, $profile: ident)) => {
+        Ok(Box::new($dependant::<
+            T,
+            { Layout::$src as u8 },
+            { Layout::$dst as u8 },
+            LINEAR_CAP,
+            GAMMA_LUT,
+            BIT_DEPTH,
+            PRECISION,
+        > {
+            profile: $profile,
+        }))
+    };
+}
+
 macro_rules! create_rgb_xyz_dependant_q4_12_executor {
     ($dep_name: ident, $dependant: ident, $resolution: ident) => {
         pub(crate) fn $dep_name<
@@ -163,67 +195,26 @@ macro_rules! create_rgb_xyz_dependant_q4_12_executor {
         {
             let q4_12_profile =
                 profile.to_q4_n::<$resolution, PRECISION, LINEAR_CAP, GAMMA_LUT, BIT_DEPTH>();
-            if (src_layout == Layout::Rgba) && (dst_layout == Layout::Rgba) {
-                return Ok(Box::new($dependant::<
-                    T,
-                    { Layout::Rgba as u8 },
-                    { Layout::Rgba as u8 },
-                    LINEAR_CAP,
-                    GAMMA_LUT,
-                    BIT_DEPTH,
-                    PRECISION,
-                > {
-                    profile: q4_12_profile,
-                }));
-            } else if (src_layout == Layout::Rgb) && (dst_layout == Layout::Rgba) {
-                return Ok(Box::new($dependant::<
-                    T,
-                    { Layout::Rgb as u8 },
-                    { Layout::Rgba as u8 },
-                    LINEAR_CAP,
-                    GAMMA_LUT,
-                    BIT_DEPTH,
-                    PRECISION,
-                > {
-                    profile: q4_12_profile,
-                }));
-            } else if (src_layout == Layout::Rgba) && (dst_layout == Layout::Rgb) {
-                return Ok(Box::new($dependant::<
-                    T,
-                    { Layout::Rgba as u8 },
-                    { Layout::Rgb as u8 },
-                    LINEAR_CAP,
-                    GAMMA_LUT,
-                    BIT_DEPTH,
-                    PRECISION,
-                > {
-                    profile: q4_12_profile,
-                }));
-            } else if (src_layout == Layout::Rgb) && (dst_layout == Layout::Rgb) {
-                return Ok(Box::new($dependant::<
-                    T,
-                    { Layout::Rgb as u8 },
-                    { Layout::Rgb as u8 },
-                    LINEAR_CAP,
-                    GAMMA_LUT,
-                    BIT_DEPTH,
-                    PRECISION,
-                > {
-                    profile: q4_12_profile,
-                }));
-            }
-            Err(CmsError::UnsupportedProfileConnection)
+            dispatch_layouts!(
+                src_layout => [Rgb, Rgba, Bgr, Bgra, Argb, Abgr],
+                dst_layout => [Rgb, Rgba, Bgr, Bgra, Argb, Abgr],
+                Err(CmsError::UnsupportedProfileConnection),
+                rgb_xyz_q4_12_executor($dependant, q4_12_profile)
+            )
         }
     };
 }
 
-#[cfg(all(target_arch = "aarch64", target_feature = "neon", feature = "neon"))]
+#[cfg(all(target_arch = "aarch64", feature = "neon"))]
 use crate::conversions::neon::TransformProfileRgbQ12Neon;
 
-#[cfg(all(target_arch = "aarch64", target_feature = "neon", feature = "neon"))]
-create_rgb_xyz_dependant_q4_12_executor!(make_rgb_xyz_q4_12, TransformProfileRgbQ12Neon, i16);
+#[cfg(all(target_arch = "aarch64", feature = "neon"))]
+create_rgb_xyz_dependant_q4_12_executor!(
+    make_rgb_xyz_q4_12_transform_neon,
+    TransformProfileRgbQ12Neon,
+    i16
+);
 
-#[cfg(not(all(target_arch = "aarch64", target_feature = "neon", feature = "neon")))]
 create_rgb_xyz_dependant_q4_12_executor!(make_rgb_xyz_q4_12, TransformProfilePcsXYZRgbQ4_12, i16);
 
 #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "sse"))]
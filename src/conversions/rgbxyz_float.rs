@@ -0,0 +1,190 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::mlaf::mlaf;
+use crate::trc::ToneCurveEvaluator;
+use crate::{ClampPolicy, CmsError, Layout, Matrix3f, TransformExecutor, powf};
+
+/// Matrix-shaper transform parts for floating point data, tone curves are evaluated
+/// on every sample instead of tables lookup.
+pub(crate) struct TransformProfileRgbFloat {
+    pub(crate) r_linear: ToneCurveEvaluator,
+    pub(crate) g_linear: ToneCurveEvaluator,
+    pub(crate) b_linear: ToneCurveEvaluator,
+    pub(crate) r_gamma: ToneCurveEvaluator,
+    pub(crate) g_gamma: ToneCurveEvaluator,
+    pub(crate) b_gamma: ToneCurveEvaluator,
+    pub(crate) adaptation_matrix: Matrix3f,
+    /// Exponent of surround compensation applied to linear values
+    pub(crate) surround_exponent: Option<f32>,
+    pub(crate) clamp_policy: ClampPolicy,
+}
+
+impl TransformProfileRgbFloat {
+    #[inline(always)]
+    fn compensate_surround(&self, v: f32) -> f32 {
+        match self.surround_exponent {
+            Some(exponent) => {
+                if v < 0. {
+                    -powf(-v, exponent)
+                } else {
+                    powf(v, exponent)
+                }
+            }
+            None => v,
+        }
+    }
+
+    #[inline(always)]
+    fn clip(&self, v: f32) -> f32 {
+        match self.clamp_policy {
+            ClampPolicy::ClampToRange => v.max(0.).min(1.),
+            ClampPolicy::PreserveExtended => v,
+        }
+    }
+}
+
+struct TransformProfilePcsXYZRgbFloat<const SRC_LAYOUT: u8, const DST_LAYOUT: u8> {
+    profile: TransformProfileRgbFloat,
+}
+
+macro_rules! rgb_xyz_float_executor {
+    ($src: ident, $dst: ident, ($profile: ident)) => {
+        Ok(Box::new(TransformProfilePcsXYZRgbFloat::<
+            { Layout::$src as u8 },
+            { Layout::$dst as u8 },
+        > {
+            profile: $profile,
+        }))
+    };
+}
+
+pub(crate) fn make_rgb_xyz_rgb_transform_f32(
+    src_layout: Layout,
+    dst_layout: Layout,
+    profile: TransformProfileRgbFloat,
+) -> Result<Box<dyn TransformExecutor<f32> + Send + Sync>, CmsError> {
+    dispatch_layouts!(
+        src_layout => [Rgb, Rgba, Bgr, Bgra, Argb, Abgr],
+        dst_layout => [Rgb, Rgba, Bgr, Bgra, Argb, Abgr],
+        Err(CmsError::UnsupportedProfileConnection),
+        rgb_xyz_float_executor(profile)
+    )
+}
+
+impl<const SRC_LAYOUT: u8, const DST_LAYOUT: u8>
+    TransformProfilePcsXYZRgbFloat<SRC_LAYOUT, DST_LAYOUT>
+{
+    #[inline(always)]
+    fn transform_pixel(&self, src: &[f32], dst: &mut [f32]) {
+        let src_cn = Layout::from(SRC_LAYOUT);
+        let dst_cn = Layout::from(DST_LAYOUT);
+        let profile = &self.profile;
+        let transform = &profile.adaptation_matrix;
+
+        let r = profile.compensate_surround(profile.r_linear.evaluate(src[src_cn.r_i()]));
+        let g = profile.compensate_surround(profile.g_linear.evaluate(src[src_cn.g_i()]));
+        let b = profile.compensate_surround(profile.b_linear.evaluate(src[src_cn.b_i()]));
+        let a = if src_cn.channels() == 4 {
+            src[src_cn.a_i()]
+        } else {
+            1.
+        };
+
+        let new_r = profile.clip(mlaf(
+            mlaf(r * transform.v[0][0], g, transform.v[0][1]),
+            b,
+            transform.v[0][2],
+        ));
+        let new_g = profile.clip(mlaf(
+            mlaf(r * transform.v[1][0], g, transform.v[1][1]),
+            b,
+            transform.v[1][2],
+        ));
+        let new_b = profile.clip(mlaf(
+            mlaf(r * transform.v[2][0], g, transform.v[2][1]),
+            b,
+            transform.v[2][2],
+        ));
+
+        dst[dst_cn.r_i()] = profile.clip(profile.r_gamma.evaluate(new_r));
+        dst[dst_cn.g_i()] = profile.clip(profile.g_gamma.evaluate(new_g));
+        dst[dst_cn.b_i()] = profile.clip(profile.b_gamma.evaluate(new_b));
+        if dst_cn.channels() == 4 {
+            dst[dst_cn.a_i()] = a;
+        }
+    }
+}
+
+impl<const SRC_LAYOUT: u8, const DST_LAYOUT: u8> TransformExecutor<f32>
+    for TransformProfilePcsXYZRgbFloat<SRC_LAYOUT, DST_LAYOUT>
+{
+    fn transform(&self, src: &[f32], dst: &mut [f32]) -> Result<(), CmsError> {
+        let src_cn = Layout::from(SRC_LAYOUT);
+        let dst_cn = Layout::from(DST_LAYOUT);
+        let src_channels = src_cn.channels();
+        let dst_channels = dst_cn.channels();
+
+        if src.len() / src_channels != dst.len() / dst_channels {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        if src.len() % src_channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if dst.len() % dst_channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+
+        for (src, dst) in src
+            .chunks_exact(src_channels)
+            .zip(dst.chunks_exact_mut(dst_channels))
+        {
+            self.transform_pixel(src, dst);
+        }
+
+        Ok(())
+    }
+
+    fn transform_in_place(&self, buf: &mut [f32]) -> Result<(), CmsError> {
+        let channels = Layout::from(SRC_LAYOUT).channels();
+        if channels != Layout::from(DST_LAYOUT).channels() {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        if buf.len() % channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+
+        let mut pixel = [0f32; 4];
+        for chunk in buf.chunks_exact_mut(channels) {
+            pixel[..channels].copy_from_slice(chunk);
+            self.transform_pixel(&pixel[..channels], chunk);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,327 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::mlaf::mlaf;
+use crate::transform::{PointeeSizeExpressible, touch_pages};
+use crate::trc::GammaTableSource;
+use crate::{CmsError, Layout, Matrix3f, TransformExecutor};
+use num_traits::AsPrimitive;
+use std::sync::OnceLock;
+
+/// Entries count of one lazily built part of gamma table.
+pub(crate) const LAZY_GAMMA_SEGMENT: usize = 4096;
+
+/// Gamma table filled in [LAZY_GAMMA_SEGMENT] parts on first access,
+/// so transforms touching a narrow range of values never pay for the whole table.
+///
+/// Segments are computed with exactly the same formulas as eager tables,
+/// thus results of lazy and eager tables are identical.
+pub(crate) struct LazyGammaTable<T, const N: usize, const BIT_DEPTH: usize> {
+    source: Option<GammaTableSource>,
+    segments: Box<[OnceLock<Box<[T; LAZY_GAMMA_SEGMENT]>>]>,
+}
+
+impl<T, const N: usize, const BIT_DEPTH: usize> LazyGammaTable<T, N, BIT_DEPTH>
+where
+    T: Copy + Default + 'static + PointeeSizeExpressible,
+    f32: AsPrimitive<T>,
+{
+    const SEGMENTS: usize = N.div_ceil(LAZY_GAMMA_SEGMENT);
+
+    /// Table computing entries from closed form on demand.
+    pub(crate) fn new(source: GammaTableSource) -> Self {
+        Self {
+            source: Some(source),
+            segments: (0..Self::SEGMENTS).map(|_| OnceLock::new()).collect(),
+        }
+    }
+
+    /// Wraps already built table, used when gamma has no closed form
+    /// and table needs numerical inversion anyway.
+    pub(crate) fn from_table(table: &[T]) -> Self {
+        let segments = (0..Self::SEGMENTS)
+            .map(|i| {
+                let mut segment = Box::new([T::default(); LAZY_GAMMA_SEGMENT]);
+                let start = i * LAZY_GAMMA_SEGMENT;
+                let end = (start + LAZY_GAMMA_SEGMENT).min(N).min(table.len());
+                segment[..end - start].copy_from_slice(&table[start..end]);
+                OnceLock::from(segment)
+            })
+            .collect();
+        Self {
+            source: None,
+            segments,
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn init_segment(&self, index: usize) -> &[T; LAZY_GAMMA_SEGMENT] {
+        self.segments[index].get_or_init(|| {
+            let mut segment = Box::new([T::default(); LAZY_GAMMA_SEGMENT]);
+            // Segments without source are always initialized on construction
+            if let Some(source) = self.source.as_ref() {
+                let start = index * LAZY_GAMMA_SEGMENT;
+                let end = (start + LAZY_GAMMA_SEGMENT).min(N);
+                for (v, dst) in (start..end).zip(segment.iter_mut()) {
+                    *dst = source.entry::<T, N, BIT_DEPTH>(v);
+                }
+            }
+            segment
+        })
+    }
+
+    /// Looks up table entry, once segment is initialized this is an atomic load
+    /// and a well predicted branch on top of regular table lookup.
+    #[inline(always)]
+    pub(crate) fn get(&self, index: usize) -> T {
+        let segment_index = index / LAZY_GAMMA_SEGMENT;
+        let segment = match self.segments[segment_index].get() {
+            Some(segment) => segment,
+            None => self.init_segment(segment_index),
+        };
+        segment[index % LAZY_GAMMA_SEGMENT]
+    }
+
+    /// Builds all segments that are not built yet.
+    pub(crate) fn warmup(&self) {
+        for (i, segment) in self.segments.iter().enumerate() {
+            let segment = match segment.get() {
+                Some(segment) => segment,
+                None => self.init_segment(i),
+            };
+            touch_pages(segment.as_slice());
+        }
+    }
+}
+
+/// Matrix-shaper transform parts with gamma tables built on demand.
+pub(crate) struct TransformProfileRgbLazy<
+    T,
+    const LINEAR_CAP: usize,
+    const GAMMA_LUT: usize,
+    const BIT_DEPTH: usize,
+> {
+    pub(crate) r_linear: Box<[f32; LINEAR_CAP]>,
+    pub(crate) g_linear: Box<[f32; LINEAR_CAP]>,
+    pub(crate) b_linear: Box<[f32; LINEAR_CAP]>,
+    pub(crate) r_gamma: LazyGammaTable<T, GAMMA_LUT, BIT_DEPTH>,
+    pub(crate) g_gamma: LazyGammaTable<T, GAMMA_LUT, BIT_DEPTH>,
+    pub(crate) b_gamma: LazyGammaTable<T, GAMMA_LUT, BIT_DEPTH>,
+    pub(crate) adaptation_matrix: Matrix3f,
+}
+
+struct TransformProfilePcsXYZRgbLazy<
+    T,
+    const SRC_LAYOUT: u8,
+    const DST_LAYOUT: u8,
+    const LINEAR_CAP: usize,
+    const GAMMA_LUT: usize,
+    const BIT_DEPTH: usize,
+> {
+    profile: TransformProfileRgbLazy<T, LINEAR_CAP, GAMMA_LUT, BIT_DEPTH>,
+}
+
+macro_rules! rgb_xyz_lazy_executor {
+    ($src: ident, $dst: ident, ($profile: ident)) => {
+        Ok(Box::new(TransformProfilePcsXYZRgbLazy::<
+            T,
+            { Layout::$src as u8 },
+            { Layout::$dst as u8 },
+            LINEAR_CAP,
+            GAMMA_LUT,
+            BIT_DEPTH,
+        > {
+            profile: $profile,
+        }))
+    };
+}
+
+pub(crate) fn make_rgb_xyz_rgb_transform_lazy<
+    T: Copy + Default + Send + Sync + PointeeSizeExpressible + 'static,
+    const LINEAR_CAP: usize,
+    const GAMMA_LUT: usize,
+    const BIT_DEPTH: usize,
+>(
+    src_layout: Layout,
+    dst_layout: Layout,
+    profile: TransformProfileRgbLazy<T, LINEAR_CAP, GAMMA_LUT, BIT_DEPTH>,
+) -> Result<Box<dyn TransformExecutor<T> + Send + Sync>, CmsError>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    dispatch_layouts!(
+        src_layout => [Rgb, Rgba, Bgr, Bgra, Argb, Abgr],
+        dst_layout => [Rgb, Rgba, Bgr, Bgra, Argb, Abgr],
+        Err(CmsError::UnsupportedProfileConnection),
+        rgb_xyz_lazy_executor(profile)
+    )
+}
+
+impl<
+    T: Copy + Default + PointeeSizeExpressible + 'static,
+    const SRC_LAYOUT: u8,
+    const DST_LAYOUT: u8,
+    const LINEAR_CAP: usize,
+    const GAMMA_LUT: usize,
+    const BIT_DEPTH: usize,
+> TransformProfilePcsXYZRgbLazy<T, SRC_LAYOUT, DST_LAYOUT, LINEAR_CAP, GAMMA_LUT, BIT_DEPTH>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    #[inline(always)]
+    fn transform_pixel(&self, src: &[T], dst: &mut [T]) {
+        let src_cn = Layout::from(SRC_LAYOUT);
+        let dst_cn = Layout::from(DST_LAYOUT);
+        let profile = &self.profile;
+        let transform = &profile.adaptation_matrix;
+        let scale = (GAMMA_LUT - 1) as f32;
+        let max_colors: T = ((1 << BIT_DEPTH) - 1).as_();
+
+        let r = profile.r_linear[src[src_cn.r_i()]._as_usize()];
+        let g = profile.g_linear[src[src_cn.g_i()]._as_usize()];
+        let b = profile.b_linear[src[src_cn.b_i()]._as_usize()];
+        let a = if src_cn.channels() == 4 {
+            src[src_cn.a_i()]
+        } else {
+            max_colors
+        };
+
+        let new_r = mlaf(
+            0.5f32,
+            mlaf(
+                mlaf(r * transform.v[0][0], g, transform.v[0][1]),
+                b,
+                transform.v[0][2],
+            )
+            .max(0f32)
+            .min(1f32),
+            scale,
+        );
+
+        let new_g = mlaf(
+            0.5f32,
+            mlaf(
+                mlaf(r * transform.v[1][0], g, transform.v[1][1]),
+                b,
+                transform.v[1][2],
+            )
+            .max(0f32)
+            .min(1f32),
+            scale,
+        );
+
+        let new_b = mlaf(
+            0.5f32,
+            mlaf(
+                mlaf(r * transform.v[2][0], g, transform.v[2][1]),
+                b,
+                transform.v[2][2],
+            )
+            .max(0f32)
+            .min(1f32),
+            scale,
+        );
+
+        dst[dst_cn.r_i()] = profile.r_gamma.get((new_r as u16) as usize);
+        dst[dst_cn.g_i()] = profile.g_gamma.get((new_g as u16) as usize);
+        dst[dst_cn.b_i()] = profile.b_gamma.get((new_b as u16) as usize);
+        if dst_cn.channels() == 4 {
+            dst[dst_cn.a_i()] = a;
+        }
+    }
+}
+
+impl<
+    T: Copy + Default + PointeeSizeExpressible + 'static,
+    const SRC_LAYOUT: u8,
+    const DST_LAYOUT: u8,
+    const LINEAR_CAP: usize,
+    const GAMMA_LUT: usize,
+    const BIT_DEPTH: usize,
+> TransformExecutor<T>
+    for TransformProfilePcsXYZRgbLazy<T, SRC_LAYOUT, DST_LAYOUT, LINEAR_CAP, GAMMA_LUT, BIT_DEPTH>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    fn warmup(&self) {
+        touch_pages(self.profile.r_linear.as_slice());
+        touch_pages(self.profile.g_linear.as_slice());
+        touch_pages(self.profile.b_linear.as_slice());
+        self.profile.r_gamma.warmup();
+        self.profile.g_gamma.warmup();
+        self.profile.b_gamma.warmup();
+    }
+
+    fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
+        let src_cn = Layout::from(SRC_LAYOUT);
+        let dst_cn = Layout::from(DST_LAYOUT);
+        let src_channels = src_cn.channels();
+        let dst_channels = dst_cn.channels();
+
+        if src.len() / src_channels != dst.len() / dst_channels {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        if src.len() % src_channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if dst.len() % dst_channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+
+        for (src, dst) in src
+            .chunks_exact(src_channels)
+            .zip(dst.chunks_exact_mut(dst_channels))
+        {
+            self.transform_pixel(src, dst);
+        }
+
+        Ok(())
+    }
+
+    fn transform_in_place(&self, buf: &mut [T]) -> Result<(), CmsError> {
+        let channels = Layout::from(SRC_LAYOUT).channels();
+        if channels != Layout::from(DST_LAYOUT).channels() {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        if buf.len() % channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+
+        let mut pixel = [T::default(); 4];
+        for chunk in buf.chunks_exact_mut(channels) {
+            pixel[..channels].copy_from_slice(chunk);
+            self.transform_pixel(&pixel[..channels], chunk);
+        }
+
+        Ok(())
+    }
+}
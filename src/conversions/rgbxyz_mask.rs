@@ -0,0 +1,112 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::mlaf::mlaf;
+use crate::{
+    CmsError, ColorProfile, GamutCheck8BitExecutor, GamutCheckExecutor, Layout, Matrix3f,
+    TransformOptions,
+};
+
+/// Linear values this far outside of [0, 1] are considered out of gamut,
+/// smaller excursions are matrix rounding noise.
+const GAMUT_EPSILON: f32 = 1e-4;
+
+/// Checks source pixels against gamut of matrix-shaper RGB destination,
+/// color is out of gamut when any linear destination channel leaves [0, 1].
+struct RgbXyzGamutCheck {
+    r_linear: Box<[f32; 256]>,
+    g_linear: Box<[f32; 256]>,
+    b_linear: Box<[f32; 256]>,
+    matrix: Matrix3f,
+    layout: Layout,
+}
+
+impl GamutCheckExecutor<u8> for RgbXyzGamutCheck {
+    fn check(&self, src: &[u8], mask: &mut [u8]) -> Result<(), CmsError> {
+        let channels = self.layout.channels();
+        if src.len() % channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if mask.len() != src.len() / channels {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+
+        let m = &self.matrix.v;
+        for (src, mask) in src.chunks_exact(channels).zip(mask.iter_mut()) {
+            let r = self.r_linear[src[self.layout.r_i()] as usize];
+            let g = self.g_linear[src[self.layout.g_i()] as usize];
+            let b = self.b_linear[src[self.layout.b_i()] as usize];
+
+            let out_of_gamut = m.iter().any(|row| {
+                let v = mlaf(mlaf(r * row[0], g, row[1]), b, row[2]);
+                !(-GAMUT_EPSILON..=1. + GAMUT_EPSILON).contains(&v)
+            });
+            *mask = if out_of_gamut { 255 } else { 0 };
+        }
+
+        Ok(())
+    }
+}
+
+/// Makes 8 bit gamut check of `source` pixels against matrix-shaper RGB `dest`.
+pub(crate) fn make_rgb_xyz_gamut_check(
+    layout: Layout,
+    source: &ColorProfile,
+    dest: &ColorProfile,
+    options: TransformOptions,
+) -> Result<Box<GamutCheck8BitExecutor>, CmsError> {
+    if !matches!(
+        layout,
+        Layout::Rgb | Layout::Rgba | Layout::Bgr | Layout::Bgra | Layout::Argb | Layout::Abgr
+    ) {
+        return Err(CmsError::InvalidLayout);
+    }
+    let use_cicp = options.allow_use_cicp_transfer;
+    let mut r_linear = source.build_r_linearize_table::<u8, 256, 8>(use_cicp)?;
+    let mut g_linear = source.build_g_linearize_table::<u8, 256, 8>(use_cicp)?;
+    let mut b_linear = source.build_b_linearize_table::<u8, 256, 8>(use_cicp)?;
+    if let Some(surround) = options.surround_compensation {
+        surround.apply_to_table(&mut r_linear);
+        surround.apply_to_table(&mut g_linear);
+        surround.apply_to_table(&mut b_linear);
+    }
+    let matrix = source
+        .transform_matrix_with_adaptation(
+            dest,
+            options.rendering_intent,
+            options.chromatic_adaptation,
+        )
+        .unwrap_or(Matrix3f::IDENTITY);
+    Ok(Box::new(RgbXyzGamutCheck {
+        r_linear,
+        g_linear,
+        b_linear,
+        matrix,
+        layout,
+    }))
+}
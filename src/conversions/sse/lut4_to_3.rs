@@ -30,10 +30,11 @@ use crate::conversions::CompressForLut;
 use crate::conversions::lut_transforms::{LUT_SAMPLING, Lut4x3Factory};
 use crate::conversions::sse::TetrahedralSse;
 use crate::conversions::sse::interpolator::{
-    PrismaticSse, PyramidalSse, SseAlignedF32, SseMdInterpolation,
+    PrismaticSse, PyramidalSse, SseAlignedF32, SseMdInterpolation, TrilinearSse,
 };
-use crate::transform::PointeeSizeExpressible;
-use crate::{CmsError, InterpolationMethod, Layout, TransformExecutor, rounding_div_ceil};
+use crate::math::rounding_div_ceil;
+use crate::transform::{PointeeSizeExpressible, touch_pages};
+use crate::{CmsError, InterpolationMethod, Layout, TransformExecutor};
 use num_traits::AsPrimitive;
 #[cfg(target_arch = "x86")]
 use std::arch::x86::*;
@@ -142,6 +143,10 @@ where
     f32: AsPrimitive<T>,
     u32: AsPrimitive<T>,
 {
+    fn warmup(&self) {
+        touch_pages(&self.lut);
+    }
+
     fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
         let cn = Layout::from(LAYOUT);
         let channels = cn.channels();
@@ -168,7 +173,9 @@ where
                 InterpolationMethod::Prism => {
                     self.transform_chunk::<PrismaticSse<GRID_SIZE>>(src, dst);
                 }
-                InterpolationMethod::Linear => {}
+                InterpolationMethod::Linear => {
+                    self.transform_chunk::<TrilinearSse<GRID_SIZE>>(src, dst);
+                }
             }
         }
 
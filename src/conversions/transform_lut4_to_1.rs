@@ -0,0 +1,148 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::conversions::CompressForLut;
+use crate::conversions::interpolator::{
+    MultidimensionalInterpolation, Prismatic, Pyramidal, Tetrahedral, Trilinear,
+};
+use crate::conversions::lut_transforms::LUT_SAMPLING;
+use crate::transform::{PointeeSizeExpressible, touch_pages};
+use crate::{CmsError, InterpolationMethod, Layout, TransformExecutor};
+use num_traits::AsPrimitive;
+use std::marker::PhantomData;
+
+/// Executes 4 channels to a single channel LUT, output is written as gray.
+pub(crate) struct TransformLut4x1<
+    T,
+    const LAYOUT: u8,
+    const GRID_SIZE: usize,
+    const BIT_DEPTH: usize,
+> {
+    pub(crate) lut: Vec<f32>,
+    pub(crate) _phantom: PhantomData<T>,
+    pub(crate) interpolation_method: InterpolationMethod,
+}
+
+impl<
+    T: Copy + AsPrimitive<f32> + Default + CompressForLut + PointeeSizeExpressible,
+    const LAYOUT: u8,
+    const GRID_SIZE: usize,
+    const BIT_DEPTH: usize,
+> TransformLut4x1<T, LAYOUT, GRID_SIZE, BIT_DEPTH>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    #[inline(always)]
+    fn transform_chunk<'k, Interpolator: MultidimensionalInterpolation<'k, GRID_SIZE>>(
+        &'k self,
+        src: &[T],
+        dst: &mut [T],
+    ) {
+        let cn = Layout::from(LAYOUT);
+        let channels = cn.channels();
+        let grid_size = GRID_SIZE as i32;
+        let grid_size3 = grid_size * grid_size * grid_size;
+
+        let value_scale = ((1 << BIT_DEPTH) - 1) as f32;
+        let max_value = ((1 << BIT_DEPTH) - 1u32).as_();
+
+        for (src, dst) in src.chunks_exact(4).zip(dst.chunks_exact_mut(channels)) {
+            let c = src[0].compress_lut::<BIT_DEPTH>();
+            let m = src[1].compress_lut::<BIT_DEPTH>();
+            let y = src[2].compress_lut::<BIT_DEPTH>();
+            let k = src[3].compress_lut::<BIT_DEPTH>();
+            let linear_k: f32 = k as i32 as f32 * (1. / LUT_SAMPLING as f32);
+            let w: i32 = k as i32 * (GRID_SIZE as i32 - 1) / LUT_SAMPLING as i32;
+            let w_n: i32 = (w + 1).min(GRID_SIZE as i32 - 1);
+            let t: f32 = linear_k * (GRID_SIZE as i32 - 1) as f32 - w as f32;
+
+            let table1 = &self.lut[(w * grid_size3) as usize..];
+            let table2 = &self.lut[(w_n * grid_size3) as usize..];
+
+            let v1 = Interpolator::new(table1).inter1(c, m, y);
+            let v2 = Interpolator::new(table2).inter1(c, m, y);
+            let v = v1 * (1. - t) + v2 * t;
+            let r = if T::FINITE {
+                v * value_scale + 0.5f32
+            } else {
+                v
+            };
+            dst[0] = r.min(value_scale).max(0f32).as_();
+            if channels == 2 {
+                dst[cn.a_i()] = max_value;
+            }
+        }
+    }
+}
+
+impl<
+    T: Copy + AsPrimitive<f32> + Default + CompressForLut + PointeeSizeExpressible,
+    const LAYOUT: u8,
+    const GRID_SIZE: usize,
+    const BIT_DEPTH: usize,
+> TransformExecutor<T> for TransformLut4x1<T, LAYOUT, GRID_SIZE, BIT_DEPTH>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    fn warmup(&self) {
+        touch_pages(&self.lut);
+    }
+
+    fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
+        let cn = Layout::from(LAYOUT);
+        let channels = cn.channels();
+        if src.len() % 4 != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if dst.len() % channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if src.len() / 4 != dst.len() / channels {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+
+        match self.interpolation_method {
+            InterpolationMethod::Tetrahedral => {
+                self.transform_chunk::<Tetrahedral<GRID_SIZE>>(src, dst);
+            }
+            InterpolationMethod::Pyramid => {
+                self.transform_chunk::<Pyramidal<GRID_SIZE>>(src, dst);
+            }
+            InterpolationMethod::Prism => {
+                self.transform_chunk::<Prismatic<GRID_SIZE>>(src, dst);
+            }
+            InterpolationMethod::Linear => {
+                self.transform_chunk::<Trilinear<GRID_SIZE>>(src, dst);
+            }
+        }
+
+        Ok(())
+    }
+}
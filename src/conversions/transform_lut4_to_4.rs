@@ -32,6 +32,7 @@ use crate::conversions::interpolator::{
 };
 use crate::conversions::lut_transforms::{LUT_SAMPLING, Lut4x3Factory};
 use crate::math::{FusedMultiplyAdd, m_clamp};
+use crate::transform::touch_pages;
 use crate::{
     CmsError, InterpolationMethod, Layout, PointeeSizeExpressible, TransformExecutor, Vector3f,
 };
@@ -149,6 +150,10 @@ where
     f32: AsPrimitive<T>,
     u32: AsPrimitive<T>,
 {
+    fn warmup(&self) {
+        touch_pages(&self.lut);
+    }
+
     fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
         let cn = Layout::from(LAYOUT);
         let channels = cn.channels();
@@ -224,3 +229,99 @@ impl Lut4x3Factory for DefaultLut4x3Factory {
         }
     }
 }
+
+/// Executes 4 channels to 4 channels LUT, both sides use CMYK storage.
+pub(crate) struct TransformLut4x4<T, const GRID_SIZE: usize, const BIT_DEPTH: usize> {
+    pub(crate) lut: Vec<f32>,
+    pub(crate) _phantom: PhantomData<T>,
+    pub(crate) interpolation_method: InterpolationMethod,
+}
+
+impl<
+    T: Copy + AsPrimitive<f32> + Default + CompressForLut + PointeeSizeExpressible,
+    const GRID_SIZE: usize,
+    const BIT_DEPTH: usize,
+> TransformLut4x4<T, GRID_SIZE, BIT_DEPTH>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    #[inline(always)]
+    fn transform_chunk<'k, Interpolator: MultidimensionalInterpolation<'k, GRID_SIZE>>(
+        &'k self,
+        src: &[T],
+        dst: &mut [T],
+    ) {
+        let grid_size = GRID_SIZE as i32;
+        let grid_size3 = grid_size * grid_size * grid_size;
+
+        let value_scale = ((1 << BIT_DEPTH) - 1) as f32;
+
+        for (src, dst) in src.chunks_exact(4).zip(dst.chunks_exact_mut(4)) {
+            let c = src[0].compress_lut::<BIT_DEPTH>();
+            let m = src[1].compress_lut::<BIT_DEPTH>();
+            let y = src[2].compress_lut::<BIT_DEPTH>();
+            let k = src[3].compress_lut::<BIT_DEPTH>();
+            let linear_k: f32 = k as i32 as f32 * (1. / LUT_SAMPLING as f32);
+            let w: i32 = k as i32 * (GRID_SIZE as i32 - 1) / LUT_SAMPLING as i32;
+            let w_n: i32 = (w + 1).min(GRID_SIZE as i32 - 1);
+            let t: f32 = linear_k * (GRID_SIZE as i32 - 1) as f32 - w as f32;
+
+            let table1 = &self.lut[(w * grid_size3 * 4) as usize..];
+            let table2 = &self.lut[(w_n * grid_size3 * 4) as usize..];
+
+            let v1 = Interpolator::new(table1).inter4(c, m, y);
+            let v2 = Interpolator::new(table2).inter4(c, m, y);
+            let v = v1 * (1. - t) + v2 * t;
+            let r = if T::FINITE {
+                v * value_scale + 0.5f32
+            } else {
+                v
+            };
+            dst[0] = r.v[0].min(value_scale).max(0f32).as_();
+            dst[1] = r.v[1].min(value_scale).max(0f32).as_();
+            dst[2] = r.v[2].min(value_scale).max(0f32).as_();
+            dst[3] = r.v[3].min(value_scale).max(0f32).as_();
+        }
+    }
+}
+
+impl<
+    T: Copy + AsPrimitive<f32> + Default + CompressForLut + PointeeSizeExpressible,
+    const GRID_SIZE: usize,
+    const BIT_DEPTH: usize,
+> TransformExecutor<T> for TransformLut4x4<T, GRID_SIZE, BIT_DEPTH>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    fn warmup(&self) {
+        touch_pages(&self.lut);
+    }
+
+    fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
+        if src.len() % 4 != 0 || dst.len() % 4 != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if src.len() != dst.len() {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+
+        match self.interpolation_method {
+            InterpolationMethod::Tetrahedral => {
+                self.transform_chunk::<Tetrahedral<GRID_SIZE>>(src, dst);
+            }
+            InterpolationMethod::Pyramid => {
+                self.transform_chunk::<Pyramidal<GRID_SIZE>>(src, dst);
+            }
+            InterpolationMethod::Prism => {
+                self.transform_chunk::<Prismatic<GRID_SIZE>>(src, dst);
+            }
+            InterpolationMethod::Linear => {
+                self.transform_chunk::<Trilinear<GRID_SIZE>>(src, dst);
+            }
+        }
+
+        Ok(())
+    }
+}
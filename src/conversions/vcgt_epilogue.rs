@@ -0,0 +1,193 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::conversions::LutSampler;
+use crate::conversions::ycbcr_epilogue::EPILOGUE_CHUNK;
+use crate::transform::{PointeeSizeExpressible, TransformPlan};
+use crate::{CmsError, Layout, TransformExecutor, Vcgt};
+use num_traits::AsPrimitive;
+
+enum VcgtRamps<T> {
+    /// Code to code tables for integer destinations
+    Tables([Vec<T>; 3]),
+    /// Ramp evaluated for each value of floating point destinations
+    Evaluate(Vcgt),
+}
+
+/// Applies video card gamma ramp to destination RGB written by inner executor.
+struct TransformVcgtEpilogue<T> {
+    executor: Box<dyn TransformExecutor<T> + Send + Sync>,
+    src_channels: usize,
+    dst_layout: Layout,
+    ramps: VcgtRamps<T>,
+}
+
+impl<T: Copy + Default + AsPrimitive<f32> + AsPrimitive<usize> + Send + Sync>
+    TransformVcgtEpilogue<T>
+where
+    f32: AsPrimitive<T>,
+{
+    #[inline(always)]
+    fn apply(&self, dst: &mut [T]) {
+        let channels = [
+            self.dst_layout.r_i(),
+            self.dst_layout.g_i(),
+            self.dst_layout.b_i(),
+        ];
+        for pixel in dst.chunks_exact_mut(self.dst_layout.channels()) {
+            for (ramp, &index) in channels.iter().enumerate() {
+                pixel[index] = match &self.ramps {
+                    VcgtRamps::Tables(tables) => {
+                        let code: usize = pixel[index].as_();
+                        tables[ramp][code]
+                    }
+                    VcgtRamps::Evaluate(vcgt) => vcgt.evaluate(ramp, pixel[index].as_()).as_(),
+                };
+            }
+        }
+    }
+}
+
+impl<T: Copy + Default + AsPrimitive<f32> + AsPrimitive<usize> + Send + Sync> TransformExecutor<T>
+    for TransformVcgtEpilogue<T>
+where
+    f32: AsPrimitive<T>,
+{
+    fn warmup(&self) {
+        self.executor.warmup();
+    }
+
+    fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
+        let dst_channels = self.dst_layout.channels();
+        if src.len() % self.src_channels != 0 || dst.len() % dst_channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if src.len() / self.src_channels != dst.len() / dst_channels {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        for (src, dst) in src
+            .chunks(EPILOGUE_CHUNK * self.src_channels)
+            .zip(dst.chunks_mut(EPILOGUE_CHUNK * dst_channels))
+        {
+            self.executor.transform(src, dst)?;
+            self.apply(dst);
+        }
+        Ok(())
+    }
+}
+
+/// Forwards sampling and adds ramp to the final executor.
+struct VcgtSampler<T: Copy + Default> {
+    sampler: Box<dyn LutSampler<T>>,
+    src_channels: usize,
+    dst_layout: Layout,
+    ramps: VcgtRamps<T>,
+}
+
+impl<T: Copy + Default + AsPrimitive<f32> + AsPrimitive<usize> + Send + Sync + 'static>
+    LutSampler<T> for VcgtSampler<T>
+where
+    f32: AsPrimitive<T>,
+{
+    fn grid_points(&self) -> usize {
+        self.sampler.grid_points()
+    }
+
+    fn output_channels(&self) -> usize {
+        self.sampler.output_channels()
+    }
+
+    fn sample(&self, start: usize, end: usize, dst: &mut [f32]) -> Result<(), CmsError> {
+        self.sampler.sample(start, end, dst)
+    }
+
+    fn make_executor(
+        self: Box<Self>,
+        lut: Vec<f32>,
+    ) -> Box<dyn TransformExecutor<T> + Send + Sync> {
+        Box::new(TransformVcgtEpilogue {
+            executor: self.sampler.make_executor(lut),
+            src_channels: self.src_channels,
+            dst_layout: self.dst_layout,
+            ramps: self.ramps,
+        })
+    }
+}
+
+/// Appends video card gamma ramp of destination RGB to the planned transform.
+pub(crate) fn append_vcgt_epilogue<
+    T: Copy
+        + Default
+        + AsPrimitive<f32>
+        + AsPrimitive<usize>
+        + PointeeSizeExpressible
+        + Send
+        + Sync
+        + 'static,
+    const BIT_DEPTH: usize,
+>(
+    plan: TransformPlan<T>,
+    src_layout: Layout,
+    dst_layout: Layout,
+    vcgt: &Vcgt,
+) -> Result<TransformPlan<T>, CmsError>
+where
+    f32: AsPrimitive<T>,
+{
+    if dst_layout == Layout::Gray || dst_layout == Layout::GrayAlpha {
+        return Err(CmsError::InvalidLayout);
+    }
+    let ramps = if T::FINITE {
+        let max_value = ((1u32 << BIT_DEPTH) - 1) as f32;
+        VcgtRamps::Tables(std::array::from_fn(|ramp| {
+            (0..=max_value as usize)
+                .map(|code| {
+                    let v = vcgt.evaluate(ramp, code as f32 / max_value);
+                    (v * max_value).round().as_()
+                })
+                .collect()
+        }))
+    } else {
+        VcgtRamps::Evaluate(vcgt.clone())
+    };
+    let src_channels = src_layout.channels();
+    Ok(match plan {
+        TransformPlan::Ready(executor) => TransformPlan::Ready(Box::new(TransformVcgtEpilogue {
+            executor,
+            src_channels,
+            dst_layout,
+            ramps,
+        })),
+        TransformPlan::Lut(sampler) => TransformPlan::Lut(Box::new(VcgtSampler {
+            sampler,
+            src_channels,
+            dst_layout,
+            ramps,
+        })),
+    })
+}
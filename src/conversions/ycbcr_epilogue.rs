@@ -0,0 +1,206 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::conversions::LutSampler;
+use crate::transform::{PointeeSizeExpressible, TransformPlan};
+use crate::{CmsError, Layout, TransformExecutor, YCbCrMatrix};
+use num_traits::AsPrimitive;
+use std::marker::PhantomData;
+
+/// Pixels converted at once, small enough to keep them in cache
+/// between the transform and the epilogue
+pub(crate) const EPILOGUE_CHUNK: usize = 2048;
+
+impl YCbCrMatrix {
+    /// Returns Kr and Kb coefficients
+    #[inline]
+    const fn kr_kb(self) -> (f64, f64) {
+        match self {
+            YCbCrMatrix::Bt601 => (0.299, 0.114),
+            YCbCrMatrix::Bt709 => (0.2126, 0.0722),
+        }
+    }
+}
+
+/// Converts device RGB written by inner executor into full range YCbCr in place.
+struct TransformYCbCrEpilogue<T, const BIT_DEPTH: usize> {
+    executor: Box<dyn TransformExecutor<T> + Send + Sync>,
+    src_channels: usize,
+    dst_layout: Layout,
+    matrix: YCbCrMatrix,
+    _phantom: PhantomData<T>,
+}
+
+impl<
+    T: Copy + Default + AsPrimitive<f32> + PointeeSizeExpressible + Send + Sync,
+    const BIT_DEPTH: usize,
+> TransformYCbCrEpilogue<T, BIT_DEPTH>
+where
+    f32: AsPrimitive<T>,
+{
+    /// Computed in double precision, so ties are rounded exactly as reference conversion does.
+    #[inline(always)]
+    fn encode(&self, dst: &mut [T]) {
+        let (kr, kb) = self.matrix.kr_kb();
+        let kg = 1. - kr - kb;
+        let cb_scale = 2. * (1. - kb);
+        let cr_scale = 2. * (1. - kr);
+        let (max_value, bias) = if T::FINITE {
+            (
+                ((1u32 << BIT_DEPTH) - 1) as f64,
+                (1u32 << (BIT_DEPTH - 1)) as f64,
+            )
+        } else {
+            (1., 0.5)
+        };
+        let quantize = |v: f64| -> T {
+            let v = if T::FINITE { v.round() } else { v };
+            (v.min(max_value).max(0.) as f32).as_()
+        };
+        let channels = self.dst_layout.channels();
+        for pixel in dst.chunks_exact_mut(channels) {
+            let r: f32 = pixel[0].as_();
+            let g: f32 = pixel[1].as_();
+            let b: f32 = pixel[2].as_();
+            let (r, g, b) = (r as f64, g as f64, b as f64);
+            let y = kr * r + kg * g + kb * b;
+            let cb = (b - y) / cb_scale + bias;
+            let cr = (r - y) / cr_scale + bias;
+            pixel[0] = quantize(y);
+            pixel[1] = quantize(cb);
+            pixel[2] = quantize(cr);
+        }
+    }
+}
+
+impl<
+    T: Copy + Default + AsPrimitive<f32> + PointeeSizeExpressible + Send + Sync,
+    const BIT_DEPTH: usize,
+> TransformExecutor<T> for TransformYCbCrEpilogue<T, BIT_DEPTH>
+where
+    f32: AsPrimitive<T>,
+{
+    fn warmup(&self) {
+        self.executor.warmup();
+    }
+
+    fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
+        let dst_channels = self.dst_layout.channels();
+        if src.len() % self.src_channels != 0 || dst.len() % dst_channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if src.len() / self.src_channels != dst.len() / dst_channels {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        for (src, dst) in src
+            .chunks(EPILOGUE_CHUNK * self.src_channels)
+            .zip(dst.chunks_mut(EPILOGUE_CHUNK * dst_channels))
+        {
+            self.executor.transform(src, dst)?;
+            self.encode(dst);
+        }
+        Ok(())
+    }
+}
+
+/// Forwards sampling and adds YCbCr epilogue to the final executor.
+struct YCbCrSampler<T: Copy + Default, const BIT_DEPTH: usize> {
+    sampler: Box<dyn LutSampler<T>>,
+    src_channels: usize,
+    dst_layout: Layout,
+    matrix: YCbCrMatrix,
+}
+
+impl<
+    T: Copy + Default + AsPrimitive<f32> + PointeeSizeExpressible + Send + Sync + 'static,
+    const BIT_DEPTH: usize,
+> LutSampler<T> for YCbCrSampler<T, BIT_DEPTH>
+where
+    f32: AsPrimitive<T>,
+{
+    fn grid_points(&self) -> usize {
+        self.sampler.grid_points()
+    }
+
+    fn output_channels(&self) -> usize {
+        self.sampler.output_channels()
+    }
+
+    fn sample(&self, start: usize, end: usize, dst: &mut [f32]) -> Result<(), CmsError> {
+        self.sampler.sample(start, end, dst)
+    }
+
+    fn make_executor(
+        self: Box<Self>,
+        lut: Vec<f32>,
+    ) -> Box<dyn TransformExecutor<T> + Send + Sync> {
+        Box::new(TransformYCbCrEpilogue::<T, BIT_DEPTH> {
+            executor: self.sampler.make_executor(lut),
+            src_channels: self.src_channels,
+            dst_layout: self.dst_layout,
+            matrix: self.matrix,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+/// Appends conversion of destination RGB into YCbCr to the planned transform.
+pub(crate) fn append_ycbcr_epilogue<
+    T: Copy + Default + AsPrimitive<f32> + PointeeSizeExpressible + Send + Sync + 'static,
+    const BIT_DEPTH: usize,
+>(
+    plan: TransformPlan<T>,
+    src_layout: Layout,
+    dst_layout: Layout,
+    matrix: YCbCrMatrix,
+) -> Result<TransformPlan<T>, CmsError>
+where
+    f32: AsPrimitive<T>,
+{
+    if dst_layout != Layout::Rgb && dst_layout != Layout::Rgba {
+        return Err(CmsError::InvalidLayout);
+    }
+    let src_channels = src_layout.channels();
+    Ok(match plan {
+        TransformPlan::Ready(executor) => {
+            TransformPlan::Ready(Box::new(TransformYCbCrEpilogue::<T, BIT_DEPTH> {
+                executor,
+                src_channels,
+                dst_layout,
+                matrix,
+                _phantom: PhantomData,
+            }))
+        }
+        TransformPlan::Lut(sampler) => TransformPlan::Lut(Box::new(YCbCrSampler::<T, BIT_DEPTH> {
+            sampler,
+            src_channels,
+            dst_layout,
+            matrix,
+        })),
+    })
+}
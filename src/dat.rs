@@ -27,7 +27,7 @@
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 use crate::CmsError;
-use crate::writer::write_u16_be;
+use crate::writer::{ByteSink, write_u16_be};
 use chrono::{Datelike, Timelike, Utc};
 
 #[repr(C)]
@@ -77,7 +77,7 @@ impl ColorDateTime {
     }
 
     #[inline]
-    pub(crate) fn encode(&self, into: &mut Vec<u8>) {
+    pub(crate) fn encode(&self, into: &mut impl ByteSink) {
         let year = self.year;
         let month = self.month;
         let day_of_the_month = self.day_of_the_month;
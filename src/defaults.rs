@@ -28,6 +28,7 @@
  */
 use crate::math::copysign;
 use crate::trc::{ToneReprCurve, curve_from_gamma};
+use crate::writer::FloatToFixedS15Fixed16;
 use crate::{
     CicpColorPrimaries, CicpProfile, ColorPrimaries, ColorProfile, DataColorSpace,
     LocalizableString, Matrix3f, MatrixCoefficients, ProfileClass, ProfileText, RenderingIntent,
@@ -398,6 +399,25 @@ impl ColorProfile {
         profile
     }
 
+    /// Creates new ROMM RGB profile, that is ProPhoto RGB with linear segment
+    /// near black as ISO 22028-2 defines.
+    ///
+    /// [ColorProfile::new_pro_photo_rgb] has pure 1.8 gamma as most ProPhoto ICC profiles do.
+    pub fn new_prophoto_rgb() -> ColorProfile {
+        let mut profile = ColorProfile::new_pro_photo_rgb();
+        // Y = X^1.8 for X >= 1/32, Y = X / 16 below
+        let curve = ToneReprCurve::Parametric(vec![1.8, 1., 0., 1. / 16., 1. / 32.]);
+        profile.red_trc = Some(curve.clone());
+        profile.blue_trc = Some(curve.clone());
+        profile.green_trc = Some(curve);
+        profile.description = Some(ProfileText::Localizable(vec![LocalizableString::new(
+            "en".to_string(),
+            "US".to_string(),
+            "ROMM RGB".to_string(),
+        )]));
+        profile
+    }
+
     /// Creates new Bt.2020 profile
     pub fn new_bt2020() -> ColorProfile {
         let mut profile = ColorProfile::default();
@@ -498,9 +518,13 @@ impl ColorProfile {
     }
 
     /// Creates new Monochrome profile
+    ///
+    /// Gamma is stored as parametric curve rounded to s15Fixed16,
+    /// so the profile is the same after encoding and parsing back.
     pub fn new_gray_with_gamma(gamma: f32) -> ColorProfile {
+        let fixed_gamma = gamma.to_s15_fixed16() as f32 / 65536.;
         ColorProfile {
-            gray_trc: Some(curve_from_gamma(gamma)),
+            gray_trc: Some(ToneReprCurve::Parametric(vec![fixed_gamma])),
             profile_class: ProfileClass::DisplayDevice,
             rendering_intent: RenderingIntent::Perceptual,
             color_space: DataColorSpace::Gray,
@@ -511,6 +535,11 @@ impl ColorProfile {
                 "US".to_string(),
                 "Public Domain".to_string(),
             )])),
+            description: Some(ProfileText::Localizable(vec![LocalizableString::new(
+                "en".to_string(),
+                "US".to_string(),
+                format!("Gray Gamma {gamma}"),
+            )])),
             ..Default::default()
         }
     }
@@ -571,3 +600,121 @@ impl ColorProfile {
         profile
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "transforms")]
+    fn test_prophoto_rgb() {
+        use crate::{Layout, TransformOptions};
+
+        let profile = ColorProfile::new_prophoto_rgb();
+        let d50 = WHITE_POINT_D50.to_xyz();
+        assert_eq!(profile.white_point, d50);
+        assert_eq!(profile.media_white_point, Some(d50));
+
+        let decoded = ColorProfile::new_from_slice(&profile.encode().unwrap()).unwrap();
+        for (decoded, source) in [
+            (decoded.red_colorant, profile.red_colorant),
+            (decoded.green_colorant, profile.green_colorant),
+            (decoded.blue_colorant, profile.blue_colorant),
+        ] {
+            assert!((decoded.x - source.x).abs() < 1e-4);
+            assert!((decoded.y - source.y).abs() < 1e-4);
+            assert!((decoded.z - source.z).abs() < 1e-4);
+        }
+        assert!(matches!(
+            &decoded.red_trc,
+            Some(ToneReprCurve::Parametric(params)) if params.len() == 5
+        ));
+
+        // Linear toe: 1/16 slope below 1/32
+        let evaluator = profile
+            .linearize_evaluator(&profile.red_trc, false)
+            .unwrap();
+        assert!((evaluator.evaluate(0.02) - 0.02 / 16.).abs() < 1e-6);
+        assert!((evaluator.evaluate(0.5) - 0.5f32.powf(1.8)).abs() < 1e-5);
+
+        // D50 white maps to D65 sRGB white through adaptation
+        let transform = profile
+            .create_transform_8bit(
+                Layout::Rgb,
+                &ColorProfile::new_srgb(),
+                Layout::Rgb,
+                TransformOptions::default(),
+            )
+            .unwrap();
+        let mut dst = [0u8; 3];
+        transform.transform(&[255, 255, 255], &mut dst).unwrap();
+        assert_eq!(dst, [255, 255, 255]);
+    }
+
+    #[test]
+    fn test_const_colorants_match_runtime() {
+        use std::hint::black_box;
+
+        for (colorants, white_point, primaries) in [
+            (
+                ColorProfile::SRGB_COLORANTS,
+                WHITE_POINT_D65,
+                ColorPrimaries::BT_709,
+            ),
+            (
+                ColorProfile::DISPLAY_P3_COLORANTS,
+                WHITE_POINT_D65,
+                ColorPrimaries::SMPTE_432,
+            ),
+            (
+                ColorProfile::ADOBE_RGB_COLORANTS,
+                WHITE_POINT_D65,
+                ColorPrimaries::ADOBE_RGB,
+            ),
+            (
+                ColorProfile::DCI_P3_COLORANTS,
+                WHITE_POINT_DCI_P3,
+                ColorPrimaries::DCI_P3,
+            ),
+            (
+                ColorProfile::PRO_PHOTO_RGB_COLORANTS,
+                WHITE_POINT_D50,
+                ColorPrimaries::PRO_PHOTO_RGB,
+            ),
+            (
+                ColorProfile::BT2020_COLORANTS,
+                WHITE_POINT_D65,
+                ColorPrimaries::BT_2020,
+            ),
+            (
+                ColorProfile::ACES_2065_1_COLORANTS,
+                WHITE_POINT_D60,
+                ColorPrimaries::ACES_2065_1,
+            ),
+            (
+                ColorProfile::ACES_CG_COLORANTS,
+                WHITE_POINT_D60,
+                ColorPrimaries::ACES_CG,
+            ),
+        ] {
+            // Compile time evaluation matches the same computation at runtime exactly
+            let runtime =
+                ColorProfile::colorants_matrix(black_box(white_point), black_box(primaries));
+            assert_eq!(colorants.v, runtime.v);
+
+            // and double precision derivation closely
+            let mut profile = ColorProfile::default();
+            profile.update_rgb_colorimetry(white_point, primaries);
+            let precise = [
+                profile.red_colorant,
+                profile.green_colorant,
+                profile.blue_colorant,
+            ];
+            for (column, xyz) in precise.iter().enumerate() {
+                assert!((colorants.v[0][column] - xyz.x).abs() < 1e-5);
+                assert!((colorants.v[1][column] - xyz.y).abs() < 1e-5);
+                assert!((colorants.v[2][column] - xyz.z).abs() < 1e-5);
+            }
+        }
+    }
+}
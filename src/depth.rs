@@ -0,0 +1,96 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+//! Rescaling of integer samples between 8 and 16 bit depth.
+//!
+//! Narrowing uses correctly rounded division by 257, so `narrow(widen(x)) == x`
+//! and no bias is introduced as naive `>> 8` does, which matters for premultiplied alpha.
+
+/// Rescales 16 bit sample into 8 bit, `round(v * 255 / 65535)`.
+#[inline(always)]
+pub const fn depth_16_to_8(v: u16) -> u8 {
+    let v = v as u32;
+    ((v + 128 - ((v + 128) >> 8)) >> 8) as u8
+}
+
+/// Rescales 8 bit sample into 16 bit, exact `v * 65535 / 255`.
+#[inline(always)]
+pub const fn depth_8_to_16(v: u8) -> u16 {
+    v as u16 * 257
+}
+
+/// Rescales integer sample to other bit depth
+#[cfg(feature = "transforms")]
+pub(crate) trait ConvertDepth<D> {
+    fn convert_depth(self) -> D;
+}
+
+#[cfg(feature = "transforms")]
+impl ConvertDepth<u16> for u8 {
+    #[inline(always)]
+    fn convert_depth(self) -> u16 {
+        depth_8_to_16(self)
+    }
+}
+
+#[cfg(feature = "transforms")]
+impl ConvertDepth<u8> for u16 {
+    #[inline(always)]
+    fn convert_depth(self) -> u8 {
+        depth_16_to_8(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_16_to_8_exhaustive() {
+        for v in 0..=u16::MAX {
+            // round half up of v * 255 / 65535 in rational arithmetic
+            let reference = ((v as u64 * 255 * 2 + 65535) / (65535 * 2)) as u8;
+            assert_eq!(depth_16_to_8(v), reference, "Mismatch at {v}");
+            assert_eq!(
+                depth_16_to_8(v),
+                ((v as u32 * 255 + 32767) / 65535) as u8,
+                "Mismatch at {v}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_8_to_16_exact() {
+        for v in 0..=u8::MAX {
+            let wide = depth_8_to_16(v);
+            assert_eq!(wide as u32 * 255, v as u32 * 65535);
+            assert_eq!(depth_16_to_8(wide), v);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use std::cell::Cell;
+
+/// Family of kernels executing a transform.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum KernelFamily {
+    /// Portable implementation, available everywhere
+    Scalar,
+    /// SSE 4.1 kernels
+    Sse41,
+    /// AVX2 + FMA kernels
+    Avx2,
+    /// NEON kernels
+    Neon,
+}
+
+impl KernelFamily {
+    /// Returns true if this family is compiled in and supported by the current CPU.
+    pub fn is_available(self) -> bool {
+        match self {
+            KernelFamily::Scalar => true,
+            KernelFamily::Sse41 => {
+                #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "sse"))]
+                {
+                    std::arch::is_x86_feature_detected!("sse4.1")
+                }
+                #[cfg(not(all(
+                    any(target_arch = "x86", target_arch = "x86_64"),
+                    feature = "sse"
+                )))]
+                {
+                    false
+                }
+            }
+            KernelFamily::Avx2 => {
+                #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "avx"))]
+                {
+                    std::arch::is_x86_feature_detected!("avx2")
+                        && std::arch::is_x86_feature_detected!("fma")
+                }
+                #[cfg(not(all(
+                    any(target_arch = "x86", target_arch = "x86_64"),
+                    feature = "avx"
+                )))]
+                {
+                    false
+                }
+            }
+            KernelFamily::Neon => {
+                // NEON is mandatory in AArch64, but some custom targets do not enable it
+                // statically, so it is detected at runtime as well.
+                #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+                {
+                    std::arch::is_aarch64_feature_detected!("neon")
+                }
+                #[cfg(not(all(target_arch = "aarch64", feature = "neon")))]
+                {
+                    false
+                }
+            }
+        }
+    }
+
+    /// Returns the family transforms pick by default on the current machine.
+    pub fn preferred() -> KernelFamily {
+        KernelFamily::available()
+            .last()
+            .copied()
+            .unwrap_or(KernelFamily::Scalar)
+    }
+
+    /// Lists all families available on the current machine, scalar first.
+    pub fn available() -> Vec<KernelFamily> {
+        [
+            KernelFamily::Scalar,
+            KernelFamily::Sse41,
+            KernelFamily::Avx2,
+            KernelFamily::Neon,
+        ]
+        .into_iter()
+        .filter(|x| x.is_available())
+        .collect()
+    }
+}
+
+/// CPU extensions detected at runtime.
+///
+/// Reported for diagnostics, so packagers can verify what a build is able to use,
+/// see also [KernelFamily::available].
+/// `fp16` and `sve` are detected only, no kernels use them yet.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct CpuFeatures {
+    pub sse41: bool,
+    pub avx2: bool,
+    pub fma: bool,
+    pub neon: bool,
+    pub fp16: bool,
+    pub sve: bool,
+}
+
+/// Detects CPU extensions of the current machine.
+pub fn cpu_features() -> CpuFeatures {
+    #[allow(unused_mut)]
+    let mut features = CpuFeatures::default();
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        features.sse41 = std::arch::is_x86_feature_detected!("sse4.1");
+        features.avx2 = std::arch::is_x86_feature_detected!("avx2");
+        features.fma = std::arch::is_x86_feature_detected!("fma");
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        features.neon = std::arch::is_aarch64_feature_detected!("neon");
+        features.fp16 = std::arch::is_aarch64_feature_detected!("fp16");
+        features.sve = std::arch::is_aarch64_feature_detected!("sve");
+        // Scalar FMA is a part of base AArch64
+        features.fma = true;
+    }
+    features
+}
+
+thread_local! {
+    static FORCED_KERNEL: Cell<Option<KernelFamily>> = const { Cell::new(None) };
+}
+
+/// Runs `f` with kernel selection forced to `family` on the current thread.
+///
+/// Transforms created inside `f` use `family` kernels where a path has them,
+/// and scalar ones otherwise. Executors keep their kernels after `f` returns.
+#[cfg(feature = "transforms")]
+pub(crate) fn with_forced_kernel<R>(family: KernelFamily, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<KernelFamily>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            FORCED_KERNEL.set(self.0);
+        }
+    }
+
+    let _restore = Restore(FORCED_KERNEL.replace(Some(family)));
+    f()
+}
+
+/// Runs `f` with scalar kernels when `deterministic` is set,
+/// see [crate::TransformOptions::deterministic].
+#[cfg(feature = "transforms")]
+pub(crate) fn with_deterministic_kernel<R>(deterministic: bool, f: impl FnOnce() -> R) -> R {
+    if deterministic {
+        with_forced_kernel(KernelFamily::Scalar, f)
+    } else {
+        f()
+    }
+}
+
+/// Checks whether dispatch is allowed to pick `family`.
+///
+/// CPU feature detection is still on the caller, this only applies the override.
+#[inline]
+#[allow(unused)]
+pub(crate) fn use_kernel(family: KernelFamily) -> bool {
+    match FORCED_KERNEL.get() {
+        None => true,
+        Some(forced) => forced == family,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kernel_availability_matches_cpu() {
+        let features = cpu_features();
+        let available = KernelFamily::available();
+        assert_eq!(available.first(), Some(&KernelFamily::Scalar));
+        assert!(available.contains(&KernelFamily::preferred()));
+        if KernelFamily::Avx2.is_available() {
+            assert!(features.avx2 && features.fma);
+        }
+        if KernelFamily::Sse41.is_available() {
+            assert!(features.sse41);
+        }
+        if KernelFamily::Neon.is_available() {
+            assert!(features.neon);
+        }
+        #[cfg(all(target_arch = "aarch64", feature = "neon"))]
+        {
+            assert!(KernelFamily::Neon.is_available());
+            assert_eq!(KernelFamily::preferred(), KernelFamily::Neon);
+        }
+    }
+}
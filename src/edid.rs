@@ -0,0 +1,190 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::trc::curve_from_gamma;
+use crate::{
+    Chromaticity, CmsError, ColorPrimaries, ColorProfile, DataColorSpace, ProfileClass,
+    RenderingIntent, WHITE_POINT_D50,
+};
+
+/// Display colorimetry stored in the EDID base block
+#[derive(Debug, Clone, Copy)]
+pub struct EdidChromaticity {
+    pub red: Chromaticity,
+    pub green: Chromaticity,
+    pub blue: Chromaticity,
+    pub white: Chromaticity,
+    /// Display transfer gamma, `None` if it is defined in an extension block
+    pub gamma: Option<f32>,
+}
+
+const EDID_HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+
+impl EdidChromaticity {
+    /// Parses chromaticity coordinates from 128 bytes EDID base block.
+    ///
+    /// Coordinates are stored as 10-bit fractions of 1024, they are returned as is,
+    /// without rounding to the decimal precision some tools display.
+    /// Zeroed coordinates mean the display doesn't report colorimetry,
+    /// this is considered as an error.
+    pub fn from_edid(edid: &[u8; 128]) -> Result<EdidChromaticity, CmsError> {
+        if edid[0..8] != EDID_HEADER {
+            return Err(CmsError::InvalidEdid);
+        }
+        let lo_rg = edid[0x19];
+        let lo_bw = edid[0x1A];
+        let read = |hi: u8, lo: u8, shift: u8| -> f32 {
+            let value = ((hi as u16) << 2) | ((lo >> shift) & 0b11) as u16;
+            value as f32 / 1024.
+        };
+        let red = Chromaticity::new(read(edid[0x1B], lo_rg, 6), read(edid[0x1C], lo_rg, 4));
+        let green = Chromaticity::new(read(edid[0x1D], lo_rg, 2), read(edid[0x1E], lo_rg, 0));
+        let blue = Chromaticity::new(read(edid[0x1F], lo_bw, 6), read(edid[0x20], lo_bw, 4));
+        let white = Chromaticity::new(read(edid[0x21], lo_bw, 2), read(edid[0x22], lo_bw, 0));
+        if [red, green, blue, white].iter().any(|c| c.y == 0.) {
+            return Err(CmsError::InvalidEdid);
+        }
+        let gamma = if edid[0x17] == 0xff {
+            None
+        } else {
+            Some((edid[0x17] as f32 + 100.) / 100.)
+        };
+        Ok(EdidChromaticity {
+            red,
+            green,
+            blue,
+            white,
+            gamma,
+        })
+    }
+}
+
+impl ColorProfile {
+    /// Creates new display profile from EDID primaries, white point and gamma
+    pub fn new_from_edid(
+        red_xy: Chromaticity,
+        green_xy: Chromaticity,
+        blue_xy: Chromaticity,
+        white_xy: Chromaticity,
+        gamma: f32,
+    ) -> ColorProfile {
+        let mut profile = ColorProfile::default();
+        profile.update_rgb_colorimetry(
+            white_xy.to_xyyb(),
+            ColorPrimaries {
+                red: red_xy,
+                green: green_xy,
+                blue: blue_xy,
+            },
+        );
+
+        let curve = curve_from_gamma(gamma);
+        profile.red_trc = Some(curve.clone());
+        profile.blue_trc = Some(curve.clone());
+        profile.green_trc = Some(curve);
+        profile.profile_class = ProfileClass::DisplayDevice;
+        profile.rendering_intent = RenderingIntent::Perceptual;
+        profile.color_space = DataColorSpace::Rgb;
+        profile.pcs = DataColorSpace::Xyz;
+        profile.media_white_point = Some(white_xy.to_xyz());
+        profile.white_point = WHITE_POINT_D50.to_xyz();
+        profile
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // EDID base block of a display reporting sRGB-like chromaticity and gamma 2.2
+    const EDID: [u8; 128] = [
+        0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x52, 0x74, 0x01, 0x00, 0x01, 0x00, 0x00,
+        0x00, 0x1f, 0x1a, 0x01, 0x04, 0xa5, 0x3c, 0x22, 0x78, 0x3a, 0xee, 0x91, 0xa3, 0x54, 0x4c,
+        0x99, 0x26, 0x0f, 0x50, 0x54, 0xa5, 0x4b, 0x00, 0x71, 0x4f, 0x81, 0x80, 0xa9, 0xc0, 0xd1,
+        0xc0, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x02, 0x3a, 0x80, 0x18, 0x71, 0x38,
+        0x2d, 0x40, 0x58, 0x2c, 0x45, 0x00, 0x56, 0x50, 0x21, 0x00, 0x00, 0x1e, 0x00, 0x00, 0x00,
+        0xfc, 0x00, 0x54, 0x65, 0x73, 0x74, 0x20, 0x64, 0x69, 0x73, 0x70, 0x6c, 0x61, 0x79, 0x0a,
+        0x00, 0x00, 0x00, 0xfd, 0x00, 0x38, 0x4c, 0x1e, 0x53, 0x11, 0x00, 0x0a, 0x20, 0x20, 0x20,
+        0x20, 0x20, 0x20, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x33,
+    ];
+
+    #[test]
+    fn test_edid_chromaticity() {
+        let chromaticity = EdidChromaticity::from_edid(&EDID).unwrap();
+        // Values as printed by edid-decode with 4 decimals
+        let reference = [
+            (chromaticity.red, 0.6396, 0.3301),
+            (chromaticity.green, 0.2998, 0.5996),
+            (chromaticity.blue, 0.1504, 0.0596),
+            (chromaticity.white, 0.3125, 0.3291),
+        ];
+        for (parsed, x, y) in reference {
+            assert!((parsed.x - x).abs() < 5e-5, "{:?} expected x {}", parsed, x);
+            assert!((parsed.y - y).abs() < 5e-5, "{:?} expected y {}", parsed, y);
+        }
+        assert_eq!(chromaticity.gamma, Some(2.2));
+    }
+
+    #[test]
+    fn test_edid_zeroed_chromaticity() {
+        let mut edid = EDID;
+        edid[0x19..=0x22].fill(0);
+        assert_eq!(
+            EdidChromaticity::from_edid(&edid).unwrap_err(),
+            CmsError::InvalidEdid
+        );
+        let mut edid = EDID;
+        edid[0] = 0x01;
+        assert!(EdidChromaticity::from_edid(&edid).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "transforms")]
+    fn test_profile_from_edid() {
+        let c = EdidChromaticity::from_edid(&EDID).unwrap();
+        let profile =
+            ColorProfile::new_from_edid(c.red, c.green, c.blue, c.white, c.gamma.unwrap());
+        let srgb = ColorProfile::new_srgb();
+        let m0 = profile.rgb_to_xyz_matrix().unwrap();
+        let m1 = srgb.rgb_to_xyz_matrix().unwrap();
+        for (r0, r1) in m0.v.iter().zip(m1.v.iter()) {
+            for (&v0, &v1) in r0.iter().zip(r1.iter()) {
+                assert!((v0 - v1).abs() < 2e-3, "{:?} {:?}", m0, m1);
+            }
+        }
+        profile
+            .create_transform_8bit(
+                crate::Layout::Rgb,
+                &srgb,
+                crate::Layout::Rgb,
+                Default::default(),
+            )
+            .unwrap();
+    }
+}
@@ -26,7 +26,7 @@
  * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
-use crate::RenderingIntent;
+use crate::{ParseWarning, RenderingIntent};
 use std::error::Error;
 use std::fmt::Display;
 
@@ -52,6 +52,111 @@ pub enum CmsError {
     UnsupportedLutRenderingIntent(RenderingIntent),
     InvalidAtoBLut,
     OverflowingError,
+    InvalidEdid,
+    TooManyTags(u32),
+    InvalidPaletteIndex(u8),
+    InvalidStride,
+    BufferTooSmall {
+        needed: usize,
+    },
+    /// Profile structure defect found by [crate::Strictness::Strict] parsing
+    MalformedProfile(ParseWarning),
+    /// Tag couldn't be read, `offset` is position of tag data in the profile
+    MalformedTag {
+        signature: u32,
+        offset: usize,
+        reason: TagDefect,
+    },
+    /// Data ends before `expected` bytes, only `got` are available
+    TruncatedData {
+        expected: usize,
+        got: usize,
+    },
+    /// [crate::LutBuildSession] was stepped after it returned finished transform
+    SessionFinished,
+}
+
+/// Reason of [CmsError::MalformedTag]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum TagDefect {
+    /// Tag data ends before `expected` bytes, only `got` are available
+    Truncated { expected: usize, got: usize },
+    /// Tag type is not allowed for the tag or not supported
+    UnknownType(u32),
+    /// CLUT has zero grid points
+    ZeroGridSize,
+    /// Curve couldn't be read or evaluated
+    InvalidCurve,
+    /// Any other inconsistency of tag data
+    Malformed,
+}
+
+impl Display for TagDefect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TagDefect::Truncated { expected, got } => f.write_fmt(format_args!(
+                "data truncated, {} bytes expected, {} available",
+                expected, got
+            )),
+            TagDefect::UnknownType(t) => {
+                f.write_fmt(format_args!("unsupported tag type '{}'", FourCc(*t)))
+            }
+            TagDefect::ZeroGridSize => f.write_str("CLUT grid size 0"),
+            TagDefect::InvalidCurve => f.write_str("invalid curve"),
+            TagDefect::Malformed => f.write_str("inconsistent tag data"),
+        }
+    }
+}
+
+/// Formats signature as four characters, non printable bytes are escaped
+struct FourCc(u32);
+
+impl Display for FourCc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for byte in self.0.to_be_bytes() {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                f.write_fmt(format_args!("{}", byte as char))?;
+            } else {
+                f.write_fmt(format_args!("\\x{:02x}", byte))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CmsError {
+    /// Error of tag reader without tag position
+    pub(crate) const fn tag_defect(reason: TagDefect) -> CmsError {
+        CmsError::MalformedTag {
+            signature: 0,
+            offset: 0,
+            reason,
+        }
+    }
+
+    /// Attributes error of tag reader to the tag at `offset`
+    pub(crate) fn at_tag(self, signature: u32, offset: usize) -> CmsError {
+        let reason = match self {
+            CmsError::MalformedTag {
+                signature: 0,
+                reason,
+                ..
+            } => reason,
+            CmsError::TruncatedData { expected, got } => TagDefect::Truncated { expected, got },
+            CmsError::InvalidProfile => TagDefect::Malformed,
+            CmsError::UnknownTagTypeDefinition(t) => TagDefect::UnknownType(t),
+            CmsError::InvalidTrcCurve
+            | CmsError::BuildTransferFunction
+            | CmsError::ParametricCurveZeroDivision
+            | CmsError::CurveLutIsTooLarge => TagDefect::InvalidCurve,
+            _ => return self,
+        };
+        CmsError::MalformedTag {
+            signature,
+            offset,
+            reason,
+        }
+    }
 }
 
 impl Display for CmsError {
@@ -96,6 +201,36 @@ impl Display for CmsError {
             CmsError::OverflowingError => {
                 f.write_str("Overflowing was happen, that is not allowed")
             }
+            CmsError::InvalidEdid => f.write_str("Invalid or incomplete EDID block"),
+            CmsError::TooManyTags(count) => {
+                f.write_fmt(format_args!("Profile has too many tags: {}", count))
+            }
+            CmsError::InvalidPaletteIndex(index) => f.write_fmt(format_args!(
+                "Palette index is out of palette range: {}",
+                index
+            )),
+            CmsError::InvalidStride => f.write_str("Stride must not be less than row length"),
+            CmsError::BufferTooSmall { needed } => {
+                f.write_fmt(format_args!("Buffer is too small, {} bytes needed", needed))
+            }
+            CmsError::MalformedProfile(defect) => {
+                f.write_fmt(format_args!("Malformed ICC profile: {:?}", defect))
+            }
+            CmsError::MalformedTag {
+                signature,
+                offset,
+                reason,
+            } => f.write_fmt(format_args!(
+                "malformed '{}' at offset {:#x}: {}",
+                FourCc(*signature),
+                offset,
+                reason
+            )),
+            CmsError::TruncatedData { expected, got } => f.write_fmt(format_args!(
+                "Data is truncated, {} bytes expected, {} available",
+                expected, got
+            )),
+            CmsError::SessionFinished => f.write_str("Transform build session already finished"),
         }
     }
 }
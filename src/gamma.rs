@@ -27,6 +27,7 @@
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 use crate::transform::PointeeSizeExpressible;
+use crate::trc::{GammaTableSource, pin_linear_endpoints, saturate_linear_table};
 use crate::{TransferCharacteristics, exp, pow, powf};
 use num_traits::AsPrimitive;
 
@@ -219,9 +220,9 @@ const fn pure_gamma_function(x: f64, gamma: f64) -> f64 {
     if x <= 0f64 {
         0f64
     } else if x >= 1f64 {
-        return 1f64;
+        1f64
     } else {
-        return pow(x, gamma);
+        pow(x, gamma)
     }
 }
 
@@ -440,6 +441,8 @@ impl TransferCharacteristics {
         for (i, g) in gamma_table.iter_mut().enumerate().take(cap_values) {
             *g = self.linearize(i as f64 * scale_value) as f32;
         }
+        pin_linear_endpoints::<T, N, BIT_DEPTH>(&mut gamma_table);
+        saturate_linear_table::<T, N, BIT_DEPTH>(&mut gamma_table);
         gamma_table
     }
 
@@ -454,18 +457,6 @@ impl TransferCharacteristics {
     where
         f32: AsPrimitive<T>,
     {
-        let mut table = Box::new([T::default(); BUCKET]);
-        let max_range = 1f64 / (N - 1) as f64;
-        let max_value = ((1 << BIT_DEPTH) - 1) as f64;
-        for (v, output) in table.iter_mut().take(N).enumerate() {
-            if T::FINITE {
-                *output = ((self.gamma(v as f64 * max_range) * max_value) as f32)
-                    .round()
-                    .as_();
-            } else {
-                *output = (self.gamma(v as f64 * max_range) as f32).as_();
-            }
-        }
-        table
+        GammaTableSource::Cicp(*self).build_table::<T, BUCKET, N, BIT_DEPTH>()
     }
 }
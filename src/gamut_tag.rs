@@ -0,0 +1,380 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::conversions::{
+    create_lut3x1, make_gamut_sampler, make_lut_transform, pcs_round_trip_delta_e,
+};
+use crate::{
+    CmsError, ColorProfile, DataColorSpace, InterpolationMethod, Lab, Layout, LutWarehouse,
+    Transform8BitExecutor, TransformMaskExecutor, TransformOptions, Xyz,
+};
+
+/// `gamt` output above this is out of gamut, i.e. anything that doesn't round to 0 in 8 bit
+const GAMUT_TAG_THRESHOLD: f32 = 0.5 / 255.;
+
+/// Checks pixels against the `gamt` tag of a printer profile
+pub trait GamutCheckExecutor<V: Copy + Default> {
+    /// Writes one byte per pixel into `mask`, 255 if the pixel is out of gamut and 0 otherwise.
+    fn check(&self, src: &[V], mask: &mut [u8]) -> Result<(), CmsError>;
+}
+
+pub type GamutCheck8BitExecutor = dyn GamutCheckExecutor<u8> + Send + Sync;
+
+struct GamutTagCheck8Bit {
+    executor: Box<Transform8BitExecutor>,
+}
+
+impl GamutCheckExecutor<u8> for GamutTagCheck8Bit {
+    fn check(&self, src: &[u8], mask: &mut [u8]) -> Result<(), CmsError> {
+        self.executor.transform(src, mask)?;
+        for v in mask.iter_mut() {
+            *v = if *v != 0 { 255 } else { 0 };
+        }
+        Ok(())
+    }
+}
+
+/// Regular transform with out-of-gamut mask written by separate gamut check
+pub(crate) struct GamutMaskExecutor {
+    pub(crate) transform: Box<Transform8BitExecutor>,
+    pub(crate) check: Box<GamutCheck8BitExecutor>,
+}
+
+impl TransformMaskExecutor<u8> for GamutMaskExecutor {
+    fn transform(&self, src: &[u8], dst: &mut [u8], mask: &mut [u8]) -> Result<(), CmsError> {
+        self.transform.transform(src, dst)?;
+        self.check.check(src, mask)
+    }
+}
+
+impl ColorProfile {
+    /// Checks if profile has `gamt` tag with 3 inputs and 1 output.
+    pub(crate) fn has_gamut_tag(&self) -> bool {
+        match &self.gamut {
+            Some(LutWarehouse::Lut(lut)) => {
+                lut.num_input_channels == 3 && lut.num_output_channels == 1
+            }
+            Some(LutWarehouse::MCurves(mab)) => {
+                mab.num_input_channels == 3 && mab.num_output_channels == 1
+            }
+            None => false,
+        }
+    }
+
+    /// Evaluates `gamt` tag for normalized PCS values of this profile.
+    fn eval_gamut_tag(
+        &self,
+        pcs: &[f32],
+        interpolation_method: InterpolationMethod,
+    ) -> Result<Vec<f32>, CmsError> {
+        let gamut = self
+            .gamut
+            .as_ref()
+            .filter(|_| self.has_gamut_tag())
+            .ok_or(CmsError::UnsupportedProfileConnection)?;
+        let mut pcs = pcs.iter().map(|x| x.max(0.).min(1.)).collect::<Vec<f32>>();
+        if self.pcs == DataColorSpace::Lab && gamut.has_legacy_lab_encoding() {
+            // Legacy 16 bit Lab encoding
+            for v in pcs.iter_mut() {
+                *v *= 65280.0 / 65535.0;
+            }
+        }
+        create_lut3x1(
+            gamut,
+            &pcs,
+            self.pcs == DataColorSpace::Xyz,
+            interpolation_method,
+        )
+    }
+
+    /// Encodes CIE XYZ as normalized PCS values of this profile.
+    fn encode_pcs(&self, xyz: Xyz) -> Option<[f32; 3]> {
+        match self.pcs {
+            DataColorSpace::Lab => {
+                let lab = Lab::from_pcs_xyz(xyz);
+                Some([lab.l, lab.a, lab.b])
+            }
+            // PCS XYZ is encoded as xyz / (1 + 32767 / 32768)
+            DataColorSpace::Xyz => Some([xyz.x, xyz.y, xyz.z].map(|v| v * (32768.0 / 65535.0))),
+            _ => None,
+        }
+    }
+
+    /// Checks CIE Lab color against profile `gamt` tag.
+    ///
+    /// Returns `None` if profile has no usable gamut tag,
+    /// otherwise `Some(true)` when the color is out of gamut.
+    pub fn gamut_check(&self, lab: Lab) -> Option<bool> {
+        if !self.has_gamut_tag() {
+            return None;
+        }
+        let pcs = self.encode_pcs(lab.to_xyz())?;
+        let v = self
+            .eval_gamut_tag(&pcs, TransformOptions::default().interpolation_method)
+            .ok()?;
+        Some(v[0] > GAMUT_TAG_THRESHOLD)
+    }
+
+    /// Checks CIE XYZ colors against gamut of this profile,
+    /// `out` receives `true` for colors out of gamut.
+    ///
+    /// Uses `gamt` tag when present. Otherwise color is out of gamut when B2A → A2B round trip
+    /// through relative colorimetric tables moves it more than
+    /// [TransformOptions::gamut_round_trip_delta_e] of default options.
+    pub fn check_gamut(&self, pcs: &[Xyz], out: &mut [bool]) -> Result<(), CmsError> {
+        self.check_gamut_with_options(pcs, out, TransformOptions::default())
+    }
+
+    /// Same as [ColorProfile::check_gamut], interpolation and round trip ΔE*ab are taken from `options`.
+    pub fn check_gamut_with_options(
+        &self,
+        pcs: &[Xyz],
+        out: &mut [bool],
+        options: TransformOptions,
+    ) -> Result<(), CmsError> {
+        if pcs.len() != out.len() {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        let mut encoded = Vec::with_capacity(pcs.len() * 3);
+        for &xyz in pcs.iter() {
+            let v = self
+                .encode_pcs(xyz)
+                .ok_or(CmsError::UnsupportedProfileConnection)?;
+            encoded.extend_from_slice(&v);
+        }
+        if self.has_gamut_tag() {
+            let values = self.eval_gamut_tag(&encoded, options.interpolation_method)?;
+            for (dst, v) in out.iter_mut().zip(values) {
+                *dst = v > GAMUT_TAG_THRESHOLD;
+            }
+            return Ok(());
+        }
+        if options.gamut_round_trip_delta_e == 0 {
+            return Err(CmsError::UnsupportedProfileConnection);
+        }
+        let max_delta_e = options.gamut_round_trip_delta_e as f32 / 10.;
+        let delta_e = pcs_round_trip_delta_e(self, &encoded, options)?;
+        for (dst, delta_e) in out.iter_mut().zip(delta_e) {
+            *dst = delta_e > max_delta_e;
+        }
+        Ok(())
+    }
+
+    /// Creates checker of 8 bit pixels in this profile against `gamt` tag of `printer`.
+    ///
+    /// Printers without `gamt` tag are checked with B2A → A2B round trip of their tables,
+    /// see [TransformOptions::gamut_round_trip_delta_e].
+    /// Only 3 channels device spaces are supported as a source.
+    pub fn create_gamut_check_8bit(
+        &self,
+        src_layout: Layout,
+        printer: &ColorProfile,
+        options: TransformOptions,
+    ) -> Result<Box<GamutCheck8BitExecutor>, CmsError> {
+        let sampler = make_gamut_sampler::<u8, 8, 256, 4096>(src_layout, self, printer, options)?;
+        let executor = make_lut_transform(sampler)?;
+        Ok(Box::new(GamutTagCheck8Bit { executor }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::LutDataType;
+    use crate::{LutType, Matrix3f};
+
+    /// Grid of 5 points per axis; nodes with `axis` coordinate above 1/4 are out of gamut
+    fn step_gamut(axis: usize) -> LutWarehouse {
+        const GRID: usize = 5;
+        let mut clut_table = vec![0f32; GRID * GRID * GRID];
+        for x in 0..GRID {
+            for y in 0..GRID {
+                for z in 0..GRID {
+                    let node = [x, y, z][axis];
+                    clut_table[(x * GRID + y) * GRID + z] = if node >= 2 { 1. } else { 0. };
+                }
+            }
+        }
+        LutWarehouse::Lut(LutDataType {
+            num_input_channels: 3,
+            num_output_channels: 1,
+            num_clut_grid_points: GRID as u8,
+            matrix: Matrix3f::IDENTITY,
+            num_input_table_entries: 2,
+            num_output_table_entries: 2,
+            input_table: vec![0., 1., 0., 1., 0., 1.],
+            clut_table,
+            output_table: vec![0., 1.],
+            lut_type: LutType::Lut16,
+        })
+    }
+
+    #[test]
+    fn test_gamut_check_lab() {
+        let mut profile = ColorProfile::new_srgb();
+        assert_eq!(profile.gamut_check(Lab::new(50., 0., 0.)), None);
+        profile.pcs = DataColorSpace::Lab;
+        // a* above -64 is out of gamut
+        profile.gamut = Some(step_gamut(1));
+        profile.gamut = Some(
+            ColorProfile::new_from_slice(&profile.encode().unwrap())
+                .unwrap()
+                .gamut
+                .expect("gamt tag must survive round-trip"),
+        );
+        assert!(profile.has_gamut_tag());
+        assert_eq!(profile.gamut_check(Lab::new(50., 40., 0.)), Some(true));
+        assert_eq!(profile.gamut_check(Lab::new(80., -50., -60.)), Some(true));
+        assert_eq!(profile.gamut_check(Lab::new(50., -80., 0.)), Some(false));
+        assert_eq!(profile.gamut_check(Lab::new(20., -100., 60.)), Some(false));
+    }
+
+    #[test]
+    fn test_gamut_check_bulk() {
+        let srgb = ColorProfile::new_srgb();
+        let mut printer = ColorProfile::new_srgb();
+        // Y above 0.5 is out of gamut
+        printer.gamut = Some(step_gamut(1));
+        let src: Vec<u8> = vec![
+            255, 0, 0, // red
+            0, 255, 0, // green
+            0, 0, 255, // blue
+            255, 255, 255, // white
+            20, 20, 20, // dark gray
+            255, 255, 0, // yellow
+        ];
+        let expected = [0u8, 255, 0, 255, 0, 255];
+        let checker = srgb
+            .create_gamut_check_8bit(Layout::Rgb, &printer, TransformOptions::default())
+            .unwrap();
+        let mut mask = vec![0u8; src.len() / 3];
+        checker.check(&src, &mut mask).unwrap();
+        assert_eq!(mask, expected);
+
+        let masked = srgb
+            .create_transform_with_mask(&printer, Layout::Rgb, TransformOptions::default())
+            .unwrap();
+        let mut dst = vec![0u8; src.len()];
+        let mut mask = vec![0u8; src.len() / 3];
+        masked.transform(&src, &mut dst, &mut mask).unwrap();
+        assert_eq!(mask, expected);
+        for (a, b) in src.iter().zip(dst.iter()) {
+            assert!(a.abs_diff(*b) <= 1, "{a} differs from {b}");
+        }
+    }
+
+    #[test]
+    fn test_check_gamut_xyz_tag() {
+        let mut profile = ColorProfile::new_srgb();
+        // Y above 0.25 in PCS encoding, i.e. about 0.5, is out of gamut
+        profile.gamut = Some(step_gamut(1));
+        let pcs = [
+            Xyz::new(0.2, 0.2, 0.2),
+            Xyz::new(0.9642, 1.0, 0.8249),
+            Xyz::new(0.4, 0.45, 0.3),
+            Xyz::new(0.4, 0.55, 0.3),
+        ];
+        let mut out = [false; 4];
+        profile.check_gamut(&pcs, &mut out).unwrap();
+        assert_eq!(out, [false, true, false, true]);
+        for (&xyz, &out) in pcs.iter().zip(out.iter()) {
+            assert_eq!(profile.gamut_check(Lab::from_xyz(xyz)), Some(out));
+        }
+        assert_eq!(
+            profile.check_gamut(&pcs, &mut [false; 3]),
+            Err(CmsError::LaneSizeMismatch)
+        );
+        profile.gamut = None;
+        assert_eq!(
+            profile.check_gamut(&pcs, &mut out),
+            Err(CmsError::UnsupportedProfileConnection)
+        );
+    }
+
+    #[test]
+    fn test_check_gamut_round_trip() {
+        let mut swop =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        swop.gamut = None;
+        let pcs = [
+            Lab::new(50., 0., 0.).to_xyz(),
+            // sRGB blue
+            Xyz::new(0.1431, 0.0606, 0.7141),
+            Lab::new(60., 10., 20.).to_xyz(),
+            // sRGB green
+            Xyz::new(0.3851, 0.7169, 0.0971),
+        ];
+        let mut out = [false; 4];
+        swop.check_gamut(&pcs, &mut out).unwrap();
+        assert_eq!(out, [false, true, false, true]);
+
+        let options = TransformOptions {
+            gamut_round_trip_delta_e: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            swop.check_gamut_with_options(&pcs, &mut out, options),
+            Err(CmsError::UnsupportedProfileConnection)
+        );
+
+        // Threshold is in tenths of ΔE, one tenth around the measured round trip flips the result
+        let encoded = swop.encode_pcs(pcs[2]).unwrap();
+        let delta_e = crate::conversions::pcs_round_trip_delta_e(
+            &swop,
+            &encoded,
+            TransformOptions::default(),
+        )
+        .unwrap()[0];
+        let tenths = (delta_e * 10.).floor() as u16;
+        for (threshold, out_of_gamut) in [(tenths, true), (tenths + 1, false)] {
+            let options = TransformOptions {
+                gamut_round_trip_delta_e: threshold,
+                ..Default::default()
+            };
+            let mut out = [false];
+            swop.check_gamut_with_options(&pcs[2..3], &mut out, options)
+                .unwrap();
+            assert_eq!(out, [out_of_gamut], "ΔE {delta_e}, threshold {threshold}");
+        }
+
+        let srgb = ColorProfile::new_srgb();
+        let src: Vec<u8> = vec![
+            128, 128, 128, 255, // gray
+            0, 0, 255, 255, // blue
+            0, 255, 0, 255, // green
+            180, 140, 120, 255, // skin tone
+        ];
+        let masked = srgb
+            .create_transform_with_mask(&swop, Layout::Rgba, TransformOptions::default())
+            .unwrap();
+        let mut dst = vec![0u8; src.len()];
+        let mut mask = vec![0u8; src.len() / 4];
+        masked.transform(&src, &mut dst, &mut mask).unwrap();
+        assert_eq!(mask, [0, 255, 255, 0]);
+    }
+}
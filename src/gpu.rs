@@ -0,0 +1,443 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::trc::ToneCurveEvaluator;
+use crate::{ClampPolicy, CmsError, ColorProfile, TransformOptions};
+use std::fmt::Write;
+
+/// Options for [ColorProfile::create_transform_wgsl]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct GpuOptions {
+    /// Bind group of the uniform buffer
+    pub group: u32,
+    /// Binding of the uniform buffer in its group
+    pub binding: u32,
+    /// Maximum count of entries of a sampled tone curve, larger tables are resampled
+    pub table_size: usize,
+}
+
+impl Default for GpuOptions {
+    fn default() -> Self {
+        Self {
+            group: 0,
+            binding: 0,
+            table_size: 1024,
+        }
+    }
+}
+
+/// WGSL counterpart of floating point matrix-shaper RGB transform.
+///
+/// `wgsl` declares `MoxcmsUniforms` struct, uniform variable `moxcms` bound as set in
+/// [GpuOptions] and `fn moxcms_transform(rgb: vec3<f32>) -> vec3<f32>`, alpha is left
+/// to the caller. `uniforms` are contents of the uniform buffer, little endian.
+///
+/// Shader does the same steps as [ColorProfile::create_transform_f32]: linearization,
+/// matrix and encoding, with the same clamping. Pure power and parametric curves are
+/// evaluated in closed form and match CPU within `1e-5`, as do sampled curves that fit
+/// into [GpuOptions::table_size] entries. Larger tables, e.g. inverted sampled curves,
+/// and CICP transfer functions are resampled to that size and interpolated linearly,
+/// with default size typical difference is below `1e-3`, steep toes such as of
+/// HLG encoding can differ up to a few percent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuTransform {
+    pub wgsl: String,
+    pub uniforms: Vec<u8>,
+}
+
+/// Tone curve as the shader evaluates it
+#[derive(Debug, Clone, PartialEq)]
+enum GpuCurve {
+    Identity,
+    Power(f32),
+    Parametric([f32; 7]),
+    Sampled(Vec<f32>),
+}
+
+impl GpuCurve {
+    fn new(evaluator: &ToneCurveEvaluator, table_size: usize) -> GpuCurve {
+        match evaluator {
+            ToneCurveEvaluator::Identity => GpuCurve::Identity,
+            ToneCurveEvaluator::Power(gamma) => GpuCurve::Power(*gamma),
+            ToneCurveEvaluator::Parametric(curve) => GpuCurve::Parametric(curve.params()),
+            ToneCurveEvaluator::Sampled(table) if table.len() <= table_size => {
+                GpuCurve::Sampled(table.clone())
+            }
+            ToneCurveEvaluator::Sampled(_)
+            | ToneCurveEvaluator::CicpLinearize(_)
+            | ToneCurveEvaluator::CicpGamma(_) => GpuCurve::Sampled(
+                (0..table_size)
+                    .map(|x| evaluator.evaluate(x as f32 / (table_size - 1) as f32))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Curves of linearization in red, green, blue order followed by curves of encoding
+const CURVES_COUNT: usize = 6;
+/// Two `vec4` of parameters per curve and one for surround exponent
+const PARAMS_COUNT: usize = CURVES_COUNT * 2 + 1;
+
+impl ColorProfile {
+    /// Creates WGSL shader code and uniforms of matrix-shaper RGB transform into `dst_pr`,
+    /// see [GpuTransform].
+    ///
+    /// LUT based transforms and YCbCr output are not supported yet and return
+    /// [CmsError::UnsupportedProfileConnection].
+    pub fn create_transform_wgsl(
+        &self,
+        dst_pr: &ColorProfile,
+        options: TransformOptions,
+        gpu_options: GpuOptions,
+    ) -> Result<GpuTransform, CmsError> {
+        if options.ycbcr_output.is_some() || !self.is_matrix_shaper_pair(dst_pr) {
+            return Err(CmsError::UnsupportedProfileConnection);
+        }
+        let profile = self.make_rgb_profile_transform_f32(dst_pr, options)?;
+        let table_size = gpu_options.table_size.max(2);
+        let curves = [
+            &profile.r_linear,
+            &profile.g_linear,
+            &profile.b_linear,
+            &profile.r_gamma,
+            &profile.g_gamma,
+            &profile.b_gamma,
+        ]
+        .map(|x| GpuCurve::new(x, table_size));
+
+        let mut params = [[0f32; 4]; PARAMS_COUNT];
+        // Identical tables are stored once, channels usually share their curves
+        let mut tables: Vec<&[f32]> = Vec::new();
+        let mut table_len = 0usize;
+        let mut table_offsets = [0usize; CURVES_COUNT];
+        for (i, curve) in curves.iter().enumerate() {
+            match curve {
+                GpuCurve::Identity => {}
+                GpuCurve::Power(gamma) => params[i * 2][0] = *gamma,
+                GpuCurve::Parametric(p) => {
+                    params[i * 2] = [p[0], p[1], p[2], p[3]];
+                    params[i * 2 + 1] = [p[4], p[5], p[6], 0.];
+                }
+                GpuCurve::Sampled(table) => {
+                    let offset = match (0..i).find(|&j| curves[j] == *curve) {
+                        Some(j) => table_offsets[j],
+                        None => {
+                            tables.push(table);
+                            table_len += table.len();
+                            table_len - table.len()
+                        }
+                    };
+                    table_offsets[i] = offset;
+                    params[i * 2] = [table.len() as f32, offset as f32, 0., 0.];
+                }
+            }
+        }
+        params[PARAMS_COUNT - 1][0] = profile.surround_exponent.unwrap_or(1.);
+
+        let mut uniforms = Vec::new();
+        let mut push = |v: f32| uniforms.extend_from_slice(&v.to_le_bytes());
+        // mat3x3<f32> is stored as three columns padded to vec4
+        let matrix = profile.adaptation_matrix;
+        for column in 0..3 {
+            for row in 0..3 {
+                push(matrix.v[row][column]);
+            }
+            push(0.);
+        }
+        for &v in params.iter().flatten() {
+            push(v);
+        }
+        for &v in tables.iter().flat_map(|x| x.iter()) {
+            push(v);
+        }
+        for _ in table_len..table_len.next_multiple_of(4) {
+            push(0.);
+        }
+
+        let wgsl = make_wgsl(
+            &curves,
+            table_len.div_ceil(4),
+            profile.surround_exponent.is_some(),
+            profile.clamp_policy,
+            gpu_options,
+        );
+        Ok(GpuTransform { wgsl, uniforms })
+    }
+}
+
+fn make_wgsl(
+    curves: &[GpuCurve; CURVES_COUNT],
+    table_vectors: usize,
+    surround: bool,
+    clamp_policy: ClampPolicy,
+    gpu_options: GpuOptions,
+) -> String {
+    let mut wgsl = String::new();
+    wgsl.push_str("struct MoxcmsUniforms {\n    matrix: mat3x3<f32>,\n");
+    let _ = writeln!(wgsl, "    params: array<vec4<f32>, {PARAMS_COUNT}>,");
+    if table_vectors > 0 {
+        let _ = writeln!(wgsl, "    table: array<vec4<f32>, {table_vectors}>,");
+    }
+    wgsl.push_str("}\n\n");
+    let _ = writeln!(
+        wgsl,
+        "@group({}) @binding({}) var<uniform> moxcms: MoxcmsUniforms;\n",
+        gpu_options.group, gpu_options.binding
+    );
+
+    wgsl.push_str(
+        "fn moxcms_power(x: f32, g: f32) -> f32 {
+    return sign(x) * pow(abs(x), g);
+}
+
+fn moxcms_parametric(x: f32, i: u32) -> f32 {
+    let p0 = moxcms.params[i];
+    let p1 = moxcms.params[i + 1u];
+    if (x < p1.x) {
+        return p0.w * x + p1.z;
+    }
+    return pow(max(p0.y * x + p0.z, 0.0), p0.x) + p1.y;
+}
+
+",
+    );
+    if table_vectors > 0 {
+        wgsl.push_str(
+            "fn moxcms_table(i: u32) -> f32 {
+    return moxcms.table[i / 4u][i % 4u];
+}
+
+fn moxcms_sampled(x: f32, i: u32) -> f32 {
+    let p0 = moxcms.params[i];
+    let offset = u32(p0.y);
+    let value = clamp(x, 0.0, 1.0) * (p0.x - 1.0);
+    let upper = ceil(value);
+    let lower = floor(value);
+    let diff = upper - value;
+    return moxcms_table(offset + u32(upper)) * (1.0 - diff) + moxcms_table(offset + u32(lower)) * diff;
+}
+
+",
+        );
+    }
+    for (i, curve) in curves.iter().enumerate() {
+        let body = match curve {
+            GpuCurve::Identity => "x".to_string(),
+            GpuCurve::Power(_) => format!("moxcms_power(x, moxcms.params[{}u].x)", i * 2),
+            GpuCurve::Parametric(_) => format!("moxcms_parametric(x, {}u)", i * 2),
+            GpuCurve::Sampled(_) => format!("moxcms_sampled(x, {}u)", i * 2),
+        };
+        let _ = writeln!(
+            wgsl,
+            "fn moxcms_curve_{i}(x: f32) -> f32 {{\n    return {body};\n}}\n"
+        );
+    }
+
+    let clip = |v: &str| match clamp_policy {
+        ClampPolicy::ClampToRange => format!("clamp({v}, vec3<f32>(0.0), vec3<f32>(1.0))"),
+        ClampPolicy::PreserveExtended => v.to_string(),
+    };
+    wgsl.push_str("fn moxcms_transform(rgb: vec3<f32>) -> vec3<f32> {\n");
+    wgsl.push_str(
+        "    var linear = vec3<f32>(moxcms_curve_0(rgb.x), moxcms_curve_1(rgb.y), moxcms_curve_2(rgb.z));\n",
+    );
+    if surround {
+        let _ = writeln!(
+            wgsl,
+            "    linear = sign(linear) * pow(abs(linear), vec3<f32>(moxcms.params[{}u].x));",
+            PARAMS_COUNT - 1
+        );
+    }
+    let _ = writeln!(wgsl, "    let mixed = {};", clip("moxcms.matrix * linear"));
+    let _ = writeln!(
+        wgsl,
+        "    return {};\n}}",
+        clip(
+            "vec3<f32>(moxcms_curve_3(mixed.x), moxcms_curve_4(mixed.y), moxcms_curve_5(mixed.z))"
+        )
+    );
+    wgsl
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Layout, RenderingIntent, ToneReprCurve};
+
+    fn validate(wgsl: &str) {
+        let module = naga::front::wgsl::parse_str(wgsl)
+            .unwrap_or_else(|e| panic!("{}\n{wgsl}", e.emit_to_string(wgsl)));
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::empty(),
+        )
+        .validate(&module)
+        .unwrap_or_else(|e| panic!("{e:?}\n{wgsl}"));
+    }
+
+    /// Evaluates shader on CPU, curve kinds are taken from the emitted code
+    /// and numbers from the uniforms
+    fn run_shader(gpu: &GpuTransform, rgb: [f32; 3]) -> [f32; 3] {
+        let u = gpu
+            .uniforms
+            .chunks_exact(4)
+            .map(|x| f32::from_le_bytes(x.try_into().unwrap()))
+            .collect::<Vec<f32>>();
+        let params = &u[12..12 + PARAMS_COUNT * 4];
+        let table = &u[12 + PARAMS_COUNT * 4..];
+        let curve = |i: usize, x: f32| {
+            let marker = format!("fn moxcms_curve_{i}(x: f32) -> f32 {{\n    return ");
+            let start = gpu.wgsl.find(&marker).unwrap() + marker.len();
+            let body = &gpu.wgsl[start..];
+            let p = &params[i * 8..i * 8 + 8];
+            if body.starts_with("moxcms_power") {
+                x.signum() * x.abs().powf(p[0])
+            } else if body.starts_with("moxcms_parametric") {
+                if x < p[4] {
+                    p[3] * x + p[6]
+                } else {
+                    (p[1] * x + p[2]).max(0.).powf(p[0]) + p[5]
+                }
+            } else if body.starts_with("moxcms_sampled") {
+                let values = &table[p[1] as usize..][..p[0] as usize];
+                let value = x.clamp(0., 1.) * (p[0] - 1.);
+                let diff = value.ceil() - value;
+                values[value.ceil() as usize] * (1. - diff) + values[value.floor() as usize] * diff
+            } else {
+                x
+            }
+        };
+        let clip = |v: f32| {
+            if gpu.wgsl.contains("clamp(moxcms.matrix") {
+                v.clamp(0., 1.)
+            } else {
+                v
+            }
+        };
+        let linear = [curve(0, rgb[0]), curve(1, rgb[1]), curve(2, rgb[2])];
+        let mut mixed = [0f32; 3];
+        for (row, dst) in mixed.iter_mut().enumerate() {
+            *dst = clip((0..3).map(|c| u[c * 4 + row] * linear[c]).sum());
+        }
+        [
+            clip(curve(3, mixed[0])),
+            clip(curve(4, mixed[1])),
+            clip(curve(5, mixed[2])),
+        ]
+    }
+
+    fn max_error(src: &ColorProfile, dst: &ColorProfile, options: TransformOptions) -> f32 {
+        let gpu = src
+            .create_transform_wgsl(dst, options, GpuOptions::default())
+            .unwrap();
+        validate(&gpu.wgsl);
+        let transform = src
+            .create_transform_f32(Layout::Rgb, dst, Layout::Rgb, options)
+            .unwrap();
+        let mut error = 0f32;
+        for r in 0..9 {
+            for g in 0..9 {
+                for b in 0..9 {
+                    let rgb = [r as f32 / 8., g as f32 / 8., b as f32 / 8.];
+                    let mut cpu = [0f32; 3];
+                    transform.transform(&rgb, &mut cpu).unwrap();
+                    let shader = run_shader(&gpu, rgb);
+                    for (a, b) in cpu.iter().zip(shader.iter()) {
+                        error = error.max((a - b).abs());
+                    }
+                }
+            }
+        }
+        error
+    }
+
+    #[test]
+    fn test_wgsl_matches_cpu() {
+        let options = TransformOptions::default();
+        let srgb = ColorProfile::new_srgb();
+        let parametric = max_error(&srgb, &ColorProfile::new_display_p3(), options);
+        assert!(parametric < 1e-5, "parametric {parametric}");
+        let power = max_error(&ColorProfile::new_adobe_rgb(), &srgb, options);
+        assert!(power < 1e-5, "power {power}");
+
+        let clamped = TransformOptions {
+            clamp_policy: ClampPolicy::ClampToRange,
+            rendering_intent: RenderingIntent::AbsoluteColorimetric,
+            ..Default::default()
+        };
+        let wide = max_error(&ColorProfile::new_bt2020(), &srgb, clamped);
+        assert!(wide < 1e-5, "clamped {wide}");
+
+        // Sampled linearization fits into the table, encoding is resampled
+        let mut sampled = ColorProfile::new_srgb();
+        sampled.cicp = None;
+        let curve = ToneReprCurve::Lut((0..256u32).map(|x| (x * x + x) as u16).collect());
+        sampled.red_trc = Some(curve.clone());
+        sampled.green_trc = Some(curve.clone());
+        sampled.blue_trc = Some(curve);
+        let to_sampled = max_error(&srgb, &sampled, options);
+        assert!(to_sampled < 1e-3, "to sampled {to_sampled}");
+        let from_sampled = max_error(&sampled, &srgb, options);
+        assert!(from_sampled < 1e-5, "from sampled {from_sampled}");
+        let pq = max_error(&ColorProfile::new_bt2020_pq(), &srgb, options);
+        assert!(pq < 1e-4, "pq {pq}");
+
+        let gpu = sampled
+            .create_transform_wgsl(&sampled, options, GpuOptions::default())
+            .unwrap();
+        // Same encoding table for all channels stored once, with 4 vectors of padding at most
+        let floats = gpu.uniforms.len() / 4;
+        assert!(floats <= 12 + PARAMS_COUNT * 4 + 256 + 1024 + 4, "{floats}");
+    }
+
+    #[test]
+    fn test_wgsl_rejects_lut_transforms() {
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let srgb = ColorProfile::new_srgb();
+        let options = TransformOptions::default();
+        assert_eq!(
+            cmyk.create_transform_wgsl(&srgb, options, GpuOptions::default()),
+            Err(CmsError::UnsupportedProfileConnection)
+        );
+        let gpu = srgb
+            .create_transform_wgsl(
+                &srgb,
+                options,
+                GpuOptions {
+                    group: 2,
+                    binding: 3,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(gpu.wgsl.contains("@group(2) @binding(3)"));
+        validate(&gpu.wgsl);
+    }
+}
@@ -115,6 +115,29 @@ impl Lab {
         Self::new(lb, a, b)
     }
 
+    /// Converts to CIE Lab from CIE XYZ already divided by D50 white point
+    #[inline]
+    #[cfg(feature = "transforms")]
+    pub(crate) const fn from_normalized_xyz(x: f32, y: f32, z: f32) -> Self {
+        let fx = f(x);
+        let fy = f(y);
+        let fz = f(z);
+
+        let lb = 116.0 * fy - 16.0;
+        let a = 500.0 * (fx - fy);
+        let b = 200.0 * (fy - fz);
+
+        Self::new(lb, a, b)
+    }
+
+    /// Converts CIE [Lab] into CIE [Xyz] divided by D50 white point
+    #[inline]
+    #[cfg(feature = "transforms")]
+    pub(crate) const fn to_normalized_xyz(self) -> Xyz {
+        let y = (self.l + 16.0) / 116.0;
+        Xyz::new(f_1(y + 0.002 * self.a), f_1(y), f_1(y - 0.005 * self.b))
+    }
+
     /// Converts CIE [Lab] into CIE [Xyz] for PCS encoding
     #[inline]
     pub const fn to_pcs_xyz(self) -> Xyz {
@@ -158,6 +181,111 @@ impl Lab {
     }
 }
 
+impl Lab {
+    /// Computes CIE 1976 color difference, euclidean distance in CIE Lab
+    #[inline]
+    pub fn delta_e_76(&self, other: &Lab) -> f32 {
+        let dl = self.l - other.l;
+        let da = self.a - other.a;
+        let db = self.b - other.b;
+        (dl * dl + da * da + db * db).sqrt()
+    }
+
+    /// Computes CIE 1994 color difference with graphic arts weights.
+    ///
+    /// Formula is not symmetric, `self` is the reference color.
+    pub fn delta_e_94(&self, other: &Lab) -> f32 {
+        const K1: f64 = 0.045;
+        const K2: f64 = 0.015;
+        let (l1, a1, b1) = (self.l as f64, self.a as f64, self.b as f64);
+        let (l2, a2, b2) = (other.l as f64, other.a as f64, other.b as f64);
+        let c1 = a1.hypot(b1);
+        let c2 = a2.hypot(b2);
+        let dl = l1 - l2;
+        let dc = c1 - c2;
+        let da = a1 - a2;
+        let db = b1 - b2;
+        // Hue difference squared, may go slightly negative due to rounding
+        let dh2 = (da * da + db * db - dc * dc).max(0.);
+        let sc = 1. + K1 * c1;
+        let sh = 1. + K2 * c1;
+        let dc = dc / sc;
+        (dl * dl + dc * dc + dh2 / (sh * sh)).sqrt() as f32
+    }
+
+    /// Computes CIEDE2000 color difference with unit parametric factors
+    pub fn delta_e_2000(&self, other: &Lab) -> f32 {
+        let (l1, a1, b1) = (self.l as f64, self.a as f64, self.b as f64);
+        let (l2, a2, b2) = (other.l as f64, other.a as f64, other.b as f64);
+
+        let c_mean = (a1.hypot(b1) + a2.hypot(b2)) * 0.5;
+        let c_mean7 = c_mean.powi(7);
+        let g = 0.5 * (1. - (c_mean7 / (c_mean7 + 25f64.powi(7))).sqrt());
+        let a1 = a1 * (1. + g);
+        let a2 = a2 * (1. + g);
+        let c1 = a1.hypot(b1);
+        let c2 = a2.hypot(b2);
+        // Hue of achromatic color is undefined and taken as zero
+        let hue = |a: f64, b: f64| {
+            if a == 0. && b == 0. {
+                0.
+            } else {
+                b.atan2(a).to_degrees().rem_euclid(360.)
+            }
+        };
+        let h1 = hue(a1, b1);
+        let h2 = hue(a2, b2);
+
+        let dl = l2 - l1;
+        let dc = c2 - c1;
+        let chroma_product = c1 * c2;
+        let dh = if chroma_product == 0. {
+            0.
+        } else {
+            let dh = h2 - h1;
+            if dh > 180. {
+                dh - 360.
+            } else if dh < -180. {
+                dh + 360.
+            } else {
+                dh
+            }
+        };
+        let dh = 2. * chroma_product.sqrt() * (dh.to_radians() * 0.5).sin();
+
+        let l_mean = (l1 + l2) * 0.5;
+        let c_mean = (c1 + c2) * 0.5;
+        // Mean hue wraps around 0/360 when hues are more than 180 degrees apart
+        let h_mean = if chroma_product == 0. {
+            h1 + h2
+        } else if (h1 - h2).abs() <= 180. {
+            (h1 + h2) * 0.5
+        } else if h1 + h2 < 360. {
+            (h1 + h2 + 360.) * 0.5
+        } else {
+            (h1 + h2 - 360.) * 0.5
+        };
+
+        let t = 1. - 0.17 * (h_mean - 30.).to_radians().cos()
+            + 0.24 * (2. * h_mean).to_radians().cos()
+            + 0.32 * (3. * h_mean + 6.).to_radians().cos()
+            - 0.20 * (4. * h_mean - 63.).to_radians().cos();
+        let l_offset = (l_mean - 50.) * (l_mean - 50.);
+        let sl = 1. + 0.015 * l_offset / (20. + l_offset).sqrt();
+        let sc = 1. + 0.045 * c_mean;
+        let sh = 1. + 0.015 * c_mean * t;
+        let d_theta = 30. * (-((h_mean - 275.) / 25.).powi(2)).exp();
+        let c_mean7 = c_mean.powi(7);
+        let rc = 2. * (c_mean7 / (c_mean7 + 25f64.powi(7))).sqrt();
+        let rt = -(2. * d_theta).to_radians().sin() * rc;
+
+        let dl = dl / sl;
+        let dc = dc / sc;
+        let dh = dh / sh;
+        (dl * dl + dc * dc + dh * dh + rt * dc * dh).sqrt() as f32
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -187,4 +315,87 @@ mod tests {
         assert!(dy < 1e-5);
         assert!(dz < 1e-5);
     }
+
+    #[test]
+    fn delta_e_2000_sharma() {
+        // G. Sharma, W. Wu, E. N. Dalal, "The CIEDE2000 color-difference formula", test data
+        const DATA: [[f32; 7]; 34] = [
+            [50.0000, 2.6772, -79.7751, 50.0000, 0.0000, -82.7485, 2.0425],
+            [50.0000, 3.1571, -77.2803, 50.0000, 0.0000, -82.7485, 2.8615],
+            [50.0000, 2.8361, -74.0200, 50.0000, 0.0000, -82.7485, 3.4412],
+            [
+                50.0000, -1.3802, -84.2814, 50.0000, 0.0000, -82.7485, 1.0000,
+            ],
+            [
+                50.0000, -1.1848, -84.8006, 50.0000, 0.0000, -82.7485, 1.0000,
+            ],
+            [
+                50.0000, -0.9009, -85.5211, 50.0000, 0.0000, -82.7485, 1.0000,
+            ],
+            [50.0000, 0.0000, 0.0000, 50.0000, -1.0000, 2.0000, 2.3669],
+            [50.0000, -1.0000, 2.0000, 50.0000, 0.0000, 0.0000, 2.3669],
+            [50.0000, 2.4900, -0.0010, 50.0000, -2.4900, 0.0009, 7.1792],
+            [50.0000, 2.4900, -0.0010, 50.0000, -2.4900, 0.0010, 7.1792],
+            [50.0000, 2.4900, -0.0010, 50.0000, -2.4900, 0.0011, 7.2195],
+            [50.0000, 2.4900, -0.0010, 50.0000, -2.4900, 0.0012, 7.2195],
+            [50.0000, -0.0010, 2.4900, 50.0000, 0.0009, -2.4900, 4.8045],
+            [50.0000, -0.0010, 2.4900, 50.0000, 0.0010, -2.4900, 4.8045],
+            [50.0000, -0.0010, 2.4900, 50.0000, 0.0011, -2.4900, 4.7461],
+            [50.0000, 2.5000, 0.0000, 50.0000, 0.0000, -2.5000, 4.3065],
+            [50.0000, 2.5000, 0.0000, 73.0000, 25.0000, -18.0000, 27.1492],
+            [50.0000, 2.5000, 0.0000, 61.0000, -5.0000, 29.0000, 22.8977],
+            [50.0000, 2.5000, 0.0000, 56.0000, -27.0000, -3.0000, 31.9030],
+            [50.0000, 2.5000, 0.0000, 58.0000, 24.0000, 15.0000, 19.4535],
+            [50.0000, 2.5000, 0.0000, 50.0000, 3.1736, 0.5854, 1.0000],
+            [50.0000, 2.5000, 0.0000, 50.0000, 3.2972, 0.0000, 1.0000],
+            [50.0000, 2.5000, 0.0000, 50.0000, 1.8634, 0.5757, 1.0000],
+            [50.0000, 2.5000, 0.0000, 50.0000, 3.2592, 0.3350, 1.0000],
+            [
+                60.2574, -34.0099, 36.2677, 60.4626, -34.1751, 39.4387, 1.2644,
+            ],
+            [
+                63.0109, -31.0961, -5.8663, 62.8187, -29.7946, -4.0864, 1.2630,
+            ],
+            [61.2901, 3.7196, -5.3901, 61.4292, 2.2480, -4.9620, 1.8731],
+            [35.0831, -44.1164, 3.7933, 35.0232, -40.0716, 1.5901, 1.8645],
+            [
+                22.7233, 20.0904, -46.6940, 23.0331, 14.9730, -42.5619, 2.0373,
+            ],
+            [36.4612, 47.8580, 18.3852, 36.2715, 50.5065, 21.2231, 1.4146],
+            [90.8027, -2.0831, 1.4410, 91.1528, -1.6435, 0.0447, 1.4441],
+            [90.9257, -0.5406, -0.9208, 88.6381, -0.8985, -0.7239, 1.5381],
+            [6.7747, -0.2908, -2.4247, 5.8714, -0.0985, -2.2286, 0.6377],
+            [2.0776, 0.0795, -1.1350, 0.9033, -0.0636, -0.5514, 0.9082],
+        ];
+        for row in DATA.iter() {
+            let lab1 = Lab::new(row[0], row[1], row[2]);
+            let lab2 = Lab::new(row[3], row[4], row[5]);
+            let forward = lab1.delta_e_2000(&lab2);
+            let backward = lab2.delta_e_2000(&lab1);
+            assert!((forward - row[6]).abs() < 1e-4, "{row:?}: {forward}");
+            assert!((backward - row[6]).abs() < 1e-4, "{row:?}: {backward}");
+        }
+    }
+
+    #[test]
+    fn delta_e_achromatic() {
+        let black = Lab::new(50., 0., 0.);
+        let gray = Lab::new(60., 0., 0.);
+        // Only lightness term is left for two neutral colors
+        let sl = 1. + 0.015 * 25. / (20f32 + 25.).sqrt();
+        assert!((black.delta_e_2000(&gray) - 10. / sl).abs() < 1e-4);
+        assert_eq!(black.delta_e_2000(&black), 0.);
+        assert_eq!(black.delta_e_76(&gray), 10.);
+        assert!((black.delta_e_94(&gray) - 10.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn delta_e_94_weights_chroma() {
+        let reference = Lab::new(50., 2.6772, -79.7751);
+        let sample = Lab::new(50., 0., -82.7485);
+        let de76 = reference.delta_e_76(&sample);
+        let de94 = reference.delta_e_94(&sample);
+        assert!(de94 < de76, "{de94} {de76}");
+        assert!((de76 - 4.0011).abs() < 1e-3, "{de76}");
+    }
 }
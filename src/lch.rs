@@ -0,0 +1,131 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::Lab;
+
+/// Representing a color in cylindrical CIE LCh(ab) color space
+///
+/// Cylindrical form of [Lab], unlike [LCh](crate::LCh) which is the one of *Luv*.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialOrd, PartialEq)]
+pub struct Lch {
+    /// `l`: lightness component (0 to 100), same as in [Lab]
+    pub l: f32,
+    /// `c`: chroma, zero represents shade of grey
+    pub c: f32,
+    /// `h`: hue in degrees in [0, 360) range, zero for achromatic colors
+    pub h: f32,
+}
+
+impl Lch {
+    /// Creates new instance
+    #[inline]
+    pub const fn new(l: f32, c: f32, h: f32) -> Lch {
+        Lch { l, c, h }
+    }
+
+    /// Converts [Lab] into [Lch]
+    #[inline]
+    pub fn from_lab(lab: Lab) -> Lch {
+        let (a, b) = (lab.a as f64, lab.b as f64);
+        let c = a.hypot(b);
+        if c == 0. {
+            return Lch::new(lab.l, 0., 0.);
+        }
+        let mut h = b.atan2(a).to_degrees();
+        if h < 0. {
+            h += 360.;
+        }
+        let h = h as f32;
+        // Tiny negative angles round up to 360 in f32
+        Lch::new(lab.l, c as f32, if h >= 360. { 0. } else { h })
+    }
+
+    /// Converts [Lch] into [Lab]
+    #[inline]
+    pub fn to_lab(&self) -> Lab {
+        let (sin, cos) = (self.h as f64).to_radians().sin_cos();
+        let c = self.c as f64;
+        Lab::new(self.l, (c * cos) as f32, (c * sin) as f32)
+    }
+}
+
+impl Lab {
+    /// Converts into cylindrical [Lch]
+    #[inline]
+    pub fn to_lch(&self) -> Lch {
+        Lch::from_lab(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for l in [0f32, 35.5, 100.] {
+            for a in (-128..=127).step_by(7) {
+                for b in (-128..=127).step_by(5) {
+                    let lab = Lab::new(l, a as f32 + 0.25, b as f32 - 0.125);
+                    let lch = lab.to_lch();
+                    assert!((0. ..360.).contains(&lch.h), "Hue {} out of range", lch.h);
+                    let restored = lch.to_lab();
+                    // Hue is stored as f32 degrees, so the error grows with chroma
+                    for (v, r) in [(lab.a, restored.a), (lab.b, restored.b)] {
+                        assert!(
+                            (v - r).abs() <= 1e-5 * lch.c.max(1.),
+                            "Expected {lab:?}, got {restored:?}"
+                        );
+                    }
+                    assert_eq!(lab.l, restored.l);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn achromatic_and_axes() {
+        for lab in [
+            Lab::new(50., 0., 0.),
+            Lab::new(50., -0., 0.),
+            Lab::new(50., 0., -0.),
+        ] {
+            let lch = lab.to_lch();
+            assert_eq!(lch, Lch::new(50., 0., 0.));
+        }
+        let hue = |a: f32, b: f32| Lab::new(50., a, b).to_lch().h;
+        assert_eq!(hue(10., 0.), 0.);
+        assert!((hue(0., 10.) - 90.).abs() < 1e-5);
+        assert!((hue(-10., 0.) - 180.).abs() < 1e-5);
+        assert!((hue(0., -10.) - 270.).abs() < 1e-5);
+        // Wraps to 0 rather than reaching 360 when rounded to f32
+        assert_eq!(hue(10., -1e-6), 0.);
+        assert!((Lab::new(50., 3., 4.).to_lch().c - 5.).abs() < 1e-6);
+    }
+}
@@ -32,79 +32,147 @@
     not(any(feature = "avx", feature = "sse", feature = "neon")),
     forbid(unsafe_code)
 )]
+#[cfg(feature = "transforms")]
+mod accuracy;
 mod chad;
 mod cicp;
+#[cfg(feature = "diagnostics")]
+mod cmyk_quality;
+mod colorant_table;
+mod compat;
+#[cfg(feature = "transforms")]
+mod consistency;
+#[cfg(feature = "transforms")]
 mod conversions;
 mod dat;
 mod defaults;
+mod depth;
+mod dispatch;
+mod edid;
 mod err;
 mod gamma;
 mod gamut;
+#[cfg(feature = "transforms")]
+mod gamut_tag;
+#[cfg(feature = "gpu")]
+mod gpu;
 mod ictcp;
 mod jzazbz;
 mod jzczhz;
 mod lab;
+mod lch;
+#[cfg(feature = "transforms")]
+mod lut_session;
 mod luv;
 /// One of main intent is to provide fast math available in const context
 /// ULP most of the methods ~3.5
 mod math;
 mod matrix;
+mod md5;
 mod mlaf;
 mod nd_array;
 mod oklab;
 mod oklch;
+#[cfg(feature = "transforms")]
+mod palette;
+mod partial_profile;
+mod policy;
+pub mod prelude;
 mod profile;
+mod profile_source;
 mod rgb;
+#[cfg(feature = "transforms")]
+mod roundtrip;
 mod safe_reader;
+#[cfg(feature = "transforms")]
+mod scanline;
+mod surround;
 mod tag;
+#[cfg(feature = "testing")]
+pub mod testing;
 mod transform;
 mod trc;
+mod validation;
+mod vcgt;
 mod writer;
 mod yrg;
 
+#[cfg(feature = "transforms")]
+pub use accuracy::{AccuracyReport, estimate_accuracy};
 pub use chad::{
-    adapt_to_d50, adapt_to_d50_d, adapt_to_illuminant, adapt_to_illuminant_d,
+    ChromaticAdaptation, adapt_to_d50, adapt_to_d50_d, adapt_to_illuminant, adapt_to_illuminant_d,
     adapt_to_illuminant_xyz, adapt_to_illuminant_xyz_d,
 };
 pub use cicp::{CicpColorPrimaries, ColorPrimaries, MatrixCoefficients, TransferCharacteristics};
+#[cfg(feature = "diagnostics")]
+pub use cmyk_quality::{CmykQualityReport, cmyk_quality_report};
+pub use colorant_table::NamedColorant;
+#[allow(deprecated)]
+pub use compat::{Array3D, Array4D, Stage, rounding_div_ceil};
+#[cfg(feature = "transforms")]
+pub use consistency::{ConsistencyReport, KernelDeviation, KernelPath, verify_simd_consistency};
 pub use dat::ColorDateTime;
 pub use defaults::{
     HLG_LUT_TABLE, PQ_LUT_TABLE, WHITE_POINT_D50, WHITE_POINT_D60, WHITE_POINT_D65,
     WHITE_POINT_DCI_P3,
 };
-pub use err::CmsError;
+pub use depth::{depth_8_to_16, depth_16_to_8};
+pub use dispatch::{CpuFeatures, KernelFamily, cpu_features};
+pub use edid::EdidChromaticity;
+pub use err::{CmsError, TagDefect};
 pub use gamut::{
     gamut_clip_adaptive_l0_0_5, gamut_clip_adaptive_l0_l_cusp, gamut_clip_preserve_chroma,
     gamut_clip_project_to_l_cusp,
 };
+#[cfg(feature = "transforms")]
+pub use gamut_tag::{GamutCheck8BitExecutor, GamutCheckExecutor};
+#[cfg(feature = "gpu")]
+pub use gpu::{GpuOptions, GpuTransform};
 pub use ictcp::ICtCp;
 pub use jzazbz::Jzazbz;
 pub use jzczhz::Jzczhz;
 pub use lab::Lab;
+pub use lch::Lch;
+#[cfg(feature = "transforms")]
+pub use lut_session::{BuildProgress, LutBuildSession};
 pub use luv::{LCh, Luv};
 pub use math::{
     atan2f, atanf, cbrtf, const_hypotf, cosf, exp, expf, floor, floorf, hypotf, log, logf, pow,
-    powf, rounding_div_ceil, sinf, sqrtf,
+    powf, sinf, sqrtf,
 };
 pub use matrix::{
     BT2020_MATRIX, Chromaticity, DISPLAY_P3_MATRIX, Matrix3, Matrix3d, Matrix3f, Matrix4f,
     SRGB_MATRIX, Vector3, Vector3d, Vector3f, Vector3i, Vector3u, Vector4, Vector4d, Vector4f, XyY,
     Xyz, Xyzd,
 };
-pub use nd_array::{Array3D, Array4D};
 pub use oklab::Oklab;
 pub use oklch::Oklch;
+#[cfg(feature = "transforms")]
+pub use palette::PaletteTransform;
+pub use partial_profile::PartialProfile;
+pub use policy::{PolicyWarning, ProfilePolicy};
 pub use profile::{
     CicpProfile, ColorProfile, DataColorSpace, DescriptionString, LocalizableString,
-    LutMCurvesType, LutType, LutWarehouse, Measurement, MeasurementGeometry, ProfileClass,
-    ProfileSignature, ProfileText, ProfileVersion, RenderingIntent, StandardIlluminant,
-    StandardObserver, TechnologySignatures, ViewingConditions,
+    LutMCurvesType, LutType, LutWarehouse, Measurement, MeasurementGeometry, ParseStatistics,
+    ParseWarning, ParsingOptions, ProfileClass, ProfileSignature, ProfileText, ProfileVersion,
+    RenderingIntent, StandardIlluminant, StandardObserver, Strictness, TechnologySignatures,
+    ViewingConditions,
 };
 pub use rgb::Rgb;
+#[cfg(feature = "transforms")]
+pub use roundtrip::{RoundTripReport, roundtrip_report};
+#[cfg(feature = "transforms")]
+pub use scanline::{ScanlineLab, ScanlineLabFactory};
+pub use surround::{Surround, SurroundCompensation};
 pub use transform::{
-    InPlaceStage, InterpolationMethod, Layout, PointeeSizeExpressible, Stage,
-    Transform8BitExecutor, Transform16BitExecutor, TransformExecutor, TransformF32BitExecutor,
-    TransformF64BitExecutor, TransformOptions,
+    AlphaMode, ClampPolicy, CompatibilityMode, DitherMode, InPlaceStage, InterpolationMethod,
+    Layout, PointeeSizeExpressible, Transform8BitExecutor, Transform8BitTo16BitExecutor,
+    Transform16BitExecutor, Transform16BitTo8BitExecutor, TransformDepthExecutor,
+    TransformExecutor, TransformF32BitExecutor, TransformF64BitExecutor, TransformMask8BitExecutor,
+    TransformMaskExecutor, TransformOptions, YCbCrMatrix,
 };
-pub use trc::{GammaLutInterpolate, ToneReprCurve, curve_from_gamma};
+pub use trc::{GammaLutInterpolate, ToneReprCurve, curve_from_fn, curve_from_gamma};
+pub use validation::{ProfileValidation, ValidationIssue};
+pub use vcgt::{Vcgt, VcgtFormula};
+pub use writer::EncodeOptions;
 pub use yrg::{Ych, Yrg, cie_y_1931_to_cie_y_2006};
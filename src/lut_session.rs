@@ -0,0 +1,394 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::conversions::{CompressForLut, LutSampler, RgbXyzFactory};
+use crate::transform::{PointeeSizeExpressible, TransformPlan};
+use crate::trc::GammaLutInterpolate;
+use crate::{CmsError, ColorProfile, Layout, TransformExecutor, TransformOptions};
+use num_traits::AsPrimitive;
+
+/// Progress of [LutBuildSession]
+pub enum BuildProgress<T: Copy + Default> {
+    /// Grid is still sampling, `done` from `total` grid points are ready
+    InProgress { done: usize, total: usize },
+    /// Transform is ready
+    Finished(Box<dyn TransformExecutor<T> + Send + Sync>),
+}
+
+enum SessionState<T: Copy + Default> {
+    Sampling {
+        sampler: Box<dyn LutSampler<T>>,
+        lut: Vec<f32>,
+        done: usize,
+    },
+    Ready(Box<dyn TransformExecutor<T> + Send + Sync>),
+    Finished,
+}
+
+/// Incremental transform construction.
+///
+/// Sampling a large LUT grid might take noticeable time, session allows
+/// to split it into small steps and drive it from an event loop.
+/// Result is exactly the same as from the one-shot `create_transform_*` methods.
+///
+/// Transforms which do not need a LUT are built at once,
+/// and the first [LutBuildSession::step] finishes them.
+pub struct LutBuildSession<T: Copy + Default> {
+    state: SessionState<T>,
+}
+
+impl<T: Copy + Default> LutBuildSession<T> {
+    fn from_plan(plan: TransformPlan<T>) -> Self {
+        let state = match plan {
+            TransformPlan::Ready(executor) => SessionState::Ready(executor),
+            TransformPlan::Lut(sampler) => {
+                let lut = vec![0f32; sampler.grid_points() * sampler.output_channels()];
+                SessionState::Sampling {
+                    sampler,
+                    lut,
+                    done: 0,
+                }
+            }
+        };
+        LutBuildSession { state }
+    }
+
+    /// Samples at most `max_samples` grid points.
+    ///
+    /// At least one grid point is sampled on every step.
+    /// If sampling fails, session keeps its progress and the step might be retried.
+    /// Stepping again once [BuildProgress::Finished] was returned
+    /// gives [CmsError::SessionFinished].
+    pub fn step(&mut self, max_samples: usize) -> Result<BuildProgress<T>, CmsError> {
+        match &mut self.state {
+            SessionState::Sampling { sampler, lut, done } => {
+                let total = sampler.grid_points();
+                let channels = sampler.output_channels();
+                let end = done.saturating_add(max_samples.max(1)).min(total);
+                sampler.sample(*done, end, &mut lut[*done * channels..end * channels])?;
+                if end < total {
+                    *done = end;
+                    return Ok(BuildProgress::InProgress { done: end, total });
+                }
+            }
+            SessionState::Ready(_) => {}
+            SessionState::Finished => return Err(CmsError::SessionFinished),
+        }
+        match std::mem::replace(&mut self.state, SessionState::Finished) {
+            SessionState::Sampling { sampler, lut, .. } => {
+                Ok(BuildProgress::Finished(sampler.make_executor(lut)))
+            }
+            SessionState::Ready(executor) => Ok(BuildProgress::Finished(executor)),
+            SessionState::Finished => Err(CmsError::SessionFinished),
+        }
+    }
+
+    /// Total count of grid points to sample, zero if transform doesn't need sampling
+    pub fn total(&self) -> usize {
+        match &self.state {
+            SessionState::Sampling { sampler, .. } => sampler.grid_points(),
+            _ => 0,
+        }
+    }
+}
+
+fn plan_nbit<
+    T: Copy
+        + Default
+        + AsPrimitive<usize>
+        + PointeeSizeExpressible
+        + Send
+        + Sync
+        + AsPrimitive<f32>
+        + CompressForLut
+        + RgbXyzFactory<T>
+        + GammaLutInterpolate,
+    const BIT_DEPTH: usize,
+    const LINEAR_CAP: usize,
+    const GAMMA_CAP: usize,
+>(
+    source: &ColorProfile,
+    src_layout: Layout,
+    dest: &ColorProfile,
+    dst_layout: Layout,
+    options: TransformOptions,
+) -> Result<LutBuildSession<T>, CmsError>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    let plan = source.plan_transform_nbit::<T, BIT_DEPTH, LINEAR_CAP, GAMMA_CAP>(
+        src_layout, dest, dst_layout, options,
+    )?;
+    Ok(LutBuildSession::from_plan(plan))
+}
+
+impl LutBuildSession<u8> {
+    /// Starts building transform between source and destination profile.
+    /// Only 8 bit is supported.
+    pub fn new(
+        source: &ColorProfile,
+        src_layout: Layout,
+        dest: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+    ) -> Result<Self, CmsError> {
+        plan_nbit::<u8, 8, 256, 4096>(source, src_layout, dest, dst_layout, options)
+    }
+}
+
+impl LutBuildSession<u16> {
+    /// Starts building transform between source and destination profile.
+    /// Use for 16 bit-depth data bit-depth only.
+    pub fn new_16bit(
+        source: &ColorProfile,
+        src_layout: Layout,
+        dest: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+    ) -> Result<Self, CmsError> {
+        plan_nbit::<u16, 16, 65536, 65536>(source, src_layout, dest, dst_layout, options)
+    }
+
+    /// Starts building transform between source and destination profile.
+    /// Use for 12 bit-depth data bit-depth only.
+    pub fn new_12bit(
+        source: &ColorProfile,
+        src_layout: Layout,
+        dest: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+    ) -> Result<Self, CmsError> {
+        plan_nbit::<u16, 12, 65536, 16384>(source, src_layout, dest, dst_layout, options)
+    }
+
+    /// Starts building transform between source and destination profile.
+    /// Use for 10 bit-depth data bit-depth only.
+    pub fn new_10bit(
+        source: &ColorProfile,
+        src_layout: Layout,
+        dest: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+    ) -> Result<Self, CmsError> {
+        plan_nbit::<u16, 10, 65536, 8192>(source, src_layout, dest, dst_layout, options)
+    }
+}
+
+impl LutBuildSession<f32> {
+    /// Starts building transform between source and destination profile.
+    /// Data has to be normalized into [0, 1] range.
+    pub fn new_f32(
+        source: &ColorProfile,
+        src_layout: Layout,
+        dest: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+    ) -> Result<Self, CmsError> {
+        plan_nbit::<f32, 1, 65536, 32768>(source, src_layout, dest, dst_layout, options)
+    }
+}
+
+impl LutBuildSession<f64> {
+    /// Starts building transform between source and destination profile.
+    /// Data has to be normalized into [0, 1] range.
+    pub fn new_f64(
+        source: &ColorProfile,
+        src_layout: Layout,
+        dest: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+    ) -> Result<Self, CmsError> {
+        plan_nbit::<f64, 1, 65536, 65536>(source, src_layout, dest, dst_layout, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_session<T: Copy + Default>(
+        mut session: LutBuildSession<T>,
+        max_samples: usize,
+    ) -> Box<dyn TransformExecutor<T> + Send + Sync> {
+        let mut last_done = 0;
+        loop {
+            match session.step(max_samples).unwrap() {
+                BuildProgress::InProgress { done, total } => {
+                    assert!(done > last_done && done < total);
+                    last_done = done;
+                }
+                BuildProgress::Finished(executor) => return executor,
+            }
+        }
+    }
+
+    #[test]
+    fn test_session_cmyk_to_rgb_matches_one_shot() {
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let srgb = ColorProfile::new_srgb();
+        let options = TransformOptions::default();
+        let one_shot = cmyk
+            .create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgb, options)
+            .unwrap();
+        let session =
+            LutBuildSession::new(&cmyk, Layout::Rgba, &srgb, Layout::Rgb, options).unwrap();
+        assert_eq!(session.total(), 17 * 17 * 17 * 17);
+        let stepped = run_session(session, 1000);
+
+        let src = (0..4 * 4096)
+            .map(|x| (x * 37 % 256) as u8)
+            .collect::<Vec<_>>();
+        let mut dst0 = vec![0u8; 3 * 4096];
+        let mut dst1 = vec![0u8; 3 * 4096];
+        one_shot.transform(&src, &mut dst0).unwrap();
+        stepped.transform(&src, &mut dst1).unwrap();
+        assert_eq!(dst0, dst1);
+    }
+
+    #[test]
+    fn test_session_rgb_to_cmyk_matches_one_shot() {
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let srgb = ColorProfile::new_srgb();
+        let options = TransformOptions::default();
+        let one_shot = srgb
+            .create_transform_16bit(Layout::Rgb, &cmyk, Layout::Rgba, options)
+            .unwrap();
+        let session =
+            LutBuildSession::new_16bit(&srgb, Layout::Rgb, &cmyk, Layout::Rgba, options).unwrap();
+        let stepped = run_session(session, 777);
+
+        let src = (0..3 * 4096)
+            .map(|x| (x * 7919 % 65536) as u16)
+            .collect::<Vec<_>>();
+        let mut dst0 = vec![0u16; 4 * 4096];
+        let mut dst1 = vec![0u16; 4 * 4096];
+        one_shot.transform(&src, &mut dst0).unwrap();
+        stepped.transform(&src, &mut dst1).unwrap();
+        assert_eq!(dst0, dst1);
+    }
+
+    #[test]
+    fn test_session_drop_in_progress() {
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let srgb = ColorProfile::new_srgb();
+        let mut session = LutBuildSession::new(
+            &cmyk,
+            Layout::Rgba,
+            &srgb,
+            Layout::Rgba,
+            TransformOptions::default(),
+        )
+        .unwrap();
+        match session.step(100).unwrap() {
+            BuildProgress::InProgress { done, total } => {
+                assert_eq!(done, 100);
+                assert_eq!(total, 17 * 17 * 17 * 17);
+            }
+            BuildProgress::Finished(_) => panic!("Grid can't be sampled in one small step"),
+        }
+        drop(session);
+    }
+
+    #[test]
+    fn test_session_matrix_shaper_finishes_at_once() {
+        let srgb = ColorProfile::new_srgb();
+        let bt2020 = ColorProfile::new_bt2020();
+        let mut session = LutBuildSession::new(
+            &srgb,
+            Layout::Rgb,
+            &bt2020,
+            Layout::Rgb,
+            TransformOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(session.total(), 0);
+        assert!(matches!(
+            session.step(1).unwrap(),
+            BuildProgress::Finished(_)
+        ));
+        assert!(matches!(session.step(1), Err(CmsError::SessionFinished)));
+    }
+
+    /// Sampler failing on its first call, sampled value is the grid point index
+    struct FlakySampler {
+        failed: std::sync::atomic::AtomicBool,
+    }
+
+    impl LutSampler<u8> for FlakySampler {
+        fn grid_points(&self) -> usize {
+            10
+        }
+
+        fn output_channels(&self) -> usize {
+            1
+        }
+
+        fn sample(&self, start: usize, end: usize, dst: &mut [f32]) -> Result<(), CmsError> {
+            if !self.failed.swap(true, std::sync::atomic::Ordering::Relaxed) {
+                return Err(CmsError::DivisionByZero);
+            }
+            for (index, dst) in (start..end).zip(dst.iter_mut()) {
+                *dst = index as f32;
+            }
+            Ok(())
+        }
+
+        fn make_executor(
+            self: Box<Self>,
+            lut: Vec<f32>,
+        ) -> Box<dyn TransformExecutor<u8> + Send + Sync> {
+            assert_eq!(lut, (0..10).map(|x| x as f32).collect::<Vec<_>>());
+            let srgb = ColorProfile::new_srgb();
+            srgb.create_transform_8bit(Layout::Rgb, &srgb, Layout::Rgb, Default::default())
+                .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_session_retries_failed_step() {
+        let mut session = LutBuildSession::from_plan(TransformPlan::Lut(Box::new(FlakySampler {
+            failed: Default::default(),
+        })));
+        assert!(matches!(session.step(4), Err(CmsError::DivisionByZero)));
+        // Failed step keeps the session sampling from the same point
+        assert_eq!(session.total(), 10);
+        assert!(matches!(
+            session.step(4).unwrap(),
+            BuildProgress::InProgress { done: 4, total: 10 }
+        ));
+        assert!(matches!(
+            session.step(10).unwrap(),
+            BuildProgress::Finished(_)
+        ));
+        assert!(matches!(session.step(1), Err(CmsError::SessionFinished)));
+    }
+}
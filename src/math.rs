@@ -572,7 +572,7 @@ pub(crate) fn hypot3f(x: f32, y: f32, z: f32) -> f32 {
 }
 
 #[inline(always)]
-pub const fn rounding_div_ceil(value: i32, div: i32) -> i32 {
+pub(crate) const fn rounding_div_ceil(value: i32, div: i32) -> i32 {
     (value + div - 1) / div
 }
 
@@ -604,6 +604,13 @@ pub(crate) trait FusedMultiplyAdd<T> {
     fn mla(&self, b: T, c: T) -> T;
 }
 
+impl FusedMultiplyAdd<f32> for f32 {
+    #[inline(always)]
+    fn mla(&self, b: f32, c: f32) -> f32 {
+        crate::mlaf::mlaf(*self, b, c)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -51,7 +51,7 @@ pub struct Array4D<'a> {
 }
 
 impl Array4D<'_> {
-    pub fn new(array: &[f32], grid_size: usize) -> Array4D {
+    pub fn new(array: &[f32], grid_size: usize) -> Array4D<'_> {
         let z_stride = grid_size as u32;
         let y_stride = z_stride * z_stride;
         let x_stride = z_stride * z_stride * z_stride;
@@ -490,8 +490,21 @@ impl ArrayFetch<Vector4f> for ArrayFetchVector4f<'_> {
     }
 }
 
+struct ArrayFetchScalar<'a> {
+    array: &'a [f32],
+    x_stride: u32,
+    y_stride: u32,
+}
+
+impl ArrayFetch<f32> for ArrayFetchScalar<'_> {
+    #[inline(always)]
+    fn fetch(&self, x: i32, y: i32, z: i32) -> f32 {
+        self.array[(x as u32 * self.x_stride + y as u32 * self.y_stride + z as u32) as usize]
+    }
+}
+
 impl Array3D<'_> {
-    pub fn new(array: &[f32], grid_size: usize) -> Array3D {
+    pub fn new(array: &[f32], grid_size: usize) -> Array3D<'_> {
         let y_stride = grid_size;
         let x_stride = y_stride * y_stride;
         Array3D {
@@ -878,4 +891,60 @@ impl Array3D<'_> {
             },
         )
     }
+
+    #[inline]
+    pub fn trilinear_scalar(&self, lin_x: f32, lin_y: f32, lin_z: f32) -> f32 {
+        self.trilinear(
+            lin_x,
+            lin_y,
+            lin_z,
+            ArrayFetchScalar {
+                array: self.array,
+                x_stride: self.x_stride,
+                y_stride: self.y_stride,
+            },
+        )
+    }
+
+    #[inline]
+    pub fn tetra_scalar(&self, lin_x: f32, lin_y: f32, lin_z: f32) -> f32 {
+        self.tetra(
+            lin_x,
+            lin_y,
+            lin_z,
+            ArrayFetchScalar {
+                array: self.array,
+                x_stride: self.x_stride,
+                y_stride: self.y_stride,
+            },
+        )
+    }
+
+    #[inline]
+    pub fn pyramid_scalar(&self, lin_x: f32, lin_y: f32, lin_z: f32) -> f32 {
+        self.pyramid(
+            lin_x,
+            lin_y,
+            lin_z,
+            ArrayFetchScalar {
+                array: self.array,
+                x_stride: self.x_stride,
+                y_stride: self.y_stride,
+            },
+        )
+    }
+
+    #[inline]
+    pub fn prism_scalar(&self, lin_x: f32, lin_y: f32, lin_z: f32) -> f32 {
+        self.prism(
+            lin_x,
+            lin_y,
+            lin_z,
+            ArrayFetchScalar {
+                array: self.array,
+                x_stride: self.x_stride,
+                y_stride: self.y_stride,
+            },
+        )
+    }
 }
@@ -0,0 +1,182 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::{CmsError, ColorProfile, Layout, Transform8BitExecutor, TransformOptions};
+
+/// Color management of indexed images, e.g. GIF or PNG-8, by converting palette only.
+///
+/// Made by [ColorProfile::create_palette_transform], palette entries are RGB triplets.
+pub struct PaletteTransform {
+    executor: Box<Transform8BitExecutor>,
+}
+
+impl PaletteTransform {
+    /// Converts palette entries in place.
+    pub fn transform_palette(&self, palette: &mut [[u8; 3]]) -> Result<(), CmsError> {
+        self.executor.transform_in_place(palette.as_flattened_mut())
+    }
+
+    /// Converts palette into a new one, source palette is left untouched.
+    pub fn transformed_palette(&self, palette: &[[u8; 3]]) -> Result<Vec<[u8; 3]>, CmsError> {
+        let mut dst = vec![[0u8; 3]; palette.len()];
+        self.executor
+            .transform(palette.as_flattened(), dst.as_flattened_mut())?;
+        Ok(dst)
+    }
+
+    /// Converts palette and expands indices into `dst` having `dst_layout`.
+    ///
+    /// Alpha, when layout has it, is set to opaque.
+    /// Each index must point into the palette.
+    pub fn transform_indexed_image(
+        &self,
+        indices: &[u8],
+        palette: &[[u8; 3]],
+        dst: &mut [u8],
+        dst_layout: Layout,
+    ) -> Result<(), CmsError> {
+        if dst_layout == Layout::Gray || dst_layout == Layout::GrayAlpha {
+            return Err(CmsError::InvalidLayout);
+        }
+        let channels = dst_layout.channels();
+        if dst.len() % channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if dst.len() / channels != indices.len() {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        let converted = self.transformed_palette(palette)?;
+        let (r_i, g_i, b_i) = (dst_layout.r_i(), dst_layout.g_i(), dst_layout.b_i());
+        let a_i = if channels == 4 { dst_layout.a_i() } else { 0 };
+        for (&index, dst) in indices.iter().zip(dst.chunks_exact_mut(channels)) {
+            let color = converted
+                .get(index as usize)
+                .ok_or(CmsError::InvalidPaletteIndex(index))?;
+            dst[r_i] = color[0];
+            dst[g_i] = color[1];
+            dst[b_i] = color[2];
+            if channels == 4 {
+                dst[a_i] = 255;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ColorProfile {
+    /// Creates converter of RGB palettes of this profile into `dst_pr`.
+    ///
+    /// Palette entries are converted as [Layout::Rgb] pixels by 8 bit transform
+    /// made with `options`, see [ColorProfile::create_transform_8bit].
+    pub fn create_palette_transform(
+        &self,
+        dst_pr: &ColorProfile,
+        options: TransformOptions,
+    ) -> Result<PaletteTransform, CmsError> {
+        let executor = self.create_transform_8bit(Layout::Rgb, dst_pr, Layout::Rgb, options)?;
+        Ok(PaletteTransform { executor })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColorProfile, TransformOptions};
+    use rand::Rng;
+
+    #[test]
+    fn test_palette_matches_expanded_image() {
+        let srgb = ColorProfile::new_srgb();
+        let bt2020 = ColorProfile::new_bt2020();
+        let transform = srgb
+            .create_transform_8bit(
+                Layout::Rgb,
+                &bt2020,
+                Layout::Rgb,
+                TransformOptions::default(),
+            )
+            .unwrap();
+        let palette_transform = srgb
+            .create_palette_transform(&bt2020, TransformOptions::default())
+            .unwrap();
+        let mut rng = rand::rng();
+        let palette = (0..256)
+            .map(|_| [rng.random(), rng.random(), rng.random()])
+            .collect::<Vec<[u8; 3]>>();
+        let indices = (0..4096)
+            .map(|_| rng.random_range(0..palette.len()) as u8)
+            .collect::<Vec<u8>>();
+
+        let expanded = indices
+            .iter()
+            .flat_map(|&x| palette[x as usize])
+            .collect::<Vec<u8>>();
+        let mut reference = vec![0u8; expanded.len()];
+        transform.transform(&expanded, &mut reference).unwrap();
+
+        let converted = palette_transform.transformed_palette(&palette).unwrap();
+        let mut in_place = palette.clone();
+        palette_transform.transform_palette(&mut in_place).unwrap();
+        assert_eq!(converted, in_place);
+        for (&index, pixel) in indices.iter().zip(reference.chunks_exact(3)) {
+            assert_eq!(converted[index as usize], pixel);
+        }
+
+        let mut rgb = vec![0u8; indices.len() * 3];
+        palette_transform
+            .transform_indexed_image(&indices, &palette, &mut rgb, Layout::Rgb)
+            .unwrap();
+        assert_eq!(rgb, reference);
+
+        let mut argb = vec![0u8; indices.len() * 4];
+        palette_transform
+            .transform_indexed_image(&indices, &palette, &mut argb, Layout::Argb)
+            .unwrap();
+        for (argb, rgb) in argb.chunks_exact(4).zip(reference.chunks_exact(3)) {
+            assert_eq!(argb, [255, rgb[0], rgb[1], rgb[2]]);
+        }
+    }
+
+    #[test]
+    fn test_indexed_image_rejects_bad_index() {
+        let srgb = ColorProfile::new_srgb();
+        let transform = srgb
+            .create_palette_transform(&srgb, TransformOptions::default())
+            .unwrap();
+        let palette = [[0u8, 0, 0], [255, 255, 255]];
+        let mut dst = [0u8; 8];
+        assert_eq!(
+            transform.transform_indexed_image(&[0, 2], &palette, &mut dst, Layout::Rgba),
+            Err(CmsError::InvalidPaletteIndex(2))
+        );
+        assert_eq!(
+            transform.transform_indexed_image(&[0, 1], &palette, &mut dst[..4], Layout::Rgba),
+            Err(CmsError::LaneSizeMismatch)
+        );
+    }
+}
@@ -0,0 +1,243 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::profile::ProfileHeader;
+use crate::tag::{TAG_SIZE, Tag};
+use crate::{CmsError, ColorProfile, ParseWarning};
+
+/// Profile read from the first bytes of its data only, see [ColorProfile::parse_prefix].
+#[derive(Debug, Clone)]
+pub struct PartialProfile {
+    /// Profile with tags which data lies entirely within available bytes
+    pub profile: ColorProfile,
+    /// Signatures of tags which data is not available, in tag table order
+    pub missing_tags: Vec<u32>,
+    /// Whole tag table is available, otherwise some tags are not even known
+    pub tag_table_complete: bool,
+    /// Profile size declared in the header
+    pub declared_size: usize,
+    /// Recoverable defects found in available tags
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl PartialProfile {
+    /// Returns missing tags which transforms read, as colorants, tone curves,
+    /// media white point and LUTs. Missing text or private tags do not matter for them.
+    pub fn missing_required_tags(&self) -> Vec<u32> {
+        self.missing_tags
+            .iter()
+            .copied()
+            .filter(|&signature| {
+                Tag::try_from(signature).is_ok_and(|tag| {
+                    matches!(
+                        tag,
+                        Tag::RedXyz
+                            | Tag::GreenXyz
+                            | Tag::BlueXyz
+                            | Tag::RedToneReproduction
+                            | Tag::GreenToneReproduction
+                            | Tag::BlueToneReproduction
+                            | Tag::GreyToneReproduction
+                            | Tag::MediaWhitePoint
+                            | Tag::CodeIndependentPoints
+                            | Tag::ChromaticAdaptation
+                            | Tag::DeviceToPcsLutPerceptual
+                            | Tag::DeviceToPcsLutColorimetric
+                            | Tag::DeviceToPcsLutSaturation
+                            | Tag::PcsToDeviceLutPerceptual
+                            | Tag::PcsToDeviceLutColorimetric
+                            | Tag::PcsToDeviceLutSaturation
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Whole profile data was available
+    pub fn is_complete(&self) -> bool {
+        self.tag_table_complete && self.missing_tags.is_empty()
+    }
+
+    /// All tags transforms read are available, the profile converts colors
+    /// exactly as the complete one would.
+    pub fn is_colorimetrically_complete(&self) -> bool {
+        self.tag_table_complete && self.missing_required_tags().is_empty()
+    }
+
+    /// Returns the profile if it is colorimetrically complete,
+    /// otherwise [CmsError::InvalidProfile].
+    pub fn into_profile(self) -> Result<ColorProfile, CmsError> {
+        if self.is_colorimetrically_complete() {
+            Ok(self.profile)
+        } else {
+            Err(CmsError::InvalidProfile)
+        }
+    }
+}
+
+impl ColorProfile {
+    /// Parses profile from the first bytes of its data, e.g. from partially downloaded image.
+    ///
+    /// Header must be available, then every tag which data lies entirely within `bytes`
+    /// is read as [ColorProfile::new_from_slice_with_warnings] does,
+    /// the rest are listed in [PartialProfile::missing_tags].
+    /// Available tags which are malformed fail parsing as usual.
+    pub fn parse_prefix(bytes: &[u8]) -> Result<PartialProfile, CmsError> {
+        let header = ProfileHeader::new_from_slice(bytes)?;
+        let header_size = size_of::<ProfileHeader>();
+        let tags_count = header.tag_count as usize;
+        let available_entries = tags_count.min((bytes.len() - header_size) / TAG_SIZE);
+
+        // Available tags are moved to the start of the table, which is shortened
+        // to them, tag data keeps its offsets
+        let mut prefix = bytes.to_vec();
+        let mut missing_tags = Vec::new();
+        let mut kept = 0usize;
+        for i in 0..available_entries {
+            let entry = &bytes[header_size + i * TAG_SIZE..header_size + (i + 1) * TAG_SIZE];
+            let signature = u32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]);
+            let offset = u32::from_be_bytes([entry[4], entry[5], entry[6], entry[7]]) as usize;
+            let size = u32::from_be_bytes([entry[8], entry[9], entry[10], entry[11]]) as usize;
+            if offset
+                .checked_add(size)
+                .is_some_and(|end| end <= bytes.len())
+            {
+                prefix[header_size + kept * TAG_SIZE..header_size + (kept + 1) * TAG_SIZE]
+                    .copy_from_slice(entry);
+                kept += 1;
+            } else {
+                missing_tags.push(signature);
+            }
+        }
+        prefix[header_size - 4..header_size].copy_from_slice(&(kept as u32).to_be_bytes());
+        let (profile, warnings) = ColorProfile::new_from_slice_with_warnings(&prefix)?;
+        Ok(PartialProfile {
+            profile,
+            missing_tags,
+            tag_table_complete: available_entries == tags_count,
+            declared_size: header.size as usize,
+            warnings,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "transforms"))]
+mod tests {
+    use super::*;
+    use crate::{Layout, TransformOptions};
+
+    /// Returns signature, offset and size of every tag in the table
+    fn tag_table(bytes: &[u8]) -> Vec<(u32, usize, usize)> {
+        let count = u32::from_be_bytes(bytes[128..132].try_into().unwrap()) as usize;
+        bytes[132..132 + count * TAG_SIZE]
+            .chunks_exact(TAG_SIZE)
+            .map(|x| {
+                (
+                    u32::from_be_bytes(x[0..4].try_into().unwrap()),
+                    u32::from_be_bytes(x[4..8].try_into().unwrap()) as usize,
+                    u32::from_be_bytes(x[8..12].try_into().unwrap()) as usize,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_prefix() {
+        let mut srgb = ColorProfile::new_srgb();
+        srgb.set_description("sRGB with a description long enough to be cut");
+        srgb.set_copyright("No copyright, use freely");
+        let bytes = srgb.encode().unwrap();
+        let table = tag_table(&bytes);
+        let end_of = |tag: Tag| {
+            let signature: u32 = tag.into();
+            let (_, offset, size) = table.iter().find(|x| x.0 == signature).unwrap();
+            offset + size
+        };
+        let desc: u32 = Tag::ProfileDescription.into();
+        let cprt: u32 = Tag::Copyright.into();
+        let colorimetry_end = table
+            .iter()
+            .filter(|x| x.0 != desc && x.0 != cprt)
+            .map(|x| x.1 + x.2)
+            .max()
+            .unwrap();
+        assert!(colorimetry_end < end_of(Tag::ProfileDescription));
+
+        let reference = ColorProfile::new_from_slice(&bytes).unwrap();
+        let transform = |profile: &ColorProfile| {
+            let t = profile
+                .create_transform_8bit(
+                    Layout::Rgb,
+                    &ColorProfile::new_display_p3(),
+                    Layout::Rgb,
+                    TransformOptions::default(),
+                )
+                .unwrap();
+            let src = (0..=255u8)
+                .flat_map(|x| [x, 255 - x, x / 2])
+                .collect::<Vec<_>>();
+            let mut dst = vec![0u8; src.len()];
+            t.transform(&src, &mut dst).unwrap();
+            dst
+        };
+        let expected = transform(&reference);
+
+        // Whole profile
+        let partial = ColorProfile::parse_prefix(&bytes).unwrap();
+        assert!(partial.is_complete());
+        assert_eq!(partial.declared_size, bytes.len());
+
+        // Text tags cut off, transforms are unaffected
+        for cut in [colorimetry_end, end_of(Tag::ProfileDescription) - 1] {
+            let partial = ColorProfile::parse_prefix(&bytes[..cut]).unwrap();
+            assert!(!partial.is_complete());
+            assert!(partial.is_colorimetrically_complete());
+            assert!(partial.missing_tags.contains(&cprt));
+            assert!(partial.missing_required_tags().is_empty());
+            let profile = partial.into_profile().unwrap();
+            assert!(profile.copyright.is_none());
+            assert_eq!(transform(&profile), expected);
+        }
+
+        // Blue tone curve cut off
+        let cut = end_of(Tag::BlueToneReproduction) - 1;
+        let partial = ColorProfile::parse_prefix(&bytes[..cut]).unwrap();
+        let btrc: u32 = Tag::BlueToneReproduction.into();
+        assert!(partial.missing_required_tags().contains(&btrc));
+        assert!(!partial.is_colorimetrically_complete());
+        assert_eq!(partial.into_profile().err(), Some(CmsError::InvalidProfile));
+
+        // Tag table cut off
+        let partial = ColorProfile::parse_prefix(&bytes[..132 + TAG_SIZE * 2 + 5]).unwrap();
+        assert!(!partial.tag_table_complete);
+        assert!(!partial.is_colorimetrically_complete());
+
+        // Header cut off
+        assert!(ColorProfile::parse_prefix(&bytes[..100]).is_err());
+    }
+}
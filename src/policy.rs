@@ -0,0 +1,212 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::{CmsError, ColorProfile, DataColorSpace, ParseWarning};
+use std::sync::OnceLock;
+
+/// Recoverable problem found by [ProfilePolicy::resolve_with_warnings]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PolicyWarning {
+    /// Embedded profile can't be parsed, policy default was used instead
+    CorruptEmbeddedProfile(CmsError),
+    /// Embedded profile describes another color space, policy default was used instead
+    ColorSpaceMismatch(DataColorSpace),
+    /// Embedded profile was used, but parser found a recoverable defect
+    Parse(ParseWarning),
+}
+
+/// Decides which profile describes an image: embedded one when it is valid,
+/// otherwise default for image color space.
+///
+/// Untagged RGB is assumed to be sRGB and untagged gray is assumed to be gray with gamma 2.2,
+/// there is no default CMYK profile until `cmyk` is set.
+/// Defaults are overridden per color space by setting corresponding field.
+#[derive(Debug, Clone, Default)]
+pub struct ProfilePolicy {
+    /// Profile for untagged RGB images, sRGB if not set
+    pub rgb: Option<ColorProfile>,
+    /// Profile for untagged gray images, gray with gamma 2.2 if not set
+    pub gray: Option<ColorProfile>,
+    /// Profile for untagged CMYK images, untagged CMYK is an error if not set
+    pub cmyk: Option<ColorProfile>,
+    /// Invalid embedded profile is an error instead of falling back to default
+    pub strict: bool,
+}
+
+fn cached_srgb() -> &'static ColorProfile {
+    static SRGB: OnceLock<ColorProfile> = OnceLock::new();
+    SRGB.get_or_init(ColorProfile::new_srgb)
+}
+
+fn cached_gray() -> &'static ColorProfile {
+    static GRAY: OnceLock<ColorProfile> = OnceLock::new();
+    GRAY.get_or_init(|| ColorProfile::new_gray_with_gamma(2.2))
+}
+
+impl ProfilePolicy {
+    /// Returns profile assumed for untagged image in `color_space`
+    pub fn default_profile(&self, color_space: DataColorSpace) -> Result<ColorProfile, CmsError> {
+        let profile = match color_space {
+            DataColorSpace::Rgb => self.rgb.as_ref().unwrap_or_else(|| cached_srgb()),
+            DataColorSpace::Gray => self.gray.as_ref().unwrap_or_else(|| cached_gray()),
+            DataColorSpace::Cmyk => self
+                .cmyk
+                .as_ref()
+                .ok_or(CmsError::UnsupportedProfileConnection)?,
+            _ => return Err(CmsError::UnsupportedProfileConnection),
+        };
+        Ok(profile.clone())
+    }
+
+    /// Resolves profile for an image in `color_space` with optional `embedded` profile.
+    ///
+    /// See [ProfilePolicy::resolve_with_warnings] for details.
+    pub fn resolve(
+        &self,
+        color_space: DataColorSpace,
+        embedded: Option<&[u8]>,
+    ) -> Result<ColorProfile, CmsError> {
+        self.resolve_with_warnings(color_space, embedded)
+            .map(|(profile, _)| profile)
+    }
+
+    /// Resolves profile for an image in `color_space` with optional `embedded` profile.
+    ///
+    /// Embedded profile is used when it parses and describes `color_space`,
+    /// otherwise the default is returned with a warning, or an error when policy is strict.
+    pub fn resolve_with_warnings(
+        &self,
+        color_space: DataColorSpace,
+        embedded: Option<&[u8]>,
+    ) -> Result<(ColorProfile, Vec<PolicyWarning>), CmsError> {
+        let Some(embedded) = embedded else {
+            return Ok((self.default_profile(color_space)?, Vec::new()));
+        };
+        let warning = match ColorProfile::new_from_slice_with_warnings(embedded) {
+            Ok((profile, warnings)) if profile.color_space == color_space => {
+                let warnings = warnings.into_iter().map(PolicyWarning::Parse).collect();
+                return Ok((profile, warnings));
+            }
+            Ok((profile, _)) => {
+                if self.strict {
+                    return Err(CmsError::InvalidProfile);
+                }
+                PolicyWarning::ColorSpaceMismatch(profile.color_space)
+            }
+            Err(err) => {
+                if self.strict {
+                    return Err(err);
+                }
+                PolicyWarning::CorruptEmbeddedProfile(err)
+            }
+        };
+        Ok((self.default_profile(color_space)?, vec![warning]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_policy() {
+        let policy = ProfilePolicy::default();
+        let srgb = ColorProfile::new_srgb();
+
+        let rgb = policy.resolve(DataColorSpace::Rgb, None).unwrap();
+        assert_eq!(rgb.red_colorant, srgb.red_colorant);
+        let gray = policy.resolve(DataColorSpace::Gray, None).unwrap();
+        assert_eq!(gray.color_space, DataColorSpace::Gray);
+        assert!(gray.gray_trc.is_some());
+        assert_eq!(
+            policy.resolve(DataColorSpace::Cmyk, None).unwrap_err(),
+            CmsError::UnsupportedProfileConnection
+        );
+        assert!(policy.resolve(DataColorSpace::Lab, None).is_err());
+
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let policy = ProfilePolicy {
+            cmyk: Some(cmyk.clone()),
+            rgb: Some(ColorProfile::new_display_p3()),
+            ..Default::default()
+        };
+        let resolved = policy.resolve(DataColorSpace::Cmyk, None).unwrap();
+        assert_eq!(resolved.color_space, DataColorSpace::Cmyk);
+        let resolved = policy.resolve(DataColorSpace::Rgb, None).unwrap();
+        assert_eq!(
+            resolved.red_colorant,
+            ColorProfile::new_display_p3().red_colorant
+        );
+
+        // Valid embedded profile wins over defaults
+        let embedded = ColorProfile::new_bt2020().encode().unwrap();
+        let (resolved, warnings) = policy
+            .resolve_with_warnings(DataColorSpace::Rgb, Some(&embedded))
+            .unwrap();
+        assert!(warnings.is_empty());
+        let bt2020 = ColorProfile::new_from_slice(&embedded).unwrap();
+        assert_eq!(resolved.red_colorant, bt2020.red_colorant);
+
+        // Corrupt embedded profile falls back to default
+        let corrupt = &embedded[..100];
+        let (resolved, warnings) = policy
+            .resolve_with_warnings(DataColorSpace::Rgb, Some(corrupt))
+            .unwrap();
+        assert_eq!(
+            resolved.red_colorant,
+            ColorProfile::new_display_p3().red_colorant
+        );
+        assert!(matches!(
+            warnings.as_slice(),
+            [PolicyWarning::CorruptEmbeddedProfile(_)]
+        ));
+
+        // Embedded profile of another color space falls back to default
+        let (resolved, warnings) = policy
+            .resolve_with_warnings(DataColorSpace::Cmyk, Some(&embedded))
+            .unwrap();
+        assert_eq!(resolved.color_space, DataColorSpace::Cmyk);
+        assert_eq!(
+            warnings,
+            vec![PolicyWarning::ColorSpaceMismatch(DataColorSpace::Rgb)]
+        );
+
+        let strict = ProfilePolicy {
+            strict: true,
+            ..policy
+        };
+        assert!(strict.resolve(DataColorSpace::Rgb, Some(corrupt)).is_err());
+        assert_eq!(
+            strict
+                .resolve(DataColorSpace::Cmyk, Some(&embedded))
+                .unwrap_err(),
+            CmsError::InvalidProfile
+        );
+    }
+}
@@ -0,0 +1,112 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+//! Stable set of items needed to parse profiles and run transforms.
+//!
+//! ```
+//! use moxcms::prelude::*;
+//! ```
+//!
+//! Items here follow semver, everything that is not re-exported
+//! by the prelude might change more often.
+pub use crate::{
+    CicpProfile, CmsError, ColorPrimaries, ColorProfile, DataColorSpace, InterpolationMethod, Lab,
+    Layout, MatrixCoefficients, ProfileClass, ProfileVersion, RenderingIntent, Rgb,
+    TransferCharacteristics, Transform8BitExecutor, Transform16BitExecutor, TransformExecutor,
+    TransformF32BitExecutor, TransformF64BitExecutor, TransformOptions, Xyz, YCbCrMatrix,
+};
+
+#[cfg(test)]
+mod tests {
+    // Items are checked by name resolution, these tests fail to build if any of them is gone
+
+    #[test]
+    #[allow(unused_imports)]
+    fn test_prelude_items() {
+        use crate::prelude::{
+            CicpProfile, CmsError, ColorPrimaries, ColorProfile, DataColorSpace,
+            InterpolationMethod, Lab, Layout, MatrixCoefficients, ProfileClass, ProfileVersion,
+            RenderingIntent, Rgb, TransferCharacteristics, Transform8BitExecutor,
+            Transform16BitExecutor, TransformExecutor, TransformF32BitExecutor,
+            TransformF64BitExecutor, TransformOptions, Xyz, YCbCrMatrix,
+        };
+    }
+
+    /// Every item re-exported from the crate root, update with care
+    #[test]
+    #[allow(unused_imports)]
+    fn test_public_api_items() {
+        #[cfg(feature = "transforms")]
+        use crate::{
+            AccuracyReport, BuildProgress, ConsistencyReport, GamutCheck8BitExecutor,
+            GamutCheckExecutor, KernelDeviation, KernelPath, LutBuildSession, PaletteTransform,
+            RoundTripReport, ScanlineLab, ScanlineLabFactory, estimate_accuracy, roundtrip_report,
+            verify_simd_consistency,
+        };
+        use crate::{
+            AlphaMode, BT2020_MATRIX, ChromaticAdaptation, Chromaticity, CicpColorPrimaries,
+            CicpProfile, ClampPolicy, CmsError, ColorDateTime, ColorPrimaries, ColorProfile,
+            CompatibilityMode, CpuFeatures, DISPLAY_P3_MATRIX, DataColorSpace, DescriptionString,
+            DitherMode, EdidChromaticity, EncodeOptions, GammaLutInterpolate, HLG_LUT_TABLE, ICtCp,
+            InPlaceStage, InterpolationMethod, Jzazbz, Jzczhz, KernelFamily, LCh, Lab, Layout, Lch,
+            LocalizableString, LutMCurvesType, LutType, LutWarehouse, Luv, Matrix3, Matrix3d,
+            Matrix3f, Matrix4f, MatrixCoefficients, Measurement, MeasurementGeometry,
+            NamedColorant, Oklab, Oklch, PQ_LUT_TABLE, ParseStatistics, ParseWarning,
+            ParsingOptions, PartialProfile, PointeeSizeExpressible, PolicyWarning, ProfileClass,
+            ProfilePolicy, ProfileSignature, ProfileText, ProfileValidation, ProfileVersion,
+            RenderingIntent, Rgb, SRGB_MATRIX, StandardIlluminant, StandardObserver, Strictness,
+            Surround, SurroundCompensation, TagDefect, TechnologySignatures, ToneReprCurve,
+            TransferCharacteristics, Transform8BitExecutor, Transform8BitTo16BitExecutor,
+            Transform16BitExecutor, Transform16BitTo8BitExecutor, TransformDepthExecutor,
+            TransformExecutor, TransformF32BitExecutor, TransformF64BitExecutor,
+            TransformMask8BitExecutor, TransformMaskExecutor, TransformOptions, ValidationIssue,
+            Vcgt, VcgtFormula, Vector3, Vector3d, Vector3f, Vector3i, Vector3u, Vector4, Vector4d,
+            Vector4f, ViewingConditions, WHITE_POINT_D50, WHITE_POINT_D60, WHITE_POINT_D65,
+            WHITE_POINT_DCI_P3, XyY, Xyz, Xyzd, YCbCrMatrix, Ych, Yrg, adapt_to_d50,
+            adapt_to_d50_d, adapt_to_illuminant, adapt_to_illuminant_d, adapt_to_illuminant_xyz,
+            adapt_to_illuminant_xyz_d, atan2f, atanf, cbrtf, cie_y_1931_to_cie_y_2006,
+            const_hypotf, cosf, cpu_features, curve_from_fn, curve_from_gamma, depth_8_to_16,
+            depth_16_to_8, exp, expf, floor, floorf, gamut_clip_adaptive_l0_0_5,
+            gamut_clip_adaptive_l0_l_cusp, gamut_clip_preserve_chroma,
+            gamut_clip_project_to_l_cusp, hypotf, log, logf, pow, powf, sinf, sqrtf,
+        };
+        #[cfg(feature = "diagnostics")]
+        use crate::{CmykQualityReport, cmyk_quality_report};
+        #[cfg(feature = "gpu")]
+        use crate::{GpuOptions, GpuTransform};
+    }
+
+    /// Items public by accident are kept only as deprecated shims
+    #[test]
+    #[allow(deprecated, unused_imports)]
+    fn test_compat_items() {
+        use crate::compat::{Array3D, Array4D, Stage, rounding_div_ceil};
+        use crate::{Array3D as _, Array4D as _, Stage as _, rounding_div_ceil as _};
+    }
+}
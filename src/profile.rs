@@ -26,20 +26,24 @@
  * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
-use crate::chad::adapt_to_d50;
+use crate::chad::{adapt_to_d50, adaption_matrix};
 use crate::cicp::{
     CicpColorPrimaries, ColorPrimaries, MatrixCoefficients, TransferCharacteristics,
 };
+use crate::colorant_table::{NamedColorant, parse_colorant_table};
 use crate::dat::ColorDateTime;
-use crate::err::CmsError;
+use crate::err::{CmsError, TagDefect};
 use crate::matrix::{BT2020_MATRIX, DISPLAY_P3_MATRIX, Matrix3f, SRGB_MATRIX, XyY, Xyz};
+use crate::profile_source::{ProfileSource, ReaderSource};
 use crate::safe_reader::{SafeAdd, SafeMul};
 use crate::tag::{TAG_SIZE, Tag, TagTypeDefinition};
 use crate::trc::ToneReprCurve;
-use crate::{Chromaticity, Layout, Matrix3d, Vector3f, Xyzd, adapt_to_d50_d};
-use std::io::Read;
+use crate::vcgt::Vcgt;
+use crate::{ChromaticAdaptation, Chromaticity, Layout, Matrix3d, Vector3f, Xyzd, adapt_to_d50_d};
+use std::io::{Read, Seek};
 
 const MAX_PROFILE_SIZE: usize = 1024 * 1024 * 10; // 10 MB max, for Fogra39 etc
+pub(crate) const MAX_TAGS_COUNT: u32 = 1024;
 
 #[inline]
 fn uint8_number_to_float(a: u8) -> f32 {
@@ -54,6 +58,28 @@ fn utf16be_to_utf16(slice: &[u8]) -> Vec<u16> {
     vec
 }
 
+/// Text of NUL terminated ASCII field, also tells if anything but NUL padding follows the NUL.
+fn ascii_text(data: &[u8]) -> (String, bool) {
+    let end = data.iter().position(|&x| x == 0).unwrap_or(data.len());
+    let past_terminator = data[end..].iter().any(|&x| x != 0);
+    (
+        String::from_utf8_lossy(&data[..end]).to_string(),
+        past_terminator,
+    )
+}
+
+/// Text of UTF-16BE field without trailing NUL padding, NULs inside are kept as text.
+///
+/// Unpaired surrogates become U+FFFD, second value tells if there were any.
+fn utf16_text(data: &[u8]) -> (String, bool) {
+    let mut units = utf16be_to_utf16(data);
+    while units.last() == Some(&0) {
+        units.pop();
+    }
+    let invalid = char::decode_utf16(units.iter().copied()).any(|x| x.is_err());
+    (String::from_utf16_lossy(&units), invalid)
+}
+
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProfileSignature {
@@ -174,7 +200,15 @@ impl DataColorSpace {
             DataColorSpace::Luv => layout != Layout::Rgb,
             DataColorSpace::YCbr => layout != Layout::Rgb,
             DataColorSpace::Yxy => layout != Layout::Rgb,
-            DataColorSpace::Rgb => layout != Layout::Rgb && layout != Layout::Rgba,
+            DataColorSpace::Rgb => !matches!(
+                layout,
+                Layout::Rgb
+                    | Layout::Rgba
+                    | Layout::Bgr
+                    | Layout::Bgra
+                    | Layout::Argb
+                    | Layout::Abgr
+            ),
             DataColorSpace::Gray => layout != Layout::Gray && layout != Layout::GrayAlpha,
             DataColorSpace::Hsv => layout != Layout::Rgb,
             DataColorSpace::Hls => layout != Layout::Rgb,
@@ -191,6 +225,27 @@ impl DataColorSpace {
             Ok(())
         }
     }
+
+    /// Count of device channels of the color space
+    pub const fn channels(self) -> usize {
+        match self {
+            DataColorSpace::Gray => 1,
+            DataColorSpace::Color2 => 2,
+            DataColorSpace::Cmyk | DataColorSpace::Color4 => 4,
+            DataColorSpace::Color5 => 5,
+            DataColorSpace::Color6 => 6,
+            DataColorSpace::Color7 => 7,
+            DataColorSpace::Color8 => 8,
+            DataColorSpace::Color9 => 9,
+            DataColorSpace::Color10 => 10,
+            DataColorSpace::Color11 => 11,
+            DataColorSpace::Color12 => 12,
+            DataColorSpace::Color13 => 13,
+            DataColorSpace::Color14 => 14,
+            DataColorSpace::Color15 => 15,
+            _ => 3,
+        }
+    }
 }
 
 #[repr(u32)]
@@ -470,14 +525,71 @@ pub enum LutWarehouse {
 }
 
 impl LutWarehouse {
-    pub(crate) fn as_lut(&self) -> Option<&LutDataType> {
-        match self {
-            LutWarehouse::Lut(lut) => Some(lut),
-            LutWarehouse::MCurves(_) => None,
-        }
+    /// lut16Type keeps legacy 16-bit Lab encoding, where 0xFF00 is L = 100, in any profile version,
+    /// lut8Type and lutAtoBType/lutBtoAType use the current one, as lcms2 does.
+    #[cfg(feature = "transforms")]
+    pub(crate) fn has_legacy_lab_encoding(&self) -> bool {
+        matches!(self, LutWarehouse::Lut(lut) if lut.lut_type == LutType::Lut16)
     }
 }
 
+/// Recoverable defect found while parsing a profile.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum ParseWarning {
+    /// Tag signature appeared in the tag table again, this occurrence was ignored
+    DuplicateTag(u32),
+    /// Unknown tag points outside of the profile data, it was not retained
+    TruncatedTag(u32),
+    /// Optional tag known to the parser, as `vcgt` or `clrt`, couldn't be read, it was not retained
+    MalformedTag(u32),
+    /// Tag data runs past the end of the profile, [Strictness::Lenient] cut it at the end
+    TagSizeClamped(u32),
+    /// Data of the first tag partially overlaps data of the second one, reported in strict mode
+    OverlappingTags(u32, u32),
+    /// Reserved bytes of tag type are not zero, reported in strict mode
+    NonZeroReserved(u32),
+    /// Reserved bytes of the header are not zero, reported in strict mode
+    NonZeroHeaderReserved,
+    /// Declared length of text runs past tag data, text was cut at the end of the tag
+    TextLengthClamped(u32),
+    /// ASCII text has data after its terminating NUL, it was ignored
+    TextPastTerminator(u32),
+    /// UTF-16 text has unpaired surrogates, they were replaced with U+FFFD
+    InvalidUtf16(u32),
+}
+
+/// How strictly profile structure is checked, see [ParsingOptions]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default)]
+pub enum Strictness {
+    /// Recoverable defects are reported as [ParseWarning], first of duplicate tags is used
+    /// and known tags running past the end of the profile fail parsing.
+    #[default]
+    Default,
+    /// Any defect, [ParseWarning] among them, fails parsing with [CmsError::MalformedProfile],
+    /// also partially overlapping tag data and non-zero reserved bytes.
+    Strict,
+    /// Accepts what lcms2 accepts: tag sizes running past the end of the profile are cut
+    /// at the end and the last of duplicate tags is used, both are reported as warnings.
+    Lenient,
+}
+
+/// Options of [ColorProfile::new_from_slice_with_options]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default)]
+pub struct ParsingOptions {
+    pub strictness: Strictness,
+}
+
+/// Tag counts gathered while parsing a profile, see [ColorProfile::parse_statistics].
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct ParseStatistics {
+    /// Tags with signature known to the parser
+    pub known_tags: usize,
+    /// Tags with signature unknown to the parser, private vendor tags included
+    pub unknown_tags: usize,
+    /// Total size of unknown tags, which are kept as raw bytes only
+    pub bytes_unparsed: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct LutDataType {
     // used by lut8Type/lut16Type (mft2) only
@@ -563,7 +675,7 @@ pub(crate) struct ProfileHeader {
     pub rendering_intent: RenderingIntent, // Relative colorimetric rendering intent
     pub illuminant: Xyz,                   // D50 standard illuminant X
     pub creator: u32,                      // Profile creator (ignored)
-    pub profile_id: [u8; 16],              // Profile id checksum (ignored)
+    pub profile_id: [u8; 16],              // Profile id MD5 checksum
     pub reserved: [u8; 28],                // Reserved (ignored)
     pub tag_count: u32,                    // Technically not part of header, but required
 }
@@ -597,7 +709,10 @@ impl ProfileHeader {
     /// Creates profile from the buffer
     pub(crate) fn new_from_slice(slice: &[u8]) -> Result<Self, CmsError> {
         if slice.len() < size_of::<ProfileHeader>() {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: size_of::<ProfileHeader>(),
+                got: slice.len(),
+            });
         }
         let mut cursor = std::io::Cursor::new(slice);
         let mut buffer = [0u8; size_of::<ProfileHeader>()];
@@ -646,7 +761,7 @@ impl ProfileHeader {
 
 /// A [Coding Independent Code Point](https://en.wikipedia.org/wiki/Coding-independent_code_points).
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct CicpProfile {
     pub color_primaries: CicpColorPrimaries,
     pub transfer_characteristics: TransferCharacteristics,
@@ -700,16 +815,64 @@ pub enum ProfileText {
 impl ProfileText {
     pub(crate) fn has_values(&self) -> bool {
         match self {
-            ProfileText::PlainString(_) => true,
-            ProfileText::Localizable(lc) => !lc.is_empty(),
-            ProfileText::Description(_) => true,
+            ProfileText::PlainString(text) => !text.is_empty(),
+            ProfileText::Localizable(lc) => lc.iter().any(|x| !x.value.is_empty()),
+            ProfileText::Description(description) => {
+                !description.unicode_string.is_empty() || !description.ascii_string.is_empty()
+            }
+        }
+    }
+
+    /// Returns text for the locale, e.g. `de-DE`, `de_DE` or `de`.
+    ///
+    /// Records of multi localized text are matched by language and country first,
+    /// then by language only, falling back to en-US and then to the first record.
+    pub fn localized(&self, locale: Option<&str>) -> Option<String> {
+        let text = match self {
+            ProfileText::PlainString(text) => text.as_str(),
+            ProfileText::Description(description) => {
+                let ascii = description.ascii_string.trim_end_matches('\0');
+                if ascii.is_empty() {
+                    description.unicode_string.as_str()
+                } else {
+                    ascii
+                }
+            }
+            ProfileText::Localizable(records) => {
+                let matches = |language: &str, country: Option<&str>| {
+                    records.iter().find(|x| {
+                        x.language.eq_ignore_ascii_case(language)
+                            && country.is_none_or(|c| x.country.eq_ignore_ascii_case(c))
+                    })
+                };
+                let requested = locale.and_then(|locale| {
+                    let mut parts = locale.split(['-', '_']);
+                    let language = parts.next()?;
+                    let country = parts.next();
+                    matches(language, country).or_else(|| matches(language, None))
+                });
+                requested
+                    .or_else(|| matches("en", Some("US")))
+                    .or_else(|| matches("en", None))
+                    .or(records.first())?
+                    .value
+                    .as_str()
+            }
+        };
+        let text = text.trim_end_matches('\0');
+        if text.is_empty() {
+            None
+        } else {
+            Some(text.to_string())
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum StandardObserver {
+    /// CIE 1931 2° standard observer
     D50,
+    /// CIE 1964 10° standard observer
     D65,
     Unknown,
 }
@@ -725,6 +888,16 @@ impl From<u32> for StandardObserver {
     }
 }
 
+impl From<StandardObserver> for u32 {
+    fn from(value: StandardObserver) -> Self {
+        match value {
+            StandardObserver::Unknown => 0,
+            StandardObserver::D50 => 1,
+            StandardObserver::D65 => 2,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct ViewingConditions {
     pub illuminant: Xyz,
@@ -732,7 +905,7 @@ pub struct ViewingConditions {
     pub observer: StandardObserver,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum MeasurementGeometry {
     Unknown,
     /// 0°:45° or 45°:0°
@@ -753,7 +926,17 @@ impl From<u32> for MeasurementGeometry {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+impl From<MeasurementGeometry> for u32 {
+    fn from(value: MeasurementGeometry) -> Self {
+        match value {
+            MeasurementGeometry::Unknown => 0,
+            MeasurementGeometry::D45to45 => 1,
+            MeasurementGeometry::D0to0 => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum StandardIlluminant {
     Unknown,
     D50,
@@ -798,15 +981,33 @@ impl From<StandardIlluminant> for u32 {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Measurement conditions of profile data from `meas` tag
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Measurement {
     pub observer: StandardObserver,
+    /// XYZ of measurement backing
     pub backing: Xyz,
     pub geometry: MeasurementGeometry,
+    /// Flare in [0, 1]
     pub flare: f32,
     pub illuminant: StandardIlluminant,
 }
 
+impl Measurement {
+    /// Profiles measured under these conditions are colorimetrically comparable:
+    /// observer, geometry and illuminant are the same.
+    ///
+    /// Unknown values match nothing, backing and flare are not compared.
+    pub fn is_compatible_with(&self, other: &Measurement) -> bool {
+        self.observer == other.observer
+            && self.observer != StandardObserver::Unknown
+            && self.geometry == other.geometry
+            && self.geometry != MeasurementGeometry::Unknown
+            && self.illuminant == other.illuminant
+            && self.illuminant != StandardIlluminant::Unknown
+    }
+}
+
 /// ICC Profile representation
 #[repr(C)]
 #[derive(Debug, Clone, Default)]
@@ -845,9 +1046,20 @@ pub struct ColorProfile {
     pub viewing_conditions_description: Option<ProfileText>,
     pub technology: Option<TechnologySignatures>,
     pub calibration_date: Option<ColorDateTime>,
+    /// Video card gamma ramp of display profiles, see [crate::TransformOptions::apply_vcgt]
+    pub vcgt: Option<Vcgt>,
+    /// Names and PCS values of device channels from `clrt` tag, in channel order
+    pub colorant_table: Vec<NamedColorant>,
+    /// Tags unknown to the parser as signature and raw tag data, written back on encoding
+    /// after the known tags unless [crate::EncodeOptions::strip_unknown_tags] is set.
+    /// Entries with signature of a known tag are not written.
+    pub unknown_tags: Vec<(u32, Vec<u8>)>,
+    pub(crate) parse_statistics: ParseStatistics,
     /// Version for internal and viewing purposes only.
     /// When encoding will be added profile will always be encoded as V4.
     pub(crate) version_internal: ProfileVersion,
+    /// Profile ID from the header of parsed bytes
+    pub(crate) profile_id_internal: [u8; 16],
 }
 
 /// Produces the nearest float to `a` with a maximum error of 1/1024 which
@@ -868,6 +1080,86 @@ impl ColorProfile {
         self.version_internal
     }
 
+    /// Returns profile ID (MD5) stored in the header of parsed profile.
+    ///
+    /// `None` when profile wasn't read from bytes or its writer left the ID zeroed,
+    /// as ICC allows. The ID is not updated when fields of the profile are changed.
+    pub fn header_profile_id(&self) -> Option<[u8; 16]> {
+        Some(self.profile_id_internal).filter(|x| x != &[0u8; 16])
+    }
+
+    /// Returns peak luminance of the device in cd/m² from Y of `lumi` tag,
+    /// `None` when the tag is absent or holds no positive value.
+    pub fn peak_luminance_nits(&self) -> Option<f32> {
+        self.luminance
+            .map(|luminance| luminance.y)
+            .filter(|&y| y.is_finite() && y > 0.)
+    }
+
+    /// Returns tag counts gathered while parsing, zeroes for profiles not read from bytes
+    pub fn parse_statistics(&self) -> ParseStatistics {
+        self.parse_statistics
+    }
+
+    /// Returns profile name from `desc` tag for the locale, see [ProfileText::localized]
+    pub fn description(&self, locale: Option<&str>) -> Option<String> {
+        self.description.as_ref()?.localized(locale)
+    }
+
+    /// Returns copyright from `cprt` tag, see [ProfileText::localized] for record choice
+    pub fn copyright(&self) -> Option<String> {
+        self.copyright.as_ref()?.localized(None)
+    }
+
+    /// Returns device manufacturer from `dmnd` tag, see [ProfileText::localized] for record choice
+    pub fn device_manufacturer_desc(&self) -> Option<String> {
+        self.device_manufacturer.as_ref()?.localized(None)
+    }
+
+    /// Returns device model from `dmdd` tag, see [ProfileText::localized] for record choice
+    pub fn device_model_desc(&self) -> Option<String> {
+        self.device_model.as_ref()?.localized(None)
+    }
+
+    /// Returns profile name for ISO 639-1 `language` and ISO 3166-1 `country` codes,
+    /// empty `country` matches any record of the language. See [ProfileText::localized].
+    pub fn description_for_locale(&self, language: &str, country: &str) -> Option<String> {
+        let locale = if country.is_empty() {
+            language.to_string()
+        } else {
+            format!("{language}-{country}")
+        };
+        self.description(Some(&locale))
+    }
+
+    fn text_or_none(text: &str) -> Option<ProfileText> {
+        if text.is_empty() {
+            None
+        } else {
+            Some(ProfileText::PlainString(text.to_string()))
+        }
+    }
+
+    /// Sets profile name written as `desc` tag, empty text removes the tag
+    pub fn set_description(&mut self, text: &str) {
+        self.description = Self::text_or_none(text);
+    }
+
+    /// Sets copyright written as `cprt` tag, empty text removes the tag
+    pub fn set_copyright(&mut self, text: &str) {
+        self.copyright = Self::text_or_none(text);
+    }
+
+    /// Sets device manufacturer written as `dmnd` tag, empty text removes the tag
+    pub fn set_device_manufacturer(&mut self, text: &str) {
+        self.device_manufacturer = Self::text_or_none(text);
+    }
+
+    /// Sets device model written as `dmdd` tag, empty text removes the tag
+    pub fn set_device_model(&mut self, text: &str) {
+        self.device_model = Self::text_or_none(text);
+    }
+
     fn read_trc_tag_s(
         slice: &[u8],
         entry: usize,
@@ -903,11 +1195,17 @@ impl ColorProfile {
             slice.len()
         };
         if last_tag_offset > slice.len() {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: last_tag_offset,
+                got: slice.len(),
+            });
         }
         let tag = &slice[entry..last_tag_offset];
         if tag.len() < TAG_SIZE {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: TAG_SIZE,
+                got: tag.len(),
+            });
         }
         if curve_type == TagTypeDefinition::LutToneCurve {
             let entry_count = u32::from_be_bytes([tag[8], tag[9], tag[10], tag[11]]) as usize;
@@ -919,7 +1217,10 @@ impl ColorProfile {
             }
             let curve_end = entry_count.safe_mul(size_of::<u16>())?.safe_add(12)?;
             if tag.len() < curve_end {
-                return Err(CmsError::InvalidProfile);
+                return Err(CmsError::TruncatedData {
+                    expected: curve_end,
+                    got: tag.len(),
+                });
             }
             let curve_sliced = &tag[12..curve_end];
             let mut curve_values = vec![0u16; entry_count];
@@ -938,7 +1239,10 @@ impl ColorProfile {
             const COUNT_TO_LENGTH: [usize; 5] = [1, 3, 4, 5, 7]; //PARAMETRIC_CURVE_TYPE
 
             if tag.len() < 12 + COUNT_TO_LENGTH[entry_count] * size_of::<u32>() {
-                return Err(CmsError::InvalidProfile);
+                return Err(CmsError::TruncatedData {
+                    expected: 12 + COUNT_TO_LENGTH[entry_count] * size_of::<u32>(),
+                    got: tag.len(),
+                });
             }
             let curve_sliced = &tag[12..12 + COUNT_TO_LENGTH[entry_count] * size_of::<u32>()];
             let mut params = vec![0f32; COUNT_TO_LENGTH[entry_count]];
@@ -954,9 +1258,9 @@ impl ColorProfile {
                 }
             }
             *read_size = 12 + COUNT_TO_LENGTH[entry_count] * 4;
-            return Ok(Some(ToneReprCurve::Parametric(params)));
+            Ok(Some(ToneReprCurve::Parametric(params)))
         } else {
-            return Err(CmsError::InvalidProfile);
+            Err(CmsError::InvalidProfile)
         }
     }
 
@@ -968,7 +1272,10 @@ impl ColorProfile {
     ) -> Result<Option<Matrix3f>, CmsError> {
         let last_tag_offset = tag_size.safe_add(entry)?;
         if last_tag_offset > slice.len() {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: last_tag_offset,
+                got: slice.len(),
+            });
         }
         if slice[entry..].len() < 8 {
             return Err(CmsError::InvalidProfile);
@@ -1011,7 +1318,10 @@ impl ColorProfile {
         }
         let last_tag_offset = tag_size.safe_add(entry)?;
         if last_tag_offset > slice.len() {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: last_tag_offset,
+                got: slice.len(),
+            });
         }
         let tag = &slice[entry..entry.safe_add(12)?];
         let tag_type = u32::from_be_bytes([tag[0], tag[1], tag[2], tag[3]]);
@@ -1035,13 +1345,16 @@ impl ColorProfile {
         }
         let last_tag_offset = tag_size.safe_add(entry)?;
         if last_tag_offset > slice.len() {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: last_tag_offset,
+                got: slice.len(),
+            });
         }
         let tag = &slice[entry..entry.safe_add(20)?];
         let tag_type = u32::from_be_bytes([tag[0], tag[1], tag[2], tag[3]]);
         let def = TagTypeDefinition::from(tag_type);
         if def == TagTypeDefinition::DateTime {
-            let tag_value = &slice[8..20];
+            let tag_value = &tag[8..20];
             let time = ColorDateTime::new_from_slice(tag_value)?;
             return Ok(Some(time));
         }
@@ -1059,7 +1372,10 @@ impl ColorProfile {
         }
         let last_tag_offset = tag_size.safe_add(entry)?;
         if last_tag_offset > slice.len() {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: last_tag_offset,
+                got: slice.len(),
+            });
         }
         let tag = &slice[entry..entry + 12];
         let tag_type = u32::from_be_bytes([tag[0], tag[1], tag[2], tag[3]]);
@@ -1067,7 +1383,7 @@ impl ColorProfile {
         if def != TagTypeDefinition::Measurement {
             return Ok(None);
         }
-        if 36 > slice.len() {
+        if tag_size < 36 {
             return Err(CmsError::InvalidProfile);
         }
         let tag = &slice[entry..entry + 36];
@@ -1102,7 +1418,10 @@ impl ColorProfile {
         }
         let last_tag_offset = tag_size.safe_add(entry)?;
         if last_tag_offset > slice.len() {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: last_tag_offset,
+                got: slice.len(),
+            });
         }
         let tag = &slice[entry..entry + 12];
         let tag_type = u32::from_be_bytes([tag[0], tag[1], tag[2], tag[3]]);
@@ -1113,7 +1432,10 @@ impl ColorProfile {
 
         let tag = &slice[entry..last_tag_offset];
         if tag.len() < 20 {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: 20,
+                got: tag.len(),
+            });
         }
         let q15_16_x = i32::from_be_bytes([tag[8], tag[9], tag[10], tag[11]]);
         let q15_16_y = i32::from_be_bytes([tag[12], tag[13], tag[14], tag[15]]);
@@ -1135,11 +1457,17 @@ impl ColorProfile {
         }
         let last_tag_offset = tag_size.safe_add(entry)?;
         if last_tag_offset > slice.len() {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: last_tag_offset,
+                got: slice.len(),
+            });
         }
         let tag = &slice[entry..last_tag_offset];
         if tag.len() < 12 {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: 12,
+                got: tag.len(),
+            });
         }
         let tag_type = u32::from_be_bytes([tag[0], tag[1], tag[2], tag[3]]);
         let def = TagTypeDefinition::from(tag_type);
@@ -1163,11 +1491,17 @@ impl ColorProfile {
         let tag_size = if tag_size == 0 { TAG_SIZE } else { tag_size };
         let last_tag_offset = tag_size.safe_add(entry)?;
         if last_tag_offset > slice.len() {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: last_tag_offset,
+                got: slice.len(),
+            });
         }
         let tag = &slice[entry..last_tag_offset];
         if tag.len() < 48 {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: 48,
+                got: tag.len(),
+            });
         }
         let tag_type = u32::from_be_bytes([tag[0], tag[1], tag[2], tag[3]]);
         LutType::try_from(tag_type)
@@ -1223,10 +1557,17 @@ impl ColorProfile {
         }))
     }
 
+    /// Reads `text`, `mluc` or `desc` tag.
+    ///
+    /// Lengths running past tag data are cut at its end, ASCII text ends at the first NUL,
+    /// only trailing NUL padding is removed from UTF-16 text, all of these are reported
+    /// as warnings for `signature`.
     fn read_string_tag(
         slice: &[u8],
         entry: usize,
         tag_size: usize,
+        signature: u32,
+        warnings: &mut Vec<ParseWarning>,
     ) -> Result<Option<ProfileText>, CmsError> {
         let tag_size = if tag_size == 0 { TAG_SIZE } else { tag_size };
         if tag_size < 4 {
@@ -1234,60 +1575,56 @@ impl ColorProfile {
         }
         let last_tag_offset = tag_size.safe_add(entry)?;
         if last_tag_offset > slice.len() {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: last_tag_offset,
+                got: slice.len(),
+            });
         }
         let tag = &slice[entry..last_tag_offset];
         if tag.len() < 8 {
             return Ok(None);
         }
+        let mut warn = |warning: ParseWarning| {
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        };
+        // Cuts `length` bytes at `offset` at the end of tag data
+        let mut field = |offset: usize, length: usize| {
+            let start = offset.min(tag.len());
+            let end = offset.saturating_add(length).min(tag.len());
+            if end - start != length {
+                warn(ParseWarning::TextLengthClamped(signature));
+            }
+            &tag[start..end]
+        };
         let tag_type =
             TagTypeDefinition::from(u32::from_be_bytes([tag[0], tag[1], tag[2], tag[3]]));
         // Ignore unknown
         if tag_type == TagTypeDefinition::Text {
-            let sliced_from_to_end = &tag[8..tag.len()];
-            let str = String::from_utf8_lossy(sliced_from_to_end);
-            return Ok(Some(ProfileText::PlainString(str.to_string())));
+            let (text, past_terminator) = ascii_text(&tag[8..]);
+            if past_terminator {
+                warnings.push(ParseWarning::TextPastTerminator(signature));
+            }
+            return Ok(Some(ProfileText::PlainString(text)));
         } else if tag_type == TagTypeDefinition::MultiLocalizedUnicode {
-            if tag.len() < 28 {
-                return Err(CmsError::InvalidProfile);
+            if tag.len() < 16 {
+                return Err(CmsError::TruncatedData {
+                    expected: 16,
+                    got: tag.len(),
+                });
             }
-            // let record_size = u32::from_be_bytes([tag[12], tag[13], tag[14], tag[15]]) as usize;
-            // // Record size is reserved to be 12.
-            // if record_size != 12 {
-            //     return Err(CmsError::InvalidIcc);
-            // }
+            // Record size is reserved to be 12.
             let records_count = u32::from_be_bytes([tag[8], tag[9], tag[10], tag[11]]) as usize;
-            let primary_language_code = String::from_utf8_lossy(&[tag[16], tag[17]]).to_string();
-            let primary_country_code = String::from_utf8_lossy(&[tag[18], tag[19]]).to_string();
-            let first_string_record_length =
-                u32::from_be_bytes([tag[20], tag[21], tag[22], tag[23]]) as usize;
-            let first_record_offset =
-                u32::from_be_bytes([tag[24], tag[25], tag[26], tag[27]]) as usize;
-
-            if tag.len() < first_record_offset.safe_add(first_string_record_length)? {
-                return Ok(None);
-            }
-
-            let resliced =
-                &tag[first_record_offset..first_record_offset + first_string_record_length];
-            let cvt = utf16be_to_utf16(resliced);
-            let string_record = String::from_utf16_lossy(&cvt);
-
-            let mut records = vec![LocalizableString {
-                language: primary_language_code,
-                country: primary_country_code,
-                value: string_record,
-            }];
-
-            for record in 1..records_count {
-                // Localizable header must be at least 12 bytes
-                let localizable_header_offset = if record == 1 {
-                    28
-                } else {
-                    28 + 12 * (record - 1)
-                };
+            let mut records = Vec::with_capacity(records_count.min((tag.len() - 16) / 12));
+            let mut invalid = false;
+            for record in 0..records_count {
+                let localizable_header_offset = 16 + 12 * record;
                 if tag.len() < localizable_header_offset + 12 {
-                    return Err(CmsError::InvalidProfile);
+                    return Err(CmsError::TruncatedData {
+                        expected: localizable_header_offset + 12,
+                        got: tag.len(),
+                    });
                 }
                 let choked = &tag[localizable_header_offset..localizable_header_offset + 12];
 
@@ -1298,64 +1635,49 @@ impl ColorProfile {
                 let string_offset =
                     u32::from_be_bytes([choked[8], choked[9], choked[10], choked[11]]) as usize;
 
-                if tag.len() < string_offset.safe_add(record_length)? {
-                    return Ok(None);
-                }
-                let resliced = &tag[string_offset..string_offset + record_length];
-                let cvt = utf16be_to_utf16(resliced);
-                let string_record = String::from_utf16_lossy(&cvt);
+                let (string_record, invalid_record) =
+                    utf16_text(field(string_offset, record_length));
+                invalid |= invalid_record;
                 records.push(LocalizableString {
                     country: country_code,
                     language: language_code,
                     value: string_record,
                 });
             }
+            if invalid {
+                warnings.push(ParseWarning::InvalidUtf16(signature));
+            }
 
             return Ok(Some(ProfileText::Localizable(records)));
         } else if tag_type == TagTypeDefinition::Description {
             if tag.len() < 12 {
-                return Err(CmsError::InvalidProfile);
+                return Err(CmsError::TruncatedData {
+                    expected: 12,
+                    got: tag.len(),
+                });
             }
+            // Counts include terminating NUL, Unicode count is in 16 bit units
             let ascii_length = u32::from_be_bytes([tag[8], tag[9], tag[10], tag[11]]) as usize;
-            if tag.len() < 12.safe_add(ascii_length)? {
-                return Err(CmsError::InvalidProfile);
+            let (ascii_string, past_terminator) = ascii_text(field(12, ascii_length));
+
+            let last_position = 12usize.saturating_add(ascii_length);
+            let uc = field(last_position, 8);
+            let (unicode_code, unicode_string, invalid) = if uc.len() == 8 {
+                let unicode_code = u32::from_be_bytes([uc[0], uc[1], uc[2], uc[3]]);
+                let unicode_length =
+                    (u32::from_be_bytes([uc[4], uc[5], uc[6], uc[7]]) as usize).saturating_mul(2);
+                let (unicode_string, invalid) =
+                    utf16_text(field(last_position + 8, unicode_length));
+                (unicode_code, unicode_string, invalid)
+            } else {
+                (0, String::new(), false)
+            };
+            if past_terminator {
+                warnings.push(ParseWarning::TextPastTerminator(signature));
             }
-            let sliced = &tag[12..12 + ascii_length];
-            let ascii_string = String::from_utf8_lossy(sliced).to_string();
-
-            let mut last_position = 12 + ascii_length;
-            if tag.len() < last_position + 8 {
-                return Err(CmsError::InvalidProfile);
+            if invalid {
+                warnings.push(ParseWarning::InvalidUtf16(signature));
             }
-            let uc = &tag[last_position..last_position + 8];
-            let unicode_code = u32::from_be_bytes([uc[0], uc[1], uc[2], uc[3]]);
-            let unicode_length = u32::from_be_bytes([uc[4], uc[5], uc[6], uc[7]]) as usize * 2;
-            if tag.len() < unicode_length.safe_add(8)?.safe_add(last_position)? {
-                return Ok(None);
-            }
-
-            last_position += 8;
-            let uc = &tag[last_position..last_position + unicode_length];
-            let wc = utf16be_to_utf16(uc);
-            let unicode_string = String::from_utf16_lossy(&wc).to_string();
-
-            // last_position += unicode_length;
-            //
-            // if tag.len() < last_position + 2 {
-            //     return Err(CmsError::InvalidIcc);
-            // }
-
-            // let uc = &tag[last_position..last_position + 2];
-            // let script_code = uc[0];
-            // let mac_length = uc[1] as usize;
-            // last_position += 2;
-            // if tag.len() < last_position + mac_length {
-            //     return Err(CmsError::InvalidIcc);
-            // }
-            //
-            // let uc = &tag[last_position..last_position + unicode_length];
-            // let wc = utf16be_to_utf16(uc);
-            // let mac_string = String::from_utf16_lossy(&wc).to_string();
 
             return Ok(Some(ProfileText::Description(DescriptionString {
                 ascii_string,
@@ -1424,11 +1746,17 @@ impl ColorProfile {
         }
         let last_tag_offset = tag_size.safe_add(entry)?;
         if last_tag_offset > slice.len() {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: last_tag_offset,
+                got: slice.len(),
+            });
         }
         let tag = &slice[entry..last_tag_offset];
         if tag.len() < 48 {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: 48,
+                got: tag.len(),
+            });
         }
         let tag_type = u32::from_be_bytes([tag[0], tag[1], tag[2], tag[3]]);
         let tag_type_definition = TagTypeDefinition::from(tag_type);
@@ -1453,7 +1781,10 @@ impl ColorProfile {
         if matrix_offset != 0 {
             let matrix_end = matrix_offset.safe_add(12 * 4)?;
             if tag.len() < matrix_end {
-                return Err(CmsError::InvalidProfile);
+                return Err(CmsError::TruncatedData {
+                    expected: matrix_end,
+                    got: tag.len(),
+                });
             }
 
             let m_tag = &tag[matrix_offset..matrix_end];
@@ -1525,6 +1856,13 @@ impl ColorProfile {
             }
             clut_size *= out_channels as u32;
 
+            if grid_points
+                .iter()
+                .take(in_channels as usize)
+                .any(|&x| x == 0)
+            {
+                return Err(CmsError::tag_defect(TagDefect::ZeroGridSize));
+            }
             if clut_size == 0 {
                 return Err(CmsError::InvalidProfile);
             }
@@ -1545,7 +1883,10 @@ impl ColorProfile {
                 clut_offset20.safe_add(clut_size.safe_mul(entry_size as u32)? as usize)?;
 
             if tag.len() < clut_end {
-                return Err(CmsError::InvalidProfile);
+                return Err(CmsError::TruncatedData {
+                    expected: clut_end,
+                    got: tag.len(),
+                });
             }
 
             let mut clut_table = vec![0f32; clut_size as usize];
@@ -1638,11 +1979,17 @@ impl ColorProfile {
         }
         let last_tag_offset = tag_size.safe_add(entry)?;
         if last_tag_offset > slice.len() {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: last_tag_offset,
+                got: slice.len(),
+            });
         }
         let tag = &slice[entry..last_tag_offset];
         if tag.len() < 48 {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: 48,
+                got: tag.len(),
+            });
         }
         let tag_type = u32::from_be_bytes([tag[0], tag[1], tag[2], tag[3]]);
         let lut_type = LutType::try_from(tag_type)?;
@@ -1697,7 +2044,7 @@ impl ColorProfile {
         match clut_size {
             1..=500_000 => {} // OK
             0 => {
-                return Err(CmsError::InvalidProfile);
+                return Err(CmsError::tag_defect(TagDefect::ZeroGridSize));
             }
             _ => {
                 return Err(CmsError::InvalidProfile);
@@ -1743,7 +2090,10 @@ impl ColorProfile {
             .safe_mul(entry_size)?
             .safe_add(input_offset)?;
         if tag.len() < linearization_table_end {
-            return Err(CmsError::InvalidProfile);
+            return Err(CmsError::TruncatedData {
+                expected: linearization_table_end,
+                got: tag.len(),
+            });
         }
         let shaped_input_table = &tag[input_offset..linearization_table_end];
         Self::read_lut_table_f32(shaped_input_table, &mut linearization_table, lut_type);
@@ -1765,9 +2115,16 @@ impl ColorProfile {
 
         let output_size = num_output_table_entries as usize * out_chan as usize;
 
+        let output_end = output_offset.safe_add(output_size.safe_mul(entry_size)?)?;
+        if tag.len() < output_end {
+            return Err(CmsError::TruncatedData {
+                expected: output_end,
+                got: tag.len(),
+            });
+        }
+
         let mut out_gamma_table = vec![0f32; output_size];
-        let shaped_output_table =
-            &tag[output_offset..output_offset.safe_add(output_size.safe_mul(entry_size)?)?];
+        let shaped_output_table = &tag[output_offset..output_end];
         Self::read_lut_table_f32(shaped_output_table, &mut out_gamma_table, lut_type);
 
         let wh = LutWarehouse::Lut(LutDataType {
@@ -1806,18 +2163,72 @@ impl ColorProfile {
     }
 
     pub fn new_from_slice(slice: &[u8]) -> Result<Self, CmsError> {
-        let header = ProfileHeader::new_from_slice(slice)?;
-        let tags_count = header.tag_count as usize;
-        if slice.len() >= MAX_PROFILE_SIZE {
-            return Err(CmsError::InvalidProfile);
+        Self::new_from_slice_with_warnings(slice).map(|(profile, _)| profile)
+    }
+
+    /// Parses profile, also reporting recoverable defects found in it.
+    ///
+    /// When tag signature appears more than once, first occurrence is used,
+    /// as lcms2 does, and [ParseWarning::DuplicateTag] is reported for the rest.
+    /// Profiles with more than 1024 tags are rejected.
+    ///
+    /// Unknown tags, as private vendor tags, never fail parsing, their data is kept
+    /// in [ColorProfile::unknown_tags] when it lies within the profile.
+    pub fn new_from_slice_with_warnings(
+        slice: &[u8],
+    ) -> Result<(Self, Vec<ParseWarning>), CmsError> {
+        Self::new_from_slice_with_options(slice, ParsingOptions::default())
+    }
+
+    /// Parses profile with structure checked as [ParsingOptions::strictness] tells,
+    /// also reporting recoverable defects found in it.
+    pub fn new_from_slice_with_options(
+        mut slice: &[u8],
+        options: ParsingOptions,
+    ) -> Result<(Self, Vec<ParseWarning>), CmsError> {
+        Self::parse_source(&mut slice, options)
+    }
+
+    /// Parses profile from a stream without reading it into memory first.
+    ///
+    /// Profile starts at current position of `reader` and takes size declared in its header,
+    /// so profiles embedded in TIFF or PDF files can be read in place. Header sizes too small
+    /// for the tag table or tag data are extended to cover them, as a stream holding only
+    /// the profile accepts the same data as [ColorProfile::new_from_slice]. Only the header,
+    /// the tag table and tags the parser needs are read, limits are the same as
+    /// [ColorProfile::new_from_slice] has, except that data after the profile doesn't count
+    /// into the maximum profile size. I/O errors are reported as [CmsError::InvalidProfile].
+    pub fn new_from_reader<R: Read + Seek>(reader: &mut R) -> Result<Self, CmsError> {
+        Self::new_from_reader_with_options(reader, ParsingOptions::default())
+            .map(|(profile, _)| profile)
+    }
+
+    /// Same as [ColorProfile::new_from_reader] with structure checked
+    /// as [ParsingOptions::strictness] tells, also reporting recoverable defects found in it.
+    pub fn new_from_reader_with_options<R: Read + Seek>(
+        reader: &mut R,
+        options: ParsingOptions,
+    ) -> Result<(Self, Vec<ParseWarning>), CmsError> {
+        Self::parse_source(&mut ReaderSource::new(reader)?, options)
+    }
+
+    fn parse_source(
+        source: &mut impl ProfileSource,
+        options: ParsingOptions,
+    ) -> Result<(Self, Vec<ParseWarning>), CmsError> {
+        let strictness = options.strictness;
+        let header =
+            ProfileHeader::new_from_slice(&source.read_at(0, size_of::<ProfileHeader>())?)?;
+        if header.tag_count > MAX_TAGS_COUNT {
+            return Err(CmsError::TooManyTags(header.tag_count));
         }
-        let tags_end = tags_count
-            .safe_mul(TAG_SIZE)?
-            .safe_add(size_of::<ProfileHeader>())?;
-        if slice.len() < tags_end {
+        let tags_count = header.tag_count as usize;
+        if source.size() >= MAX_PROFILE_SIZE {
             return Err(CmsError::InvalidProfile);
         }
-        let tags_slice = &slice[size_of::<ProfileHeader>()..tags_end];
+        let tags_slice = source
+            .read_at(size_of::<ProfileHeader>(), tags_count.safe_mul(TAG_SIZE)?)?
+            .into_owned();
         let mut profile = ColorProfile {
             rendering_intent: header.rendering_intent,
             pcs: header.pcs,
@@ -1825,156 +2236,291 @@ impl ColorProfile {
             color_space: header.data_color_space,
             white_point: header.illuminant,
             version_internal: header.version,
+            profile_id_internal: header.profile_id,
             ..Default::default()
         };
-        let color_space = profile.color_space;
+        let mut warnings = Vec::new();
+        let entries = Self::read_tag_table(source, &tags_slice, strictness, &mut warnings)?;
+        if strictness == Strictness::Strict && header.reserved.iter().any(|&x| x != 0) {
+            return Err(CmsError::MalformedProfile(
+                ParseWarning::NonZeroHeaderReserved,
+            ));
+        }
+        for (tag_value, tag_entry, tag_size) in entries {
+            let Ok(tag) = Tag::try_from(tag_value) else {
+                profile.parse_statistics.unknown_tags += 1;
+                profile.parse_statistics.bytes_unparsed += tag_size;
+                match source.read_at(tag_entry as usize, tag_size) {
+                    Ok(data) => profile.unknown_tags.push((tag_value, data.into_owned())),
+                    Err(_) => warnings.push(ParseWarning::TruncatedTag(tag_value)),
+                }
+                continue;
+            };
+            profile.parse_statistics.known_tags += 1;
+            // Readers get data of the tag only, at zero offset
+            source
+                .read_at(tag_entry as usize, tag_size)
+                .and_then(|data| {
+                    profile.read_known_tag(tag, tag_value, &data, 0, tag_size, &mut warnings)
+                })
+                .map_err(|err| err.at_tag(tag_value, tag_entry as usize))?;
+        }
+
+        if strictness == Strictness::Strict {
+            if let Some(&warning) = warnings.first() {
+                return Err(CmsError::MalformedProfile(warning));
+            }
+        }
+
+        Ok((profile, warnings))
+    }
+
+    /// Reads tag the parser knows into the profile
+    fn read_known_tag(
+        &mut self,
+        tag: Tag,
+        tag_value: u32,
+        slice: &[u8],
+        tag_entry: u32,
+        tag_size: usize,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<(), CmsError> {
+        let color_space = self.color_space;
+        match tag {
+            Tag::RedXyz => {
+                if color_space == DataColorSpace::Rgb {
+                    self.red_colorant = Self::read_xyz_tag(slice, tag_entry as usize, tag_size)?;
+                }
+            }
+            Tag::GreenXyz => {
+                if color_space == DataColorSpace::Rgb {
+                    self.green_colorant = Self::read_xyz_tag(slice, tag_entry as usize, tag_size)?;
+                }
+            }
+            Tag::BlueXyz => {
+                if color_space == DataColorSpace::Rgb {
+                    self.blue_colorant = Self::read_xyz_tag(slice, tag_entry as usize, tag_size)?;
+                }
+            }
+            Tag::RedToneReproduction => {
+                if color_space == DataColorSpace::Rgb {
+                    self.red_trc = Self::read_trc_tag_s(slice, tag_entry as usize, tag_size)?;
+                }
+            }
+            Tag::GreenToneReproduction => {
+                if color_space == DataColorSpace::Rgb {
+                    self.green_trc = Self::read_trc_tag_s(slice, tag_entry as usize, tag_size)?;
+                }
+            }
+            Tag::BlueToneReproduction => {
+                if color_space == DataColorSpace::Rgb {
+                    self.blue_trc = Self::read_trc_tag_s(slice, tag_entry as usize, tag_size)?;
+                }
+            }
+            Tag::GreyToneReproduction => {
+                if color_space == DataColorSpace::Gray || color_space == DataColorSpace::Rgb {
+                    self.gray_trc = Self::read_trc_tag_s(slice, tag_entry as usize, tag_size)?;
+                }
+            }
+            Tag::MediaWhitePoint => match Self::read_xyz_tag(slice, tag_entry as usize, tag_size) {
+                Ok(wt) => self.media_white_point = Some(wt),
+                Err(err) => return Err(err),
+            },
+            Tag::Luminance => match Self::read_xyz_tag(slice, tag_entry as usize, tag_size) {
+                Ok(wt) => self.luminance = Some(wt),
+                Err(err) => return Err(err),
+            },
+            Tag::Measurement => {
+                self.measurement = Self::read_meas_tag(slice, tag_entry as usize, tag_size)?;
+            }
+            Tag::CodeIndependentPoints => {
+                self.cicp = Self::read_cicp_tag(slice, tag_entry as usize, tag_size)?;
+            }
+            Tag::ChromaticAdaptation => {
+                self.chromatic_adaptation =
+                    Self::read_chad_tag(slice, tag_entry as usize, tag_size)?;
+            }
+            Tag::BlackPoint => match Self::read_xyz_tag(slice, tag_entry as usize, tag_size) {
+                Ok(wt) => self.black_point = Some(wt),
+                Err(err) => return Err(err),
+            },
+            Tag::DeviceToPcsLutPerceptual => {
+                self.lut_a_to_b_perceptual = Self::read_lut_tag(slice, tag_entry, tag_size)?;
+            }
+            Tag::DeviceToPcsLutColorimetric => {
+                self.lut_a_to_b_colorimetric = Self::read_lut_tag(slice, tag_entry, tag_size)?;
+            }
+            Tag::DeviceToPcsLutSaturation => {
+                self.lut_a_to_b_saturation = Self::read_lut_tag(slice, tag_entry, tag_size)?;
+            }
+            Tag::PcsToDeviceLutPerceptual => {
+                self.lut_b_to_a_perceptual = Self::read_lut_tag(slice, tag_entry, tag_size)?;
+            }
+            Tag::PcsToDeviceLutColorimetric => {
+                self.lut_b_to_a_colorimetric = Self::read_lut_tag(slice, tag_entry, tag_size)?;
+            }
+            Tag::PcsToDeviceLutSaturation => {
+                self.lut_b_to_a_saturation = Self::read_lut_tag(slice, tag_entry, tag_size)?;
+            }
+            Tag::Gamut => {
+                self.gamut = Self::read_lut_tag(slice, tag_entry, tag_size)?;
+            }
+            Tag::Copyright => {
+                self.copyright = Self::read_string_tag(
+                    slice,
+                    tag_entry as usize,
+                    tag_size,
+                    tag_value,
+                    warnings,
+                )?;
+            }
+            Tag::ProfileDescription => {
+                self.description = Self::read_string_tag(
+                    slice,
+                    tag_entry as usize,
+                    tag_size,
+                    tag_value,
+                    warnings,
+                )?;
+            }
+            Tag::ViewingConditionsDescription => {
+                self.viewing_conditions_description = Self::read_string_tag(
+                    slice,
+                    tag_entry as usize,
+                    tag_size,
+                    tag_value,
+                    warnings,
+                )?;
+            }
+            Tag::DeviceModel => {
+                self.device_model = Self::read_string_tag(
+                    slice,
+                    tag_entry as usize,
+                    tag_size,
+                    tag_value,
+                    warnings,
+                )?;
+            }
+            Tag::DeviceManufacturer => {
+                self.device_manufacturer = Self::read_string_tag(
+                    slice,
+                    tag_entry as usize,
+                    tag_size,
+                    tag_value,
+                    warnings,
+                )?;
+            }
+            Tag::CharTarget => {
+                self.char_target = Self::read_string_tag(
+                    slice,
+                    tag_entry as usize,
+                    tag_size,
+                    tag_value,
+                    warnings,
+                )?;
+            }
+            Tag::Chromaticity => {}
+            Tag::ObserverConditions => {
+                self.viewing_conditions =
+                    Self::read_viewing_conditions(slice, tag_entry as usize, tag_size)?;
+            }
+            Tag::Technology => {
+                self.technology = Self::read_tech_tag(slice, tag_entry as usize, tag_size)?;
+            }
+            Tag::CalibrationDateTime => {
+                self.calibration_date =
+                    Self::read_date_time_tag(slice, tag_entry as usize, tag_size)?;
+            }
+            Tag::VideoCardGamma => {
+                // Private Apple tag, profile stays usable without it
+                self.vcgt = slice
+                    .get(tag_entry as usize..(tag_entry as usize).saturating_add(tag_size))
+                    .and_then(|data| Vcgt::new_from_slice(data).ok());
+                if self.vcgt.is_none() {
+                    warnings.push(ParseWarning::MalformedTag(tag_value));
+                }
+            }
+            Tag::ColorantTable => {
+                match slice
+                    .get(tag_entry as usize..(tag_entry as usize).saturating_add(tag_size))
+                    .map(|data| parse_colorant_table(data, self.pcs))
+                {
+                    Some(Ok(colorants)) => self.colorant_table = colorants,
+                    _ => warnings.push(ParseWarning::MalformedTag(tag_value)),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads signature, offset and size of tags to parse from the tag table
+    fn read_tag_table(
+        source: &mut impl ProfileSource,
+        tags_slice: &[u8],
+        strictness: Strictness,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<Vec<(u32, u32, usize)>, CmsError> {
+        let mut entries: Vec<(u32, u32, usize)> = Vec::with_capacity(tags_slice.len() / TAG_SIZE);
         for tag in tags_slice.chunks_exact(TAG_SIZE) {
             let tag_value = u32::from_be_bytes([tag[0], tag[1], tag[2], tag[3]]);
             let tag_entry = u32::from_be_bytes([tag[4], tag[5], tag[6], tag[7]]);
-            let tag_size = u32::from_be_bytes([tag[8], tag[9], tag[10], tag[11]]) as usize;
-            // Just ignore unknown tags
-            if let Ok(tag) = Tag::try_from(tag_value) {
-                match tag {
-                    Tag::RedXyz => {
-                        if color_space == DataColorSpace::Rgb {
-                            profile.red_colorant =
-                                Self::read_xyz_tag(slice, tag_entry as usize, tag_size)?;
-                        }
-                    }
-                    Tag::GreenXyz => {
-                        if color_space == DataColorSpace::Rgb {
-                            profile.green_colorant =
-                                Self::read_xyz_tag(slice, tag_entry as usize, tag_size)?;
-                        }
-                    }
-                    Tag::BlueXyz => {
-                        if color_space == DataColorSpace::Rgb {
-                            profile.blue_colorant =
-                                Self::read_xyz_tag(slice, tag_entry as usize, tag_size)?;
-                        }
-                    }
-                    Tag::RedToneReproduction => {
-                        if color_space == DataColorSpace::Rgb {
-                            profile.red_trc =
-                                Self::read_trc_tag_s(slice, tag_entry as usize, tag_size)?;
-                        }
-                    }
-                    Tag::GreenToneReproduction => {
-                        if color_space == DataColorSpace::Rgb {
-                            profile.green_trc =
-                                Self::read_trc_tag_s(slice, tag_entry as usize, tag_size)?;
-                        }
-                    }
-                    Tag::BlueToneReproduction => {
-                        if color_space == DataColorSpace::Rgb {
-                            profile.blue_trc =
-                                Self::read_trc_tag_s(slice, tag_entry as usize, tag_size)?;
-                        }
-                    }
-                    Tag::GreyToneReproduction => {
-                        if color_space == DataColorSpace::Rgb {
-                            profile.gray_trc =
-                                Self::read_trc_tag_s(slice, tag_entry as usize, tag_size)?;
-                        }
-                    }
-                    Tag::MediaWhitePoint => {
-                        match Self::read_xyz_tag(slice, tag_entry as usize, tag_size) {
-                            Ok(wt) => profile.media_white_point = Some(wt),
-                            Err(err) => return Err(err),
-                        }
-                    }
-                    Tag::Luminance => {
-                        match Self::read_xyz_tag(slice, tag_entry as usize, tag_size) {
-                            Ok(wt) => profile.luminance = Some(wt),
-                            Err(err) => return Err(err),
-                        }
-                    }
-                    Tag::Measurement => {
-                        profile.measurement =
-                            Self::read_meas_tag(slice, tag_entry as usize, tag_size)?;
-                    }
-                    Tag::CodeIndependentPoints => {
-                        profile.cicp = Self::read_cicp_tag(slice, tag_entry as usize, tag_size)?;
-                    }
-                    Tag::ChromaticAdaptation => {
-                        profile.chromatic_adaptation =
-                            Self::read_chad_tag(slice, tag_entry as usize, tag_size)?;
-                    }
-                    Tag::BlackPoint => {
-                        match Self::read_xyz_tag(slice, tag_entry as usize, tag_size) {
-                            Ok(wt) => profile.black_point = Some(wt),
-                            Err(err) => return Err(err),
-                        }
-                    }
-                    Tag::DeviceToPcsLutPerceptual => {
-                        profile.lut_a_to_b_perceptual =
-                            Self::read_lut_tag(slice, tag_entry, tag_size)?;
-                    }
-                    Tag::DeviceToPcsLutColorimetric => {
-                        profile.lut_a_to_b_colorimetric =
-                            Self::read_lut_tag(slice, tag_entry, tag_size)?;
-                    }
-                    Tag::DeviceToPcsLutSaturation => {
-                        profile.lut_a_to_b_saturation =
-                            Self::read_lut_tag(slice, tag_entry, tag_size)?;
-                    }
-                    Tag::PcsToDeviceLutPerceptual => {
-                        profile.lut_b_to_a_perceptual =
-                            Self::read_lut_tag(slice, tag_entry, tag_size)?;
-                    }
-                    Tag::PcsToDeviceLutColorimetric => {
-                        profile.lut_b_to_a_colorimetric =
-                            Self::read_lut_tag(slice, tag_entry, tag_size)?;
-                    }
-                    Tag::PcsToDeviceLutSaturation => {
-                        profile.lut_b_to_a_saturation =
-                            Self::read_lut_tag(slice, tag_entry, tag_size)?;
-                    }
-                    Tag::Gamut => {
-                        profile.gamut = Self::read_lut_tag(slice, tag_entry, tag_size)?;
-                    }
-                    Tag::Copyright => {
-                        profile.copyright =
-                            Self::read_string_tag(slice, tag_entry as usize, tag_size)?;
+            let mut tag_size = u32::from_be_bytes([tag[8], tag[9], tag[10], tag[11]]) as usize;
+            if (tag_entry as usize).saturating_add(tag_size) > source.size() {
+                match strictness {
+                    Strictness::Strict => {
+                        return Err(CmsError::MalformedProfile(ParseWarning::TruncatedTag(
+                            tag_value,
+                        )));
                     }
-                    Tag::ProfileDescription => {
-                        profile.description =
-                            Self::read_string_tag(slice, tag_entry as usize, tag_size)?;
+                    Strictness::Lenient if (tag_entry as usize) < source.size() => {
+                        tag_size = source.size() - tag_entry as usize;
+                        warnings.push(ParseWarning::TagSizeClamped(tag_value));
                     }
-                    Tag::ViewingConditionsDescription => {
-                        profile.viewing_conditions_description =
-                            Self::read_string_tag(slice, tag_entry as usize, tag_size)?;
-                    }
-                    Tag::DeviceModel => {
-                        profile.device_model =
-                            Self::read_string_tag(slice, tag_entry as usize, tag_size)?;
-                    }
-                    Tag::DeviceManufacturer => {
-                        profile.device_manufacturer =
-                            Self::read_string_tag(slice, tag_entry as usize, tag_size)?;
-                    }
-                    Tag::CharTarget => {
-                        profile.char_target =
-                            Self::read_string_tag(slice, tag_entry as usize, tag_size)?;
-                    }
-                    Tag::Chromaticity => {}
-                    Tag::ObserverConditions => {
-                        profile.viewing_conditions =
-                            Self::read_viewing_conditions(slice, tag_entry as usize, tag_size)?;
+                    _ => {}
+                }
+            }
+            if let Some(position) = entries.iter().position(|x| x.0 == tag_value) {
+                match strictness {
+                    Strictness::Strict => {
+                        return Err(CmsError::MalformedProfile(ParseWarning::DuplicateTag(
+                            tag_value,
+                        )));
                     }
-                    Tag::Technology => {
-                        profile.technology =
-                            Self::read_tech_tag(slice, tag_entry as usize, tag_size)?;
+                    Strictness::Default => {
+                        warnings.push(ParseWarning::DuplicateTag(tag_value));
+                        continue;
                     }
-                    Tag::CalibrationDateTime => {
-                        profile.calibration_date =
-                            Self::read_date_time_tag(slice, tag_entry as usize, tag_size)?;
+                    Strictness::Lenient => {
+                        warnings.push(ParseWarning::DuplicateTag(tag_value));
+                        entries.remove(position);
                     }
                 }
             }
+            entries.push((tag_value, tag_entry, tag_size));
+        }
+
+        if strictness == Strictness::Strict {
+            for (i, &(signature, offset, size)) in entries.iter().enumerate() {
+                let start = offset as usize;
+                // Tags may share the same data, but not a part of it
+                if let Some(other) = entries[..i].iter().find(|other| {
+                    let other_start = other.1 as usize;
+                    let shared = other.1 == offset && other.2 == size;
+                    !shared && start < other_start + other.2 && other_start < start + size
+                }) {
+                    return Err(CmsError::MalformedProfile(ParseWarning::OverlappingTags(
+                        other.0, signature,
+                    )));
+                }
+                if size >= 8 && source.read_at(start + 4, 4)?.iter().any(|&x| x != 0) {
+                    return Err(CmsError::MalformedProfile(ParseWarning::NonZeroReserved(
+                        signature,
+                    )));
+                }
+            }
         }
 
-        Ok(profile)
+        Ok(entries)
     }
 }
 
@@ -2156,6 +2702,118 @@ impl ColorProfile {
         Some(dest_inverse.mat_mul(source))
     }
 
+    /// Returns matrix adapting media white point of the profile into the D50 PCS illuminant.
+    ///
+    /// The `chad` tag is used when present, otherwise Bradford adaptation is computed
+    /// from the media white point when it differs from D50.
+    pub fn chromatic_adaptation_matrix(&self) -> Matrix3f {
+        self.chromatic_adaptation_to_d50()
+            .unwrap_or(Matrix3f::IDENTITY)
+    }
+
+    /// Same as [ColorProfile::chromatic_adaptation_matrix], `None` when no adaptation is needed.
+    pub(crate) fn chromatic_adaptation_to_d50(&self) -> Option<Matrix3f> {
+        if let Some(chad) = self.chromatic_adaptation {
+            return Some(chad);
+        }
+        let d50 = Chromaticity::D50.to_xyz();
+        match self.media_white_point {
+            Some(wp)
+                if wp.y > 0.
+                    && ((wp.x - d50.x).abs() > 1e-3
+                        || (wp.y - d50.y).abs() > 1e-3
+                        || (wp.z - d50.z).abs() > 1e-3) =>
+            {
+                Some(adaption_matrix(wp, d50))
+            }
+            _ => None,
+        }
+    }
+
+    /// Computes transform matrix RGB -> XYZ -> RGB for the requested rendering intent.
+    ///
+    /// Absolute colorimetric intent scales PCS XYZ by the ratio of media white points
+    /// per component, as lcms2 does.
+    /// Other intents are the same as [ColorProfile::transform_matrix].
+    pub fn transform_matrix_for_intent(
+        &self,
+        dest: &ColorProfile,
+        intent: RenderingIntent,
+    ) -> Option<Matrix3f> {
+        if intent != RenderingIntent::AbsoluteColorimetric {
+            return self.transform_matrix(dest);
+        }
+        let source = self.rgb_to_xyz_matrix()?;
+        let dst = dest.rgb_to_xyz_matrix()?;
+        let scale = self.absolute_intent_scale(dest);
+        let white_scale = Matrix3f {
+            v: [
+                [scale.v[0], 0., 0.],
+                [0., scale.v[1], 0.],
+                [0., 0., scale.v[2]],
+            ],
+        };
+        Some(dst.inverse().mat_mul(white_scale.mat_mul(source)))
+    }
+
+    /// Computes transform matrix RGB -> XYZ -> RGB adapting media white of `self`
+    /// into media white of `dest` with the given chromatic adaptation transform.
+    ///
+    /// Colorants are brought back to media white of each profile by inverse of its
+    /// chromatic adaptation first. [ChromaticAdaptation::Bradford] is the one ICC uses
+    /// for `chad`, so it goes through D50 PCS directly, as
+    /// [ColorProfile::transform_matrix_for_intent] does.
+    /// Absolute colorimetric intent doesn't adapt white and ignores `adaptation`.
+    pub fn transform_matrix_with_adaptation(
+        &self,
+        dest: &ColorProfile,
+        intent: RenderingIntent,
+        adaptation: ChromaticAdaptation,
+    ) -> Option<Matrix3f> {
+        if intent == RenderingIntent::AbsoluteColorimetric
+            || adaptation == ChromaticAdaptation::Bradford
+        {
+            return self.transform_matrix_for_intent(dest, intent);
+        }
+        let source = self
+            .chromatic_adaptation_matrix()
+            .inverse()
+            .mat_mul(self.rgb_to_xyz_matrix()?);
+        let dst = dest
+            .chromatic_adaptation_matrix()
+            .inverse()
+            .mat_mul(dest.rgb_to_xyz_matrix()?);
+        let cat =
+            adaptation.adaptation_matrix(self.adapted_media_white(), dest.adapted_media_white());
+        Some(dst.inverse().mat_mul(cat.mat_mul(source)))
+    }
+
+    /// Media white point recovered from D50 PCS with inverse chromatic adaptation.
+    pub(crate) fn adapted_media_white(&self) -> Xyz {
+        let white = self
+            .chromatic_adaptation_matrix()
+            .inverse()
+            .mul_vector(Chromaticity::D50.to_xyz().to_vector());
+        Xyz::new(white.v[0], white.v[1], white.v[2])
+    }
+
+    /// Per component scale of PCS XYZ from `self` into `dest` for absolute colorimetric intent.
+    pub(crate) fn absolute_intent_scale(&self, dest: &ColorProfile) -> Vector3f {
+        let src = self.adapted_media_white();
+        let dst = dest.adapted_media_white();
+        let ratio = |s: f32, d: f32| {
+            let r = s / d;
+            if r.is_finite() && r > 0. { r } else { 1. }
+        };
+        Vector3f {
+            v: [
+                ratio(src.x, dst.x),
+                ratio(src.y, dst.y),
+                ratio(src.z, dst.z),
+            ],
+        }
+    }
+
     /// Returns volume of colors stored in profile
     pub fn profile_volume(&self) -> Option<f32> {
         let red_prim = self.red_colorant;
@@ -2172,6 +2830,56 @@ impl ColorProfile {
         Some(det / 6.0f32)
     }
 
+    /// Returns media white point, undoing chromatic adaptation into D50 PCS.
+    ///
+    /// This is the white absolute colorimetric intent of [ColorProfile::transform_matrix_for_intent]
+    /// is built from, unlike `white_point` field which holds PCS illuminant from the header.
+    pub fn white_point(&self) -> Option<Chromaticity> {
+        self.device_chromaticity(Chromaticity::D50.to_xyz())
+    }
+
+    /// Returns red primary of matrix-shaper RGB profile, `None` for LUT based profiles.
+    pub fn red_primary(&self) -> Option<Chromaticity> {
+        self.primary::<0>()
+    }
+
+    /// Returns green primary of matrix-shaper RGB profile, `None` for LUT based profiles.
+    pub fn green_primary(&self) -> Option<Chromaticity> {
+        self.primary::<1>()
+    }
+
+    /// Returns blue primary of matrix-shaper RGB profile, `None` for LUT based profiles.
+    pub fn blue_primary(&self) -> Option<Chromaticity> {
+        self.primary::<2>()
+    }
+
+    fn primary<const N: usize>(&self) -> Option<Chromaticity> {
+        if self.color_space != DataColorSpace::Rgb
+            || self.has_device_to_pcs_lut()
+            || self.has_pcs_to_device_lut()
+        {
+            return None;
+        }
+        let colorants = self.colorant_matrix();
+        self.device_chromaticity(Xyz::new(
+            colorants.v[0][N],
+            colorants.v[1][N],
+            colorants.v[2][N],
+        ))
+    }
+
+    /// Converts PCS relative XYZ back into device white point and returns its chromaticity.
+    fn device_chromaticity(&self, pcs: Xyz) -> Option<Chromaticity> {
+        let device = self
+            .chromatic_adaptation_matrix()
+            .inverse()
+            .mul_vector(pcs.to_vector());
+        let chromaticity = Chromaticity::try_from(Xyz::new(device.v[0], device.v[1], device.v[2]));
+        chromaticity
+            .ok()
+            .filter(|x| x.x.is_finite() && x.y.is_finite())
+    }
+
     pub(crate) fn has_device_to_pcs_lut(&self) -> bool {
         self.lut_a_to_b_perceptual.is_some()
             || self.lut_a_to_b_saturation.is_some()
@@ -2184,3 +2892,851 @@ impl ColorProfile {
             || self.lut_b_to_a_colorimetric.is_some()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::writer::FloatToFixedS15Fixed16;
+
+    fn xyz_tag(xyz: Xyz) -> Vec<u8> {
+        let mut data = b"XYZ \0\0\0\0".to_vec();
+        for v in [xyz.x, xyz.y, xyz.z] {
+            data.extend_from_slice(&v.to_s15_fixed16().to_be_bytes());
+        }
+        data
+    }
+
+    // Lays out tag table and data after header of encoded sRGB profile
+    fn assemble_profile(tags: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let header = ColorProfile::new_srgb().encode().unwrap()[..128].to_vec();
+        let mut table = Vec::new();
+        let mut data = Vec::new();
+        let mut offset = 132 + tags.len() * TAG_SIZE;
+        for (signature, entry) in tags.iter() {
+            table.extend_from_slice(&signature.to_be_bytes());
+            table.extend_from_slice(&(offset as u32).to_be_bytes());
+            table.extend_from_slice(&(entry.len() as u32).to_be_bytes());
+            data.extend_from_slice(entry);
+            offset += entry.len();
+        }
+        let mut profile = header;
+        profile.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+        profile.extend(table);
+        profile.extend(data);
+        let size = profile.len() as u32;
+        profile[..4].copy_from_slice(&size.to_be_bytes());
+        profile
+    }
+
+    #[test]
+    fn test_duplicate_tag_first_wins() {
+        let wtpt: u32 = Tag::MediaWhitePoint.into();
+        let first = Xyz::new(0.9642, 1.0, 0.8249);
+        let second = Xyz::new(0.9505, 1.0, 1.089);
+        let bytes = assemble_profile(&[(wtpt, xyz_tag(first)), (wtpt, xyz_tag(second))]);
+        let (profile, warnings) = ColorProfile::new_from_slice_with_warnings(&bytes).unwrap();
+        let white = profile.media_white_point.unwrap();
+        assert!((white.x - first.x).abs() < 1e-4 && (white.z - first.z).abs() < 1e-4);
+        assert_eq!(warnings, vec![ParseWarning::DuplicateTag(wtpt)]);
+        // Plain parsing picks the same tag
+        let plain = ColorProfile::new_from_slice(&bytes).unwrap();
+        assert!((plain.media_white_point.unwrap().z - first.z).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_parsing_strictness() {
+        let parse = |bytes: &[u8], strictness| {
+            ColorProfile::new_from_slice_with_options(bytes, ParsingOptions { strictness })
+        };
+        let encoded = ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc"))
+            .unwrap()
+            .encode()
+            .unwrap();
+        let (_, warnings) = parse(&encoded, Strictness::Strict).unwrap();
+        assert!(warnings.is_empty());
+
+        // Duplicate tags
+        let wtpt: u32 = Tag::MediaWhitePoint.into();
+        let first = Xyz::new(0.9642, 1.0, 0.8249);
+        let last = Xyz::new(0.9505, 1.0, 1.089);
+        let bytes = assemble_profile(&[(wtpt, xyz_tag(first)), (wtpt, xyz_tag(last))]);
+        let (profile, warnings) = parse(&bytes, Strictness::Lenient).unwrap();
+        assert!((profile.media_white_point.unwrap().z - last.z).abs() < 1e-4);
+        assert_eq!(warnings, [ParseWarning::DuplicateTag(wtpt)]);
+        let (profile, _) = parse(&bytes, Strictness::Default).unwrap();
+        assert!((profile.media_white_point.unwrap().z - first.z).abs() < 1e-4);
+        assert_eq!(
+            parse(&bytes, Strictness::Strict).unwrap_err(),
+            CmsError::MalformedProfile(ParseWarning::DuplicateTag(wtpt))
+        );
+
+        // Size of the last tag rounded up past the end
+        let mut bytes = apple_like_display(&[]);
+        let btrc: u32 = Tag::BlueToneReproduction.into();
+        let size_at = 132 + 6 * TAG_SIZE + 8;
+        let size = u32::from_be_bytes(bytes[size_at..size_at + 4].try_into().unwrap());
+        bytes[size_at..size_at + 4].copy_from_slice(&(size + 2).to_be_bytes());
+        assert!(parse(&bytes, Strictness::Default).is_err());
+        let (profile, warnings) = parse(&bytes, Strictness::Lenient).unwrap();
+        assert_eq!(warnings, [ParseWarning::TagSizeClamped(btrc)]);
+        assert!(profile.blue_trc.is_some());
+        assert_eq!(
+            parse(&bytes, Strictness::Strict).unwrap_err(),
+            CmsError::MalformedProfile(ParseWarning::TruncatedTag(btrc))
+        );
+
+        // Non-zero reserved bytes
+        let rxyz: u32 = Tag::RedXyz.into();
+        let mut tag = xyz_tag(first);
+        tag[5] = 1;
+        let bytes = assemble_profile(&[(rxyz, tag)]);
+        assert!(parse(&bytes, Strictness::Default).is_ok());
+        assert_eq!(
+            parse(&bytes, Strictness::Strict).unwrap_err(),
+            CmsError::MalformedProfile(ParseWarning::NonZeroReserved(rxyz))
+        );
+        let mut bytes = assemble_profile(&[(rxyz, xyz_tag(first))]);
+        bytes[110] = 1;
+        assert!(parse(&bytes, Strictness::Default).is_ok());
+        assert_eq!(
+            parse(&bytes, Strictness::Strict).unwrap_err(),
+            CmsError::MalformedProfile(ParseWarning::NonZeroHeaderReserved)
+        );
+
+        // Green colorant starts in the middle of the red one, shared data is fine
+        let gxyz: u32 = Tag::GreenXyz.into();
+        let mut bytes = assemble_profile(&[(rxyz, xyz_tag(first)), (gxyz, xyz_tag(first))]);
+        let offset = u32::from_be_bytes(bytes[136..140].try_into().unwrap());
+        bytes[148..152].copy_from_slice(&offset.to_be_bytes());
+        assert!(parse(&bytes, Strictness::Strict).is_ok());
+        bytes[148..152].copy_from_slice(&(offset + 4).to_be_bytes());
+        assert!(parse(&bytes, Strictness::Default).is_ok());
+        assert_eq!(
+            parse(&bytes, Strictness::Strict).unwrap_err(),
+            CmsError::MalformedProfile(ParseWarning::OverlappingTags(rxyz, gxyz))
+        );
+    }
+
+    #[test]
+    fn test_malformed_tag_error() {
+        let swop = include_bytes!("../assets/us_swop_coated.icc");
+        assert_eq!(
+            ColorProfile::new_from_slice(&swop[..100]).err(),
+            Some(CmsError::TruncatedData {
+                expected: 132,
+                got: 100
+            })
+        );
+
+        // A2B0 is lut16 at 316, byte 10 holds CLUT grid points
+        let a2b0 = u32::from(Tag::DeviceToPcsLutPerceptual);
+        let mut data = swop.to_vec();
+        data[316 + 10] = 0;
+        let err = ColorProfile::new_from_slice(&data).unwrap_err();
+        assert_eq!(
+            err,
+            CmsError::MalformedTag {
+                signature: a2b0,
+                offset: 316,
+                reason: TagDefect::ZeroGridSize
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "malformed 'A2B0' at offset 0x13c: CLUT grid size 0"
+        );
+
+        let mut profile = ColorProfile::new_srgb();
+        profile.media_white_point = Some(Xyz::new(0.9642, 1., 0.8249));
+        let mut data = profile.encode().unwrap();
+        let tag_count = u32::from_be_bytes(data[128..132].try_into().unwrap()) as usize;
+        let wtpt = u32::from(Tag::MediaWhitePoint);
+        let entry = (0..tag_count)
+            .map(|i| 132 + i * TAG_SIZE)
+            .find(|&x| u32::from_be_bytes(data[x..x + 4].try_into().unwrap()) == wtpt)
+            .unwrap();
+        let offset = u32::from_be_bytes(data[entry + 4..entry + 8].try_into().unwrap()) as usize;
+        data[entry + 8..entry + 12].copy_from_slice(&12u32.to_be_bytes());
+        assert_eq!(
+            ColorProfile::new_from_slice(&data).err(),
+            Some(CmsError::MalformedTag {
+                signature: wtpt,
+                offset,
+                reason: TagDefect::Truncated {
+                    expected: 20,
+                    got: 12
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_new_from_reader() {
+        use std::io::{Cursor, Seek, SeekFrom};
+
+        let swop = include_bytes!("../assets/us_swop_coated.icc");
+        let expected = ColorProfile::new_from_slice(swop).unwrap();
+
+        // Profile embedded between other data, as in TIFF or PDF
+        let mut container = vec![0xAAu8; 1000];
+        container.extend_from_slice(swop);
+        container.extend_from_slice(&[0x55; 1000]);
+        let mut reader = Cursor::new(container);
+        reader.seek(SeekFrom::Start(1000)).unwrap();
+        let profile = ColorProfile::new_from_reader(&mut reader).unwrap();
+        assert_eq!(format!("{profile:?}"), format!("{expected:?}"));
+
+        let srgb = ColorProfile::new_srgb().encode().unwrap();
+        let profile = ColorProfile::new_from_reader(&mut Cursor::new(&srgb)).unwrap();
+        assert_eq!(
+            format!("{profile:?}"),
+            format!("{:?}", ColorProfile::new_from_slice(&srgb).unwrap())
+        );
+
+        let truncated = &swop[..swop.len() / 2];
+        assert_eq!(
+            ColorProfile::new_from_reader(&mut Cursor::new(truncated)).err(),
+            ColorProfile::new_from_slice(truncated).err()
+        );
+
+        // Header size not covering tags, the stream is used as far as tags need
+        for declared in [132u32, 1000, swop.len() as u32 / 2] {
+            let mut under_declared = swop.to_vec();
+            under_declared[..4].copy_from_slice(&declared.to_be_bytes());
+            let expected = ColorProfile::new_from_slice(&under_declared).unwrap();
+            let profile = ColorProfile::new_from_reader(&mut Cursor::new(&under_declared)).unwrap();
+            assert_eq!(format!("{profile:?}"), format!("{expected:?}"));
+            // Data following the profile is still not taken in
+            under_declared.extend_from_slice(&[0x55; 1000]);
+            let profile = ColorProfile::new_from_reader(&mut Cursor::new(&under_declared)).unwrap();
+            assert_eq!(format!("{profile:?}"), format!("{expected:?}"));
+        }
+
+        assert_eq!(
+            ColorProfile::new_from_reader(&mut Cursor::new(&swop[..100])).err(),
+            Some(CmsError::TruncatedData {
+                expected: 132,
+                got: 100
+            })
+        );
+    }
+
+    /// `mluc` tag with one record per `(units, declared length)`
+    fn mluc_tag(records: &[(&[u16], usize)]) -> Vec<u8> {
+        let mut tag = b"mluc\0\0\0\0".to_vec();
+        tag.extend_from_slice(&(records.len() as u32).to_be_bytes());
+        tag.extend_from_slice(&12u32.to_be_bytes());
+        let mut offset = 16 + 12 * records.len();
+        for &(units, declared) in records {
+            tag.extend_from_slice(b"enUS");
+            tag.extend_from_slice(&(declared as u32).to_be_bytes());
+            tag.extend_from_slice(&(offset as u32).to_be_bytes());
+            offset += units.len() * 2;
+        }
+        for &(units, _) in records {
+            tag.extend(units.iter().flat_map(|x| x.to_be_bytes()));
+        }
+        tag
+    }
+
+    #[test]
+    fn test_malformed_text_tags() {
+        let desc = u32::from(Tag::ProfileDescription);
+        let read = |tag: &[u8]| {
+            let mut warnings = Vec::new();
+            let text = ColorProfile::read_string_tag(tag, 0, tag.len(), desc, &mut warnings)
+                .unwrap()
+                .and_then(|x| x.localized(None));
+            (text, warnings)
+        };
+        let utf16 = |text: &str| text.encode_utf16().collect::<Vec<u16>>();
+
+        // Legacy ASCII count covers garbage after the NUL
+        let mut tag = b"desc\0\0\0\0".to_vec();
+        tag.extend_from_slice(&20u32.to_be_bytes());
+        tag.extend_from_slice(b"Monitor (D65)\0GARBAG");
+        tag.extend_from_slice(&[0; 8 + 3 + 67]);
+        assert_eq!(
+            read(&tag),
+            (
+                Some("Monitor (D65)".to_string()),
+                vec![ParseWarning::TextPastTerminator(desc)]
+            )
+        );
+
+        // Legacy ASCII count runs past the tag, Unicode part is missing
+        let mut tag = b"desc\0\0\0\0".to_vec();
+        tag.extend_from_slice(&64u32.to_be_bytes());
+        tag.extend_from_slice(b"Monitor");
+        assert_eq!(
+            read(&tag),
+            (
+                Some("Monitor".to_string()),
+                vec![ParseWarning::TextLengthClamped(desc)]
+            )
+        );
+
+        let tag = b"text\0\0\0\0Copyright\0junk\0".to_vec();
+        assert_eq!(
+            read(&tag),
+            (
+                Some("Copyright".to_string()),
+                vec![ParseWarning::TextPastTerminator(desc)]
+            )
+        );
+
+        // NULs inside UTF-16 text are data, trailing ones are padding
+        let units = utf16("Display\0 (D65)\0\0");
+        assert_eq!(
+            read(&mluc_tag(&[(&units, units.len() * 2)])),
+            (Some("Display\0 (D65)".to_string()), vec![])
+        );
+
+        let mut units = utf16("AB");
+        units.insert(1, 0xD800);
+        assert_eq!(
+            read(&mluc_tag(&[(&units, units.len() * 2)])),
+            (
+                Some("A\u{FFFD}B".to_string()),
+                vec![ParseWarning::InvalidUtf16(desc)]
+            )
+        );
+
+        let units = utf16("Wide gamut");
+        let (text, warnings) = read(&mluc_tag(&[(&units, 400)]));
+        assert_eq!(text.as_deref(), Some("Wide gamut"));
+        assert_eq!(warnings, vec![ParseWarning::TextLengthClamped(desc)]);
+
+        // Whole profile keeps parsing and reports the defect
+        let mut profile = ColorProfile::new_srgb();
+        profile.set_description("Display");
+        let mut data = profile.encode().unwrap();
+        let needle = utf16("Display")
+            .iter()
+            .flat_map(|x| x.to_be_bytes())
+            .collect::<Vec<u8>>();
+        let position = data
+            .windows(needle.len())
+            .position(|x| x == needle)
+            .unwrap();
+        data[position..position + 2].copy_from_slice(&0xDC00u16.to_be_bytes());
+        let (decoded, warnings) = ColorProfile::new_from_slice_with_warnings(&data).unwrap();
+        assert_eq!(decoded.description(None).as_deref(), Some("\u{FFFD}isplay"));
+        assert_eq!(warnings, vec![ParseWarning::InvalidUtf16(desc)]);
+        let strict = ParsingOptions {
+            strictness: Strictness::Strict,
+        };
+        assert_eq!(
+            ColorProfile::new_from_slice_with_options(&data, strict).err(),
+            Some(CmsError::MalformedProfile(ParseWarning::InvalidUtf16(desc)))
+        );
+    }
+
+    #[test]
+    fn test_tags_count_limit() {
+        // Unknown private tags are allowed well beyond a hundred
+        let tags = (0..300u32)
+            .map(|i| (0x7a7a_0000 + i, xyz_tag(Xyz::default())))
+            .collect::<Vec<_>>();
+        let (_, warnings) =
+            ColorProfile::new_from_slice_with_warnings(&assemble_profile(&tags)).unwrap();
+        assert!(warnings.is_empty());
+
+        let tags = (0..MAX_TAGS_COUNT + 1)
+            .map(|i| (0x7a7a_0000 + i, Vec::new()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ColorProfile::new_from_slice(&assemble_profile(&tags)).unwrap_err(),
+            CmsError::TooManyTags(MAX_TAGS_COUNT + 1)
+        );
+    }
+
+    // Display profile laid out as Apple writes them, with private tags of odd sizes,
+    // so that data of every following tag is misaligned
+    fn apple_like_display(private: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let srgb = ColorProfile::new_srgb();
+        let curv = b"curv\0\0\0\0\0\0\0\x01\x02\x33".to_vec();
+        let mut tags = vec![(Tag::MediaWhitePoint.into(), xyz_tag(srgb.white_point))];
+        tags.extend_from_slice(private);
+        tags.extend([
+            (Tag::RedXyz.into(), xyz_tag(srgb.red_colorant)),
+            (Tag::GreenXyz.into(), xyz_tag(srgb.green_colorant)),
+            (Tag::BlueXyz.into(), xyz_tag(srgb.blue_colorant)),
+            (Tag::RedToneReproduction.into(), curv.clone()),
+            (Tag::GreenToneReproduction.into(), curv.clone()),
+            (Tag::BlueToneReproduction.into(), curv),
+        ]);
+        assemble_profile(&tags)
+    }
+
+    #[test]
+    fn test_measurement_tag() {
+        let mut srgb = ColorProfile::new_srgb();
+        assert!(srgb.measurement.is_none());
+        let measurement = Measurement {
+            observer: StandardObserver::D50,
+            backing: Xyz::new(0.0123, 0.0131, 0.0105),
+            geometry: MeasurementGeometry::D45to45,
+            flare: 0.01,
+            illuminant: StandardIlluminant::D50,
+        };
+        srgb.measurement = Some(measurement);
+        let encoded = srgb.encode().unwrap();
+        let decoded = ColorProfile::new_from_slice(&encoded).unwrap();
+        let parsed = decoded.measurement.unwrap();
+        assert_eq!(parsed.observer, measurement.observer);
+        assert_eq!(parsed.geometry, measurement.geometry);
+        assert_eq!(parsed.illuminant, measurement.illuminant);
+        assert!((parsed.flare - measurement.flare).abs() < 1e-4);
+        assert!((parsed.backing.y - measurement.backing.y).abs() < 1e-4);
+        assert_eq!(decoded.encode().unwrap()[128..], encoded[128..]);
+
+        assert!(parsed.is_compatible_with(&measurement));
+        let ten_degree = Measurement {
+            observer: StandardObserver::D65,
+            ..measurement
+        };
+        assert!(!parsed.is_compatible_with(&ten_degree));
+        let unknown = Measurement {
+            geometry: MeasurementGeometry::Unknown,
+            ..measurement
+        };
+        assert!(!unknown.is_compatible_with(&unknown));
+
+        // Tag shorter than its fields
+        let meas: u32 = Tag::Measurement.into();
+        let mut bytes = encoded;
+        let tag_count = u32::from_be_bytes(bytes[128..132].try_into().unwrap()) as usize;
+        let entry = bytes[132..132 + tag_count * TAG_SIZE]
+            .chunks_exact(TAG_SIZE)
+            .position(|x| u32::from_be_bytes(x[0..4].try_into().unwrap()) == meas)
+            .unwrap();
+        let size_at = 132 + entry * TAG_SIZE + 8;
+        bytes[size_at..size_at + 4].copy_from_slice(&30u32.to_be_bytes());
+        assert!(ColorProfile::new_from_slice(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_colorant_table_tag() {
+        let mut cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        assert!(cmyk.colorant_table.is_empty());
+        let names = ["Cyan", "Magenta", "Yellow", "Black"];
+        cmyk.colorant_table = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| NamedColorant {
+                name: name.to_string(),
+                pcs: Vector3f {
+                    v: [90. - i as f32 * 20., 10. * i as f32, -10. * i as f32],
+                },
+            })
+            .collect();
+        let (decoded, warnings) =
+            ColorProfile::new_from_slice_with_warnings(&cmyk.encode().unwrap()).unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(decoded.unknown_tags, cmyk.unknown_tags);
+        assert_eq!(
+            decoded
+                .colorant_table
+                .iter()
+                .map(|x| x.name.as_str())
+                .collect::<Vec<_>>(),
+            names
+        );
+        for (a, b) in decoded
+            .colorant_table
+            .iter()
+            .zip(cmyk.colorant_table.iter())
+        {
+            for (x, y) in a.pcs.v.iter().zip(b.pcs.v.iter()) {
+                assert!((x - y).abs() < 0.01, "{x} vs {y}");
+            }
+        }
+
+        // Truncated tag is dropped with a warning, the rest of the profile stays readable
+        let clrt: u32 = Tag::ColorantTable.into();
+        let mut bytes = cmyk.encode().unwrap();
+        let tag_count = u32::from_be_bytes(bytes[128..132].try_into().unwrap()) as usize;
+        let entry = bytes[132..132 + tag_count * TAG_SIZE]
+            .chunks_exact(TAG_SIZE)
+            .position(|x| u32::from_be_bytes(x[0..4].try_into().unwrap()) == clrt)
+            .unwrap();
+        let size_at = 132 + entry * TAG_SIZE + 8;
+        bytes[size_at..size_at + 4].copy_from_slice(&20u32.to_be_bytes());
+        let (decoded, warnings) = ColorProfile::new_from_slice_with_warnings(&bytes).unwrap();
+        assert_eq!(warnings, [ParseWarning::MalformedTag(clrt)]);
+        assert!(decoded.colorant_table.is_empty());
+        assert!(decoded.lut_a_to_b_perceptual.is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "transforms")]
+    fn test_private_display_tags() {
+        use crate::{Layout, TransformOptions};
+
+        let mmod = u32::from_be_bytes(*b"mmod");
+        let vcgt = u32::from_be_bytes(*b"vcgt");
+        let ndin = u32::from_be_bytes(*b"ndin");
+        // Manufacturer, model, serial and date after type and reserved, 40 bytes
+        let mut mmod_data = b"mmod\0\0\0\0\0\0\x06\x10\0\0\xa0\x50".to_vec();
+        mmod_data.resize(40, 0x11);
+        // Table with 3 channels of 3 one byte entries, 27 bytes
+        let mut vcgt_data = b"vcgt\0\0\0\0\0\0\0\0\0\x03\0\x03\0\x01".to_vec();
+        vcgt_data.extend_from_slice(&[0, 0x80, 0xff, 0, 0x7f, 0xff, 0, 0x81, 0xfe]);
+        let ndin_data = b"ndin\0\0\0".to_vec();
+        let bytes = apple_like_display(&[
+            (mmod, mmod_data.clone()),
+            (vcgt, vcgt_data.clone()),
+            (ndin, ndin_data.clone()),
+        ]);
+        let private = [(mmod, mmod_data), (ndin, ndin_data)];
+
+        let (profile, warnings) = ColorProfile::new_from_slice_with_warnings(&bytes).unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(profile.unknown_tags, private);
+        assert_eq!(
+            profile.vcgt,
+            Some(Vcgt::Table([
+                vec![0., 128. / 255., 1.],
+                vec![0., 127. / 255., 1.],
+                vec![0., 129. / 255., 254. / 255.],
+            ]))
+        );
+        assert_eq!(
+            profile.parse_statistics(),
+            ParseStatistics {
+                known_tags: 8,
+                unknown_tags: 2,
+                bytes_unparsed: 40 + 7,
+            }
+        );
+        let transform = profile
+            .create_transform_8bit(
+                Layout::Rgb,
+                &ColorProfile::new_srgb(),
+                Layout::Rgb,
+                TransformOptions::default(),
+            )
+            .unwrap();
+        let mut dst = [0u8; 3];
+        transform.transform(&[255, 255, 255], &mut dst).unwrap();
+        assert_eq!(dst, [255, 255, 255]);
+
+        // Private tags keep their bytes through encoding, vcgt is written with 16 bit entries
+        let decoded = ColorProfile::new_from_slice(&profile.encode().unwrap()).unwrap();
+        assert_eq!(decoded.unknown_tags, private);
+        assert_eq!(decoded.parse_statistics(), profile.parse_statistics());
+        let (Some(Vcgt::Table(decoded_ramps)), Some(Vcgt::Table(ramps))) =
+            (&decoded.vcgt, &profile.vcgt)
+        else {
+            panic!("vcgt must survive encoding");
+        };
+        for (a, b) in decoded_ramps.iter().flatten().zip(ramps.iter().flatten()) {
+            assert!((a - b).abs() < 1e-6);
+        }
+
+        // Broken vcgt doesn't fail the profile
+        let mut broken = bytes.clone();
+        let entry = 132 + 2 * TAG_SIZE;
+        assert_eq!(&broken[entry..entry + 4], b"vcgt");
+        broken[entry + 8..entry + 12].copy_from_slice(&12u32.to_be_bytes());
+        let (profile, warnings) = ColorProfile::new_from_slice_with_warnings(&broken).unwrap();
+        assert_eq!(warnings, vec![ParseWarning::MalformedTag(vcgt)]);
+        assert_eq!(profile.vcgt, None);
+
+        // Private tag pointing past the end is dropped, the profile still parses
+        let mut truncated = bytes.clone();
+        let entry = 132 + TAG_SIZE;
+        truncated[entry + 8..entry + 12].copy_from_slice(&4096u32.to_be_bytes());
+        let (profile, warnings) = ColorProfile::new_from_slice_with_warnings(&truncated).unwrap();
+        assert_eq!(warnings, vec![ParseWarning::TruncatedTag(mmod)]);
+        assert_eq!(profile.unknown_tags.len(), 1);
+        assert!(profile.red_trc.is_some());
+    }
+
+    #[test]
+    fn test_unknown_tags_survive_editing() {
+        use crate::EncodeOptions;
+
+        let meta = u32::from_be_bytes(*b"meta");
+        let ms00 = u32::from_be_bytes(*b"MS00");
+        // Empty dictType and a vendor blob of odd size
+        let meta_data = b"dict\0\0\0\0\0\0\0\0\0\0\0\x10".to_vec();
+        let ms00_data = (0..61u8).collect::<Vec<u8>>();
+        let private = [(meta, meta_data), (ms00, ms00_data)];
+        let mut profile = ColorProfile::new_from_slice(&apple_like_display(&private)).unwrap();
+        profile.set_description("Edited");
+        profile.rendering_intent = RenderingIntent::Saturation;
+
+        let encoded = profile.encode().unwrap();
+        let decoded = ColorProfile::new_from_slice(&encoded).unwrap();
+        assert_eq!(decoded.description(None).as_deref(), Some("Edited"));
+        assert_eq!(decoded.rendering_intent, RenderingIntent::Saturation);
+        assert_eq!(decoded.unknown_tags, private);
+        // Second cycle writes the very same bytes
+        assert_eq!(decoded.encode().unwrap(), encoded);
+        // Tags keep 4 byte alignment after odd sized data
+        let tags_count = u32::from_be_bytes(encoded[128..132].try_into().unwrap()) as usize;
+        let tag_table = &encoded[132..][..TAG_SIZE * tags_count];
+        for entry in tag_table.chunks_exact(TAG_SIZE) {
+            assert_eq!(u32::from_be_bytes(entry[4..8].try_into().unwrap()) % 4, 0);
+        }
+
+        let strip = EncodeOptions {
+            strip_unknown_tags: true,
+            ..Default::default()
+        };
+        let stripped = profile.encode_with(&strip).unwrap();
+        assert_eq!(stripped.len(), profile.encoded_size(&strip).unwrap());
+        assert!(stripped.len() < encoded.len());
+        let decoded = ColorProfile::new_from_slice(&stripped).unwrap();
+        assert!(decoded.unknown_tags.is_empty());
+        assert_eq!(decoded.description(None).as_deref(), Some("Edited"));
+    }
+
+    #[test]
+    fn test_peak_luminance() {
+        let mut profile = ColorProfile::new_bt2020_pq();
+        assert_eq!(profile.peak_luminance_nits(), None);
+        profile.luminance = Some(Xyz::new(950.3, 1000., 1089.));
+        let decoded = ColorProfile::new_from_slice(&profile.encode().unwrap()).unwrap();
+        let peak = decoded.peak_luminance_nits().unwrap();
+        assert!((peak - 1000.).abs() < 1e-3, "{peak}");
+
+        let lumi: u32 = Tag::Luminance.into();
+        let bytes = assemble_profile(&[(lumi, xyz_tag(Xyz::new(0., 0., 0.)))]);
+        let zero = ColorProfile::new_from_slice(&bytes).unwrap();
+        assert!(zero.luminance.is_some());
+        assert_eq!(zero.peak_luminance_nits(), None);
+    }
+
+    #[test]
+    fn test_encode_has_no_duplicate_tags() {
+        let mut profile =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        profile.media_white_point = Some(Xyz::new(0.9642, 1.0, 0.8249));
+        profile.gray_trc = Some(ToneReprCurve::Lut(vec![]));
+        let encoded = profile.encode().unwrap();
+        let (_, warnings) = ColorProfile::new_from_slice_with_warnings(&encoded).unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+    }
+
+    #[test]
+    fn test_cicp_round_trip() {
+        use crate::{CicpColorPrimaries, MatrixCoefficients, TransferCharacteristics};
+
+        for (transfer_characteristics, full_range) in [
+            (TransferCharacteristics::Smpte2084, false),
+            (TransferCharacteristics::Hlg, true),
+            (TransferCharacteristics::Srgb, true),
+        ] {
+            let mut profile = ColorProfile::new_bt2020();
+            let cicp = CicpProfile {
+                color_primaries: CicpColorPrimaries::Bt2020,
+                transfer_characteristics,
+                matrix_coefficients: MatrixCoefficients::Bt2020Ncl,
+                full_range,
+            };
+            profile.cicp = Some(cicp);
+            let decoded = ColorProfile::new_from_slice(&profile.encode().unwrap()).unwrap();
+            assert_eq!(decoded.cicp, Some(cicp));
+        }
+
+        let mut profile = ColorProfile::new_bt2020();
+        profile.cicp = None;
+        let decoded = ColorProfile::new_from_slice(&profile.encode().unwrap()).unwrap();
+        assert_eq!(decoded.cicp, None);
+    }
+
+    #[test]
+    fn test_chad_round_trip_and_fallback() {
+        let d65 = Chromaticity::D65.to_xyz();
+        let bradford = adaption_matrix(d65, Chromaticity::D50.to_xyz());
+
+        let mut explicit = ColorProfile::new_srgb();
+        explicit.media_white_point = Some(d65);
+        explicit.chromatic_adaptation = Some(bradford);
+        let decoded = ColorProfile::new_from_slice(&explicit.encode().unwrap()).unwrap();
+        let chad = decoded.chromatic_adaptation.unwrap();
+        for (row, expected) in chad.v.iter().zip(bradford.v.iter()) {
+            for (&v, &e) in row.iter().zip(expected.iter()) {
+                assert!((v - e).abs() < 1e-4, "chad {v} expected {e}");
+            }
+        }
+
+        let mut fallback = explicit.clone();
+        fallback.chromatic_adaptation = None;
+        let srgb = ColorProfile::new_srgb();
+        let a = explicit
+            .transform_matrix_for_intent(&srgb, RenderingIntent::AbsoluteColorimetric)
+            .unwrap();
+        let b = fallback
+            .transform_matrix_for_intent(&srgb, RenderingIntent::AbsoluteColorimetric)
+            .unwrap();
+        assert_eq!(a.v, b.v);
+
+        // D65 media white is preserved in absolute intent into a D50 media white profile
+        let mut d50_white = ColorProfile::new_srgb();
+        d50_white.media_white_point = Some(Chromaticity::D50.to_xyz());
+        let m = explicit
+            .transform_matrix_for_intent(&d50_white, RenderingIntent::AbsoluteColorimetric)
+            .unwrap();
+        let xyz = d50_white
+            .rgb_to_xyz_matrix()
+            .unwrap()
+            .mul_vector(m.mul_vector(Vector3f { v: [1., 1., 1.] }));
+        assert!((xyz.v[0] / xyz.v[1] - d65.x / d65.y).abs() < 1e-3);
+        assert!((xyz.v[2] / xyz.v[1] - d65.z / d65.y).abs() < 1e-3);
+
+        // Relative intent ignores adaptation
+        let relative = explicit
+            .transform_matrix_for_intent(&d50_white, RenderingIntent::RelativeColorimetric)
+            .unwrap();
+        assert_eq!(relative.v, explicit.transform_matrix(&d50_white).unwrap().v);
+    }
+
+    #[test]
+    fn test_text_tags_roundtrip() {
+        let mut profile = ColorProfile::new_display_p3();
+        profile.set_description("Mon");
+        profile.set_copyright("© 2025 Ünïcode 🎨");
+        profile.set_device_manufacturer("ACME");
+        profile.set_device_model("");
+        assert!(profile.device_model.is_none());
+        let encoded = profile.encode().unwrap();
+        assert_eq!(encoded.len() % 4, 0);
+        let tag_count = u32::from_be_bytes(encoded[128..132].try_into().unwrap()) as usize;
+        let mut signatures = Vec::new();
+        for tag in encoded[132..132 + tag_count * TAG_SIZE].chunks_exact(TAG_SIZE) {
+            let offset = u32::from_be_bytes(tag[4..8].try_into().unwrap());
+            assert_eq!(offset % 4, 0, "Tag data must be 4 byte aligned");
+            signatures.push(u32::from_be_bytes(tag[0..4].try_into().unwrap()));
+        }
+        assert!(!signatures.contains(&Tag::DeviceModel.into()));
+
+        let decoded = ColorProfile::new_from_slice(&encoded).unwrap();
+        assert_eq!(decoded.description(None).as_deref(), Some("Mon"));
+        assert_eq!(decoded.copyright().as_deref(), Some("© 2025 Ünïcode 🎨"));
+        assert_eq!(decoded.device_manufacturer_desc().as_deref(), Some("ACME"));
+        assert!(decoded.device_model_desc().is_none());
+
+        let swop =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        assert!(matches!(swop.copyright, Some(ProfileText::PlainString(_))));
+        let copyright = swop.copyright().unwrap();
+        assert!(
+            copyright.starts_with("Copyright X-Rite, Inc.") && !copyright.ends_with('\0'),
+            "{copyright}"
+        );
+        let mut edited = swop.clone();
+        edited.set_device_model("Proof");
+        let decoded = ColorProfile::new_from_slice(&edited.encode().unwrap()).unwrap();
+        assert_eq!(decoded.copyright(), swop.copyright());
+        assert_eq!(decoded.device_model_desc().as_deref(), Some("Proof"));
+    }
+
+    #[test]
+    fn test_description_locale() {
+        assert_eq!(
+            ColorProfile::new_srgb().description(None).as_deref(),
+            Some("sRGB IEC61966-2.1")
+        );
+        let swop =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        assert!(matches!(
+            swop.description,
+            Some(ProfileText::Description(_))
+        ));
+        assert_eq!(
+            swop.description(Some("de-DE")).as_deref(),
+            Some("SWOP2006_Coated5v2.icc")
+        );
+
+        let record = |language: &str, country: &str, value: &str| {
+            LocalizableString::new(language.to_string(), country.to_string(), value.to_string())
+        };
+        let mut profile = ColorProfile::new_display_p3();
+        profile.description = Some(ProfileText::Localizable(vec![
+            record("de", "DE", "Farbraum"),
+            record("en", "US", "Color space"),
+            record("fr", "FR", "Espace colorimétrique"),
+        ]));
+        let decoded = ColorProfile::new_from_slice(&profile.encode().unwrap()).unwrap();
+        for profile in [profile.clone(), decoded] {
+            assert_eq!(
+                profile.description(Some("de-DE")).as_deref(),
+                Some("Farbraum")
+            );
+            assert_eq!(
+                profile.description(Some("de_AT")).as_deref(),
+                Some("Farbraum")
+            );
+            assert_eq!(
+                profile.description(Some("fr")).as_deref(),
+                Some("Espace colorimétrique")
+            );
+            assert_eq!(
+                profile.description(Some("ja-JP")).as_deref(),
+                Some("Color space")
+            );
+            assert_eq!(profile.description(None).as_deref(), Some("Color space"));
+            assert_eq!(
+                profile.description_for_locale("fr", "FR").as_deref(),
+                Some("Espace colorimétrique")
+            );
+            assert_eq!(
+                profile.description_for_locale("de", "").as_deref(),
+                Some("Farbraum")
+            );
+            assert_eq!(
+                profile.description_for_locale("ja", "JP").as_deref(),
+                Some("Color space")
+            );
+        }
+
+        profile.description = Some(ProfileText::Localizable(vec![
+            record("de", "DE", "Farbraum"),
+            record("fr", "FR", "Espace colorimétrique"),
+        ]));
+        assert_eq!(
+            profile.description(Some("it-IT")).as_deref(),
+            Some("Farbraum")
+        );
+        assert_eq!(
+            profile.description_for_locale("it", "IT").as_deref(),
+            Some("Farbraum")
+        );
+        profile.description = None;
+        assert_eq!(profile.description(None), None);
+    }
+
+    #[test]
+    fn test_white_point_and_primaries() {
+        let close = |c: Option<Chromaticity>, x: f32, y: f32| {
+            let c = c.unwrap();
+            assert!(
+                (c.x - x).abs() < 1e-3 && (c.y - y).abs() < 1e-3,
+                "{c:?} expected {x}, {y}"
+            );
+        };
+        let srgb = ColorProfile::new_srgb();
+        close(srgb.white_point(), 0.3127, 0.3290);
+        close(srgb.red_primary(), 0.64, 0.33);
+        close(srgb.green_primary(), 0.30, 0.60);
+        close(srgb.blue_primary(), 0.15, 0.06);
+
+        let decoded = ColorProfile::new_from_slice(&srgb.encode().unwrap()).unwrap();
+        close(decoded.red_primary(), 0.64, 0.33);
+
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        assert!(cmyk.red_primary().is_none());
+        assert!(cmyk.white_point().is_some());
+        assert!(
+            ColorProfile::new_gray_with_gamma(2.2)
+                .green_primary()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,142 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::err::CmsError;
+use crate::profile::MAX_TAGS_COUNT;
+use crate::tag::TAG_SIZE;
+use std::borrow::Cow;
+use std::io::{Read, Seek, SeekFrom};
+
+/// Header size up to tag count, tag table follows it
+const TAG_TABLE_START: usize = 132;
+
+/// Random access to bytes of a profile being parsed
+pub(crate) trait ProfileSource {
+    /// Length of the profile data
+    fn size(&self) -> usize;
+
+    /// Reads `len` bytes at `offset`,
+    /// [CmsError::TruncatedData] if profile data ends before.
+    fn read_at(&mut self, offset: usize, len: usize) -> Result<Cow<'_, [u8]>, CmsError>;
+}
+
+fn check_range(offset: usize, len: usize, size: usize) -> Result<(), CmsError> {
+    match offset.checked_add(len) {
+        Some(end) if end <= size => Ok(()),
+        end => Err(CmsError::TruncatedData {
+            expected: end.unwrap_or(usize::MAX),
+            got: size,
+        }),
+    }
+}
+
+impl ProfileSource for &[u8] {
+    fn size(&self) -> usize {
+        self.len()
+    }
+
+    fn read_at(&mut self, offset: usize, len: usize) -> Result<Cow<'_, [u8]>, CmsError> {
+        check_range(offset, len, self.len())?;
+        Ok(Cow::Borrowed(&self[offset..offset + len]))
+    }
+}
+
+/// Profile read from a stream, starting at its position when created.
+///
+/// Only data needed is read, each tag is read with one seek.
+pub(crate) struct ReaderSource<'a, R> {
+    reader: &'a mut R,
+    start: u64,
+    size: usize,
+}
+
+impl<'a, R: Read + Seek> ReaderSource<'a, R> {
+    /// Profile takes size declared in its header, or the rest of the stream when it is shorter,
+    /// so profiles embedded in larger files can be read in place.
+    ///
+    /// Declared size is often wrong in real profiles. When it doesn't cover the tag table
+    /// and data of every tag, size is extended up to the end of the last of them,
+    /// so stream gives the same data as [ColorProfile::new_from_slice] would.
+    ///
+    /// [ColorProfile::new_from_slice]: crate::ColorProfile::new_from_slice
+    pub(crate) fn new(reader: &'a mut R) -> Result<Self, CmsError> {
+        let start = reader
+            .stream_position()
+            .map_err(|_| CmsError::InvalidProfile)?;
+        let end = reader
+            .seek(SeekFrom::End(0))
+            .map_err(|_| CmsError::InvalidProfile)?;
+        let remaining = usize::try_from(end.saturating_sub(start)).unwrap_or(usize::MAX);
+        let mut source = Self {
+            reader,
+            start,
+            size: remaining,
+        };
+        if remaining >= TAG_TABLE_START {
+            let header = source.read_at(0, TAG_TABLE_START)?;
+            let declared =
+                u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+            let tag_count =
+                u32::from_be_bytes([header[128], header[129], header[130], header[131]]);
+            // Smaller sizes can't hold header and tag count, too many tags fail parsing anyway
+            if declared >= TAG_TABLE_START && tag_count <= MAX_TAGS_COUNT {
+                let available = (remaining - TAG_TABLE_START) / TAG_SIZE;
+                let table_size = (tag_count as usize).min(available) * TAG_SIZE;
+                let table = source.read_at(TAG_TABLE_START, table_size)?;
+                let extent = table
+                    .chunks_exact(TAG_SIZE)
+                    .map(|tag| {
+                        let offset = u32::from_be_bytes([tag[4], tag[5], tag[6], tag[7]]);
+                        let size = u32::from_be_bytes([tag[8], tag[9], tag[10], tag[11]]);
+                        (offset as usize).saturating_add(size as usize)
+                    })
+                    .fold(TAG_TABLE_START + table_size, usize::max);
+                source.size = declared.max(extent).min(remaining);
+            }
+        }
+        Ok(source)
+    }
+}
+
+impl<R: Read + Seek> ProfileSource for ReaderSource<'_, R> {
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn read_at(&mut self, offset: usize, len: usize) -> Result<Cow<'_, [u8]>, CmsError> {
+        check_range(offset, len, self.size)?;
+        self.reader
+            .seek(SeekFrom::Start(self.start + offset as u64))
+            .map_err(|_| CmsError::InvalidProfile)?;
+        let mut data = vec![0u8; len];
+        self.reader
+            .read_exact(&mut data)
+            .map_err(|_| CmsError::InvalidProfile)?;
+        Ok(Cow::Owned(data))
+    }
+}
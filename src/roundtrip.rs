@@ -0,0 +1,172 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::lab::Lab;
+use crate::trc::curve_from_gamma;
+use crate::{
+    CmsError, ColorProfile, DataColorSpace, Layout, ProfileClass, RenderingIntent,
+    TransformOptions, Xyz,
+};
+
+/// Result of [roundtrip_report]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundTripReport {
+    /// Mean CIE 1976 delta E between source and round-tripped colors
+    pub mean_delta_e: f32,
+    /// Maximum CIE 1976 delta E between source and round-tripped colors
+    pub max_delta_e: f32,
+    /// Normalized device value of the first profile that has the maximum error
+    pub worst_device_value: Vec<f32>,
+    /// Count of evaluated device values
+    pub samples: usize,
+}
+
+pub(crate) fn device_layout(color_space: DataColorSpace) -> Result<Layout, CmsError> {
+    match color_space {
+        DataColorSpace::Rgb | DataColorSpace::Lab | DataColorSpace::Color3 => Ok(Layout::Rgb),
+        DataColorSpace::Cmyk | DataColorSpace::Color4 => Ok(Layout::Rgba),
+        _ => Err(CmsError::UnsupportedProfileConnection),
+    }
+}
+
+/// Linear RGB profile with the identity colorants,
+/// its device values are D50 normalized PCS XYZ.
+pub(crate) fn normalized_xyz_profile() -> ColorProfile {
+    let curve = curve_from_gamma(1.0);
+    ColorProfile {
+        red_colorant: Xyz::new(1., 0., 0.),
+        green_colorant: Xyz::new(0., 1., 0.),
+        blue_colorant: Xyz::new(0., 0., 1.),
+        red_trc: Some(curve.clone()),
+        green_trc: Some(curve.clone()),
+        blue_trc: Some(curve),
+        profile_class: ProfileClass::DisplayDevice,
+        color_space: DataColorSpace::Rgb,
+        pcs: DataColorSpace::Xyz,
+        ..Default::default()
+    }
+}
+
+/// Transforms device values from `a` to `b` and back to `a`, and measures the error.
+///
+/// Device space of `a` is sampled on a regular grid with `samples` points per channel,
+/// errors are measured in CIE Lab of the profile `a`.
+/// RGB and CMYK device spaces are supported.
+pub fn roundtrip_report(
+    a: &ColorProfile,
+    b: &ColorProfile,
+    intent: RenderingIntent,
+    samples: usize,
+) -> Result<RoundTripReport, CmsError> {
+    let options = TransformOptions {
+        rendering_intent: intent,
+        ..Default::default()
+    };
+    let a_layout = device_layout(a.color_space)?;
+    let b_layout = device_layout(b.color_space)?;
+
+    let forward = a.create_transform_f32(a_layout, b, b_layout, options)?;
+    let backward = b.create_transform_f32(b_layout, a, a_layout, options)?;
+    let measure =
+        a.create_transform_f32(a_layout, &normalized_xyz_profile(), Layout::Rgb, options)?;
+
+    let channels = a_layout.channels();
+    let grid = samples.max(2);
+    let count = grid
+        .checked_pow(channels as u32)
+        .ok_or(CmsError::OverflowingError)?;
+    let scale = 1. / (grid - 1) as f32;
+
+    let mut src = Vec::with_capacity(count * channels);
+    for i in 0..count {
+        let mut index = i;
+        for _ in 0..channels {
+            src.push((index % grid) as f32 * scale);
+            index /= grid;
+        }
+    }
+
+    let mut intermediate = vec![0f32; count * b_layout.channels()];
+    forward.transform(&src, &mut intermediate)?;
+    let mut round_tripped = vec![0f32; src.len()];
+    backward.transform(&intermediate, &mut round_tripped)?;
+
+    let mut xyz_src = vec![0f32; count * 3];
+    measure.transform(&src, &mut xyz_src)?;
+    let mut xyz_round_tripped = vec![0f32; count * 3];
+    measure.transform(&round_tripped, &mut xyz_round_tripped)?;
+
+    let mut sum = 0f64;
+    let mut max_delta_e = 0f32;
+    let mut worst = 0usize;
+    for (i, (v0, v1)) in xyz_src
+        .chunks_exact(3)
+        .zip(xyz_round_tripped.chunks_exact(3))
+        .enumerate()
+    {
+        let lab0 = Lab::from_normalized_xyz(v0[0], v0[1], v0[2]);
+        let lab1 = Lab::from_normalized_xyz(v1[0], v1[1], v1[2]);
+        let delta_e = lab0.delta_e_76(&lab1);
+        sum += delta_e as f64;
+        if delta_e > max_delta_e {
+            max_delta_e = delta_e;
+            worst = i;
+        }
+    }
+
+    Ok(RoundTripReport {
+        mean_delta_e: (sum / count as f64) as f32,
+        max_delta_e,
+        worst_device_value: src[worst * channels..(worst + 1) * channels].to_vec(),
+        samples: count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_srgb_bt2020() {
+        let srgb = ColorProfile::new_srgb();
+        let bt2020 = ColorProfile::new_bt2020();
+        let report = roundtrip_report(&srgb, &bt2020, RenderingIntent::Perceptual, 17).unwrap();
+        assert_eq!(report.samples, 17 * 17 * 17);
+        assert!(report.mean_delta_e < 0.1, "{:?}", report);
+        assert!(report.max_delta_e >= report.mean_delta_e);
+        assert_eq!(report.worst_device_value.len(), 3);
+    }
+
+    #[test]
+    fn test_roundtrip_srgb_identity() {
+        let srgb = ColorProfile::new_srgb();
+        let report = roundtrip_report(&srgb, &srgb, RenderingIntent::Perceptual, 17).unwrap();
+        assert!(report.mean_delta_e < 0.05, "{:?}", report);
+        assert!(report.max_delta_e < 0.2, "{:?}", report);
+    }
+}
@@ -0,0 +1,426 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::mlaf::mlaf;
+use crate::transform::PointeeSizeExpressible;
+use crate::trc::GammaLutInterpolate;
+use crate::{Chromaticity, CmsError, ColorProfile, DataColorSpace, Lab, Layout, Matrix3f, Xyz};
+use num_traits::AsPrimitive;
+
+/// Converts scanlines of RGB device values into CIE Lab and back,
+/// using the same linearization, matrix and gamma tables as the matrix-shaper transform.
+///
+/// Lab values are interleaved as L, a, b in natural units (L in 0..100), when the layout
+/// has alpha it is carried as the fourth value normalized into [0, 1].
+pub struct ScanlineLab<T> {
+    layout: Layout,
+    linear: [Box<[f32]>; 3],
+    gamma: [Box<[T]>; 3],
+    gamma_lut: usize,
+    bit_depth: usize,
+    rgb_to_xyz: Matrix3f,
+    xyz_to_rgb: Matrix3f,
+}
+
+#[doc(hidden)]
+pub trait ScanlineLabFactory: Sized {
+    fn make_scanline_lab(
+        profile: &ColorProfile,
+        layout: Layout,
+    ) -> Result<ScanlineLab<Self>, CmsError>;
+}
+
+impl ScanlineLabFactory for u8 {
+    fn make_scanline_lab(
+        profile: &ColorProfile,
+        layout: Layout,
+    ) -> Result<ScanlineLab<Self>, CmsError> {
+        make_scanline_lab::<u8, 8, 256, 4096>(profile, layout)
+    }
+}
+
+impl ScanlineLabFactory for u16 {
+    fn make_scanline_lab(
+        profile: &ColorProfile,
+        layout: Layout,
+    ) -> Result<ScanlineLab<Self>, CmsError> {
+        make_scanline_lab::<u16, 16, 65536, 65536>(profile, layout)
+    }
+}
+
+fn make_scanline_lab<
+    T: Copy + Default + PointeeSizeExpressible + GammaLutInterpolate + 'static,
+    const BIT_DEPTH: usize,
+    const LINEAR_CAP: usize,
+    const GAMMA_CAP: usize,
+>(
+    profile: &ColorProfile,
+    layout: Layout,
+) -> Result<ScanlineLab<T>, CmsError>
+where
+    f32: AsPrimitive<T>,
+    u32: AsPrimitive<T>,
+{
+    if !matches!(
+        layout,
+        Layout::Rgb | Layout::Rgba | Layout::Bgr | Layout::Bgra | Layout::Argb | Layout::Abgr
+    ) {
+        return Err(CmsError::InvalidLayout);
+    }
+    if profile.color_space != DataColorSpace::Rgb
+        || profile.pcs != DataColorSpace::Xyz
+        || !profile.has_full_colors_triplet()
+    {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+    let rgb_to_xyz = profile
+        .rgb_to_xyz_matrix()
+        .ok_or(CmsError::UnsupportedProfileConnection)?;
+    let xyz_to_rgb = rgb_to_xyz.inverse();
+    let lin_r = profile.build_r_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(false)?;
+    let lin_g = profile.build_g_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(false)?;
+    let lin_b = profile.build_b_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(false)?;
+    let gamma_r =
+        profile.build_gamma_table::<T, 65536, GAMMA_CAP, BIT_DEPTH>(&profile.red_trc, false)?;
+    let gamma_g =
+        profile.build_gamma_table::<T, 65536, GAMMA_CAP, BIT_DEPTH>(&profile.green_trc, false)?;
+    let gamma_b =
+        profile.build_gamma_table::<T, 65536, GAMMA_CAP, BIT_DEPTH>(&profile.blue_trc, false)?;
+    Ok(ScanlineLab {
+        layout,
+        linear: [lin_r, lin_g, lin_b],
+        gamma: [gamma_r, gamma_g, gamma_b],
+        gamma_lut: GAMMA_CAP,
+        bit_depth: BIT_DEPTH,
+        rgb_to_xyz,
+        xyz_to_rgb,
+    })
+}
+
+impl<T: Copy + PointeeSizeExpressible + AsPrimitive<f32> + 'static> ScanlineLab<T>
+where
+    f32: AsPrimitive<T>,
+{
+    fn check_lanes(&self, src: usize, dst: usize) -> Result<(), CmsError> {
+        let channels = self.layout.channels();
+        if src % channels != 0 || dst % channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if src != dst {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        Ok(())
+    }
+
+    /// Converts `src` device values into Lab values in `dst`.
+    ///
+    /// `dst` has the same count of channels as the layout.
+    pub fn to_lab(&self, src: &[T], dst: &mut [f32]) -> Result<(), CmsError> {
+        self.check_lanes(src.len(), dst.len())?;
+        let cn = self.layout;
+        let channels = cn.channels();
+        let max_value = ((1u32 << self.bit_depth) - 1) as f32;
+        const WP: Xyz = Chromaticity::D50.to_xyz();
+        let m = self.rgb_to_xyz;
+
+        for (src, dst) in src
+            .chunks_exact(channels)
+            .zip(dst.chunks_exact_mut(channels))
+        {
+            let r = self.linear[0][src[cn.r_i()]._as_usize()];
+            let g = self.linear[1][src[cn.g_i()]._as_usize()];
+            let b = self.linear[2][src[cn.b_i()]._as_usize()];
+
+            let x = mlaf(mlaf(r * m.v[0][0], g, m.v[0][1]), b, m.v[0][2]);
+            let y = mlaf(mlaf(r * m.v[1][0], g, m.v[1][1]), b, m.v[1][2]);
+            let z = mlaf(mlaf(r * m.v[2][0], g, m.v[2][1]), b, m.v[2][2]);
+
+            let lab = Lab::from_normalized_xyz(x / WP.x, y / WP.y, z / WP.z);
+            dst[0] = lab.l;
+            dst[1] = lab.a;
+            dst[2] = lab.b;
+            if cn.has_alpha() {
+                dst[3] = src[cn.a_i()].as_() / max_value;
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts Lab values in `src` back into device values in `dst`.
+    ///
+    /// Colors outside of the profile gamut are clipped.
+    pub fn from_lab(&self, src: &[f32], dst: &mut [T]) -> Result<(), CmsError> {
+        self.check_lanes(src.len(), dst.len())?;
+        let cn = self.layout;
+        let channels = cn.channels();
+        let max_value = ((1u32 << self.bit_depth) - 1) as f32;
+        let scale = (self.gamma_lut - 1) as f32;
+        const WP: Xyz = Chromaticity::D50.to_xyz();
+        let m = self.xyz_to_rgb;
+
+        for (src, dst) in src
+            .chunks_exact(channels)
+            .zip(dst.chunks_exact_mut(channels))
+        {
+            let xyz = Lab::new(src[0], src[1], src[2]).to_normalized_xyz();
+            let x = xyz.x * WP.x;
+            let y = xyz.y * WP.y;
+            let z = xyz.z * WP.z;
+
+            let r = mlaf(mlaf(x * m.v[0][0], y, m.v[0][1]), z, m.v[0][2]);
+            let g = mlaf(mlaf(x * m.v[1][0], y, m.v[1][1]), z, m.v[1][2]);
+            let b = mlaf(mlaf(x * m.v[2][0], y, m.v[2][1]), z, m.v[2][2]);
+
+            let r = mlaf(0.5f32, r.max(0f32).min(1f32), scale) as u16 as usize;
+            let g = mlaf(0.5f32, g.max(0f32).min(1f32), scale) as u16 as usize;
+            let b = mlaf(0.5f32, b.max(0f32).min(1f32), scale) as u16 as usize;
+
+            dst[cn.r_i()] = self.gamma[0][r];
+            dst[cn.g_i()] = self.gamma[1][g];
+            dst[cn.b_i()] = self.gamma[2][b];
+            if cn.has_alpha() {
+                dst[cn.a_i()] = mlaf(0.5f32, src[3].max(0f32).min(1f32), max_value).as_();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ColorProfile {
+    /// Creates reusable converter of RGB scanlines of this profile into CIE Lab and back.
+    ///
+    /// Only 8 and 16 bit data is supported.
+    pub fn create_scanline_lab<T: ScanlineLabFactory>(
+        &self,
+        layout: Layout,
+    ) -> Result<ScanlineLab<T>, CmsError> {
+        T::make_scanline_lab(self, layout)
+    }
+
+    /// Converts RGB scanline of this profile into CIE Lab.
+    ///
+    /// Tables are built on every call, use [ColorProfile::create_scanline_lab]
+    /// when more than one scanline has to be converted.
+    pub fn scanline_to_lab<
+        T: ScanlineLabFactory + Copy + PointeeSizeExpressible + AsPrimitive<f32> + 'static,
+    >(
+        &self,
+        src: &[T],
+        layout: Layout,
+        dst: &mut [f32],
+    ) -> Result<(), CmsError>
+    where
+        f32: AsPrimitive<T>,
+    {
+        self.create_scanline_lab::<T>(layout)?.to_lab(src, dst)
+    }
+
+    /// Converts CIE Lab scanline into RGB device values of this profile.
+    ///
+    /// Tables are built on every call, use [ColorProfile::create_scanline_lab]
+    /// when more than one scanline has to be converted.
+    pub fn lab_to_scanline<
+        T: ScanlineLabFactory + Copy + PointeeSizeExpressible + AsPrimitive<f32> + 'static,
+    >(
+        &self,
+        src: &[f32],
+        layout: Layout,
+        dst: &mut [T],
+    ) -> Result<(), CmsError>
+    where
+        f32: AsPrimitive<T>,
+    {
+        self.create_scanline_lab::<T>(layout)?.from_lab(src, dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::LutDataType;
+    use crate::{LutType, LutWarehouse, TransformOptions};
+
+    fn identity_lut() -> LutWarehouse {
+        let mut clut_table = Vec::new();
+        for x in 0..2 {
+            for y in 0..2 {
+                for z in 0..2 {
+                    clut_table.extend_from_slice(&[x as f32, y as f32, z as f32]);
+                }
+            }
+        }
+        LutWarehouse::Lut(LutDataType {
+            num_input_channels: 3,
+            num_output_channels: 3,
+            num_clut_grid_points: 2,
+            matrix: Matrix3f::IDENTITY,
+            num_input_table_entries: 2,
+            num_output_table_entries: 2,
+            input_table: vec![0., 1., 0., 1., 0., 1.],
+            clut_table,
+            output_table: vec![0., 1., 0., 1., 0., 1.],
+            lut_type: LutType::Lut16,
+        })
+    }
+
+    fn lab_profile() -> ColorProfile {
+        ColorProfile {
+            color_space: DataColorSpace::Lab,
+            pcs: DataColorSpace::Lab,
+            lut_a_to_b_perceptual: Some(identity_lut()),
+            lut_b_to_a_perceptual: Some(identity_lut()),
+            ..Default::default()
+        }
+    }
+
+    fn encode_lab8(lab: &[f32]) -> [u8; 3] {
+        [
+            (lab[0] / 100. * 255.).round().clamp(0., 255.) as u8,
+            (lab[1] + 128.).round().clamp(0., 255.) as u8,
+            (lab[2] + 128.).round().clamp(0., 255.) as u8,
+        ]
+    }
+
+    fn rgb_grid() -> Vec<u8> {
+        let mut src = Vec::new();
+        for r in (0..=255u8).step_by(15) {
+            for g in (0..=255u8).step_by(15) {
+                for b in (0..=255u8).step_by(15) {
+                    src.extend_from_slice(&[r, g, b]);
+                }
+            }
+        }
+        src
+    }
+
+    #[test]
+    fn test_scanline_to_lab_matches_transform() {
+        let srgb = ColorProfile::new_srgb();
+        let lab = lab_profile();
+        let src = rgb_grid();
+        let options = TransformOptions {
+            interpolation_method: crate::InterpolationMethod::Tetrahedral,
+            ..Default::default()
+        };
+        let mut reference = vec![0u8; src.len()];
+        srgb.create_transform_8bit(Layout::Rgb, &lab, Layout::Rgb, options)
+            .unwrap()
+            .transform(&src, &mut reference)
+            .unwrap();
+        let mut values = vec![0f32; src.len()];
+        srgb.scanline_to_lab(&src, Layout::Rgb, &mut values)
+            .unwrap();
+        for (values, reference) in values.chunks_exact(3).zip(reference.chunks_exact(3)) {
+            let encoded = encode_lab8(values);
+            for (&a, &b) in encoded.iter().zip(reference.iter()) {
+                assert!(
+                    a.abs_diff(b) <= 1,
+                    "Lab {values:?} encoded as {encoded:?}, transform gave {reference:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_lab_to_scanline_inverts_transform() {
+        let srgb = ColorProfile::new_srgb();
+        let lab = lab_profile();
+        let src = rgb_grid();
+        let options = TransformOptions {
+            interpolation_method: crate::InterpolationMethod::Tetrahedral,
+            ..Default::default()
+        };
+        let src_f32 = src.iter().map(|&x| x as f32 / 255.).collect::<Vec<f32>>();
+        let mut pcs = vec![0f32; src.len()];
+        srgb.create_transform_f32(Layout::Rgb, &lab, Layout::Rgb, options)
+            .unwrap()
+            .transform(&src_f32, &mut pcs)
+            .unwrap();
+        let values = pcs
+            .chunks_exact(3)
+            .flat_map(|x| [x[0] * 100., x[1] * 255. - 128., x[2] * 255. - 128.])
+            .collect::<Vec<f32>>();
+        let mut dst = vec![0u8; src.len()];
+        srgb.lab_to_scanline(&values, Layout::Rgb, &mut dst)
+            .unwrap();
+        for (src, dst) in src.chunks_exact(3).zip(dst.chunks_exact(3)) {
+            for (&a, &b) in src.iter().zip(dst.iter()) {
+                assert!(a.abs_diff(b) <= 1, "{src:?} -> {dst:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_scanline_lab_round_trip() {
+        let profile = ColorProfile::new_display_p3();
+        for layout in [Layout::Rgba, Layout::Bgra] {
+            let converter = profile.create_scanline_lab::<u8>(layout).unwrap();
+            let src = rgb_grid()
+                .chunks_exact(3)
+                .enumerate()
+                .flat_map(|(i, x)| [x[0], x[1], x[2], i as u8])
+                .collect::<Vec<u8>>();
+            let mut values = vec![0f32; src.len()];
+            converter.to_lab(&src, &mut values).unwrap();
+            let mut dst = vec![0u8; src.len()];
+            converter.from_lab(&values, &mut dst).unwrap();
+            for (src, dst) in src.chunks_exact(4).zip(dst.chunks_exact(4)) {
+                for c in 0..3 {
+                    assert!(src[c].abs_diff(dst[c]) <= 1, "{src:?} -> {dst:?}");
+                }
+                assert_eq!(src[3], dst[3]);
+            }
+        }
+
+        let converter = profile.create_scanline_lab::<u16>(Layout::Rgb).unwrap();
+        let src = rgb_grid()
+            .iter()
+            .map(|&x| x as u16 * 257)
+            .collect::<Vec<u16>>();
+        let mut values = vec![0f32; src.len()];
+        converter.to_lab(&src, &mut values).unwrap();
+        let mut dst = vec![0u16; src.len()];
+        converter.from_lab(&values, &mut dst).unwrap();
+        // One step of 16 bit gamma table is ~10 LSB in shadows, so compare
+        // against the identity transform that goes through the same tables
+        let mut reference = vec![0u16; src.len()];
+        profile
+            .create_transform_16bit(
+                Layout::Rgb,
+                &profile,
+                Layout::Rgb,
+                TransformOptions::default(),
+            )
+            .unwrap()
+            .transform(&src, &mut reference)
+            .unwrap();
+        for ((&a, &b), &c) in src.iter().zip(dst.iter()).zip(reference.iter()) {
+            assert!(b.abs_diff(c) <= 16, "{a} -> {b}, transform gave {c}");
+        }
+    }
+}
@@ -0,0 +1,116 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+/// Viewing surround of the display the content is shown on.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum Surround {
+    /// Cinema, surround is much darker than the screen
+    Dark,
+    /// Home theatre or mastering room, ITU-R BT.1886 reference environment
+    Dim,
+    /// Office or other bright environment
+    Average,
+}
+
+impl Surround {
+    /// Relative system gamma of the surround, dark surround is the reference.
+    ///
+    /// Darker surround lowers perceived contrast, so the same content needs a higher
+    /// end-to-end gamma there: 1.0 for dark, 1.09 for dim and 1.19 for average,
+    /// which follows from display gammas 2.6, 2.4 and 2.2.
+    #[inline]
+    pub const fn exponent(self) -> f32 {
+        match self {
+            Surround::Dark => 1.0,
+            Surround::Dim => 1.09,
+            Surround::Average => 1.19,
+        }
+    }
+}
+
+/// Adjusts system gamma when content mastered for `source` surround
+/// is going to be viewed in `destination` surround.
+///
+/// Linear light is raised to `source.exponent() / destination.exponent()`,
+/// e.g. dim to dark is 1.09, so mid-gray 0.18 becomes 0.154.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct SurroundCompensation {
+    pub source: Surround,
+    pub destination: Surround,
+}
+
+impl SurroundCompensation {
+    /// Exponent applied to linear light
+    #[inline]
+    pub const fn exponent(self) -> f32 {
+        self.source.exponent() / self.destination.exponent()
+    }
+
+    /// Checks if compensation changes anything
+    #[inline]
+    pub const fn is_identity(self) -> bool {
+        self.source as u8 == self.destination as u8
+    }
+
+    /// Applies compensation to linearization table.
+    #[cfg(feature = "transforms")]
+    pub(crate) fn apply_to_table<const N: usize>(self, table: &mut [f32; N]) {
+        if self.is_identity() {
+            return;
+        }
+        let exponent = self.exponent();
+        for v in table.iter_mut() {
+            *v = v.max(0.).powf(exponent);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "transforms"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_surround_exponent() {
+        let dim_to_dark = SurroundCompensation {
+            source: Surround::Dim,
+            destination: Surround::Dark,
+        };
+        assert!((dim_to_dark.exponent() - 1.09).abs() < 1e-6);
+        let mut table = [0.18f32; 4];
+        dim_to_dark.apply_to_table(&mut table);
+        assert!((table[0] - 0.18f32.powf(1.09)).abs() < 1e-6);
+        assert!(
+            SurroundCompensation {
+                source: Surround::Average,
+                destination: Surround::Average,
+            }
+            .is_identity()
+        );
+    }
+}
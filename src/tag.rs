@@ -62,6 +62,8 @@ pub(crate) enum Tag {
     CharTarget,
     Technology,
     CalibrationDateTime,
+    VideoCardGamma,
+    ColorantTable,
 }
 
 impl TryFrom<u32> for Tag {
@@ -128,6 +130,10 @@ impl TryFrom<u32> for Tag {
             return Ok(Self::Technology);
         } else if value == u32::from_ne_bytes(*b"calt").to_be() {
             return Ok(Self::CalibrationDateTime);
+        } else if value == u32::from_ne_bytes(*b"vcgt").to_be() {
+            return Ok(Self::VideoCardGamma);
+        } else if value == u32::from_ne_bytes(*b"clrt").to_be() {
+            return Ok(Self::ColorantTable);
         }
         Err(CmsError::UnknownTag(value))
     }
@@ -166,6 +172,8 @@ impl From<Tag> for u32 {
             Tag::CharTarget => u32::from_ne_bytes(*b"targ").to_be(),
             Tag::Technology => u32::from_ne_bytes(*b"tech").to_be(),
             Tag::CalibrationDateTime => u32::from_ne_bytes(*b"calt").to_be(),
+            Tag::VideoCardGamma => u32::from_ne_bytes(*b"vcgt").to_be(),
+            Tag::ColorantTable => u32::from_ne_bytes(*b"clrt").to_be(),
         }
     }
 }
@@ -0,0 +1,212 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+//! Helpers for golden-image tests of transform output.
+//!
+//! Kernels for different CPU features are allowed to differ by one least significant bit,
+//! so comparing rendered buffers byte by byte is fragile. This module provides
+//! tolerance based comparison and compact checksums which are stable against such noise.
+use crate::Layout;
+use num_traits::AsPrimitive;
+
+/// Number of 4 bit cells in the checksum
+const CHECKSUM_CELLS: usize = 16;
+/// Quantization levels of one cell
+const CHECKSUM_LEVELS: f64 = 15.;
+
+/// Computes perceptual checksum of a pixel buffer, which is resilient to rounding differences.
+///
+/// Construction:
+/// 1. Samples are normalized to `[0, 1]` using `bit_depth`.
+/// 2. Buffer is split into `16 / channels` segments of consecutive pixels,
+///    and every channel, including alpha, is averaged over each segment.
+/// 3. Every average is quantized into 16 levels and stored in its own 4 bit cell,
+///    segment by segment, channel by channel, starting from the lowest bits.
+///
+/// Checksums must be compared with [`assert_similar`] or [`checksum_distance`], not with `==`.
+/// Guarantees:
+/// - Changing samples by at most one code value changes every cell by at most 1,
+///   thus checksums compared with tolerance `1` stay equal.
+/// - Shifting the average of any channel over a segment by more than
+///   2/15 of full range, without clipping, changes its cell by at least 2.
+///
+/// `bit_depth` is the number of significant bits, e.g. 8, 10, 16;
+/// for floating point samples `1` means `[0, 1]` range.
+pub fn perceptual_checksum<T: Copy + AsPrimitive<f32>>(
+    pixels: &[T],
+    layout: Layout,
+    bit_depth: usize,
+) -> u64 {
+    let channels = layout.channels();
+    let segments = CHECKSUM_CELLS / channels;
+    let max_value = max_value(bit_depth);
+    let pixel_count = pixels.len() / channels;
+    let mut checksum = 0u64;
+    for segment in 0..segments {
+        let start = pixel_count * segment / segments;
+        let end = pixel_count * (segment + 1) / segments;
+        if start == end {
+            continue;
+        }
+        let mut sums = [0f64; 4];
+        for pixel in pixels[start * channels..end * channels].chunks_exact(channels) {
+            for (sum, &v) in sums.iter_mut().zip(pixel.iter()) {
+                *sum += v.as_() as f64;
+            }
+        }
+        for (channel, &sum) in sums.iter().take(channels).enumerate() {
+            let normalized = (sum / (end - start) as f64 / max_value).max(0.).min(1.);
+            let level = (normalized * CHECKSUM_LEVELS).round() as u64;
+            checksum |= level << ((segment * channels + channel) * 4);
+        }
+    }
+    checksum
+}
+
+/// Returns the largest difference between corresponding cells of two [`perceptual_checksum`]s.
+pub fn checksum_distance(a: u64, b: u64) -> u8 {
+    (0..CHECKSUM_CELLS)
+        .map(|i| {
+            let x = ((a >> (i * 4)) & 0xf) as u8;
+            let y = ((b >> (i * 4)) & 0xf) as u8;
+            x.abs_diff(y)
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Asserts that two [`perceptual_checksum`]s differ by no more than `tolerance` levels in any cell.
+///
+/// Tolerance `1` accepts any one code value noise for bit depths from 4.
+#[track_caller]
+pub fn assert_similar(a: u64, b: u64, tolerance: u8) {
+    let distance = checksum_distance(a, b);
+    assert!(
+        distance <= tolerance,
+        "Checksums {a:#018x} and {b:#018x} differ by {distance}, tolerance {tolerance}"
+    );
+}
+
+/// Asserts that each sample of `a` differs from `b` by no more than `tolerance` code values.
+///
+/// # Panics
+/// Panics on the first sample out of tolerance, or if buffers have different length.
+#[track_caller]
+pub fn assert_samples_similar<T: Copy + AsPrimitive<f32> + std::fmt::Debug>(
+    a: &[T],
+    b: &[T],
+    tolerance: f32,
+) {
+    assert_eq!(a.len(), b.len(), "Buffers must have the same length");
+    for (i, (&x, &y)) in a.iter().zip(b.iter()).enumerate() {
+        let diff = (x.as_() - y.as_()).abs();
+        assert!(
+            diff <= tolerance,
+            "Sample {i} differs by {diff}, tolerance {tolerance}: {x:?} vs {y:?}"
+        );
+    }
+}
+
+#[inline]
+fn max_value(bit_depth: usize) -> f64 {
+    if bit_depth <= 1 {
+        1.
+    } else {
+        ((1u64 << bit_depth.min(32)) - 1) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColorProfile, TransformOptions};
+
+    fn gradient() -> Vec<u8> {
+        let mut src = vec![0u8; 256 * 64 * 3];
+        for (i, pixel) in src.chunks_exact_mut(3).enumerate() {
+            pixel[0] = (i % 256) as u8;
+            pixel[1] = ((i / 256) * 4) as u8;
+            pixel[2] = 255 - (i % 256) as u8;
+        }
+        src
+    }
+
+    #[test]
+    fn test_checksum_ignores_lsb_noise() {
+        let src = gradient();
+        let noisy = src
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| match i % 3 {
+                0 => x.saturating_add(1),
+                1 => x.saturating_sub(1),
+                _ => x,
+            })
+            .collect::<Vec<_>>();
+        assert_similar(
+            perceptual_checksum(&src, Layout::Rgb, 8),
+            perceptual_checksum(&noisy, Layout::Rgb, 8),
+            1,
+        );
+        assert_samples_similar(&src, &noisy, 1.);
+
+        let shifted = src.iter().map(|&x| x / 2).collect::<Vec<_>>();
+        assert!(
+            checksum_distance(
+                perceptual_checksum(&src, Layout::Rgb, 8),
+                perceptual_checksum(&shifted, Layout::Rgb, 8)
+            ) > 1
+        );
+    }
+
+    #[test]
+    fn test_checksum_transform_kernels() {
+        let src = gradient();
+        let bt2020 = ColorProfile::new_bt2020();
+        let srgb = ColorProfile::new_srgb();
+        let mut checksums = Vec::new();
+        for prefer_fixed_point in [false, true] {
+            let transform = bt2020
+                .create_transform_8bit(
+                    Layout::Rgb,
+                    &srgb,
+                    Layout::Rgb,
+                    TransformOptions {
+                        prefer_fixed_point,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            let mut dst = vec![0u8; src.len()];
+            transform.transform(&src, &mut dst).unwrap();
+            checksums.push(perceptual_checksum(&dst, Layout::Rgb, 8));
+        }
+        assert_similar(checksums[0], checksums[1], 1);
+    }
+}
@@ -26,36 +26,307 @@
  * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
+#[cfg(feature = "transforms")]
 use crate::conversions::{
-    CompressForLut, RgbXyzFactory, ToneReproductionRgbToGray, TransformProfileRgb, make_gray_to_x,
-    make_lut_transform, make_rgb_to_gray,
+    CompressForLut, GrayDepthTrc, LazyGammaTable, LutSampler, QCMS_GAMMA_LUT, RgbXyzFactory,
+    ToneReproductionRgbToGray, TransformProfileRgb, TransformProfileRgbFloat,
+    TransformProfileRgbLazy, append_vcgt_epilogue, append_ycbcr_epilogue, has_q4_12_simd_kernel,
+    is_trc_only_transform, make_custom_stage_transform, make_device_link_sampler,
+    make_gray_depth_transform, make_gray_to_x, make_lut_sampler, make_lut_transform,
+    make_multi_profile_sampler, make_nchannel_transform, make_qcms_profile_transform,
+    make_rgb_to_gray, make_rgb_trc_transform, make_rgb_xyz_gamut_check, make_rgb_xyz_rgb_transform,
+    make_rgb_xyz_rgb_transform_lazy, wrap_premultiplied_alpha, wrap_premultiplied_alpha_depth,
+    wrap_premultiplied_gamut_check,
 };
+#[cfg(feature = "transforms")]
+use crate::depth::ConvertDepth;
+#[cfg(feature = "transforms")]
+use crate::dispatch::with_deterministic_kernel;
 use crate::err::CmsError;
-use crate::profile::LutDataType;
-use crate::trc::GammaLutInterpolate;
-use crate::{ColorProfile, DataColorSpace, LutWarehouse, RenderingIntent, Vector3f, Xyz};
+#[cfg(feature = "transforms")]
+use crate::gamut_tag::GamutMaskExecutor;
+#[cfg(feature = "transforms")]
+use crate::trc::{GammaLutInterpolate, ToneReprCurve};
+use crate::{ChromaticAdaptation, RenderingIntent, SurroundCompensation};
+#[cfg(feature = "transforms")]
+use crate::{ColorProfile, DataColorSpace, LutWarehouse, Matrix3f, Vector3f, Xyz};
+#[cfg(feature = "transforms")]
 use num_traits::AsPrimitive;
+#[cfg(feature = "transforms")]
+use std::sync::Arc;
 
 /// Transformation executor itself
 pub trait TransformExecutor<V: Copy + Default> {
     /// Count of samples always must match.
     /// If there is N samples of *Cmyk* source then N samples of *Rgb* is expected as an output.
     fn transform(&self, src: &[V], dst: &mut [V]) -> Result<(), CmsError>;
+
+    /// Transforms `buf` in place, source and destination layouts must have the same count of channels.
+    ///
+    /// Default implementation copies `buf` into a scratch buffer and calls [TransformExecutor::transform],
+    /// executors able to work in place avoid the copy.
+    fn transform_in_place(&self, buf: &mut [V]) -> Result<(), CmsError> {
+        let src = buf.to_vec();
+        self.transform(&src, buf)
+    }
+
+    /// Transforms `height` rows of `width` pixels, rows start every `src_stride` and `dst_stride`
+    /// samples, row padding is neither read nor written.
+    ///
+    /// Strides must not be less than `width` times channels count of corresponding layout,
+    /// otherwise [CmsError::InvalidStride] is returned.
+    ///
+    /// Executors made by [ColorProfile] support this, default implementation doesn't know
+    /// channels count and returns [CmsError::UnsupportedChannelConfiguration].
+    fn transform_strided(
+        &self,
+        _src: &[V],
+        _src_stride: usize,
+        _dst: &mut [V],
+        _dst_stride: usize,
+        _width: usize,
+        _height: usize,
+    ) -> Result<(), CmsError> {
+        Err(CmsError::UnsupportedChannelConfiguration)
+    }
+
+    /// Transforms single pixel, e.g. for color pickers.
+    ///
+    /// `src` must hold exactly one pixel of source layout, otherwise [CmsError::LaneSizeMismatch]
+    /// is returned. Converted pixel takes as many leading values of the result
+    /// as destination layout has channels, the rest are default.
+    ///
+    /// Executors made by [ColorProfile] support this, default implementation doesn't know
+    /// channels count and returns [CmsError::UnsupportedChannelConfiguration].
+    fn transform_pixel(&self, _src: &[V]) -> Result<[V; 4], CmsError> {
+        Err(CmsError::UnsupportedChannelConfiguration)
+    }
+
+    /// Same as [TransformExecutor::transform], spread over rayon thread pool.
+    ///
+    /// Executors made by [ColorProfile] split the buffers into chunks of whole pixels,
+    /// each chunk runs on its own stack buffers, so threads share nothing but the executor,
+    /// which has to be `Sync` as `Transform*Executor` aliases are.
+    /// Default implementation doesn't know channels count and transforms on the calling thread.
+    #[cfg(feature = "rayon")]
+    fn transform_parallel(&self, src: &[V], dst: &mut [V]) -> Result<(), CmsError> {
+        self.transform(src, dst)
+    }
+
+    /// Touches all internal tables and LUTs once, so the first [TransformExecutor::transform]
+    /// call doesn't pay for page faults and cold caches.
+    ///
+    /// Intended to be called off the critical path right after creation, does nothing by default.
+    fn warmup(&self) {}
+}
+
+/// Executor that knows channels count of its layouts, to walk strided images
+#[cfg(feature = "transforms")]
+struct TransformLayoutsExecutor<T> {
+    executor: Box<dyn TransformExecutor<T> + Send + Sync>,
+    src_channels: usize,
+    dst_channels: usize,
+    diffusion: Option<ErrorDiffusion>,
+}
+
+/// Floyd–Steinberg error diffusion of integer output.
+///
+/// Exact values come from `f32` transform between the same profiles and layouts.
+#[cfg(feature = "transforms")]
+struct ErrorDiffusion {
+    exact: Box<TransformF32BitExecutor>,
+    max_value: f32,
+}
+
+#[cfg(feature = "transforms")]
+impl ErrorDiffusion {
+    #[allow(clippy::too_many_arguments)]
+    fn transform_rows<T: Copy + AsPrimitive<f32>>(
+        &self,
+        src: &[T],
+        src_stride: usize,
+        src_row: usize,
+        dst: &mut [T],
+        dst_stride: usize,
+        dst_row: usize,
+        channels: usize,
+    ) -> Result<(), CmsError>
+    where
+        f32: AsPrimitive<T>,
+    {
+        let scale = 1. / self.max_value;
+        let mut src_f32 = vec![0f32; src_row];
+        let mut exact = vec![0f32; dst_row];
+        // Errors carried into current and next row, padded by a pixel on both sides
+        let mut current = vec![0f32; dst_row + 2 * channels];
+        let mut next = vec![0f32; dst_row + 2 * channels];
+        for (src, dst) in src.chunks(src_stride).zip(dst.chunks_mut(dst_stride)) {
+            for (dst, &src) in src_f32.iter_mut().zip(src[..src_row].iter()) {
+                *dst = src.as_() * scale;
+            }
+            self.exact.transform(&src_f32, &mut exact)?;
+            for (i, (dst, &exact)) in dst[..dst_row].iter_mut().zip(exact.iter()).enumerate() {
+                let e = i + channels;
+                let v = exact.max(0.).min(1.) * self.max_value + current[e];
+                let quantized = v.round().max(0.).min(self.max_value);
+                *dst = quantized.as_();
+                let error = v - quantized;
+                current[e + channels] += error * (7. / 16.);
+                next[e - channels] += error * (3. / 16.);
+                next[e] += error * (5. / 16.);
+                next[e + channels] += error * (1. / 16.);
+            }
+            std::mem::swap(&mut current, &mut next);
+            next.fill(0.);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "transforms")]
+impl<T: Copy + Default + Send + Sync + AsPrimitive<f32>> TransformExecutor<T>
+    for TransformLayoutsExecutor<T>
+where
+    f32: AsPrimitive<T>,
+{
+    fn transform(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
+        self.executor.transform(src, dst)
+    }
+
+    fn transform_in_place(&self, buf: &mut [T]) -> Result<(), CmsError> {
+        self.executor.transform_in_place(buf)
+    }
+
+    fn transform_strided(
+        &self,
+        src: &[T],
+        src_stride: usize,
+        dst: &mut [T],
+        dst_stride: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<(), CmsError> {
+        let src_row = width * self.src_channels;
+        let dst_row = width * self.dst_channels;
+        if src_stride < src_row || dst_stride < dst_row {
+            return Err(CmsError::InvalidStride);
+        }
+        if height == 0 {
+            return Ok(());
+        }
+        // Last row doesn't need padding
+        if src.len() < (height - 1) * src_stride + src_row
+            || dst.len() < (height - 1) * dst_stride + dst_row
+        {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        if let Some(diffusion) = &self.diffusion {
+            return diffusion.transform_rows(
+                &src[..(height - 1) * src_stride + src_row],
+                src_stride,
+                src_row,
+                &mut dst[..(height - 1) * dst_stride + dst_row],
+                dst_stride,
+                dst_row,
+                self.dst_channels,
+            );
+        }
+        for y in 0..height {
+            let src = &src[y * src_stride..y * src_stride + src_row];
+            let dst = &mut dst[y * dst_stride..y * dst_stride + dst_row];
+            self.executor.transform(src, dst)?;
+        }
+        Ok(())
+    }
+
+    fn transform_pixel(&self, src: &[T]) -> Result<[T; 4], CmsError> {
+        if src.len() != self.src_channels {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        let mut dst = [T::default(); 4];
+        self.executor
+            .transform(src, &mut dst[..self.dst_channels])?;
+        Ok(dst)
+    }
+
+    #[cfg(feature = "rayon")]
+    fn transform_parallel(&self, src: &[T], dst: &mut [T]) -> Result<(), CmsError> {
+        use rayon::prelude::*;
+        // Enough pixels for a task to outweigh scheduling
+        const CHUNK_PIXELS: usize = 4096;
+        if src.len() % self.src_channels != 0 || dst.len() % self.dst_channels != 0 {
+            return Err(CmsError::LaneMultipleOfChannels);
+        }
+        if src.len() / self.src_channels != dst.len() / self.dst_channels {
+            return Err(CmsError::LaneSizeMismatch);
+        }
+        src.par_chunks(CHUNK_PIXELS * self.src_channels)
+            .zip(dst.par_chunks_mut(CHUNK_PIXELS * self.dst_channels))
+            .try_for_each(|(src, dst)| self.executor.transform(src, dst))
+    }
+
+    fn warmup(&self) {
+        self.executor.warmup();
+    }
+}
+
+/// Reads one value per 4 KiB page of `data` into a black box
+#[cfg(feature = "transforms")]
+pub(crate) fn touch_pages<V: Copy>(data: &[V]) {
+    const PAGE_SIZE: usize = 4096;
+    let step = (PAGE_SIZE / size_of::<V>().max(1)).max(1);
+    for &v in data.iter().step_by(step) {
+        core::hint::black_box(v);
+    }
+    if let Some(&v) = data.last() {
+        core::hint::black_box(v);
+    }
+}
+
+/// Checks if the last channel of layouts in `color_space` may be alpha,
+/// fourth channel of CMYK and similar spaces is a color one.
+#[cfg(feature = "transforms")]
+fn has_alpha_channel(color_space: DataColorSpace) -> bool {
+    matches!(color_space, DataColorSpace::Rgb | DataColorSpace::Gray)
+}
+
+/// Transformation executor that also reports out-of-gamut pixels
+pub trait TransformMaskExecutor<V: Copy + Default> {
+    /// Transforms `src` into `dst` and writes one byte per pixel into `mask`,
+    /// 255 if the pixel is out of destination gamut and 0 otherwise.
+    fn transform(&self, src: &[V], dst: &mut [V], mask: &mut [u8]) -> Result<(), CmsError>;
+}
+
+/// Transformation executor that writes samples of other bit depth than it reads
+pub trait TransformDepthExecutor<S: Copy + Default, D: Copy + Default> {
+    /// Count of samples always must match, as in [TransformExecutor::transform].
+    fn transform(&self, src: &[S], dst: &mut [D]) -> Result<(), CmsError>;
 }
 
 /// Helper for intermediate transformation stages
-pub trait Stage {
+#[cfg(feature = "transforms")]
+pub(crate) trait Stage {
     fn transform(&self, src: &[f32], dst: &mut [f32]) -> Result<(), CmsError>;
 }
 
-/// Helper for intermediate transformation stages
+/// Operation made in place on interleaved `f32` values.
+///
+/// Besides internal use, implementations can be run inside a transform,
+/// see [crate::ColorProfile::create_transform_with_stage_f32].
 pub trait InPlaceStage {
+    /// Processes `dst`, its length is a multiple of channels count of the stage input.
     fn transform(&self, dst: &mut [f32]) -> Result<(), CmsError>;
 }
 
 /// Declares additional transformation options
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
 pub struct TransformOptions {
+    /// Rendering intent used to pick LUT tags of LUT based profiles.
+    ///
+    /// If profile doesn't carry tags for requested intent, perceptual tags
+    /// (`A2B0`, `B2A0`) are used instead, as ICC specification prescribes.
+    /// Matrix-shaper transforms run perceptual and saturation intents as relative
+    /// colorimetric, see [ColorProfile::effective_rendering_intent].
     pub rendering_intent: RenderingIntent,
     /// If set it will try to use Transfer Characteristics from CICP
     /// on transform. This might be more precise and faster.
@@ -69,9 +340,125 @@ pub struct TransformOptions {
     pub prefer_fixed_point: bool,
     /// Interpolation method for 3D LUT
     pub interpolation_method: InterpolationMethod,
+    /// If set, destination RGB is converted to full range YCbCr with the given matrix
+    /// before it is written, as JPEG encoders expect.
+    ///
+    /// Conversion is made on gamma encoded values, Y, Cb, Cr replaces R, G, B channels,
+    /// alpha is kept as is. Destination must be RGB.
+    pub ycbcr_output: Option<YCbCrMatrix>,
+    /// Adjusts system gamma for a different viewing surround of destination,
+    /// applied in linear light between source linearization and destination encoding.
+    ///
+    /// Supported by matrix-shaper and gray transforms only.
+    pub surround_compensation: Option<SurroundCompensation>,
+    /// Handling of values out of [0, 1] after adaptation matrix and in encoded result.
+    ///
+    /// Integer destinations, LUT based and YCbCr output transforms always clamp,
+    /// see [ColorProfile::effective_clamp_policy_f32].
+    pub clamp_policy: ClampPolicy,
+    /// Builds destination gamma tables of 16-bit matrix-shaper transforms in parts on first use
+    /// instead of all at once, making transform creation cheaper.
+    ///
+    /// Results are identical to eager tables. Worth it when transform is created often
+    /// and used for a few pixels only, otherwise eager tables are as fast or faster.
+    pub lazy_gamma_tables: bool,
+    /// Gray to gray transforms between bit depths treat gray as alpha or mask,
+    /// tone curves are not applied and only depth is changed.
+    pub treat_gray_as_alpha: bool,
+    /// Chromatic adaptation transform between media white points of matrix-shaper profiles,
+    /// see [ColorProfile::transform_matrix_with_adaptation].
+    pub chromatic_adaptation: ChromaticAdaptation,
+    /// Dithering of 8 to 16 bit integer output, floating point transforms ignore it.
+    ///
+    /// [DitherMode::ErrorDiffusion] needs row geometry, so it is applied only by
+    /// [TransformExecutor::transform_strided], [TransformExecutor::transform] never dithers.
+    pub dither: DitherMode,
+    /// Largest CIE ΔE*ab a color may move in B2A → A2B round trip through destination
+    /// tables and still be in gamut, in tenths of ΔE: 20 means ΔE 2.0.
+    ///
+    /// Fixed point keeps options `Eq` and `Hash`. Gamut checks use it for LUT based
+    /// destinations without `gamt` tag, 0 disables the fallback.
+    pub gamut_round_trip_delta_e: u16,
+    /// Applies `vcgt` ramp of destination display profile after destination encoding,
+    /// so output matches what display shows once OS has loaded the ramp.
+    ///
+    /// Destination must be RGB, profiles without `vcgt` tag are not affected.
+    pub apply_vcgt: bool,
+    /// Replicates numeric behavior of another CMS, see [CompatibilityMode].
+    pub compatibility_mode: CompatibilityMode,
+    /// Whether color channels of RGB and gray layouts with alpha are multiplied by alpha.
+    pub alpha_mode: AlphaMode,
+    /// Makes output independent of CPU the transform runs on.
+    ///
+    /// SIMD kernels differ in rounding, AVX2 ones fuse multiply-add while SSE and scalar
+    /// ones do not, so the same build may produce values differing by one code between machines.
+    /// This option forces the portable scalar kernels, trading speed for bit-identical
+    /// results on every machine running the same build.
+    ///
+    /// Scalar kernels fuse multiply-add only when the build enables it statically,
+    /// as AArch64 and x86 builds with `fma` target feature do, so results may still
+    /// differ between builds for different targets.
+    pub deterministic: bool,
     // pub black_point_compensation: bool,
 }
 
+/// Relation of color channels to alpha channel
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default)]
+pub enum AlphaMode {
+    /// Color channels are independent of alpha
+    #[default]
+    Straight,
+    /// Color channels are multiplied by alpha.
+    ///
+    /// Color is divided by alpha before the transform and multiplied by alpha after it,
+    /// so edges keep their color. Color of fully transparent pixels becomes zero.
+    /// Destination without alpha receives straight color.
+    Premultiplied,
+}
+
+/// Numeric behavior transforms replicate
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default)]
+pub enum CompatibilityMode {
+    /// Tables, rounding and intents of this crate
+    #[default]
+    Native,
+    /// Pixel parity with qcms having precached output profile, as Firefox uses it.
+    ///
+    /// 8 bit matrix-shaper RGB transforms sample source curves into 256 entries and
+    /// invert destination curves into 8192 entries output table the way qcms does,
+    /// combined colorant matrix is computed in single precision without chromatic adaptation,
+    /// linear values are clamped to `[0, 8191/8192]` and rounded into the table as
+    /// qcms SIMD kernels do. CICP transfer is not used.
+    ///
+    /// Rendering intent is ignored, LUT based profiles always use perceptual tables
+    /// as in qcms. Transforms of other bit depths are not affected by the mode.
+    Qcms,
+}
+
+/// Dithering of quantized integer output
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default)]
+pub enum DitherMode {
+    /// Values are rounded to the nearest code
+    #[default]
+    None,
+    /// Floyd–Steinberg error diffusion, quantization error is carried to the right
+    /// and to the next row, so the average of a region matches exact values.
+    ///
+    /// Each value stays within 1 code of the exact one and results are deterministic.
+    /// Useful for linear-light 8-bit outputs which otherwise band in darks.
+    ErrorDiffusion,
+}
+
+/// Handling of destination values out of [0, 1] range
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default)]
+pub enum ClampPolicy {
+    /// Values are clamped into [0, 1]
+    ClampToRange,
+    /// Out of range values are kept as is, for scene-referred floating point output
+    #[default]
+    PreserveExtended,
+}
+
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default)]
 /// Defines the interpolation method.
 ///
@@ -92,6 +479,31 @@ pub enum InterpolationMethod {
     Linear,
 }
 
+/// Defines YCbCr matrix for [TransformOptions::ycbcr_output].
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum YCbCrMatrix {
+    /// ITU-R BT.601, used by JPEG
+    Bt601,
+    /// ITU-R BT.709
+    Bt709,
+}
+
+impl TransformOptions {
+    /// Drops what [TransformOptions::compatibility_mode] doesn't support.
+    #[cfg(feature = "transforms")]
+    fn with_compatibility_applied(self) -> TransformOptions {
+        match self.compatibility_mode {
+            CompatibilityMode::Native => self,
+            // qcms knows neither intents nor CICP
+            CompatibilityMode::Qcms => TransformOptions {
+                rendering_intent: RenderingIntent::Perceptual,
+                allow_use_cicp_transfer: false,
+                ..self
+            },
+        }
+    }
+}
+
 impl Default for TransformOptions {
     fn default() -> Self {
         Self {
@@ -99,6 +511,18 @@ impl Default for TransformOptions {
             allow_use_cicp_transfer: true,
             prefer_fixed_point: true,
             interpolation_method: InterpolationMethod::default(),
+            ycbcr_output: None,
+            surround_compensation: None,
+            clamp_policy: ClampPolicy::default(),
+            lazy_gamma_tables: false,
+            treat_gray_as_alpha: false,
+            chromatic_adaptation: ChromaticAdaptation::default(),
+            dither: DitherMode::None,
+            gamut_round_trip_delta_e: 20,
+            apply_vcgt: false,
+            compatibility_mode: CompatibilityMode::Native,
+            alpha_mode: AlphaMode::Straight,
+            deterministic: false,
             // black_point_compensation: false,
         }
     }
@@ -108,60 +532,121 @@ pub type Transform8BitExecutor = dyn TransformExecutor<u8> + Send + Sync;
 pub type Transform16BitExecutor = dyn TransformExecutor<u16> + Send + Sync;
 pub type TransformF32BitExecutor = dyn TransformExecutor<f32> + Send + Sync;
 pub type TransformF64BitExecutor = dyn TransformExecutor<f64> + Send + Sync;
+pub type TransformMask8BitExecutor = dyn TransformMaskExecutor<u8> + Send + Sync;
+pub type Transform8BitTo16BitExecutor = dyn TransformDepthExecutor<u8, u16> + Send + Sync;
+pub type Transform16BitTo8BitExecutor = dyn TransformDepthExecutor<u16, u8> + Send + Sync;
 
 /// Layout declares a data layout.
 /// For RGB it shows also the channel order.
 /// To handle different data bit-depth appropriate executor must be used.
 /// Cmyk8 uses the same layout as Rgba8.
+/// Bgr, Bgra and alpha-first Argb, Abgr are accepted wherever RGB data is expected.
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub enum Layout {
     Rgb = 0,
     Rgba = 1,
     Gray = 2,
     GrayAlpha = 3,
+    Bgr = 4,
+    Bgra = 5,
+    Argb = 6,
+    Abgr = 7,
 }
 
 impl Layout {
-    /// Returns Red channel index
+    /// Returns Red channel index, panics on gray layouts, see [Layout::try_r_i].
     #[inline(always)]
     pub const fn r_i(self) -> usize {
-        match self {
-            Layout::Rgb => 0,
-            Layout::Rgba => 0,
-            Layout::Gray => unimplemented!(),
-            Layout::GrayAlpha => unimplemented!(),
+        match self.try_r_i() {
+            Some(i) => i,
+            None => unimplemented!(),
         }
     }
 
-    /// Returns Green channel index
+    /// Returns Green channel index, panics on gray layouts, see [Layout::try_g_i].
     #[inline(always)]
     pub const fn g_i(self) -> usize {
-        match self {
-            Layout::Rgb => 1,
-            Layout::Rgba => 1,
-            Layout::Gray => unimplemented!(),
-            Layout::GrayAlpha => unimplemented!(),
+        match self.try_g_i() {
+            Some(i) => i,
+            None => unimplemented!(),
         }
     }
 
-    /// Returns Blue channel index
+    /// Returns Blue channel index, panics on gray layouts, see [Layout::try_b_i].
     #[inline(always)]
     pub const fn b_i(self) -> usize {
-        match self {
-            Layout::Rgb => 2,
-            Layout::Rgba => 2,
-            Layout::Gray => unimplemented!(),
-            Layout::GrayAlpha => unimplemented!(),
+        match self.try_b_i() {
+            Some(i) => i,
+            None => unimplemented!(),
         }
     }
 
+    /// Returns Alpha channel index, panics on layouts without alpha, see [Layout::try_a_i].
     #[inline(always)]
     pub const fn a_i(self) -> usize {
+        match self.try_a_i() {
+            Some(i) => i,
+            None => unimplemented!(),
+        }
+    }
+
+    /// Returns Red channel index, `None` for gray layouts
+    #[inline]
+    pub const fn try_r_i(self) -> Option<usize> {
         match self {
-            Layout::Rgb => unimplemented!(),
-            Layout::Rgba => 3,
-            Layout::Gray => unimplemented!(),
-            Layout::GrayAlpha => 1,
+            Layout::Rgb => Some(0),
+            Layout::Rgba => Some(0),
+            Layout::Gray => None,
+            Layout::GrayAlpha => None,
+            Layout::Bgr => Some(2),
+            Layout::Bgra => Some(2),
+            Layout::Argb => Some(1),
+            Layout::Abgr => Some(3),
+        }
+    }
+
+    /// Returns Green channel index, `None` for gray layouts
+    #[inline]
+    pub const fn try_g_i(self) -> Option<usize> {
+        match self {
+            Layout::Rgb => Some(1),
+            Layout::Rgba => Some(1),
+            Layout::Gray => None,
+            Layout::GrayAlpha => None,
+            Layout::Bgr => Some(1),
+            Layout::Bgra => Some(1),
+            Layout::Argb => Some(2),
+            Layout::Abgr => Some(2),
+        }
+    }
+
+    /// Returns Blue channel index, `None` for gray layouts
+    #[inline]
+    pub const fn try_b_i(self) -> Option<usize> {
+        match self {
+            Layout::Rgb => Some(2),
+            Layout::Rgba => Some(2),
+            Layout::Gray => None,
+            Layout::GrayAlpha => None,
+            Layout::Bgr => Some(0),
+            Layout::Bgra => Some(0),
+            Layout::Argb => Some(3),
+            Layout::Abgr => Some(1),
+        }
+    }
+
+    /// Returns Alpha channel index, `None` for layouts without alpha
+    #[inline]
+    pub const fn try_a_i(self) -> Option<usize> {
+        match self {
+            Layout::Rgb => None,
+            Layout::Rgba => Some(3),
+            Layout::Gray => None,
+            Layout::GrayAlpha => Some(1),
+            Layout::Bgr => None,
+            Layout::Bgra => Some(3),
+            Layout::Argb => Some(0),
+            Layout::Abgr => Some(0),
         }
     }
 
@@ -172,6 +657,10 @@ impl Layout {
             Layout::Rgba => true,
             Layout::Gray => false,
             Layout::GrayAlpha => true,
+            Layout::Bgr => false,
+            Layout::Bgra => true,
+            Layout::Argb => true,
+            Layout::Abgr => true,
         }
     }
 
@@ -182,34 +671,57 @@ impl Layout {
             Layout::Rgba => 4,
             Layout::Gray => 1,
             Layout::GrayAlpha => 2,
+            Layout::Bgr => 3,
+            Layout::Bgra => 4,
+            Layout::Argb => 4,
+            Layout::Abgr => 4,
         }
     }
 }
 
+/// Panics on values out of range, see [Layout::try_from_u8] for the fallible conversion.
 impl From<u8> for Layout {
     fn from(value: u8) -> Self {
-        match value {
-            0 => Layout::Rgb,
-            1 => Layout::Rgba,
-            2 => Layout::Gray,
-            3 => Layout::GrayAlpha,
-            _ => unimplemented!(),
-        }
+        Layout::resolve(value)
     }
 }
 
 impl Layout {
-    pub const fn resolve(value: u8) -> Self {
-        match value {
+    /// Converts layout discriminant, as it comes over FFI, into [Layout].
+    ///
+    /// Returns [CmsError::InvalidLayout] for values out of range.
+    pub const fn try_from_u8(value: u8) -> Result<Self, CmsError> {
+        Ok(match value {
             0 => Layout::Rgb,
             1 => Layout::Rgba,
             2 => Layout::Gray,
             3 => Layout::GrayAlpha,
-            _ => unimplemented!(),
+            4 => Layout::Bgr,
+            5 => Layout::Bgra,
+            6 => Layout::Argb,
+            7 => Layout::Abgr,
+            _ => return Err(CmsError::InvalidLayout),
+        })
+    }
+
+    /// Same as [Layout::try_from_u8] usable for const generics, panics on values out of range.
+    pub const fn resolve(value: u8) -> Self {
+        match Layout::try_from_u8(value) {
+            Ok(layout) => layout,
+            Err(_) => panic!("layout value out of range"),
         }
     }
 }
 
+/// Resolved way to build a transform
+#[cfg(feature = "transforms")]
+pub(crate) enum TransformPlan<T: Copy + Default> {
+    /// Executor doesn't need any sampling
+    Ready(Box<dyn TransformExecutor<T> + Send + Sync>),
+    /// Executor needs LUT grid to be sampled first
+    Lut(Box<dyn LutSampler<T>>),
+}
+
 #[doc(hidden)]
 pub trait PointeeSizeExpressible {
     fn _as_usize(self) -> usize;
@@ -267,6 +779,7 @@ impl PointeeSizeExpressible for f64 {
     const NOT_FINITE_LINEAR_TABLE_SIZE: usize = 1 << 16;
 }
 
+#[cfg(feature = "transforms")]
 impl ColorProfile {
     pub(crate) fn has_full_colors_triplet(&self) -> bool {
         self.red_colorant != Xyz::default()
@@ -291,6 +804,7 @@ impl ColorProfile {
 
     /// Creates transform between source and destination profile
     /// Use for 12 bit-depth data bit-depth only.
+    /// Color values above 4095 saturate to 4095, alpha is passed through as is.
     pub fn create_transform_12bit(
         &self,
         src_layout: Layout,
@@ -303,6 +817,7 @@ impl ColorProfile {
 
     /// Creates transform between source and destination profile
     /// Use for 10 bit-depth data bit-depth only.
+    /// Color values above 1023 saturate to 1023, alpha is passed through as is.
     pub fn create_transform_10bit(
         &self,
         src_layout: Layout,
@@ -315,8 +830,12 @@ impl ColorProfile {
 
     /// Creates transform between source and destination profile
     /// Data has to be normalized into [0, 1] range.
+    ///
+    /// Matrix-shaper RGB transforms evaluate tone curves directly instead of tables,
+    /// values out of [0, 1] pass through unless [TransformOptions::clamp_policy] is
+    /// [ClampPolicy::ClampToRange].
     /// ICC profiles and LUT tables do not exist in infinite precision.
-    /// Thus, this implementation considers `f32` as 14-bit values.
+    /// Thus, LUT based transforms consider `f32` as 14-bit values.
     pub fn create_transform_f32(
         &self,
         src_layout: Layout,
@@ -366,62 +885,514 @@ impl ColorProfile {
         f32: AsPrimitive<T>,
         u32: AsPrimitive<T>,
     {
-        if self.color_space == DataColorSpace::Rgb
+        with_deterministic_kernel(options.deterministic, || {
+            self.create_transform_nbit_impl::<T, BIT_DEPTH, LINEAR_CAP, GAMMA_CAP>(
+                src_layout, dst_pr, dst_layout, options,
+            )
+        })
+    }
+
+    fn create_transform_nbit_impl<
+        T: Copy
+            + Default
+            + AsPrimitive<usize>
+            + PointeeSizeExpressible
+            + Send
+            + Sync
+            + AsPrimitive<f32>
+            + CompressForLut
+            + RgbXyzFactory<T>
+            + GammaLutInterpolate,
+        const BIT_DEPTH: usize,
+        const LINEAR_CAP: usize,
+        const GAMMA_CAP: usize,
+    >(
+        &self,
+        src_layout: Layout,
+        dst_pr: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+    ) -> Result<Box<dyn TransformExecutor<T> + Send + Sync>, CmsError>
+    where
+        f32: AsPrimitive<T>,
+        u32: AsPrimitive<T>,
+    {
+        let executor = match self.plan_transform_nbit::<T, BIT_DEPTH, LINEAR_CAP, GAMMA_CAP>(
+            src_layout, dst_pr, dst_layout, options,
+        )? {
+            TransformPlan::Ready(executor) => executor,
+            TransformPlan::Lut(sampler) => make_lut_transform(sampler)?,
+        };
+        let diffusion = if T::FINITE && options.dither == DitherMode::ErrorDiffusion {
+            let exact = self.create_transform_f32(
+                src_layout,
+                dst_pr,
+                dst_layout,
+                TransformOptions {
+                    dither: DitherMode::None,
+                    clamp_policy: ClampPolicy::ClampToRange,
+                    ..options
+                },
+            )?;
+            Some(ErrorDiffusion {
+                exact,
+                max_value: ((1u32 << BIT_DEPTH) - 1) as f32,
+            })
+        } else {
+            None
+        };
+        Ok(Box::new(TransformLayoutsExecutor {
+            executor,
+            src_channels: src_layout.channels(),
+            dst_channels: dst_layout.channels(),
+            diffusion,
+        }))
+    }
+
+    /// Builds linearization, gamma tables and matrix for matrix-shaper RGB to RGB transform.
+    pub(crate) fn make_rgb_profile_transform<
+        T: Copy + Default + PointeeSizeExpressible + AsPrimitive<f32> + GammaLutInterpolate,
+        const BIT_DEPTH: usize,
+        const LINEAR_CAP: usize,
+        const GAMMA_CAP: usize,
+    >(
+        &self,
+        dst_pr: &ColorProfile,
+        options: TransformOptions,
+    ) -> Result<TransformProfileRgb<T, LINEAR_CAP>, CmsError>
+    where
+        f32: AsPrimitive<T>,
+        u32: AsPrimitive<T>,
+    {
+        let transform = self.transform_matrix_with_adaptation(
+            dst_pr,
+            options.rendering_intent,
+            options.chromatic_adaptation,
+        );
+
+        let mut lin_r = self
+            .build_r_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(options.allow_use_cicp_transfer)?;
+        let mut lin_g = self
+            .build_g_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(options.allow_use_cicp_transfer)?;
+        let mut lin_b = self
+            .build_b_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(options.allow_use_cicp_transfer)?;
+        if let Some(surround) = options.surround_compensation {
+            surround.apply_to_table(&mut lin_r);
+            surround.apply_to_table(&mut lin_g);
+            surround.apply_to_table(&mut lin_b);
+        }
+
+        let gamma_r = dst_pr.build_gamma_table::<T, 65536, GAMMA_CAP, BIT_DEPTH>(
+            &dst_pr.red_trc,
+            options.allow_use_cicp_transfer,
+        )?;
+        let gamma_g = dst_pr.build_gamma_table::<T, 65536, GAMMA_CAP, BIT_DEPTH>(
+            &dst_pr.green_trc,
+            options.allow_use_cicp_transfer,
+        )?;
+        let gamma_b = dst_pr.build_gamma_table::<T, 65536, GAMMA_CAP, BIT_DEPTH>(
+            &dst_pr.blue_trc,
+            options.allow_use_cicp_transfer,
+        )?;
+
+        Ok(TransformProfileRgb {
+            r_linear: lin_r,
+            g_linear: lin_g,
+            b_linear: lin_b,
+            r_gamma: gamma_r,
+            g_gamma: gamma_g,
+            b_gamma: gamma_b,
+            adaptation_matrix: transform,
+        })
+    }
+
+    /// Builds linearization tables and on demand gamma tables for matrix-shaper RGB to RGB transform.
+    pub(crate) fn make_rgb_profile_transform_lazy<
+        T: Copy + Default + PointeeSizeExpressible + AsPrimitive<f32> + GammaLutInterpolate,
+        const BIT_DEPTH: usize,
+        const LINEAR_CAP: usize,
+        const GAMMA_CAP: usize,
+    >(
+        &self,
+        dst_pr: &ColorProfile,
+        options: TransformOptions,
+    ) -> Result<TransformProfileRgbLazy<T, LINEAR_CAP, GAMMA_CAP, BIT_DEPTH>, CmsError>
+    where
+        f32: AsPrimitive<T>,
+        u32: AsPrimitive<T>,
+    {
+        let use_cicp = options.allow_use_cicp_transfer;
+        let mut lin_r = self.build_r_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(use_cicp)?;
+        let mut lin_g = self.build_g_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(use_cicp)?;
+        let mut lin_b = self.build_b_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(use_cicp)?;
+        if let Some(surround) = options.surround_compensation {
+            surround.apply_to_table(&mut lin_r);
+            surround.apply_to_table(&mut lin_g);
+            surround.apply_to_table(&mut lin_b);
+        }
+
+        let make_gamma =
+            |trc: &Option<ToneReprCurve>| match dst_pr.gamma_table_source(trc, use_cicp) {
+                Some(source) => Ok(LazyGammaTable::new(source)),
+                None => dst_pr
+                    .build_gamma_table::<T, 65536, GAMMA_CAP, BIT_DEPTH>(trc, use_cicp)
+                    .map(|table| LazyGammaTable::from_table(table.as_slice())),
+            };
+
+        Ok(TransformProfileRgbLazy {
+            r_linear: lin_r,
+            g_linear: lin_g,
+            b_linear: lin_b,
+            r_gamma: make_gamma(&dst_pr.red_trc)?,
+            g_gamma: make_gamma(&dst_pr.green_trc)?,
+            b_gamma: make_gamma(&dst_pr.blue_trc)?,
+            adaptation_matrix: self
+                .transform_matrix_with_adaptation(
+                    dst_pr,
+                    options.rendering_intent,
+                    options.chromatic_adaptation,
+                )
+                .unwrap_or(Matrix3f::IDENTITY),
+        })
+    }
+
+    /// Builds tone curve evaluators and matrix for floating point matrix-shaper RGB to RGB transform.
+    pub(crate) fn make_rgb_profile_transform_f32(
+        &self,
+        dst_pr: &ColorProfile,
+        options: TransformOptions,
+    ) -> Result<TransformProfileRgbFloat, CmsError> {
+        let use_cicp = options.allow_use_cicp_transfer;
+        Ok(TransformProfileRgbFloat {
+            r_linear: self.linearize_evaluator(&self.red_trc, use_cicp)?,
+            g_linear: self.linearize_evaluator(&self.green_trc, use_cicp)?,
+            b_linear: self.linearize_evaluator(&self.blue_trc, use_cicp)?,
+            r_gamma: dst_pr.gamma_evaluator(&dst_pr.red_trc, use_cicp)?,
+            g_gamma: dst_pr.gamma_evaluator(&dst_pr.green_trc, use_cicp)?,
+            b_gamma: dst_pr.gamma_evaluator(&dst_pr.blue_trc, use_cicp)?,
+            adaptation_matrix: self
+                .transform_matrix_with_adaptation(
+                    dst_pr,
+                    options.rendering_intent,
+                    options.chromatic_adaptation,
+                )
+                .unwrap_or(Matrix3f::IDENTITY),
+            surround_exponent: options
+                .surround_compensation
+                .filter(|x| !x.is_identity())
+                .map(|x| x.exponent()),
+            clamp_policy: self.effective_clamp_policy_f32(dst_pr, options),
+        })
+    }
+
+    /// Checks if transform into `dst_pr` is RGB matrix-shaper one.
+    pub(crate) fn is_matrix_shaper_pair(&self, dst_pr: &ColorProfile) -> bool {
+        self.color_space == DataColorSpace::Rgb
             && dst_pr.pcs == DataColorSpace::Xyz
             && dst_pr.color_space == DataColorSpace::Rgb
             && self.pcs == DataColorSpace::Xyz
             && self.has_full_colors_triplet()
             && dst_pr.has_full_colors_triplet()
-        {
-            if src_layout == Layout::Gray || src_layout == Layout::GrayAlpha {
-                return Err(CmsError::InvalidLayout);
-            }
-            if dst_layout == Layout::Gray || dst_layout == Layout::GrayAlpha {
-                return Err(CmsError::InvalidLayout);
-            }
-            let transform = self.transform_matrix(dst_pr);
-
-            let lin_r = self.build_r_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(
-                options.allow_use_cicp_transfer,
-            )?;
-            let lin_g = self.build_g_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(
-                options.allow_use_cicp_transfer,
-            )?;
-            let lin_b = self.build_b_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(
-                options.allow_use_cicp_transfer,
-            )?;
+    }
 
-            let gamma_r = dst_pr.build_gamma_table::<T, 65536, GAMMA_CAP, BIT_DEPTH>(
-                &self.red_trc,
-                options.allow_use_cicp_transfer,
-            )?;
-            let gamma_g = dst_pr.build_gamma_table::<T, 65536, GAMMA_CAP, BIT_DEPTH>(
-                &self.green_trc,
-                options.allow_use_cicp_transfer,
-            )?;
-            let gamma_b = dst_pr.build_gamma_table::<T, 65536, GAMMA_CAP, BIT_DEPTH>(
-                &self.blue_trc,
-                options.allow_use_cicp_transfer,
-            )?;
+    /// Returns clamp policy `f32` transform into `dst_pr` made with `options` applies.
+    ///
+    /// Only matrix-shaper RGB transforms evaluate curves out of [0, 1], LUT based,
+    /// YCbCr output and all integer transforms use [ClampPolicy::ClampToRange].
+    pub fn effective_clamp_policy_f32(
+        &self,
+        dst_pr: &ColorProfile,
+        options: TransformOptions,
+    ) -> ClampPolicy {
+        if options.ycbcr_output.is_none() && self.is_matrix_shaper_pair(dst_pr) {
+            options.clamp_policy
+        } else {
+            ClampPolicy::ClampToRange
+        }
+    }
 
-            let profile_transform = TransformProfileRgb {
-                r_linear: lin_r,
-                g_linear: lin_g,
-                b_linear: lin_b,
-                r_gamma: gamma_r,
-                g_gamma: gamma_g,
-                b_gamma: gamma_b,
-                adaptation_matrix: transform,
+    /// Returns rendering intent transform into `dst_pr` made with `options` executes.
+    ///
+    /// Matrix-shaper profiles have no perceptual or saturation tables, so as ICC requires
+    /// those intents run as [RenderingIntent::RelativeColorimetric] between two of them,
+    /// while [RenderingIntent::AbsoluteColorimetric] scales by media white points.
+    /// Gray tone curve transforms don't adapt white at all and are always relative.
+    /// LUT based transforms report requested intent, [TransformOptions::clamp_policy]
+    /// is independent of intent in every case.
+    pub fn effective_rendering_intent(
+        &self,
+        dst_pr: &ColorProfile,
+        options: TransformOptions,
+    ) -> RenderingIntent {
+        let intent = options.rendering_intent;
+        if self.is_matrix_shaper_pair(dst_pr) {
+            return match intent {
+                RenderingIntent::AbsoluteColorimetric => intent,
+                RenderingIntent::Perceptual
+                | RenderingIntent::RelativeColorimetric
+                | RenderingIntent::Saturation => RenderingIntent::RelativeColorimetric,
             };
-
-            return T::make_transform::<LINEAR_CAP, GAMMA_CAP, BIT_DEPTH>(
-                src_layout,
-                dst_layout,
-                profile_transform,
-                options,
+        }
+        let gray_trc = self.pcs == DataColorSpace::Xyz
+            && dst_pr.pcs == DataColorSpace::Xyz
+            && matches!(
+                (self.color_space, dst_pr.color_space),
+                (
+                    DataColorSpace::Gray,
+                    DataColorSpace::Rgb | DataColorSpace::Gray
+                ) | (DataColorSpace::Rgb, DataColorSpace::Gray)
             );
-        } else if self.color_space == DataColorSpace::Gray
-            && (dst_pr.color_space == DataColorSpace::Rgb
+        if gray_trc {
+            RenderingIntent::RelativeColorimetric
+        } else {
+            intent
+        }
+    }
+
+    /// Returns whether integer transform into `dst_pr` made with `options` skips matrix stage.
+    ///
+    /// Matrix-shaper profiles sharing primaries and white point differ by tone curves only,
+    /// then source linearization and destination curves are composed into one table per channel.
+    /// `bit_depth` is the one of [ColorProfile::create_transform_8bit] and its 10, 12 and 16 bit
+    /// siblings. Floating point transforms, qcms compatibility at 8 bit and lazy gamma tables
+    /// at 16 bit always run the matrix.
+    pub fn effective_trc_only(
+        &self,
+        dst_pr: &ColorProfile,
+        bit_depth: usize,
+        options: TransformOptions,
+    ) -> bool {
+        let options = options.with_compatibility_applied();
+        let table_path = match bit_depth {
+            8 => options.compatibility_mode == CompatibilityMode::Native,
+            10 | 12 => true,
+            16 => !options.lazy_gamma_tables,
+            _ => false,
+        };
+        table_path
+            && self.is_matrix_shaper_pair(dst_pr)
+            && is_trc_only_transform(self.transform_matrix_with_adaptation(
+                dst_pr,
+                options.rendering_intent,
+                options.chromatic_adaptation,
+            ))
+    }
+
+    /// Returns whether integer transform into `dst_pr` made with `options` runs
+    /// matrix in Q4.12 fixed point, as [TransformOptions::prefer_fixed_point] requests.
+    ///
+    /// Fixed point is used at 8 bit, and at 10 bit where a SIMD kernel is available,
+    /// only when the matrix has enough headroom: scaled coefficients and linearized values
+    /// must fit `i16` and worst-case row sums `i32`, otherwise transform falls back to floating
+    /// point. As nothing can wrap, fixed point clamps where float path does and has no clipping
+    /// of its own to report, see [ColorProfile::create_transform_with_mask] for out of gamut colors.
+    /// `bit_depth` is the one of [ColorProfile::create_transform_8bit] and its siblings.
+    pub fn effective_fixed_point(
+        &self,
+        dst_pr: &ColorProfile,
+        bit_depth: usize,
+        options: TransformOptions,
+    ) -> bool {
+        if !options.prefer_fixed_point
+            || !self.is_matrix_shaper_pair(dst_pr)
+            || self.effective_trc_only(dst_pr, bit_depth, options)
+        {
+            return false;
+        }
+        let options = options.with_compatibility_applied();
+        match bit_depth {
+            8 if options.compatibility_mode == CompatibilityMode::Native => self
+                .make_rgb_profile_transform::<u8, 8, 256, 4096>(dst_pr, options)
+                .is_ok_and(|x| x.fits_q4_n::<12, 4096, 8>()),
+            10 => {
+                with_deterministic_kernel(options.deterministic, has_q4_12_simd_kernel)
+                    && self
+                        .make_rgb_profile_transform::<u16, 10, 65536, 8192>(dst_pr, options)
+                        .is_ok_and(|x| x.fits_q4_n::<12, 8192, 10>())
+            }
+            _ => false,
+        }
+    }
+
+    /// Resolves the way transform is going to be made,
+    /// LUT based transforms are returned not sampled yet.
+    pub(crate) fn plan_transform_nbit<
+        T: Copy
+            + Default
+            + AsPrimitive<usize>
+            + PointeeSizeExpressible
+            + Send
+            + Sync
+            + AsPrimitive<f32>
+            + CompressForLut
+            + RgbXyzFactory<T>
+            + GammaLutInterpolate,
+        const BIT_DEPTH: usize,
+        const LINEAR_CAP: usize,
+        const GAMMA_CAP: usize,
+    >(
+        &self,
+        src_layout: Layout,
+        dst_pr: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+    ) -> Result<TransformPlan<T>, CmsError>
+    where
+        f32: AsPrimitive<T>,
+        u32: AsPrimitive<T>,
+    {
+        let plan = self.plan_device_transform_nbit::<T, BIT_DEPTH, LINEAR_CAP, GAMMA_CAP>(
+            src_layout, dst_pr, dst_layout, options,
+        )?;
+        self.finish_transform_plan::<T, BIT_DEPTH>(plan, src_layout, dst_pr, dst_layout, options)
+    }
+
+    /// Appends destination `vcgt`, YCbCr output and premultiplied alpha requested by `options`
+    /// to transform planned from this profile into `dst_pr`.
+    pub(crate) fn finish_transform_plan<
+        T: Copy
+            + Default
+            + AsPrimitive<usize>
+            + PointeeSizeExpressible
+            + Send
+            + Sync
+            + AsPrimitive<f32>
+            + 'static,
+        const BIT_DEPTH: usize,
+    >(
+        &self,
+        mut plan: TransformPlan<T>,
+        src_layout: Layout,
+        dst_pr: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+    ) -> Result<TransformPlan<T>, CmsError>
+    where
+        f32: AsPrimitive<T>,
+        u32: AsPrimitive<T>,
+    {
+        if let Some(vcgt) = dst_pr.vcgt.as_ref().filter(|_| options.apply_vcgt) {
+            if dst_pr.color_space != DataColorSpace::Rgb {
+                return Err(CmsError::UnsupportedProfileConnection);
+            }
+            plan = append_vcgt_epilogue::<T, BIT_DEPTH>(plan, src_layout, dst_layout, vcgt)?;
+        }
+        if let Some(matrix) = options.ycbcr_output {
+            if dst_pr.color_space != DataColorSpace::Rgb {
+                return Err(CmsError::UnsupportedProfileConnection);
+            }
+            plan = append_ycbcr_epilogue::<T, BIT_DEPTH>(plan, src_layout, dst_layout, matrix)?;
+        }
+        if options.alpha_mode == AlphaMode::Premultiplied {
+            plan = wrap_premultiplied_alpha::<T, BIT_DEPTH>(
+                plan,
+                src_layout,
+                has_alpha_channel(self.color_space),
+                dst_layout,
+                has_alpha_channel(dst_pr.color_space),
+            );
+        }
+        Ok(plan)
+    }
+
+    fn plan_device_transform_nbit<
+        T: Copy
+            + Default
+            + AsPrimitive<usize>
+            + PointeeSizeExpressible
+            + Send
+            + Sync
+            + AsPrimitive<f32>
+            + CompressForLut
+            + RgbXyzFactory<T>
+            + GammaLutInterpolate,
+        const BIT_DEPTH: usize,
+        const LINEAR_CAP: usize,
+        const GAMMA_CAP: usize,
+    >(
+        &self,
+        src_layout: Layout,
+        dst_pr: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+    ) -> Result<TransformPlan<T>, CmsError>
+    where
+        f32: AsPrimitive<T>,
+        u32: AsPrimitive<T>,
+    {
+        let options = options.with_compatibility_applied();
+        if self.is_matrix_shaper_pair(dst_pr) {
+            if src_layout == Layout::Gray || src_layout == Layout::GrayAlpha {
+                return Err(CmsError::InvalidLayout);
+            }
+            if dst_layout == Layout::Gray || dst_layout == Layout::GrayAlpha {
+                return Err(CmsError::InvalidLayout);
+            }
+            if options.compatibility_mode == CompatibilityMode::Qcms && T::FINITE && BIT_DEPTH == 8
+            {
+                let profile_transform = make_qcms_profile_transform::<T>(self, dst_pr)?;
+                return Ok(TransformPlan::Ready(make_rgb_xyz_rgb_transform::<
+                    T,
+                    256,
+                    QCMS_GAMMA_LUT,
+                    8,
+                >(
+                    src_layout,
+                    dst_layout,
+                    profile_transform,
+                )?));
+            }
+            if let Some(executor) =
+                T::make_evaluated_transform(self, dst_pr, src_layout, dst_layout, options)?
+            {
+                return Ok(TransformPlan::Ready(executor));
+            }
+            if options.lazy_gamma_tables && T::FINITE && GAMMA_CAP == 65536 {
+                let profile_transform = self
+                    .make_rgb_profile_transform_lazy::<T, BIT_DEPTH, LINEAR_CAP, GAMMA_CAP>(
+                        dst_pr, options,
+                    )?;
+                return Ok(TransformPlan::Ready(make_rgb_xyz_rgb_transform_lazy::<
+                    T,
+                    LINEAR_CAP,
+                    GAMMA_CAP,
+                    BIT_DEPTH,
+                >(
+                    src_layout,
+                    dst_layout,
+                    profile_transform,
+                )?));
+            }
+            let profile_transform = self
+                .make_rgb_profile_transform::<T, BIT_DEPTH, LINEAR_CAP, GAMMA_CAP>(
+                    dst_pr, options,
+                )?;
+
+            if T::FINITE && is_trc_only_transform(profile_transform.adaptation_matrix) {
+                return Ok(TransformPlan::Ready(make_rgb_trc_transform::<
+                    T,
+                    LINEAR_CAP,
+                    GAMMA_CAP,
+                    BIT_DEPTH,
+                >(
+                    src_layout,
+                    dst_layout,
+                    profile_transform,
+                )?));
+            }
+
+            return Ok(TransformPlan::Ready(T::make_transform::<
+                LINEAR_CAP,
+                GAMMA_CAP,
+                BIT_DEPTH,
+            >(
+                src_layout,
+                dst_layout,
+                profile_transform,
+                options,
+            )?));
+        } else if self.color_space == DataColorSpace::Gray
+            && (dst_pr.color_space == DataColorSpace::Rgb
                 || dst_pr.color_space == DataColorSpace::Gray)
             && self.pcs == DataColorSpace::Xyz
             && dst_pr.pcs == DataColorSpace::Xyz
@@ -429,18 +1400,29 @@ impl ColorProfile {
             if src_layout != Layout::GrayAlpha && src_layout != Layout::Gray {
                 return Err(CmsError::InvalidLayout);
             }
-            let gray_linear = self.build_gray_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>()?;
+            let mut gray_linear = self
+                .build_gray_linearize_table_cicp::<T, LINEAR_CAP, BIT_DEPTH>(
+                    options.allow_use_cicp_transfer,
+                )?;
+            if let Some(surround) = options.surround_compensation {
+                surround.apply_to_table(&mut gray_linear);
+            }
             let gray_gamma = dst_pr.build_gamma_table::<T, 65536, GAMMA_CAP, BIT_DEPTH>(
                 &self.gray_trc,
                 options.allow_use_cicp_transfer,
             )?;
 
-            return make_gray_to_x::<T, LINEAR_CAP, BIT_DEPTH, GAMMA_CAP>(
+            return Ok(TransformPlan::Ready(make_gray_to_x::<
+                T,
+                LINEAR_CAP,
+                BIT_DEPTH,
+                GAMMA_CAP,
+            >(
                 src_layout,
                 dst_layout,
                 gray_linear,
                 gray_gamma,
-            );
+            )?));
         } else if self.color_space == DataColorSpace::Rgb
             && dst_pr.color_space == DataColorSpace::Gray
             && dst_pr.pcs == DataColorSpace::Xyz
@@ -453,15 +1435,20 @@ impl ColorProfile {
                 return Err(CmsError::InvalidLayout);
             }
 
-            let lin_r = self.build_r_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(
+            let mut lin_r = self.build_r_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(
                 options.allow_use_cicp_transfer,
             )?;
-            let lin_g = self.build_g_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(
+            let mut lin_g = self.build_g_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(
                 options.allow_use_cicp_transfer,
             )?;
-            let lin_b = self.build_b_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(
+            let mut lin_b = self.build_b_linearize_table::<T, LINEAR_CAP, BIT_DEPTH>(
                 options.allow_use_cicp_transfer,
             )?;
+            if let Some(surround) = options.surround_compensation {
+                surround.apply_to_table(&mut lin_r);
+                surround.apply_to_table(&mut lin_g);
+                surround.apply_to_table(&mut lin_b);
+            }
             let gray_linear = dst_pr.build_gamma_table::<T, 65536, GAMMA_CAP, BIT_DEPTH>(
                 &dst_pr.gray_trc,
                 options.allow_use_cicp_transfer,
@@ -482,9 +1469,14 @@ impl ColorProfile {
                 gray_gamma: gray_linear,
             };
 
-            return Ok(make_rgb_to_gray::<T, LINEAR_CAP, BIT_DEPTH, GAMMA_CAP>(
+            return Ok(TransformPlan::Ready(make_rgb_to_gray::<
+                T,
+                LINEAR_CAP,
+                BIT_DEPTH,
+                GAMMA_CAP,
+            >(
                 src_layout, dst_layout, trc_box, vector,
-            ));
+            )));
         } else if (self.color_space == DataColorSpace::Cmyk
             || self.color_space == DataColorSpace::Rgb
             || self.color_space == DataColorSpace::Lab
@@ -494,19 +1486,35 @@ impl ColorProfile {
                 || dst_pr.color_space == DataColorSpace::Cmyk
                 || dst_pr.color_space == DataColorSpace::Lab
                 || dst_pr.color_space == DataColorSpace::Color3
-                || dst_pr.color_space == DataColorSpace::Color4)
+                || dst_pr.color_space == DataColorSpace::Color4
+                || (dst_pr.color_space == DataColorSpace::Gray
+                    && (self.color_space == DataColorSpace::Cmyk
+                        || self.color_space == DataColorSpace::Color4)))
             && (dst_pr.pcs == DataColorSpace::Xyz || dst_pr.pcs == DataColorSpace::Lab)
             && (self.pcs == DataColorSpace::Xyz || self.pcs == DataColorSpace::Lab)
         {
             if src_layout == Layout::Gray || src_layout == Layout::GrayAlpha {
                 return Err(CmsError::InvalidLayout);
             }
-            if dst_layout == Layout::Gray || dst_layout == Layout::GrayAlpha {
+            if dst_pr.color_space != DataColorSpace::Gray
+                && (dst_layout == Layout::Gray || dst_layout == Layout::GrayAlpha)
+            {
                 return Err(CmsError::InvalidLayout);
             }
-            return make_lut_transform::<T, BIT_DEPTH, LINEAR_CAP, GAMMA_CAP>(
+            if options
+                .surround_compensation
+                .is_some_and(|surround| !surround.is_identity())
+            {
+                return Err(CmsError::UnsupportedProfileConnection);
+            }
+            return Ok(TransformPlan::Lut(make_lut_sampler::<
+                T,
+                BIT_DEPTH,
+                LINEAR_CAP,
+                GAMMA_CAP,
+            >(
                 src_layout, self, dst_layout, dst_pr, options,
-            );
+            )?));
         }
 
         Err(CmsError::UnsupportedProfileConnection)
@@ -524,25 +1532,315 @@ impl ColorProfile {
         self.create_transform_nbit::<u8, 8, 256, 4096>(src_layout, dst_pr, dst_layout, options)
     }
 
-    pub(crate) fn get_device_to_pcs_lut(&self, intent: RenderingIntent) -> Option<&LutDataType> {
-        match intent {
-            RenderingIntent::AbsoluteColorimetric => self
-                .lut_a_to_b_colorimetric
-                .as_ref()
-                .and_then(|x| x.as_lut()),
-            RenderingIntent::Saturation => {
-                self.lut_a_to_b_saturation.as_ref().and_then(|x| x.as_lut())
-            }
-            RenderingIntent::RelativeColorimetric => self
-                .lut_a_to_b_colorimetric
-                .as_ref()
-                .and_then(|x| x.as_lut()),
-            RenderingIntent::Perceptual => {
-                self.lut_a_to_b_perceptual.as_ref().and_then(|x| x.as_lut())
+    /// Creates 8 bit transform from a profile with `channels` device channels,
+    /// e.g. `5CLR`..`8CLR` profiles of hexachrome or CMYK with spot colors.
+    ///
+    /// Source buffer is interleaved `channels` samples per pixel, `channels`
+    /// must match [DataColorSpace::channels] of the profile and be at most 8.
+    /// Source `AToB` CLUT is interpolated multilinearly, [TransformOptions::interpolation_method]
+    /// applies to destination tables only.
+    pub fn create_nchannel_transform_8bit(
+        &self,
+        channels: usize,
+        dst_pr: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+    ) -> Result<Box<Transform8BitExecutor>, CmsError> {
+        make_nchannel_transform::<u8, 8>(channels, self, dst_pr, dst_layout, options)
+    }
+
+    /// Creates 16 bit transform from a profile with `channels` device channels,
+    /// see [ColorProfile::create_nchannel_transform_8bit].
+    pub fn create_nchannel_transform_16bit(
+        &self,
+        channels: usize,
+        dst_pr: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+    ) -> Result<Box<Transform16BitExecutor>, CmsError> {
+        make_nchannel_transform::<u16, 16>(channels, self, dst_pr, dst_layout, options)
+    }
+
+    /// Creates 8 bit transform running `stage` on linear light values, e.g. to apply
+    /// channel gains without reimplementing an executor.
+    ///
+    /// Destination has to be RGB matrix-shaper profile. Stage receives interleaved
+    /// linear RGB in destination primaries, 3 values per pixel, after source is
+    /// converted and before destination tone curves are applied. Alpha bypasses the stage.
+    ///
+    /// Source is decoded into `f32` working set, so this is slower than [ColorProfile::create_transform_8bit],
+    /// [TransformOptions::dither] is not applied.
+    pub fn create_transform_with_stage_8bit(
+        &self,
+        src_layout: Layout,
+        dst_pr: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+        stage: Arc<dyn InPlaceStage + Send + Sync>,
+    ) -> Result<Box<Transform8BitExecutor>, CmsError> {
+        make_custom_stage_transform::<u8, 8>(self, src_layout, dst_pr, dst_layout, options, stage)
+    }
+
+    /// Creates 16 bit transform running `stage` on linear light values,
+    /// see [ColorProfile::create_transform_with_stage_8bit].
+    pub fn create_transform_with_stage_16bit(
+        &self,
+        src_layout: Layout,
+        dst_pr: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+        stage: Arc<dyn InPlaceStage + Send + Sync>,
+    ) -> Result<Box<Transform16BitExecutor>, CmsError> {
+        make_custom_stage_transform::<u16, 16>(self, src_layout, dst_pr, dst_layout, options, stage)
+    }
+
+    /// Creates `f32` transform running `stage` on linear light values,
+    /// see [ColorProfile::create_transform_with_stage_8bit].
+    ///
+    /// Values produced by the stage out of [0, 1] are handled by [TransformOptions::clamp_policy].
+    pub fn create_transform_with_stage_f32(
+        &self,
+        src_layout: Layout,
+        dst_pr: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+        stage: Arc<dyn InPlaceStage + Send + Sync>,
+    ) -> Result<Box<TransformF32BitExecutor>, CmsError> {
+        make_custom_stage_transform::<f32, 1>(self, src_layout, dst_pr, dst_layout, options, stage)
+    }
+
+    /// Creates 8 bit transform from device link profile.
+    ///
+    /// Device link carries the whole conversion in its `A2B0` LUT, from `color_space`
+    /// of the profile to the space declared in place of PCS. RGB and CMYK are supported
+    /// on both sides, layouts must match channel counts of those spaces.
+    /// [TransformOptions::alpha_mode] applies to RGB sides only.
+    pub fn create_device_link_transform_8bit(
+        &self,
+        src_layout: Layout,
+        dst_layout: Layout,
+        options: TransformOptions,
+    ) -> Result<Box<Transform8BitExecutor>, CmsError> {
+        let mut plan = TransformPlan::Lut(make_device_link_sampler::<u8, 8>(
+            self, src_layout, dst_layout, options,
+        )?);
+        if options.alpha_mode == AlphaMode::Premultiplied {
+            // Device link declares its output space in place of PCS
+            plan = wrap_premultiplied_alpha::<u8, 8>(
+                plan,
+                src_layout,
+                has_alpha_channel(self.color_space),
+                dst_layout,
+                has_alpha_channel(self.pcs),
+            );
+        }
+        Ok(Box::new(TransformLayoutsExecutor {
+            executor: with_deterministic_kernel(options.deterministic, || match plan {
+                TransformPlan::Ready(executor) => Ok(executor),
+                TransformPlan::Lut(sampler) => make_lut_transform(sampler),
+            })?,
+            src_channels: src_layout.channels(),
+            dst_channels: dst_layout.channels(),
+            diffusion: None,
+        }))
+    }
+
+    /// Creates 8 bit transform through chain of profiles.
+    ///
+    /// First profile is the source, last one is the destination, profiles between them
+    /// must be abstract Lab or XYZ profiles, e.g. editing looks, applied in PCS in given order.
+    /// `intents` selects tables of every profile, so it must have one intent per profile.
+    /// Whole chain is sampled into single LUT when transform is created, so it runs
+    /// as fast as LUT transform between two profiles.
+    /// Destination `vcgt`, [TransformOptions::ycbcr_output] and [TransformOptions::alpha_mode]
+    /// are applied as [ColorProfile::create_transform_8bit] does.
+    pub fn create_multi_profile_transform(
+        profiles: &[&ColorProfile],
+        src_layout: Layout,
+        dst_layout: Layout,
+        intents: &[RenderingIntent],
+        options: TransformOptions,
+    ) -> Result<Box<Transform8BitExecutor>, CmsError> {
+        let [source, abstracts @ .., dest] = profiles else {
+            return Err(CmsError::UnsupportedProfileConnection);
+        };
+        if intents.len() != profiles.len() {
+            return Err(CmsError::InvalidRenderingIntent);
+        }
+        let abstracts = abstracts
+            .iter()
+            .zip(intents[1..].iter())
+            .map(|(&profile, &intent)| (profile, intent))
+            .collect::<Vec<_>>();
+        let options = TransformOptions {
+            rendering_intent: intents[0],
+            ..options
+        };
+        let sampler = make_multi_profile_sampler::<u8, 8, 256, 4096>(
+            src_layout,
+            source,
+            &abstracts,
+            dst_layout,
+            dest,
+            intents[intents.len() - 1],
+            options,
+        )?;
+        let plan = source.finish_transform_plan::<u8, 8>(
+            TransformPlan::Lut(sampler),
+            src_layout,
+            dest,
+            dst_layout,
+            options,
+        )?;
+        Ok(Box::new(TransformLayoutsExecutor {
+            executor: with_deterministic_kernel(options.deterministic, || match plan {
+                TransformPlan::Ready(executor) => Ok(executor),
+                TransformPlan::Lut(sampler) => make_lut_transform(sampler),
+            })?,
+            src_channels: src_layout.channels(),
+            dst_channels: dst_layout.channels(),
+            diffusion: None,
+        }))
+    }
+
+    /// Creates gray to gray transform reading 8 bit and writing 16 bit samples.
+    ///
+    /// Both profiles must be gray and layouts `Gray` or `GrayAlpha`, alpha is rescaled
+    /// and [TransformOptions::alpha_mode] is honoured.
+    /// See [TransformOptions::treat_gray_as_alpha] for masks that must not be color managed.
+    pub fn create_gray_transform_8bit_to_16bit(
+        &self,
+        src_layout: Layout,
+        dst_pr: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+    ) -> Result<Box<Transform8BitTo16BitExecutor>, CmsError> {
+        self.create_gray_depth_transform::<u8, u16, 8, 16, 256, 65536>(
+            src_layout, dst_pr, dst_layout, options,
+        )
+    }
+
+    /// Creates gray to gray transform reading 16 bit and writing 8 bit samples.
+    ///
+    /// Same as [ColorProfile::create_gray_transform_8bit_to_16bit] in other direction.
+    pub fn create_gray_transform_16bit_to_8bit(
+        &self,
+        src_layout: Layout,
+        dst_pr: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+    ) -> Result<Box<Transform16BitTo8BitExecutor>, CmsError> {
+        self.create_gray_depth_transform::<u16, u8, 16, 8, 65536, 65536>(
+            src_layout, dst_pr, dst_layout, options,
+        )
+    }
+
+    fn create_gray_depth_transform<
+        S: Copy
+            + Default
+            + PointeeSizeExpressible
+            + ConvertDepth<D>
+            + AsPrimitive<f32>
+            + AsPrimitive<usize>
+            + Send
+            + Sync
+            + 'static,
+        D: Copy
+            + Default
+            + PointeeSizeExpressible
+            + GammaLutInterpolate
+            + AsPrimitive<f32>
+            + AsPrimitive<usize>
+            + Send
+            + Sync
+            + 'static,
+        const SRC_BIT_DEPTH: usize,
+        const DST_BIT_DEPTH: usize,
+        const LINEAR_CAP: usize,
+        const GAMMA_CAP: usize,
+    >(
+        &self,
+        src_layout: Layout,
+        dst_pr: &ColorProfile,
+        dst_layout: Layout,
+        options: TransformOptions,
+    ) -> Result<Box<dyn TransformDepthExecutor<S, D> + Send + Sync>, CmsError>
+    where
+        f32: AsPrimitive<S> + AsPrimitive<D>,
+        u32: AsPrimitive<S> + AsPrimitive<D>,
+    {
+        if self.color_space != DataColorSpace::Gray || dst_pr.color_space != DataColorSpace::Gray {
+            return Err(CmsError::UnsupportedProfileConnection);
+        }
+        let trc = if options.treat_gray_as_alpha {
+            None
+        } else {
+            let mut linear = self.build_gray_linearize_table_cicp::<S, LINEAR_CAP, SRC_BIT_DEPTH>(
+                options.allow_use_cicp_transfer,
+            )?;
+            if let Some(surround) = options.surround_compensation {
+                surround.apply_to_table(&mut linear);
             }
+            let gamma = dst_pr.build_gamma_table::<D, 65536, GAMMA_CAP, DST_BIT_DEPTH>(
+                &dst_pr.gray_trc,
+                options.allow_use_cicp_transfer,
+            )?;
+            Some(GrayDepthTrc {
+                linear,
+                gamma,
+                gamma_scale: (GAMMA_CAP - 1) as f32,
+            })
+        };
+        let executor =
+            make_gray_depth_transform::<S, D, DST_BIT_DEPTH>(src_layout, dst_layout, trc)?;
+        if options.alpha_mode == AlphaMode::Premultiplied {
+            return Ok(wrap_premultiplied_alpha_depth::<
+                S,
+                D,
+                SRC_BIT_DEPTH,
+                DST_BIT_DEPTH,
+            >(executor, src_layout, dst_layout));
         }
+        Ok(executor)
+    }
+
+    /// Creates 8 bit transform that also writes out-of-gamut mask.
+    ///
+    /// When destination has `gamt` tag, mask is taken from it. LUT based destinations without
+    /// the tag are checked with round trip of their tables, see
+    /// [TransformOptions::gamut_round_trip_delta_e], otherwise only matrix-shaper RGB profiles
+    /// are supported. Source and destination share `layout`.
+    /// Mask holds one byte per pixel, 255 marks colors out of the destination gamut.
+    /// Colors are written exactly as [ColorProfile::create_transform_8bit] writes them,
+    /// premultiplied alpha pixels are checked by their straight colors.
+    pub fn create_transform_with_mask(
+        &self,
+        dst_pr: &ColorProfile,
+        layout: Layout,
+        options: TransformOptions,
+    ) -> Result<Box<TransformMask8BitExecutor>, CmsError> {
+        let round_trip_check = options.gamut_round_trip_delta_e != 0
+            && dst_pr.has_device_to_pcs_lut()
+            && dst_pr.has_pcs_to_device_lut();
+        let check = if dst_pr.has_gamut_tag() || round_trip_check {
+            self.create_gamut_check_8bit(layout, dst_pr, options)?
+        } else if self.is_matrix_shaper_pair(dst_pr) {
+            make_rgb_xyz_gamut_check(layout, self, dst_pr, options)?
+        } else {
+            return Err(CmsError::UnsupportedProfileConnection);
+        };
+        let check = if options.alpha_mode == AlphaMode::Premultiplied
+            && has_alpha_channel(self.color_space)
+        {
+            wrap_premultiplied_gamut_check(check, layout)
+        } else {
+            check
+        };
+        let transform = self.create_transform_8bit(layout, dst_pr, layout, options)?;
+        Ok(Box::new(GamutMaskExecutor { transform, check }))
     }
 
+    /// Returns `AToB` tag for the intent, falling back to perceptual `A2B0` when absent
     pub(crate) fn get_device_to_pcs(&self, intent: RenderingIntent) -> Option<&LutWarehouse> {
         match intent {
             RenderingIntent::AbsoluteColorimetric => self.lut_a_to_b_colorimetric.as_ref(),
@@ -550,27 +1848,10 @@ impl ColorProfile {
             RenderingIntent::RelativeColorimetric => self.lut_a_to_b_colorimetric.as_ref(),
             RenderingIntent::Perceptual => self.lut_a_to_b_perceptual.as_ref(),
         }
+        .or(self.lut_a_to_b_perceptual.as_ref())
     }
 
-    pub(crate) fn get_pcs_to_device_lut(&self, intent: RenderingIntent) -> Option<&LutDataType> {
-        match intent {
-            RenderingIntent::AbsoluteColorimetric => self
-                .lut_b_to_a_colorimetric
-                .as_ref()
-                .and_then(|x| x.as_lut()),
-            RenderingIntent::Saturation => {
-                self.lut_b_to_a_saturation.as_ref().and_then(|x| x.as_lut())
-            }
-            RenderingIntent::RelativeColorimetric => self
-                .lut_b_to_a_colorimetric
-                .as_ref()
-                .and_then(|x| x.as_lut()),
-            RenderingIntent::Perceptual => {
-                self.lut_b_to_a_perceptual.as_ref().and_then(|x| x.as_lut())
-            }
-        }
-    }
-
+    /// Returns `BToA` tag for the intent, falling back to perceptual `B2A0` when absent
     pub(crate) fn get_pcs_to_device(&self, intent: RenderingIntent) -> Option<&LutWarehouse> {
         match intent {
             RenderingIntent::AbsoluteColorimetric => self.lut_b_to_a_colorimetric.as_ref(),
@@ -578,30 +1859,107 @@ impl ColorProfile {
             RenderingIntent::RelativeColorimetric => self.lut_b_to_a_colorimetric.as_ref(),
             RenderingIntent::Perceptual => self.lut_b_to_a_perceptual.as_ref(),
         }
+        .or(self.lut_b_to_a_perceptual.as_ref())
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "transforms"))]
 mod tests {
-    use crate::{ColorProfile, Layout, RenderingIntent, TransformOptions};
+    use crate::profile::LutDataType;
+    use crate::{
+        AlphaMode, ChromaticAdaptation, Chromaticity, ClampPolicy, CmsError, ColorProfile,
+        CompatibilityMode, DataColorSpace, DitherMode, InterpolationMethod, Layout, LutMCurvesType,
+        LutType, LutWarehouse, Matrix3f, ProfileClass, RenderingIntent, Surround,
+        SurroundCompensation, ToneReprCurve, TransformOptions, Vcgt, VcgtFormula, Vector3f,
+        YCbCrMatrix, depth_16_to_8,
+    };
+    use num_traits::AsPrimitive;
     use rand::Rng;
 
-    #[test]
-    fn test_transform_rgb8() {
-        let mut srgb_profile = ColorProfile::new_srgb();
-        let bt2020_profile = ColorProfile::new_bt2020();
-        let random_point_x = rand::rng().random_range(0..255);
-        let transform = bt2020_profile
-            .create_transform_8bit(
-                Layout::Rgb,
-                &srgb_profile,
-                Layout::Rgb,
-                TransformOptions::default(),
-            )
-            .unwrap();
-        let src = vec![random_point_x; 256 * 256 * 3];
-        let mut dst = vec![random_point_x; 256 * 256 * 3];
-        transform.transform(&src, &mut dst).unwrap();
+    /// Same check as `testing::assert_samples_similar`, which is built only with `testing` feature
+    #[track_caller]
+    fn assert_samples_similar<T: Copy + AsPrimitive<f32> + std::fmt::Debug>(
+        a: &[T],
+        b: &[T],
+        tolerance: f32,
+    ) {
+        assert_eq!(a.len(), b.len());
+        for (i, (&x, &y)) in a.iter().zip(b.iter()).enumerate() {
+            let diff = (x.as_() - y.as_()).abs();
+            assert!(
+                diff <= tolerance,
+                "Sample {i} differs by {diff}: {x:?} vs {y:?}"
+            );
+        }
+    }
+
+    /// Divides colors by alpha in the last channel, rounding as premultiplied alpha transforms do
+    fn unpremultiply_samples<T: Copy + AsPrimitive<u32>>(
+        src: &[T],
+        channels: usize,
+        max: u32,
+    ) -> Vec<T>
+    where
+        u32: AsPrimitive<T>,
+    {
+        let mut dst = src.to_vec();
+        for px in dst.chunks_exact_mut(channels) {
+            let a: u32 = px[channels - 1].as_();
+            for v in px[..channels - 1].iter_mut() {
+                let c: u32 = (*v).as_();
+                *v = (c * max + a / 2)
+                    .checked_div(a)
+                    .map_or(0, |x| x.min(max))
+                    .as_();
+            }
+        }
+        dst
+    }
+
+    /// Multiplies colors by alpha in the last channel
+    fn premultiply_samples<T: Copy + AsPrimitive<u32>>(
+        src: &[T],
+        channels: usize,
+        max: u32,
+    ) -> Vec<T>
+    where
+        u32: AsPrimitive<T>,
+    {
+        let mut dst = src.to_vec();
+        for px in dst.chunks_exact_mut(channels) {
+            let a: u32 = px[channels - 1].as_();
+            for v in px[..channels - 1].iter_mut() {
+                let c: u32 = (*v).as_();
+                *v = ((c * a + max / 2) / max).as_();
+            }
+        }
+        dst
+    }
+
+    /// Premultiplied RGBA ramp with alpha from opaque to transparent
+    fn premultiplied_rgba_ramp() -> Vec<u8> {
+        let straight = (0..=255u8)
+            .flat_map(|x| [x, 255 - x, x / 3, [255, 192, 128, 64, 1, 0][x as usize % 6]])
+            .collect::<Vec<_>>();
+        premultiply_samples(&straight, 4, 255)
+    }
+
+    #[test]
+    fn test_transform_rgb8() {
+        let mut srgb_profile = ColorProfile::new_srgb();
+        let bt2020_profile = ColorProfile::new_bt2020();
+        let random_point_x = rand::rng().random_range(0..255);
+        let transform = bt2020_profile
+            .create_transform_8bit(
+                Layout::Rgb,
+                &srgb_profile,
+                Layout::Rgb,
+                TransformOptions::default(),
+            )
+            .unwrap();
+        let src = vec![random_point_x; 256 * 256 * 3];
+        let mut dst = vec![random_point_x; 256 * 256 * 3];
+        transform.transform(&src, &mut dst).unwrap();
 
         let transform = bt2020_profile
             .create_transform_8bit(
@@ -658,6 +2016,69 @@ mod tests {
         transform.transform(&src, &mut dst).unwrap();
     }
 
+    #[test]
+    fn test_transform_rgb_gamma_mismatch() {
+        // sRGB primaries with pure gamma, any difference to sRGB is in destination TRC only
+        let mut gamma22 = ColorProfile::new_srgb();
+        let curve = crate::curve_from_gamma(2.2);
+        gamma22.red_trc = Some(curve.clone());
+        gamma22.green_trc = Some(curve.clone());
+        gamma22.blue_trc = Some(curve);
+        // CICP would take precedence over the curves
+        gamma22.cicp = None;
+        // u8Fixed8 encoding of 2.2
+        let gamma = 563. / 256.;
+        let reference = |x: f32| {
+            let linear = if x <= 0.04045 {
+                x / 12.92
+            } else {
+                ((x + 0.055) / 1.055).powf(2.4)
+            };
+            linear.powf(1. / gamma)
+        };
+        let srgb = ColorProfile::new_srgb();
+
+        let src = (0..=255u8).flat_map(|x| [x, x, x]).collect::<Vec<_>>();
+        let mut dst = vec![0u8; src.len()];
+        srgb.create_transform_8bit(
+            Layout::Rgb,
+            &gamma22,
+            Layout::Rgb,
+            TransformOptions::default(),
+        )
+        .unwrap()
+        .transform(&src, &mut dst)
+        .unwrap();
+        for (&s, &d) in src.iter().zip(dst.iter()) {
+            let expected = reference(s as f32 / 255.) * 255.;
+            assert!(
+                (d as f32 - expected).abs() <= 1.,
+                "{s} became {d}, expected {expected}"
+            );
+        }
+
+        let src = (0..=1000)
+            .flat_map(|x| [x as f32 / 1000.; 3])
+            .collect::<Vec<_>>();
+        let mut dst = vec![0f32; src.len()];
+        srgb.create_transform_f32(
+            Layout::Rgb,
+            &gamma22,
+            Layout::Rgb,
+            TransformOptions::default(),
+        )
+        .unwrap()
+        .transform(&src, &mut dst)
+        .unwrap();
+        for (&s, &d) in src.iter().zip(dst.iter()) {
+            let expected = reference(s);
+            assert!(
+                (d - expected).abs() < 2e-3,
+                "{s} became {d}, expected {expected}"
+            );
+        }
+    }
+
     #[test]
     fn test_transform_gray_to_rgb8() {
         let srgb_profile = ColorProfile::new_gray_with_gamma(2.2f32);
@@ -694,6 +2115,46 @@ mod tests {
         transform.transform(&src, &mut dst).unwrap();
     }
 
+    #[test]
+    fn test_transform_gray_to_rgb8_unaligned() {
+        let gray = ColorProfile::new_gray_with_gamma(2.2f32);
+        let srgb = ColorProfile::new_srgb();
+        let src = (0..1000u32)
+            .map(|i| (i * 37 % 256) as u8)
+            .collect::<Vec<_>>();
+        let srgb_encode = |linear: f32| {
+            if linear < 0.0031308 {
+                linear * 12.92
+            } else {
+                1.055 * linear.powf(1. / 2.4) - 0.055
+            }
+        };
+        for layout in [Layout::Rgb, Layout::Rgba] {
+            let channels = layout.channels();
+            let transform = gray
+                .create_transform_8bit(Layout::Gray, &srgb, layout, TransformOptions::default())
+                .unwrap();
+            let mut dst = vec![0u8; src.len() * channels];
+            transform.transform(&src, &mut dst).unwrap();
+            for (&v, pixel) in src.iter().zip(dst.chunks_exact(channels)) {
+                let expected = srgb_encode((v as f32 / 255.).powf(2.2)) * 255.;
+                for &c in pixel[..3].iter() {
+                    assert!(
+                        (c as f32 - expected).abs() <= 1.,
+                        "{v}: {pixel:?} {expected}"
+                    );
+                }
+                if layout == Layout::Rgba {
+                    assert_eq!(pixel[3], 255);
+                }
+                // Pixel converted alone matches the bulk result
+                let mut single = vec![0u8; channels];
+                transform.transform(&[v], &mut single).unwrap();
+                assert_eq!(single, pixel);
+            }
+        }
+    }
+
     #[test]
     fn test_transform_gray_to_gray_alpha8() {
         let srgb_profile = ColorProfile::new_gray_with_gamma(2.2f32);
@@ -765,4 +2226,3160 @@ mod tests {
         let mut dst = vec![random_point_x; 256 * 256 * 3];
         transform.transform(&src, &mut dst).unwrap();
     }
+
+    #[test]
+    fn test_transform_with_mask() {
+        let bt2020_profile = ColorProfile::new_bt2020();
+        let srgb_profile = ColorProfile::new_srgb();
+        let options = TransformOptions::default();
+        let src: Vec<u8> = vec![
+            0, 255, 0, 255, // saturated BT.2020 green is outside sRGB
+            128, 128, 128, 255, // neutral
+            255, 0, 0, 128, // saturated BT.2020 red
+            60, 70, 65, 0, // muted color
+            255, 255, 255, 255, // white
+            0, 0, 255, 7, // saturated BT.2020 blue
+        ];
+        let masked = bt2020_profile
+            .create_transform_with_mask(&srgb_profile, Layout::Rgba, options)
+            .unwrap();
+        let mut dst = vec![0u8; src.len()];
+        let mut mask = vec![0u8; src.len() / 4];
+        masked.transform(&src, &mut dst, &mut mask).unwrap();
+
+        let regular = bt2020_profile
+            .create_transform_8bit(Layout::Rgba, &srgb_profile, Layout::Rgba, options)
+            .unwrap();
+        let mut regular_dst = vec![0u8; src.len()];
+        regular.transform(&src, &mut regular_dst).unwrap();
+        assert_eq!(dst, regular_dst);
+
+        let ramp = (0..=255u8)
+            .flat_map(|x| [x, 255 - x, x / 3, x])
+            .collect::<Vec<_>>();
+        let mut ramp_dst = vec![0u8; ramp.len()];
+        let mut ramp_mask = vec![0u8; ramp.len() / 4];
+        masked
+            .transform(&ramp, &mut ramp_dst, &mut ramp_mask)
+            .unwrap();
+        let mut ramp_regular = vec![0u8; ramp.len()];
+        regular.transform(&ramp, &mut ramp_regular).unwrap();
+        assert_eq!(ramp_dst, ramp_regular);
+
+        let lin_r = bt2020_profile
+            .build_r_linearize_table::<u8, 256, 8>(false)
+            .unwrap();
+        let lin_g = bt2020_profile
+            .build_g_linearize_table::<u8, 256, 8>(false)
+            .unwrap();
+        let lin_b = bt2020_profile
+            .build_b_linearize_table::<u8, 256, 8>(false)
+            .unwrap();
+        let matrix = bt2020_profile.transform_matrix(&srgb_profile).unwrap();
+        let expected = src
+            .chunks_exact(4)
+            .map(|px| {
+                let lin = [
+                    lin_r[px[0] as usize],
+                    lin_g[px[1] as usize],
+                    lin_b[px[2] as usize],
+                ];
+                let out_of_gamut = matrix.v.iter().any(|row| {
+                    let v = row[0] * lin[0] + row[1] * lin[1] + row[2] * lin[2];
+                    !(-1e-3..=1. + 1e-3).contains(&v)
+                });
+                if out_of_gamut { 255 } else { 0 }
+            })
+            .collect::<Vec<u8>>();
+        assert_eq!(mask, expected);
+        assert_eq!(mask, [255, 0, 255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_transform_lab_pcs_cmyk_neutral_ramps() {
+        // us_swop_coated is Lab PCS with lut16 tables, neutral CMY and K ramps
+        // by 17 into sRGB as recorded from lcms2
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        assert_eq!(cmyk.pcs, DataColorSpace::Lab);
+        let srgb = ColorProfile::new_srgb();
+        let perceptual: [u8; 96] = [
+            255, 255, 255, 237, 234, 233, 218, 211, 210, 200, 191, 190, 185, 173, 171, 171, 158,
+            156, 159, 144, 142, 148, 132, 130, 136, 120, 118, 126, 109, 108, 115, 99, 99, 105, 90,
+            90, 94, 81, 82, 82, 73, 75, 70, 65, 68, 56, 57, 60, 255, 255, 255, 240, 241, 242, 224,
+            225, 226, 207, 208, 210, 191, 192, 195, 176, 178, 181, 163, 165, 167, 150, 152, 155,
+            137, 139, 142, 125, 127, 129, 113, 114, 116, 100, 101, 103, 88, 88, 90, 73, 73, 75, 57,
+            55, 57, 40, 37, 38,
+        ];
+        let relative: [u8; 96] = [
+            255, 255, 255, 239, 236, 235, 224, 217, 216, 209, 200, 198, 195, 183, 181, 181, 168,
+            166, 168, 154, 152, 156, 140, 138, 144, 128, 126, 133, 117, 115, 121, 106, 105, 110,
+            96, 97, 99, 88, 89, 88, 80, 82, 78, 73, 76, 67, 68, 71, 255, 255, 255, 242, 242, 243,
+            227, 229, 230, 213, 215, 217, 200, 202, 204, 186, 188, 191, 173, 175, 178, 160, 162,
+            165, 147, 149, 151, 134, 135, 138, 120, 122, 124, 107, 108, 110, 94, 94, 96, 81, 80,
+            82, 68, 66, 68, 55, 53, 54,
+        ];
+        let src = (0..=255u8)
+            .step_by(17)
+            .map(|v| [v, v, v, 0])
+            .chain((0..=255u8).step_by(17).map(|v| [0, 0, 0, v]))
+            .flatten()
+            .collect::<Vec<u8>>();
+        for (rendering_intent, expected) in [
+            (RenderingIntent::Perceptual, perceptual),
+            (RenderingIntent::RelativeColorimetric, relative),
+        ] {
+            let transform = cmyk
+                .create_transform_8bit(
+                    Layout::Rgba,
+                    &srgb,
+                    Layout::Rgb,
+                    TransformOptions {
+                        rendering_intent,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            let mut dst = vec![0u8; expected.len()];
+            transform.transform(&src, &mut dst).unwrap();
+            for (i, (got, want)) in dst.iter().zip(expected.iter()).enumerate() {
+                assert!(
+                    got.abs_diff(*want) <= 1,
+                    "{rendering_intent:?} pixel {:?}: {:?} vs lcms2 {:?}",
+                    &src[i / 3 * 4..i / 3 * 4 + 4],
+                    &dst[i / 3 * 3..i / 3 * 3 + 3],
+                    &expected[i / 3 * 3..i / 3 * 3 + 3],
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_transform_cmyk_to_gray() {
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let srgb = ColorProfile::new_srgb();
+        let gray = ColorProfile::new_gray_with_gamma(2.2);
+        let options = TransformOptions {
+            interpolation_method: InterpolationMethod::Tetrahedral,
+            ..Default::default()
+        };
+
+        let mut src = Vec::new();
+        for c in (0..=255u8).step_by(51) {
+            for m in (0..=255u8).step_by(51) {
+                for y in (0..=255u8).step_by(85) {
+                    for k in (0..=255u8).step_by(85) {
+                        src.extend_from_slice(&[c, m, y, k]);
+                    }
+                }
+            }
+        }
+        let pixels = src.len() / 4;
+
+        let to_rgb = cmyk
+            .create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgb, options)
+            .unwrap();
+        let mut rgb = vec![0u8; pixels * 3];
+        to_rgb.transform(&src, &mut rgb).unwrap();
+
+        let to_gray = cmyk
+            .create_transform_8bit(Layout::Rgba, &gray, Layout::Gray, options)
+            .unwrap();
+        let mut dst = vec![0u8; pixels];
+        to_gray.transform(&src, &mut dst).unwrap();
+
+        let lin = srgb.build_r_linearize_table::<u8, 256, 8>(false).unwrap();
+        let matrix = srgb.rgb_to_xyz_matrix().unwrap();
+        // Colors outside of sRGB are clipped, so only colors inside sRGB gamut are compared
+        let (luminance, reference): (Vec<f32>, Vec<f32>) = rgb
+            .chunks_exact(3)
+            .zip(dst.iter())
+            .filter(|(rgb, _)| rgb.iter().all(|&x| x != 0 && x != 255))
+            .map(|(rgb, &gray)| {
+                let y = matrix.v[1][0] * lin[rgb[0] as usize]
+                    + matrix.v[1][1] * lin[rgb[1] as usize]
+                    + matrix.v[1][2] * lin[rgb[2] as usize];
+                ((gray as f32 / 255.).powf(2.2), y)
+            })
+            .unzip();
+        assert!(luminance.len() > pixels / 2);
+        assert_samples_similar(&luminance, &reference, 0.01);
+
+        let to_gray_alpha = cmyk
+            .create_transform_8bit(Layout::Rgba, &gray, Layout::GrayAlpha, options)
+            .unwrap();
+        let mut dst_alpha = vec![0u8; pixels * 2];
+        to_gray_alpha.transform(&src, &mut dst_alpha).unwrap();
+        for (v, va) in dst.iter().zip(dst_alpha.chunks_exact(2)) {
+            assert_eq!(*v, va[0]);
+            assert_eq!(va[1], 255);
+        }
+
+        let to_gray16 = cmyk
+            .create_transform_16bit(Layout::Rgba, &gray, Layout::Gray, options)
+            .unwrap();
+        let src16 = src.iter().map(|&x| x as u16 * 257).collect::<Vec<u16>>();
+        let mut dst16 = vec![0u16; pixels];
+        to_gray16.transform(&src16, &mut dst16).unwrap();
+        let dst16_8 = dst16.iter().map(|&x| depth_16_to_8(x)).collect::<Vec<u8>>();
+        assert_samples_similar(&dst16_8, &dst, 1.);
+
+        assert!(
+            cmyk.create_transform_8bit(Layout::Rgba, &srgb, Layout::Gray, options)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_transform_f32_evaluates_curves() {
+        let srgb = ColorProfile::new_srgb();
+        let mut linear = srgb.clone();
+        linear.cicp = None;
+        linear.red_trc = Some(ToneReprCurve::Lut(vec![]));
+        linear.green_trc = Some(ToneReprCurve::Lut(vec![]));
+        linear.blue_trc = Some(ToneReprCurve::Lut(vec![]));
+        let transform = srgb
+            .create_transform_f32(
+                Layout::Rgb,
+                &linear,
+                Layout::Rgb,
+                TransformOptions::default(),
+            )
+            .unwrap();
+        let src = [0.0f32, 0.02, 0.2, 0.5, 0.73, 1.0];
+        let mut dst = [0f32; 6];
+        transform.transform(&src, &mut dst).unwrap();
+        for (&s, &d) in src.iter().zip(dst.iter()) {
+            let expected = if s <= 0.04045 {
+                s / 12.92
+            } else {
+                ((s + 0.055) / 1.055).powf(2.4)
+            };
+            assert!(
+                (expected - d).abs() < 1e-4,
+                "Expected {expected} for {s}, got {d}"
+            );
+        }
+
+        let inverse = linear
+            .create_transform_f32(Layout::Rgb, &srgb, Layout::Rgb, TransformOptions::default())
+            .unwrap();
+        let mut back = [0f32; 6];
+        inverse.transform(&dst, &mut back).unwrap();
+        for (&s, &d) in src.iter().zip(back.iter()) {
+            assert!((s - d).abs() < 1e-4, "Expected {s}, got {d}");
+        }
+    }
+
+    #[test]
+    fn test_transform_f32_keeps_out_of_range() {
+        let p3 = ColorProfile::new_display_p3();
+        let srgb = ColorProfile::new_srgb();
+        let transform = p3
+            .create_transform_f32(Layout::Rgb, &srgb, Layout::Rgb, TransformOptions::default())
+            .unwrap();
+        let src = [0f32, 1., 0.];
+        let mut dst = [0f32; 3];
+        transform.transform(&src, &mut dst).unwrap();
+        assert!(dst[0] < 0., "P3 green has negative sRGB red, got {:?}", dst);
+        assert!(
+            dst[2] < 0.,
+            "P3 green has negative sRGB blue, got {:?}",
+            dst
+        );
+        assert!(dst[1] > 1., "P3 green exceeds sRGB green, got {:?}", dst);
+
+        let clipping = p3
+            .create_transform_f32(
+                Layout::Rgb,
+                &srgb,
+                Layout::Rgb,
+                TransformOptions {
+                    clamp_policy: ClampPolicy::ClampToRange,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let mut clipped = [0f32; 3];
+        clipping.transform(&src, &mut clipped).unwrap();
+        assert_samples_similar(&clipped, &[0., 1., 0.], 1e-5);
+
+        // Identity transform passes out of range values as is
+        let identity = srgb
+            .create_transform_f32(Layout::Rgb, &srgb, Layout::Rgb, TransformOptions::default())
+            .unwrap();
+        let src = [-0.25f32, 1.5, 0.5];
+        let mut dst = [0f32; 3];
+        identity.transform(&src, &mut dst).unwrap();
+        assert_samples_similar(&dst, &src, 1e-4);
+    }
+
+    #[test]
+    fn test_transform_clamp_policy() {
+        let p3 = ColorProfile::new_display_p3();
+        let srgb = ColorProfile::new_srgb();
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        for clamp_policy in [ClampPolicy::ClampToRange, ClampPolicy::PreserveExtended] {
+            let options = TransformOptions {
+                clamp_policy,
+                ..Default::default()
+            };
+            assert_eq!(p3.effective_clamp_policy_f32(&srgb, options), clamp_policy);
+            assert_eq!(
+                cmyk.effective_clamp_policy_f32(&srgb, options),
+                ClampPolicy::ClampToRange
+            );
+            assert_eq!(
+                p3.effective_clamp_policy_f32(
+                    &srgb,
+                    TransformOptions {
+                        ycbcr_output: Some(YCbCrMatrix::Bt709),
+                        ..options
+                    }
+                ),
+                ClampPolicy::ClampToRange
+            );
+
+            // Integer destinations are bounded regardless of policy
+            let transform = p3
+                .create_transform_16bit(Layout::Rgb, &srgb, Layout::Rgb, options)
+                .unwrap();
+            let mut dst = [0u16; 3];
+            transform.transform(&[0, 65535, 0], &mut dst).unwrap();
+            assert_eq!(dst[0], 0);
+            assert_eq!(dst[1], 65535);
+
+            let transform = p3
+                .create_transform_f32(Layout::Rgb, &srgb, Layout::Rgb, options)
+                .unwrap();
+            let mut dst = [0f32; 3];
+            transform.transform(&[0., 1., 0.], &mut dst).unwrap();
+            let in_range = dst.iter().all(|&v| (0. ..=1.).contains(&v));
+            assert_eq!(
+                in_range,
+                clamp_policy == ClampPolicy::ClampToRange,
+                "Policy {clamp_policy:?}, got {dst:?}"
+            );
+
+            // LUT based transforms clamp regardless of policy
+            let transform = cmyk
+                .create_transform_f32(Layout::Rgba, &srgb, Layout::Rgb, options)
+                .unwrap();
+            let mut dst = [0f32; 3];
+            transform
+                .transform(&[0.3, 0.6, 0.1, 0.2], &mut dst)
+                .unwrap();
+            assert!(dst.iter().all(|&v| (0. ..=1.).contains(&v)), "{dst:?}");
+        }
+    }
+
+    #[test]
+    fn test_transform_chromatic_adaptation() {
+        let d65 = Chromaticity::D65.to_xyz();
+        let d50 = Chromaticity::D50.to_xyz();
+        let mut srgb = ColorProfile::new_srgb();
+        srgb.media_white_point = Some(d65);
+        srgb.chromatic_adaptation = None;
+        let mut d50_white = srgb.clone();
+        d50_white.media_white_point = Some(d50);
+
+        // D65 neutral is adapted differently by cone spaces
+        let neutral = Vector3f {
+            v: [d65.x, d65.y, d65.z],
+        };
+        let bradford = ChromaticAdaptation::Bradford
+            .adaptation_matrix(d65, d50)
+            .mul_vector(neutral);
+        let xyz = ChromaticAdaptation::Xyz
+            .adaptation_matrix(d65, d50)
+            .mul_vector(neutral);
+        for (b, x) in bradford.v.iter().zip(xyz.v.iter()) {
+            assert!(
+                (b - x).abs() < 1e-4,
+                "White is adapted to white by any model"
+            );
+        }
+        let gray_green = Vector3f {
+            v: [0.3 * d65.x, 0.35 * d65.y, 0.3 * d65.z],
+        };
+        let bradford = ChromaticAdaptation::Bradford
+            .adaptation_matrix(d65, d50)
+            .mul_vector(gray_green);
+        let xyz = ChromaticAdaptation::Xyz
+            .adaptation_matrix(d65, d50)
+            .mul_vector(gray_green);
+        assert!(
+            bradford
+                .v
+                .iter()
+                .zip(xyz.v.iter())
+                .any(|(b, x)| (b - x).abs() > 1e-3),
+            "Bradford {bradford:?} and XYZ scaling {xyz:?} must differ"
+        );
+
+        // Bradford goes through the PCS, as profiles are adapted with it
+        let default = srgb.transform_matrix(&d50_white).unwrap();
+        let pcs = srgb
+            .transform_matrix_with_adaptation(
+                &d50_white,
+                RenderingIntent::Perceptual,
+                ChromaticAdaptation::Bradford,
+            )
+            .unwrap();
+        assert_eq!(default.v, pcs.v);
+
+        let src = [0.3f32, 0.6, 0.45, 1., 1., 1.];
+        let mut results = Vec::new();
+        for chromatic_adaptation in [
+            ChromaticAdaptation::Bradford,
+            ChromaticAdaptation::Cat02,
+            ChromaticAdaptation::VonKries,
+            ChromaticAdaptation::Xyz,
+        ] {
+            let transform = srgb
+                .create_transform_f32(
+                    Layout::Rgb,
+                    &d50_white,
+                    Layout::Rgb,
+                    TransformOptions {
+                        chromatic_adaptation,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            let mut dst = [0f32; 6];
+            transform.transform(&src, &mut dst).unwrap();
+            assert_samples_similar(&dst[3..], &[1., 1., 1.], 1e-3);
+            results.push(dst);
+        }
+        let bradford = &results[0];
+        for (i, other) in results.iter().enumerate().skip(1) {
+            assert!(
+                bradford[..3]
+                    .iter()
+                    .zip(other[..3].iter())
+                    .any(|(b, o)| (b - o).abs() > 1e-3),
+                "Model {i} matches Bradford: {bradford:?} {other:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_transform_error_diffusion() {
+        let srgb = ColorProfile::new_srgb();
+        let mut linear = ColorProfile::new_srgb();
+        linear.red_trc = Some(ToneReprCurve::Parametric(vec![1.]));
+        linear.green_trc = linear.red_trc.clone();
+        linear.blue_trc = linear.red_trc.clone();
+        linear.cicp = None;
+
+        // Dark gradient, each source value covers a region 16 columns wide
+        const REGION: usize = 16;
+        let (width, height) = (16 * REGION, 32usize);
+        let src = (0..height)
+            .flat_map(|_| (0..width).flat_map(|x| [(x / REGION) as u8 * 3; 3]))
+            .collect::<Vec<u8>>();
+        let options = TransformOptions {
+            dither: DitherMode::ErrorDiffusion,
+            ..Default::default()
+        };
+        let transform = srgb
+            .create_transform_8bit(Layout::Rgb, &linear, Layout::Rgb, options)
+            .unwrap();
+        let mut dst = vec![0u8; src.len()];
+        transform
+            .transform_strided(&src, width * 3, &mut dst, width * 3, width, height)
+            .unwrap();
+        let mut repeated = vec![0u8; src.len()];
+        transform
+            .transform_strided(&src, width * 3, &mut repeated, width * 3, width, height)
+            .unwrap();
+        assert_eq!(dst, repeated, "Error diffusion must be deterministic");
+
+        // Plain slices are never dithered
+        let plain = srgb
+            .create_transform_8bit(Layout::Rgb, &linear, Layout::Rgb, Default::default())
+            .unwrap();
+        let mut rounded = vec![0u8; src.len()];
+        plain.transform(&src, &mut rounded).unwrap();
+        let mut undithered = vec![0u8; src.len()];
+        transform.transform(&src, &mut undithered).unwrap();
+        assert_eq!(rounded, undithered);
+
+        let exact_transform = srgb
+            .create_transform_f32(Layout::Rgb, &linear, Layout::Rgb, Default::default())
+            .unwrap();
+        let src_f32 = src.iter().map(|&x| x as f32 / 255.).collect::<Vec<f32>>();
+        let mut exact = vec![0f32; src.len()];
+        exact_transform.transform(&src_f32, &mut exact).unwrap();
+        for (&d, &e) in dst.iter().zip(exact.iter()) {
+            assert!(
+                (d as f32 - e * 255.).abs() <= 1.,
+                "Expected {} within 1 code, got {d}",
+                e * 255.
+            );
+        }
+        let region_error = |values: &[u8], region: usize| {
+            let (mut sum, mut count) = (0f32, 0f32);
+            for y in 0..height {
+                for x in region * REGION..(region + 1) * REGION {
+                    for c in 0..3 {
+                        let i = (y * width + x) * 3 + c;
+                        sum += values[i] as f32 - exact[i] * 255.;
+                        count += 1.;
+                    }
+                }
+            }
+            sum / count
+        };
+        let mut max_rounding_error = 0f32;
+        for region in 0..width / REGION {
+            let error = region_error(&dst, region);
+            assert!(error.abs() < 0.05, "Region {region} mean error {error}");
+            max_rounding_error = max_rounding_error.max(region_error(&rounded, region).abs());
+        }
+        assert!(
+            max_rounding_error > 0.2,
+            "Gradient has to band without dithering"
+        );
+    }
+
+    #[test]
+    fn test_transform_ycbcr_output() {
+        let bt2020_profile = ColorProfile::new_bt2020();
+        let srgb_profile = ColorProfile::new_srgb();
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let mut rng = rand::rng();
+        let src = (0..4096 * 4)
+            .map(|_| rng.random_range(0..=255u8))
+            .collect::<Vec<u8>>();
+
+        let reference_ycbcr = |rgb: &[u8], matrix: YCbCrMatrix| -> [u8; 3] {
+            let (kr, kb) = match matrix {
+                YCbCrMatrix::Bt601 => (0.299f64, 0.114f64),
+                YCbCrMatrix::Bt709 => (0.2126f64, 0.0722f64),
+            };
+            let (r, g, b) = (rgb[0] as f64, rgb[1] as f64, rgb[2] as f64);
+            let y = kr * r + (1. - kr - kb) * g + kb * b;
+            let cb = (b - y) / (2. * (1. - kb)) + 128.;
+            let cr = (r - y) / (2. * (1. - kr)) + 128.;
+            [y, cb, cr].map(|v| v.round().min(255.).max(0.) as u8)
+        };
+
+        for (source, src_layout) in [(&bt2020_profile, Layout::Rgba), (&cmyk, Layout::Rgba)] {
+            for dst_layout in [Layout::Rgb, Layout::Rgba] {
+                for matrix in [YCbCrMatrix::Bt601, YCbCrMatrix::Bt709] {
+                    let options = TransformOptions {
+                        interpolation_method: InterpolationMethod::Tetrahedral,
+                        ..Default::default()
+                    };
+                    let rgb_transform = source
+                        .create_transform_8bit(src_layout, &srgb_profile, dst_layout, options)
+                        .unwrap();
+                    let ycbcr_transform = source
+                        .create_transform_8bit(
+                            src_layout,
+                            &srgb_profile,
+                            dst_layout,
+                            TransformOptions {
+                                ycbcr_output: Some(matrix),
+                                ..options
+                            },
+                        )
+                        .unwrap();
+                    let pixels = src.len() / 4;
+                    let mut rgb = vec![0u8; pixels * dst_layout.channels()];
+                    rgb_transform.transform(&src, &mut rgb).unwrap();
+                    let mut ycbcr = vec![0u8; pixels * dst_layout.channels()];
+                    ycbcr_transform.transform(&src, &mut ycbcr).unwrap();
+
+                    for (rgb, ycbcr) in rgb
+                        .chunks_exact(dst_layout.channels())
+                        .zip(ycbcr.chunks_exact(dst_layout.channels()))
+                    {
+                        assert_eq!(reference_ycbcr(rgb, matrix), ycbcr[..3], "{rgb:?}");
+                        if dst_layout == Layout::Rgba {
+                            assert_eq!(rgb[3], ycbcr[3]);
+                        }
+                    }
+                }
+            }
+        }
+
+        let options = TransformOptions {
+            ycbcr_output: Some(YCbCrMatrix::Bt601),
+            ..Default::default()
+        };
+        assert!(
+            srgb_profile
+                .create_transform_8bit(
+                    Layout::Rgb,
+                    &ColorProfile::new_gray_with_gamma(2.2),
+                    Layout::Gray,
+                    options
+                )
+                .is_err()
+        );
+    }
+
+    fn srgb_encode(v: f32) -> f32 {
+        if v <= 0.0031308 {
+            v * 12.92
+        } else {
+            1.055 * v.powf(1. / 2.4) - 0.055
+        }
+    }
+
+    #[test]
+    fn test_surround_compensation_dim_to_dark() {
+        let srgb = ColorProfile::new_srgb();
+        let transform = srgb
+            .create_transform_f32(
+                Layout::Rgb,
+                &srgb,
+                Layout::Rgb,
+                TransformOptions {
+                    surround_compensation: Some(SurroundCompensation {
+                        source: Surround::Dim,
+                        destination: Surround::Dark,
+                    }),
+                    ..TransformOptions::default()
+                },
+            )
+            .unwrap();
+        let mid_gray = srgb_encode(0.18);
+        let src = [mid_gray; 3];
+        let mut dst = [0f32; 3];
+        transform.transform(&src, &mut dst).unwrap();
+        let expected = srgb_encode(0.18f32.powf(1.09));
+        for &v in dst.iter() {
+            assert!(
+                (v - expected).abs() < 1e-3,
+                "Mid-gray should map to {expected}, but it was {v}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_surround_compensation_identity_is_noop() {
+        let srgb = ColorProfile::new_srgb();
+        let bt2020 = ColorProfile::new_bt2020();
+        let src = (0..=255u8)
+            .flat_map(|x| [x, 255 - x, x / 2])
+            .collect::<Vec<u8>>();
+        let mut reference = vec![0u8; src.len()];
+        bt2020
+            .create_transform_8bit(Layout::Rgb, &srgb, Layout::Rgb, TransformOptions::default())
+            .unwrap()
+            .transform(&src, &mut reference)
+            .unwrap();
+        for surround in [Surround::Dark, Surround::Dim, Surround::Average] {
+            let mut dst = vec![0u8; src.len()];
+            bt2020
+                .create_transform_8bit(
+                    Layout::Rgb,
+                    &srgb,
+                    Layout::Rgb,
+                    TransformOptions {
+                        surround_compensation: Some(SurroundCompensation {
+                            source: surround,
+                            destination: surround,
+                        }),
+                        ..TransformOptions::default()
+                    },
+                )
+                .unwrap()
+                .transform(&src, &mut dst)
+                .unwrap();
+            assert_eq!(dst, reference);
+        }
+    }
+
+    #[test]
+    fn test_transform_saturates_values_above_bit_depth() {
+        use crate::dispatch::{KernelFamily, with_forced_kernel};
+        let srgb = ColorProfile::new_srgb();
+        let bt2020 = ColorProfile::new_bt2020();
+        let gray = ColorProfile::new_gray_with_gamma(2.2);
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let cases = [
+            (&bt2020, Layout::Rgb, &srgb, Layout::Rgb),
+            (&bt2020, Layout::Rgba, &srgb, Layout::Rgba),
+            (&gray, Layout::Gray, &srgb, Layout::Rgb),
+            (&srgb, Layout::Rgb, &gray, Layout::Gray),
+            (&cmyk, Layout::Rgba, &srgb, Layout::Rgb),
+            (&srgb, Layout::Rgb, &cmyk, Layout::Rgba),
+        ];
+        for family in KernelFamily::available() {
+            for prefer_fixed_point in [false, true] {
+                let options = TransformOptions {
+                    prefer_fixed_point,
+                    ..Default::default()
+                };
+                for bit_depth in [10, 12] {
+                    let max_value = (1u16 << bit_depth) - 1;
+                    for &(src_pr, src_layout, dst_pr, dst_layout) in cases.iter() {
+                        let transform = with_forced_kernel(family, || {
+                            if bit_depth == 10 {
+                                src_pr
+                                    .create_transform_10bit(src_layout, dst_pr, dst_layout, options)
+                            } else {
+                                src_pr
+                                    .create_transform_12bit(src_layout, dst_pr, dst_layout, options)
+                            }
+                        })
+                        .unwrap();
+                        let src = [0, 17, max_value, max_value + 1, max_value * 2, 65535]
+                            .iter()
+                            .cycle()
+                            .take(src_layout.channels() * 16)
+                            .copied()
+                            .collect::<Vec<u16>>();
+                        let clamped = src
+                            .iter()
+                            .enumerate()
+                            .map(|(i, &v)| {
+                                if src_layout.has_alpha() && dst_layout.has_alpha() && i % 4 == 3 {
+                                    v
+                                } else {
+                                    v.min(max_value)
+                                }
+                            })
+                            .collect::<Vec<u16>>();
+                        let mut dst = vec![0u16; dst_layout.channels() * 16];
+                        let mut reference = vec![0u16; dst_layout.channels() * 16];
+                        transform.transform(&src, &mut dst).unwrap();
+                        transform.transform(&clamped, &mut reference).unwrap();
+                        assert_eq!(
+                            dst, reference,
+                            "{family:?}, fixed point {prefer_fixed_point}, {bit_depth} bit, {src_layout:?} -> {dst_layout:?}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn pack_rgb_layout<T: Copy>(pixels: &[[T; 4]], layout: Layout) -> Vec<T> {
+        let mut packed = Vec::with_capacity(pixels.len() * layout.channels());
+        for pixel in pixels.iter() {
+            let mut dst = [pixel[0]; 4];
+            dst[layout.r_i()] = pixel[0];
+            dst[layout.g_i()] = pixel[1];
+            dst[layout.b_i()] = pixel[2];
+            if layout.has_alpha() {
+                dst[layout.a_i()] = pixel[3];
+            }
+            packed.extend_from_slice(&dst[..layout.channels()]);
+        }
+        packed
+    }
+
+    fn unpack_rgb_layout<T: Copy>(packed: &[T], layout: Layout) -> Vec<[T; 3]> {
+        packed
+            .chunks_exact(layout.channels())
+            .map(|src| [src[layout.r_i()], src[layout.g_i()], src[layout.b_i()]])
+            .collect()
+    }
+
+    #[test]
+    fn test_transform_bgr_layouts_match_rgb() {
+        let srgb = ColorProfile::new_srgb();
+        let bt2020 = ColorProfile::new_bt2020();
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let pixels = (0..64u32)
+            .map(|i| {
+                [
+                    (i * 4) as u8,
+                    (255 - i * 3) as u8,
+                    (i * 37 % 256) as u8,
+                    (i * 11) as u8,
+                ]
+            })
+            .collect::<Vec<[u8; 4]>>();
+        let layouts = [
+            Layout::Rgb,
+            Layout::Rgba,
+            Layout::Bgr,
+            Layout::Bgra,
+            Layout::Argb,
+            Layout::Abgr,
+        ];
+        for prefer_fixed_point in [false, true] {
+            let options = TransformOptions {
+                prefer_fixed_point,
+                ..Default::default()
+            };
+            let reference_transform = bt2020
+                .create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgba, options)
+                .unwrap();
+            let reference_src = pack_rgb_layout(&pixels, Layout::Rgba);
+            let mut reference = vec![0u8; reference_src.len()];
+            reference_transform
+                .transform(&reference_src, &mut reference)
+                .unwrap();
+            let reference = unpack_rgb_layout(&reference, Layout::Rgba);
+            for src_layout in layouts {
+                for dst_layout in layouts {
+                    let transform = bt2020
+                        .create_transform_8bit(src_layout, &srgb, dst_layout, options)
+                        .unwrap();
+                    let src = pack_rgb_layout(&pixels, src_layout);
+                    let mut dst = vec![0u8; pixels.len() * dst_layout.channels()];
+                    transform.transform(&src, &mut dst).unwrap();
+                    assert_eq!(
+                        unpack_rgb_layout(&dst, dst_layout),
+                        reference,
+                        "{src_layout:?} -> {dst_layout:?}, fixed point {prefer_fixed_point}"
+                    );
+                    if src_layout.has_alpha() && dst_layout.has_alpha() {
+                        for (pixel, dst) in pixels.iter().zip(dst.chunks_exact(4)) {
+                            assert_eq!(pixel[3], dst[dst_layout.a_i()]);
+                        }
+                    }
+                }
+            }
+        }
+
+        let pixels16 = pixels
+            .iter()
+            .map(|p| p.map(|x| x as u16 * 257))
+            .collect::<Vec<[u16; 4]>>();
+        let mut reference = vec![0u16; pixels16.len() * 3];
+        bt2020
+            .create_transform_16bit(Layout::Rgb, &srgb, Layout::Rgb, TransformOptions::default())
+            .unwrap()
+            .transform(&pack_rgb_layout(&pixels16, Layout::Rgb), &mut reference)
+            .unwrap();
+        let mut dst = vec![0u16; pixels16.len() * 4];
+        bt2020
+            .create_transform_16bit(
+                Layout::Bgr,
+                &srgb,
+                Layout::Bgra,
+                TransformOptions::default(),
+            )
+            .unwrap()
+            .transform(&pack_rgb_layout(&pixels16, Layout::Bgr), &mut dst)
+            .unwrap();
+        assert_eq!(
+            unpack_rgb_layout(&dst, Layout::Bgra),
+            unpack_rgb_layout(&reference, Layout::Rgb)
+        );
+
+        // LUT based paths
+        let cmyk_src = pixels.iter().flatten().copied().collect::<Vec<u8>>();
+        let mut reference = vec![0u8; pixels.len() * 3];
+        cmyk.create_transform_8bit(
+            Layout::Rgba,
+            &srgb,
+            Layout::Rgb,
+            TransformOptions::default(),
+        )
+        .unwrap()
+        .transform(&cmyk_src, &mut reference)
+        .unwrap();
+        let mut dst = vec![0u8; pixels.len() * 3];
+        cmyk.create_transform_8bit(
+            Layout::Rgba,
+            &srgb,
+            Layout::Bgr,
+            TransformOptions::default(),
+        )
+        .unwrap()
+        .transform(&cmyk_src, &mut dst)
+        .unwrap();
+        assert_eq!(
+            unpack_rgb_layout(&dst, Layout::Bgr),
+            unpack_rgb_layout(&reference, Layout::Rgb)
+        );
+
+        let mut reference = vec![0u8; pixels.len() * 4];
+        srgb.create_transform_8bit(
+            Layout::Rgb,
+            &cmyk,
+            Layout::Rgba,
+            TransformOptions::default(),
+        )
+        .unwrap()
+        .transform(&pack_rgb_layout(&pixels, Layout::Rgb), &mut reference)
+        .unwrap();
+        let mut dst = vec![0u8; pixels.len() * 4];
+        srgb.create_transform_8bit(
+            Layout::Bgra,
+            &cmyk,
+            Layout::Rgba,
+            TransformOptions::default(),
+        )
+        .unwrap()
+        .transform(&pack_rgb_layout(&pixels, Layout::Bgra), &mut dst)
+        .unwrap();
+        assert_eq!(dst, reference);
+    }
+
+    #[test]
+    fn test_transform_gray_bgr_layouts() {
+        let srgb = ColorProfile::new_srgb();
+        let gray = ColorProfile::new_gray_with_gamma(2.2);
+        let pixels = (0..=255u8)
+            .map(|x| [x, x / 2, 255 - x, x])
+            .collect::<Vec<[u8; 4]>>();
+
+        let mut reference = vec![0u8; pixels.len()];
+        srgb.create_transform_8bit(
+            Layout::Rgb,
+            &gray,
+            Layout::Gray,
+            TransformOptions::default(),
+        )
+        .unwrap()
+        .transform(&pack_rgb_layout(&pixels, Layout::Rgb), &mut reference)
+        .unwrap();
+        let mut dst = vec![0u8; pixels.len()];
+        srgb.create_transform_8bit(
+            Layout::Bgra,
+            &gray,
+            Layout::Gray,
+            TransformOptions::default(),
+        )
+        .unwrap()
+        .transform(&pack_rgb_layout(&pixels, Layout::Bgra), &mut dst)
+        .unwrap();
+        assert_eq!(dst, reference);
+
+        let src = (0..=255u8).collect::<Vec<u8>>();
+        let mut reference = vec![0u8; src.len() * 3];
+        gray.create_transform_8bit(
+            Layout::Gray,
+            &srgb,
+            Layout::Rgb,
+            TransformOptions::default(),
+        )
+        .unwrap()
+        .transform(&src, &mut reference)
+        .unwrap();
+        let mut dst = vec![0u8; src.len() * 4];
+        gray.create_transform_8bit(
+            Layout::Gray,
+            &srgb,
+            Layout::Bgra,
+            TransformOptions::default(),
+        )
+        .unwrap()
+        .transform(&src, &mut dst)
+        .unwrap();
+        assert_eq!(
+            unpack_rgb_layout(&dst, Layout::Bgra),
+            unpack_rgb_layout(&reference, Layout::Rgb)
+        );
+        assert!(dst.chunks_exact(4).all(|x| x[3] == 255));
+    }
+
+    #[test]
+    fn test_transform_lazy_gamma_tables() {
+        use crate::dispatch::{KernelFamily, with_forced_kernel};
+
+        let srgb = ColorProfile::new_srgb();
+        // Sampled curve has no closed form inverse
+        let mut sampled = ColorProfile::new_bt2020();
+        sampled.green_trc = Some(ToneReprCurve::Lut(
+            (0..1024u32)
+                .map(|i| ((i as f32 / 1023.).powf(2.4) * 65535.).round() as u16)
+                .collect(),
+        ));
+        let src = (0..8192u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 16) as u16)
+            .collect::<Vec<u16>>();
+
+        with_forced_kernel(KernelFamily::Scalar, || {
+            for dst_pr in [
+                ColorProfile::new_bt2020(),
+                ColorProfile::new_adobe_rgb(),
+                ColorProfile::new_display_p3_pq(),
+                ColorProfile::new_pro_photo_rgb(),
+                sampled.clone(),
+            ] {
+                for allow_use_cicp_transfer in [false, true] {
+                    let eager_options = TransformOptions {
+                        allow_use_cicp_transfer,
+                        ..Default::default()
+                    };
+                    let lazy_options = TransformOptions {
+                        lazy_gamma_tables: true,
+                        ..eager_options
+                    };
+                    let eager = srgb
+                        .create_transform_16bit(Layout::Rgba, &dst_pr, Layout::Rgba, eager_options)
+                        .unwrap();
+                    let lazy = srgb
+                        .create_transform_16bit(Layout::Rgba, &dst_pr, Layout::Rgba, lazy_options)
+                        .unwrap();
+
+                    let mut reference = vec![0u16; src.len()];
+                    eager.transform(&src, &mut reference).unwrap();
+                    // Part of tables first, then everything
+                    let mut target = vec![0u16; src.len()];
+                    lazy.transform(&src[..64], &mut target[..64]).unwrap();
+                    assert_eq!(target[..64], reference[..64]);
+                    lazy.transform(&src, &mut target).unwrap();
+                    assert_eq!(target, reference);
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_transform_strided() {
+        let srgb = ColorProfile::new_srgb();
+        let bt2020 = ColorProfile::new_bt2020();
+        let transform = srgb
+            .create_transform_8bit(
+                Layout::Rgb,
+                &bt2020,
+                Layout::Rgba,
+                TransformOptions::default(),
+            )
+            .unwrap();
+
+        let (width, height) = (7usize, 5usize);
+        let (src_stride, dst_stride) = (24usize, 32usize);
+        let src = (0..src_stride * height)
+            .map(|i| (i * 37 % 256) as u8)
+            .collect::<Vec<u8>>();
+        // Padding must be left untouched
+        let mut dst = vec![0xABu8; dst_stride * height];
+        transform
+            .transform_strided(&src, src_stride, &mut dst, dst_stride, width, height)
+            .unwrap();
+
+        for y in 0..height {
+            let mut reference = vec![0u8; width * 4];
+            transform
+                .transform(
+                    &src[y * src_stride..y * src_stride + width * 3],
+                    &mut reference,
+                )
+                .unwrap();
+            let row = &dst[y * dst_stride..(y + 1) * dst_stride];
+            assert_eq!(row[..width * 4], reference);
+            assert!(row[width * 4..].iter().all(|&x| x == 0xAB));
+        }
+
+        assert_eq!(
+            transform.transform_strided(&src, 20, &mut dst, dst_stride, width, height),
+            Err(CmsError::InvalidStride)
+        );
+        assert_eq!(
+            transform.transform_strided(&src, src_stride, &mut dst, 27, width, height),
+            Err(CmsError::InvalidStride)
+        );
+        assert_eq!(
+            transform.transform_strided(
+                &src[..100],
+                src_stride,
+                &mut dst,
+                dst_stride,
+                width,
+                height
+            ),
+            Err(CmsError::LaneSizeMismatch)
+        );
+    }
+
+    #[test]
+    fn test_gray_depth_transform_alpha() {
+        let gray = ColorProfile::new_gray_with_gamma(2.2);
+        let options = TransformOptions {
+            treat_gray_as_alpha: true,
+            ..Default::default()
+        };
+
+        let transform = gray
+            .create_gray_transform_8bit_to_16bit(
+                Layout::GrayAlpha,
+                &gray,
+                Layout::GrayAlpha,
+                options,
+            )
+            .unwrap();
+        let src = (0..=255u8).flat_map(|v| [v, 255 - v]).collect::<Vec<u8>>();
+        let mut dst = vec![0u16; src.len()];
+        transform.transform(&src, &mut dst).unwrap();
+        for (v, dst) in (0..=255u16).zip(dst.chunks_exact(2)) {
+            assert_eq!(dst, [v * 257, (255 - v) * 257]);
+        }
+
+        let transform = gray
+            .create_gray_transform_16bit_to_8bit(Layout::Gray, &gray, Layout::GrayAlpha, options)
+            .unwrap();
+        let src = (0..=u16::MAX).collect::<Vec<u16>>();
+        let mut dst = vec![0u8; src.len() * 2];
+        transform.transform(&src, &mut dst).unwrap();
+        for (&v, dst) in src.iter().zip(dst.chunks_exact(2)) {
+            let expected = ((v as u32 * 255 + 32767) / 65535) as u8;
+            assert_eq!(dst, [expected, 255]);
+        }
+    }
+
+    #[test]
+    fn test_gray_depth_transform_trc() {
+        let gray = ColorProfile::new_gray_with_gamma(2.2);
+        let transform = gray
+            .create_gray_transform_8bit_to_16bit(
+                Layout::Gray,
+                &gray,
+                Layout::Gray,
+                TransformOptions::default(),
+            )
+            .unwrap();
+        let src = (0..=255u8).collect::<Vec<u8>>();
+        let mut dst = vec![0u16; src.len()];
+        transform.transform(&src, &mut dst).unwrap();
+        for (&v, &dst) in src.iter().zip(dst.iter()) {
+            assert!((dst as i32 - v as i32 * 257).abs() <= 257, "{v} -> {dst}");
+        }
+
+        let transform = gray
+            .create_gray_transform_16bit_to_8bit(
+                Layout::Gray,
+                &gray,
+                Layout::Gray,
+                TransformOptions::default(),
+            )
+            .unwrap();
+        let src = (0..=u16::MAX).step_by(3).collect::<Vec<u16>>();
+        let mut dst = vec![0u8; src.len()];
+        transform.transform(&src, &mut dst).unwrap();
+        for (&v, &dst) in src.iter().zip(dst.iter()) {
+            assert!(
+                (dst as i32 - depth_16_to_8(v) as i32).abs() <= 1,
+                "{v} -> {dst}"
+            );
+        }
+
+        assert!(matches!(
+            gray.create_gray_transform_8bit_to_16bit(
+                Layout::Rgb,
+                &gray,
+                Layout::Gray,
+                TransformOptions::default()
+            ),
+            Err(CmsError::InvalidLayout)
+        ));
+        assert!(matches!(
+            gray.create_gray_transform_8bit_to_16bit(
+                Layout::Gray,
+                &ColorProfile::new_srgb(),
+                Layout::Gray,
+                TransformOptions::default()
+            ),
+            Err(CmsError::UnsupportedProfileConnection)
+        ));
+    }
+
+    #[test]
+    fn test_gray_transform_uses_cicp() {
+        use crate::{CicpColorPrimaries, CicpProfile, MatrixCoefficients, TransferCharacteristics};
+
+        let linear = ColorProfile::new_gray_with_gamma(1.0);
+        let mut srgb_coded = linear.clone();
+        srgb_coded.cicp = Some(CicpProfile {
+            color_primaries: CicpColorPrimaries::Bt709,
+            transfer_characteristics: TransferCharacteristics::Srgb,
+            matrix_coefficients: MatrixCoefficients::Bt709,
+            full_range: true,
+        });
+
+        let mut dst = [0u8];
+        for (allow_use_cicp_transfer, expected) in [(true, 55), (false, 128)] {
+            let options = TransformOptions {
+                allow_use_cicp_transfer,
+                ..Default::default()
+            };
+            let transform = srgb_coded
+                .create_transform_8bit(Layout::Gray, &linear, Layout::Gray, options)
+                .unwrap();
+            transform.transform(&[128], &mut dst).unwrap();
+            assert_eq!(dst[0], expected, "{allow_use_cicp_transfer}");
+        }
+    }
+
+    #[test]
+    fn test_transform_in_place() {
+        use crate::dispatch::{KernelFamily, with_forced_kernel};
+
+        let srgb = ColorProfile::new_srgb();
+        let bt2020 = ColorProfile::new_bt2020();
+        let src8 = (0..4032u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 7) as u8)
+            .collect::<Vec<u8>>();
+        let src_f32 = src8.iter().map(|&x| x as f32 / 255.).collect::<Vec<f32>>();
+
+        for family in KernelFamily::available() {
+            with_forced_kernel(family, || {
+                for layout in [Layout::Rgb, Layout::Rgba, Layout::Bgra] {
+                    for prefer_fixed_point in [false, true] {
+                        let options = TransformOptions {
+                            prefer_fixed_point,
+                            ..Default::default()
+                        };
+                        let transform = srgb
+                            .create_transform_8bit(layout, &bt2020, layout, options)
+                            .unwrap();
+                        let mut reference = vec![0u8; src8.len()];
+                        transform.transform(&src8, &mut reference).unwrap();
+                        let mut buf = src8.clone();
+                        transform.transform_in_place(&mut buf).unwrap();
+                        assert_eq!(buf, reference, "{family:?} {layout:?}");
+
+                        let transform = srgb
+                            .create_transform_f32(layout, &bt2020, layout, options)
+                            .unwrap();
+                        let mut reference = vec![0f32; src_f32.len()];
+                        transform.transform(&src_f32, &mut reference).unwrap();
+                        let mut buf = src_f32.clone();
+                        transform.transform_in_place(&mut buf).unwrap();
+                        assert_eq!(buf, reference, "{family:?} {layout:?}");
+                    }
+                }
+            });
+        }
+
+        let gray = ColorProfile::new_gray_with_gamma(2.2);
+        let gray18 = ColorProfile::new_gray_with_gamma(1.8);
+        for layout in [Layout::Gray, Layout::GrayAlpha] {
+            let transform = gray
+                .create_transform_8bit(layout, &gray18, layout, TransformOptions::default())
+                .unwrap();
+            let mut reference = vec![0u8; src8.len()];
+            transform.transform(&src8, &mut reference).unwrap();
+            let mut buf = src8.clone();
+            transform.transform_in_place(&mut buf).unwrap();
+            assert_eq!(buf, reference, "{layout:?}");
+        }
+
+        let transform = srgb
+            .create_transform_8bit(
+                Layout::Rgba,
+                &bt2020,
+                Layout::Rgba,
+                TransformOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(
+            transform.transform_in_place(&mut [0u8; 7]),
+            Err(CmsError::LaneMultipleOfChannels)
+        );
+        let transform = srgb
+            .create_transform_8bit(
+                Layout::Rgb,
+                &bt2020,
+                Layout::Rgba,
+                TransformOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(
+            transform.transform_in_place(&mut [0u8; 12]),
+            Err(CmsError::LaneSizeMismatch)
+        );
+    }
+
+    #[test]
+    fn test_warmup_keeps_results() {
+        use crate::dispatch::{KernelFamily, with_forced_kernel};
+        use crate::{Transform8BitExecutor, Transform16BitExecutor};
+
+        let srgb = ColorProfile::new_srgb();
+        let bt2020 = ColorProfile::new_bt2020();
+        let gray = ColorProfile::new_gray_with_gamma(2.2);
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let src8 = (0..4096u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 7) as u8)
+            .collect::<Vec<u8>>();
+        let src16 = (0..4096u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 5) as u16)
+            .collect::<Vec<u16>>();
+
+        for family in KernelFamily::available() {
+            for prefer_fixed_point in [false, true] {
+                let options = TransformOptions {
+                    prefer_fixed_point,
+                    ..Default::default()
+                };
+                let ycbcr = TransformOptions {
+                    ycbcr_output: Some(YCbCrMatrix::Bt709),
+                    ..options
+                };
+                let make8 = || -> Vec<(Box<Transform8BitExecutor>, usize, usize)> {
+                    vec![
+                        (
+                            bt2020
+                                .create_transform_8bit(Layout::Rgb, &srgb, Layout::Rgb, options)
+                                .unwrap(),
+                            3,
+                            3,
+                        ),
+                        (
+                            cmyk.create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgb, options)
+                                .unwrap(),
+                            4,
+                            3,
+                        ),
+                        (
+                            srgb.create_transform_8bit(Layout::Rgb, &gray, Layout::Gray, options)
+                                .unwrap(),
+                            3,
+                            1,
+                        ),
+                        (
+                            gray.create_transform_8bit(Layout::Gray, &srgb, Layout::Rgba, options)
+                                .unwrap(),
+                            1,
+                            4,
+                        ),
+                        (
+                            srgb.create_transform_8bit(Layout::Rgb, &srgb, Layout::Rgb, ycbcr)
+                                .unwrap(),
+                            3,
+                            3,
+                        ),
+                    ]
+                };
+                with_forced_kernel(family, || {
+                    for ((cold, src_cn, dst_cn), (warm, _, _)) in make8().iter().zip(make8()) {
+                        let src = &src8[..src8.len() / 12 * 12];
+                        let mut reference = vec![0u8; src.len() / src_cn * dst_cn];
+                        cold.transform(src, &mut reference).unwrap();
+                        warm.warmup();
+                        let mut dst = vec![0u8; reference.len()];
+                        warm.transform(src, &mut dst).unwrap();
+                        assert_eq!(dst, reference, "{family:?}, fixed {prefer_fixed_point}");
+                    }
+
+                    let make16 = || -> Box<Transform16BitExecutor> {
+                        cmyk.create_transform_16bit(Layout::Rgba, &bt2020, Layout::Rgba, options)
+                            .unwrap()
+                    };
+                    let mut reference = vec![0u16; src16.len()];
+                    make16().transform(&src16, &mut reference).unwrap();
+                    let warm = make16();
+                    warm.warmup();
+                    let mut dst = vec![0u16; src16.len()];
+                    warm.transform(&src16, &mut dst).unwrap();
+                    assert_eq!(dst, reference, "{family:?}, fixed {prefer_fixed_point}");
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn test_transform_alpha_first_layouts() {
+        use crate::dispatch::{KernelFamily, with_forced_kernel};
+
+        let srgb = ColorProfile::new_srgb();
+        let bt2020 = ColorProfile::new_bt2020();
+        let lut_rgb =
+            ColorProfile::new_from_slice(include_bytes!("../assets/srgb_perceptual.icc")).unwrap();
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let pixels = (0..256u32)
+            .map(|i| {
+                [
+                    i as u8,
+                    (255 - i) as u8,
+                    (i * 37 % 256) as u8,
+                    (i * 11 % 256) as u8,
+                ]
+            })
+            .collect::<Vec<[u8; 4]>>();
+        let rgba = pack_rgb_layout(&pixels, Layout::Rgba);
+
+        for family in KernelFamily::available() {
+            with_forced_kernel(family, || {
+                for prefer_fixed_point in [false, true] {
+                    let options = TransformOptions {
+                        prefer_fixed_point,
+                        ..Default::default()
+                    };
+                    for (src_profile, dst_profile) in
+                        [(&bt2020, &srgb), (&lut_rgb, &srgb), (&srgb, &lut_rgb)]
+                    {
+                        let mut reference = vec![0u8; rgba.len()];
+                        src_profile
+                            .create_transform_8bit(Layout::Rgba, dst_profile, Layout::Rgba, options)
+                            .unwrap()
+                            .transform(&rgba, &mut reference)
+                            .unwrap();
+                        for layout in [Layout::Argb, Layout::Abgr] {
+                            let src = pack_rgb_layout(&pixels, layout);
+                            let mut dst = vec![0u8; src.len()];
+                            src_profile
+                                .create_transform_8bit(layout, dst_profile, layout, options)
+                                .unwrap()
+                                .transform(&src, &mut dst)
+                                .unwrap();
+                            assert_eq!(
+                                unpack_rgb_layout(&dst, layout),
+                                unpack_rgb_layout(&reference, Layout::Rgba),
+                                "{family:?}, {layout:?}, fixed point {prefer_fixed_point}"
+                            );
+                            for (pixel, dst) in pixels.iter().zip(dst.chunks_exact(4)) {
+                                assert_eq!(dst[0], pixel[3]);
+                            }
+                        }
+                    }
+                }
+
+                // CMYK has no alpha, so alpha-first output is opaque
+                let mut reference = vec![0u8; rgba.len()];
+                cmyk.create_transform_8bit(
+                    Layout::Rgba,
+                    &srgb,
+                    Layout::Rgba,
+                    TransformOptions::default(),
+                )
+                .unwrap()
+                .transform(&rgba, &mut reference)
+                .unwrap();
+                let mut dst = vec![0u8; rgba.len()];
+                cmyk.create_transform_8bit(
+                    Layout::Rgba,
+                    &srgb,
+                    Layout::Argb,
+                    TransformOptions::default(),
+                )
+                .unwrap()
+                .transform(&rgba, &mut dst)
+                .unwrap();
+                assert_eq!(
+                    unpack_rgb_layout(&dst, Layout::Argb),
+                    unpack_rgb_layout(&reference, Layout::Rgba)
+                );
+                assert!(dst.chunks_exact(4).all(|x| x[0] == 255));
+
+                let mut reference = vec![0u8; rgba.len()];
+                srgb.create_transform_8bit(
+                    Layout::Rgba,
+                    &cmyk,
+                    Layout::Rgba,
+                    TransformOptions::default(),
+                )
+                .unwrap()
+                .transform(&rgba, &mut reference)
+                .unwrap();
+                let mut dst = vec![0u8; rgba.len()];
+                srgb.create_transform_8bit(
+                    Layout::Abgr,
+                    &cmyk,
+                    Layout::Rgba,
+                    TransformOptions::default(),
+                )
+                .unwrap()
+                .transform(&pack_rgb_layout(&pixels, Layout::Abgr), &mut dst)
+                .unwrap();
+                assert_eq!(dst, reference, "{family:?}");
+            });
+        }
+
+        let gray = ColorProfile::new_gray_with_gamma(2.2);
+        let src = (0..=255u8).flat_map(|x| [x, 255 - x]).collect::<Vec<u8>>();
+        let mut dst = vec![0u8; 256 * 4];
+        gray.create_transform_8bit(
+            Layout::GrayAlpha,
+            &srgb,
+            Layout::Argb,
+            TransformOptions::default(),
+        )
+        .unwrap()
+        .transform(&src, &mut dst)
+        .unwrap();
+        for (src, dst) in src.chunks_exact(2).zip(dst.chunks_exact(4)) {
+            assert_eq!(dst[0], src[1]);
+            assert!(dst[1] == dst[2] && dst[2] == dst[3]);
+        }
+
+        let mut reference = vec![0u8; pixels.len() * 2];
+        srgb.create_transform_8bit(
+            Layout::Rgba,
+            &gray,
+            Layout::GrayAlpha,
+            TransformOptions::default(),
+        )
+        .unwrap()
+        .transform(&rgba, &mut reference)
+        .unwrap();
+        let mut dst = vec![0u8; pixels.len() * 2];
+        srgb.create_transform_8bit(
+            Layout::Argb,
+            &gray,
+            Layout::GrayAlpha,
+            TransformOptions::default(),
+        )
+        .unwrap()
+        .transform(&pack_rgb_layout(&pixels, Layout::Argb), &mut dst)
+        .unwrap();
+        assert_eq!(dst, reference);
+    }
+
+    #[test]
+    fn test_transform_exact_endpoints() {
+        use crate::{curve_from_fn, curve_from_gamma};
+        let srgb_eotf = |x: f32| {
+            if x <= 0.04045 {
+                x / 12.92
+            } else {
+                ((x + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        let with_trc = |mut profile: ColorProfile, trc: ToneReprCurve| {
+            profile.cicp = None;
+            profile.red_trc = Some(trc.clone());
+            profile.green_trc = Some(trc.clone());
+            profile.blue_trc = Some(trc);
+            profile
+        };
+        let pairs = [
+            (
+                ColorProfile::new_srgb(),
+                with_trc(ColorProfile::new_srgb(), curve_from_fn(srgb_eotf)),
+            ),
+            (
+                with_trc(ColorProfile::new_srgb(), curve_from_fn(srgb_eotf)),
+                ColorProfile::new_srgb(),
+            ),
+            (
+                with_trc(ColorProfile::new_bt2020(), curve_from_gamma(2.2)),
+                with_trc(ColorProfile::new_bt2020(), curve_from_fn(|x| x.powf(2.2))),
+            ),
+            (
+                ColorProfile::new_display_p3(),
+                ColorProfile::new_display_p3(),
+            ),
+        ];
+        for (src, dst) in pairs.iter() {
+            let matrix = src.transform_matrix(dst).unwrap();
+            for (r, row) in matrix.v.iter().enumerate() {
+                for (c, &v) in row.iter().enumerate() {
+                    let expected = if r == c { 1. } else { 0. };
+                    assert!((v - expected).abs() < 1e-3, "{:?}", matrix);
+                }
+            }
+
+            let src8 = [255u8, 255, 255, 0, 0, 0];
+            let mut dst8 = [1u8; 6];
+            src.create_transform_8bit(Layout::Rgb, dst, Layout::Rgb, TransformOptions::default())
+                .unwrap()
+                .transform(&src8, &mut dst8)
+                .unwrap();
+            assert_eq!(dst8, src8);
+
+            for bit_depth in [10usize, 12, 16] {
+                let max = ((1u32 << bit_depth) - 1) as u16;
+                let src16 = [max, max, max, 0, 0, 0];
+                let mut dst16 = [1u16; 6];
+                let options = TransformOptions::default();
+                let transform = match bit_depth {
+                    10 => src.create_transform_10bit(Layout::Rgb, dst, Layout::Rgb, options),
+                    12 => src.create_transform_12bit(Layout::Rgb, dst, Layout::Rgb, options),
+                    _ => src.create_transform_16bit(Layout::Rgb, dst, Layout::Rgb, options),
+                };
+                transform.unwrap().transform(&src16, &mut dst16).unwrap();
+                assert_eq!(dst16, src16, "{bit_depth} bit");
+            }
+        }
+    }
+
+    #[test]
+    fn test_transform_rgb_to_cmyk() {
+        let srgb = ColorProfile::new_srgb();
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        assert!(cmyk.lut_b_to_a_perceptual.is_some());
+
+        let src = [255u8, 255, 255, 0, 0, 0, 128, 128, 128, 200, 60, 40];
+        let mut dst = [0u8; 16];
+        srgb.create_transform_8bit(
+            Layout::Rgb,
+            &cmyk,
+            Layout::Rgba,
+            TransformOptions::default(),
+        )
+        .unwrap()
+        .transform(&src, &mut dst)
+        .unwrap();
+        // Paper white has no ink, black is mostly K
+        assert!(dst[..4].iter().all(|&x| x < 8), "white -> {:?}", &dst[..4]);
+        assert!(dst[7] > 200, "black -> {:?}", &dst[4..8]);
+
+        let mut back = [0u8; 12];
+        cmyk.create_transform_8bit(
+            Layout::Rgba,
+            &srgb,
+            Layout::Rgb,
+            TransformOptions::default(),
+        )
+        .unwrap()
+        .transform(&dst, &mut back)
+        .unwrap();
+        for (src, back) in src.chunks_exact(3).zip(back.chunks_exact(3)).skip(2) {
+            for (&a, &b) in src.iter().zip(back.iter()) {
+                assert!(a.abs_diff(b) <= 12, "{src:?} -> {back:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_transform_rgb_to_cmyk_ink_limit() {
+        let srgb = ColorProfile::new_srgb();
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let Some(LutWarehouse::Lut(b2a)) = &cmyk.lut_b_to_a_perceptual else {
+            panic!("B2A0 is expected to be mft2");
+        };
+        let entries = b2a.num_output_table_entries as usize;
+        let output = |channel: usize, v: f32| {
+            let table = &b2a.output_table[channel * entries..(channel + 1) * entries];
+            let pos = v.clamp(0., 1.) * (entries - 1) as f32;
+            let lo = pos.floor() as usize;
+            let hi = (lo + 1).min(entries - 1);
+            table[lo] + (table[hi] - table[lo]) * (pos - lo as f32)
+        };
+        // Total area coverage the separation table was built for
+        let ink_limit = b2a
+            .clut_table
+            .chunks_exact(4)
+            .map(|x| (0..4).map(|c| output(c, x[c])).sum::<f32>())
+            .fold(0f32, f32::max);
+
+        let src = (0..16u8)
+            .flat_map(|r| (0..16u8).flat_map(move |g| (0..16u8).map(move |b| (r, g, b))))
+            .flat_map(|(r, g, b)| [r * 17, g * 17, b * 17])
+            .collect::<Vec<u8>>();
+        let mut dst = vec![0u8; src.len() / 3 * 4];
+        srgb.create_transform_8bit(
+            Layout::Rgb,
+            &cmyk,
+            Layout::Rgba,
+            TransformOptions::default(),
+        )
+        .unwrap()
+        .transform(&src, &mut dst)
+        .unwrap();
+        let max_ink = dst
+            .chunks_exact(4)
+            .map(|x| x.iter().map(|&v| v as f32 / 255.).sum::<f32>())
+            .fold(0f32, f32::max);
+        assert!(max_ink <= ink_limit + 4. / 255., "{max_ink} > {ink_limit}");
+    }
+
+    #[test]
+    fn test_transform_cmyk_to_cmyk() {
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let srgb = ColorProfile::new_srgb();
+        let options = TransformOptions {
+            rendering_intent: RenderingIntent::RelativeColorimetric,
+            ..Default::default()
+        };
+        let transform = cmyk
+            .create_transform_8bit(Layout::Rgba, &cmyk, Layout::Rgba, options)
+            .unwrap();
+        // Inks without black come back as they are
+        let src = [
+            0u8, 0, 0, 0, 128, 0, 0, 0, 0, 128, 0, 0, 0, 0, 128, 0, 60, 40, 20, 0,
+        ];
+        let mut dst = [0u8; 20];
+        transform.transform(&src, &mut dst).unwrap();
+        for (src, dst) in src.chunks_exact(4).zip(dst.chunks_exact(4)) {
+            for (&a, &b) in src.iter().zip(dst.iter()) {
+                assert!(a.abs_diff(b) <= 4, "{src:?} -> {dst:?}");
+            }
+        }
+
+        // Black generation of BToA may differ, but color must not
+        let src = [0u8, 0, 0, 128, 60, 40, 20, 30, 200, 150, 100, 20];
+        let mut dst = [0u8; 12];
+        transform.transform(&src, &mut dst).unwrap();
+        let to_rgb = cmyk
+            .create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgb, options)
+            .unwrap();
+        let mut src_rgb = [0u8; 9];
+        let mut dst_rgb = [0u8; 9];
+        to_rgb.transform(&src, &mut src_rgb).unwrap();
+        to_rgb.transform(&dst, &mut dst_rgb).unwrap();
+        for (&a, &b) in src_rgb.iter().zip(dst_rgb.iter()) {
+            assert!(a.abs_diff(b) <= 6, "{src_rgb:?} -> {dst_rgb:?}");
+        }
+    }
+
+    #[test]
+    fn test_transform_absolute_media_white() {
+        use crate::Chromaticity;
+        let srgb = ColorProfile::new_srgb();
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let relative = TransformOptions {
+            rendering_intent: RenderingIntent::RelativeColorimetric,
+            ..Default::default()
+        };
+        let absolute = TransformOptions {
+            rendering_intent: RenderingIntent::AbsoluteColorimetric,
+            ..Default::default()
+        };
+        let run = |source: &ColorProfile, dest: &ColorProfile, options| {
+            let src = [255u8, 255, 255, 128, 128, 128, 200, 80, 40];
+            let mut dst = [0u8; 9];
+            source
+                .create_transform_8bit(Layout::Rgb, dest, Layout::Rgb, options)
+                .unwrap()
+                .transform(&src, &mut dst)
+                .unwrap();
+            dst
+        };
+
+        // Paper white of the printer differs from the display white, so it is simulated
+        let paper = cmyk.absolute_intent_scale(&srgb);
+        assert!(paper.v.iter().any(|x| (x - 1.).abs() > 1e-3));
+        let to_cmyk = |options| {
+            let src = [255u8, 255, 255];
+            let mut dst = [0u8; 4];
+            srgb.create_transform_8bit(Layout::Rgb, &cmyk, Layout::Rgba, options)
+                .unwrap()
+                .transform(&src, &mut dst)
+                .unwrap();
+            dst
+        };
+        assert_ne!(to_cmyk(relative), to_cmyk(absolute));
+
+        // Same media white points make absolute intent the same as relative one
+        assert_eq!(run(&srgb, &srgb, relative), run(&srgb, &srgb, absolute));
+        let mut d65 = ColorProfile::new_srgb();
+        d65.media_white_point = Some(Chromaticity::D65.to_xyz());
+        let display_p3 = {
+            let mut p3 = ColorProfile::new_display_p3();
+            p3.media_white_point = Some(Chromaticity::D65.to_xyz());
+            p3
+        };
+        let relative_p3 = run(&d65, &display_p3, relative);
+        let absolute_p3 = run(&d65, &display_p3, absolute);
+        for (&a, &b) in relative_p3.iter().zip(absolute_p3.iter()) {
+            assert!(a.abs_diff(b) <= 1, "{relative_p3:?} -> {absolute_p3:?}");
+        }
+    }
+
+    #[test]
+    fn test_transform_absolute_paper_white_tint() {
+        // ProPhoto RGB has D50 media white, warmer than D65 of sRGB
+        let warm = ColorProfile::new_pro_photo_rgb();
+        let srgb = ColorProfile::new_srgb();
+        let run = |intent| {
+            let options = TransformOptions {
+                rendering_intent: intent,
+                ..Default::default()
+            };
+            let src = [255u8, 255, 255, 128, 128, 128];
+            let mut dst = [0u8; 6];
+            warm.create_transform_8bit(Layout::Rgb, &srgb, Layout::Rgb, options)
+                .unwrap()
+                .transform(&src, &mut dst)
+                .unwrap();
+            let src = [1f32, 1., 1.];
+            let mut dst_f32 = [0f32; 3];
+            warm.create_transform_f32(Layout::Rgb, &srgb, Layout::Rgb, options)
+                .unwrap()
+                .transform(&src, &mut dst_f32)
+                .unwrap();
+            (dst, dst_f32)
+        };
+
+        let (relative, relative_f32) = run(RenderingIntent::RelativeColorimetric);
+        assert_eq!(&relative[..3], &[255, 255, 255]);
+        assert!(relative_f32.iter().all(|&x| (x - 1.).abs() < 1e-3));
+        for pair in relative[3..].windows(2) {
+            assert!(pair[0].abs_diff(pair[1]) <= 1, "{relative:?}");
+        }
+
+        // White of the source is kept, so it comes out yellowish instead of neutral
+        let (absolute, absolute_f32) = run(RenderingIntent::AbsoluteColorimetric);
+        assert_eq!(absolute[0], 255);
+        assert!(absolute[2] < 230, "{absolute:?}");
+        assert!(absolute[3] > absolute[5] + 10, "{absolute:?}");
+        assert!(absolute_f32[0] > absolute_f32[1] && absolute_f32[1] > absolute_f32[2]);
+        assert!(absolute_f32[2] < 0.9, "{absolute_f32:?}");
+    }
+
+    #[test]
+    fn test_transform_lut_sampling_precision() {
+        let srgb = ColorProfile::new_srgb();
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let options = TransformOptions::default();
+        let reference = cmyk
+            .create_transform_f32(Layout::Rgba, &srgb, Layout::Rgb, options)
+            .unwrap();
+        for bit_depth in [10u32, 12, 16] {
+            let max_value = ((1u32 << bit_depth) - 1) as f32;
+            let transform = match bit_depth {
+                10 => cmyk.create_transform_10bit(Layout::Rgba, &srgb, Layout::Rgb, options),
+                12 => cmyk.create_transform_12bit(Layout::Rgba, &srgb, Layout::Rgb, options),
+                _ => cmyk.create_transform_16bit(Layout::Rgba, &srgb, Layout::Rgb, options),
+            }
+            .unwrap();
+            let src = (0..=4096u32)
+                .map(|x| (x as f32 / 4096. * max_value).round() as u16)
+                .flat_map(|x| [x, x / 2, max_value as u16 - x, x / 4])
+                .collect::<Vec<u16>>();
+            let mut dst = vec![0u16; src.len() / 4 * 3];
+            transform.transform(&src, &mut dst).unwrap();
+
+            let src_f32 = src
+                .iter()
+                .map(|&x| x as f32 / max_value)
+                .collect::<Vec<f32>>();
+            let src_8bit = src_f32
+                .iter()
+                .map(|&x| (x * 255.).round() / 255.)
+                .collect::<Vec<f32>>();
+            let mut dst_f32 = vec![0f32; dst.len()];
+            let mut dst_8bit = vec![0f32; dst.len()];
+            reference.transform(&src_f32, &mut dst_f32).unwrap();
+            reference.transform(&src_8bit, &mut dst_8bit).unwrap();
+
+            let max_error = |values: &mut dyn Iterator<Item = f32>| {
+                values
+                    .zip(dst_f32.iter())
+                    .map(|(v, &r)| (v - r * max_value).abs())
+                    .fold(0f32, f32::max)
+            };
+            let error = max_error(&mut dst.iter().map(|&x| x as f32));
+            // What the LUT path would do if it sampled inputs at 8-bit resolution
+            let error_8bit = max_error(&mut dst_8bit.iter().map(|&x| x * max_value));
+            assert!(
+                error * 2. < error_8bit,
+                "{bit_depth} bit: error {error}, 8-bit sampling error {error_8bit}"
+            );
+            assert!(error <= 1., "{bit_depth} bit: error {error}");
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_transform_parallel() {
+        let srgb = ColorProfile::new_srgb();
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let transform = cmyk
+            .create_transform_8bit(
+                Layout::Rgba,
+                &srgb,
+                Layout::Rgb,
+                TransformOptions::default(),
+            )
+            .unwrap();
+        // Not a multiple of chunk size, so the last chunk is partial
+        let pixels = 100_003usize;
+        let src = (0..pixels * 4)
+            .map(|x| (x.wrapping_mul(2654435761) >> 7) as u8)
+            .collect::<Vec<u8>>();
+        let mut sequential = vec![0u8; pixels * 3];
+        let mut parallel = vec![0u8; pixels * 3];
+        transform.transform(&src, &mut sequential).unwrap();
+        transform.transform_parallel(&src, &mut parallel).unwrap();
+        assert_eq!(sequential, parallel);
+
+        assert_eq!(
+            transform.transform_parallel(&src[1..], &mut parallel[1..]),
+            Err(CmsError::LaneMultipleOfChannels)
+        );
+        assert_eq!(
+            transform.transform_parallel(&src[4..], &mut parallel),
+            Err(CmsError::LaneSizeMismatch)
+        );
+    }
+
+    #[test]
+    fn test_transform_matrix_intents_are_relative() {
+        let srgb = ColorProfile::new_srgb();
+        let pairs = [
+            (ColorProfile::new_pro_photo_rgb(), srgb.clone()),
+            (srgb.clone(), ColorProfile::new_display_p3()),
+            (ColorProfile::new_bt2020(), ColorProfile::new_adobe_rgb()),
+        ];
+        let with_intent = |rendering_intent| TransformOptions {
+            rendering_intent,
+            ..Default::default()
+        };
+        let relative = with_intent(RenderingIntent::RelativeColorimetric);
+        let src_8 = (0..=255u8)
+            .flat_map(|x| [x, 255 - x, x / 3])
+            .collect::<Vec<u8>>();
+        let src_16 = src_8.iter().map(|&x| x as u16 * 257).collect::<Vec<u16>>();
+        let src_f32 = src_8.iter().map(|&x| x as f32 / 255.).collect::<Vec<f32>>();
+        for (source, dest) in pairs.iter() {
+            let run = |options: TransformOptions| {
+                let mut dst_8 = vec![0u8; src_8.len()];
+                source
+                    .create_transform_8bit(Layout::Rgb, dest, Layout::Rgb, options)
+                    .unwrap()
+                    .transform(&src_8, &mut dst_8)
+                    .unwrap();
+                let mut dst_16 = vec![0u16; src_16.len()];
+                source
+                    .create_transform_16bit(Layout::Rgb, dest, Layout::Rgb, options)
+                    .unwrap()
+                    .transform(&src_16, &mut dst_16)
+                    .unwrap();
+                let mut dst_f32 = vec![0f32; src_f32.len()];
+                source
+                    .create_transform_f32(Layout::Rgb, dest, Layout::Rgb, options)
+                    .unwrap()
+                    .transform(&src_f32, &mut dst_f32)
+                    .unwrap();
+                (dst_8, dst_16, dst_f32)
+            };
+            let reference = run(relative);
+            for intent in [RenderingIntent::Perceptual, RenderingIntent::Saturation] {
+                assert!(run(with_intent(intent)) == reference, "{intent:?}");
+                assert_eq!(
+                    source.effective_rendering_intent(dest, with_intent(intent)),
+                    RenderingIntent::RelativeColorimetric
+                );
+            }
+            // Substitution doesn't touch clipping
+            let clamped = TransformOptions {
+                clamp_policy: ClampPolicy::ClampToRange,
+                ..with_intent(RenderingIntent::Perceptual)
+            };
+            assert_eq!(
+                source.effective_clamp_policy_f32(dest, clamped),
+                ClampPolicy::ClampToRange
+            );
+        }
+
+        let absolute = with_intent(RenderingIntent::AbsoluteColorimetric);
+        let warm = &pairs[0].0;
+        assert_eq!(
+            warm.effective_rendering_intent(&srgb, absolute),
+            RenderingIntent::AbsoluteColorimetric
+        );
+        let gray = ColorProfile::new_gray_with_gamma(2.2);
+        assert_eq!(
+            gray.effective_rendering_intent(&srgb, absolute),
+            RenderingIntent::RelativeColorimetric
+        );
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let perceptual = with_intent(RenderingIntent::Perceptual);
+        assert_eq!(
+            cmyk.effective_rendering_intent(&srgb, perceptual),
+            RenderingIntent::Perceptual
+        );
+    }
+
+    #[test]
+    fn test_transform_falls_back_to_perceptual_lut() {
+        let srgb = ColorProfile::new_srgb();
+        let mut cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        cmyk.lut_a_to_b_colorimetric = None;
+        cmyk.lut_a_to_b_saturation = None;
+        cmyk.lut_b_to_a_colorimetric = None;
+        cmyk.lut_b_to_a_saturation = None;
+
+        let src = (0..=255u8)
+            .flat_map(|x| [x, 255 - x, x / 2])
+            .collect::<Vec<u8>>();
+        let perceptual = TransformOptions::default();
+        let mut reference = vec![0u8; src.len() / 3 * 4];
+        srgb.create_transform_8bit(Layout::Rgb, &cmyk, Layout::Rgba, perceptual)
+            .unwrap()
+            .transform(&src, &mut reference)
+            .unwrap();
+        let mut reference_back = vec![0u8; src.len()];
+        cmyk.create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgb, perceptual)
+            .unwrap()
+            .transform(&reference, &mut reference_back)
+            .unwrap();
+        for intent in [
+            RenderingIntent::RelativeColorimetric,
+            RenderingIntent::AbsoluteColorimetric,
+            RenderingIntent::Saturation,
+        ] {
+            let options = TransformOptions {
+                rendering_intent: intent,
+                ..Default::default()
+            };
+            let mut dst = vec![0u8; reference.len()];
+            srgb.create_transform_8bit(Layout::Rgb, &cmyk, Layout::Rgba, options)
+                .unwrap()
+                .transform(&src, &mut dst)
+                .unwrap();
+            assert_eq!(dst, reference, "{intent:?}");
+            let mut back = vec![0u8; src.len()];
+            cmyk.create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgb, options)
+                .unwrap()
+                .transform(&dst, &mut back)
+                .unwrap();
+            assert_eq!(back, reference_back, "{intent:?}");
+        }
+    }
+
+    fn lut8_profile(matrix: Matrix3f, clut: impl Fn([f32; 3]) -> [f32; 3]) -> ColorProfile {
+        let ramp = (0..256).map(|x| x as f32 / 255.).collect::<Vec<f32>>();
+        let mut clut_table = Vec::new();
+        for x in 0..2 {
+            for y in 0..2 {
+                for z in 0..2 {
+                    clut_table.extend_from_slice(&clut([x as f32, y as f32, z as f32]));
+                }
+            }
+        }
+        let lut = LutWarehouse::Lut(LutDataType {
+            num_input_channels: 3,
+            num_output_channels: 3,
+            num_clut_grid_points: 2,
+            matrix,
+            num_input_table_entries: 256,
+            num_output_table_entries: 256,
+            input_table: ramp.repeat(3),
+            clut_table,
+            output_table: ramp.repeat(3),
+            lut_type: LutType::Lut8,
+        });
+        let profile = ColorProfile {
+            color_space: DataColorSpace::Rgb,
+            pcs: DataColorSpace::Xyz,
+            lut_a_to_b_perceptual: Some(lut.clone()),
+            lut_b_to_a_perceptual: Some(lut),
+            ..Default::default()
+        };
+        ColorProfile::new_from_slice(&profile.encode().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_transform_lut16_legacy_lab() {
+        // L = 100, a = b = 0 in legacy 16-bit Lab encoding is 0xFF00, 0x8000, 0x8000
+        let white = [65280. / 65535., 32768. / 65535., 32768. / 65535.];
+        let ramp = [0f32, 1.];
+        let lut = LutWarehouse::Lut(LutDataType {
+            num_input_channels: 4,
+            num_output_channels: 3,
+            num_clut_grid_points: 2,
+            matrix: Matrix3f::IDENTITY,
+            num_input_table_entries: 2,
+            num_output_table_entries: 2,
+            input_table: ramp.repeat(4),
+            clut_table: white.repeat(16),
+            output_table: ramp.repeat(3),
+            lut_type: LutType::Lut16,
+        });
+        let profile = ColorProfile {
+            color_space: DataColorSpace::Cmyk,
+            pcs: DataColorSpace::Lab,
+            lut_a_to_b_perceptual: Some(lut),
+            ..Default::default()
+        };
+        // Encoded as v4, lut16Type must still use legacy encoding
+        let cmyk = ColorProfile::new_from_slice(&profile.encode().unwrap()).unwrap();
+        let srgb = ColorProfile::new_srgb();
+        let mut dst = [0u8; 6];
+        cmyk.create_transform_8bit(
+            Layout::Rgba,
+            &srgb,
+            Layout::Rgb,
+            TransformOptions::default(),
+        )
+        .unwrap()
+        .transform(&[0, 0, 0, 0, 128, 30, 200, 255], &mut dst)
+        .unwrap();
+        assert_eq!(dst, [255; 6]);
+    }
+
+    #[test]
+    fn test_transform_lut8() {
+        let swap = Matrix3f {
+            v: [[0., 0., 1.], [0., 1., 0.], [1., 0., 0.]],
+        };
+        let swapped = lut8_profile(swap, |v| v);
+        match swapped.lut_b_to_a_perceptual.as_ref() {
+            Some(LutWarehouse::Lut(lut)) => {
+                assert_eq!(lut.lut_type, LutType::Lut8);
+                assert!(lut.matrix.test_equality(swap));
+                assert_eq!(lut.input_table.len(), 256 * 3);
+                assert!((lut.input_table[128] - 128. / 255.).abs() < 1e-6);
+            }
+            _ => panic!("lut8Type expected"),
+        }
+        let clut_swapped = lut8_profile(Matrix3f::IDENTITY, |v| [v[2], v[1], v[0]]);
+        let identity = lut8_profile(Matrix3f::IDENTITY, |v| v);
+
+        let srgb = ColorProfile::new_srgb();
+        let src = [255u8, 0, 0, 30, 200, 90, 128, 128, 128, 10, 20, 240];
+        let run = |from: &ColorProfile, to: &ColorProfile| {
+            let mut dst = [0u8; 12];
+            from.create_transform_8bit(Layout::Rgb, to, Layout::Rgb, TransformOptions::default())
+                .unwrap()
+                .transform(&src, &mut dst)
+                .unwrap();
+            dst
+        };
+
+        // PCS is XYZ, so matrix of B2A is applied before input curves
+        let by_matrix = run(&srgb, &swapped);
+        assert_samples_similar(&by_matrix, &run(&srgb, &clut_swapped), 1.);
+        assert_ne!(by_matrix, run(&srgb, &identity));
+
+        // Device side of A2B is RGB, thus matrix must be ignored
+        assert_eq!(run(&swapped, &srgb), run(&identity, &srgb));
+    }
+
+    /// v4 camera like RGB profile with non-trivial mAB CLUT and Lab PCS
+    fn rgb_mab_profile() -> ColorProfile {
+        const GRID: usize = 9;
+        let mut clut = Vec::with_capacity(GRID * GRID * GRID * 3);
+        for r in 0..GRID {
+            for g in 0..GRID {
+                for b in 0..GRID {
+                    let [r, g, b] = [r, g, b].map(|x| x as f32 / (GRID - 1) as f32);
+                    let y = 0.25 * r + 0.65 * g + 0.1 * b;
+                    clut.push(y.powf(1. / 2.4));
+                    clut.push(0.5 + 0.35 * (r - g) * (1. - 0.3 * b));
+                    clut.push(0.5 + 0.3 * (g * g - b));
+                }
+            }
+        }
+        let mut grid_points = [0u8; 16];
+        grid_points[..3].fill(GRID as u8);
+        let profile = ColorProfile {
+            profile_class: ProfileClass::InputDevice,
+            color_space: DataColorSpace::Rgb,
+            pcs: DataColorSpace::Lab,
+            lut_a_to_b_perceptual: Some(LutWarehouse::MCurves(LutMCurvesType {
+                num_input_channels: 3,
+                num_output_channels: 3,
+                grid_points,
+                clut,
+                a_curves: vec![ToneReprCurve::Parametric(vec![2.2]); 3],
+                b_curves: vec![ToneReprCurve::Lut(Vec::new()); 3],
+                m_curves: Vec::new(),
+                matrix: Matrix3f::IDENTITY,
+                bias: Vector3f::default(),
+            })),
+            ..Default::default()
+        };
+        ColorProfile::new_from_slice(&profile.encode().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_rgb_lut_source_across_depths() {
+        use crate::conversions::CompressForLut;
+        // Maximum of every depth addresses the last grid node
+        assert_eq!(1023u16.compress_lut::<10>(), 65535);
+        assert_eq!(4095u16.compress_lut::<12>(), 65535);
+        assert_eq!(5000u16.compress_lut::<12>(), 65535);
+        assert_eq!(512u16.compress_lut::<10>(), 32800);
+
+        let camera = rgb_mab_profile();
+        assert!(matches!(
+            camera.lut_a_to_b_perceptual,
+            Some(LutWarehouse::MCurves(_))
+        ));
+        let srgb = ColorProfile::new_srgb();
+        let options = TransformOptions::default();
+        let exact_transform = camera
+            .create_transform_f32(Layout::Rgb, &srgb, Layout::Rgb, options)
+            .unwrap();
+
+        let mut rng = rand::rng();
+        let src = (0..3 * 4096)
+            .map(|_| rng.random_range(0f32..=1.))
+            .collect::<Vec<_>>();
+        // Reference is evaluated from the same quantized input, so only output precision counts
+        let exact_of = |max: f32| {
+            let src = src
+                .iter()
+                .map(|&x| (x * max).round() / max)
+                .collect::<Vec<_>>();
+            let mut exact = vec![0f32; src.len()];
+            exact_transform.transform(&src, &mut exact).unwrap();
+            exact
+        };
+        // CLUT is not trivial, so LUT must actually be evaluated
+        let exact = exact_of(65535.);
+        assert!(
+            src.iter()
+                .zip(exact.iter())
+                .any(|(s, d)| (s - d).abs() > 0.1)
+        );
+
+        let src8 = src
+            .iter()
+            .map(|&x| (x * 255.).round() as u8)
+            .collect::<Vec<_>>();
+        let mut dst8 = vec![0u8; src.len()];
+        camera
+            .create_transform_8bit(Layout::Rgb, &srgb, Layout::Rgb, options)
+            .unwrap()
+            .transform(&src8, &mut dst8)
+            .unwrap();
+        let dst8 = dst8.iter().map(|&x| x as f32 / 255.).collect::<Vec<_>>();
+        assert_samples_similar(&dst8, &exact_of(255.), 1.5 / 255.);
+
+        // Tolerances are in codes of each depth, dark tones of 16-bit output meet gamma table steps
+        for (bit_depth, tolerance) in [(10, 2.), (12, 3.), (16, 24.)] {
+            let max = ((1u32 << bit_depth) - 1) as f32;
+            let create = match bit_depth {
+                10 => ColorProfile::create_transform_10bit,
+                12 => ColorProfile::create_transform_12bit,
+                _ => ColorProfile::create_transform_16bit,
+            };
+            let src16 = src
+                .iter()
+                .map(|&x| (x * max).round() as u16)
+                .collect::<Vec<_>>();
+            let mut dst16 = vec![0u16; src.len()];
+            create(&camera, Layout::Rgb, &srgb, Layout::Rgb, options)
+                .unwrap()
+                .transform(&src16, &mut dst16)
+                .unwrap();
+            let dst = dst16.iter().map(|&x| x as f32 / max).collect::<Vec<_>>();
+            assert_samples_similar(&dst, &exact_of(max), tolerance / max);
+
+            // SIMD kernels agree with scalar one at every depth
+            let mut scalar = vec![0u16; src.len()];
+            create(
+                &camera,
+                Layout::Rgb,
+                &srgb,
+                Layout::Rgb,
+                TransformOptions {
+                    deterministic: true,
+                    ..options
+                },
+            )
+            .unwrap()
+            .transform(&src16, &mut scalar)
+            .unwrap();
+            assert_samples_similar(&dst16, &scalar, 1.);
+        }
+    }
+
+    #[test]
+    fn test_fixed_point_matrix_headroom() {
+        use crate::conversions::RgbXyzFactory;
+        use crate::dispatch::{KernelFamily, with_forced_kernel};
+
+        // Coefficients close to Q4.12 limit of 32767 / 4095
+        let near_limit = Matrix3f {
+            v: [[7.9, -3.5, -3.4], [-3.4, 7.9, -3.5], [-3.5, -3.4, 7.9]],
+        };
+        let beyond_limit = Matrix3f {
+            v: [[9.5, -4.2, -4.3], [-4.3, 9.5, -4.2], [-4.2, -4.3, 9.5]],
+        };
+        let srgb = ColorProfile::new_srgb();
+        let make_u8 = |matrix: Matrix3f, prefer_fixed_point: bool| {
+            let options = TransformOptions {
+                prefer_fixed_point,
+                ..Default::default()
+            };
+            let mut profile = srgb
+                .make_rgb_profile_transform::<u8, 8, 256, 4096>(&srgb, options)
+                .unwrap();
+            profile.adaptation_matrix = Some(matrix);
+            let fits = profile.fits_q4_n::<12, 4096, 8>();
+            let transform = <u8 as RgbXyzFactory<u8>>::make_transform::<256, 4096, 8>(
+                Layout::Rgb,
+                Layout::Rgb,
+                profile,
+                options,
+            )
+            .unwrap();
+            (fits, transform)
+        };
+        let src = (0..=255u8)
+            .step_by(3)
+            .flat_map(|r| {
+                (0..=255u8)
+                    .step_by(3)
+                    .flat_map(move |g| (0..=255u8).step_by(3).flat_map(move |b| [r, g, b]))
+            })
+            .collect::<Vec<u8>>();
+
+        for family in KernelFamily::available() {
+            with_forced_kernel(family, || {
+                let (fits, fixed) = make_u8(near_limit, true);
+                assert!(fits);
+                let (_, float) = make_u8(near_limit, false);
+                let mut fixed_dst = vec![0u8; src.len()];
+                let mut float_dst = vec![0u8; src.len()];
+                fixed.transform(&src, &mut fixed_dst).unwrap();
+                float.transform(&src, &mut float_dst).unwrap();
+                let max_diff = fixed_dst
+                    .iter()
+                    .zip(float_dst.iter())
+                    .map(|(&a, &b)| a.abs_diff(b))
+                    .max()
+                    .unwrap();
+                // Linearization rounding is amplified by sum of absolute coefficients,
+                // nearly 15x here, so a few codes in the dark end are expected
+                assert!(max_diff <= 8, "{family:?}: {max_diff}");
+
+                // Saturated coefficients must not be used, fixed point falls back to float
+                let (fits, fixed) = make_u8(beyond_limit, true);
+                assert!(!fits);
+                let (_, float) = make_u8(beyond_limit, false);
+                fixed.transform(&src, &mut fixed_dst).unwrap();
+                float.transform(&src, &mut float_dst).unwrap();
+                assert_eq!(fixed_dst, float_dst, "{family:?}");
+            });
+        }
+
+        // Row accumulation overflowing i32 with 32768 entries linearization of f32 path
+        let wide_rows = Matrix3f {
+            v: [[7.9, -7.9, 1.0], [0., 1., 0.], [0., 0., 1.]],
+        };
+        let mut profile = srgb
+            .make_rgb_profile_transform::<f32, 1, 65536, 32768>(&srgb, TransformOptions::default())
+            .unwrap();
+        assert!(profile.fits_q4_n::<12, 32768, 1>());
+        profile.adaptation_matrix = Some(wide_rows);
+        assert!(!profile.fits_q4_n::<12, 32768, 1>());
+        profile.adaptation_matrix = Some(near_limit);
+        assert!(profile.fits_q4_n::<12, 32768, 1>());
+    }
+
+    #[test]
+    fn test_effective_fixed_point() {
+        use crate::conversions::has_q4_12_simd_kernel;
+        use crate::{ColorPrimaries, XyY};
+
+        let srgb = ColorProfile::new_srgb();
+        let bt2020 = ColorProfile::new_bt2020();
+        let options = TransformOptions::default();
+        assert!(srgb.effective_fixed_point(&bt2020, 8, options));
+        assert_eq!(
+            srgb.effective_fixed_point(&bt2020, 10, options),
+            has_q4_12_simd_kernel()
+        );
+        for bit_depth in [12, 16, 1] {
+            assert!(!srgb.effective_fixed_point(&bt2020, bit_depth, options));
+        }
+        let float = TransformOptions {
+            prefer_fixed_point: false,
+            ..Default::default()
+        };
+        assert!(!srgb.effective_fixed_point(&bt2020, 8, float));
+        let deterministic = TransformOptions {
+            deterministic: true,
+            ..Default::default()
+        };
+        assert!(srgb.effective_fixed_point(&bt2020, 8, deterministic));
+        assert!(!srgb.effective_fixed_point(&bt2020, 10, deterministic));
+        // Tone curves only transform has no matrix to run
+        assert!(!srgb.effective_fixed_point(&srgb, 8, options));
+
+        // Primaries this close to white point need coefficients above Q4.12 range
+        let mut narrow = srgb.clone();
+        narrow.cicp = None;
+        narrow.update_rgb_colorimetry(
+            XyY {
+                x: 0.3127,
+                y: 0.3290,
+                yb: 1.,
+            },
+            ColorPrimaries {
+                red: Chromaticity {
+                    x: 0.3327,
+                    y: 0.3290,
+                },
+                green: Chromaticity {
+                    x: 0.3127,
+                    y: 0.3490,
+                },
+                blue: Chromaticity {
+                    x: 0.2927,
+                    y: 0.3090,
+                },
+            },
+        );
+        assert!(!bt2020.effective_fixed_point(&narrow, 8, options));
+        let src = (0..=255u8)
+            .flat_map(|x| [x, 255 - x, x / 3])
+            .collect::<Vec<_>>();
+        let mut fixed = vec![0u8; src.len()];
+        let mut reference = vec![0u8; src.len()];
+        bt2020
+            .create_transform_8bit(Layout::Rgb, &narrow, Layout::Rgb, options)
+            .unwrap()
+            .transform(&src, &mut fixed)
+            .unwrap();
+        bt2020
+            .create_transform_8bit(Layout::Rgb, &narrow, Layout::Rgb, float)
+            .unwrap()
+            .transform(&src, &mut reference)
+            .unwrap();
+        assert_eq!(fixed, reference);
+    }
+
+    #[test]
+    fn test_transform_trc_only() {
+        use crate::conversions::{RgbXyzFactory, TransformProfileRgb, is_trc_only_transform};
+        use crate::curve_from_gamma;
+        use crate::dispatch::{KernelFamily, with_forced_kernel};
+
+        // CICP primaries would replace colorants by their rounded s15Fixed16 values
+        let mut srgb = ColorProfile::new_srgb();
+        srgb.cicp = None;
+        let mut linear_srgb = srgb.clone();
+        linear_srgb.red_trc = Some(curve_from_gamma(1.0));
+        linear_srgb.green_trc = linear_srgb.red_trc.clone();
+        linear_srgb.blue_trc = linear_srgb.red_trc.clone();
+        let mut gamma_srgb = linear_srgb.clone();
+        gamma_srgb.red_trc = Some(curve_from_gamma(2.2));
+        gamma_srgb.green_trc = gamma_srgb.red_trc.clone();
+        gamma_srgb.blue_trc = gamma_srgb.red_trc.clone();
+
+        assert!(!is_trc_only_transform(
+            ColorProfile::new_bt2020().transform_matrix(&srgb)
+        ));
+        assert!(!ColorProfile::new_bt2020().effective_trc_only(&srgb, 8, Default::default()));
+        let lazy = TransformOptions {
+            lazy_gamma_tables: true,
+            ..Default::default()
+        };
+        assert!(srgb.effective_trc_only(&gamma_srgb, 8, lazy));
+        assert!(!srgb.effective_trc_only(&gamma_srgb, 16, lazy));
+        let qcms = TransformOptions {
+            compatibility_mode: CompatibilityMode::Qcms,
+            ..Default::default()
+        };
+        assert!(!srgb.effective_trc_only(&gamma_srgb, 8, qcms));
+        assert!(srgb.effective_trc_only(&gamma_srgb, 16, qcms));
+
+        // Matrix is identity up to float error of inversion, fast path uses exact identity
+        fn identity<T: Clone, const N: usize>(
+            mut profile: TransformProfileRgb<T, N>,
+        ) -> TransformProfileRgb<T, N> {
+            profile.adaptation_matrix = Some(Matrix3f::IDENTITY);
+            profile
+        }
+
+        let pairs = [
+            (&srgb, &linear_srgb),
+            (&linear_srgb, &srgb),
+            (&srgb, &gamma_srgb),
+        ];
+        let options = TransformOptions {
+            prefer_fixed_point: false,
+            ..Default::default()
+        };
+        let src8 = (0..=255u8)
+            .flat_map(|x| [x, 255 - x, x / 3, x])
+            .collect::<Vec<_>>();
+        let src16 = (0..=65535u16)
+            .flat_map(|x| [x, 65535 - x, x / 3, x])
+            .collect::<Vec<_>>();
+        for (src_pr, dst_pr) in pairs {
+            assert!(is_trc_only_transform(src_pr.transform_matrix(dst_pr)));
+            for bit_depth in [8, 10, 12, 16] {
+                assert!(src_pr.effective_trc_only(dst_pr, bit_depth, options));
+            }
+            assert!(!src_pr.effective_trc_only(dst_pr, 1, options));
+            with_forced_kernel(KernelFamily::Scalar, || {
+                let fast = src_pr
+                    .create_transform_8bit(Layout::Rgba, dst_pr, Layout::Rgba, options)
+                    .unwrap();
+                let general = <u8 as RgbXyzFactory<u8>>::make_transform::<256, 4096, 8>(
+                    Layout::Rgba,
+                    Layout::Rgba,
+                    identity(
+                        src_pr
+                            .make_rgb_profile_transform::<u8, 8, 256, 4096>(dst_pr, options)
+                            .unwrap(),
+                    ),
+                    options,
+                )
+                .unwrap();
+                let mut a = vec![0u8; src8.len()];
+                let mut b = vec![0u8; src8.len()];
+                fast.transform(&src8, &mut a).unwrap();
+                general.transform(&src8, &mut b).unwrap();
+                assert_eq!(a, b);
+
+                let fast = src_pr
+                    .create_transform_16bit(Layout::Rgba, dst_pr, Layout::Rgba, options)
+                    .unwrap();
+                let general = <u16 as RgbXyzFactory<u16>>::make_transform::<65536, 65536, 16>(
+                    Layout::Rgba,
+                    Layout::Rgba,
+                    identity(
+                        src_pr
+                            .make_rgb_profile_transform::<u16, 16, 65536, 65536>(dst_pr, options)
+                            .unwrap(),
+                    ),
+                    options,
+                )
+                .unwrap();
+                let mut a = vec![0u16; src16.len()];
+                let mut b = vec![0u16; src16.len()];
+                fast.transform(&src16, &mut a).unwrap();
+                general.transform(&src16, &mut b).unwrap();
+                assert_eq!(a, b);
+            });
+        }
+    }
+
+    #[test]
+    fn test_transform_apply_vcgt() {
+        let srgb = ColorProfile::new_srgb();
+        let mut display = ColorProfile::new_srgb();
+        // Odd sized ramps so that table entries don't line up with codes
+        let ramp = |f: fn(f32) -> f32| (0..700).map(|i| f(i as f32 / 699.)).collect::<Vec<_>>();
+        display.vcgt = Some(Vcgt::Table([
+            ramp(|x| 0.9 * x),
+            ramp(|x| x.powf(1.1)),
+            ramp(|x| 0.05 + 0.95 * x),
+        ]));
+        let vcgt = display.vcgt.clone().unwrap();
+        let options = TransformOptions {
+            apply_vcgt: true,
+            ..Default::default()
+        };
+        let src = (0..=255u8)
+            .flat_map(|x| [x, 255 - x, x / 2, 255])
+            .collect::<Vec<_>>();
+
+        let plain = srgb
+            .create_transform_8bit(Layout::Rgba, &display, Layout::Bgra, Default::default())
+            .unwrap();
+        let calibrated = srgb
+            .create_transform_8bit(Layout::Rgba, &display, Layout::Bgra, options)
+            .unwrap();
+        let mut expected = vec![0u8; src.len()];
+        plain.transform(&src, &mut expected).unwrap();
+        for pixel in expected.chunks_exact_mut(4) {
+            for (ramp, index) in [2, 1, 0].into_iter().enumerate() {
+                let v = vcgt.evaluate(ramp, pixel[index] as f32 / 255.);
+                pixel[index] = (v * 255.).round() as u8;
+            }
+        }
+        let mut dst = vec![0u8; src.len()];
+        calibrated.transform(&src, &mut dst).unwrap();
+        assert_eq!(dst, expected);
+
+        // 16 bit and floating point follow the same ramp
+        let src16 = src.iter().map(|&x| x as u16 * 257).collect::<Vec<_>>();
+        let mut dst16 = vec![0u16; src.len()];
+        srgb.create_transform_16bit(Layout::Rgba, &display, Layout::Bgra, options)
+            .unwrap()
+            .transform(&src16, &mut dst16)
+            .unwrap();
+        let srcf = src.iter().map(|&x| x as f32 / 255.).collect::<Vec<_>>();
+        let mut dstf = vec![0f32; src.len()];
+        srgb.create_transform_f32(Layout::Rgba, &display, Layout::Bgra, options)
+            .unwrap()
+            .transform(&srcf, &mut dstf)
+            .unwrap();
+        for ((&a, &b), &c) in dst.iter().zip(dst16.iter()).zip(dstf.iter()) {
+            assert!((a as f32 - b as f32 / 257.).abs() <= 1., "{a} {b}");
+            assert!((a as f32 - c * 255.).abs() <= 1., "{a} {c}");
+        }
+
+        // Formula ramp on LUT based transform
+        let swop =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let formula = VcgtFormula {
+            gamma: 0.8,
+            min: 0.02,
+            max: 0.97,
+        };
+        display.vcgt = Some(Vcgt::Formula([formula; 3]));
+        let cmyk = (0..64u8)
+            .flat_map(|x| [x * 4, 255 - x * 4, x, x / 2])
+            .collect::<Vec<_>>();
+        let mut expected = vec![0u8; cmyk.len() / 4 * 3];
+        swop.create_transform_8bit(Layout::Rgba, &display, Layout::Rgb, Default::default())
+            .unwrap()
+            .transform(&cmyk, &mut expected)
+            .unwrap();
+        let mut dst = vec![0u8; expected.len()];
+        swop.create_transform_8bit(Layout::Rgba, &display, Layout::Rgb, options)
+            .unwrap()
+            .transform(&cmyk, &mut dst)
+            .unwrap();
+        let ramped = expected
+            .iter()
+            .map(|&x| {
+                let x = x as f32 / 255.;
+                let v = formula.min + (formula.max - formula.min) * x.powf(formula.gamma);
+                (v * 255.).round() as u8
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(dst, ramped);
+
+        // Only RGB destinations can carry a ramp
+        let mut gray = ColorProfile::new_gray_with_gamma(2.2);
+        gray.vcgt = display.vcgt.clone();
+        assert!(
+            srgb.create_transform_8bit(Layout::Rgb, &gray, Layout::Gray, options)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_mask_and_multi_profile_transforms_apply_vcgt() {
+        let bt2020 = ColorProfile::new_bt2020();
+        let mut display = ColorProfile::new_srgb();
+        let ramp = |f: fn(f32) -> f32| (0..700).map(|i| f(i as f32 / 699.)).collect::<Vec<_>>();
+        display.vcgt = Some(Vcgt::Table([
+            ramp(|x| 0.9 * x),
+            ramp(|x| x.powf(1.1)),
+            ramp(|x| 0.05 + 0.95 * x),
+        ]));
+        let vcgt = display.vcgt.clone().unwrap();
+        let options = TransformOptions {
+            apply_vcgt: true,
+            ..Default::default()
+        };
+        let apply_ramps = |pixels: &mut [u8], channels: usize| {
+            for pixel in pixels.chunks_exact_mut(channels) {
+                for (ramp, v) in pixel[..3].iter_mut().enumerate() {
+                    *v = (vcgt.evaluate(ramp, *v as f32 / 255.) * 255.).round() as u8;
+                }
+            }
+        };
+        let src = (0..=255u8)
+            .flat_map(|x| [x, 255 - x, x / 2, 255])
+            .collect::<Vec<_>>();
+
+        let mut expected = vec![0u8; src.len()];
+        let mut expected_mask = vec![0u8; src.len() / 4];
+        bt2020
+            .create_transform_with_mask(&display, Layout::Rgba, Default::default())
+            .unwrap()
+            .transform(&src, &mut expected, &mut expected_mask)
+            .unwrap();
+        apply_ramps(&mut expected, 4);
+        let mut dst = vec![0u8; src.len()];
+        let mut mask = vec![0u8; src.len() / 4];
+        bt2020
+            .create_transform_with_mask(&display, Layout::Rgba, options)
+            .unwrap()
+            .transform(&src, &mut dst, &mut mask)
+            .unwrap();
+        assert_eq!(dst, expected);
+        assert_eq!(mask, expected_mask);
+
+        let multi_profile = |options| {
+            ColorProfile::create_multi_profile_transform(
+                &[&bt2020, &make_abstract_lab(0.5), &display],
+                Layout::Rgba,
+                Layout::Rgb,
+                &[RenderingIntent::Perceptual; 3],
+                options,
+            )
+            .unwrap()
+        };
+        let mut expected = vec![0u8; src.len() / 4 * 3];
+        multi_profile(Default::default())
+            .transform(&src, &mut expected)
+            .unwrap();
+        apply_ramps(&mut expected, 3);
+        let mut dst = vec![0u8; expected.len()];
+        multi_profile(options).transform(&src, &mut dst).unwrap();
+        assert_eq!(dst, expected);
+    }
+
+    #[test]
+    fn test_layout_try_from_u8() {
+        for layout in [
+            Layout::Rgb,
+            Layout::Rgba,
+            Layout::Gray,
+            Layout::GrayAlpha,
+            Layout::Bgr,
+            Layout::Bgra,
+            Layout::Argb,
+            Layout::Abgr,
+        ] {
+            assert_eq!(Layout::try_from_u8(layout as u8), Ok(layout));
+            assert_eq!(Layout::from(layout as u8), layout);
+        }
+        assert_eq!(Layout::try_from_u8(8), Err(CmsError::InvalidLayout));
+        assert_eq!(Layout::try_from_u8(u8::MAX), Err(CmsError::InvalidLayout));
+    }
+
+    #[test]
+    fn test_transform_pixel() {
+        let srgb = ColorProfile::new_srgb();
+        let p3 = ColorProfile::new_display_p3();
+        let transform = srgb
+            .create_transform_8bit(Layout::Bgr, &p3, Layout::Rgba, TransformOptions::default())
+            .unwrap();
+        let mut expected = [0u8; 4];
+        transform.transform(&[30, 200, 120], &mut expected).unwrap();
+        assert_eq!(transform.transform_pixel(&[30, 200, 120]), Ok(expected));
+        assert_eq!(
+            transform.transform_pixel(&[30, 200]),
+            Err(CmsError::LaneSizeMismatch)
+        );
+        assert_eq!(
+            transform.transform_pixel(&[30, 200, 120, 255]),
+            Err(CmsError::LaneSizeMismatch)
+        );
+
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let transform = cmyk
+            .create_transform_f32(
+                Layout::Rgba,
+                &srgb,
+                Layout::Rgb,
+                TransformOptions::default(),
+            )
+            .unwrap();
+        let mut expected = [0f32; 3];
+        transform
+            .transform(&[0.1, 0.5, 0.9, 0.2], &mut expected)
+            .unwrap();
+        let pixel = transform.transform_pixel(&[0.1, 0.5, 0.9, 0.2]).unwrap();
+        assert_eq!(pixel[..3], expected);
+        assert_eq!(pixel[3], 0.);
+    }
+
+    #[test]
+    fn test_deterministic_transform() {
+        use crate::dispatch::{KernelFamily, with_forced_kernel};
+
+        let srgb = ColorProfile::new_srgb();
+        let bt2020 = ColorProfile::new_bt2020();
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let mut rng = rand::rng();
+        let src8 = (0..4096).map(|_| rng.random::<u8>()).collect::<Vec<u8>>();
+        let src16 = (0..4096).map(|_| rng.random::<u16>()).collect::<Vec<u16>>();
+        let src_f32 = (0..4096).map(|_| rng.random::<f32>()).collect::<Vec<f32>>();
+
+        let run = |family: KernelFamily, prefer_fixed_point: bool| {
+            let options = TransformOptions {
+                prefer_fixed_point,
+                deterministic: true,
+                ..Default::default()
+            };
+            with_forced_kernel(family, || {
+                let mut dst8 = vec![0u8; src8.len()];
+                srgb.create_transform_8bit(Layout::Rgba, &bt2020, Layout::Rgba, options)
+                    .unwrap()
+                    .transform(&src8, &mut dst8)
+                    .unwrap();
+                let mut dst16 = vec![0u16; src16.len()];
+                srgb.create_transform_16bit(Layout::Rgba, &bt2020, Layout::Rgba, options)
+                    .unwrap()
+                    .transform(&src16, &mut dst16)
+                    .unwrap();
+                let mut dst_f32 = vec![0f32; src_f32.len()];
+                srgb.create_transform_f32(Layout::Rgba, &bt2020, Layout::Rgba, options)
+                    .unwrap()
+                    .transform(&src_f32, &mut dst_f32)
+                    .unwrap();
+                let mut lut8 = vec![0u8; src8.len() / 4 * 3];
+                cmyk.create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgb, options)
+                    .unwrap()
+                    .transform(&src8, &mut lut8)
+                    .unwrap();
+                let mut lut16 = vec![0u16; src16.len() / 4 * 3];
+                cmyk.create_transform_16bit(Layout::Rgba, &srgb, Layout::Rgb, options)
+                    .unwrap()
+                    .transform(&src16, &mut lut16)
+                    .unwrap();
+                (
+                    dst8,
+                    dst16,
+                    dst_f32.iter().map(|x| x.to_bits()).collect::<Vec<u32>>(),
+                    lut8,
+                    lut16,
+                )
+            })
+        };
+
+        for prefer_fixed_point in [false, true] {
+            let reference = run(KernelFamily::Scalar, prefer_fixed_point);
+            for family in KernelFamily::available() {
+                assert!(
+                    run(family, prefer_fixed_point) == reference,
+                    "{family:?} {prefer_fixed_point}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_premultiplied_alpha() {
+        let srgb = ColorProfile::new_srgb();
+        let pro_photo = ColorProfile::new_pro_photo_rgb();
+        let premultiplied = TransformOptions {
+            alpha_mode: AlphaMode::Premultiplied,
+            ..Default::default()
+        };
+        let make = |options| {
+            srgb.create_transform_8bit(Layout::Rgba, &pro_photo, Layout::Rgba, options)
+                .unwrap()
+        };
+        let straight = make(TransformOptions::default());
+        let premultiplied = make(premultiplied);
+
+        // Opaque pixels are not affected
+        let opaque = [200u8, 40, 90, 255, 0, 0, 0, 255, 255, 255, 255, 255];
+        let mut expected = [0u8; 12];
+        straight.transform(&opaque, &mut expected).unwrap();
+        let mut dst = [0u8; 12];
+        premultiplied.transform(&opaque, &mut dst).unwrap();
+        assert_eq!(dst, expected);
+
+        // Semi-transparent edge keeps color of the opaque one
+        let color = [200u8, 40, 90];
+        let mut opaque_dst = [0u8; 4];
+        straight
+            .transform(&[color[0], color[1], color[2], 255], &mut opaque_dst)
+            .unwrap();
+        for alpha in [192u8, 128, 64] {
+            let pm = |c: u8| ((c as u32 * alpha as u32 + 127) / 255) as u8;
+            let src = [pm(color[0]), pm(color[1]), pm(color[2]), alpha];
+            let mut dst = [0u8; 4];
+            premultiplied.transform(&src, &mut dst).unwrap();
+            assert_eq!(dst[3], alpha);
+            for c in 0..3 {
+                let expected = opaque_dst[c] as f32 * alpha as f32 / 255.;
+                assert!(
+                    (dst[c] as f32 - expected).abs() <= 1.5,
+                    "alpha {alpha}: {dst:?} vs {opaque_dst:?}"
+                );
+            }
+            // Transforming premultiplied values as straight ones darkens the edge
+            let mut naive = [0u8; 4];
+            straight.transform(&src, &mut naive).unwrap();
+            assert!(
+                (0..3).any(|c| dst[c].abs_diff(naive[c]) > 3),
+                "{dst:?} {naive:?}"
+            );
+        }
+
+        // Transparent pixels have no color, destination without alpha gets straight color
+        let mut dst = [1u8; 4];
+        premultiplied.transform(&[10, 20, 30, 0], &mut dst).unwrap();
+        assert_eq!(dst, [0, 0, 0, 0]);
+        let to_rgb = srgb
+            .create_transform_f32(
+                Layout::Rgba,
+                &srgb,
+                Layout::Rgb,
+                TransformOptions {
+                    alpha_mode: AlphaMode::Premultiplied,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let mut dst = [0f32; 3];
+        to_rgb.transform(&[0.4, 0.2, 0.1, 0.5], &mut dst).unwrap();
+        assert_samples_similar(&dst, &[0.8, 0.4, 0.2], 1e-3);
+    }
+
+    #[test]
+    fn test_premultiplied_alpha_mask_transform() {
+        let bt2020 = ColorProfile::new_bt2020();
+        let srgb = ColorProfile::new_srgb();
+        let options = TransformOptions {
+            alpha_mode: AlphaMode::Premultiplied,
+            ..Default::default()
+        };
+        let src = premultiplied_rgba_ramp();
+        let masked = bt2020
+            .create_transform_with_mask(&srgb, Layout::Rgba, options)
+            .unwrap();
+        let mut dst = vec![0u8; src.len()];
+        let mut mask = vec![0u8; src.len() / 4];
+        masked.transform(&src, &mut dst, &mut mask).unwrap();
+
+        let regular = bt2020
+            .create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgba, options)
+            .unwrap();
+        let mut expected = vec![0u8; src.len()];
+        regular.transform(&src, &mut expected).unwrap();
+        assert_eq!(dst, expected);
+
+        // Gamut is checked on straight colors
+        let straight = bt2020
+            .create_transform_with_mask(&srgb, Layout::Rgba, TransformOptions::default())
+            .unwrap();
+        let mut straight_dst = vec![0u8; src.len()];
+        let mut expected_mask = vec![0u8; mask.len()];
+        straight
+            .transform(
+                &unpremultiply_samples(&src, 4, 255),
+                &mut straight_dst,
+                &mut expected_mask,
+            )
+            .unwrap();
+        assert_eq!(mask, expected_mask);
+        assert!(mask.contains(&255));
+    }
+
+    #[test]
+    fn test_premultiplied_alpha_multi_profile_transform() {
+        let srgb = ColorProfile::new_srgb();
+        let look = make_abstract_lab(0.5);
+        let intents = [RenderingIntent::Perceptual; 3];
+        let create = |alpha_mode| {
+            ColorProfile::create_multi_profile_transform(
+                &[&srgb, &look, &srgb],
+                Layout::Rgba,
+                Layout::Rgba,
+                &intents,
+                TransformOptions {
+                    alpha_mode,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+        let src = premultiplied_rgba_ramp();
+        let mut dst = vec![0u8; src.len()];
+        create(AlphaMode::Premultiplied)
+            .transform(&src, &mut dst)
+            .unwrap();
+        let mut straight = vec![0u8; src.len()];
+        create(AlphaMode::Straight)
+            .transform(&unpremultiply_samples(&src, 4, 255), &mut straight)
+            .unwrap();
+        assert_eq!(dst, premultiply_samples(&straight, 4, 255));
+    }
+
+    #[test]
+    fn test_premultiplied_alpha_gray_depth_transform() {
+        let src_pr = ColorProfile::new_gray_with_gamma(2.2);
+        let dst_pr = ColorProfile::new_gray_with_gamma(1.8);
+        let options = |alpha_mode| TransformOptions {
+            alpha_mode,
+            ..Default::default()
+        };
+
+        let src = premultiply_samples(
+            &(0..=255u8)
+                .flat_map(|x| [x, [255, 128, 7, 0][x as usize % 4]])
+                .collect::<Vec<_>>(),
+            2,
+            255,
+        );
+        let transform = |alpha_mode| {
+            src_pr
+                .create_gray_transform_8bit_to_16bit(
+                    Layout::GrayAlpha,
+                    &dst_pr,
+                    Layout::GrayAlpha,
+                    options(alpha_mode),
+                )
+                .unwrap()
+        };
+        let mut dst = vec![0u16; src.len()];
+        transform(AlphaMode::Premultiplied)
+            .transform(&src, &mut dst)
+            .unwrap();
+        let mut straight = vec![0u16; src.len()];
+        transform(AlphaMode::Straight)
+            .transform(&unpremultiply_samples(&src, 2, 255), &mut straight)
+            .unwrap();
+        assert_eq!(dst, premultiply_samples(&straight, 2, 65535));
+
+        let src = premultiply_samples(
+            &(0..=u16::MAX)
+                .step_by(7)
+                .flat_map(|x| [x, [65535, 32768, 300, 0][x as usize % 4]])
+                .collect::<Vec<_>>(),
+            2,
+            65535,
+        );
+        let transform = |alpha_mode| {
+            src_pr
+                .create_gray_transform_16bit_to_8bit(
+                    Layout::GrayAlpha,
+                    &dst_pr,
+                    Layout::GrayAlpha,
+                    options(alpha_mode),
+                )
+                .unwrap()
+        };
+        let mut dst = vec![0u8; src.len()];
+        transform(AlphaMode::Premultiplied)
+            .transform(&src, &mut dst)
+            .unwrap();
+        let mut straight = vec![0u8; src.len()];
+        transform(AlphaMode::Straight)
+            .transform(&unpremultiply_samples(&src, 2, 65535), &mut straight)
+            .unwrap();
+        assert_eq!(dst, premultiply_samples(&straight, 2, 255));
+    }
+
+    #[test]
+    fn test_layout_try_channel_indices() {
+        for layout in (0..=7).map(|x| Layout::try_from_u8(x).unwrap()) {
+            let is_gray = matches!(layout, Layout::Gray | Layout::GrayAlpha);
+            let indices = [layout.try_r_i(), layout.try_g_i(), layout.try_b_i()];
+            assert_eq!(indices.iter().all(|x| x.is_none()), is_gray);
+            assert_eq!(layout.try_a_i().is_some(), layout.has_alpha());
+            let mut present = indices
+                .into_iter()
+                .chain([layout.try_a_i()])
+                .flatten()
+                .collect::<Vec<usize>>();
+            present.sort_unstable();
+            present.dedup();
+            assert!(present.iter().all(|&x| x < layout.channels()));
+            if !is_gray {
+                assert_eq!(present.len(), layout.channels());
+                assert_eq!(layout.try_r_i(), Some(layout.r_i()));
+                assert_eq!(layout.try_b_i(), Some(layout.b_i()));
+            }
+        }
+    }
+
+    /// Abstract Lab profile keeping lightness and scaling chroma by `chroma`
+    fn make_abstract_lab(chroma: f32) -> ColorProfile {
+        let mut clut_table = Vec::new();
+        for l in [0f32, 1.] {
+            for a in [0f32, 1.] {
+                for b in [0f32, 1.] {
+                    let neutral = 128. / 255.;
+                    clut_table.push(l);
+                    clut_table.push(neutral + (a - neutral) * chroma);
+                    clut_table.push(neutral + (b - neutral) * chroma);
+                }
+            }
+        }
+        ColorProfile {
+            profile_class: ProfileClass::Abstract,
+            color_space: DataColorSpace::Lab,
+            pcs: DataColorSpace::Lab,
+            lut_a_to_b_perceptual: Some(LutWarehouse::Lut(LutDataType {
+                num_input_channels: 3,
+                num_output_channels: 3,
+                num_clut_grid_points: 2,
+                matrix: Matrix3f::IDENTITY,
+                num_input_table_entries: 2,
+                num_output_table_entries: 2,
+                input_table: vec![0., 1., 0., 1., 0., 1.],
+                clut_table,
+                output_table: vec![0., 1., 0., 1., 0., 1.],
+                lut_type: LutType::Lut8,
+            })),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_multi_profile_transform() {
+        let srgb = ColorProfile::new_srgb();
+        let intents = [RenderingIntent::Perceptual; 3];
+        let src = [200u8, 30, 60, 20, 120, 240, 128, 128, 128];
+
+        let identity = make_abstract_lab(1.);
+        let transform = ColorProfile::create_multi_profile_transform(
+            &[&srgb, &identity, &srgb],
+            Layout::Rgb,
+            Layout::Rgb,
+            &intents,
+            TransformOptions::default(),
+        )
+        .unwrap();
+        let mut dst = [0u8; 9];
+        transform.transform(&src, &mut dst).unwrap();
+        for (&s, &d) in src.iter().zip(dst.iter()) {
+            assert!((s as i32 - d as i32).abs() <= 2, "{src:?} became {dst:?}");
+        }
+
+        let grayscale = make_abstract_lab(0.);
+        let transform = ColorProfile::create_multi_profile_transform(
+            &[&srgb, &grayscale, &srgb],
+            Layout::Rgb,
+            Layout::Rgb,
+            &intents,
+            TransformOptions::default(),
+        )
+        .unwrap();
+        transform.transform(&src, &mut dst).unwrap();
+        for px in dst.chunks_exact(3) {
+            let max = px.iter().max().unwrap();
+            let min = px.iter().min().unwrap();
+            assert!(max - min <= 2, "{px:?} is not gray");
+        }
+        // Lightness is kept, so gray input stays where it was
+        assert!((dst[6] as i32 - 128).abs() <= 2);
+    }
+
+    #[test]
+    fn test_multi_profile_transform_ycbcr() {
+        let srgb = ColorProfile::new_srgb();
+        let options = TransformOptions {
+            ycbcr_output: Some(YCbCrMatrix::Bt709),
+            ..Default::default()
+        };
+        let transform = ColorProfile::create_multi_profile_transform(
+            &[&srgb, &srgb],
+            Layout::Rgb,
+            Layout::Rgb,
+            &[RenderingIntent::Perceptual; 2],
+            options,
+        )
+        .unwrap();
+        let reference = srgb
+            .create_transform_8bit(Layout::Rgb, &srgb, Layout::Rgb, options)
+            .unwrap();
+        let src = (0..=255u8)
+            .flat_map(|x| [x, 255 - x, x / 2])
+            .collect::<Vec<_>>();
+        let mut dst = vec![0u8; src.len()];
+        let mut expected = vec![0u8; src.len()];
+        transform.transform(&src, &mut dst).unwrap();
+        reference.transform(&src, &mut expected).unwrap();
+        assert_samples_similar(&dst, &expected, 2.);
+
+        // Red has low luma and the largest Cr
+        let mut red = [0u8; 3];
+        transform.transform(&[255, 0, 0], &mut red).unwrap();
+        assert!(red[0] < 80 && red[2] > 240, "{red:?}");
+    }
+
+    #[test]
+    fn test_multi_profile_transform_rejects_invalid_chain() {
+        let srgb = ColorProfile::new_srgb();
+        let intents = [RenderingIntent::Perceptual; 3];
+        let create = |profiles: &[&ColorProfile], intents: &[RenderingIntent]| {
+            ColorProfile::create_multi_profile_transform(
+                profiles,
+                Layout::Rgb,
+                Layout::Rgb,
+                intents,
+                TransformOptions::default(),
+            )
+            .err()
+        };
+        assert_eq!(
+            create(&[&srgb], &intents[..1]),
+            Some(CmsError::UnsupportedProfileConnection)
+        );
+        assert_eq!(
+            create(&[&srgb, &srgb, &srgb], &intents),
+            Some(CmsError::UnsupportedProfileConnection)
+        );
+        let look = make_abstract_lab(0.5);
+        assert_eq!(
+            create(&[&srgb, &look, &srgb], &intents[..2]),
+            Some(CmsError::InvalidRenderingIntent)
+        );
+    }
 }
@@ -30,7 +30,7 @@ use crate::math::m_clamp;
 use crate::mlaf::mlaf;
 use crate::transform::PointeeSizeExpressible;
 use crate::writer::FloatToFixedU8Fixed8;
-use crate::{CmsError, ColorProfile, pow, powf};
+use crate::{CmsError, ColorProfile, TransferCharacteristics, pow, powf};
 use num_traits::AsPrimitive;
 
 #[derive(Clone, Debug)]
@@ -39,42 +39,7 @@ pub enum ToneReprCurve {
     Parametric(Vec<f32>),
 }
 
-#[allow(clippy::many_single_char_names)]
-pub(crate) fn build_srgb_gamma_table(num_entries: i32) -> Vec<u16> {
-    let gamma: f64 = 2.4;
-    let a: f64 = 1.0 / 1.055;
-    let b: f64 = 0.055 / 1.055;
-    let c: f64 = 1.0 / 12.92;
-    let d: f64 = 0.04045;
-    build_parametric_table(num_entries, a, b, c, d, gamma)
-}
-
-#[allow(clippy::many_single_char_names)]
-#[inline]
-pub(crate) fn build_parametric_table(
-    num_entries: i32,
-    a: f64,
-    b: f64,
-    c: f64,
-    d: f64,
-    g: f64,
-) -> Vec<u16> {
-    build_trc_table(
-        num_entries,
-        // IEC 61966-2.1 (sRGB)
-        // Y = (aX + b)^Gamma | X >= d
-        // Y = cX             | X < d
-        |x| {
-            if x >= d {
-                let e: f64 = a * x + b;
-                if e > 0. { pow(e, g) } else { 0. }
-            } else {
-                c * x
-            }
-        },
-    )
-}
-
+/// Samples `eotf` into `num_entries` of 16-bit `curv` values, rounded half up.
 pub(crate) fn build_trc_table(num_entries: i32, eotf: impl Fn(f64) -> f64) -> Vec<u16> {
     let mut table = vec![0u16; num_entries as usize];
 
@@ -94,13 +59,61 @@ pub(crate) fn build_trc_table(num_entries: i32, eotf: impl Fn(f64) -> f64) -> Ve
     table
 }
 
+/// Step and minimum of `curv` table length search
+const TRC_TABLE_LENGTH_STEP: usize = 256;
+/// Maximum `curv` table length that is made from a function
+const MAX_TRC_TABLE_ENTRIES: usize = 4096;
+
+/// Max error of linear interpolation between `num_entries` samples of `eotf`,
+/// measured at several points of every interval.
+fn trc_interpolation_error(num_entries: usize, eotf: &impl Fn(f64) -> f64) -> f64 {
+    const POINTS: usize = 8;
+    let curve = |x: f64| eotf(x).max(0.).min(1.);
+    let step = 1. / (num_entries - 1) as f64;
+    let mut max_error = 0f64;
+    let mut y0 = curve(0.);
+    for i in 1..num_entries {
+        let x0 = (i - 1) as f64 * step;
+        let y1 = curve(i as f64 * step);
+        for j in 1..POINTS {
+            let t = j as f64 / POINTS as f64;
+            let interpolated = y0 + (y1 - y0) * t;
+            max_error = max_error.max((interpolated - curve(x0 + step * t)).abs());
+        }
+        y0 = y1;
+    }
+    max_error
+}
+
+/// Samples `eotf` into the shortest `curv` table, up to [MAX_TRC_TABLE_ENTRIES],
+/// linear interpolation of which stays within 0.5/65535 of the function.
+///
+/// Values are rounded half up, so table is within 1/65535 of the function everywhere.
+/// Functions too steep for that, as PQ near black is, get the largest table.
+pub(crate) fn build_adaptive_trc_table(eotf: impl Fn(f64) -> f64) -> Vec<u16> {
+    const MAX_ERROR: f64 = 0.5 / 65535.;
+    let num_entries = (TRC_TABLE_LENGTH_STEP..MAX_TRC_TABLE_ENTRIES)
+        .step_by(TRC_TABLE_LENGTH_STEP)
+        .find(|&n| trc_interpolation_error(n, &eotf) < MAX_ERROR)
+        .unwrap_or(MAX_TRC_TABLE_ENTRIES);
+    build_trc_table(num_entries as i32, eotf)
+}
+
+/// Creates Tone Reproduction curve sampling function from encoded to linear values
+///
+/// Function is evaluated in [0, 1], results are clamped to [0, 1].
+/// Table length is chosen to keep curve within 1/65535 of the function, up to 4096 entries.
+pub fn curve_from_fn(eotf: impl Fn(f32) -> f32) -> ToneReprCurve {
+    ToneReprCurve::Lut(build_adaptive_trc_table(|x| eotf(x as f32) as f64))
+}
+
 /// Creates Tone Reproduction curve from gamma
 pub fn curve_from_gamma(gamma: f32) -> ToneReprCurve {
     ToneReprCurve::Lut(vec![gamma.to_u8_fixed8()])
 }
 
-#[derive(Debug)]
-struct ParametricCurve {
+#[derive(Clone, Debug)]
+pub(crate) struct ParametricCurve {
     g: f32,
     a: f32,
     b: f32,
@@ -131,7 +144,7 @@ impl ParametricCurve {
                 a,
                 b,
                 c: 0.,
-                d: -b / a,
+                d: Self::pivot(a, b),
                 e: 0.,
                 f: 0.,
             }),
@@ -140,7 +153,7 @@ impl ParametricCurve {
                 a,
                 b,
                 c: 0.,
-                d: -b / a,
+                d: Self::pivot(a, b),
                 e: c,
                 f: c,
             }),
@@ -166,15 +179,28 @@ impl ParametricCurve {
         }
     }
 
+    /// Point where `a * x + b` crosses zero, with `a == 0` curve is constant `b^g`
+    /// over the whole domain.
+    #[inline]
+    fn pivot(a: f32, b: f32) -> f32 {
+        if a == 0. { 0. } else { -b / a }
+    }
+
+    #[inline]
     fn eval(&self, x: f32) -> f32 {
         if x < self.d {
             self.c * x + self.f
         } else {
-            powf(self.a * x + self.b, self.g) + self.e
+            powf((self.a * x + self.b).max(0.), self.g) + self.e
         }
     }
 
-    #[allow(dead_code)]
+    /// Parameters in `[g, a, b, c, d, e, f]` order of ICC type 4 function
+    #[cfg(feature = "gpu")]
+    pub(crate) fn params(&self) -> [f32; 7] {
+        [self.g, self.a, self.b, self.c, self.d, self.e, self.f]
+    }
+
     #[allow(clippy::many_single_char_names)]
     fn invert(&self) -> Option<ParametricCurve> {
         // First check if the function is continuous at the cross-over point d.
@@ -205,6 +231,11 @@ impl ParametricCurve {
         if d <= 0. {
             c = 1.;
             f = 0.;
+        } else if self.c == 0. {
+            // Flat segment, every y below the cross-over goes back to the cross-over point,
+            // which is continuous with the power segment
+            c = 0.;
+            f = self.d;
         } else {
             c = 1. / self.c;
             f = -self.f / self.c;
@@ -235,6 +266,46 @@ impl ParametricCurve {
     }
 }
 
+/// Tone curve evaluated as a function for every sample instead of being
+/// sampled into a linearization or gamma table.
+///
+/// Analytic curves are extended past [0, 1]: pure power curves are mirrored
+/// around zero, parametric curves continue their segments.
+/// Curves given as samples and CICP transfer functions are defined only on [0, 1].
+#[derive(Clone, Debug)]
+pub(crate) enum ToneCurveEvaluator {
+    Identity,
+    Power(f32),
+    Parametric(ParametricCurve),
+    #[cfg(feature = "transforms")]
+    CicpLinearize(TransferCharacteristics),
+    #[cfg(feature = "transforms")]
+    CicpGamma(TransferCharacteristics),
+    Sampled(Vec<f32>),
+}
+
+impl ToneCurveEvaluator {
+    #[inline]
+    pub(crate) fn evaluate(&self, x: f32) -> f32 {
+        match self {
+            ToneCurveEvaluator::Identity => x,
+            ToneCurveEvaluator::Power(gamma) => {
+                if x < 0. {
+                    -powf(-x, *gamma)
+                } else {
+                    powf(x, *gamma)
+                }
+            }
+            ToneCurveEvaluator::Parametric(curve) => curve.eval(x),
+            #[cfg(feature = "transforms")]
+            ToneCurveEvaluator::CicpLinearize(tc) => tc.linearize(x as f64) as f32,
+            #[cfg(feature = "transforms")]
+            ToneCurveEvaluator::CicpGamma(tc) => tc.gamma(x as f64) as f32,
+            ToneCurveEvaluator::Sampled(table) => lut_interp_linear_float(x, table),
+        }
+    }
+}
+
 #[inline]
 fn u8_fixed_8number_to_float(x: u16) -> f32 {
     // 0x0000 = 0.
@@ -243,6 +314,56 @@ fn u8_fixed_8number_to_float(x: u16) -> f32 {
     (x as i32 as f64 / 256.0) as f32
 }
 
+/// Table endpoints closer than this to 0 or 1 are pinned to them exactly,
+/// so rounding accumulated while building tables can't move device black and white.
+const ENDPOINT_PIN_TOLERANCE: f32 = 1. / 1024.;
+
+/// Pins linearization of zero and of the maximum code to exactly 0 and 1.
+pub(crate) fn pin_linear_endpoints<
+    T: PointeeSizeExpressible,
+    const N: usize,
+    const BIT_DEPTH: usize,
+>(
+    table: &mut [f32; N],
+) {
+    let max_code = if T::FINITE {
+        (1usize << BIT_DEPTH) - 1
+    } else {
+        T::NOT_FINITE_LINEAR_TABLE_SIZE - 1
+    };
+    if max_code >= N {
+        return;
+    }
+    if table[0].abs() < ENDPOINT_PIN_TOLERANCE {
+        table[0] = 0.;
+    }
+    if (table[max_code] - 1.).abs() < ENDPOINT_PIN_TOLERANCE {
+        table[max_code] = 1.;
+    }
+}
+
+/// Fills entries past the declared bit depth with the value at the maximum code,
+/// so out of range N-bit input saturates instead of reading zeroes.
+pub(crate) fn saturate_linear_table<
+    T: PointeeSizeExpressible,
+    const N: usize,
+    const BIT_DEPTH: usize,
+>(
+    table: &mut [f32; N],
+) {
+    if !T::FINITE {
+        return;
+    }
+    let cap_values = (1u32 << BIT_DEPTH) as usize;
+    if cap_values >= N {
+        return;
+    }
+    let max_value = table[cap_values - 1];
+    for v in table.iter_mut().skip(cap_values) {
+        *v = max_value;
+    }
+}
+
 fn passthrough_table<T: PointeeSizeExpressible, const N: usize, const BIT_DEPTH: usize>()
 -> Box<[f32; N]> {
     let mut gamma_table = Box::new([0f32; N]);
@@ -289,7 +410,7 @@ fn linear_forward_table<T: PointeeSizeExpressible, const N: usize, const BIT_DEP
 
 #[inline]
 pub(crate) fn lut_interp_linear_float(x: f32, table: &[f32]) -> f32 {
-    let value = x * (table.len() - 1) as f32;
+    let value = x.max(0.).min(1.) * (table.len() - 1) as f32;
 
     let upper: i32 = value.ceil() as i32;
     let lower: i32 = value.floor() as i32;
@@ -386,20 +507,7 @@ pub(crate) fn make_gamma_linear_table<
 where
     f32: AsPrimitive<T>,
 {
-    let mut table = Box::new([T::default(); BUCKET]);
-    let max_range = if T::FINITE {
-        (1f64 / ((N - 1) as f64 / (1 << BIT_DEPTH) as f64)) as f32
-    } else {
-        (1f64 / ((N - 1) as f64)) as f32
-    };
-    for (v, output) in table.iter_mut().take(N).enumerate() {
-        if T::FINITE {
-            *output = (v as f32 * max_range).round().as_();
-        } else {
-            *output = (v as f32 * max_range).as_();
-        }
-    }
-    table
+    GammaTableSource::Linear.build_table::<T, BUCKET, N, BIT_DEPTH>()
 }
 
 #[inline]
@@ -550,6 +658,18 @@ where
     for (v, output) in new_table.iter_mut().take(N).enumerate() {
         *output = T::gamma_lut_interp::<T, N, BIT_DEPTH>(v as u32, table);
     }
+    let tolerance = (65535. * ENDPOINT_PIN_TOLERANCE) as u16;
+    if table.first().is_some_and(|&x| x <= tolerance) {
+        new_table[0] = 0u32.as_();
+    }
+    if table.last().is_some_and(|&x| x >= 65535 - tolerance) {
+        let max_code = if T::FINITE {
+            (1u32 << BIT_DEPTH) - 1
+        } else {
+            1
+        };
+        new_table[N - 1] = max_code.as_();
+    }
     new_table
 }
 
@@ -578,13 +698,93 @@ fn make_gamma_pow_table<
 where
     f32: AsPrimitive<T>,
 {
-    let mut table = Box::new([T::default(); BUCKET]);
-    let scale = 1f32 / (N - 1) as f32;
-    let cap = ((1 << BIT_DEPTH) - 1) as f32;
-    for (v, output) in table.iter_mut().take(N).enumerate() {
-        *output = (cap * powf(v as f32 * scale, gamma)).round().as_();
+    GammaTableSource::Power(gamma).build_table::<T, BUCKET, N, BIT_DEPTH>()
+}
+
+/// Closed form of a gamma table, any entry may be computed independently,
+/// so tables might be filled lazily by parts.
+#[derive(Clone, Debug)]
+pub(crate) enum GammaTableSource {
+    Linear,
+    Power(f32),
+    /// Already inverted curve
+    Parametric(ParametricCurve),
+    Cicp(TransferCharacteristics),
+}
+
+impl GammaTableSource {
+    /// Computes entry `v` of `N` entries gamma table.
+    #[inline]
+    pub(crate) fn entry<
+        T: Copy + 'static + PointeeSizeExpressible,
+        const N: usize,
+        const BIT_DEPTH: usize,
+    >(
+        &self,
+        v: usize,
+    ) -> T
+    where
+        f32: AsPrimitive<T>,
+    {
+        let cap = if T::FINITE {
+            ((1 << BIT_DEPTH) - 1) as f32
+        } else {
+            1.
+        };
+        let value = match self {
+            GammaTableSource::Linear => {
+                let max_range = if T::FINITE {
+                    (1f64 / ((N - 1) as f64 / (1 << BIT_DEPTH) as f64)) as f32
+                } else {
+                    (1f64 / ((N - 1) as f64)) as f32
+                };
+                v as f32 * max_range
+            }
+            GammaTableSource::Power(gamma) => {
+                let scale = 1f32 / (N - 1) as f32;
+                cap * powf(v as f32 * scale, *gamma)
+            }
+            GammaTableSource::Parametric(curve) => {
+                let scale = 1f32 / (N - 1) as f32;
+                cap * m_clamp(curve.eval(v as f32 * scale), 0., 1.)
+            }
+            GammaTableSource::Cicp(tc) => {
+                let max_range = 1f64 / (N - 1) as f64;
+                (tc.gamma(v as f64 * max_range) * cap as f64) as f32
+            }
+        };
+        let tolerance = cap * ENDPOINT_PIN_TOLERANCE;
+        let value = if v == 0 && value.abs() < tolerance {
+            0.
+        } else if v == N - 1 && (value - cap).abs() <= tolerance.max(1.) {
+            cap
+        } else {
+            value
+        };
+        if T::FINITE {
+            value.round().as_()
+        } else {
+            value.as_()
+        }
+    }
+
+    pub(crate) fn build_table<
+        T: Default + Copy + 'static + PointeeSizeExpressible,
+        const BUCKET: usize,
+        const N: usize,
+        const BIT_DEPTH: usize,
+    >(
+        &self,
+    ) -> Box<[T; BUCKET]>
+    where
+        f32: AsPrimitive<T>,
+    {
+        let mut table = Box::new([T::default(); BUCKET]);
+        for (v, output) in table.iter_mut().take(N).enumerate() {
+            *output = self.entry::<T, N, BIT_DEPTH>(v);
+        }
+        table
     }
-    table
 }
 
 fn lut_inverse_interp16(value: u16, lut_table: &[u16]) -> u16 {
@@ -699,13 +899,104 @@ impl ToneReprCurve {
     >(
         &self,
     ) -> Option<Box<[f32; N]>> {
-        match self {
+        let mut table = match self {
             ToneReprCurve::Parametric(params) => linear_curve_parametric::<T, N, BIT_DEPTH>(params),
             ToneReprCurve::Lut(data) => match data.len() {
                 0 => Some(passthrough_table::<T, N, BIT_DEPTH>()),
                 1 => Some(linear_forward_table::<T, N, BIT_DEPTH>(data[0])),
                 _ => Some(linear_lut_interpolate::<T, N, BIT_DEPTH>(data)),
             },
+        }?;
+        pin_linear_endpoints::<T, N, BIT_DEPTH>(&mut table);
+        saturate_linear_table::<T, N, BIT_DEPTH>(&mut table);
+        Some(table)
+    }
+
+    /// Checks if curve is a closed form function rather than a set of samples.
+    #[cfg(feature = "transforms")]
+    pub(crate) fn is_analytic(&self) -> bool {
+        match self {
+            ToneReprCurve::Parametric(_) => true,
+            ToneReprCurve::Lut(data) => data.len() < 2,
+        }
+    }
+
+    /// Makes evaluator of the curve, from encoded to linear values.
+    pub(crate) fn linearize_evaluator(&self) -> Option<ToneCurveEvaluator> {
+        match self {
+            ToneReprCurve::Parametric(params) => {
+                if params.len() == 1 {
+                    return Some(ToneCurveEvaluator::Power(params[0]));
+                }
+                ParametricCurve::new(params).map(ToneCurveEvaluator::Parametric)
+            }
+            ToneReprCurve::Lut(data) => match data.len() {
+                0 => Some(ToneCurveEvaluator::Identity),
+                1 => Some(ToneCurveEvaluator::Power(u8_fixed_8number_to_float(
+                    data[0],
+                ))),
+                _ => Some(ToneCurveEvaluator::Sampled(
+                    data.iter().map(|&x| x as f32 * (1. / 65535.)).collect(),
+                )),
+            },
+        }
+    }
+
+    /// Makes evaluator of the inverse curve, from linear to encoded values.
+    #[cfg(feature = "transforms")]
+    pub(crate) fn gamma_evaluator(&self) -> Option<ToneCurveEvaluator> {
+        match self {
+            ToneReprCurve::Parametric(params) => {
+                if params.len() == 1 {
+                    return Some(ToneCurveEvaluator::Power(1. / params[0]));
+                }
+                let curve = ParametricCurve::new(params)?;
+                if let Some(inverted) = curve.invert() {
+                    return Some(ToneCurveEvaluator::Parametric(inverted));
+                }
+                // Not invertible in closed form, e.g. curves with a flat toe
+                let samples = linear_curve_parametric_s::<4096>(params)?;
+                let samples = samples
+                    .iter()
+                    .map(|&x| (x * 65535.).round() as u16)
+                    .collect::<Vec<u16>>();
+                Some(ToneCurveEvaluator::Sampled(
+                    invert_lut(&samples, 4096)
+                        .iter()
+                        .map(|&x| x as f32 * (1. / 65535.))
+                        .collect(),
+                ))
+            }
+            ToneReprCurve::Lut(data) => match data.len() {
+                0 => Some(ToneCurveEvaluator::Identity),
+                1 => Some(ToneCurveEvaluator::Power(
+                    1. / u8_fixed_8number_to_float(data[0]),
+                )),
+                _ => Some(ToneCurveEvaluator::Sampled(
+                    invert_lut(data, data.len().max(4096))
+                        .iter()
+                        .map(|&x| x as f32 * (1. / 65535.))
+                        .collect(),
+                )),
+            },
+        }
+    }
+
+    /// Closed form of gamma table this curve builds, `None` when table
+    /// can only be made by numerical inversion.
+    #[cfg(feature = "transforms")]
+    pub(crate) fn gamma_table_source(&self) -> Option<GammaTableSource> {
+        match self {
+            ToneReprCurve::Parametric(params) => ParametricCurve::new(params)
+                .and_then(|x| x.invert())
+                .map(GammaTableSource::Parametric),
+            ToneReprCurve::Lut(data) => match data.len() {
+                0 => Some(GammaTableSource::Linear),
+                1 => Some(GammaTableSource::Power(
+                    1. / u8_fixed_8number_to_float(data[0]),
+                )),
+                _ => None,
+            },
         }
     }
 
@@ -724,6 +1015,13 @@ impl ToneReprCurve {
     {
         match self {
             ToneReprCurve::Parametric(params) => {
+                if let Some(inverted) = ParametricCurve::new(params).and_then(|x| x.invert()) {
+                    return Some(
+                        GammaTableSource::Parametric(inverted)
+                            .build_table::<T, BUCKET, N, BIT_DEPTH>(),
+                    );
+                }
+                // Analytic inverse doesn't exist, invert numerically
                 let mut gamma_table_uint = Box::new([0; N]);
 
                 let inverted_size: usize = N;
@@ -777,6 +1075,26 @@ impl ColorProfile {
             .ok_or(CmsError::BuildTransferFunction)
     }
 
+    /// Produces LUT for Gray transfer curve checking CICP for Transfer characteristics first.
+    #[cfg(feature = "transforms")]
+    pub(crate) fn build_gray_linearize_table_cicp<
+        T: PointeeSizeExpressible,
+        const N: usize,
+        const BIT_DEPTH: usize,
+    >(
+        &self,
+        use_cicp: bool,
+    ) -> Result<Box<[f32; N]>, CmsError> {
+        if use_cicp {
+            if let Some(tc) = self.cicp.as_ref().map(|c| c.transfer_characteristics) {
+                if tc.has_transfer_curve() {
+                    return Ok(tc.make_linear_table::<T, N, BIT_DEPTH>());
+                }
+            }
+        }
+        self.build_gray_linearize_table::<T, N, BIT_DEPTH>()
+    }
+
     /// Produces LUT for Red transfer curve with N depth
     pub fn build_r_linearize_table<
         T: PointeeSizeExpressible,
@@ -883,6 +1201,74 @@ impl ColorProfile {
         self.build_gamma_table::<u16, 65536, 65536, 16>(trc, use_cicp)
     }
 
+    /// Makes evaluator from encoded to linear values for floating point transforms.
+    ///
+    /// Analytic ICC curves are preferred since they may be evaluated out of [0, 1] range,
+    /// CICP transfer characteristics are used next if allowed, then sampled ICC curve.
+    #[cfg(feature = "transforms")]
+    pub(crate) fn linearize_evaluator(
+        &self,
+        trc: &Option<ToneReprCurve>,
+        use_cicp: bool,
+    ) -> Result<ToneCurveEvaluator, CmsError> {
+        if let Some(trc) = trc.as_ref().filter(|x| x.is_analytic()) {
+            return trc
+                .linearize_evaluator()
+                .ok_or(CmsError::BuildTransferFunction);
+        }
+        if use_cicp {
+            if let Some(tc) = self.cicp.as_ref().map(|c| c.transfer_characteristics) {
+                if tc.has_transfer_curve() {
+                    return Ok(ToneCurveEvaluator::CicpLinearize(tc));
+                }
+            }
+        }
+        trc.as_ref()
+            .and_then(|trc| trc.linearize_evaluator())
+            .ok_or(CmsError::BuildTransferFunction)
+    }
+
+    /// Makes evaluator from linear to encoded values for floating point transforms.
+    ///
+    /// Sources are preferred in the same order as `linearize_evaluator` does.
+    #[cfg(feature = "transforms")]
+    pub(crate) fn gamma_evaluator(
+        &self,
+        trc: &Option<ToneReprCurve>,
+        use_cicp: bool,
+    ) -> Result<ToneCurveEvaluator, CmsError> {
+        if let Some(trc) = trc.as_ref().filter(|x| x.is_analytic()) {
+            return trc.gamma_evaluator().ok_or(CmsError::BuildTransferFunction);
+        }
+        if use_cicp {
+            if let Some(tc) = self.cicp.as_ref().map(|c| c.transfer_characteristics) {
+                if tc.has_transfer_curve() {
+                    return Ok(ToneCurveEvaluator::CicpGamma(tc));
+                }
+            }
+        }
+        trc.as_ref()
+            .and_then(|trc| trc.gamma_evaluator())
+            .ok_or(CmsError::BuildTransferFunction)
+    }
+
+    /// Closed form of the table [ColorProfile::build_gamma_table] makes, if there is one.
+    #[cfg(feature = "transforms")]
+    pub(crate) fn gamma_table_source(
+        &self,
+        trc: &Option<ToneReprCurve>,
+        use_cicp: bool,
+    ) -> Option<GammaTableSource> {
+        if use_cicp {
+            if let Some(tc) = self.cicp.as_ref().map(|c| c.transfer_characteristics) {
+                if tc.has_transfer_curve() {
+                    return Some(GammaTableSource::Cicp(tc));
+                }
+            }
+        }
+        trc.as_ref().and_then(|trc| trc.gamma_table_source())
+    }
+
     /// Builds gamma table checking CICP for Transfer characteristics first.
     pub fn build_gamma_table<
         T: Default + Copy + 'static + PointeeSizeExpressible + GammaLutInterpolate,
@@ -910,3 +1296,163 @@ impl ColorProfile {
             .ok_or(CmsError::BuildTransferFunction)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One curve of every parametric function type, 0 to 4
+    const CURVES: [&[f32]; 5] = [
+        &[2.2],
+        &[2.4, 1.1, -0.1],
+        &[1.8, 0.9, 0.05, 0.1],
+        &[2.4, 1. / 1.055, 0.055 / 1.055, 1. / 12.92, 0.04045],
+        &[2.2, 0.95, 0.05, 0.5, 0.03, 0.02, 0.0087],
+    ];
+
+    #[test]
+    fn test_parametric_inverse_all_types() {
+        for params in CURVES {
+            let curve = ParametricCurve::new(params).unwrap();
+            let inverse = curve.invert().unwrap_or_else(|| panic!("{params:?}"));
+            for i in 0..=1000 {
+                let x = i as f32 / 1000.;
+                // Flat toe of types 1 and 2 maps back to the cross-over point
+                let expected = x.max(curve.d.min(1.));
+                let y = curve.eval(x);
+                let back = inverse.eval(y);
+                if x >= curve.d || curve.c != 0. {
+                    assert!((back - x).abs() < 1e-3, "{params:?}: {x} -> {y} -> {back}");
+                } else {
+                    assert!((back - expected).abs() < 1e-3, "{params:?}: {x} -> {back}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parametric_degenerate() {
+        // a == 0 makes curve constant, there is no inverse
+        let constant = ParametricCurve::new(&[2.0, 0., 0.5]).unwrap();
+        assert!((constant.eval(0.3) - 0.25).abs() < 1e-6);
+        assert!(constant.invert().is_none());
+        let table =
+            ToneReprCurve::Parametric(vec![2.0, 0., 0.5]).build_gamma_table::<u8, 65536, 4096, 8>();
+        assert!(table.is_some());
+
+        // Zero slope linear segment
+        let flat = ParametricCurve::new(&[2.4, 1., 0., 0., 0.1]).unwrap();
+        assert_eq!(flat.eval(0.05), 0.);
+        let inverse = flat.invert().unwrap();
+        assert!((inverse.eval(flat.eval(0.5)) - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_parametric_tables_match_sampled() {
+        let profile = ColorProfile::new_srgb();
+        for params in CURVES {
+            let curve = ParametricCurve::new(params).unwrap();
+            let parametric = Some(ToneReprCurve::Parametric(params.to_vec()));
+            let sampled = Some(ToneReprCurve::Lut(
+                (0..4096)
+                    .map(|i| (curve.eval(i as f32 / 4095.).clamp(0., 1.) * 65535.).round() as u16)
+                    .collect(),
+            ));
+
+            let lin = profile.build_8bit_lin_table(&parametric).unwrap();
+            let lin_sampled = profile.build_8bit_lin_table(&sampled).unwrap();
+            for (i, (&a, &b)) in lin.iter().zip(lin_sampled.iter()).enumerate() {
+                assert!((a - b).abs() < 1e-4, "{params:?} at {i}: {a} vs {b}");
+            }
+
+            let gamma = profile
+                .build_gamma_table::<u8, 65536, 4096, 8>(&parametric, false)
+                .unwrap();
+            let gamma_sampled = profile
+                .build_gamma_table::<u8, 65536, 4096, 8>(&sampled, false)
+                .unwrap();
+            // Flat toe has no single inverse
+            let start = (curve.eval(curve.d.max(0.)) * 65535.).floor() as usize + 1;
+            for (i, (&a, &b)) in gamma
+                .iter()
+                .zip(gamma_sampled.iter())
+                .enumerate()
+                .skip(start)
+            {
+                assert!(a.abs_diff(b) <= 1, "{params:?} at {i}: {a} vs {b}");
+            }
+        }
+
+        // sRGB parametric definition against the closed form
+        let srgb = profile.build_8bit_lin_table(&profile.red_trc).unwrap();
+        for (i, &v) in srgb.iter().enumerate() {
+            let reference = TransferCharacteristics::Srgb.linearize(i as f64 / 255.) as f32;
+            assert!((v - reference).abs() < 1e-4, "{i}: {v} vs {reference}");
+        }
+    }
+
+    #[test]
+    fn test_parametric_gamma_table_inverts_curve() {
+        for params in CURVES {
+            let curve = ParametricCurve::new(params).unwrap();
+            let table = ToneReprCurve::Parametric(params.to_vec())
+                .build_gamma_table::<u16, 65536, 65536, 16>()
+                .unwrap();
+            let start = curve.eval(curve.d.max(0.));
+            for (i, &v) in table.iter().enumerate().step_by(97) {
+                let linear = i as f32 / 65535.;
+                if linear < start {
+                    continue;
+                }
+                let back = curve.eval(v as f32 / 65535.);
+                assert!(
+                    (back - linear).abs() < 1e-3,
+                    "{params:?} at {i}: {linear} vs {back}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "transforms")]
+    fn test_curve_from_fn_round_trip() {
+        use crate::gamma::hlg_to_linear;
+
+        let power = |x: f32| powf(x, 2.2);
+        let srgb = ParametricCurve::new(CURVES[3]).unwrap();
+        let curves: [(ToneReprCurve, &dyn Fn(f64) -> f64); 3] = [
+            (curve_from_fn(power), &|x| power(x as f32) as f64),
+            (TransferCharacteristics::Hlg.try_into().unwrap(), &|x| {
+                hlg_to_linear(x).min(1.)
+            }),
+            (curve_from_fn(|x| srgb.eval(x)), &|x| {
+                srgb.eval(x as f32) as f64
+            }),
+        ];
+
+        for (curve, eotf) in curves {
+            let ToneReprCurve::Lut(table) = &curve else {
+                unreachable!()
+            };
+            assert!(table.len() > 1 && table.len() <= MAX_TRC_TABLE_ENTRIES);
+
+            let mut profile = ColorProfile::new_srgb();
+            profile.red_trc = Some(curve.clone());
+            let encoded = profile.encode().unwrap();
+            let decoded = ColorProfile::new_from_slice(&encoded).unwrap();
+            let Some(ToneReprCurve::Lut(decoded_table)) = &decoded.red_trc else {
+                panic!("curv is expected");
+            };
+            assert_eq!(decoded_table, table);
+
+            let evaluator = decoded
+                .linearize_evaluator(&decoded.red_trc, false)
+                .unwrap();
+            for i in 0..10_000 {
+                let x = i as f64 / 9_999.;
+                let error = (evaluator.evaluate(x as f32) as f64 - eotf(x)).abs() * 65535.;
+                assert!(error <= 1., "{} entries at {x}: {error}", table.len());
+            }
+        }
+    }
+}
@@ -0,0 +1,401 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::tag::Tag;
+use crate::{
+    CmsError, ColorProfile, DataColorSpace, LutWarehouse, ProfileClass, ToneReprCurve, Xyz,
+};
+
+/// Problem found by [ColorProfile::validate]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ValidationIssue {
+    /// Tag required by the profile class and color space is absent, tag signature is given
+    MissingTag(u32),
+    /// Tone curve of the tag is not monotonic
+    NonMonotonicCurve(u32),
+    /// Tone curve of the tag has parameters which can't be evaluated
+    InvalidCurve(u32),
+    /// CLUT of the tag has less than 2 grid points in some dimension
+    InvalidGridSize(u32),
+    /// LUT of the tag doesn't match channels count of the profile, or its tables are truncated
+    InvalidLutShape(u32),
+    /// Header illuminant is not finite or zero
+    InvalidWhitePoint,
+    /// Media white point is not finite or zero
+    InvalidMediaWhitePoint,
+    /// Profile class or color space can't be used in transforms
+    UnsupportedProfile,
+}
+
+/// Result of [ColorProfile::validate]
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ProfileValidation {
+    /// Issues that make the profile unusable for transforms
+    pub errors: Vec<ValidationIssue>,
+    /// Non-fatal oddities, transforms still can be made
+    pub warnings: Vec<ValidationIssue>,
+}
+
+impl ProfileValidation {
+    /// Returns true if there are no hard errors
+    pub fn is_usable(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+fn color_space_channels(color_space: DataColorSpace) -> Option<usize> {
+    match color_space {
+        DataColorSpace::Gray => Some(1),
+        DataColorSpace::Color2 => Some(2),
+        DataColorSpace::Xyz
+        | DataColorSpace::Lab
+        | DataColorSpace::Luv
+        | DataColorSpace::YCbr
+        | DataColorSpace::Yxy
+        | DataColorSpace::Rgb
+        | DataColorSpace::Hsv
+        | DataColorSpace::Hls
+        | DataColorSpace::Cmy
+        | DataColorSpace::Color3 => Some(3),
+        DataColorSpace::Cmyk | DataColorSpace::Color4 => Some(4),
+        _ => None,
+    }
+}
+
+fn is_valid_white(xyz: Xyz) -> bool {
+    xyz.x.is_finite() && xyz.y.is_finite() && xyz.z.is_finite() && xyz.y > 0.
+}
+
+fn validate_curve(curve: &ToneReprCurve, tag: Tag, validation: &mut ProfileValidation) {
+    let Some(evaluator) = curve.linearize_evaluator() else {
+        validation
+            .errors
+            .push(ValidationIssue::InvalidCurve(tag.into()));
+        return;
+    };
+    const SAMPLES: usize = 1024;
+    let mut rising = true;
+    let mut falling = true;
+    let mut previous = evaluator.evaluate(0.);
+    for i in 1..SAMPLES {
+        let value = evaluator.evaluate(i as f32 / (SAMPLES - 1) as f32);
+        if !value.is_finite() {
+            validation
+                .errors
+                .push(ValidationIssue::InvalidCurve(tag.into()));
+            return;
+        }
+        rising &= value >= previous;
+        falling &= value <= previous;
+        previous = value;
+    }
+    if !rising && !falling {
+        validation
+            .warnings
+            .push(ValidationIssue::NonMonotonicCurve(tag.into()));
+    }
+}
+
+fn validate_lut(
+    lut: &LutWarehouse,
+    tag: Tag,
+    inputs: usize,
+    outputs: usize,
+    validation: &mut ProfileValidation,
+) -> Result<(), CmsError> {
+    let (in_channels, out_channels, grid, clut_len) = match lut {
+        LutWarehouse::Lut(lut) => {
+            let grid = [lut.num_clut_grid_points; 16];
+            (
+                lut.num_input_channels as usize,
+                lut.num_output_channels as usize,
+                grid,
+                lut.clut_table.len(),
+            )
+        }
+        LutWarehouse::MCurves(mab) => (
+            mab.num_input_channels as usize,
+            mab.num_output_channels as usize,
+            mab.grid_points,
+            mab.clut.len(),
+        ),
+    };
+    if in_channels != inputs || out_channels != outputs || in_channels > grid.len() {
+        validation
+            .errors
+            .push(ValidationIssue::InvalidLutShape(tag.into()));
+        return Ok(());
+    }
+    // Matrix/curves only mAB and mBA tags have no CLUT
+    if clut_len == 0 && matches!(lut, LutWarehouse::MCurves(_)) {
+        return Ok(());
+    }
+    if grid[..in_channels].iter().any(|&x| x < 2) {
+        validation
+            .errors
+            .push(ValidationIssue::InvalidGridSize(tag.into()));
+        return Ok(());
+    }
+    let mut expected = out_channels;
+    for &points in grid[..in_channels].iter() {
+        expected = expected
+            .checked_mul(points as usize)
+            .ok_or(CmsError::OverflowingError)?;
+    }
+    if clut_len < expected {
+        validation
+            .errors
+            .push(ValidationIssue::InvalidLutShape(tag.into()));
+    }
+    Ok(())
+}
+
+impl ColorProfile {
+    /// Checks if the profile is usable for transforms.
+    ///
+    /// Required tags per profile class and color space, tone curves, CLUT grids
+    /// and white points are examined. Hard errors and non-fatal oddities are reported separately,
+    /// an error is returned only if sizes of the profile tables overflow.
+    pub fn validate(&self) -> Result<ProfileValidation, CmsError> {
+        let mut validation = ProfileValidation::default();
+
+        if !is_valid_white(self.white_point) {
+            validation.errors.push(ValidationIssue::InvalidWhitePoint);
+        }
+        match self.media_white_point {
+            Some(wp) if !is_valid_white(wp) => {
+                validation
+                    .errors
+                    .push(ValidationIssue::InvalidMediaWhitePoint);
+            }
+            Some(_) => {}
+            None => validation
+                .warnings
+                .push(ValidationIssue::MissingTag(Tag::MediaWhitePoint.into())),
+        }
+        if self.description.is_none() {
+            validation
+                .warnings
+                .push(ValidationIssue::MissingTag(Tag::ProfileDescription.into()));
+        }
+
+        let channels = color_space_channels(self.color_space);
+        let supported_class = matches!(
+            self.profile_class,
+            ProfileClass::InputDevice
+                | ProfileClass::DisplayDevice
+                | ProfileClass::OutputDevice
+                | ProfileClass::ColorSpace
+        );
+        let supported_pcs = self.pcs == DataColorSpace::Xyz || self.pcs == DataColorSpace::Lab;
+        let Some(channels) = channels.filter(|_| supported_class && supported_pcs) else {
+            validation.errors.push(ValidationIssue::UnsupportedProfile);
+            return Ok(validation);
+        };
+
+        for (lut, tag) in [
+            (&self.lut_a_to_b_perceptual, Tag::DeviceToPcsLutPerceptual),
+            (
+                &self.lut_a_to_b_colorimetric,
+                Tag::DeviceToPcsLutColorimetric,
+            ),
+            (&self.lut_a_to_b_saturation, Tag::DeviceToPcsLutSaturation),
+        ] {
+            if let Some(lut) = lut {
+                validate_lut(lut, tag, channels, 3, &mut validation)?;
+            }
+        }
+        for (lut, tag) in [
+            (&self.lut_b_to_a_perceptual, Tag::PcsToDeviceLutPerceptual),
+            (
+                &self.lut_b_to_a_colorimetric,
+                Tag::PcsToDeviceLutColorimetric,
+            ),
+            (&self.lut_b_to_a_saturation, Tag::PcsToDeviceLutSaturation),
+        ] {
+            if let Some(lut) = lut {
+                validate_lut(lut, tag, 3, channels, &mut validation)?;
+            }
+        }
+        if let Some(gamut) = &self.gamut {
+            validate_lut(gamut, Tag::Gamut, 3, 1, &mut validation)?;
+        }
+
+        for (curve, tag) in [
+            (&self.red_trc, Tag::RedToneReproduction),
+            (&self.green_trc, Tag::GreenToneReproduction),
+            (&self.blue_trc, Tag::BlueToneReproduction),
+            (&self.gray_trc, Tag::GreyToneReproduction),
+        ] {
+            if let Some(curve) = curve {
+                validate_curve(curve, tag, &mut validation);
+            }
+        }
+
+        let has_lut = self.has_device_to_pcs_lut();
+        match self.color_space {
+            DataColorSpace::Rgb if !has_lut => {
+                for (missing, tag) in [
+                    (self.red_colorant == Xyz::default(), Tag::RedXyz),
+                    (self.green_colorant == Xyz::default(), Tag::GreenXyz),
+                    (self.blue_colorant == Xyz::default(), Tag::BlueXyz),
+                    (self.red_trc.is_none(), Tag::RedToneReproduction),
+                    (self.green_trc.is_none(), Tag::GreenToneReproduction),
+                    (self.blue_trc.is_none(), Tag::BlueToneReproduction),
+                ] {
+                    if missing {
+                        validation
+                            .errors
+                            .push(ValidationIssue::MissingTag(tag.into()));
+                    }
+                }
+            }
+            DataColorSpace::Gray if !has_lut => {
+                if self.gray_trc.is_none() {
+                    validation.errors.push(ValidationIssue::MissingTag(
+                        Tag::GreyToneReproduction.into(),
+                    ));
+                }
+            }
+            DataColorSpace::Rgb | DataColorSpace::Gray => {}
+            _ => {
+                if !has_lut {
+                    validation.errors.push(ValidationIssue::MissingTag(
+                        Tag::DeviceToPcsLutPerceptual.into(),
+                    ));
+                }
+            }
+        }
+
+        // Output profiles are the destination of separations, so they need inverse tables
+        if self.profile_class == ProfileClass::OutputDevice {
+            let matrix_shaper = match self.color_space {
+                DataColorSpace::Rgb => {
+                    self.red_trc.is_some() && self.green_trc.is_some() && self.blue_trc.is_some()
+                }
+                DataColorSpace::Gray => self.gray_trc.is_some(),
+                _ => false,
+            };
+            if !self.has_pcs_to_device_lut() && !matrix_shaper {
+                validation.errors.push(ValidationIssue::MissingTag(
+                    Tag::PcsToDeviceLutPerceptual.into(),
+                ));
+            }
+            if self.gamut.is_none() && !matrix_shaper {
+                validation
+                    .warnings
+                    .push(ValidationIssue::MissingTag(Tag::Gamut.into()));
+            }
+        }
+
+        Ok(validation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_builtin_profiles() {
+        for profile in [
+            ColorProfile::new_srgb(),
+            ColorProfile::new_bt2020_pq(),
+            ColorProfile::new_gray_with_gamma(2.2),
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap(),
+        ] {
+            let validation = profile.validate().unwrap();
+            assert!(validation.is_usable(), "{validation:?}");
+            assert!(
+                !validation
+                    .warnings
+                    .iter()
+                    .any(|x| matches!(x, ValidationIssue::NonMonotonicCurve(_))),
+                "{validation:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_reports_issues() {
+        let mut rgb = ColorProfile::new_srgb();
+        rgb.green_trc = None;
+        rgb.white_point = Xyz::default();
+        rgb.red_trc = Some(ToneReprCurve::Lut(vec![0, 40000, 20000, 65535]));
+        let validation = rgb.validate().unwrap();
+        assert!(!validation.is_usable());
+        assert!(validation.errors.contains(&ValidationIssue::MissingTag(
+            Tag::GreenToneReproduction.into()
+        )));
+        assert!(
+            validation
+                .errors
+                .contains(&ValidationIssue::InvalidWhitePoint)
+        );
+        assert!(
+            validation
+                .warnings
+                .contains(&ValidationIssue::NonMonotonicCurve(
+                    Tag::RedToneReproduction.into()
+                ))
+        );
+
+        let cmyk =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        let mut no_b2a = cmyk.clone();
+        no_b2a.lut_b_to_a_perceptual = None;
+        no_b2a.lut_b_to_a_colorimetric = None;
+        no_b2a.lut_b_to_a_saturation = None;
+        assert_eq!(
+            no_b2a.validate().unwrap().errors,
+            vec![ValidationIssue::MissingTag(
+                Tag::PcsToDeviceLutPerceptual.into()
+            )]
+        );
+
+        let mut bad_grid = cmyk.clone();
+        if let Some(LutWarehouse::Lut(lut)) = &mut bad_grid.lut_a_to_b_perceptual {
+            lut.num_clut_grid_points = 1;
+        }
+        assert_eq!(
+            bad_grid.validate().unwrap().errors,
+            vec![ValidationIssue::InvalidGridSize(
+                Tag::DeviceToPcsLutPerceptual.into()
+            )]
+        );
+
+        let mut rgb_a2b = ColorProfile::new_srgb();
+        rgb_a2b.lut_a_to_b_perceptual = cmyk.lut_a_to_b_perceptual.clone();
+        assert_eq!(
+            rgb_a2b.validate().unwrap().errors,
+            vec![ValidationIssue::InvalidLutShape(
+                Tag::DeviceToPcsLutPerceptual.into()
+            )]
+        );
+    }
+}
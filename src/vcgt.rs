@@ -0,0 +1,254 @@
+/*
+ * // Copyright (c) Radzivon Bartoshyk 3/2025. All rights reserved.
+ * //
+ * // Redistribution and use in source and binary forms, with or without modification,
+ * // are permitted provided that the following conditions are met:
+ * //
+ * // 1.  Redistributions of source code must retain the above copyright notice, this
+ * // list of conditions and the following disclaimer.
+ * //
+ * // 2.  Redistributions in binary form must reproduce the above copyright notice,
+ * // this list of conditions and the following disclaimer in the documentation
+ * // and/or other materials provided with the distribution.
+ * //
+ * // 3.  Neither the name of the copyright holder nor the names of its
+ * // contributors may be used to endorse or promote products derived from
+ * // this software without specific prior written permission.
+ * //
+ * // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * // AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * // IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * // DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * // FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * // DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * // SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * // CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use crate::CmsError;
+use crate::profile::s15_fixed16_number_to_float;
+use crate::writer::{ByteSink, FloatToFixedS15Fixed16, write_u16_be, write_u32_be};
+
+/// Ramp of one channel of formula `vcgt`, `min + (max - min) * x^gamma`
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct VcgtFormula {
+    pub gamma: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Video card gamma ramp of Apple `vcgt` tag.
+///
+/// Display calibration tools store it in display profiles, OS loads it into the display
+/// pipeline, so it applies to everything after color management.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Vcgt {
+    /// Red, green and blue ramps sampled uniformly over [0, 1], values are in [0, 1].
+    ///
+    /// Single channel tables are kept as three equal ramps.
+    Table([Vec<f32>; 3]),
+    /// Red, green and blue ramps given by formula
+    Formula([VcgtFormula; 3]),
+}
+
+const VCGT_TABLE: u32 = 0;
+const VCGT_FORMULA: u32 = 1;
+
+impl Vcgt {
+    /// Parses `vcgt` tag data, starting with tag type signature
+    pub(crate) fn new_from_slice(slice: &[u8]) -> Result<Vcgt, CmsError> {
+        if slice.len() < 12 || &slice[0..4] != b"vcgt" {
+            return Err(CmsError::InvalidProfile);
+        }
+        let gamma_type = u32::from_be_bytes([slice[8], slice[9], slice[10], slice[11]]);
+        match gamma_type {
+            VCGT_TABLE => {
+                if slice.len() < 18 {
+                    return Err(CmsError::InvalidProfile);
+                }
+                let channels = u16::from_be_bytes([slice[12], slice[13]]) as usize;
+                let entries = u16::from_be_bytes([slice[14], slice[15]]) as usize;
+                let entry_size = u16::from_be_bytes([slice[16], slice[17]]) as usize;
+                if (channels != 1 && channels != 3)
+                    || entries < 2
+                    || (entry_size != 1 && entry_size != 2)
+                {
+                    return Err(CmsError::InvalidProfile);
+                }
+                let data = slice
+                    .get(18..18 + channels * entries * entry_size)
+                    .ok_or(CmsError::InvalidProfile)?;
+                let ramps = data
+                    .chunks_exact(entries * entry_size)
+                    .map(|ramp| {
+                        if entry_size == 1 {
+                            ramp.iter().map(|&x| x as f32 / 255.).collect()
+                        } else {
+                            ramp.chunks_exact(2)
+                                .map(|x| u16::from_be_bytes([x[0], x[1]]) as f32 / 65535.)
+                                .collect()
+                        }
+                    })
+                    .collect::<Vec<Vec<f32>>>();
+                Ok(if channels == 1 {
+                    Vcgt::Table([ramps[0].clone(), ramps[0].clone(), ramps[0].clone()])
+                } else {
+                    Vcgt::Table([ramps[0].clone(), ramps[1].clone(), ramps[2].clone()])
+                })
+            }
+            VCGT_FORMULA => {
+                let data = slice.get(12..48).ok_or(CmsError::InvalidProfile)?;
+                let mut values = [0f32; 9];
+                for (dst, src) in values.iter_mut().zip(data.chunks_exact(4)) {
+                    *dst = s15_fixed16_number_to_float(i32::from_be_bytes([
+                        src[0], src[1], src[2], src[3],
+                    ]));
+                }
+                let formula = |i: usize| VcgtFormula {
+                    gamma: values[i * 3],
+                    min: values[i * 3 + 1],
+                    max: values[i * 3 + 2],
+                };
+                Ok(Vcgt::Formula([formula(0), formula(1), formula(2)]))
+            }
+            _ => Err(CmsError::InvalidProfile),
+        }
+    }
+
+    /// Writes tag data, tables are always written with 3 channels of 16 bit entries.
+    /// Returns count of bytes written.
+    pub(crate) fn encode(&self, into: &mut impl ByteSink) -> Result<usize, CmsError> {
+        into.push(b'v');
+        into.push(b'c');
+        into.push(b'g');
+        into.push(b't');
+        write_u32_be(into, 0);
+        match self {
+            Vcgt::Table(ramps) => {
+                let entries = ramps[0].len();
+                if entries < 2
+                    || entries > u16::MAX as usize
+                    || ramps.iter().any(|x| x.len() != entries)
+                {
+                    return Err(CmsError::InvalidProfile);
+                }
+                write_u32_be(into, VCGT_TABLE);
+                write_u16_be(into, 3);
+                write_u16_be(into, entries as u16);
+                write_u16_be(into, 2);
+                for &v in ramps.iter().flatten() {
+                    write_u16_be(into, (v.max(0.).min(1.) * 65535. + 0.5) as u16);
+                }
+                Ok(18 + 3 * entries * 2)
+            }
+            Vcgt::Formula(formulas) => {
+                write_u32_be(into, VCGT_FORMULA);
+                for formula in formulas.iter() {
+                    for v in [formula.gamma, formula.min, formula.max] {
+                        write_u32_be(into, v.to_s15_fixed16() as u32);
+                    }
+                }
+                Ok(12 + 9 * 4)
+            }
+        }
+    }
+
+    /// Evaluates ramp of `channel`, 0 is red, 1 is green and 2 is blue, at `x` in [0, 1].
+    ///
+    /// Tables are interpolated linearly.
+    pub fn evaluate(&self, channel: usize, x: f32) -> f32 {
+        let x = x.max(0.).min(1.);
+        match self {
+            Vcgt::Table(ramps) => {
+                let ramp = &ramps[channel];
+                let position = x * (ramp.len() - 1) as f32;
+                let index = (position as usize).min(ramp.len() - 2);
+                let t = position - index as f32;
+                ramp[index] + (ramp[index + 1] - ramp[index]) * t
+            }
+            Vcgt::Formula(formulas) => {
+                let formula = formulas[channel];
+                formula.min + (formula.max - formula.min) * x.powf(formula.gamma)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table_tag(channels: u16, entry_size: u16, data: &[u8]) -> Vec<u8> {
+        let entries = data.len() / (channels * entry_size) as usize;
+        let mut tag = b"vcgt\0\0\0\0\0\0\0\0".to_vec();
+        tag.extend_from_slice(&channels.to_be_bytes());
+        tag.extend_from_slice(&(entries as u16).to_be_bytes());
+        tag.extend_from_slice(&entry_size.to_be_bytes());
+        tag.extend_from_slice(data);
+        tag
+    }
+
+    #[test]
+    fn test_vcgt_table_sizes() {
+        for entries in [256usize, 1024, 37] {
+            // Ramps of 0.9x, x and sqrt(x) sampled at 16 bit
+            let ramp = |f: fn(f32) -> f32| {
+                (0..entries).flat_map(move |i| {
+                    let v = f(i as f32 / (entries - 1) as f32);
+                    ((v * 65535.).round() as u16).to_be_bytes()
+                })
+            };
+            let data = ramp(|x| 0.9 * x)
+                .chain(ramp(|x| x))
+                .chain(ramp(|x| x.sqrt()))
+                .collect::<Vec<u8>>();
+            let vcgt = Vcgt::new_from_slice(&table_tag(3, 2, &data)).unwrap();
+            for i in 0..=1000 {
+                let x = i as f32 / 1000.;
+                assert!((vcgt.evaluate(0, x) - 0.9 * x).abs() < 1e-4);
+                assert!((vcgt.evaluate(1, x) - x).abs() < 1e-4);
+                // Linear interpolation of concave ramp stays below it
+                let step = 1. / (entries - 1) as f32;
+                let expected = x.sqrt();
+                let error = expected - vcgt.evaluate(2, x);
+                assert!(error > -1e-4 && error < step.sqrt() / 4. + 1e-4, "{x}");
+            }
+
+            let mut encoded = Vec::new();
+            let size = vcgt.encode(&mut encoded).unwrap();
+            assert_eq!(size, encoded.len());
+            assert_eq!(Vcgt::new_from_slice(&encoded).unwrap(), vcgt);
+        }
+    }
+
+    #[test]
+    fn test_vcgt_single_channel_8bit() {
+        let data = [0u8, 64, 128, 255];
+        let vcgt = Vcgt::new_from_slice(&table_tag(1, 1, &data)).unwrap();
+        for channel in 0..3 {
+            assert_eq!(vcgt.evaluate(channel, 0.), 0.);
+            assert_eq!(vcgt.evaluate(channel, 1.), 1.);
+            assert!((vcgt.evaluate(channel, 0.5) - 96. / 255.).abs() < 1e-6);
+        }
+        assert!(Vcgt::new_from_slice(&table_tag(2, 1, &data)).is_err());
+        assert!(Vcgt::new_from_slice(&table_tag(1, 1, &data)[..20]).is_err());
+    }
+
+    #[test]
+    fn test_vcgt_formula() {
+        let mut tag = b"vcgt\0\0\0\0\0\0\0\x01".to_vec();
+        for (gamma, min, max) in [(1.0f32, 0.0f32, 1.0f32), (2.2, 0.1, 0.9), (0.5, 0., 0.5)] {
+            for v in [gamma, min, max] {
+                tag.extend_from_slice(&v.to_s15_fixed16().to_be_bytes());
+            }
+        }
+        let vcgt = Vcgt::new_from_slice(&tag).unwrap();
+        assert!((vcgt.evaluate(0, 0.3) - 0.3).abs() < 1e-4);
+        assert!((vcgt.evaluate(1, 0.5) - (0.1 + 0.8 * 0.5f32.powf(2.2))).abs() < 1e-4);
+        assert!((vcgt.evaluate(2, 0.25) - 0.25).abs() < 1e-4);
+        let mut encoded = Vec::new();
+        assert_eq!(vcgt.encode(&mut encoded).unwrap(), tag.len());
+        assert_eq!(encoded, tag);
+    }
+}
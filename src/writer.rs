@@ -26,13 +26,17 @@
  * // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
  * // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
+use crate::colorant_table::encode_colorant_table;
+use crate::md5::md5;
 use crate::profile::{LutDataType, ProfileHeader};
 use crate::tag::{TAG_SIZE, Tag, TagTypeDefinition};
 use crate::trc::ToneReprCurve;
 use crate::{
     CicpProfile, CmsError, ColorDateTime, ColorProfile, LocalizableString, LutMCurvesType, LutType,
-    LutWarehouse, Matrix3f, ProfileSignature, ProfileText, ProfileVersion, Vector3f, Xyz,
+    LutWarehouse, Matrix3f, Measurement, ProfileClass, ProfileSignature, ProfileText,
+    ProfileVersion, Vector3f, Xyz,
 };
+use std::borrow::Cow;
 
 pub(crate) trait FloatToFixedS15Fixed16 {
     fn to_s15_fixed16(self) -> i32;
@@ -66,8 +70,96 @@ impl FloatToFixedS15Fixed16 for f32 {
     }
 }
 
+/// Options for [ColorProfile::encode_into] and [ColorProfile::encoded_size]
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct EncodeOptions {
+    /// Creation date written into the header, current date if not set
+    pub creation_date_time: Option<ColorDateTime>,
+    /// Leaves [ColorProfile::unknown_tags] out instead of writing them back
+    pub strip_unknown_tags: bool,
+    /// Points sampled tone reproduction curves are resampled to, e.g. 256, 1024 or 4096.
+    ///
+    /// `0` writes sampled curves with their own number of points.
+    /// Pure gamma curves are always written as a single `u8Fixed8` gamma
+    /// and parametric curves as `para`, this affects only `rTRC`, `gTRC`, `bTRC` and `kTRC`.
+    pub trc_table_size: usize,
+}
+
+/// Destination of encoded profile bytes
+pub(crate) trait ByteSink {
+    fn push(&mut self, value: u8);
+
+    /// Count of bytes written so far
+    fn len(&self) -> usize;
+}
+
+impl ByteSink for Vec<u8> {
+    #[inline]
+    fn push(&mut self, value: u8) {
+        Vec::push(self, value);
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+}
+
+/// Counts bytes instead of storing them, used to size the profile ahead of writing it
+#[derive(Default)]
+struct ByteCounter {
+    len: usize,
+}
+
+impl ByteSink for ByteCounter {
+    #[inline]
+    fn push(&mut self, _: u8) {
+        self.len += 1;
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+/// Writes into caller storage, bytes beyond its end are counted but dropped
+struct SliceSink<'a> {
+    buf: &'a mut [u8],
+    position: usize,
+}
+
+impl<'a> SliceSink<'a> {
+    fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, position: 0 }
+    }
+
+    /// Checks that storage was filled exactly as it was sized
+    fn finish(&self) -> Result<(), CmsError> {
+        if self.position != self.buf.len() {
+            return Err(CmsError::InvalidProfile);
+        }
+        Ok(())
+    }
+}
+
+impl ByteSink for SliceSink<'_> {
+    #[inline]
+    fn push(&mut self, value: u8) {
+        if let Some(dst) = self.buf.get_mut(self.position) {
+            *dst = value;
+        }
+        self.position += 1;
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.position
+    }
+}
+
 #[inline]
-fn write_u32_be(into: &mut Vec<u8>, value: u32) {
+pub(crate) fn write_u32_be(into: &mut impl ByteSink, value: u32) {
     let bytes = value.to_be_bytes();
     into.push(bytes[0]);
     into.push(bytes[1]);
@@ -76,21 +168,21 @@ fn write_u32_be(into: &mut Vec<u8>, value: u32) {
 }
 
 #[inline]
-pub(crate) fn write_u16_be(into: &mut Vec<u8>, value: u16) {
+pub(crate) fn write_u16_be(into: &mut impl ByteSink, value: u16) {
     let bytes = value.to_be_bytes();
     into.push(bytes[0]);
     into.push(bytes[1]);
 }
 
 #[inline]
-pub(crate) fn write_fixed_array<const N: usize>(into: &mut Vec<u8>, value: &[u8; N]) {
+pub(crate) fn write_fixed_array<const N: usize>(into: &mut impl ByteSink, value: &[u8; N]) {
     for &i in value.iter() {
         into.push(i);
     }
 }
 
 #[inline]
-fn write_i32_be(into: &mut Vec<u8>, value: i32) {
+fn write_i32_be(into: &mut impl ByteSink, value: i32) {
     let bytes = value.to_be_bytes();
     into.push(bytes[0]);
     into.push(bytes[1]);
@@ -113,7 +205,7 @@ fn first_two_ascii_bytes(s: &String) -> [u8; 2] {
 
 /// Writes Multi Localized Unicode
 #[inline]
-fn write_mluc(into: &mut Vec<u8>, strings: &[LocalizableString]) -> usize {
+fn write_mluc(into: &mut impl ByteSink, strings: &[LocalizableString]) -> usize {
     assert!(!strings.is_empty());
     let start = into.len();
     let tag_def: u32 = TagTypeDefinition::MultiLocalizedUnicode.into();
@@ -126,7 +218,8 @@ fn write_mluc(into: &mut Vec<u8>, strings: &[LocalizableString]) -> usize {
     write_fixed_array(into, &lang);
     let country = first_two_ascii_bytes(&strings[0].country);
     write_fixed_array(into, &country);
-    let first_string_len = strings[0].value.len() * 2;
+    // Lengths are in bytes of UTF-16 encoding, not of UTF-8 one
+    let first_string_len = strings[0].value.encode_utf16().count() * 2;
     write_u32_be(into, first_string_len as u32);
     let mut first_string_offset = 16 + 12 * strings.len();
     write_u32_be(into, first_string_offset as u32);
@@ -136,7 +229,7 @@ fn write_mluc(into: &mut Vec<u8>, strings: &[LocalizableString]) -> usize {
         write_fixed_array(into, &lang);
         let country = first_two_ascii_bytes(&record.country);
         write_fixed_array(into, &country);
-        let first_string_len = record.value.len() * 2;
+        let first_string_len = record.value.encode_utf16().count() * 2;
         write_u32_be(into, first_string_len as u32);
         write_u32_be(into, first_string_offset as u32);
         first_string_offset += first_string_len;
@@ -151,7 +244,7 @@ fn write_mluc(into: &mut Vec<u8>, strings: &[LocalizableString]) -> usize {
 }
 
 #[inline]
-fn write_string_value(into: &mut Vec<u8>, text: &ProfileText) -> usize {
+fn write_string_value(into: &mut impl ByteSink, text: &ProfileText) -> usize {
     match text {
         ProfileText::PlainString(text) => {
             let vec = vec![LocalizableString {
@@ -168,10 +261,15 @@ fn write_string_value(into: &mut Vec<u8>, text: &ProfileText) -> usize {
             write_mluc(into, localizable)
         }
         ProfileText::Description(description) => {
+            let value = if description.unicode_string.is_empty() {
+                description.ascii_string.clone()
+            } else {
+                description.unicode_string.clone()
+            };
             let vec = vec![LocalizableString {
                 language: "en".to_string(),
                 country: "US".to_string(),
-                value: description.unicode_string.clone(),
+                value,
             }];
             write_mluc(into, &vec)
         }
@@ -179,7 +277,7 @@ fn write_string_value(into: &mut Vec<u8>, text: &ProfileText) -> usize {
 }
 
 #[inline]
-fn write_xyz_tag_value(into: &mut Vec<u8>, xyz: Xyz) {
+fn write_xyz_tag_value(into: &mut impl ByteSink, xyz: Xyz) {
     let tag_definition: u32 = TagTypeDefinition::Xyz.into();
     write_u32_be(into, tag_definition);
     write_u32_be(into, 0);
@@ -192,15 +290,84 @@ fn write_xyz_tag_value(into: &mut Vec<u8>, xyz: Xyz) {
 }
 
 #[inline]
-fn write_tag_entry(into: &mut Vec<u8>, tag: Tag, tag_entry: usize, tag_size: usize) {
-    let tag_value: u32 = tag.into();
-    write_u32_be(into, tag_value);
+fn write_meas_tag_value(into: &mut impl ByteSink, measurement: &Measurement) {
+    let tag_definition: u32 = TagTypeDefinition::Measurement.into();
+    write_u32_be(into, tag_definition);
+    write_u32_be(into, 0);
+    write_u32_be(into, measurement.observer.into());
+    write_i32_be(into, measurement.backing.x.to_s15_fixed16());
+    write_i32_be(into, measurement.backing.y.to_s15_fixed16());
+    write_i32_be(into, measurement.backing.z.to_s15_fixed16());
+    write_u32_be(into, measurement.geometry.into());
+    // u16Fixed16Number
+    write_u32_be(into, (measurement.flare.max(0.) * 65536. + 0.5) as u32);
+    write_u32_be(into, measurement.illuminant.into());
+}
+
+/// Pads tag data, ICC requires every tag to start on a 4 byte boundary.
+/// Returns padded size.
+#[inline]
+fn pad_tag_data(into: &mut impl ByteSink, entry_size: usize) -> usize {
+    let padded = entry_size.next_multiple_of(4);
+    for _ in entry_size..padded {
+        into.push(0);
+    }
+    padded
+}
+
+#[inline]
+fn write_tag_entry(into: &mut impl ByteSink, tag: Tag, tag_entry: usize, tag_size: usize) {
+    write_raw_tag_entry(into, tag.into(), tag_entry, tag_size);
+}
+
+#[inline]
+fn write_raw_tag_entry(
+    into: &mut impl ByteSink,
+    signature: u32,
+    tag_entry: usize,
+    tag_size: usize,
+) {
+    write_u32_be(into, signature);
     write_u32_be(into, tag_entry as u32);
     write_u32_be(into, tag_size as u32);
 }
 
-fn write_trc_entry(into: &mut Vec<u8>, trc: &ToneReprCurve) -> Result<usize, CmsError> {
+/// Resamples a curve given by samples to `table_size` points, `0` keeps the curve as is
+fn resample_trc(trc: &ToneReprCurve, table_size: usize) -> Cow<'_, ToneReprCurve> {
     match trc {
+        ToneReprCurve::Lut(lut) if table_size != 0 && lut.len() > 1 && lut.len() != table_size => {
+            let table_size = table_size.max(2);
+            let Some(evaluator) = trc.linearize_evaluator() else {
+                return Cow::Borrowed(trc);
+            };
+            let scale = 1. / (table_size - 1) as f32;
+            let resampled = (0..table_size)
+                .map(|i| {
+                    let v = evaluator.evaluate(i as f32 * scale);
+                    (v * 65535.).round().clamp(0., 65535.) as u16
+                })
+                .collect();
+            Cow::Owned(ToneReprCurve::Lut(resampled))
+        }
+        _ => Cow::Borrowed(trc),
+    }
+}
+
+fn write_trc_entry(into: &mut impl ByteSink, trc: &ToneReprCurve) -> Result<usize, CmsError> {
+    match trc {
+        ToneReprCurve::Parametric(parametric_curve) if parametric_curve.len() == 1 => {
+            // Pure gamma, written as `curv` with a single u8Fixed8 entry
+            let gamma = parametric_curve[0];
+            if !(0. ..256.).contains(&gamma) {
+                return Err(CmsError::InvalidProfile);
+            }
+            let curv: u32 = TagTypeDefinition::LutToneCurve.into();
+            write_u32_be(into, curv);
+            write_u32_be(into, 0);
+            write_u32_be(into, 1);
+            write_u16_be(into, gamma.to_u8_fixed8());
+            Ok(14)
+        }
         ToneReprCurve::Lut(lut) => {
             let curv: u32 = TagTypeDefinition::LutToneCurve.into();
             write_u32_be(into, curv);
@@ -242,7 +409,7 @@ fn write_trc_entry(into: &mut Vec<u8>, trc: &ToneReprCurve) -> Result<usize, Cms
 }
 
 #[inline]
-fn write_cicp_entry(into: &mut Vec<u8>, cicp: &CicpProfile) {
+fn write_cicp_entry(into: &mut impl ByteSink, cicp: &CicpProfile) {
     let cicp_tag: u32 = TagTypeDefinition::Cicp.into();
     write_u32_be(into, cicp_tag);
     write_u32_be(into, 0);
@@ -252,7 +419,7 @@ fn write_cicp_entry(into: &mut Vec<u8>, cicp: &CicpProfile) {
     into.push(if cicp.full_range { 1 } else { 0 });
 }
 
-fn write_chad(into: &mut Vec<u8>, matrix: Matrix3f) {
+fn write_chad(into: &mut impl ByteSink, matrix: Matrix3f) {
     let arr_type: u32 = TagTypeDefinition::S15Fixed16Array.into();
     write_u32_be(into, arr_type);
     write_u32_be(into, 0);
@@ -260,7 +427,7 @@ fn write_chad(into: &mut Vec<u8>, matrix: Matrix3f) {
 }
 
 #[inline]
-fn write_matrix3f(into: &mut Vec<u8>, v: Matrix3f) {
+fn write_matrix3f(into: &mut impl ByteSink, v: Matrix3f) {
     write_i32_be(into, v.v[0][0].to_s15_fixed16());
     write_i32_be(into, v.v[0][1].to_s15_fixed16());
     write_i32_be(into, v.v[0][2].to_s15_fixed16());
@@ -275,14 +442,14 @@ fn write_matrix3f(into: &mut Vec<u8>, v: Matrix3f) {
 }
 
 #[inline]
-fn write_vector3f(into: &mut Vec<u8>, v: Vector3f) {
+fn write_vector3f(into: &mut impl ByteSink, v: Vector3f) {
     write_i32_be(into, v.v[0].to_s15_fixed16());
     write_i32_be(into, v.v[1].to_s15_fixed16());
     write_i32_be(into, v.v[2].to_s15_fixed16());
 }
 
 #[inline]
-fn write_lut16_entry(into: &mut Vec<u8>, lut: &LutDataType) -> usize {
+fn write_lut16_entry(into: &mut impl ByteSink, lut: &LutDataType) -> usize {
     let start = into.len();
     let lut16_tag: u32 = LutType::Lut16.into();
     write_u32_be(into, lut16_tag);
@@ -307,9 +474,49 @@ fn write_lut16_entry(into: &mut Vec<u8>, lut: &LutDataType) -> usize {
     end - start
 }
 
+fn write_lut8_entry(into: &mut impl ByteSink, lut: &LutDataType) -> usize {
+    let start = into.len();
+    let lut8_tag: u32 = LutType::Lut8.into();
+    write_u32_be(into, lut8_tag);
+    write_u32_be(into, 0);
+    into.push(lut.num_input_channels);
+    into.push(lut.num_output_channels);
+    into.push(lut.num_clut_grid_points);
+    into.push(0);
+    write_matrix3f(into, lut.matrix);
+    // lut8Type has no table sizes, input and output tables are always 256 entries
+    for item in lut
+        .input_table
+        .iter()
+        .chain(lut.clut_table.iter())
+        .chain(lut.output_table.iter())
+    {
+        into.push((item * 255. + 0.5).max(0.).min(255.) as u8);
+    }
+    let end = into.len();
+    end - start
+}
+
+/// Writes curves each padded to 4 bytes, returns written size
+fn write_padded_curves(
+    into: &mut impl ByteSink,
+    curves: &[ToneReprCurve],
+) -> Result<usize, CmsError> {
+    let mut size = 0usize;
+    for trc in curves.iter() {
+        let mut curve_size = write_trc_entry(into, trc)?;
+        while curve_size % 4 != 0 {
+            into.push(0);
+            curve_size += 1;
+        }
+        size += curve_size;
+    }
+    Ok(size)
+}
+
 #[inline]
 fn write_mab_entry(
-    into: &mut Vec<u8>,
+    into: &mut impl ByteSink,
     lut: &LutMCurvesType,
     is_a_to_b: bool,
 ) -> Result<usize, CmsError> {
@@ -324,136 +531,159 @@ fn write_mab_entry(
     into.push(lut.num_input_channels);
     into.push(lut.num_output_channels);
     write_u16_be(into, 0);
+
+    // Offsets precede the data, so sections are sized ahead
+    let b_curves_size = write_padded_curves(&mut ByteCounter::default(), &lut.b_curves)?;
+    let m_curves_size = write_padded_curves(&mut ByteCounter::default(), &lut.m_curves)?;
+    // CLUT is padded to 4 bytes as any other element
+    let clut_size = if !lut.clut.is_empty() {
+        (lut.grid_points.len() + 4 + lut.clut.len() * 2).next_multiple_of(4)
+    } else {
+        0
+    };
+    let offset_if = |present: bool, offset: usize| if present { offset as u32 } else { 0 };
+
     let mut working_offset = 32usize;
     // Offset to "B curves"
-    if !lut.b_curves.is_empty() {
-        write_u32_be(into, working_offset as u32);
-    } else {
-        write_u32_be(into, 0);
-    }
-    let mut data = Vec::new();
-    if !lut.b_curves.is_empty() {
-        for trc in lut.b_curves.iter() {
-            let mut curve_size = write_trc_entry(&mut data, trc)?;
-            while curve_size % 4 != 0 {
-                data.push(0);
-                curve_size += 1;
-            }
-            working_offset += curve_size;
-        }
-    }
+    write_u32_be(into, offset_if(!lut.b_curves.is_empty(), working_offset));
+    working_offset += b_curves_size;
     // Offset to matrix
     write_u32_be(into, working_offset as u32);
-    write_matrix3f(&mut data, lut.matrix);
-    write_vector3f(&mut data, lut.bias);
     working_offset += 9 * 4 + 3 * 4;
     // Offset to "M curves"
-    if !lut.m_curves.is_empty() {
-        write_u32_be(into, working_offset as u32);
-    } else {
-        write_u32_be(into, 0);
-    }
-    if !lut.m_curves.is_empty() {
-        for trc in lut.m_curves.iter() {
-            let mut curve_size = write_trc_entry(&mut data, trc)?;
-            while curve_size % 4 != 0 {
-                data.push(0);
-                curve_size += 1;
-            }
-            working_offset += curve_size;
-        }
-    }
-
+    write_u32_be(into, offset_if(!lut.m_curves.is_empty(), working_offset));
+    working_offset += m_curves_size;
     // Offset to CLUT
-    if !lut.clut.is_empty() {
-        write_u32_be(into, working_offset as u32);
-    } else {
-        write_u32_be(into, 0);
-    }
-    let clut_start = data.len();
+    write_u32_be(into, offset_if(!lut.clut.is_empty(), working_offset));
+    working_offset += clut_size;
+    // Offset to "A curves"
+    write_u32_be(into, offset_if(!lut.a_curves.is_empty(), working_offset));
+
+    write_padded_curves(into, &lut.b_curves)?;
+    write_matrix3f(into, lut.matrix);
+    write_vector3f(into, lut.bias);
+    write_padded_curves(into, &lut.m_curves)?;
     if !lut.clut.is_empty() {
         // Writing CLUT
         for &pt in lut.grid_points.iter() {
-            data.push(pt);
-        }
-        data.push(2);
-        data.push(0);
-        data.push(0);
-        data.push(0);
-        if !lut.clut.is_empty() {
-            for element in lut.clut.iter() {
-                write_u16_be(&mut data, element.to_fixed_u16());
-            }
+            into.push(pt);
         }
-    }
-    let clut_size = data.len() - clut_start;
-    working_offset += clut_size;
-    // Offset to "A curves"
-    if !lut.a_curves.is_empty() {
-        write_u32_be(into, working_offset as u32);
-    } else {
-        write_u32_be(into, 0);
-    }
-    if !lut.a_curves.is_empty() {
-        for trc in lut.a_curves.iter() {
-            let mut curve_size = write_trc_entry(&mut data, trc)?;
-            while curve_size % 4 != 0 {
-                data.push(0);
-                curve_size += 1;
-            }
-            working_offset += curve_size;
+        into.push(2);
+        into.push(0);
+        into.push(0);
+        into.push(0);
+        for element in lut.clut.iter() {
+            write_u16_be(into, element.to_fixed_u16());
+        }
+        if lut.clut.len() % 2 != 0 {
+            write_u16_be(into, 0);
         }
     }
-
-    into.extend(data);
+    write_padded_curves(into, &lut.a_curves)?;
 
     let end = into.len();
     Ok(end - start)
 }
 
-fn write_lut(into: &mut Vec<u8>, lut: &LutWarehouse, is_a_to_b: bool) -> Result<usize, CmsError> {
+fn write_lut(
+    into: &mut impl ByteSink,
+    lut: &LutWarehouse,
+    is_a_to_b: bool,
+) -> Result<usize, CmsError> {
     match lut {
-        LutWarehouse::Lut(lut) => Ok(write_lut16_entry(into, lut)),
+        LutWarehouse::Lut(lut) => {
+            if lut.lut_type == LutType::Lut8
+                && lut.num_input_table_entries == 256
+                && lut.num_output_table_entries == 256
+            {
+                Ok(write_lut8_entry(into, lut))
+            } else {
+                Ok(write_lut16_entry(into, lut))
+            }
+        }
         LutWarehouse::MCurves(mab) => write_mab_entry(into, mab, is_a_to_b),
     }
 }
 
+/// MD5 of the profile with flags, rendering intent and profile ID header fields zeroed,
+/// as required by ICC.1 7.2.18
+///
+/// Fields are zeroed in place and restored after, so profile is left unchanged.
+fn compute_profile_id(profile: &mut [u8]) -> [u8; 16] {
+    let flags: [u8; 4] = profile[44..48].try_into().unwrap();
+    let rendering_intent: [u8; 4] = profile[64..68].try_into().unwrap();
+    let profile_id: [u8; 16] = profile[84..100].try_into().unwrap();
+    profile[44..48].fill(0);
+    profile[64..68].fill(0);
+    profile[84..100].fill(0);
+    let digest = md5(profile);
+    profile[44..48].copy_from_slice(&flags);
+    profile[64..68].copy_from_slice(&rendering_intent);
+    profile[84..100].copy_from_slice(&profile_id);
+    digest
+}
+
 impl ProfileHeader {
-    fn encode(&self) -> Vec<u8> {
-        let mut encoder: Vec<u8> = Vec::with_capacity(size_of::<ProfileHeader>());
-        write_u32_be(&mut encoder, self.size); // Size
-        write_u32_be(&mut encoder, 0); // CMM Type
-        write_u32_be(&mut encoder, self.version.into()); // Version Number Type
-        write_u32_be(&mut encoder, self.profile_class.into()); // Profile class
-        write_u32_be(&mut encoder, self.data_color_space.into()); // Data color space
-        write_u32_be(&mut encoder, self.pcs.into()); // PCS
-        self.creation_date_time.encode(&mut encoder); // Date time
-        write_u32_be(&mut encoder, self.signature.into()); // Profile signature
-        write_u32_be(&mut encoder, self.platform);
-        write_u32_be(&mut encoder, self.flags);
-        write_u32_be(&mut encoder, self.device_manufacturer);
-        write_u32_be(&mut encoder, self.device_model);
+    fn encode(&self, encoder: &mut impl ByteSink) {
+        write_u32_be(encoder, self.size); // Size
+        write_u32_be(encoder, 0); // CMM Type
+        write_u32_be(encoder, self.version.into()); // Version Number Type
+        write_u32_be(encoder, self.profile_class.into()); // Profile class
+        write_u32_be(encoder, self.data_color_space.into()); // Data color space
+        write_u32_be(encoder, self.pcs.into()); // PCS
+        self.creation_date_time.encode(encoder); // Date time
+        write_u32_be(encoder, self.signature.into()); // Profile signature
+        write_u32_be(encoder, self.platform);
+        write_u32_be(encoder, self.flags);
+        write_u32_be(encoder, self.device_manufacturer);
+        write_u32_be(encoder, self.device_model);
         for &i in self.device_attributes.iter() {
             encoder.push(i);
         }
-        write_u32_be(&mut encoder, self.rendering_intent.into());
-        write_i32_be(&mut encoder, self.illuminant.x.to_s15_fixed16());
-        write_i32_be(&mut encoder, self.illuminant.y.to_s15_fixed16());
-        write_i32_be(&mut encoder, self.illuminant.z.to_s15_fixed16());
-        write_u32_be(&mut encoder, self.creator);
+        write_u32_be(encoder, self.rendering_intent.into());
+        write_i32_be(encoder, self.illuminant.x.to_s15_fixed16());
+        write_i32_be(encoder, self.illuminant.y.to_s15_fixed16());
+        write_i32_be(encoder, self.illuminant.z.to_s15_fixed16());
+        write_u32_be(encoder, self.creator);
         for &i in self.profile_id.iter() {
             encoder.push(i);
         }
         for &i in self.reserved.iter() {
             encoder.push(i);
         }
-        write_u32_be(&mut encoder, self.tag_count);
-        encoder
+        write_u32_be(encoder, self.tag_count);
     }
 }
 
 impl ColorProfile {
-    fn writable_tags_count(&self) -> usize {
+    /// Media white written as `wtpt`.
+    ///
+    /// Display profiles are adapted into PCS illuminant and keep their white in `chad`,
+    /// other classes keep measured media white, e.g. paper white of output profiles.
+    fn encoded_media_white(&self) -> Option<Xyz> {
+        if self.profile_class != ProfileClass::DisplayDevice {
+            if let Some(media_white) = self.media_white_point {
+                return Some(media_white);
+            }
+        }
+        if self.white_point != Xyz::default() {
+            Some(self.white_point)
+        } else {
+            None
+        }
+    }
+
+    /// Matrix written as `chad`, derived from media white for display profiles only,
+    /// since media white of other classes is not adapted.
+    fn encoded_chromatic_adaptation(&self) -> Option<Matrix3f> {
+        if self.profile_class == ProfileClass::DisplayDevice {
+            self.chromatic_adaptation_to_d50()
+        } else {
+            self.chromatic_adaptation
+        }
+    }
+
+    fn writable_tags_count(&self, options: &EncodeOptions) -> usize {
         let mut tags_count = 0usize;
         if self.red_colorant != Xyz::default() {
             tags_count += 1;
@@ -479,13 +709,16 @@ impl ColorProfile {
         if self.cicp.is_some() {
             tags_count += 1;
         }
-        if self.media_white_point.is_some() {
+        if self.encoded_media_white().is_some() {
+            tags_count += 1;
+        }
+        if self.black_point.is_some() {
             tags_count += 1;
         }
         if self.gamut.is_some() {
             tags_count += 1;
         }
-        if self.chromatic_adaptation.is_some() {
+        if self.encoded_chromatic_adaptation().is_some() {
             tags_count += 1;
         }
         if self.lut_a_to_b_perceptual.is_some() {
@@ -509,6 +742,9 @@ impl ColorProfile {
         if self.luminance.is_some() {
             tags_count += 1;
         }
+        if self.measurement.is_some() {
+            tags_count += 1;
+        }
         if let Some(description) = &self.description {
             if description.has_values() {
                 tags_count += 1;
@@ -534,237 +770,335 @@ impl ColorProfile {
                 tags_count += 1;
             }
         }
+        if self.vcgt.is_some() {
+            tags_count += 1;
+        }
+        if !self.colorant_table.is_empty() {
+            tags_count += 1;
+        }
+        tags_count += self.writable_unknown_tags(options).count();
         tags_count
     }
 
+    /// Unknown tags written as is, known signatures are always written from their fields
+    fn writable_unknown_tags(
+        &self,
+        options: &EncodeOptions,
+    ) -> impl Iterator<Item = &(u32, Vec<u8>)> {
+        let tags = if options.strip_unknown_tags {
+            &[][..]
+        } else {
+            &self.unknown_tags[..]
+        };
+        tags.iter()
+            .filter(|(signature, _)| Tag::try_from(*signature).is_err())
+    }
+
     /// Encodes profile
+    ///
+    /// Profile ID in the header is filled with MD5 of the encoded profile.
     pub fn encode(&self) -> Result<Vec<u8>, CmsError> {
-        let mut entries = Vec::new();
-        let tags_count = self.writable_tags_count();
-        let mut tags = Vec::with_capacity(TAG_SIZE * tags_count);
+        self.encode_with(&EncodeOptions::default())
+    }
+
+    /// Encodes profile with given options
+    pub fn encode_with(&self, options: &EncodeOptions) -> Result<Vec<u8>, CmsError> {
+        let mut encoded = vec![0u8; self.encoded_size(options)?];
+        self.encode_into(&mut encoded, options)?;
+        Ok(encoded)
+    }
+
+    /// Returns size in bytes [ColorProfile::encode_into] writes, without encoding the profile.
+    ///
+    pub fn encoded_size(&self, options: &EncodeOptions) -> Result<usize, CmsError> {
+        let mut tags = ByteCounter::default();
+        let mut entries = ByteCounter::default();
+        self.encode_tags(&mut tags, &mut entries, options)?;
+        Ok(size_of::<ProfileHeader>() + tags.len() + entries.len())
+    }
+
+    /// Encodes profile into caller storage, returns count of bytes written.
+    ///
+    /// Fails with [CmsError::BufferTooSmall] if `buf` is shorter than
+    /// [ColorProfile::encoded_size], nothing is written in that case.
+    pub fn encode_into(&self, buf: &mut [u8], options: &EncodeOptions) -> Result<usize, CmsError> {
+        let needed = self.encoded_size(options)?;
+        if buf.len() < needed {
+            return Err(CmsError::BufferTooSmall { needed });
+        }
+        let profile = &mut buf[..needed];
+        let tags_count = self.writable_tags_count(options);
+        let (header, rest) = profile.split_at_mut(size_of::<ProfileHeader>());
+        let (tags, entries) = rest.split_at_mut(TAG_SIZE * tags_count);
+        let mut tags = SliceSink::new(tags);
+        let mut entries = SliceSink::new(entries);
+        self.encode_tags(&mut tags, &mut entries, options)?;
+        tags.finish()?;
+        entries.finish()?;
+
+        let profile_header = ProfileHeader {
+            size: needed as u32,
+            pcs: self.pcs,
+            profile_class: self.profile_class,
+            rendering_intent: self.rendering_intent,
+            cmm_type: 0,
+            version: if self.cicp.is_some() {
+                ProfileVersion::V4_3
+            } else {
+                ProfileVersion::V4_0
+            },
+            data_color_space: self.color_space,
+            creation_date_time: options
+                .creation_date_time
+                .unwrap_or_else(ColorDateTime::now),
+            signature: ProfileSignature::Acsp,
+            platform: 0u32,
+            flags: 0u32,
+            device_manufacturer: 0u32,
+            device_model: 0u32,
+            device_attributes: [0u8; 8],
+            illuminant: self.white_point,
+            creator: 0u32,
+            profile_id: [0u8; 16],
+            reserved: [0u8; 28],
+            tag_count: tags_count as u32,
+        };
+        let mut header = SliceSink::new(header);
+        profile_header.encode(&mut header);
+        header.finish()?;
+
+        // Every tag is written from its own field, so a duplicate means a bug here,
+        // and readers would disagree on which entry to use.
+        let tag_table = &profile[size_of::<ProfileHeader>()..][..TAG_SIZE * tags_count];
+        let signatures = tag_table
+            .chunks_exact(TAG_SIZE)
+            .map(|x| u32::from_be_bytes([x[0], x[1], x[2], x[3]]));
+        if signatures
+            .clone()
+            .enumerate()
+            .any(|(i, signature)| signatures.clone().take(i).any(|x| x == signature))
+        {
+            return Err(CmsError::InvalidProfile);
+        }
+
+        let profile_id = compute_profile_id(profile);
+        profile[84..100].copy_from_slice(&profile_id);
+        Ok(needed)
+    }
+
+    /// Returns MD5 of this profile encoded with zeroed creation date, usable as a cache key.
+    ///
+    /// Same profile content always gives the same hash. This is not profile ID of any
+    /// encoded profile: ICC computes the ID with creation date included, and [ColorProfile::encode]
+    /// stamps current date. See [ColorProfile::header_profile_id] for ID of parsed profiles.
+    pub fn content_hash(&self) -> Result<[u8; 16], CmsError> {
+        let encoded = self.encode_with(&EncodeOptions {
+            creation_date_time: Some(ColorDateTime::default()),
+            ..Default::default()
+        })?;
+        Ok(encoded[84..100].try_into().unwrap())
+    }
+
+    /// Writes tag table and tag data, offsets are counted from the profile start
+    fn encode_tags(
+        &self,
+        tags: &mut impl ByteSink,
+        entries: &mut impl ByteSink,
+        options: &EncodeOptions,
+    ) -> Result<(), CmsError> {
+        let tags_count = self.writable_tags_count(options);
         let mut base_offset = size_of::<ProfileHeader>() + TAG_SIZE * tags_count;
         if self.red_colorant != Xyz::default() {
-            write_tag_entry(&mut tags, Tag::RedXyz, base_offset, 20);
-            write_xyz_tag_value(&mut entries, self.red_colorant);
+            write_tag_entry(tags, Tag::RedXyz, base_offset, 20);
+            write_xyz_tag_value(entries, self.red_colorant);
             base_offset += 20;
         }
         if self.green_colorant != Xyz::default() {
-            write_tag_entry(&mut tags, Tag::GreenXyz, base_offset, 20);
-            write_xyz_tag_value(&mut entries, self.green_colorant);
+            write_tag_entry(tags, Tag::GreenXyz, base_offset, 20);
+            write_xyz_tag_value(entries, self.green_colorant);
             base_offset += 20;
         }
         if self.blue_colorant != Xyz::default() {
-            write_tag_entry(&mut tags, Tag::BlueXyz, base_offset, 20);
-            write_xyz_tag_value(&mut entries, self.blue_colorant);
+            write_tag_entry(tags, Tag::BlueXyz, base_offset, 20);
+            write_xyz_tag_value(entries, self.blue_colorant);
             base_offset += 20;
         }
-        if let Some(chad) = self.chromatic_adaptation {
-            write_tag_entry(&mut tags, Tag::ChromaticAdaptation, base_offset, 8 + 9 * 4);
-            write_chad(&mut entries, chad);
+        if let Some(chad) = self.encoded_chromatic_adaptation() {
+            write_tag_entry(tags, Tag::ChromaticAdaptation, base_offset, 8 + 9 * 4);
+            write_chad(entries, chad);
             base_offset += 8 + 9 * 4;
         }
         if let Some(trc) = &self.red_trc {
-            let entry_size = write_trc_entry(&mut entries, trc)?;
-            write_tag_entry(&mut tags, Tag::RedToneReproduction, base_offset, entry_size);
-            base_offset += entry_size;
+            let trc = resample_trc(trc, options.trc_table_size);
+            let entry_size = write_trc_entry(entries, &trc)?;
+            write_tag_entry(tags, Tag::RedToneReproduction, base_offset, entry_size);
+            base_offset += pad_tag_data(entries, entry_size);
         }
         if let Some(trc) = &self.green_trc {
-            let entry_size = write_trc_entry(&mut entries, trc)?;
-            write_tag_entry(
-                &mut tags,
-                Tag::GreenToneReproduction,
-                base_offset,
-                entry_size,
-            );
-            base_offset += entry_size;
+            let trc = resample_trc(trc, options.trc_table_size);
+            let entry_size = write_trc_entry(entries, &trc)?;
+            write_tag_entry(tags, Tag::GreenToneReproduction, base_offset, entry_size);
+            base_offset += pad_tag_data(entries, entry_size);
         }
         if let Some(trc) = &self.blue_trc {
-            let entry_size = write_trc_entry(&mut entries, trc)?;
-            write_tag_entry(
-                &mut tags,
-                Tag::BlueToneReproduction,
-                base_offset,
-                entry_size,
-            );
-            base_offset += entry_size;
+            let trc = resample_trc(trc, options.trc_table_size);
+            let entry_size = write_trc_entry(entries, &trc)?;
+            write_tag_entry(tags, Tag::BlueToneReproduction, base_offset, entry_size);
+            base_offset += pad_tag_data(entries, entry_size);
         }
         if let Some(trc) = &self.gray_trc {
-            let entry_size = write_trc_entry(&mut entries, trc)?;
-            write_tag_entry(
-                &mut tags,
-                Tag::GreyToneReproduction,
-                base_offset,
-                entry_size,
-            );
-            base_offset += entry_size;
+            let trc = resample_trc(trc, options.trc_table_size);
+            let entry_size = write_trc_entry(entries, &trc)?;
+            write_tag_entry(tags, Tag::GreyToneReproduction, base_offset, entry_size);
+            base_offset += pad_tag_data(entries, entry_size);
         }
-        if self.white_point != Xyz::default() {
-            write_tag_entry(&mut tags, Tag::MediaWhitePoint, base_offset, 20);
-            write_xyz_tag_value(&mut entries, self.white_point);
+        if let Some(media_white) = self.encoded_media_white() {
+            write_tag_entry(tags, Tag::MediaWhitePoint, base_offset, 20);
+            write_xyz_tag_value(entries, media_white);
+            base_offset += 20;
+        }
+        if let Some(black_point) = self.black_point {
+            write_tag_entry(tags, Tag::BlackPoint, base_offset, 20);
+            write_xyz_tag_value(entries, black_point);
             base_offset += 20;
         }
-
-        let has_cicp = self.cicp.is_some();
 
         if let Some(cicp) = &self.cicp {
-            write_tag_entry(&mut tags, Tag::CodeIndependentPoints, base_offset, 12);
-            write_cicp_entry(&mut entries, cicp);
+            write_tag_entry(tags, Tag::CodeIndependentPoints, base_offset, 12);
+            write_cicp_entry(entries, cicp);
             base_offset += 12;
         }
 
         if let Some(lut) = &self.lut_a_to_b_perceptual {
-            let entry_size = write_lut(&mut entries, lut, true)?;
-            write_tag_entry(
-                &mut tags,
-                Tag::DeviceToPcsLutPerceptual,
-                base_offset,
-                entry_size,
-            );
-            base_offset += entry_size;
+            let entry_size = write_lut(entries, lut, true)?;
+            write_tag_entry(tags, Tag::DeviceToPcsLutPerceptual, base_offset, entry_size);
+            base_offset += pad_tag_data(entries, entry_size);
         }
 
         if let Some(lut) = &self.lut_a_to_b_colorimetric {
-            let entry_size = write_lut(&mut entries, lut, true)?;
+            let entry_size = write_lut(entries, lut, true)?;
             write_tag_entry(
-                &mut tags,
+                tags,
                 Tag::DeviceToPcsLutColorimetric,
                 base_offset,
                 entry_size,
             );
-            base_offset += entry_size;
+            base_offset += pad_tag_data(entries, entry_size);
         }
 
         if let Some(lut) = &self.lut_a_to_b_saturation {
-            let entry_size = write_lut(&mut entries, lut, true)?;
-            write_tag_entry(
-                &mut tags,
-                Tag::DeviceToPcsLutSaturation,
-                base_offset,
-                entry_size,
-            );
-            base_offset += entry_size;
+            let entry_size = write_lut(entries, lut, true)?;
+            write_tag_entry(tags, Tag::DeviceToPcsLutSaturation, base_offset, entry_size);
+            base_offset += pad_tag_data(entries, entry_size);
         }
 
         if let Some(lut) = &self.lut_b_to_a_perceptual {
-            let entry_size = write_lut(&mut entries, lut, false)?;
-            write_tag_entry(
-                &mut tags,
-                Tag::PcsToDeviceLutPerceptual,
-                base_offset,
-                entry_size,
-            );
-            base_offset += entry_size;
+            let entry_size = write_lut(entries, lut, false)?;
+            write_tag_entry(tags, Tag::PcsToDeviceLutPerceptual, base_offset, entry_size);
+            base_offset += pad_tag_data(entries, entry_size);
         }
 
         if let Some(lut) = &self.lut_b_to_a_colorimetric {
-            let entry_size = write_lut(&mut entries, lut, false)?;
+            let entry_size = write_lut(entries, lut, false)?;
             write_tag_entry(
-                &mut tags,
+                tags,
                 Tag::PcsToDeviceLutColorimetric,
                 base_offset,
                 entry_size,
             );
-            base_offset += entry_size;
+            base_offset += pad_tag_data(entries, entry_size);
         }
 
         if let Some(lut) = &self.lut_b_to_a_saturation {
-            let entry_size = write_lut(&mut entries, lut, false)?;
-            write_tag_entry(
-                &mut tags,
-                Tag::PcsToDeviceLutSaturation,
-                base_offset,
-                entry_size,
-            );
-            base_offset += entry_size;
+            let entry_size = write_lut(entries, lut, false)?;
+            write_tag_entry(tags, Tag::PcsToDeviceLutSaturation, base_offset, entry_size);
+            base_offset += pad_tag_data(entries, entry_size);
         }
 
         if let Some(lut) = &self.gamut {
-            let entry_size = write_lut(&mut entries, lut, false)?;
-            write_tag_entry(&mut tags, Tag::Gamut, base_offset, entry_size);
-            base_offset += entry_size;
+            let entry_size = write_lut(entries, lut, false)?;
+            write_tag_entry(tags, Tag::Gamut, base_offset, entry_size);
+            base_offset += pad_tag_data(entries, entry_size);
         }
 
         if let Some(luminance) = self.luminance {
-            write_tag_entry(&mut tags, Tag::Luminance, base_offset, 20);
-            write_xyz_tag_value(&mut entries, luminance);
+            write_tag_entry(tags, Tag::Luminance, base_offset, 20);
+            write_xyz_tag_value(entries, luminance);
             base_offset += 20;
         }
 
+        if let Some(measurement) = &self.measurement {
+            write_tag_entry(tags, Tag::Measurement, base_offset, 36);
+            write_meas_tag_value(entries, measurement);
+            base_offset += 36;
+        }
+
         if let Some(description) = &self.description {
             if description.has_values() {
-                let entry_size = write_string_value(&mut entries, description);
-                write_tag_entry(&mut tags, Tag::ProfileDescription, base_offset, entry_size);
-                base_offset += entry_size;
+                let entry_size = write_string_value(entries, description);
+                write_tag_entry(tags, Tag::ProfileDescription, base_offset, entry_size);
+                base_offset += pad_tag_data(entries, entry_size);
             }
         }
 
         if let Some(copyright) = &self.copyright {
             if copyright.has_values() {
-                let entry_size = write_string_value(&mut entries, copyright);
-                write_tag_entry(&mut tags, Tag::Copyright, base_offset, entry_size);
-                base_offset += entry_size;
+                let entry_size = write_string_value(entries, copyright);
+                write_tag_entry(tags, Tag::Copyright, base_offset, entry_size);
+                base_offset += pad_tag_data(entries, entry_size);
             }
         }
 
         if let Some(vd) = &self.viewing_conditions_description {
             if vd.has_values() {
-                let entry_size = write_string_value(&mut entries, vd);
+                let entry_size = write_string_value(entries, vd);
                 write_tag_entry(
-                    &mut tags,
+                    tags,
                     Tag::ViewingConditionsDescription,
                     base_offset,
                     entry_size,
                 );
-                base_offset += entry_size;
+                base_offset += pad_tag_data(entries, entry_size);
             }
         }
 
         if let Some(vd) = &self.device_model {
             if vd.has_values() {
-                let entry_size = write_string_value(&mut entries, vd);
-                write_tag_entry(&mut tags, Tag::DeviceModel, base_offset, entry_size);
-                base_offset += entry_size;
+                let entry_size = write_string_value(entries, vd);
+                write_tag_entry(tags, Tag::DeviceModel, base_offset, entry_size);
+                base_offset += pad_tag_data(entries, entry_size);
             }
         }
 
         if let Some(vd) = &self.device_manufacturer {
             if vd.has_values() {
-                let entry_size = write_string_value(&mut entries, vd);
-                write_tag_entry(&mut tags, Tag::DeviceManufacturer, base_offset, entry_size);
-                // base_offset += entry_size;
+                let entry_size = write_string_value(entries, vd);
+                write_tag_entry(tags, Tag::DeviceManufacturer, base_offset, entry_size);
+                base_offset += pad_tag_data(entries, entry_size);
             }
         }
 
-        tags.extend(entries);
+        if let Some(vcgt) = &self.vcgt {
+            let entry_size = vcgt.encode(entries)?;
+            write_tag_entry(tags, Tag::VideoCardGamma, base_offset, entry_size);
+            base_offset += pad_tag_data(entries, entry_size);
+        }
 
-        let profile_header = ProfileHeader {
-            size: size_of::<ProfileHeader>() as u32 + tags.len() as u32,
-            pcs: self.pcs,
-            profile_class: self.profile_class,
-            rendering_intent: self.rendering_intent,
-            cmm_type: 0,
-            version: if has_cicp {
-                ProfileVersion::V4_3
-            } else {
-                ProfileVersion::V4_0
-            },
-            data_color_space: self.color_space,
-            creation_date_time: ColorDateTime::now(),
-            signature: ProfileSignature::Acsp,
-            platform: 0u32,
-            flags: 0u32,
-            device_manufacturer: 0u32,
-            device_model: 0u32,
-            device_attributes: [0u8; 8],
-            illuminant: self.white_point,
-            creator: 0u32,
-            profile_id: [0u8; 16],
-            reserved: [0u8; 28],
-            tag_count: tags_count as u32,
-        };
-        let mut header = profile_header.encode();
-        header.extend(tags);
-        Ok(header)
+        if !self.colorant_table.is_empty() {
+            let entry_size = encode_colorant_table(&self.colorant_table, self.pcs, entries)?;
+            write_tag_entry(tags, Tag::ColorantTable, base_offset, entry_size);
+            base_offset += pad_tag_data(entries, entry_size);
+        }
+
+        for (signature, data) in self.writable_unknown_tags(options) {
+            write_raw_tag_entry(tags, *signature, base_offset, data.len());
+            for &byte in data.iter() {
+                entries.push(byte);
+            }
+            base_offset += pad_tag_data(entries, data.len());
+        }
+
+        Ok(())
     }
 }
 
@@ -802,4 +1136,455 @@ mod tests {
             (32767f32 + (65535f32 / 65536f32)).to_s15_fixed16()
         );
     }
+
+    #[test]
+    fn encode_profile_id() {
+        use crate::{ColorProfile, RenderingIntent};
+        let srgb = ColorProfile::new_srgb();
+        let mut encoded = srgb.encode().unwrap();
+        let id: [u8; 16] = encoded[84..100].try_into().unwrap();
+        assert_ne!(id, [0u8; 16]);
+        assert_eq!(id, compute_profile_id(&mut encoded));
+        assert_eq!(&encoded[84..100], &id);
+
+        // Flags and rendering intent are excluded from the digest
+        let mut patched = encoded.clone();
+        patched[44..48].copy_from_slice(&1u32.to_be_bytes());
+        patched[64..68].copy_from_slice(&3u32.to_be_bytes());
+        assert_eq!(compute_profile_id(&mut patched), id);
+
+        let mut intent = srgb.clone();
+        intent.rendering_intent = RenderingIntent::AbsoluteColorimetric;
+        assert_eq!(intent.content_hash().unwrap(), srgb.content_hash().unwrap());
+        assert_ne!(
+            ColorProfile::new_display_p3().content_hash().unwrap(),
+            srgb.content_hash().unwrap()
+        );
+
+        let parsed = ColorProfile::new_from_slice(&encoded).unwrap();
+        assert_eq!(parsed.header_profile_id(), Some(id));
+        assert_eq!(srgb.header_profile_id(), None);
+        let mut unset = encoded.clone();
+        unset[84..100].fill(0);
+        let parsed = ColorProfile::new_from_slice(&unset).unwrap();
+        assert_eq!(parsed.header_profile_id(), None);
+    }
+
+    #[test]
+    fn encoded_size_matches_written() {
+        use crate::{ColorProfile, DataColorSpace, EncodeOptions};
+
+        // CLUT-heavy mAB with curves that need padding
+        let grid_size = 17usize;
+        let mut grid_points = [0u8; 16];
+        grid_points[..3].fill(grid_size as u8);
+        let clut = (0..grid_size * grid_size * grid_size * 3)
+            .map(|x| (x % 255) as f32 / 255.)
+            .collect::<Vec<f32>>();
+        let curves = vec![
+            ToneReprCurve::Lut(vec![0, 32768, 65535]),
+            ToneReprCurve::Parametric(vec![2.2]),
+            ToneReprCurve::Parametric(vec![2.4, 1. / 1.055, 0.055 / 1.055, 1. / 12.92, 0.04045]),
+        ];
+        let mab = ColorProfile {
+            color_space: DataColorSpace::Rgb,
+            pcs: DataColorSpace::Lab,
+            lut_a_to_b_perceptual: Some(LutWarehouse::MCurves(LutMCurvesType {
+                num_input_channels: 3,
+                num_output_channels: 3,
+                grid_points,
+                clut,
+                a_curves: curves.clone(),
+                b_curves: curves.clone(),
+                m_curves: curves,
+                matrix: Matrix3f::IDENTITY,
+                bias: Vector3f::default(),
+            })),
+            ..ColorProfile::new_srgb()
+        };
+
+        let options = EncodeOptions {
+            creation_date_time: Some(ColorDateTime::default()),
+            ..Default::default()
+        };
+        for profile in [
+            ColorProfile::new_srgb(),
+            ColorProfile::new_gray_with_gamma(2.2),
+            ColorProfile::new_from_slice(include_bytes!("../assets/bt_2020.icc")).unwrap(),
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap(),
+            mab,
+        ] {
+            let size = profile.encoded_size(&options).unwrap();
+            let mut buf = vec![0u8; size + 7];
+            assert_eq!(profile.encode_into(&mut buf, &options).unwrap(), size);
+            assert!(buf[size..].iter().all(|&x| x == 0));
+            assert_eq!(
+                u32::from_be_bytes(buf[..4].try_into().unwrap()) as usize,
+                size
+            );
+            assert_eq!(profile.encode_with(&options).unwrap(), &buf[..size]);
+            assert_eq!(profile.encode().unwrap().len(), size);
+            assert!(ColorProfile::new_from_slice(&buf[..size]).is_ok());
+            assert_eq!(
+                profile.encode_into(&mut buf[..size - 1], &options),
+                Err(CmsError::BufferTooSmall { needed: size })
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "transforms")]
+    fn encode_gray_profile() {
+        use crate::{ColorProfile, DataColorSpace, Layout, TransformOptions};
+
+        let gray = ColorProfile::new_gray_with_gamma(2.2);
+        let encoded = gray.encode().unwrap();
+        assert_eq!(&encoded[16..20], b"GRAY");
+        let tag_count = u32::from_be_bytes(encoded[128..132].try_into().unwrap()) as usize;
+        let tag_types = encoded[132..132 + tag_count * TAG_SIZE]
+            .chunks_exact(TAG_SIZE)
+            .map(|x| {
+                let offset = u32::from_be_bytes(x[4..8].try_into().unwrap()) as usize;
+                let signature = u32::from_be_bytes(x[0..4].try_into().unwrap());
+                (signature, &encoded[offset..offset + 4])
+            })
+            .collect::<Vec<_>>();
+        let tag_type = |tag: Tag| {
+            let signature: u32 = tag.into();
+            tag_types
+                .iter()
+                .find(|x| x.0 == signature)
+                .map(|x| x.1.to_vec())
+        };
+        assert_eq!(tag_type(Tag::GreyToneReproduction).unwrap(), b"curv");
+        assert_eq!(tag_type(Tag::MediaWhitePoint).unwrap(), b"XYZ ");
+        assert_eq!(tag_type(Tag::ProfileDescription).unwrap(), b"mluc");
+        assert!(tag_type(Tag::RedToneReproduction).is_none());
+        assert!(tag_type(Tag::RedXyz).is_none());
+
+        let decoded = ColorProfile::new_from_slice(&encoded).unwrap();
+        assert_eq!(decoded.color_space, DataColorSpace::Gray);
+        assert_eq!(decoded.description(None).as_deref(), Some("Gray Gamma 2.2"));
+
+        let srgb = ColorProfile::new_srgb();
+        let src = (0..=255u8).collect::<Vec<u8>>();
+        let src_f32 = src.iter().map(|&x| x as f32 / 255.).collect::<Vec<f32>>();
+        let mut outputs = Vec::new();
+        for profile in [&gray, &decoded] {
+            let transform = profile
+                .create_transform_8bit(
+                    Layout::Gray,
+                    &srgb,
+                    Layout::Rgb,
+                    TransformOptions::default(),
+                )
+                .unwrap();
+            let mut dst = vec![0u8; src.len() * 3];
+            transform.transform(&src, &mut dst).unwrap();
+            let transform = profile
+                .create_transform_f32(
+                    Layout::Gray,
+                    &srgb,
+                    Layout::Rgb,
+                    TransformOptions::default(),
+                )
+                .unwrap();
+            let mut dst_f32 = vec![0f32; src.len() * 3];
+            transform.transform(&src_f32, &mut dst_f32).unwrap();
+            outputs.push((dst, dst_f32));
+        }
+        // Gamma is written as u8Fixed8, 2.2 comes back as 563 / 256
+        for (&a, &b) in outputs[0].0.iter().zip(outputs[1].0.iter()) {
+            assert!(a.abs_diff(b) <= 1, "{a} vs {b}");
+        }
+        for (&a, &b) in outputs[0].1.iter().zip(outputs[1].1.iter()) {
+            assert!((a - b).abs() < 1e-3, "{a} vs {b}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "transforms")]
+    fn encode_cmyk_luts() {
+        use crate::{
+            ColorProfile, DataColorSpace, Layout, ProfileClass, RenderingIntent, TransformOptions,
+        };
+
+        let within_code = |a: &[f32], b: &[f32]| {
+            assert_eq!(a.len(), b.len());
+            for (&a, &b) in a.iter().zip(b.iter()) {
+                assert!((a - b).abs() <= 1. / 65535., "Expected {a}, got {b}");
+            }
+        };
+        let compare_luts = |a: &Option<LutWarehouse>, b: &Option<LutWarehouse>| match (a, b) {
+            (Some(LutWarehouse::Lut(a)), Some(LutWarehouse::Lut(b))) => {
+                assert_eq!(a.lut_type, b.lut_type);
+                assert_eq!(a.num_clut_grid_points, b.num_clut_grid_points);
+                assert_eq!(a.num_input_table_entries, b.num_input_table_entries);
+                within_code(&a.input_table, &b.input_table);
+                within_code(&a.clut_table, &b.clut_table);
+                within_code(&a.output_table, &b.output_table);
+            }
+            (Some(LutWarehouse::MCurves(a)), Some(LutWarehouse::MCurves(b))) => {
+                assert_eq!(a.grid_points, b.grid_points);
+                assert_eq!(a.a_curves.len(), b.a_curves.len());
+                assert_eq!(a.m_curves.len(), b.m_curves.len());
+                assert_eq!(a.b_curves.len(), b.b_curves.len());
+                within_code(&a.clut, &b.clut);
+            }
+            (None, None) => {}
+            _ => panic!("LUT type has to be preserved"),
+        };
+        let compare_profiles = |a: &ColorProfile, b: &ColorProfile| {
+            assert_eq!(a.color_space, b.color_space);
+            assert_eq!(a.pcs, b.pcs);
+            compare_luts(&a.lut_a_to_b_perceptual, &b.lut_a_to_b_perceptual);
+            compare_luts(&a.lut_a_to_b_colorimetric, &b.lut_a_to_b_colorimetric);
+            compare_luts(&a.lut_a_to_b_saturation, &b.lut_a_to_b_saturation);
+            compare_luts(&a.lut_b_to_a_perceptual, &b.lut_b_to_a_perceptual);
+            compare_luts(&a.lut_b_to_a_colorimetric, &b.lut_b_to_a_colorimetric);
+            compare_luts(&a.lut_b_to_a_saturation, &b.lut_b_to_a_saturation);
+            compare_luts(&a.gamut, &b.gamut);
+        };
+
+        // lut16 and lut8 tags, with tables moved off the 16-bit grid as after resampling
+        let mut swop =
+            ColorProfile::new_from_slice(include_bytes!("../assets/us_swop_coated.icc")).unwrap();
+        if let Some(LutWarehouse::Lut(lut)) = &mut swop.lut_a_to_b_colorimetric {
+            for v in lut.clut_table.iter_mut() {
+                *v = *v * 0.993 + 0.003;
+            }
+        }
+        let decoded = ColorProfile::new_from_slice(&swop.encode().unwrap()).unwrap();
+        compare_profiles(&swop, &decoded);
+        // Paper white and black survive, absolute intent relies on them
+        let paper = swop.media_white_point.unwrap();
+        let decoded_paper = decoded.media_white_point.unwrap();
+        assert!((paper.x - decoded_paper.x).abs() < 1e-4);
+        assert!((paper.z - decoded_paper.z).abs() < 1e-4);
+        assert!(decoded.black_point.is_some());
+
+        let srgb = ColorProfile::new_srgb();
+        let options = TransformOptions {
+            rendering_intent: RenderingIntent::AbsoluteColorimetric,
+            ..Default::default()
+        };
+        let src = [0u8, 0, 0, 0, 40, 120, 200, 30];
+        let mut results = Vec::new();
+        for profile in [&swop, &decoded] {
+            let transform = profile
+                .create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgb, options)
+                .unwrap();
+            let mut dst = [0u8; 6];
+            transform.transform(&src, &mut dst).unwrap();
+            results.push(dst);
+        }
+        assert_eq!(results[0], results[1]);
+
+        // mAB and mBA tags
+        let curves = |count: usize| {
+            (0..count)
+                .map(|i| ToneReprCurve::Parametric(vec![1. + i as f32 * 0.1]))
+                .collect::<Vec<_>>()
+        };
+        let grid = |inputs: usize, outputs: usize, size: usize| {
+            let mut grid_points = [0u8; 16];
+            grid_points[..inputs].fill(size as u8);
+            let clut = (0..size.pow(inputs as u32) * outputs)
+                .map(|i| ((i * 7919) % 10007) as f32 / 10007.)
+                .collect::<Vec<f32>>();
+            (grid_points, clut)
+        };
+        let (a2b_grid, a2b_clut) = grid(4, 3, 5);
+        let (b2a_grid, b2a_clut) = grid(3, 4, 9);
+        let mab = ColorProfile {
+            profile_class: ProfileClass::OutputDevice,
+            color_space: DataColorSpace::Cmyk,
+            pcs: DataColorSpace::Lab,
+            lut_a_to_b_perceptual: Some(LutWarehouse::MCurves(LutMCurvesType {
+                num_input_channels: 4,
+                num_output_channels: 3,
+                grid_points: a2b_grid,
+                clut: a2b_clut,
+                a_curves: curves(4),
+                b_curves: curves(3),
+                m_curves: vec![],
+                matrix: Matrix3f::IDENTITY,
+                bias: Vector3f::default(),
+            })),
+            lut_b_to_a_perceptual: Some(LutWarehouse::MCurves(LutMCurvesType {
+                num_input_channels: 3,
+                num_output_channels: 4,
+                grid_points: b2a_grid,
+                clut: b2a_clut,
+                a_curves: curves(4),
+                b_curves: curves(3),
+                m_curves: vec![],
+                matrix: Matrix3f::IDENTITY,
+                bias: Vector3f::default(),
+            })),
+            ..Default::default()
+        };
+        let decoded = ColorProfile::new_from_slice(&mab.encode().unwrap()).unwrap();
+        compare_profiles(&mab, &decoded);
+    }
+
+    #[test]
+    #[cfg(feature = "transforms")]
+    fn encode_chad_for_adapted_white() {
+        use crate::{Chromaticity, ColorProfile, Layout, RenderingIntent, TransformOptions};
+
+        let bt2020 = ColorProfile::new_bt2020();
+        let decoded = ColorProfile::new_from_slice(&bt2020.encode().unwrap()).unwrap();
+        let d50 = Chromaticity::D50.to_xyz();
+        let wtpt = decoded.media_white_point.unwrap();
+        assert!((wtpt.x - d50.x).abs() < 1e-3 && (wtpt.z - d50.z).abs() < 1e-3);
+        let chad = decoded.chromatic_adaptation.unwrap();
+        let bradford = bt2020.chromatic_adaptation_matrix();
+        for (row, expected) in chad.v.iter().zip(bradford.v.iter()) {
+            for (&v, &e) in row.iter().zip(expected.iter()) {
+                assert!((v - e).abs() <= 0.5 / 65536.);
+            }
+        }
+
+        let srgb = ColorProfile::new_srgb();
+        let src = (0..=255u8)
+            .step_by(5)
+            .flat_map(|x| [x, 255 - x, x / 2])
+            .collect::<Vec<_>>();
+        for rendering_intent in [
+            RenderingIntent::RelativeColorimetric,
+            RenderingIntent::AbsoluteColorimetric,
+        ] {
+            let options = TransformOptions {
+                rendering_intent,
+                ..Default::default()
+            };
+            let run = |profile: &ColorProfile| {
+                let transform = profile
+                    .create_transform_8bit(Layout::Rgb, &srgb, Layout::Rgb, options)
+                    .unwrap();
+                let mut dst = vec![0u8; src.len()];
+                transform.transform(&src, &mut dst).unwrap();
+                dst
+            };
+            // Colorants are quantized to s15Fixed16, thus rare off by one is allowed
+            let max_diff = run(&bt2020)
+                .iter()
+                .zip(run(&decoded).iter())
+                .map(|(&a, &b)| a.abs_diff(b))
+                .max()
+                .unwrap();
+            assert!(max_diff <= 1, "{rendering_intent:?}: {max_diff}");
+        }
+
+        // D50 white needs no adaptation
+        let pro_photo = ColorProfile::new_pro_photo_rgb();
+        let decoded = ColorProfile::new_from_slice(&pro_photo.encode().unwrap()).unwrap();
+        assert!(decoded.chromatic_adaptation.is_none());
+    }
+
+    #[test]
+    fn encode_parametric_trc() {
+        use crate::{ColorProfile, TransferCharacteristics};
+
+        let srgb = ColorProfile::new_srgb();
+        let encoded = srgb.encode().unwrap();
+        let decoded = ColorProfile::new_from_slice(&encoded).unwrap();
+        let Some(ToneReprCurve::Parametric(source)) = &srgb.red_trc else {
+            unreachable!()
+        };
+        for trc in [&decoded.red_trc, &decoded.green_trc, &decoded.blue_trc] {
+            let Some(ToneReprCurve::Parametric(params)) = trc else {
+                panic!("para is expected, got {trc:?}");
+            };
+            assert_eq!(params.len(), source.len());
+            for (&param, &source) in params.iter().zip(source.iter()) {
+                assert!((param - source).abs() <= 0.5 / 65536.);
+                assert_eq!(param.to_s15_fixed16(), source.to_s15_fixed16());
+            }
+        }
+        // Parameters survive next round trip as is
+        let reencoded = decoded.encode().unwrap();
+        assert_eq!(reencoded, encoded);
+
+        let mut sampled = srgb.clone();
+        sampled.red_trc = Some(ToneReprCurve::Lut(vec![0; 256]));
+        sampled.green_trc = sampled.red_trc.clone();
+        sampled.blue_trc = sampled.red_trc.clone();
+        assert!(sampled.encode().unwrap().len() > encoded.len() + 3 * 256);
+
+        for tc in [
+            TransferCharacteristics::Srgb,
+            TransferCharacteristics::Smpte240,
+        ] {
+            let curve = ToneReprCurve::try_from(tc).unwrap();
+            assert!(matches!(curve, ToneReprCurve::Parametric(_)), "{tc:?}");
+        }
+        let smpte240 = ToneReprCurve::try_from(TransferCharacteristics::Smpte240)
+            .unwrap()
+            .linearize_evaluator()
+            .unwrap();
+        for i in 0..=100 {
+            let x = i as f64 / 100.;
+            let reference = crate::gamma::smpte240_to_linear(x) as f32;
+            assert!((smpte240.evaluate(x as f32) - reference).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn encode_trc_table_size() {
+        use crate::{ColorProfile, EncodeOptions};
+
+        let srgb =
+            ToneReprCurve::Parametric(vec![2.4, 1. / 1.055, 0.055 / 1.055, 1. / 12.92, 0.04045])
+                .linearize_evaluator()
+                .unwrap();
+        let source = ToneReprCurve::Lut(
+            (0..1024)
+                .map(|i| (srgb.evaluate(i as f32 / 1023.) * 65535.).round() as u16)
+                .collect(),
+        );
+        let mut profile = ColorProfile::new_srgb();
+        profile.red_trc = Some(source.clone());
+        profile.green_trc = Some(ToneReprCurve::Parametric(vec![2.2]));
+        profile.blue_trc = None;
+
+        let reference = source.linearize_evaluator().unwrap();
+        for (table_size, expected_len, tolerance) in
+            [(0, 1024, 1e-6), (256, 256, 1e-3), (4096, 4096, 1e-4)]
+        {
+            let options = EncodeOptions {
+                trc_table_size: table_size,
+                ..Default::default()
+            };
+            let encoded = profile.encode_with(&options).unwrap();
+            assert_eq!(encoded.len(), profile.encoded_size(&options).unwrap());
+            let decoded = ColorProfile::new_from_slice(&encoded).unwrap();
+            let Some(ToneReprCurve::Lut(lut)) = &decoded.red_trc else {
+                panic!("curv is expected, got {:?}", decoded.red_trc);
+            };
+            assert_eq!(lut.len(), expected_len);
+            let evaluator = decoded
+                .red_trc
+                .as_ref()
+                .unwrap()
+                .linearize_evaluator()
+                .unwrap();
+            let max_deviation = (0..=10000)
+                .map(|i| {
+                    let x = i as f32 / 10000.;
+                    (evaluator.evaluate(x) - reference.evaluate(x)).abs()
+                })
+                .fold(0f32, f32::max);
+            assert!(
+                max_deviation < tolerance,
+                "{table_size} points deviate by {max_deviation}"
+            );
+            // Pure gamma is never sampled
+            let Some(ToneReprCurve::Lut(gamma)) = &decoded.green_trc else {
+                panic!("curv is expected, got {:?}", decoded.green_trc);
+            };
+            assert_eq!(gamma, &vec![563]);
+        }
+    }
 }